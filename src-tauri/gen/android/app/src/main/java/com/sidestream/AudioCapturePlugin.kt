@@ -0,0 +1,137 @@
+package com.sidestream
+
+import android.Manifest
+import android.app.Activity
+import android.media.AudioFormat
+import android.media.AudioRecord
+import android.media.MediaRecorder
+import app.tauri.PermissionState
+import app.tauri.annotation.Command
+import app.tauri.annotation.Permission
+import app.tauri.annotation.PermissionCallback
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+import java.io.File
+import java.io.FileOutputStream
+
+private const val SAMPLE_RATE = 16000
+private const val MICROPHONE = "microphone"
+
+/**
+ * Microphone capture for the `audio-capture` plugin (see src/audio_mobile.rs).
+ * Records 16 kHz mono 16-bit PCM to a cache file and returns its path on stop.
+ * Requires `<uses-permission android:name="android.permission.RECORD_AUDIO" />`
+ * in AndroidManifest.xml.
+ */
+@TauriPlugin(
+    permissions = [Permission(strings = [Manifest.permission.RECORD_AUDIO], alias = MICROPHONE)]
+)
+class AudioCapturePlugin(private val activity: Activity) : Plugin(activity) {
+    private var recorder: AudioRecord? = null
+    private var writer: Thread? = null
+    private var output: File? = null
+
+    @Volatile
+    private var running = false
+
+    @Command
+    fun startCapture(invoke: Invoke) {
+        if (getPermissionState(MICROPHONE) != PermissionState.GRANTED) {
+            requestPermissionForAlias(MICROPHONE, invoke, "microphonePermissionCallback")
+            return
+        }
+        begin(invoke)
+    }
+
+    @PermissionCallback
+    private fun microphonePermissionCallback(invoke: Invoke) {
+        if (getPermissionState(MICROPHONE) == PermissionState.GRANTED) {
+            begin(invoke)
+        } else {
+            invoke.reject("Microphone permission was denied")
+        }
+    }
+
+    private fun begin(invoke: Invoke) {
+        if (running) {
+            invoke.reject("Already recording")
+            return
+        }
+
+        val bufferSize = AudioRecord.getMinBufferSize(
+            SAMPLE_RATE,
+            AudioFormat.CHANNEL_IN_MONO,
+            AudioFormat.ENCODING_PCM_16BIT
+        )
+        val record = try {
+            AudioRecord(
+                MediaRecorder.AudioSource.VOICE_RECOGNITION,
+                SAMPLE_RATE,
+                AudioFormat.CHANNEL_IN_MONO,
+                AudioFormat.ENCODING_PCM_16BIT,
+                bufferSize * 4
+            )
+        } catch (e: SecurityException) {
+            invoke.reject("Microphone permission was denied")
+            return
+        }
+        if (record.state != AudioRecord.STATE_INITIALIZED) {
+            record.release()
+            invoke.reject("No input device available")
+            return
+        }
+
+        val file = File.createTempFile("recording", ".pcm", activity.cacheDir)
+        running = true
+        record.startRecording()
+        writer = Thread {
+            FileOutputStream(file).use { out ->
+                val buffer = ByteArray(bufferSize)
+                while (running) {
+                    val read = record.read(buffer, 0, buffer.size)
+                    if (read > 0) {
+                        out.write(buffer, 0, read)
+                    }
+                }
+            }
+        }.also { it.start() }
+        recorder = record
+        output = file
+        invoke.resolve()
+    }
+
+    /** Stop the recorder and writer thread; returns the file written to */
+    private fun end(): File? {
+        running = false
+        writer?.join()
+        writer = null
+        recorder?.let {
+            it.stop()
+            it.release()
+        }
+        recorder = null
+        return output.also { output = null }
+    }
+
+    @Command
+    fun stopCapture(invoke: Invoke) {
+        if (!running) {
+            invoke.reject("Not currently recording")
+            return
+        }
+        val file = end()
+        val result = JSObject()
+        result.put("path", file?.absolutePath)
+        result.put("sampleRate", SAMPLE_RATE)
+        result.put("channels", 1)
+        invoke.resolve(result)
+    }
+
+    @Command
+    fun cancelCapture(invoke: Invoke) {
+        end()?.delete()
+        invoke.resolve()
+    }
+}
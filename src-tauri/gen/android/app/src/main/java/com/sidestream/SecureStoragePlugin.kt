@@ -0,0 +1,101 @@
+package com.sidestream
+
+import android.app.Activity
+import android.content.Context
+import android.security.keystore.KeyGenParameterSpec
+import android.security.keystore.KeyProperties
+import android.util.Base64
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+import java.security.KeyStore
+import javax.crypto.Cipher
+import javax.crypto.KeyGenerator
+import javax.crypto.SecretKey
+import javax.crypto.spec.GCMParameterSpec
+
+private const val KEYSTORE = "AndroidKeyStore"
+private const val KEY_ALIAS = "com.sidestream.secure"
+private const val PREFERENCES = "com.sidestream.secure"
+private const val TRANSFORMATION = "AES/GCM/NoPadding"
+private const val IV_SIZE = 12
+private const val TAG_BITS = 128
+
+@InvokeArg
+class ItemArgs {
+    lateinit var name: String
+    var value: String? = null
+}
+
+/**
+ * Secret storage for the `secure-storage` plugin (see src/secure_storage_mobile.rs).
+ * Values are encrypted with an AES key that never leaves the Android Keystore
+ * and kept in private shared preferences as base64(iv + ciphertext).
+ */
+@TauriPlugin
+class SecureStoragePlugin(private val activity: Activity) : Plugin(activity) {
+    private val preferences by lazy {
+        activity.getSharedPreferences(PREFERENCES, Context.MODE_PRIVATE)
+    }
+
+    private fun secretKey(): SecretKey {
+        val keyStore = KeyStore.getInstance(KEYSTORE).apply { load(null) }
+        (keyStore.getKey(KEY_ALIAS, null) as? SecretKey)?.let { return it }
+
+        val generator = KeyGenerator.getInstance(KeyProperties.KEY_ALGORITHM_AES, KEYSTORE)
+        generator.init(
+            KeyGenParameterSpec.Builder(
+                KEY_ALIAS,
+                KeyProperties.PURPOSE_ENCRYPT or KeyProperties.PURPOSE_DECRYPT
+            )
+                .setBlockModes(KeyProperties.BLOCK_MODE_GCM)
+                .setEncryptionPaddings(KeyProperties.ENCRYPTION_PADDING_NONE)
+                .build()
+        )
+        return generator.generateKey()
+    }
+
+    @Command
+    fun getItem(invoke: Invoke) {
+        val args = invoke.parseArgs(ItemArgs::class.java)
+        val result = JSObject()
+        try {
+            val stored = preferences.getString(args.name, null)
+            if (stored != null) {
+                val data = Base64.decode(stored, Base64.NO_WRAP)
+                val cipher = Cipher.getInstance(TRANSFORMATION)
+                cipher.init(
+                    Cipher.DECRYPT_MODE,
+                    secretKey(),
+                    GCMParameterSpec(TAG_BITS, data, 0, IV_SIZE)
+                )
+                val plaintext = cipher.doFinal(data, IV_SIZE, data.size - IV_SIZE)
+                result.put("value", String(plaintext, Charsets.UTF_8))
+            }
+            invoke.resolve(result)
+        } catch (e: Exception) {
+            invoke.reject("Failed to read ${args.name}: ${e.message}")
+        }
+    }
+
+    @Command
+    fun setItem(invoke: Invoke) {
+        val args = invoke.parseArgs(ItemArgs::class.java)
+        try {
+            val cipher = Cipher.getInstance(TRANSFORMATION)
+            cipher.init(Cipher.ENCRYPT_MODE, secretKey())
+            val ciphertext = cipher.doFinal((args.value ?: "").toByteArray(Charsets.UTF_8))
+            val stored = Base64.encodeToString(cipher.iv + ciphertext, Base64.NO_WRAP)
+            if (!preferences.edit().putString(args.name, stored).commit()) {
+                invoke.reject("Failed to save ${args.name}")
+                return
+            }
+            invoke.resolve()
+        } catch (e: Exception) {
+            invoke.reject("Failed to save ${args.name}: ${e.message}")
+        }
+    }
+}
@@ -0,0 +1,78 @@
+import AVFoundation
+import Tauri
+
+/// Microphone capture for the `audio-capture` plugin (see src/audio_mobile.rs).
+/// Records 16 kHz mono 16-bit PCM to a temporary WAV file and returns its
+/// path on stop. Uses NSMicrophoneUsageDescription from Info.plist.
+class AudioCapturePlugin: Plugin {
+  private let sampleRate = 16000
+  private var recorder: AVAudioRecorder?
+
+  @objc public func startCapture(_ invoke: Invoke) {
+    if recorder != nil {
+      invoke.reject("Already recording")
+      return
+    }
+
+    let session = AVAudioSession.sharedInstance()
+    session.requestRecordPermission { granted in
+      DispatchQueue.main.async {
+        guard granted else {
+          invoke.reject("Microphone permission was denied")
+          return
+        }
+        do {
+          try session.setCategory(.playAndRecord, mode: .default, options: [.defaultToSpeaker, .allowBluetooth])
+          try session.setActive(true)
+
+          let url = FileManager.default.temporaryDirectory
+            .appendingPathComponent("recording-\(UUID().uuidString).wav")
+          let settings: [String: Any] = [
+            AVFormatIDKey: kAudioFormatLinearPCM,
+            AVSampleRateKey: self.sampleRate,
+            AVNumberOfChannelsKey: 1,
+            AVLinearPCMBitDepthKey: 16,
+            AVLinearPCMIsFloatKey: false,
+            AVLinearPCMIsBigEndianKey: false,
+          ]
+          let recorder = try AVAudioRecorder(url: url, settings: settings)
+          guard recorder.record() else {
+            invoke.reject("Failed to start recording")
+            return
+          }
+          self.recorder = recorder
+          invoke.resolve()
+        } catch {
+          invoke.reject("Failed to start recording: \(error.localizedDescription)")
+        }
+      }
+    }
+  }
+
+  /// Stop the recorder and release the audio session so other audio resumes
+  private func end() -> AVAudioRecorder? {
+    guard let recorder = recorder else { return nil }
+    recorder.stop()
+    self.recorder = nil
+    try? AVAudioSession.sharedInstance().setActive(false, options: .notifyOthersOnDeactivation)
+    return recorder
+  }
+
+  @objc public func stopCapture(_ invoke: Invoke) {
+    guard let recorder = end() else {
+      invoke.reject("Not currently recording")
+      return
+    }
+    invoke.resolve(["path": recorder.url.path, "sampleRate": sampleRate, "channels": 1])
+  }
+
+  @objc public func cancelCapture(_ invoke: Invoke) {
+    end()?.deleteRecording()
+    invoke.resolve()
+  }
+}
+
+@_cdecl("init_plugin_audio_capture")
+func initPlugin() -> Plugin {
+  return AudioCapturePlugin()
+}
@@ -0,0 +1,69 @@
+import Foundation
+import Security
+import Tauri
+
+private let keychainService = "com.sidestream.secure"
+
+class ItemArgs: Decodable {
+  let name: String
+  let value: String?
+}
+
+/// Secret storage for the `secure-storage` plugin (see src/secure_storage_mobile.rs).
+/// Values are generic-password Keychain items, readable after first unlock and
+/// never synced or restored to another device.
+class SecureStoragePlugin: Plugin {
+  private func query(_ name: String) -> [String: Any] {
+    return [
+      kSecClass as String: kSecClassGenericPassword,
+      kSecAttrService as String: keychainService,
+      kSecAttrAccount as String: name,
+    ]
+  }
+
+  @objc public func getItem(_ invoke: Invoke) throws {
+    let args = try invoke.parseArgs(ItemArgs.self)
+    var request = query(args.name)
+    request[kSecReturnData as String] = true
+    request[kSecMatchLimit as String] = kSecMatchLimitOne
+
+    var item: CFTypeRef?
+    let status = SecItemCopyMatching(request as CFDictionary, &item)
+    switch status {
+    case errSecSuccess:
+      let value = (item as? Data).flatMap { String(data: $0, encoding: .utf8) }
+      invoke.resolve(["value": value as Any])
+    case errSecItemNotFound:
+      invoke.resolve([:])
+    default:
+      invoke.reject("Failed to read \(args.name) from the Keychain (status \(status))")
+    }
+  }
+
+  @objc public func setItem(_ invoke: Invoke) throws {
+    let args = try invoke.parseArgs(ItemArgs.self)
+    let data = Data((args.value ?? "").utf8)
+
+    var status = SecItemUpdate(
+      query(args.name) as CFDictionary,
+      [kSecValueData as String: data] as CFDictionary
+    )
+    if status == errSecItemNotFound {
+      var item = query(args.name)
+      item[kSecValueData as String] = data
+      item[kSecAttrAccessible as String] = kSecAttrAccessibleAfterFirstUnlockThisDeviceOnly
+      status = SecItemAdd(item as CFDictionary, nil)
+    }
+
+    if status == errSecSuccess {
+      invoke.resolve()
+    } else {
+      invoke.reject("Failed to save \(args.name) to the Keychain (status \(status))")
+    }
+  }
+}
+
+@_cdecl("init_plugin_secure_storage")
+func initSecureStoragePlugin() -> Plugin {
+  return SecureStoragePlugin()
+}
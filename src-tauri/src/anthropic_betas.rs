@@ -0,0 +1,203 @@
+//! Anthropic beta features
+//!
+//! Anthropic gates newer features behind `anthropic-beta` header values
+//! (`code-execution-2025-08-25`, `files-api-2025-04-14`, ...). Each one the
+//! app uses is registered here with its header value, so requests name the
+//! features they need and `beta_header` composes the comma-separated header.
+//! Streaming requests that register any tool (chat, discovery) also get
+//! fine-grained tool streaming, added from the request body when sent.
+//! A feature can be switched off in settings, for when a beta misbehaves or
+//! has graduated and no longer needs the header; requests then go without
+//! it. Toggles are kept in memory for the request builders, which have no
+//! app handle, and persisted in settings.
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::settings;
+
+const ANTHROPIC_BETAS_KEY: &str = "anthropic_betas";
+
+/// Feature id → enabled; features not listed are enabled
+static TOGGLES: RwLock<BTreeMap<String, bool>> = RwLock::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BetaFeature {
+    /// Code execution tools, and reusing a container across turns
+    CodeExecution,
+    /// The web_fetch tool registered alongside web_search
+    WebFetch,
+    /// Tool inputs stream without server-side JSON buffering, so they can be
+    /// forwarded as `chat-tool-input-delta` events
+    FineGrainedToolStreaming,
+    /// Thinking between tool calls, not only up front
+    InterleavedThinking,
+    /// Reading files that code execution generated
+    FilesApi,
+}
+
+impl BetaFeature {
+    pub const ALL: &'static [BetaFeature] = &[
+        BetaFeature::CodeExecution,
+        BetaFeature::WebFetch,
+        BetaFeature::FineGrainedToolStreaming,
+        BetaFeature::InterleavedThinking,
+        BetaFeature::FilesApi,
+    ];
+
+    /// Settings id
+    pub fn id(self) -> &'static str {
+        match self {
+            BetaFeature::CodeExecution => "code_execution",
+            BetaFeature::WebFetch => "web_fetch",
+            BetaFeature::FineGrainedToolStreaming => "fine_grained_tool_streaming",
+            BetaFeature::InterleavedThinking => "interleaved_thinking",
+            BetaFeature::FilesApi => "files_api",
+        }
+    }
+
+    /// `anthropic-beta` header value
+    pub fn header_value(self) -> &'static str {
+        match self {
+            BetaFeature::CodeExecution => "code-execution-2025-08-25",
+            BetaFeature::WebFetch => "web-fetch-2025-09-10",
+            BetaFeature::FineGrainedToolStreaming => "fine-grained-tool-streaming-2025-05-14",
+            BetaFeature::InterleavedThinking => "interleaved-thinking-2025-05-14",
+            BetaFeature::FilesApi => "files-api-2025-04-14",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|feature| feature.id() == id)
+    }
+}
+
+/// A registered beta and whether it is on (for the settings UI)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnthropicBeta {
+    pub id: String,
+    pub header_value: String,
+    pub enabled: bool,
+}
+
+fn is_enabled(toggles: &BTreeMap<String, bool>, feature: BetaFeature) -> bool {
+    toggles.get(feature.id()).copied().unwrap_or(true)
+}
+
+/// Header value for the enabled features among `features`, each once, in
+/// the order given
+fn compose(toggles: &BTreeMap<String, bool>, features: &[BetaFeature]) -> Option<String> {
+    let mut values: Vec<&str> = Vec::new();
+    for feature in features.iter().copied().filter(|f| is_enabled(toggles, *f)) {
+        if !values.contains(&feature.header_value()) {
+            values.push(feature.header_value());
+        }
+    }
+    Some(values.join(",")).filter(|header| !header.is_empty())
+}
+
+/// Load the stored toggles. Called once at startup.
+pub fn init(app: &tauri::AppHandle) {
+    *TOGGLES.write() = settings::get_setting(app, ANTHROPIC_BETAS_KEY).unwrap_or_default();
+}
+
+/// The `anthropic-beta` header a request needing `features` sends, if any
+pub fn beta_header(features: &[BetaFeature]) -> Option<String> {
+    compose(&TOGGLES.read(), features)
+}
+
+fn with_tool_streaming(toggles: &BTreeMap<String, bool>, header: Option<&str>, body: &serde_json::Value) -> Option<String> {
+    let uses_tools = body["tools"].as_array().is_some_and(|tools| !tools.is_empty());
+    let tool_streaming = compose(toggles, &[BetaFeature::FineGrainedToolStreaming]).filter(|_| uses_tools);
+    let values: Vec<&str> = header.into_iter().chain(tool_streaming.as_deref()).collect();
+    Some(values.join(",")).filter(|header| !header.is_empty())
+}
+
+/// The `anthropic-beta` header for a streaming request: `header`, plus fine
+/// grained tool streaming when the body registers any tool
+pub fn streaming_beta_header(header: Option<&str>, body: &serde_json::Value) -> Option<String> {
+    with_tool_streaming(&TOGGLES.read(), header, body)
+}
+
+/// Add the `anthropic-beta` header for `features` to a request
+pub fn with_betas(request: reqwest::RequestBuilder, features: &[BetaFeature]) -> reqwest::RequestBuilder {
+    match beta_header(features) {
+        Some(header) => request.header("anthropic-beta", header),
+        None => request,
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_anthropic_betas() -> Vec<AnthropicBeta> {
+    let toggles = TOGGLES.read();
+    BetaFeature::ALL
+        .iter()
+        .map(|feature| AnthropicBeta {
+            id: feature.id().to_string(),
+            header_value: feature.header_value().to_string(),
+            enabled: is_enabled(&toggles, *feature),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn set_anthropic_beta_enabled(app: tauri::AppHandle, id: String, enabled: bool) -> Result<(), String> {
+    let feature = BetaFeature::from_id(&id).ok_or_else(|| format!("Unknown Anthropic beta: {}", id))?;
+    let mut toggles = TOGGLES.read().clone();
+    toggles.insert(feature.id().to_string(), enabled);
+    settings::set_setting(&app, ANTHROPIC_BETAS_KEY, &toggles)?;
+    *TOGGLES.write() = toggles;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composes_enabled_betas_once_each() {
+        let mut toggles = BTreeMap::new();
+        let features = [
+            BetaFeature::CodeExecution,
+            BetaFeature::FineGrainedToolStreaming,
+            BetaFeature::CodeExecution,
+            BetaFeature::InterleavedThinking,
+        ];
+        assert_eq!(
+            compose(&toggles, &features).as_deref(),
+            Some("code-execution-2025-08-25,fine-grained-tool-streaming-2025-05-14,interleaved-thinking-2025-05-14")
+        );
+
+        toggles.insert("fine_grained_tool_streaming".to_string(), false);
+        assert_eq!(
+            compose(&toggles, &features).as_deref(),
+            Some("code-execution-2025-08-25,interleaved-thinking-2025-05-14")
+        );
+        assert_eq!(compose(&toggles, &[BetaFeature::FineGrainedToolStreaming]), None);
+        assert_eq!(compose(&toggles, &[]), None);
+    }
+
+    #[test]
+    fn tool_streaming_follows_the_request_tools() {
+        let mut toggles = BTreeMap::new();
+        let with_tools = serde_json::json!({"tools": [{"name": "report_resources"}]});
+        assert_eq!(
+            with_tool_streaming(&toggles, None, &with_tools).as_deref(),
+            Some("fine-grained-tool-streaming-2025-05-14")
+        );
+        assert_eq!(
+            with_tool_streaming(&toggles, Some("code-execution-2025-08-25"), &with_tools).as_deref(),
+            Some("code-execution-2025-08-25,fine-grained-tool-streaming-2025-05-14")
+        );
+        assert_eq!(with_tool_streaming(&toggles, None, &serde_json::json!({"tools": []})), None);
+
+        toggles.insert("fine_grained_tool_streaming".to_string(), false);
+        assert_eq!(with_tool_streaming(&toggles, None, &with_tools), None);
+    }
+}
@@ -0,0 +1,247 @@
+//! Attachment elision
+//!
+//! Images and PDFs attached early in a long conversation are resent with
+//! every later turn, although the model has usually said what it needed to
+//! about them. When enabled, attachments in user messages more than
+//! `after_turns` turns back are replaced with a short placeholder such as
+//! `[image: chart.png, discussed earlier]` in the request. Attachments the
+//! user marks live (stored on the session by attachment id), and those in
+//! pinned messages, are always sent in full. Stored messages keep their
+//! attachments.
+//!
+//! The frontend tags each attachment block with its `attachmentId` and
+//! `filename`; the provider adapters (`message_format`) leave both out of
+//! what they send.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+use crate::commands::{get_stored_session, update_stored_session};
+use crate::llm::ChatMessage;
+use crate::pins;
+use crate::request_adjustments::{AdjustmentKind, RequestAdjustment};
+use crate::settings;
+
+const ATTACHMENT_ELISION_KEY: &str = "attachment_elision";
+
+/// Session field holding the ids of the attachments kept live
+pub const LIVE_ATTACHMENTS_FIELD: &str = "liveAttachments";
+
+/// Attachment block key carrying the id of the attachment it was built from
+pub const ATTACHMENT_ID_KEY: &str = "attachmentId";
+
+/// Content block types that are attachments
+const ATTACHMENT_TYPES: &[&str] = &["image", "document", "file"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AttachmentElisionSettings {
+    pub enabled: bool,
+    /// Attachments from more turns back than this are replaced
+    pub after_turns: usize,
+}
+
+impl Default for AttachmentElisionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after_turns: 4,
+        }
+    }
+}
+
+/// Ids of the attachments kept live, from the session field
+fn live_attachments(field: &Value) -> Vec<String> {
+    field
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect()
+}
+
+/// Placeholder text for an attachment block
+fn placeholder(block: &Value) -> String {
+    let kind = match block["type"].as_str() {
+        Some("image") => "image",
+        Some("document") => "document",
+        _ => "file",
+    };
+    match block["filename"].as_str().filter(|name| !name.is_empty()) {
+        Some(name) => format!("[{}: {}, discussed earlier]", kind, name),
+        None => format!("[{}, discussed earlier]", kind),
+    }
+}
+
+/// Replace attachments in user messages older than the last `after_turns`
+/// turns, unless they are live or their message is pinned. Returns the
+/// messages and how many attachments were replaced.
+fn elide(
+    mut messages: Vec<ChatMessage>,
+    after_turns: usize,
+    live: &HashSet<String>,
+    pinned: &HashSet<String>,
+) -> (Vec<ChatMessage>, usize) {
+    let mut turns_after = 0;
+    let mut elided = 0;
+    for message in messages.iter_mut().rev() {
+        if message.role != "user" {
+            continue;
+        }
+        turns_after += 1;
+        if turns_after <= after_turns {
+            continue;
+        }
+        if message.id.as_ref().is_some_and(|id| pinned.contains(id)) {
+            continue;
+        }
+        let Value::Array(blocks) = &mut message.content else {
+            continue;
+        };
+        for block in blocks.iter_mut() {
+            let is_attachment = block["type"].as_str().is_some_and(|t| ATTACHMENT_TYPES.contains(&t));
+            let is_live = block[ATTACHMENT_ID_KEY].as_str().is_some_and(|id| live.contains(id));
+            if is_attachment && !is_live {
+                *block = serde_json::json!({"type": "text", "text": placeholder(block)});
+                elided += 1;
+            }
+        }
+    }
+    (messages, elided)
+}
+
+pub fn attachment_elision_settings(app: &tauri::AppHandle) -> AttachmentElisionSettings {
+    settings::get_setting(app, ATTACHMENT_ELISION_KEY).unwrap_or_default()
+}
+
+/// Replace old attachments per the elision settings, with the adjustment to
+/// report when any were replaced
+pub fn elide_old_attachments(
+    app: &tauri::AppHandle,
+    session_id: Option<&str>,
+    messages: Vec<ChatMessage>,
+) -> (Vec<ChatMessage>, Option<RequestAdjustment>) {
+    let settings = attachment_elision_settings(app);
+    if !settings.enabled {
+        return (messages, None);
+    }
+    let session = session_id.and_then(|id| get_stored_session(app, id).ok().flatten());
+    let live: HashSet<String> = session.as_ref().map(|s| live_attachments(&s[LIVE_ATTACHMENTS_FIELD])).unwrap_or_default().into_iter().collect();
+    let pinned: HashSet<String> = session.as_ref().map(pins::pinned_ids).unwrap_or_default().into_iter().collect();
+
+    let (messages, elided) = elide(messages, settings.after_turns, &live, &pinned);
+    let adjustment = (elided > 0).then(|| {
+        let noun = if elided == 1 { "attachment" } else { "attachments" };
+        RequestAdjustment::new(
+            AdjustmentKind::AttachmentsElided,
+            format!("Sent {} earlier {} as placeholders", elided, noun),
+        )
+    });
+    (messages, adjustment)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_attachment_elision_settings(app: tauri::AppHandle) -> AttachmentElisionSettings {
+    attachment_elision_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_attachment_elision_settings(
+    app: tauri::AppHandle,
+    elision: AttachmentElisionSettings,
+) -> Result<(), String> {
+    settings::set_setting(&app, ATTACHMENT_ELISION_KEY, &elision)
+}
+
+/// Keep an attachment (by its id) sent in full however old it gets, or let
+/// it be elided again
+#[tauri::command]
+pub fn set_attachment_live(
+    app: tauri::AppHandle,
+    session_id: String,
+    attachment_id: String,
+    live: bool,
+) -> Result<(), String> {
+    update_stored_session(&app, &session_id, |fields| {
+        let mut attachments = live_attachments(fields.get(LIVE_ATTACHMENTS_FIELD).unwrap_or(&Value::Null));
+        attachments.retain(|id| id != &attachment_id);
+        if live {
+            attachments.push(attachment_id);
+        }
+        if attachments.is_empty() {
+            fields.remove(LIVE_ATTACHMENTS_FIELD);
+        } else {
+            fields.insert(LIVE_ATTACHMENTS_FIELD.to_string(), serde_json::json!(attachments));
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, role: &str, content: Value) -> ChatMessage {
+        ChatMessage {
+            id: Some(id.to_string()),
+            role: role.to_string(),
+            content,
+            thinking_blocks: None,
+        }
+    }
+
+    /// A user message as `formatMessageContent` sends it: an image, a PDF
+    /// and the prompt, each attachment tagged with its id and name
+    fn chart_message(id: &str) -> ChatMessage {
+        message(
+            id,
+            "user",
+            serde_json::json!([
+                {"type": "image", "attachmentId": format!("{}-img", id), "filename": "chart.png",
+                 "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}},
+                {"type": "document", "attachmentId": format!("{}-pdf", id), "filename": "report.pdf",
+                 "source": {"type": "base64", "media_type": "application/pdf", "data": "BBBB"}},
+                {"type": "text", "text": "What does this show?"}
+            ]),
+        )
+    }
+
+    #[test]
+    fn old_attachments_become_placeholders_unless_kept() {
+        let messages = vec![
+            chart_message("u1"),
+            message("a1", "assistant", Value::String("A trend".to_string())),
+            chart_message("u2"),
+            message("a2", "assistant", Value::String("Same trend".to_string())),
+            chart_message("u3"),
+        ];
+        let live = HashSet::from(["u2-pdf".to_string()]);
+
+        let (elided, count) = elide(messages.clone(), 1, &live, &HashSet::new());
+        assert_eq!(count, 3);
+        assert_eq!(elided[0].content[0]["text"], "[image: chart.png, discussed earlier]");
+        assert_eq!(elided[0].content[1]["text"], "[document: report.pdf, discussed earlier]");
+        assert_eq!(elided[0].content[2]["text"], "What does this show?");
+        assert_eq!(elided[2].content[0]["type"], "text");
+        assert_eq!(elided[2].content[1]["type"], "document");
+        assert_eq!(elided[4].content, messages[4].content);
+
+        let (_, count) = elide(messages, 1, &HashSet::new(), &HashSet::from(["u1".to_string()]));
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn attachment_tags_are_not_sent() {
+        let parts = crate::message_format::parse_content(&chart_message("u1").content);
+        let anthropic = crate::message_format::anthropic::content(&parts);
+        assert!(anthropic[0].get(ATTACHMENT_ID_KEY).is_none() && anthropic[0].get("filename").is_none());
+        assert!(anthropic[1].get(ATTACHMENT_ID_KEY).is_none());
+    }
+}
@@ -0,0 +1,258 @@
+//! Attachment cache
+//!
+//! Derived data for attachments and generated images lives under
+//! `app_data_dir/attachments`, keyed by a SHA-256 of the original bytes so the
+//! same image is only processed once. Thumbnails are small WebP images the
+//! session list and history can render without decoding full-size base64.
+//!
+//! Long pastes can also become attachments (`prepare_pasted_text`): a plain
+//! text document the message carries inline, sent as a document block.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::settings;
+
+/// Longest edge of a generated thumbnail, in pixels
+const THUMBNAIL_MAX_DIMENSION: u32 = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Thumbnail {
+    /// Cache key (SHA-256 of the original image bytes)
+    pub key: String,
+    /// `data:image/webp;base64,...` URL ready for an <img> tag
+    pub data_url: String,
+}
+
+/// Root directory of the attachment cache
+pub fn attachment_cache_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join("attachments"))
+}
+
+pub fn thumbnail_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(attachment_cache_dir(app)?.join("thumbnails"))
+}
+
+/// Hex SHA-256 of some bytes, used as a content-addressed cache key
+pub fn content_key(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Decode base64 that may be wrapped in a data URL ("data:image/png;base64,...")
+pub fn decode_base64_payload(data: &str) -> Result<Vec<u8>, String> {
+    let payload = match data.strip_prefix("data:") {
+        Some(rest) => rest
+            .split_once(',')
+            .map(|(_, b64)| b64)
+            .ok_or("Malformed data URL")?,
+        None => data,
+    };
+    BASE64
+        .decode(payload.trim())
+        .map_err(|e| format!("Invalid base64 data: {}", e))
+}
+
+/// Downscale an encoded image so its longest edge is at most
+/// THUMBNAIL_MAX_DIMENSION and encode it as WebP
+fn make_thumbnail(image_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let image = image::load_from_memory(image_bytes)
+        .map_err(|e| format!("Failed to decode image: {}", e))?;
+
+    let thumbnail = if image.width() > THUMBNAIL_MAX_DIMENSION || image.height() > THUMBNAIL_MAX_DIMENSION {
+        image.thumbnail(THUMBNAIL_MAX_DIMENSION, THUMBNAIL_MAX_DIMENSION)
+    } else {
+        image
+    };
+
+    let mut out = Cursor::new(Vec::new());
+    thumbnail
+        .to_rgba8()
+        .write_to(&mut out, image::ImageFormat::WebP)
+        .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+
+    Ok(out.into_inner())
+}
+
+fn thumbnail_from_webp(key: String, webp: &[u8]) -> Thumbnail {
+    Thumbnail {
+        key,
+        data_url: format!("data:image/webp;base64,{}", BASE64.encode(webp)),
+    }
+}
+
+/// Generate (or load from cache) a thumbnail for an image attachment or a
+/// generated image. Accepts raw base64 or a data URL.
+#[tauri::command]
+pub async fn generate_thumbnail(app: tauri::AppHandle, data: String) -> Result<Thumbnail, String> {
+    let image_bytes = decode_base64_payload(&data)?;
+    let key = content_key(&image_bytes);
+
+    let dir = thumbnail_dir(&app)?;
+    let path = dir.join(format!("{}.webp", key));
+    if let Ok(cached) = fs::read(&path) {
+        return Ok(thumbnail_from_webp(key, &cached));
+    }
+
+    // Decoding and resizing full-size images is CPU-bound; keep it off the async runtime
+    let webp = tokio::task::spawn_blocking(move || make_thumbnail(&image_bytes))
+        .await
+        .map_err(|e| format!("Thumbnail task failed: {}", e))??;
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create thumbnail cache: {}", e))?;
+    if let Err(e) = fs::write(&path, &webp) {
+        // A cache write failure shouldn't stop the thumbnail from rendering
+        eprintln!("Failed to cache thumbnail {}: {}", key, e);
+    }
+
+    Ok(thumbnail_from_webp(key, &webp))
+}
+
+/// Look up a previously generated thumbnail by its cache key
+#[tauri::command]
+pub async fn get_thumbnail(app: tauri::AppHandle, key: String) -> Result<Option<Thumbnail>, String> {
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid thumbnail key: {}", key));
+    }
+    let path = thumbnail_dir(&app)?.join(format!("{}.webp", key));
+    Ok(fs::read(&path).ok().map(|webp| thumbnail_from_webp(key, &webp)))
+}
+
+// ============================================================================
+// Long Pastes
+// ============================================================================
+
+const PASTE_THRESHOLD_SETTING_KEY: &str = "paste_attachment_threshold_tokens";
+
+/// Pastes estimated above this many tokens become a document attachment
+const DEFAULT_PASTE_THRESHOLD_TOKENS: u32 = 2000;
+
+/// Rough token estimate (~4 characters per token for English text). Good
+/// enough for threshold decisions; not meant for billing.
+pub fn estimate_tokens(text: &str) -> u32 {
+    text.chars().count().div_ceil(4) as u32
+}
+
+/// A long paste converted into an attachment. Field names match the
+/// frontend's `Attachment` type so it can be added to a message directly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PastedAttachment {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub attachment_type: String,
+    pub name: String,
+    pub mime_type: String,
+    /// Base64 of the UTF-8 text
+    pub data: String,
+    pub token_count: u32,
+}
+
+/// Outcome of `prepare_pasted_text`: either keep the text inline, or attach it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PastedTextResult {
+    pub token_count: u32,
+    pub threshold_tokens: u32,
+    /// Present when the paste exceeded the threshold and should be attached
+    pub attachment: Option<PastedAttachment>,
+}
+
+fn paste_threshold_tokens(app: &tauri::AppHandle) -> u32 {
+    settings::get_setting(app, PASTE_THRESHOLD_SETTING_KEY).unwrap_or(DEFAULT_PASTE_THRESHOLD_TOKENS)
+}
+
+/// Decide whether pasted text should stay inline or become a text document
+/// attachment. Giant pastes inline in the message text make every later edit
+/// to the prompt miss the cache; as an attachment the paste is a stable
+/// document block (see `message_format`).
+#[tauri::command]
+pub async fn prepare_pasted_text(
+    app: tauri::AppHandle,
+    text: String,
+) -> Result<PastedTextResult, String> {
+    let token_count = estimate_tokens(&text);
+    let threshold_tokens = paste_threshold_tokens(&app);
+
+    if token_count <= threshold_tokens {
+        return Ok(PastedTextResult {
+            token_count,
+            threshold_tokens,
+            attachment: None,
+        });
+    }
+
+    let key = content_key(text.as_bytes());
+    Ok(PastedTextResult {
+        token_count,
+        threshold_tokens,
+        attachment: Some(PastedAttachment {
+            id: format!("paste-{}", &key[..12]),
+            attachment_type: "document".to_string(),
+            name: format!("Pasted text (~{} tokens).txt", token_count),
+            mime_type: "text/plain".to_string(),
+            data: BASE64.encode(text.as_bytes()),
+            token_count,
+        }),
+    })
+}
+
+#[tauri::command]
+pub fn get_paste_threshold(app: tauri::AppHandle) -> u32 {
+    paste_threshold_tokens(&app)
+}
+
+#[tauri::command]
+pub fn set_paste_threshold(app: tauri::AppHandle, tokens: u32) -> Result<(), String> {
+    settings::set_setting(&app, PASTE_THRESHOLD_SETTING_KEY, &tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_png(width: u32, height: u32) -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(width, height, image::Rgba([200, 40, 40, 255]));
+        let mut out = Cursor::new(Vec::new());
+        image.write_to(&mut out, image::ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn thumbnail_fits_within_max_dimension_and_keeps_aspect() {
+        let webp = make_thumbnail(&encode_png(1024, 512)).unwrap();
+        let thumb = image::load_from_memory(&webp).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (256, 128));
+    }
+
+    #[test]
+    fn small_images_are_not_upscaled() {
+        let webp = make_thumbnail(&encode_png(64, 32)).unwrap();
+        let thumb = image::load_from_memory(&webp).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (64, 32));
+    }
+
+    #[test]
+    fn token_estimate_rounds_up() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens(&"x".repeat(8000)), 2000);
+    }
+
+    #[test]
+    fn decodes_data_urls_and_raw_base64() {
+        assert_eq!(decode_base64_payload("data:image/png;base64,aGk=").unwrap(), b"hi");
+        assert_eq!(decode_base64_payload("aGk=").unwrap(), b"hi");
+    }
+}
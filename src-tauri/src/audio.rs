@@ -9,6 +9,12 @@ use std::sync::Arc;
 use std::thread;
 
 use crate::commands::transcribe_audio_bytes;
+use crate::local_transcription::{self, TranscriptionBackend};
+use crate::settings;
+use crate::voice_intents;
+
+/// Setting holding the name of the last input device chosen for recording
+const INPUT_DEVICE_KEY: &str = "input_device";
 
 // ============================================================================
 // State Types
@@ -21,6 +27,10 @@ pub enum RecordingState {
     Stopping,
 }
 
+/// Code at the start of the error returned when a finished recording could
+/// not be transcribed; the audio is kept for `retry_last_transcription`
+pub const TRANSCRIPTION_FAILED_ERROR: &str = "transcription_failed";
+
 /// Shared data between the recording thread and the Tauri commands
 pub struct SharedRecordingData {
     pub state: RecordingState,
@@ -29,6 +39,9 @@ pub struct SharedRecordingData {
     pub channels: u16,
     pub should_stop: bool,
     pub error: Option<String>,
+    /// WAV bytes of the most recent finished recording, kept so the turn can be
+    /// persisted (see `voice_turns`) after the transcription comes back
+    pub last_recording: Option<Vec<u8>>,
 }
 
 impl Default for SharedRecordingData {
@@ -40,6 +53,7 @@ impl Default for SharedRecordingData {
             channels: 1,
             should_stop: false,
             error: None,
+            last_recording: None,
         }
     }
 }
@@ -92,11 +106,22 @@ pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
     Ok(devices)
 }
 
+/// The input device last chosen for recording, if it is still connected
+/// (None means the default device)
+pub fn preferred_input_device(app: &tauri::AppHandle) -> Option<String> {
+    let name: String = settings::get_setting(app, INPUT_DEVICE_KEY)?;
+    list_audio_devices()
+        .ok()?
+        .iter()
+        .any(|device| device.name == name)
+        .then_some(name)
+}
+
 // ============================================================================
 // WAV Encoding
 // ============================================================================
 
-fn encode_wav(samples: &[i16], sample_rate: u32, channels: u16) -> Result<Vec<u8>, String> {
+pub fn encode_wav(samples: &[i16], sample_rate: u32, channels: u16) -> Result<Vec<u8>, String> {
     let spec = WavSpec {
         channels,
         sample_rate,
@@ -127,14 +152,29 @@ fn encode_wav(samples: &[i16], sample_rate: u32, channels: u16) -> Result<Vec<u8
 // Recording Thread Function
 // ============================================================================
 
-fn run_recording_thread(data: Arc<Mutex<SharedRecordingData>>) {
-    // Get default input device
+/// The input device with the given name, or the default input device
+fn select_input_device(host: &cpal::Host, device_name: Option<&str>) -> Option<Device> {
+    match device_name {
+        Some(name) => host
+            .input_devices()
+            .ok()?
+            .find(|device| device.name().ok().as_deref() == Some(name)),
+        None => host.default_input_device(),
+    }
+}
+
+/// Capture from an input device (the default one unless named) into `data`
+/// until `should_stop` is set
+pub fn run_recording_thread(data: Arc<Mutex<SharedRecordingData>>, device_name: Option<String>) {
     let host = cpal::default_host();
-    let device = match host.default_input_device() {
+    let device = match select_input_device(&host, device_name.as_deref()) {
         Some(d) => d,
         None => {
             let mut guard = data.lock();
-            guard.error = Some("No input device available".to_string());
+            guard.error = Some(match device_name {
+                Some(name) => format!("Input device not found: {}", name),
+                None => "No input device available".to_string(),
+            });
             guard.state = RecordingState::Idle;
             return;
         }
@@ -151,6 +191,51 @@ fn run_recording_thread(data: Arc<Mutex<SharedRecordingData>>) {
         }
     };
 
+    record_from_device(data, &device, supported_config);
+}
+
+/// Capture what the default output device plays (system audio) into `data`
+/// until `should_stop` is set. WASAPI records an output device in loopback
+/// mode when an input stream is built on it; other hosts have no loopback
+/// capture, so there a loopback input device (e.g. BlackHole on macOS) has
+/// to be recorded with `run_recording_thread` instead.
+pub fn run_system_audio_recording_thread(data: Arc<Mutex<SharedRecordingData>>) {
+    if !cfg!(target_os = "windows") {
+        let mut guard = data.lock();
+        guard.error = Some(
+            "System audio capture is only available on Windows; record a loopback input device instead".to_string(),
+        );
+        guard.state = RecordingState::Idle;
+        return;
+    }
+
+    let host = cpal::default_host();
+    let Some(device) = host.default_output_device() else {
+        let mut guard = data.lock();
+        guard.error = Some("No output device available".to_string());
+        guard.state = RecordingState::Idle;
+        return;
+    };
+
+    let supported_config = match device.default_output_config() {
+        Ok(c) => c,
+        Err(e) => {
+            let mut guard = data.lock();
+            guard.error = Some(format!("Failed to get output config: {}", e));
+            guard.state = RecordingState::Idle;
+            return;
+        }
+    };
+
+    record_from_device(data, &device, supported_config);
+}
+
+/// Run an input stream on `device` into `data` until `should_stop` is set
+fn record_from_device(
+    data: Arc<Mutex<SharedRecordingData>>,
+    device: &Device,
+    supported_config: cpal::SupportedStreamConfig,
+) {
     let config: StreamConfig = supported_config.config();
 
     // Update config info
@@ -165,9 +250,9 @@ fn run_recording_thread(data: Arc<Mutex<SharedRecordingData>>) {
 
     // Build input stream based on sample format
     let stream = match supported_config.sample_format() {
-        SampleFormat::I16 => build_input_stream_i16(&device, &config, data_clone),
-        SampleFormat::U16 => build_input_stream_u16(&device, &config, data_clone),
-        SampleFormat::F32 => build_input_stream_f32(&device, &config, data_clone),
+        SampleFormat::I16 => build_input_stream_i16(device, &config, data_clone),
+        SampleFormat::U16 => build_input_stream_u16(device, &config, data_clone),
+        SampleFormat::F32 => build_input_stream_f32(device, &config, data_clone),
         format => Err(format!("Unsupported sample format: {:?}", format)),
     };
 
@@ -284,31 +369,17 @@ fn build_input_stream_f32(
 // Tauri Commands
 // ============================================================================
 
-#[tauri::command]
-pub async fn start_audio_recording(state: tauri::State<'_, AudioState>) -> Result<(), String> {
-    let data = state.data.clone();
-
-    // Check if already recording
-    {
-        let guard = data.lock();
-        if guard.state == RecordingState::Recording {
-            return Err("Already recording".to_string());
-        }
-    }
-
-    // Reset state and start recording
-    {
-        let mut guard = data.lock();
-        guard.samples.clear();
-        guard.error = None;
-        guard.should_stop = false;
-        guard.state = RecordingState::Recording;
-    }
-
+/// Start capturing into `data`: a cpal recording thread on desktop
+#[cfg(desktop)]
+async fn begin_capture(
+    _app: &tauri::AppHandle,
+    data: &Arc<Mutex<SharedRecordingData>>,
+    device_name: Option<String>,
+) -> Result<(), String> {
     // Spawn recording thread
     let data_clone = data.clone();
     thread::spawn(move || {
-        run_recording_thread(data_clone);
+        run_recording_thread(data_clone, device_name);
     });
 
     // Wait a bit and check for immediate errors
@@ -322,21 +393,24 @@ pub async fn start_audio_recording(state: tauri::State<'_, AudioState>) -> Resul
     Ok(())
 }
 
-#[tauri::command]
-pub async fn stop_audio_recording(
-    app: tauri::AppHandle,
-    state: tauri::State<'_, AudioState>,
-) -> Result<String, String> {
-    let data = state.data.clone();
-
-    // Check if recording
-    {
-        let guard = data.lock();
-        if guard.state != RecordingState::Recording {
-            return Err("Not currently recording".to_string());
-        }
+/// Start capturing into `data`: the native audio-capture plugin on mobile
+/// (which always records from the system's current input)
+#[cfg(mobile)]
+async fn begin_capture(
+    app: &tauri::AppHandle,
+    data: &Arc<Mutex<SharedRecordingData>>,
+    _device_name: Option<String>,
+) -> Result<(), String> {
+    let result = crate::audio_mobile::start_capture(app).await;
+    if result.is_err() {
+        data.lock().state = RecordingState::Idle;
     }
+    result
+}
 
+/// Signal the recording thread to stop and wait until it has finished
+#[cfg(desktop)]
+async fn end_capture(_app: &tauri::AppHandle, data: &Arc<Mutex<SharedRecordingData>>) {
     // Signal stop
     {
         let mut guard = data.lock();
@@ -355,6 +429,56 @@ pub async fn stop_audio_recording(
             break;
         }
     }
+}
+
+/// Stop the native recorder and move its samples into `data`
+#[cfg(mobile)]
+async fn end_capture(app: &tauri::AppHandle, data: &Arc<Mutex<SharedRecordingData>>) {
+    data.lock().state = RecordingState::Stopping;
+    let captured = crate::audio_mobile::stop_capture(app).await;
+
+    let mut guard = data.lock();
+    match captured {
+        Ok((samples, sample_rate, channels)) => {
+            guard.samples = samples;
+            guard.sample_rate = sample_rate;
+            guard.channels = channels;
+        }
+        Err(e) => guard.error = Some(e),
+    }
+    guard.state = RecordingState::Idle;
+}
+
+/// Stop capturing without keeping the audio
+#[cfg(desktop)]
+async fn abort_capture(_app: &tauri::AppHandle, data: &Arc<Mutex<SharedRecordingData>>) {
+    // Signal stop
+    data.lock().should_stop = true;
+}
+
+/// Stop capturing without keeping the audio
+#[cfg(mobile)]
+async fn abort_capture(app: &tauri::AppHandle, _data: &Arc<Mutex<SharedRecordingData>>) {
+    if let Err(e) = crate::audio_mobile::cancel_capture(app).await {
+        eprintln!("{}", e);
+    }
+}
+
+/// Stop the current recording and return it as WAV bytes, keeping a copy in
+/// `last_recording`
+async fn finish_recording(
+    app: &tauri::AppHandle,
+    data: &Arc<Mutex<SharedRecordingData>>,
+) -> Result<Vec<u8>, String> {
+    // Check if recording
+    {
+        let guard = data.lock();
+        if guard.state != RecordingState::Recording {
+            return Err("Not currently recording".to_string());
+        }
+    }
+
+    end_capture(app, data).await;
 
     // Get the recorded audio
     let (samples, sample_rate, channels, error) = {
@@ -373,21 +497,93 @@ pub async fn stop_audio_recording(
 
     // Encode to WAV
     let wav_bytes = encode_wav(&samples, sample_rate, channels)?;
+    data.lock().last_recording = Some(wav_bytes.clone());
+    Ok(wav_bytes)
+}
 
-    // Transcribe the audio
-    transcribe_audio_bytes(&app, wav_bytes, "audio.wav", "audio/wav").await
+/// Transcribe a recording (with OpenAI or on the device, per the transcription
+/// settings) and check it for spoken commands. On failure the
+/// recording stays in `last_recording`, so the error is marked retryable.
+async fn transcribe_recording(app: &tauri::AppHandle, wav_bytes: Vec<u8>) -> Result<String, String> {
+    let settings = local_transcription::transcription_settings(app);
+    let transcription = match settings.backend {
+        TranscriptionBackend::Local => local_transcription::transcribe_wav(app, &settings.model, wav_bytes).await,
+        TranscriptionBackend::Openai => transcribe_audio_bytes(app, wav_bytes, "audio.wav", "audio/wav").await,
+    }
+    .map_err(|e| format!("{}: {}", TRANSCRIPTION_FAILED_ERROR, e))?;
+    Ok(voice_intents::process_transcription(app, transcription))
 }
 
+/// Start recording from `device_name` (as listed by `get_audio_devices`).
+/// A named device is remembered; without one the remembered device is used
+/// if it is still connected, else the default input.
 #[tauri::command]
-pub async fn cancel_audio_recording(state: tauri::State<'_, AudioState>) -> Result<(), String> {
+pub async fn start_audio_recording(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AudioState>,
+    device_name: Option<String>,
+) -> Result<(), String> {
     let data = state.data.clone();
 
-    // Signal stop
+    // Check if already recording
+    {
+        let guard = data.lock();
+        if guard.state == RecordingState::Recording {
+            return Err("Already recording".to_string());
+        }
+    }
+
+    // Reset state and start recording
     {
         let mut guard = data.lock();
-        guard.should_stop = true;
+        guard.samples.clear();
+        guard.error = None;
+        guard.should_stop = false;
+        guard.state = RecordingState::Recording;
     }
 
+    let device = device_name.clone().or_else(|| preferred_input_device(&app));
+    begin_capture(&app, &data, device).await?;
+
+    if let Some(name) = device_name {
+        settings::set_setting(&app, INPUT_DEVICE_KEY, &name)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_audio_recording(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AudioState>,
+) -> Result<String, String> {
+    let wav_bytes = finish_recording(&app, &state.data).await?;
+    transcribe_recording(&app, wav_bytes).await
+}
+
+/// Transcribe the last recording again after a `transcription_failed` error
+#[tauri::command]
+pub async fn retry_last_transcription(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AudioState>,
+) -> Result<String, String> {
+    let wav_bytes = state
+        .data
+        .lock()
+        .last_recording
+        .clone()
+        .ok_or("No recording to transcribe")?;
+    transcribe_recording(&app, wav_bytes).await
+}
+
+#[tauri::command]
+pub async fn cancel_audio_recording(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AudioState>,
+) -> Result<(), String> {
+    let data = state.data.clone();
+
+    abort_capture(&app, &data).await;
+
     // Wait a bit for cleanup
     tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
@@ -407,6 +603,12 @@ pub fn get_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
     list_audio_devices()
 }
 
+/// The remembered input device, for preselecting it in device pickers
+#[tauri::command]
+pub fn get_preferred_input_device(app: tauri::AppHandle) -> Option<String> {
+    preferred_input_device(&app)
+}
+
 #[tauri::command]
 pub fn get_recording_state(state: tauri::State<'_, AudioState>) -> Result<String, String> {
     let guard = state.data.lock();
@@ -420,54 +622,10 @@ pub fn get_recording_state(state: tauri::State<'_, AudioState>) -> Result<String
 /// Stop recording and return raw base64-encoded WAV audio (for Gemini native multimodal)
 #[tauri::command]
 pub async fn stop_audio_recording_raw(
+    app: tauri::AppHandle,
     state: tauri::State<'_, AudioState>,
 ) -> Result<String, String> {
-    let data = state.data.clone();
-
-    // Check if recording
-    {
-        let guard = data.lock();
-        if guard.state != RecordingState::Recording {
-            return Err("Not currently recording".to_string());
-        }
-    }
-
-    // Signal stop
-    {
-        let mut guard = data.lock();
-        guard.should_stop = true;
-        guard.state = RecordingState::Stopping;
-    }
-
-    // Wait for recording thread to finish
-    loop {
-        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
-        let state = {
-            let guard = data.lock();
-            guard.state.clone()
-        };
-        if state == RecordingState::Idle {
-            break;
-        }
-    }
-
-    // Get the recorded audio
-    let (samples, sample_rate, channels, error) = {
-        let mut guard = data.lock();
-        let samples = std::mem::take(&mut guard.samples);
-        (samples, guard.sample_rate, guard.channels, guard.error.take())
-    };
-
-    if let Some(err) = error {
-        return Err(err);
-    }
-
-    if samples.is_empty() {
-        return Err("No audio recorded".to_string());
-    }
-
-    // Encode to WAV
-    let wav_bytes = encode_wav(&samples, sample_rate, channels)?;
+    let wav_bytes = finish_recording(&app, &state.data).await?;
 
     // Return as base64
     Ok(BASE64.encode(&wav_bytes))
@@ -0,0 +1,358 @@
+//! Splitting long recordings for transcription
+//!
+//! Transcription models cap upload sizes and output length, so long files are
+//! transcribed in pieces. WAV is split on sample frames and MP3 on frame
+//! boundaries, both without decoding; other compressed formats (m4a, ogg,
+//! flac, ...) can't be cut without a decoder and are returned whole.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::OnceLock;
+use tauri::Emitter;
+
+/// Largest possible MPEG audio frame (320 kbit/s at 32 kHz, padded)
+const MAX_MP3_FRAME_LEN: usize = 1441;
+
+/// Longest run of words checked when removing the overlap between pieces
+const MAX_OVERLAP_WORDS: usize = 60;
+
+/// How to cut a recording into pieces
+#[derive(Debug, Clone, Copy)]
+pub struct SplitOptions {
+    /// Target length of each piece
+    pub chunk_secs: u32,
+    /// Audio repeated at the start of each piece, so a word cut at a boundary
+    /// is heard whole in one of the two pieces
+    pub overlap_secs: u32,
+    /// Upper bound on a piece's size, for providers that limit upload size
+    pub max_bytes: usize,
+}
+
+/// Event payload reporting that another piece of a long recording has been
+/// transcribed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscriptionProgressEvent {
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Emit `transcription-progress` for a multi-piece transcription
+pub fn emit_transcription_progress(app: &tauri::AppHandle, completed: usize, total: usize) {
+    if total < 2 {
+        return;
+    }
+    if let Err(err) = app.emit("transcription-progress", TranscriptionProgressEvent { completed, total }) {
+        eprintln!("Failed to emit transcription-progress event: {}", err);
+    }
+}
+
+/// A RIFF/WAVE file's format chunk and the location of its sample data
+struct WavLayout {
+    fmt: Vec<u8>,
+    data_start: usize,
+    data_end: usize,
+    byte_rate: usize,
+    block_align: usize,
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn parse_wav(bytes: &[u8]) -> Option<WavLayout> {
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+        return None;
+    }
+
+    let mut fmt = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = read_u32(bytes, pos + 4)? as usize;
+        let body = pos + 8;
+        if id == b"fmt " {
+            fmt = Some(bytes.get(body..body + size)?.to_vec());
+        } else if id == b"data" {
+            let fmt = fmt?;
+            let byte_rate = read_u32(&fmt, 8)? as usize;
+            let block_align = read_u16(&fmt, 12)? as usize;
+            if byte_rate == 0 || block_align == 0 {
+                return None;
+            }
+            return Some(WavLayout {
+                fmt,
+                data_start: body,
+                // Recorders that were interrupted can leave a size past the end
+                data_end: (body + size).min(bytes.len()),
+                byte_rate,
+                block_align,
+            });
+        }
+        // Chunks are padded to an even size
+        pos = body + size + (size & 1);
+    }
+    None
+}
+
+/// A standalone WAV file with the given format chunk and sample data
+fn wav_file(fmt: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(20 + fmt.len() + data.len());
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&((4 + 8 + fmt.len() + 8 + data.len()) as u32).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&(fmt.len() as u32).to_le_bytes());
+    out.extend_from_slice(fmt);
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+/// Byte ranges covering `len` bytes in pieces of about `chunk_len`, each
+/// starting `overlap_len` before the previous one ended. `align` moves a cut
+/// to a valid boundary (a sample frame or an MP3 frame header).
+fn piece_ranges(
+    len: usize,
+    chunk_len: usize,
+    overlap_len: usize,
+    align: impl Fn(usize) -> usize,
+) -> Vec<Range<usize>> {
+    let overlap_len = overlap_len.min(chunk_len / 2);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = if start + chunk_len >= len {
+            len
+        } else {
+            align(start + chunk_len).min(len)
+        };
+        ranges.push(start..end);
+        if end >= len {
+            return ranges;
+        }
+        start = align(end - overlap_len).max(start + 1);
+    }
+}
+
+fn split_wav(bytes: &[u8], options: SplitOptions) -> Option<Vec<Vec<u8>>> {
+    let layout = parse_wav(bytes)?;
+    let data = &bytes[layout.data_start..layout.data_end];
+    let align_down = |n: usize| n / layout.block_align * layout.block_align;
+
+    // Whole sample frames only, so every piece starts on a frame boundary
+    let header_len = 20 + layout.fmt.len() + 8;
+    let chunk_len = align_down(
+        (layout.byte_rate * options.chunk_secs as usize).min(options.max_bytes.saturating_sub(header_len)),
+    )
+    .max(layout.block_align);
+    if data.len() <= chunk_len {
+        return None;
+    }
+    let overlap_len = align_down(layout.byte_rate * options.overlap_secs as usize);
+
+    Some(
+        piece_ranges(data.len(), chunk_len, overlap_len, align_down)
+            .into_iter()
+            .map(|range| wav_file(&layout.fmt, &data[range]))
+            .collect(),
+    )
+}
+
+/// Bitrate in kbit/s of the MPEG audio frame starting at `at`, if there is one
+fn mp3_frame_bitrate(bytes: &[u8], at: usize) -> Option<usize> {
+    const MPEG1_LAYER3: [usize; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+    const MPEG2_LAYER3: [usize; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+    let header = bytes.get(at..at + 4)?;
+    let is_sync = header[0] == 0xFF && header[1] & 0xE0 == 0xE0;
+    let version = (header[1] >> 3) & 0b11;
+    let layer = (header[1] >> 1) & 0b11;
+    let bitrate_index = (header[2] >> 4) as usize;
+    let sample_rate_index = (header[2] >> 2) & 0b11;
+    if !is_sync || version == 0b01 || layer == 0 || bitrate_index == 0 || bitrate_index == 15 || sample_rate_index == 3 {
+        return None;
+    }
+
+    let table = if version == 0b11 { &MPEG1_LAYER3 } else { &MPEG2_LAYER3 };
+    Some(table[bitrate_index])
+}
+
+fn split_mp3(bytes: &[u8], options: SplitOptions) -> Option<Vec<Vec<u8>>> {
+    let first_frame = (0..bytes.len()).find(|&i| mp3_frame_bitrate(bytes, i).is_some())?;
+    let bitrate = mp3_frame_bitrate(bytes, first_frame)?;
+    // kbit/s to bytes/s; VBR files make this an estimate, which is fine here
+    let bytes_per_sec = bitrate * 125;
+    // Leave room for the cut moving forward to the next frame header
+    let chunk_len = (bytes_per_sec * options.chunk_secs as usize)
+        .min(options.max_bytes.saturating_sub(MAX_MP3_FRAME_LEN))
+        .max(1);
+    if bytes.len() <= chunk_len {
+        return None;
+    }
+    let overlap_len = bytes_per_sec * options.overlap_secs as usize;
+
+    // Cut at the first frame header at or after each position
+    let next_frame = |at: usize| {
+        (at..bytes.len())
+            .find(|&i| mp3_frame_bitrate(bytes, i).is_some())
+            .unwrap_or(bytes.len())
+    };
+
+    Some(
+        piece_ranges(bytes.len(), chunk_len, overlap_len, next_frame)
+            .into_iter()
+            .map(|range| bytes[range].to_vec())
+            .collect(),
+    )
+}
+
+/// Split audio into pieces per `options`. Audio that is short enough, or in a
+/// format that can't be split without decoding, comes back as a single piece.
+pub fn split_audio(bytes: Vec<u8>, mime_type: &str, options: SplitOptions) -> Vec<Vec<u8>> {
+    let pieces = match mime_type {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => split_wav(&bytes, options),
+        "audio/mpeg" | "audio/mp3" => split_mp3(&bytes, options),
+        _ => None,
+    };
+    pieces.unwrap_or_else(|| vec![bytes])
+}
+
+fn word_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\S+").unwrap())
+}
+
+/// A word reduced to what matters when comparing transcripts
+fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Join the transcripts of overlapping pieces. Words at the start of a piece
+/// that repeat the end of the previous transcript (the overlap) are dropped.
+pub fn stitch_transcripts(parts: &[String]) -> String {
+    let mut stitched = String::new();
+    for part in parts.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        if stitched.is_empty() {
+            stitched.push_str(part);
+            continue;
+        }
+
+        let previous: Vec<String> = stitched
+            .split_whitespace()
+            .rev()
+            .take(MAX_OVERLAP_WORDS)
+            .map(normalize_word)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+        let words: Vec<_> = word_regex().find_iter(part).take(MAX_OVERLAP_WORDS).collect();
+        let next: Vec<String> = words.iter().map(|w| normalize_word(w.as_str())).collect();
+
+        // Longest run (two words or more) ending the previous text and starting this one
+        let overlap = (2..=previous.len().min(next.len()))
+            .rev()
+            .find(|&k| previous[previous.len() - k..] == next[..k]);
+
+        let rest = match overlap {
+            Some(k) => part[words[k - 1].end()..].trim_start(),
+            None => part,
+        };
+        if !rest.is_empty() {
+            stitched.push(' ');
+            stitched.push_str(rest);
+        }
+    }
+    stitched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_wav(seconds: u32) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for i in 0..(8000 * seconds) {
+            writer.write_sample((i % 100) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn wav_splits_into_valid_files_covering_every_sample() {
+        let options = SplitOptions {
+            chunk_secs: 10,
+            overlap_secs: 0,
+            max_bytes: usize::MAX,
+        };
+        let pieces = split_audio(sample_wav(25), "audio/wav", options);
+        assert_eq!(pieces.len(), 3);
+
+        let lengths: Vec<u32> = pieces
+            .iter()
+            .map(|piece| hound::WavReader::new(std::io::Cursor::new(piece)).unwrap().len())
+            .collect();
+        assert_eq!(lengths, vec![80_000, 80_000, 40_000]);
+
+        // With overlap, each piece repeats the last 2s of the previous one
+        let pieces = split_audio(sample_wav(25), "audio/wav", SplitOptions { overlap_secs: 2, ..options });
+        let lengths: Vec<u32> = pieces
+            .iter()
+            .map(|piece| hound::WavReader::new(std::io::Cursor::new(piece)).unwrap().len())
+            .collect();
+        assert_eq!(lengths, vec![80_000, 80_000, 72_000]);
+    }
+
+    #[test]
+    fn mp3_splits_on_frame_headers_and_other_formats_stay_whole() {
+        // 128 kbit/s MPEG-1 Layer III frames (417 bytes each)
+        let frame = {
+            let mut frame = vec![0u8; 417];
+            frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x64]);
+            frame
+        };
+        let mp3: Vec<u8> = frame.repeat(100);
+
+        let options = SplitOptions {
+            chunk_secs: 1,
+            overlap_secs: 0,
+            max_bytes: usize::MAX,
+        };
+        let pieces = split_audio(mp3.clone(), "audio/mpeg", options);
+        assert!(pieces.len() > 1);
+        assert!(pieces.iter().all(|p| p[..2] == [0xFF, 0xFB]));
+        assert_eq!(pieces.concat(), mp3);
+
+        assert_eq!(split_audio(mp3.clone(), "audio/ogg", options), vec![mp3]);
+    }
+
+    #[test]
+    fn stitching_drops_the_repeated_overlap() {
+        let parts = vec![
+            "We should ship the release on Friday, after the".to_string(),
+            "after the final review. Then we celebrate.".to_string(),
+            "Totally new segment.".to_string(),
+        ];
+        assert_eq!(
+            stitch_transcripts(&parts),
+            "We should ship the release on Friday, after the final review. Then we celebrate. Totally new segment."
+        );
+    }
+}
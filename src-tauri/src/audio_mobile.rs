@@ -0,0 +1,96 @@
+//! Mobile audio capture
+//!
+//! On Android and iOS the microphone needs a runtime permission and an audio
+//! session set up by the platform, which cpal doesn't do. Capture there goes
+//! through the app's `audio-capture` native plugin instead
+//! (`AudioCapturePlugin.kt` / `AudioCapturePlugin.swift` in the generated
+//! mobile projects): it records 16 kHz mono PCM to a temporary file and hands
+//! back the path when stopped. `audio` calls into this module behind the same
+//! recording commands as the desktop path.
+
+use serde::Deserialize;
+use tauri::plugin::{Builder, PluginHandle, TauriPlugin};
+use tauri::{Manager, Wry};
+
+#[cfg(target_os = "ios")]
+tauri::ios_plugin_binding!(init_plugin_audio_capture);
+
+/// Handle to the registered native plugin
+struct MobileAudio(PluginHandle<Wry>);
+
+/// Reply of the native `stopCapture` command
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CapturedAudio {
+    /// A WAV file (iOS) or raw little-endian 16-bit PCM (Android)
+    path: String,
+    sample_rate: u32,
+    channels: u16,
+}
+
+/// The `audio-capture` plugin; registers the native side on startup
+pub fn init() -> TauriPlugin<Wry> {
+    Builder::new("audio-capture")
+        .setup(|app, api| {
+            #[cfg(target_os = "android")]
+            let handle = api.register_android_plugin("com.sidestream", "AudioCapturePlugin")?;
+            #[cfg(target_os = "ios")]
+            let handle = api.register_ios_plugin(init_plugin_audio_capture)?;
+            app.manage(MobileAudio(handle));
+            Ok(())
+        })
+        .build()
+}
+
+fn plugin(app: &tauri::AppHandle) -> Result<PluginHandle<Wry>, String> {
+    app.try_state::<MobileAudio>()
+        .map(|audio| audio.0.clone())
+        .ok_or_else(|| "Audio capture is not available".to_string())
+}
+
+/// Ask for microphone access if needed and start recording
+pub async fn start_capture(app: &tauri::AppHandle) -> Result<(), String> {
+    plugin(app)?
+        .run_mobile_plugin_async::<serde_json::Value>("startCapture", ())
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start recording: {}", e))
+}
+
+/// Stop recording and return the samples, sample rate and channel count
+pub async fn stop_capture(app: &tauri::AppHandle) -> Result<(Vec<i16>, u32, u16), String> {
+    let captured: CapturedAudio = plugin(app)?
+        .run_mobile_plugin_async("stopCapture", ())
+        .await
+        .map_err(|e| format!("Failed to stop recording: {}", e))?;
+
+    let bytes = tokio::fs::read(&captured.path)
+        .await
+        .map_err(|e| format!("Failed to read recording: {}", e))?;
+    if let Err(e) = tokio::fs::remove_file(&captured.path).await {
+        eprintln!("Failed to remove recording file: {}", e);
+    }
+
+    let samples = if captured.path.ends_with(".wav") {
+        hound::WavReader::new(std::io::Cursor::new(bytes))
+            .map_err(|e| format!("Failed to read recording: {}", e))?
+            .samples::<i16>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read recording: {}", e))?
+    } else {
+        bytes
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect()
+    };
+    Ok((samples, captured.sample_rate, captured.channels))
+}
+
+/// Stop recording and discard it
+pub async fn cancel_capture(app: &tauri::AppHandle) -> Result<(), String> {
+    plugin(app)?
+        .run_mobile_plugin_async::<serde_json::Value>("cancelCapture", ())
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to cancel recording: {}", e))
+}
@@ -0,0 +1,265 @@
+//! Audit log of outbound requests
+//!
+//! For users who must account for what data left their machine. When turned
+//! on, every request that carries user content to a provider (chat, discovery,
+//! transcription, speech, file uploads) appends one JSON line to
+//! `app_data_dir/audit/audit.jsonl`: when, to which provider and model, its
+//! size, an estimate of its tokens and a SHA-256 of the exact bytes sent. The
+//! content itself is never written. Each entry also carries the hash of the
+//! entry before it, so `verify_audit_log` can detect edited or removed lines.
+//!
+//! This is separate from `llm_logger`, which is a developer debugging aid
+//! that records full request bodies.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use tauri::Manager;
+
+use crate::settings;
+
+const AUDIT_LOG_ENABLED_KEY: &str = "audit_log_enabled";
+
+/// `prev_hash` of the first entry
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static LOG_PATH: OnceLock<PathBuf> = OnceLock::new();
+/// Hash of the last entry written; also serializes appends
+static LAST_HASH: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub provider: String,
+    /// Empty when the request isn't tied to a model (e.g. file uploads)
+    pub model: String,
+    pub endpoint: String,
+    pub bytes: usize,
+    /// About four characters per token of the text sent; None for audio and
+    /// file uploads
+    pub estimated_tokens: Option<u64>,
+    pub content_sha256: String,
+    pub prev_hash: String,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Estimate tokens from the text in a request body, skipping inline base64
+/// payloads (images, documents, audio)
+fn estimate_tokens(body: &serde_json::Value) -> u64 {
+    fn text_chars(value: &serde_json::Value) -> usize {
+        match value {
+            serde_json::Value::String(s) if s.starts_with("data:") => 0,
+            serde_json::Value::String(s) => s.chars().count(),
+            serde_json::Value::Array(items) => items.iter().map(text_chars).sum(),
+            serde_json::Value::Object(fields) => fields
+                .iter()
+                .filter(|(key, _)| key.as_str() != "data")
+                .map(|(_, value)| text_chars(value))
+                .sum(),
+            _ => 0,
+        }
+    }
+    (text_chars(body) as u64).div_ceil(4)
+}
+
+/// Last line of the log, read from the end so large logs load quickly
+fn read_last_line(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let start = len.saturating_sub(64 * 1024);
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut tail = String::new();
+    file.read_to_string(&mut tail).ok()?;
+    tail.lines().rev().find(|line| !line.trim().is_empty()).map(str::to_string)
+}
+
+/// Locate the log and load whether it is turned on. Called once at startup.
+pub fn init(app: &tauri::AppHandle) {
+    match app.path().app_data_dir() {
+        Ok(dir) => {
+            let _ = LOG_PATH.set(dir.join("audit").join("audit.jsonl"));
+        }
+        Err(e) => eprintln!("Audit log unavailable: {}", e),
+    }
+    ENABLED.store(
+        settings::get_setting(app, AUDIT_LOG_ENABLED_KEY).unwrap_or(false),
+        Ordering::Relaxed,
+    );
+}
+
+fn append(provider: &str, model: &str, endpoint: &str, content: &[u8], estimated_tokens: Option<u64>) {
+    let Some(path) = LOG_PATH.get() else {
+        return;
+    };
+
+    let mut last_hash = LAST_HASH.lock();
+    let prev_hash = last_hash
+        .clone()
+        .or_else(|| read_last_line(path).map(|line| sha256_hex(line.as_bytes())))
+        .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+    let entry = AuditEntry {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        provider: provider.to_string(),
+        model: model.to_string(),
+        endpoint: endpoint.to_string(),
+        bytes: content.len(),
+        estimated_tokens,
+        content_sha256: sha256_hex(content),
+        prev_hash,
+    };
+    let line = match serde_json::to_string(&entry) {
+        Ok(line) => line,
+        Err(e) => {
+            eprintln!("Failed to serialize audit entry: {}", e);
+            return;
+        }
+    };
+
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| OpenOptions::new().create(true).append(true).open(path))
+        .and_then(|mut file| writeln!(file, "{}", line));
+    match written {
+        Ok(()) => *last_hash = Some(sha256_hex(line.as_bytes())),
+        Err(e) => eprintln!("Failed to write audit entry: {}", e),
+    }
+}
+
+/// Record a JSON request body about to be sent
+pub fn record_json(provider: &str, model: &str, endpoint: &str, body: &serde_json::Value) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let content = serde_json::to_vec(body).unwrap_or_default();
+    append(provider, model, endpoint, &content, Some(estimate_tokens(body)));
+}
+
+/// Record raw bytes about to be sent (audio, file uploads)
+pub fn record_bytes(provider: &str, model: &str, endpoint: &str, content: &[u8]) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    append(provider, model, endpoint, content, None);
+}
+
+/// Check the hash chain; returns the number of entries, or the line number
+/// of the first entry that doesn't follow from the one before it
+fn verify_lines<I: Iterator<Item = String>>(lines: I) -> Result<usize, String> {
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut count = 0;
+    for (index, line) in lines.enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: AuditEntry = serde_json::from_str(&line)
+            .map_err(|e| format!("Line {} is not an audit entry: {}", index + 1, e))?;
+        if entry.prev_hash != prev_hash {
+            return Err(format!(
+                "Line {} doesn't follow from the entry before it; the log was modified",
+                index + 1
+            ));
+        }
+        prev_hash = sha256_hex(line.as_bytes());
+        count += 1;
+    }
+    Ok(count)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_audit_log_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[tauri::command]
+pub fn set_audit_log_enabled(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set_setting(&app, AUDIT_LOG_ENABLED_KEY, &enabled)?;
+    ENABLED.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Verify the log's hash chain; returns the number of entries
+#[tauri::command]
+pub fn verify_audit_log() -> Result<usize, String> {
+    let path = LOG_PATH.get().ok_or("Audit log unavailable")?;
+    if !path.exists() {
+        return Ok(0);
+    }
+    let _guard = LAST_HASH.lock();
+    let file = File::open(path).map_err(|e| format!("Failed to open audit log: {}", e))?;
+    let lines = BufReader::new(file)
+        .lines()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read audit log: {}", e))?;
+    verify_lines(lines.into_iter())
+}
+
+/// Copy the log to `path` (e.g. chosen with a save dialog)
+#[tauri::command]
+pub fn export_audit_log(path: String) -> Result<(), String> {
+    let source = LOG_PATH.get().ok_or("Audit log unavailable")?;
+    let _guard = LAST_HASH.lock();
+    let result = if source.exists() {
+        fs::copy(source, &path).map(|_| ())
+    } else {
+        fs::write(&path, "")
+    };
+    result.map_err(|e| format!("Failed to export audit log: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_hash_chain_and_detects_removed_entries() {
+        let entry = |prev_hash: &str, model: &str| {
+            serde_json::to_string(&AuditEntry {
+                timestamp: "2026-10-16T00:00:00Z".to_string(),
+                provider: "anthropic".to_string(),
+                model: model.to_string(),
+                endpoint: "messages".to_string(),
+                bytes: 10,
+                estimated_tokens: Some(3),
+                content_sha256: sha256_hex(b"body"),
+                prev_hash: prev_hash.to_string(),
+            })
+            .unwrap()
+        };
+        let first = entry(GENESIS_HASH, "a");
+        let second = entry(&sha256_hex(first.as_bytes()), "b");
+        let third = entry(&sha256_hex(second.as_bytes()), "c");
+
+        let lines = |lines: &[&String]| lines.iter().map(|l| l.to_string()).collect::<Vec<_>>().into_iter();
+        assert_eq!(verify_lines(lines(&[&first, &second, &third])), Ok(3));
+        assert!(verify_lines(lines(&[&first, &third])).is_err());
+    }
+
+    #[test]
+    fn token_estimate_skips_inline_payloads() {
+        let body = serde_json::json!({"messages": [{"content": [
+            {"text": "12345678"},
+            {"source": {"data": "A".repeat(1000)}},
+            {"image_url": format!("data:image/png;base64,{}", "A".repeat(1000))}
+        ]}]});
+        assert_eq!(estimate_tokens(&body), 2);
+    }
+}
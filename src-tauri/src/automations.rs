@@ -0,0 +1,382 @@
+//! Scheduled prompts (automations)
+//!
+//! An automation is a prompt + model + cron-like schedule. While the app is
+//! open a background loop checks the schedules once a minute, runs due
+//! automations through the provider layer, stores each result as a new chat
+//! session and emits `automation-completed` / `automation-failed` events so the
+//! frontend can notify the user.
+
+use chrono::{Datelike, Local, Timelike};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::commands::{new_session_id, new_session_json, session_message, store_session};
+use crate::llm::complete_prompt;
+use crate::settings;
+
+const AUTOMATIONS_SETTING_KEY: &str = "automations";
+
+/// How often the scheduler wakes up to look for due automations
+const SCHEDULER_TICK: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Automation {
+    /// Assigned on first save when empty
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub prompt: String,
+    pub model: String,
+    pub system_prompt: Option<String>,
+    /// Five-field cron expression: "minute hour day-of-month month day-of-week"
+    pub schedule: String,
+    #[serde(default)]
+    pub web_search_enabled: bool,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub last_run_at: Option<String>,
+    pub last_session_id: Option<String>,
+    pub last_error: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationCompletedEvent {
+    pub automation_id: String,
+    pub name: String,
+    pub session_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationFailedEvent {
+    pub automation_id: String,
+    pub name: String,
+    pub error: String,
+}
+
+// ============================================================================
+// Schedules
+// ============================================================================
+
+/// One parsed cron field: the set of allowed values
+#[derive(Debug, Clone, PartialEq)]
+struct CronField {
+    allowed: Vec<u32>,
+    /// True for a bare "*" (matters for the day-of-month / day-of-week rule)
+    wildcard: bool,
+}
+
+impl CronField {
+    fn parse(spec: &str, min: u32, max: u32) -> Result<Self, String> {
+        let mut allowed = Vec::new();
+        for part in spec.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => {
+                    let step: u32 = step.parse().map_err(|_| format!("Invalid step: {}", part))?;
+                    if step == 0 {
+                        return Err(format!("Invalid step: {}", part));
+                    }
+                    (range, step)
+                }
+                None => (part, 1),
+            };
+            let (start, end) = if range == "*" {
+                (min, max)
+            } else if let Some((a, b)) = range.split_once('-') {
+                let a = a.parse().map_err(|_| format!("Invalid range: {}", part))?;
+                let b = b.parse().map_err(|_| format!("Invalid range: {}", part))?;
+                (a, b)
+            } else {
+                let value = range.parse().map_err(|_| format!("Invalid value: {}", part))?;
+                // "5/15" means "starting at 5, every 15"
+                (value, if step > 1 { max } else { value })
+            };
+            if start < min || end > max || start > end {
+                return Err(format!("Value out of range ({}-{}): {}", min, max, part));
+            }
+            allowed.extend((start..=end).step_by(step as usize));
+        }
+        Ok(Self {
+            allowed,
+            wildcard: spec == "*",
+        })
+    }
+
+    fn matches(&self, value: u32) -> bool {
+        self.allowed.contains(&value)
+    }
+}
+
+/// A parsed five-field cron schedule, evaluated in local time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    minute: CronField,
+    hour: CronField,
+    day_of_month: CronField,
+    month: CronField,
+    day_of_week: CronField,
+}
+
+impl Schedule {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, dom, month, dow] = fields.as_slice() else {
+            return Err(format!(
+                "Schedule must have 5 fields (minute hour day month weekday): {}",
+                expr
+            ));
+        };
+        let mut day_of_week = CronField::parse(dow, 0, 7)?;
+        // Both 0 and 7 mean Sunday
+        if day_of_week.matches(7) {
+            day_of_week.allowed.push(0);
+        }
+        Ok(Self {
+            minute: CronField::parse(minute, 0, 59)?,
+            hour: CronField::parse(hour, 0, 23)?,
+            day_of_month: CronField::parse(dom, 1, 31)?,
+            month: CronField::parse(month, 1, 12)?,
+            day_of_week,
+        })
+    }
+
+    pub fn matches<T: Datelike + Timelike>(&self, time: &T) -> bool {
+        if !self.minute.matches(time.minute())
+            || !self.hour.matches(time.hour())
+            || !self.month.matches(time.month())
+        {
+            return false;
+        }
+        let dom = self.day_of_month.matches(time.day());
+        let dow = self.day_of_week.matches(time.weekday().num_days_from_sunday());
+        // Standard cron: when both day fields are restricted, either may match
+        match (self.day_of_month.wildcard, self.day_of_week.wildcard) {
+            (true, true) => true,
+            (true, false) => dow,
+            (false, true) => dom,
+            (false, false) => dom || dow,
+        }
+    }
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+fn load_automations(app: &tauri::AppHandle) -> Vec<Automation> {
+    settings::get_setting(app, AUTOMATIONS_SETTING_KEY).unwrap_or_default()
+}
+
+fn save_automations(app: &tauri::AppHandle, automations: &[Automation]) -> Result<(), String> {
+    settings::set_setting(app, AUTOMATIONS_SETTING_KEY, &automations)
+}
+
+/// Record the outcome of a run on the stored automation
+fn record_run(app: &tauri::AppHandle, id: &str, result: &Result<String, String>) {
+    let mut automations = load_automations(app);
+    if let Some(automation) = automations.iter_mut().find(|a| a.id == id) {
+        automation.last_run_at = Some(Local::now().to_rfc3339());
+        match result {
+            Ok(session_id) => {
+                automation.last_session_id = Some(session_id.clone());
+                automation.last_error = None;
+            }
+            Err(err) => automation.last_error = Some(err.clone()),
+        }
+        if let Err(err) = save_automations(app, &automations) {
+            eprintln!("Failed to save automation run: {}", err);
+        }
+    }
+}
+
+// ============================================================================
+// Running
+// ============================================================================
+
+/// Run an automation's prompt and store the reply as a new session.
+/// Returns the new session id.
+async fn execute_automation(app: &tauri::AppHandle, automation: &Automation) -> Result<String, String> {
+    let reply = complete_prompt(
+        app,
+        &automation.model,
+        automation.system_prompt.as_deref(),
+        &automation.prompt,
+        automation.web_search_enabled,
+    )
+    .await?;
+
+    let turn_id = new_session_id();
+    let title = format!("{} — {}", automation.name, Local::now().format("%Y-%m-%d %H:%M"));
+    let session = new_session_json(
+        &title,
+        &automation.model,
+        automation.web_search_enabled,
+        vec![
+            session_message("user", &automation.prompt, &turn_id),
+            session_message("assistant", &reply, &turn_id),
+        ],
+    );
+    let session_id = session["id"].as_str().unwrap_or_default().to_string();
+    store_session(app, session)?;
+
+    Ok(session_id)
+}
+
+/// Run an automation, record the outcome and notify the frontend
+async fn run_and_notify(app: &tauri::AppHandle, automation: &Automation) -> Result<String, String> {
+    let result = execute_automation(app, automation).await;
+    record_run(app, &automation.id, &result);
+
+    match &result {
+        Ok(session_id) => {
+            let event = AutomationCompletedEvent {
+                automation_id: automation.id.clone(),
+                name: automation.name.clone(),
+                session_id: session_id.clone(),
+            };
+            if let Err(err) = app.emit("automation-completed", event) {
+                eprintln!("Failed to emit automation-completed event: {}", err);
+            }
+        }
+        Err(error) => {
+            let event = AutomationFailedEvent {
+                automation_id: automation.id.clone(),
+                name: automation.name.clone(),
+                error: error.clone(),
+            };
+            if let Err(err) = app.emit("automation-failed", event) {
+                eprintln!("Failed to emit automation-failed event: {}", err);
+            }
+        }
+    }
+
+    result
+}
+
+/// Background loop that runs due automations while the app is open.
+/// Spawned once from `setup`.
+pub async fn run_scheduler(app: tauri::AppHandle) {
+    // Minute (as "YYYY-MM-DD HH:MM") of the last check, so each minute is handled once
+    let mut last_checked_minute = String::new();
+
+    loop {
+        tokio::time::sleep(SCHEDULER_TICK).await;
+
+        let now = Local::now();
+        let minute = now.format("%Y-%m-%d %H:%M").to_string();
+        if minute == last_checked_minute {
+            continue;
+        }
+        last_checked_minute = minute;
+
+        for automation in load_automations(&app).into_iter().filter(|a| a.enabled) {
+            let due = Schedule::parse(&automation.schedule)
+                .map(|s| s.matches(&now))
+                .unwrap_or(false);
+            if due {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    let _ = run_and_notify(&app, &automation).await;
+                });
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn list_automations(app: tauri::AppHandle) -> Vec<Automation> {
+    load_automations(&app)
+}
+
+/// Create or update an automation (matched by id). Returns the saved automation.
+#[tauri::command]
+pub fn save_automation(app: tauri::AppHandle, automation: Automation) -> Result<Automation, String> {
+    Schedule::parse(&automation.schedule)?;
+    if automation.prompt.trim().is_empty() {
+        return Err("Automation prompt cannot be empty".to_string());
+    }
+
+    let mut automation = automation;
+    if automation.id.is_empty() {
+        automation.id = new_session_id();
+    }
+
+    let mut automations = load_automations(&app);
+    match automations.iter_mut().find(|a| a.id == automation.id) {
+        Some(existing) => *existing = automation.clone(),
+        None => automations.push(automation.clone()),
+    }
+    save_automations(&app, &automations)?;
+
+    Ok(automation)
+}
+
+#[tauri::command]
+pub fn delete_automation(app: tauri::AppHandle, automation_id: String) -> Result<(), String> {
+    let mut automations = load_automations(&app);
+    automations.retain(|a| a.id != automation_id);
+    save_automations(&app, &automations)
+}
+
+/// Run an automation immediately, regardless of its schedule.
+/// Returns the id of the session holding the result.
+#[tauri::command]
+pub async fn run_automation_now(app: tauri::AppHandle, automation_id: String) -> Result<String, String> {
+    let automation = load_automations(&app)
+        .into_iter()
+        .find(|a| a.id == automation_id)
+        .ok_or_else(|| format!("Automation not found: {}", automation_id))?;
+    run_and_notify(&app, &automation).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> chrono::NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn weekday_mornings_schedule() {
+        let schedule = Schedule::parse("30 8 * * 1-5").unwrap();
+        assert!(schedule.matches(&at(2026, 10, 16, 8, 30))); // Friday
+        assert!(!schedule.matches(&at(2026, 10, 17, 8, 30))); // Saturday
+        assert!(!schedule.matches(&at(2026, 10, 16, 8, 31)));
+    }
+
+    #[test]
+    fn steps_lists_and_sunday_aliases() {
+        let schedule = Schedule::parse("*/15 9,17 * * 7").unwrap();
+        assert!(schedule.matches(&at(2026, 10, 18, 17, 45))); // Sunday
+        assert!(!schedule.matches(&at(2026, 10, 18, 17, 50)));
+        assert!(!schedule.matches(&at(2026, 10, 18, 12, 0)));
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either() {
+        let schedule = Schedule::parse("0 0 1 * 1").unwrap();
+        assert!(schedule.matches(&at(2026, 10, 1, 0, 0))); // 1st (a Thursday)
+        assert!(schedule.matches(&at(2026, 10, 19, 0, 0))); // a Monday
+        assert!(!schedule.matches(&at(2026, 10, 20, 0, 0)));
+    }
+
+    #[test]
+    fn rejects_malformed_schedules() {
+        assert!(Schedule::parse("* * * *").is_err());
+        assert!(Schedule::parse("60 * * * *").is_err());
+        assert!(Schedule::parse("*/0 * * * *").is_err());
+    }
+}
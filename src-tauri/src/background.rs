@@ -0,0 +1,213 @@
+//! Background completion on mobile
+//!
+//! iOS and Android background the app whenever the user switches away, often
+//! in the middle of a long generation. Chat turns run as their own task so
+//! they finish regardless; a turn that completes while the app is in the
+//! background is recorded here and announced with a local notification. When
+//! the app comes back the notifications are cleared, and the frontend picks
+//! up the finished replies with `take_background_completions` (its stream
+//! events may have been dropped while the webview was suspended).
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Manager;
+
+use crate::error::SidestreamError;
+
+/// Characters of the reply shown in the notification
+const NOTIFICATION_PREVIEW_CHARS: usize = 120;
+
+/// A chat turn that finished while the app was in the background
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundCompletion {
+    pub turn_id: String,
+    pub session_id: Option<String>,
+    /// Reply text as streamed (the frontend's copy may be incomplete)
+    pub text: String,
+    pub error: Option<String>,
+    pub completed_at: String,
+}
+
+/// Tauri-managed foreground/background tracking
+#[derive(Default)]
+pub struct BackgroundState {
+    backgrounded: AtomicBool,
+    completions: Mutex<Vec<BackgroundCompletion>>,
+}
+
+impl BackgroundState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Notification id for a turn (stable, so it can be removed later)
+#[cfg_attr(not(mobile), allow(dead_code))]
+fn notification_id(turn_id: &str) -> i32 {
+    turn_id
+        .bytes()
+        .fold(0i32, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as i32))
+        & i32::MAX
+}
+
+/// Body of an incognito turn's notification, which leaves out the reply
+/// and error text (they would stay on the lock screen and in the
+/// notification history)
+fn incognito_notification_body(failed: bool) -> String {
+    if failed {
+        "The response failed.".to_string()
+    } else {
+        "Open the app to read it.".to_string()
+    }
+}
+
+fn notification_body(text: &str, error: Option<&str>) -> String {
+    if let Some(error) = error {
+        return format!("The response failed: {}", error);
+    }
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > NOTIFICATION_PREVIEW_CHARS {
+        let preview: String = text.chars().take(NOTIFICATION_PREVIEW_CHARS).collect();
+        format!("{}…", preview.trim_end())
+    } else {
+        text
+    }
+}
+
+#[cfg(mobile)]
+fn post_notification(app: &tauri::AppHandle, turn_id: &str, body: String) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let result = app
+        .notification()
+        .builder()
+        .id(notification_id(turn_id))
+        .title("Response ready")
+        .body(body)
+        .show();
+    if let Err(err) = result {
+        eprintln!("Failed to post completion notification: {}", err);
+    }
+}
+
+#[cfg(not(mobile))]
+fn post_notification(_app: &tauri::AppHandle, _turn_id: &str, _body: String) {}
+
+/// Clear delivered notifications for replies the user has now come back to
+#[cfg(mobile)]
+fn clear_notifications(app: &tauri::AppHandle, turn_ids: &[String]) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let ids = turn_ids.iter().map(|id| notification_id(id)).collect();
+    if let Err(err) = app.notification().remove_active(ids) {
+        eprintln!("Failed to clear completion notifications: {}", err);
+    }
+}
+
+#[cfg(not(mobile))]
+fn clear_notifications(_app: &tauri::AppHandle, _turn_ids: &[String]) {}
+
+/// Ask for notification permission before the first long generation, while
+/// the app is still in front (it can't prompt from the background)
+#[cfg(mobile)]
+pub fn ensure_notification_permission(app: &tauri::AppHandle) {
+    use tauri_plugin_notification::{NotificationExt, PermissionState};
+
+    let notification = app.notification();
+    if matches!(
+        notification.permission_state(),
+        Ok(PermissionState::Prompt | PermissionState::PromptWithRationale)
+    ) {
+        if let Err(err) = notification.request_permission() {
+            eprintln!("Failed to request notification permission: {}", err);
+        }
+    }
+}
+
+#[cfg(not(mobile))]
+pub fn ensure_notification_permission(_app: &tauri::AppHandle) {}
+
+/// Track window focus. Only mobile counts losing focus as being backgrounded;
+/// on desktop an unfocused window is still on screen.
+pub fn set_focused(app: &tauri::AppHandle, focused: bool) {
+    if !cfg!(mobile) {
+        return;
+    }
+    let Some(state) = app.try_state::<BackgroundState>() else {
+        return;
+    };
+    state.backgrounded.store(!focused, Ordering::SeqCst);
+    if focused {
+        let turn_ids: Vec<String> = state.completions.lock().iter().map(|c| c.turn_id.clone()).collect();
+        if !turn_ids.is_empty() {
+            clear_notifications(app, &turn_ids);
+        }
+    }
+}
+
+/// Record a finished turn (with the text it streamed) and notify if the app
+/// is in the background. Incognito turns get a notification without content.
+pub fn finish_turn(
+    app: &tauri::AppHandle,
+    turn_id: &str,
+    session_id: Option<&str>,
+    text: &str,
+    result: &Result<(), SidestreamError>,
+    incognito: bool,
+) {
+    let Some(state) = app.try_state::<BackgroundState>() else {
+        return;
+    };
+    if !state.backgrounded.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let error = result.as_ref().err().map(ToString::to_string);
+
+    let body = if incognito {
+        incognito_notification_body(error.is_some())
+    } else {
+        notification_body(text, error.as_deref())
+    };
+    post_notification(app, turn_id, body);
+    state.completions.lock().push(BackgroundCompletion {
+        turn_id: turn_id.to_string(),
+        session_id: session_id.map(str::to_string),
+        text: text.to_string(),
+        error,
+        completed_at: chrono::Utc::now().to_rfc3339(),
+    });
+}
+
+/// Replies that finished in the background since the last call. Taking them
+/// marks them as seen and clears their notifications.
+#[tauri::command]
+pub fn take_background_completions(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, BackgroundState>,
+) -> Vec<BackgroundCompletion> {
+    let completions = std::mem::take(&mut *state.completions.lock());
+    if !completions.is_empty() {
+        let turn_ids: Vec<String> = completions.iter().map(|c| c.turn_id.clone()).collect();
+        clear_notifications(&app, &turn_ids);
+    }
+    completions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notification_body_previews_reply_or_error() {
+        assert_eq!(notification_body("Short\n\nanswer.", None), "Short answer.");
+        let long = "word ".repeat(50);
+        let body = notification_body(&long, None);
+        assert!(body.ends_with('…'));
+        assert!(body.chars().count() <= NOTIFICATION_PREVIEW_CHARS + 1);
+        assert_eq!(notification_body("", Some("timeout")), "The response failed: timeout");
+        assert!(notification_id("turn-1") >= 0);
+    }
+}
@@ -0,0 +1,262 @@
+//! Prompt cache warmup
+//!
+//! Opening a session with a long conversation (or a large system prompt)
+//! means its first turn pays full price and latency for a prefix the
+//! provider could have cached. When warmup is on, the frontend calls
+//! `warm_prompt_cache` as a session opens, with the same messages and
+//! options it would send; the request is built the way a turn builds it
+//! and sent in the background as a one-token completion, so Anthropic (via
+//! the turn's cache breakpoints) or OpenAI (via the session's cache key)
+//! has the prefix cached when the real turn arrives. Only prefixes long
+//! enough to be cached are warmed. `prompt-cache-warmed` reports the result.
+
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::commands::get_api_key_async;
+use crate::content_filters;
+use crate::error::SidestreamError;
+use crate::llm::{get_provider_for_model, prepare_attachments, ChatMessage};
+use crate::llm_anthropic;
+use crate::pins;
+use crate::providers::anthropic::AnthropicClient;
+use crate::providers::openai::{ChatRequestConfig as OpenAIChatRequestConfig, OpenAIClient};
+use crate::settings;
+use crate::snippets;
+use crate::system_prompts;
+use crate::workspaces;
+
+const PROMPT_CACHE_WARMUP_KEY: &str = "prompt_cache_warmup";
+
+/// Shortest prefix either provider caches (Anthropic's minimum for most
+/// models, OpenAI's for all)
+const MIN_CACHEABLE_TOKENS: usize = 1024;
+
+/// Smallest output OpenAI accepts
+const OPENAI_MIN_OUTPUT_TOKENS: u32 = 16;
+
+/// Stand-in for the next user message, after the cached prefix
+const PLACEHOLDER_MESSAGE: &str = ".";
+
+/// What a turn in the session would send
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarmupRequest {
+    pub session_id: String,
+    /// Falls back to the chat model default
+    pub model: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    pub system_prompt: Option<String>,
+    #[serde(default)]
+    pub web_search_enabled: bool,
+    #[serde(default)]
+    pub code_execution_enabled: bool,
+}
+
+/// Event payload once a warmup request has completed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptCacheWarmedEvent {
+    pub session_id: String,
+    pub provider: String,
+    pub model: String,
+    pub input_tokens: u64,
+    /// Tokens written to the cache (Anthropic reports these; OpenAI doesn't)
+    pub cache_write_tokens: u64,
+    /// Tokens that were already cached
+    pub cache_read_tokens: u64,
+}
+
+fn warmup_enabled(app: &tauri::AppHandle) -> bool {
+    settings::get_setting(app, PROMPT_CACHE_WARMUP_KEY).unwrap_or(false)
+}
+
+/// Rough token count of the prefix (~4 characters per token)
+fn estimated_prefix_tokens(system_prompt: Option<&str>, messages: &[ChatMessage]) -> usize {
+    let message_chars: usize = messages.iter().map(|m| m.content.to_string().len()).sum();
+    (system_prompt.map_or(0, str::len) + message_chars) / 4
+}
+
+/// The conversation followed by a placeholder user message, so the request
+/// is valid and the prefix ends where the next turn's new message begins
+fn with_placeholder(mut messages: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+    messages.push(serde_json::json!({"role": "user", "content": PLACEHOLDER_MESSAGE}));
+    messages
+}
+
+fn usage_event(request: &WarmupRequest, provider: &str, model: &str, usage: &serde_json::Value) -> PromptCacheWarmedEvent {
+    let tokens = |value: &serde_json::Value| value.as_u64().unwrap_or(0);
+    let (cache_write_tokens, cache_read_tokens) = match provider {
+        "anthropic" => (
+            tokens(&usage["cache_creation_input_tokens"]),
+            tokens(&usage["cache_read_input_tokens"]),
+        ),
+        _ => (0, tokens(&usage["input_tokens_details"]["cached_tokens"])),
+    };
+    PromptCacheWarmedEvent {
+        session_id: request.session_id.clone(),
+        provider: provider.to_string(),
+        model: model.to_string(),
+        input_tokens: tokens(&usage["input_tokens"]),
+        cache_write_tokens,
+        cache_read_tokens,
+    }
+}
+
+async fn warm_anthropic(
+    app: &tauri::AppHandle,
+    request: &WarmupRequest,
+    model: &str,
+    messages: &[ChatMessage],
+    system_prompt: Option<String>,
+) -> Result<serde_json::Value, SidestreamError> {
+    let client = AnthropicClient::new(get_api_key_async(app, "anthropic").await?);
+    // Built as the turn builds it, so the cache breakpoint on the last
+    // message falls where the next turn looks for it. Thinking stays off: a
+    // one-token reply leaves no room for a thinking budget.
+    let (mut config, beta_header) = llm_anthropic::chat_request_config(
+        model,
+        messages,
+        system_prompt,
+        None,
+        request.web_search_enabled,
+        request.code_execution_enabled,
+        None,
+    );
+    config.messages = with_placeholder(config.messages);
+    let mut body = client.build_chat_request(&config);
+    body["max_tokens"] = serde_json::json!(1);
+    body["stream"] = serde_json::json!(false);
+    let response = client.send_request_with_beta(&body, beta_header.as_deref()).await?;
+    Ok(response["usage"].clone())
+}
+
+async fn warm_openai(
+    app: &tauri::AppHandle,
+    request: &WarmupRequest,
+    model: &str,
+    messages: &[ChatMessage],
+    system_prompt: Option<String>,
+) -> Result<serde_json::Value, SidestreamError> {
+    let client = OpenAIClient::new(get_api_key_async(app, "openai").await?);
+    let messages = messages
+        .iter()
+        .map(|m| serde_json::json!({"role": m.role, "content": m.content}))
+        .collect();
+    // Same tools, instructions and cache key as a turn; reasoning effort
+    // isn't part of the cached prefix
+    let config = OpenAIChatRequestConfig {
+        model: model.to_string(),
+        messages: with_placeholder(messages),
+        system_prompt,
+        reasoning_effort: None,
+        web_search_enabled: request.web_search_enabled,
+        prompt_cache_key: Some(format!("chat-{}", request.session_id)),
+        code_interpreter_enabled: request.code_execution_enabled,
+        container_id: None,
+    };
+    let mut body = client.build_chat_request(&config);
+    body["max_output_tokens"] = serde_json::json!(OPENAI_MIN_OUTPUT_TOKENS);
+    body["stream"] = serde_json::json!(false);
+    if body.get("tools").is_some() {
+        body["tool_choice"] = serde_json::json!("none");
+    }
+    let response = client.send_request(&body).await?;
+    Ok(response["usage"].clone())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Warm the provider's prompt cache for a session in the background.
+/// Returns whether a warmup request was started: not when warmup is off, the
+/// model's provider has no prompt cache to warm, or the prefix is too short
+/// to be cached.
+#[tauri::command]
+pub async fn warm_prompt_cache(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    request: WarmupRequest,
+) -> Result<bool, String> {
+    if !warmup_enabled(&app) {
+        return Ok(false);
+    }
+    let model = request
+        .model
+        .clone()
+        .unwrap_or_else(|| workspaces::default_chat_model(&app));
+    let provider = get_provider_for_model(&model);
+    if !matches!(provider, "anthropic" | "openai") {
+        return Ok(false);
+    }
+
+    // The same preparation as a turn, up to the new message
+    let messages = prepare_attachments(snippets::expand_messages(&app, request.messages.clone()));
+    let messages = content_filters::mask_messages(&app, messages);
+    let messages = pins::assemble_context(&app, Some(&request.session_id), messages);
+    let system_prompt =
+        system_prompts::layered_system_prompt(&app, request.system_prompt.as_deref(), Some(&request.session_id));
+    if estimated_prefix_tokens(system_prompt.as_deref(), &messages) < MIN_CACHEABLE_TOKENS {
+        return Ok(false);
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let usage = match provider {
+            "anthropic" => warm_anthropic(&app, &request, &model, &messages, system_prompt).await,
+            _ => warm_openai(&app, &request, &model, &messages, system_prompt).await,
+        };
+        match usage {
+            Ok(usage) => {
+                let event = usage_event(&request, provider, &model, &usage);
+                if let Err(err) = window.emit("prompt-cache-warmed", event) {
+                    eprintln!("Failed to emit prompt-cache-warmed event: {}", err);
+                }
+            }
+            Err(e) => eprintln!("Prompt cache warmup failed: {}", e),
+        }
+    });
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn get_prompt_cache_warmup(app: tauri::AppHandle) -> bool {
+    warmup_enabled(&app)
+}
+
+#[tauri::command]
+pub fn set_prompt_cache_warmup(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set_setting(&app, PROMPT_CACHE_WARMUP_KEY, &enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_cache_usage_for_each_provider() {
+        let request = WarmupRequest {
+            session_id: "s1".to_string(),
+            model: None,
+            messages: Vec::new(),
+            system_prompt: None,
+            web_search_enabled: false,
+            code_execution_enabled: false,
+        };
+        let anthropic = usage_event(
+            &request,
+            "anthropic",
+            "claude-sonnet-4-6",
+            &serde_json::json!({"input_tokens": 3, "cache_creation_input_tokens": 4200, "cache_read_input_tokens": 0}),
+        );
+        assert_eq!((anthropic.input_tokens, anthropic.cache_write_tokens, anthropic.cache_read_tokens), (3, 4200, 0));
+        let openai = usage_event(
+            &request,
+            "openai",
+            "gpt-5",
+            &serde_json::json!({"input_tokens": 2100, "input_tokens_details": {"cached_tokens": 1920}}),
+        );
+        assert_eq!((openai.input_tokens, openai.cache_write_tokens, openai.cache_read_tokens), (2100, 0, 1920));
+
+        assert_eq!(with_placeholder(Vec::new()), [serde_json::json!({"role": "user", "content": "."})]);
+    }
+}
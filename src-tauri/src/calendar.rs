@@ -0,0 +1,250 @@
+//! Calendar and reminder extraction
+//!
+//! `extract_calendar_items` asks a model to pull events and to-dos out of an
+//! assistant reply or a transcript as schema-constrained JSON. The frontend
+//! shows them for confirmation, then `add_calendar_items` writes the confirmed
+//! ones to macOS Calendar and Reminders through AppleScript.
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::settings;
+use crate::structured::structured_completion;
+
+const EXTRACTION_INSTRUCTION: &str = "Extract calendar events and to-dos from the text the user sends. Give dates as local times in the form YYYY-MM-DDTHH:MM; for all-day events set allDay and give start as YYYY-MM-DD. Resolve relative dates (\"next Tuesday\", \"tomorrow\") against today's date given below. Only include items the text actually states or clearly commits to; return empty lists if there are none.";
+
+/// JSON Schema of `CalendarItems`
+fn calendar_items_schema() -> serde_json::Value {
+    let optional_string = serde_json::json!({"type": ["string", "null"]});
+    serde_json::json!({
+        "type": "object",
+        "required": ["events", "todos"],
+        "properties": {
+            "events": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["title", "start"],
+                    "properties": {
+                        "title": {"type": "string"},
+                        "start": {"type": "string"},
+                        "end": optional_string,
+                        "allDay": {"type": "boolean"},
+                        "location": optional_string,
+                        "notes": optional_string
+                    }
+                }
+            },
+            "todos": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["title"],
+                    "properties": {
+                        "title": {"type": "string"},
+                        "due": optional_string,
+                        "notes": optional_string
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarEvent {
+    pub title: String,
+    /// Local date-time ("YYYY-MM-DDTHH:MM"), or a date for all-day events
+    pub start: String,
+    pub end: Option<String>,
+    #[serde(default)]
+    pub all_day: bool,
+    pub location: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoItem {
+    pub title: String,
+    /// Local date-time or date, if the to-do has a deadline
+    pub due: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalendarItems {
+    pub events: Vec<CalendarEvent>,
+    pub todos: Vec<TodoItem>,
+}
+
+/// Parse "YYYY-MM-DDTHH:MM[:SS]" or "YYYY-MM-DD" (midnight)
+fn parse_local_datetime(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// Quote a string for AppleScript
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// AppleScript statements setting variable `name` to a date. Built from
+/// components because date literals are parsed in the user's locale.
+fn applescript_date(name: &str, value: &NaiveDateTime) -> String {
+    format!(
+        "set {name} to current date\nset day of {name} to 1\nset year of {name} to {}\nset month of {name} to {}\nset day of {name} to {}\nset time of {name} to {}\n",
+        value.year(),
+        value.month(),
+        value.day(),
+        value.num_seconds_from_midnight(),
+        name = name
+    )
+}
+
+fn event_script(event: &CalendarEvent) -> Result<String, String> {
+    let start = parse_local_datetime(&event.start)
+        .ok_or_else(|| format!("Invalid start for \"{}\": {}", event.title, event.start))?;
+    let end = match event.end.as_deref().and_then(parse_local_datetime) {
+        Some(end) if end > start => end,
+        _ if event.all_day => start + chrono::Duration::days(1),
+        _ => start + chrono::Duration::hours(1),
+    };
+
+    let mut properties = vec![
+        format!("summary:{}", applescript_string(&event.title)),
+        "start date:startDate".to_string(),
+        "end date:endDate".to_string(),
+        format!("allday event:{}", event.all_day),
+    ];
+    if let Some(location) = &event.location {
+        properties.push(format!("location:{}", applescript_string(location)));
+    }
+    if let Some(notes) = &event.notes {
+        properties.push(format!("description:{}", applescript_string(notes)));
+    }
+
+    Ok(format!(
+        "{}{}tell application \"Calendar\"\ntell (first calendar whose writable is true)\nmake new event at end with properties {{{}}}\nend tell\nend tell",
+        applescript_date("startDate", &start),
+        applescript_date("endDate", &end),
+        properties.join(", ")
+    ))
+}
+
+fn todo_script(todo: &TodoItem) -> String {
+    let mut script = String::new();
+    let mut properties = vec![format!("name:{}", applescript_string(&todo.title))];
+    if let Some(due) = todo.due.as_deref().and_then(parse_local_datetime) {
+        script.push_str(&applescript_date("dueDate", &due));
+        properties.push("due date:dueDate".to_string());
+    }
+    if let Some(notes) = &todo.notes {
+        properties.push(format!("body:{}", applescript_string(notes)));
+    }
+    script.push_str(&format!(
+        "tell application \"Reminders\"\ntell default list\nmake new reminder with properties {{{}}}\nend tell\nend tell",
+        properties.join(", ")
+    ));
+    script
+}
+
+#[cfg(target_os = "macos")]
+fn run_applescript(script: &str) -> Result<(), String> {
+    let output = std::process::Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .output()
+        .map_err(|e| format!("Failed to run osascript: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn run_applescript(_script: &str) -> Result<(), String> {
+    Err("Adding to Calendar and Reminders is only supported on macOS".to_string())
+}
+
+/// Extract events and to-dos from text (an assistant reply or a transcript).
+/// Uses the summarization model default unless a model is given.
+#[tauri::command]
+pub async fn extract_calendar_items(
+    app: tauri::AppHandle,
+    text: String,
+    model: Option<String>,
+) -> Result<CalendarItems, String> {
+    let model = model.unwrap_or_else(|| settings::model_defaults(&app).summarization);
+    let instruction = format!(
+        "{}\n\nToday is {}.",
+        EXTRACTION_INSTRUCTION,
+        chrono::Local::now().format("%A, %Y-%m-%d %H:%M")
+    );
+    let items = structured_completion(&app, &model, Some(&instruction), &text, &calendar_items_schema()).await?;
+    serde_json::from_value(items).map_err(|e| format!("Failed to read extracted items: {}", e))
+}
+
+/// Write confirmed items to Calendar (events) and Reminders (to-dos). Returns
+/// how many were added; items that fail are reported in the error.
+#[tauri::command]
+pub async fn add_calendar_items(items: CalendarItems) -> Result<usize, String> {
+    let mut scripts = Vec::new();
+    for event in &items.events {
+        scripts.push((event.title.clone(), event_script(event)?));
+    }
+    for todo in &items.todos {
+        scripts.push((todo.title.clone(), todo_script(todo)));
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let mut added = 0;
+        let mut failures = Vec::new();
+        for (title, script) in scripts {
+            match run_applescript(&script) {
+                Ok(()) => added += 1,
+                Err(e) => failures.push(format!("{}: {}", title, e)),
+            }
+        }
+        if failures.is_empty() {
+            Ok(added)
+        } else {
+            Err(format!("Added {} item(s); failed: {}", added, failures.join("; ")))
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_scripts_build_dates_from_components_and_escape_text() {
+        let event = CalendarEvent {
+            title: "Say \"hi\"".to_string(),
+            start: "2026-03-05T14:30".to_string(),
+            end: None,
+            all_day: false,
+            location: None,
+            notes: None,
+        };
+        let script = event_script(&event).unwrap();
+        assert!(script.contains("set month of startDate to 3"));
+        assert!(script.contains("set time of startDate to 52200"));
+        // Defaults to a one-hour event
+        assert!(script.contains("set time of endDate to 55800"));
+        assert!(script.contains(r#"summary:"Say \"hi\"""#));
+    }
+}
@@ -0,0 +1,619 @@
+//! Citation metadata
+//!
+//! Citation chips only get a URL (and for Gemini, an opaque grounding redirect
+//! link). `resolve_citation` follows redirects to the real page and reads its
+//! title, site name, favicon and publish date from the HTML head. Results are
+//! cached on disk under `app_data_dir/citations`, keyed by the requested URL.
+//!
+//! While a reply streams, `CitationNormalizer` cleans each turn's inline
+//! citations before they are emitted: tracking parameters are stripped, and
+//! every citation of a page cited several times (often with slightly
+//! different titles) carries the best of its titles seen so far.
+//!
+//! Gemini cites pages through opaque grounding redirect links. Its citations
+//! are emitted from a spawned task once each link is resolved with a HEAD
+//! request that follows the redirect, and the page it leads to replaces it
+//! (with its domain as the title where Gemini only gave a domain), so the UI
+//! and exports show real sources without holding up the stream. The mapping
+//! is cached in memory and in `app_data_dir/citations/grounding_redirects.json`,
+//! for up to 30 days and 5000 links.
+
+use parking_lot::Mutex;
+use regex::Regex;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::Manager;
+
+use crate::attachments::content_key;
+use crate::llm::{emit_stream_delta, StreamDelta};
+use crate::providers::anthropic::InlineCitation;
+use crate::proxy;
+
+/// Only the start of a page is read; the metadata lives in <head>
+const MAX_HTML_BYTES: usize = 512 * 1024;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Host of Gemini's grounding redirect links
+const GROUNDING_REDIRECT_HOST: &str = "vertexaisearch.cloud.google.com";
+
+/// Resolving a redirect holds up the citations, so it gets less time than a
+/// metadata fetch
+const REDIRECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+const REDIRECT_CACHE_FILE: &str = "grounding_redirects.json";
+
+/// Resolved redirects are forgotten after this many seconds (30 days)
+const REDIRECT_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Most redirects kept; the oldest go first
+const MAX_CACHED_REDIRECTS: usize = 5000;
+
+/// A redirect link's page and when it was resolved (Unix seconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedRedirect {
+    page: String,
+    resolved_at: i64,
+}
+
+/// Redirect link → page, loaded from disk on first use
+static REDIRECTS: Mutex<Option<BTreeMap<String, CachedRedirect>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationMetadata {
+    /// The URL that was requested (possibly a redirect link)
+    pub url: String,
+    /// The page's final URL after redirects
+    pub resolved_url: String,
+    pub title: Option<String>,
+    pub site_name: Option<String>,
+    pub favicon_url: Option<String>,
+    /// Publish date as written by the page (usually ISO 8601)
+    pub published_at: Option<String>,
+}
+
+pub fn citation_cache_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join("citations"))
+}
+
+fn meta_tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<meta\s[^>]*>").unwrap())
+}
+
+fn link_tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<link\s[^>]*>").unwrap())
+}
+
+fn title_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap())
+}
+
+fn time_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(?is)<time\s[^>]*datetime\s*=\s*["']([^"']+)["']"#).unwrap())
+}
+
+fn json_ld_date_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#""datePublished"\s*:\s*"([^"]+)""#).unwrap())
+}
+
+/// Value of an attribute in a single tag
+fn tag_attribute(tag: &str, name: &str) -> Option<String> {
+    let pattern = format!(r#"(?is)\b{}\s*=\s*(?:"([^"]*)"|'([^']*)')"#, regex::escape(name));
+    let re = Regex::new(&pattern).ok()?;
+    let captures = re.captures(tag)?;
+    captures
+        .get(1)
+        .or_else(|| captures.get(2))
+        .map(|m| decode_entities(m.as_str().trim()))
+}
+
+/// Decode the handful of HTML entities common in titles
+pub(crate) fn decode_entities(text: &str) -> String {
+    text.replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+}
+
+/// Content of the first <meta> tag whose property/name/itemprop is one of `keys`
+fn meta_content(html: &str, keys: &[&str]) -> Option<String> {
+    for key in keys {
+        for tag in meta_tag_regex().find_iter(html) {
+            let tag = tag.as_str();
+            let matches_key = ["property", "name", "itemprop"]
+                .iter()
+                .filter_map(|attr| tag_attribute(tag, attr))
+                .any(|value| value.eq_ignore_ascii_case(key));
+            if matches_key {
+                if let Some(content) = tag_attribute(tag, "content").filter(|c| !c.is_empty()) {
+                    return Some(content);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The page's declared icon, resolved against the page URL, or /favicon.ico
+fn favicon_url(html: &str, base: &Url) -> Option<String> {
+    let declared = link_tag_regex().find_iter(html).find_map(|tag| {
+        let tag = tag.as_str();
+        let rel = tag_attribute(tag, "rel")?.to_lowercase();
+        let is_icon = rel.split_whitespace().any(|r| r == "icon" || r == "apple-touch-icon");
+        if is_icon {
+            tag_attribute(tag, "href")
+        } else {
+            None
+        }
+    });
+    base.join(declared.as_deref().unwrap_or("/favicon.ico"))
+        .ok()
+        .map(|u| u.to_string())
+}
+
+/// Extract citation metadata from (the head of) a page
+fn parse_html_metadata(html: &str, requested_url: &str, page_url: &Url) -> CitationMetadata {
+    let title = meta_content(html, &["og:title", "twitter:title"]).or_else(|| {
+        title_regex()
+            .captures(html)
+            .map(|c| decode_entities(c[1].split_whitespace().collect::<Vec<_>>().join(" ").as_str()))
+            .filter(|t| !t.is_empty())
+    });
+    let site_name = meta_content(html, &["og:site_name", "application-name"])
+        .or_else(|| page_url.host_str().map(|h| h.trim_start_matches("www.").to_string()));
+    let published_at = meta_content(
+        html,
+        &["article:published_time", "datePublished", "date", "pubdate", "dc.date"],
+    )
+    .or_else(|| json_ld_date_regex().captures(html).map(|c| c[1].to_string()))
+    .or_else(|| time_regex().captures(html).map(|c| c[1].to_string()));
+
+    CitationMetadata {
+        url: requested_url.to_string(),
+        resolved_url: page_url.to_string(),
+        title,
+        site_name,
+        favicon_url: favicon_url(html, page_url),
+        published_at,
+    }
+}
+
+async fn fetch_citation_metadata(url: &str) -> Result<CitationMetadata, String> {
+    let client = proxy::client_builder()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Sidestream")
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    // Redirects (including Gemini grounding links) are followed by reqwest
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+    let page_url = response.url().clone();
+
+    if !response.status().is_success() {
+        // The redirect target alone is still useful to show
+        return Ok(CitationMetadata {
+            url: url.to_string(),
+            site_name: page_url.host_str().map(|h| h.trim_start_matches("www.").to_string()),
+            resolved_url: page_url.to_string(),
+            ..Default::default()
+        });
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        body.extend_from_slice(&chunk);
+        if body.len() >= MAX_HTML_BYTES || body.windows(7).any(|w| w.eq_ignore_ascii_case(b"</head>")) {
+            break;
+        }
+    }
+
+    Ok(parse_html_metadata(&String::from_utf8_lossy(&body), url, &page_url))
+}
+
+/// Resolve a citation URL to display metadata (title, site, favicon, date)
+#[tauri::command]
+pub async fn resolve_citation(app: tauri::AppHandle, url: String) -> Result<CitationMetadata, String> {
+    let cache_dir = citation_cache_dir(&app)?;
+    let cache_path = cache_dir.join(format!("{}.json", content_key(url.as_bytes())));
+    if let Some(cached) = fs::read(&cache_path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+    {
+        return Ok(cached);
+    }
+
+    let metadata = fetch_citation_metadata(&url).await?;
+
+    if fs::create_dir_all(&cache_dir).is_ok() {
+        if let Ok(json) = serde_json::to_vec(&metadata) {
+            if let Err(e) = fs::write(&cache_path, json) {
+                eprintln!("Failed to cache citation metadata: {}", e);
+            }
+        }
+    }
+
+    Ok(metadata)
+}
+
+// ============================================================================
+// Grounding redirects
+// ============================================================================
+
+/// Whether `url` is a Gemini grounding redirect link
+pub fn is_grounding_redirect(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|parsed| parsed.host_str() == Some(GROUNDING_REDIRECT_HOST))
+}
+
+fn host_without_www(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    parsed.host_str().map(|h| h.trim_start_matches("www.").to_string())
+}
+
+/// Cached page URLs for the given redirect links
+fn cached_redirects(app: &tauri::AppHandle, urls: &[String]) -> BTreeMap<String, String> {
+    let now = chrono::Utc::now().timestamp();
+    let mut redirects = REDIRECTS.lock();
+    let cache = redirects.get_or_insert_with(|| {
+        citation_cache_dir(app)
+            .ok()
+            .and_then(|dir| fs::read(dir.join(REDIRECT_CACHE_FILE)).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    });
+    urls.iter()
+        .filter_map(|url| cache.get(url).map(|cached| (url, cached)))
+        .filter(|(_, cached)| now - cached.resolved_at < REDIRECT_TTL_SECS)
+        .map(|(url, cached)| (url.clone(), cached.page.clone()))
+        .collect()
+}
+
+/// Drop expired redirects, then the oldest beyond the cap
+fn prune_redirects(cache: &mut BTreeMap<String, CachedRedirect>, now: i64) {
+    cache.retain(|_, cached| now - cached.resolved_at < REDIRECT_TTL_SECS);
+    if cache.len() > MAX_CACHED_REDIRECTS {
+        let mut ages: Vec<i64> = cache.values().map(|cached| cached.resolved_at).collect();
+        ages.sort_unstable();
+        let cutoff = ages[cache.len() - MAX_CACHED_REDIRECTS];
+        cache.retain(|_, cached| cached.resolved_at >= cutoff);
+    }
+}
+
+fn store_redirects(app: &tauri::AppHandle, resolved: &BTreeMap<String, String>) {
+    let now = chrono::Utc::now().timestamp();
+    let json = {
+        let mut redirects = REDIRECTS.lock();
+        let cache = redirects.get_or_insert_with(BTreeMap::new);
+        cache.extend(resolved.iter().map(|(url, page)| {
+            let cached = CachedRedirect {
+                page: page.clone(),
+                resolved_at: now,
+            };
+            (url.clone(), cached)
+        }));
+        prune_redirects(cache, now);
+        serde_json::to_vec(&*cache)
+    };
+    let Ok(dir) = citation_cache_dir(app) else {
+        return;
+    };
+    if let (Ok(()), Ok(json)) = (fs::create_dir_all(&dir), json) {
+        if let Err(e) = fs::write(dir.join(REDIRECT_CACHE_FILE), json) {
+            eprintln!("Failed to cache grounding redirects: {}", e);
+        }
+    }
+}
+
+/// The page a redirect link leads to. Only the final URL matters, so a
+/// HEAD request is enough even when the page itself rejects HEAD.
+async fn follow_redirect(client: &reqwest::Client, url: &str) -> Option<String> {
+    let response = client.head(url).send().await.ok()?;
+    let page = response.url().as_str();
+    (!is_grounding_redirect(page)).then(|| page.to_string())
+}
+
+/// Citations with redirect links swapped for their pages. A title that is
+/// only a domain (all Gemini gives) becomes the page's domain.
+fn with_resolved_urls(citations: Vec<InlineCitation>, resolved: &BTreeMap<String, String>) -> Vec<InlineCitation> {
+    citations
+        .into_iter()
+        .map(|mut citation| {
+            if let Some(page) = resolved.get(&citation.url) {
+                if title_rank(&citation.title).0 < 2 {
+                    if let Some(host) = host_without_www(page) {
+                        citation.title = host;
+                    }
+                }
+                citation.url = page.clone();
+            }
+            citation
+        })
+        .collect()
+}
+
+/// Resolve the grounding redirect links among `citations` (from the cache
+/// where possible). Links that can't be resolved are kept as they are.
+pub async fn resolve_grounding_redirects(app: &tauri::AppHandle, citations: Vec<InlineCitation>) -> Vec<InlineCitation> {
+    let mut urls: Vec<String> = citations
+        .iter()
+        .map(|citation| citation.url.clone())
+        .filter(|url| is_grounding_redirect(url))
+        .collect();
+    urls.sort_unstable();
+    urls.dedup();
+    if urls.is_empty() {
+        return citations;
+    }
+
+    let mut resolved = cached_redirects(app, &urls);
+    let missing: Vec<&String> = urls.iter().filter(|url| !resolved.contains_key(*url)).collect();
+    if !missing.is_empty() {
+        match proxy::client_builder().timeout(REDIRECT_TIMEOUT).build() {
+            Ok(client) => {
+                let pages = futures::future::join_all(missing.iter().map(|url| follow_redirect(&client, url))).await;
+                let found: BTreeMap<String, String> = missing
+                    .into_iter()
+                    .zip(pages)
+                    .filter_map(|(url, page)| Some((url.clone(), page?)))
+                    .collect();
+                if !found.is_empty() {
+                    store_redirects(app, &found);
+                    resolved.extend(found);
+                }
+            }
+            Err(e) => eprintln!("Failed to build client for grounding redirects: {}", e),
+        }
+    }
+    with_resolved_urls(citations, &resolved)
+}
+
+/// Resolve the grounding redirects among `citations` in a spawned task and
+/// emit them as a delta of `turn_id` when done, so the stream goes on while
+/// the links are followed. Wait for the handles (`finish_citation_deltas`)
+/// before the turn's done event.
+pub fn spawn_citation_delta(window: &tauri::Window, turn_id: &str, citations: Vec<InlineCitation>) -> JoinHandle<()> {
+    let window = window.clone();
+    let turn_id = turn_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let inline_citations = resolve_grounding_redirects(window.app_handle(), citations).await;
+        if inline_citations.is_empty() {
+            return;
+        }
+        let delta = StreamDelta {
+            turn_id,
+            text: String::new(),
+            citations: None,
+            inline_citations: Some(inline_citations),
+            thinking: None,
+            execution: None,
+        };
+        if let Err(err) = emit_stream_delta(&window, delta) {
+            eprintln!("Failed to emit chat-stream-delta event: {}", err);
+        }
+    })
+}
+
+/// Wait for the citation deltas still being resolved
+pub async fn finish_citation_deltas(pending: &mut Vec<JoinHandle<()>>) {
+    futures::future::join_all(pending.drain(..)).await;
+}
+
+// ============================================================================
+// Per-turn normalization
+// ============================================================================
+
+/// Query parameters that only record where a click came from (besides `utm_*`)
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "ref_src", "_hsenc", "_hsmi",
+];
+
+fn is_tracking_param(name: &str) -> bool {
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name)
+}
+
+/// A citation URL without tracking parameters or fragment
+pub fn clean_citation_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url.trim()) else {
+        return url.trim().to_string();
+    };
+    parsed.set_fragment(None);
+    let kept: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| !is_tracking_param(name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    parsed.to_string()
+}
+
+/// Identifies a page across URL variants (scheme, `www.`, trailing slash)
+fn canonical_key(url: &str) -> String {
+    let Ok(parsed) = Url::parse(url) else {
+        return url.to_lowercase();
+    };
+    let host = parsed.host_str().unwrap_or_default().trim_start_matches("www.").to_lowercase();
+    let path = parsed.path().trim_end_matches('/');
+    match parsed.query() {
+        Some(query) => format!("{}{}?{}", host, path, query),
+        None => format!("{}{}", host, path),
+    }
+}
+
+/// Real titles beat bare URLs and domains, which beat nothing; among real
+/// titles the more descriptive (longer, up to a point) wins
+fn title_rank(title: &str) -> (u8, usize) {
+    if title.is_empty() {
+        (0, 0)
+    } else if title.starts_with("http") || (!title.contains(' ') && title.contains('.')) {
+        (1, 0)
+    } else {
+        (2, title.chars().count().min(120))
+    }
+}
+
+/// Tracks the best title of each page a turn has cited
+#[derive(Default)]
+pub struct CitationNormalizer {
+    titles: HashMap<String, String>,
+}
+
+impl CitationNormalizer {
+    /// Clean URLs and titles, and give every citation of a page the best
+    /// title this turn has seen for it. Each citation marks its own place
+    /// in the reply, so repeats are kept.
+    pub fn normalize(&mut self, citations: Vec<InlineCitation>) -> Vec<InlineCitation> {
+        let mut batch: Vec<(String, InlineCitation)> = citations
+            .into_iter()
+            .map(|mut citation| {
+                citation.url = clean_citation_url(&citation.url);
+                citation.title = citation.title.split_whitespace().collect::<Vec<_>>().join(" ");
+                (canonical_key(&citation.url), citation)
+            })
+            .collect();
+        for (key, citation) in &batch {
+            let best = self.titles.entry(key.clone()).or_default();
+            if title_rank(&citation.title) > title_rank(best) {
+                *best = citation.title.clone();
+            }
+        }
+        for (key, citation) in &mut batch {
+            citation.title = self.titles[key.as_str()].clone();
+        }
+        batch.into_iter().map(|(_, citation)| citation).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_open_graph_metadata_and_relative_icons() {
+        let html = r#"<html><head>
+            <title>Fallback</title>
+            <meta property="og:title" content="Rust 2024 &amp; beyond">
+            <meta property="og:site_name" content="The Rust Blog" />
+            <meta name="article:published_time" content="2026-02-20T10:00:00Z">
+            <link rel="shortcut icon" href="/static/icon.png">
+        </head>"#;
+        let page = Url::parse("https://blog.rust-lang.org/2026/02/20/post.html").unwrap();
+        let meta = parse_html_metadata(html, "https://redirect.example/abc", &page);
+        assert_eq!(meta.title.as_deref(), Some("Rust 2024 & beyond"));
+        assert_eq!(meta.site_name.as_deref(), Some("The Rust Blog"));
+        assert_eq!(meta.published_at.as_deref(), Some("2026-02-20T10:00:00Z"));
+        assert_eq!(meta.favicon_url.as_deref(), Some("https://blog.rust-lang.org/static/icon.png"));
+        assert_eq!(meta.url, "https://redirect.example/abc");
+    }
+
+    #[test]
+    fn falls_back_to_title_tag_host_and_default_favicon() {
+        let html = "<head><title>\n  Plain   page\n</title></head>";
+        let page = Url::parse("https://www.example.com/a/b").unwrap();
+        let meta = parse_html_metadata(html, page.as_str(), &page);
+        assert_eq!(meta.title.as_deref(), Some("Plain page"));
+        assert_eq!(meta.site_name.as_deref(), Some("example.com"));
+        assert_eq!(meta.favicon_url.as_deref(), Some("https://www.example.com/favicon.ico"));
+        assert_eq!(meta.published_at, None);
+    }
+
+    #[test]
+    fn normalizer_strips_tracking_and_merges_titles_per_page() {
+        let cite = |url: &str, title: &str, offset: usize| InlineCitation {
+            url: url.to_string(),
+            title: title.to_string(),
+            cited_text: String::new(),
+            char_offset: offset,
+        };
+        let mut normalizer = CitationNormalizer::default();
+        let first = normalizer.normalize(vec![
+            cite("https://www.example.com/guide/?utm_source=openai&page=2#intro", "example.com", 10),
+            cite("https://example.com/guide?page=2", "The  Complete\nGuide", 40),
+            cite("https://other.org/a", "Other", 50),
+        ]);
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0].url, "https://www.example.com/guide/?page=2");
+        assert_eq!(first[0].title, "The Complete Guide");
+        assert_eq!(first[0].char_offset, 10);
+        assert_eq!(first[1].title, "The Complete Guide");
+        assert_eq!(first[2].title, "Other");
+
+        let later = normalizer.normalize(vec![cite("http://example.com/guide/?page=2&fbclid=x", "Guide", 90)]);
+        assert_eq!(later.len(), 1);
+        assert_eq!(later[0].url, "http://example.com/guide/?page=2");
+        assert_eq!(later[0].title, "The Complete Guide");
+        assert_eq!(later[0].char_offset, 90);
+    }
+
+    #[test]
+    fn swaps_grounding_redirects_for_their_pages() {
+        let redirect = "https://vertexaisearch.cloud.google.com/grounding-api-redirect/AbC123";
+        assert!(is_grounding_redirect(redirect));
+        assert!(!is_grounding_redirect("https://example.com/grounding-api-redirect/AbC123"));
+
+        let cite = |url: &str, title: &str| InlineCitation {
+            url: url.to_string(),
+            title: title.to_string(),
+            cited_text: String::new(),
+            char_offset: 0,
+        };
+        let resolved = BTreeMap::from([(redirect.to_string(), "https://www.rust-lang.org/learn".to_string())]);
+        let citations = with_resolved_urls(
+            vec![
+                cite(redirect, ""),
+                cite(redirect, "Learn Rust"),
+                cite("https://vertexaisearch.cloud.google.com/grounding-api-redirect/Unresolved", "other.org"),
+            ],
+            &resolved,
+        );
+        assert_eq!(citations[0].url, "https://www.rust-lang.org/learn");
+        assert_eq!(citations[0].title, "rust-lang.org");
+        assert_eq!(citations[1].title, "Learn Rust");
+        assert!(is_grounding_redirect(&citations[2].url));
+    }
+
+    #[test]
+    fn redirect_cache_expires_and_keeps_the_newest() {
+        let now = 1_800_000_000;
+        let cached = |resolved_at: i64| CachedRedirect {
+            page: "https://example.com".to_string(),
+            resolved_at,
+        };
+        let mut cache: BTreeMap<String, CachedRedirect> = (0..MAX_CACHED_REDIRECTS as i64 + 10)
+            .map(|i| (format!("link-{}", i), cached(now - i)))
+            .collect();
+        cache.insert("stale".to_string(), cached(now - REDIRECT_TTL_SECS));
+        prune_redirects(&mut cache, now);
+        assert_eq!(cache.len(), MAX_CACHED_REDIRECTS);
+        assert!(cache.contains_key("link-0"));
+        assert!(!cache.contains_key(&format!("link-{}", MAX_CACHED_REDIRECTS)));
+        assert!(!cache.contains_key("stale"));
+    }
+}
@@ -0,0 +1,277 @@
+//! Code block languages in exports
+//!
+//! Models often fence code without naming its language, which leaves
+//! exported chats with plain, unlabeled blocks. Exports detect the language
+//! of such blocks with keyword heuristics: Markdown exports get an annotated
+//! fence, and HTML exports get a `language-*` class plus syntax highlighting
+//! (spans styled by a small inline stylesheet, since the file stands alone).
+
+use regex::{Captures, Regex};
+use std::sync::OnceLock;
+
+/// Signals per language: (substring, weight). Each signal counts at most
+/// three times.
+const SIGNALS: &[(&str, &[(&str, u32)])] = &[
+    ("rust", &[("fn ", 2), ("let mut ", 3), ("pub fn ", 3), ("impl ", 2), ("println!", 3), ("use std::", 4), ("&self", 3), ("-> ", 1), ("::", 1), ("Option<", 2)]),
+    ("python", &[("def ", 2), ("elif ", 3), ("self.", 2), ("__init__", 4), ("import ", 1), ("print(", 1), ("None", 1), ("):\n", 2), ("lambda ", 2)]),
+    ("javascript", &[("function ", 2), ("const ", 1), ("console.log", 3), ("=> ", 1), ("===", 2), ("require(", 3), ("document.", 3), ("module.exports", 4)]),
+    ("typescript", &[("interface ", 2), (": string", 3), (": number", 3), (": boolean", 3), ("export type ", 3), ("import type ", 4), ("<T>", 2)]),
+    ("go", &[("func ", 2), ("package ", 3), (":= ", 3), ("fmt.", 3), ("err != nil", 4), ("go func", 4)]),
+    ("java", &[("public class ", 3), ("public static void", 4), ("System.out.", 4), ("import java.", 5), ("@Override", 3), ("private final ", 2)]),
+    ("c", &[("#include <std", 4), ("printf(", 2), ("int main(", 2), ("malloc(", 3), ("->", 1)]),
+    ("cpp", &[("std::", 3), ("#include <iostream>", 5), ("cout <<", 4), ("template <", 3), ("nullptr", 3)]),
+    ("csharp", &[("using System", 4), ("Console.WriteLine", 5), ("namespace ", 2), ("public async Task", 4)]),
+    ("ruby", &[("puts ", 2), ("do |", 4), ("attr_accessor", 4), ("require '", 3), ("\nend", 1)]),
+    ("php", &[("<?php", 8), ("$this->", 4), ("echo $", 3)]),
+    ("bash", &[("#!/bin/bash", 8), ("#!/bin/sh", 8), ("sudo ", 3), ("echo ", 1), ("export ", 1), ("fi\n", 3), ("npm install", 3), ("pip install", 3), ("brew install", 4), ("apt-get", 4), ("cd ", 1), ("git clone", 3)]),
+    ("sql", &[("SELECT ", 3), ("FROM ", 2), ("WHERE ", 2), ("INSERT INTO", 4), ("CREATE TABLE", 4), ("JOIN ", 2), ("GROUP BY", 3)]),
+    ("html", &[("<!DOCTYPE", 6), ("<html", 4), ("<div", 3), ("<body", 3), ("</", 1)]),
+    ("css", &[("px;", 3), ("color:", 2), ("margin:", 2), ("display:", 2), ("@media", 4)]),
+    ("yaml", &[("---\n", 2), ("apiVersion:", 5), ("- name:", 3), (": |", 2)]),
+    ("swift", &[("import SwiftUI", 6), ("import Foundation", 4), ("guard let", 4), ("var body: some View", 6), ("func ", 1)]),
+    ("kotlin", &[("fun ", 3), ("val ", 2), ("data class", 4)]),
+];
+
+/// Below this score a block stays unlabeled
+const MIN_SCORE: u32 = 3;
+
+/// Guess the language of a code block
+pub fn detect_language(code: &str) -> Option<&'static str> {
+    let trimmed = code.trim();
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some("json");
+    }
+    SIGNALS
+        .iter()
+        .map(|(language, signals)| {
+            let score: u32 = signals
+                .iter()
+                .map(|(signal, weight)| weight * (code.matches(signal).count().min(3) as u32))
+                .sum();
+            (*language, score)
+        })
+        .filter(|(_, score)| *score >= MIN_SCORE)
+        .max_by_key(|(_, score)| *score)
+        .map(|(language, _)| language)
+}
+
+/// Add detected languages to Markdown code fences that have none
+pub fn annotate_markdown_fences(markdown: &str) -> String {
+    let lines: Vec<&str> = markdown.split('\n').collect();
+    let mut out = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let fence = line.trim_start();
+        if !fence.starts_with("```") {
+            out.push(line.to_string());
+            i += 1;
+            continue;
+        }
+        let marker_len = fence.len() - fence.trim_start_matches('`').len();
+        let marker = &fence[..marker_len];
+        let close = lines[i + 1..].iter().position(|l| l.trim() == marker).map(|p| i + 1 + p);
+        let Some(close) = close else {
+            out.push(line.to_string());
+            i += 1;
+            continue;
+        };
+        let unlabeled = fence[marker_len..].trim().is_empty();
+        let body = lines[i + 1..close].join("\n");
+        match unlabeled.then(|| detect_language(&body)).flatten() {
+            Some(language) => out.push(format!("{}{}", line.trim_end(), language)),
+            None => out.push(line.to_string()),
+        }
+        out.extend(lines[i + 1..=close].iter().map(|l| l.to_string()));
+        i = close + 1;
+    }
+    out.join("\n")
+}
+
+// ============================================================================
+// HTML highlighting
+// ============================================================================
+
+/// Styles for the highlight spans, added to exported HTML
+const HIGHLIGHT_STYLES: &str = "<style>.hl-keyword{color:#c678dd}.hl-string{color:#98c379}.hl-number{color:#d19a66}.hl-comment{color:#7f848e;font-style:italic}</style>";
+
+fn keywords(language: &str) -> &'static [&'static str] {
+    match language {
+        "rust" => &["as", "async", "await", "break", "const", "continue", "crate", "else", "enum", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "trait", "true", "type", "use", "where", "while"],
+        "python" => &["and", "as", "async", "await", "break", "class", "continue", "def", "elif", "else", "except", "False", "finally", "for", "from", "if", "import", "in", "is", "lambda", "None", "not", "or", "pass", "raise", "return", "True", "try", "while", "with", "yield"],
+        "go" => &["break", "case", "chan", "const", "continue", "default", "defer", "else", "for", "func", "go", "if", "import", "interface", "map", "nil", "package", "range", "return", "select", "struct", "switch", "type", "var"],
+        "sql" => &["AND", "AS", "BY", "CREATE", "DELETE", "FROM", "GROUP", "INSERT", "INTO", "JOIN", "LEFT", "LIMIT", "NOT", "NULL", "ON", "OR", "ORDER", "SELECT", "SET", "TABLE", "UPDATE", "VALUES", "WHERE"],
+        "bash" => &["case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in", "local", "then", "while"],
+        "ruby" => &["class", "def", "do", "else", "elsif", "end", "false", "if", "module", "nil", "require", "return", "self", "true", "unless", "while", "yield"],
+        "json" | "yaml" | "html" | "css" => &["true", "false", "null"],
+        // C-family languages share most of their keywords
+        _ => &["abstract", "async", "await", "break", "case", "catch", "class", "const", "continue", "default", "do", "else", "enum", "export", "extends", "false", "final", "finally", "for", "fun", "func", "function", "if", "implements", "import", "interface", "let", "namespace", "new", "null", "nullptr", "override", "private", "protected", "public", "return", "static", "struct", "switch", "this", "throw", "true", "try", "typedef", "val", "var", "void", "while"],
+    }
+}
+
+fn line_comment(language: &str) -> Option<&'static str> {
+    match language {
+        "python" | "ruby" | "bash" | "yaml" => Some("#"),
+        "sql" => Some("--"),
+        "json" | "html" | "css" => None,
+        _ => Some("//"),
+    }
+}
+
+fn block_comment(language: &str) -> Option<(&'static str, &'static str)> {
+    match language {
+        "python" | "ruby" | "bash" | "yaml" | "sql" | "json" => None,
+        "html" => Some(("<!--", "-->")),
+        _ => Some(("/*", "*/")),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+}
+
+fn span(class: &str, text: &str) -> String {
+    format!("<span class=\"hl-{}\">{}</span>", class, escape_html(text))
+}
+
+/// Highlight code as escaped HTML with comment, string, number and keyword
+/// spans
+fn highlight(code: &str, language: &str) -> String {
+    let keywords = keywords(language);
+    let line_comment = line_comment(language);
+    let block_comment = block_comment(language);
+    let mut out = String::with_capacity(code.len() * 2);
+    let mut rest = code;
+    while let Some(c) = rest.chars().next() {
+        if let Some((open, close)) = block_comment.filter(|(open, _)| rest.starts_with(open)) {
+            let end = rest[open.len()..].find(close).map_or(rest.len(), |p| open.len() + p + close.len());
+            out.push_str(&span("comment", &rest[..end]));
+            rest = &rest[end..];
+        } else if line_comment.is_some_and(|marker| rest.starts_with(marker)) {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            out.push_str(&span("comment", &rest[..end]));
+            rest = &rest[end..];
+        } else if matches!(c, '"' | '\'' | '`') && !(language == "rust" && c == '\'') {
+            let mut end = rest.len();
+            let mut escaped = false;
+            for (i, ch) in rest.char_indices().skip(1) {
+                if ch == '\n' && c != '`' {
+                    end = i;
+                    break;
+                }
+                if ch == c && !escaped {
+                    end = i + 1;
+                    break;
+                }
+                escaped = ch == '\\' && !escaped;
+            }
+            out.push_str(&span("string", &rest[..end]));
+            rest = &rest[end..];
+        } else if c.is_ascii_digit() {
+            let end = rest.find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '.' || ch == '_')).unwrap_or(rest.len());
+            out.push_str(&span("number", &rest[..end]));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest.find(|ch: char| !(ch.is_alphanumeric() || ch == '_')).unwrap_or(rest.len());
+            let word = &rest[..end];
+            let is_keyword = if language == "sql" {
+                keywords.iter().any(|k| k.eq_ignore_ascii_case(word))
+            } else {
+                keywords.contains(&word)
+            };
+            if is_keyword {
+                out.push_str(&span("keyword", word));
+            } else {
+                out.push_str(&escape_html(word));
+            }
+            rest = &rest[end..];
+        } else {
+            out.push_str(&escape_html(&c.to_string()));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+fn code_block_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(?s)<pre[^>]*>\s*<code([^>]*)>(.*?)</code>\s*</pre>"#).unwrap())
+}
+
+fn language_class_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"language-([A-Za-z0-9+#-]+)").unwrap())
+}
+
+/// Label and highlight the code blocks of an exported HTML document. Blocks
+/// holding markup other than text are left as they are.
+pub fn highlight_html_code_blocks(html: &str) -> String {
+    let mut highlighted_any = false;
+    let html = code_block_regex().replace_all(html, |caps: &Captures| {
+        let inner = &caps[2];
+        if inner.contains('<') {
+            return caps[0].to_string();
+        }
+        let code = unescape_html(inner);
+        let language = match language_class_regex().captures(&caps[1]) {
+            Some(class) => class[1].to_lowercase(),
+            None => match detect_language(&code) {
+                Some(language) => language.to_string(),
+                None => return caps[0].to_string(),
+            },
+        };
+        highlighted_any = true;
+        format!(
+            "<pre class=\"language-{0}\"><code class=\"language-{0}\">{1}</code></pre>",
+            language,
+            highlight(&code, &language)
+        )
+    });
+    let mut html = html.into_owned();
+    if highlighted_any {
+        match html.find("</head>") {
+            Some(index) => html.insert_str(index, HIGHLIGHT_STYLES),
+            None => html.insert_str(0, HIGHLIGHT_STYLES),
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_languages_and_labels_exports() {
+        assert_eq!(detect_language("fn main() {\n    let mut x = 1;\n    println!(\"{}\", x);\n}"), Some("rust"));
+        assert_eq!(detect_language("def greet(name):\n    print(name)\n    return None"), Some("python"));
+        assert_eq!(detect_language("SELECT id FROM users WHERE active = 1"), Some("sql"));
+        assert_eq!(detect_language("{\"a\": [1, 2]}"), Some("json"));
+        assert_eq!(detect_language("hello there"), None);
+
+        let markdown = "Try:\n\n```\ndef f(x):\n    return x\n```\n\n```text\ndef f\n```";
+        assert_eq!(
+            annotate_markdown_fences(markdown),
+            "Try:\n\n```python\ndef f(x):\n    return x\n```\n\n```text\ndef f\n```"
+        );
+
+        let html = "<html><head></head><body><pre><code>let mut s = \"a &amp; b\"; // note\nprintln!(\"{}\", s);</code></pre></body></html>";
+        let out = highlight_html_code_blocks(html);
+        assert!(out.contains("<code class=\"language-rust\">"));
+        assert!(out.contains("<span class=\"hl-keyword\">let</span>"));
+        assert!(out.contains("<span class=\"hl-string\">\"a &amp; b\"</span>"));
+        assert!(out.contains("<span class=\"hl-comment\">// note</span>"));
+        assert!(out.contains("<style>.hl-keyword"));
+    }
+}
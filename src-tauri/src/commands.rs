@@ -1,11 +1,28 @@
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 use tauri::Manager;
-use tauri_plugin_store::StoreExt;
 
+use crate::audio_chunks::{emit_transcription_progress, split_audio, stitch_transcripts, SplitOptions};
+use crate::anthropic_betas::{self, BetaFeature};
+use crate::audit_log;
+use crate::code_languages;
+use crate::drafts;
+use crate::error::SidestreamError;
 use crate::mime_utils;
+use crate::provider_endpoints;
+use crate::proxy;
 use crate::secure_storage;
+use crate::session_lock;
+use crate::session_store;
+use crate::settings::{self, TranscriptTimestamps};
+use crate::snapshots;
+use crate::thinking_transcripts;
+#[cfg(desktop)]
+use crate::tray;
+use crate::voice_turns;
+use crate::workspaces;
 
 /// Log frontend errors to stderr (visible in terminal where app runs)
 #[tauri::command]
@@ -42,106 +59,279 @@ pub fn log_debug(app: tauri::AppHandle, context: String, message: String) {
     }
 }
 
-const SESSIONS_STORE_PATH: &str = "chat-sessions.json";
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiKeysConfig {
     pub anthropic: bool,
     pub openai: bool,
     pub google: bool,
+    pub openrouter: bool,
 }
 
 fn validate_provider(provider: &str) -> Result<(), String> {
     match provider {
-        "anthropic" | "openai" | "google" => Ok(()),
+        "anthropic" | "openai" | "google" | "openrouter" => Ok(()),
         _ => Err(format!("Invalid provider: {}", provider)),
     }
 }
 
+/// Save a provider's API key, to a key profile (see `workspaces`) when one is
+/// given
 #[tauri::command]
 pub async fn save_api_key(
     app: tauri::AppHandle,
     provider: String,
     key: String,
+    profile: Option<String>,
 ) -> Result<(), String> {
     validate_provider(&provider)?;
-    secure_storage::save_api_key_secure(&app, &provider, &key).await
+    workspaces::validate_key_profile(profile.as_deref())?;
+    secure_storage::save_api_key_secure(&app, &workspaces::profile_key_name(&provider, profile.as_deref()), &key).await
 }
 
 #[tauri::command]
 pub async fn has_api_key(app: tauri::AppHandle) -> Result<bool, String> {
     let config = get_configured_providers(app).await?;
-    Ok(config.anthropic || config.openai || config.google)
+    Ok(config.anthropic || config.openai || config.google || config.openrouter)
 }
 
 #[tauri::command]
-pub async fn delete_api_key(app: tauri::AppHandle, provider: String) -> Result<(), String> {
+pub async fn delete_api_key(app: tauri::AppHandle, provider: String, profile: Option<String>) -> Result<(), String> {
     validate_provider(&provider)?;
-    secure_storage::delete_api_key_secure(&app, &provider).await
+    workspaces::validate_key_profile(profile.as_deref())?;
+    secure_storage::delete_api_key_secure(&app, &workspaces::profile_key_name(&provider, profile.as_deref())).await
 }
 
+/// Providers with a key, in the current workspace's key profile
 #[tauri::command]
 pub async fn get_configured_providers(app: tauri::AppHandle) -> Result<ApiKeysConfig, String> {
+    let profile = workspaces::current_key_profile(&app);
+    let key_name = |provider: &str| workspaces::profile_key_name(provider, profile.as_deref());
     Ok(ApiKeysConfig {
-        anthropic: secure_storage::has_api_key_secure(&app, "anthropic").await,
-        openai: secure_storage::has_api_key_secure(&app, "openai").await,
-        google: secure_storage::has_api_key_secure(&app, "google").await,
+        anthropic: secure_storage::has_api_key_secure(&app, &key_name("anthropic")).await,
+        openai: secure_storage::has_api_key_secure(&app, &key_name("openai")).await,
+        google: secure_storage::has_api_key_secure(&app, &key_name("google")).await,
+        openrouter: secure_storage::has_api_key_secure(&app, &key_name("openrouter")).await,
     })
 }
 
-/// Get an API key from secure storage (used internally by LLM modules)
+/// Get an API key from secure storage (used internally by LLM modules),
+/// from the current workspace's key profile if it has one
 pub async fn get_api_key_async(app: &tauri::AppHandle, provider: &str) -> Result<String, String> {
-    secure_storage::get_api_key_secure(app, provider).await
+    let profile = workspaces::current_key_profile(app);
+    secure_storage::get_api_key_secure(app, &workspaces::profile_key_name(provider, profile.as_deref())).await
+}
+
+/// Whether `get_api_key_async` has a key for the provider
+pub async fn has_api_key_async(app: &tauri::AppHandle, provider: &str) -> bool {
+    get_api_key_async(app, provider).await.is_ok()
 }
 
 // Chat session persistence commands
 
+/// Save a session from the frontend; fails with `SessionLocked` while the
+/// session is locked
 #[tauri::command]
 pub async fn save_chat_session(
     app: tauri::AppHandle,
     session: serde_json::Value,
-) -> Result<(), String> {
-    let store = app
-        .store(SESSIONS_STORE_PATH)
-        .map_err(|e| e.to_string())?;
-
+) -> Result<(), SidestreamError> {
     let session_id = session
         .get("id")
         .and_then(|v| v.as_str())
-        .ok_or("Session must have an id")?
+        .ok_or_else(|| "Session must have an id".to_string())?
         .to_string();
+    session_lock::ensure_unlocked(&app, &session_id)?;
 
-    store.set(&session_id, session);
-    store.save().map_err(|e| e.to_string())?;
+    session_store::modify(&app, &session_id, |existing| {
+        let mut session = session;
+        preserve_backend_session_fields(existing.as_ref(), &mut session);
+        if existing.is_none() {
+            workspaces::join_current_workspace(&app, &mut session);
+        }
+        Ok(session)
+    })?;
+    #[cfg(desktop)]
+    tray::refresh_recent_sessions(&app);
 
     Ok(())
 }
 
+/// Session fields written by backend commands rather than the frontend.
+/// The frontend saves whole session objects, so these are carried over from
+/// the stored copy whenever an incoming session doesn't include them.
+const BACKEND_SESSION_FIELDS: &[&str] = &[
+    "voiceTurns",
+    "readback",
+    "geminiThinkingBudget",
+    "sessionSystemPrompt",
+    "personaId",
+    "responseLanguage",
+    "meetingSummary",
+    "pinnedMessageIds",
+    "appearance",
+    "lockedAt",
+    "thinkingTranscripts",
+    "responseMetadata",
+    "followUpAt",
+    "workspaceId",
+    "promptVariables",
+    "importedFrom",
+    "liveAttachments",
+    "branchOf",
+    "branches",
+];
+
+fn preserve_backend_session_fields(
+    existing: Option<&serde_json::Value>,
+    incoming: &mut serde_json::Value,
+) {
+    let (Some(existing), Some(incoming)) = (
+        existing.and_then(|v| v.as_object()),
+        incoming.as_object_mut(),
+    ) else {
+        return;
+    };
+
+    for field in BACKEND_SESSION_FIELDS {
+        if !incoming.contains_key(*field) {
+            if let Some(value) = existing.get(*field) {
+                incoming.insert(field.to_string(), value.clone());
+            }
+        }
+    }
+}
+
+/// Apply an in-place edit to a stored session and persist it.
+/// Used by backend commands that own part of the session object.
+pub fn update_stored_session<F>(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    edit: F,
+) -> Result<serde_json::Value, String>
+where
+    F: FnOnce(&mut serde_json::Map<String, serde_json::Value>) -> Result<(), String>,
+{
+    session_store::modify(app, session_id, |stored| {
+        let mut session = stored.ok_or_else(|| format!("Session not found: {}", session_id))?;
+        let fields = session
+            .as_object_mut()
+            .ok_or("Stored session is not an object")?;
+        edit(fields)?;
+        Ok(session)
+    })
+}
+
+/// Generate a random (v4) UUID for a new session, matching the frontend's
+/// `crypto.randomUUID()` ids
+pub fn new_session_id() -> String {
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// Save a whole session object built by the backend (import, duplicate, ...)
+pub fn store_session(app: &tauri::AppHandle, session: serde_json::Value) -> Result<(), String> {
+    let mut session = session;
+    workspaces::join_current_workspace(app, &mut session);
+    session_store::save(app, &session)
+}
+
+/// Build a session object in the frontend's `ChatSession` shape for
+/// conversations created by the backend (automations, workflows, ...)
+pub fn new_session_json(
+    title: &str,
+    model: &str,
+    web_search_enabled: bool,
+    messages: Vec<serde_json::Value>,
+) -> serde_json::Value {
+    let now = chrono::Utc::now().to_rfc3339();
+    serde_json::json!({
+        "id": new_session_id(),
+        "title": title,
+        "createdAt": now,
+        "updatedAt": now,
+        "messages": messages,
+        "discoveryItems": [],
+        "settings": {
+            "frontierModel": model,
+            "evaluatorModel": model,
+            "extendedThinkingEnabled": false,
+            "webSearchEnabled": web_search_enabled,
+        },
+    })
+}
+
+/// Build a message in the frontend's `Message` shape
+pub fn session_message(role: &str, content: &str, turn_id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": new_session_id(),
+        "role": role,
+        "content": content,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "turnId": turn_id,
+    })
+}
+
+/// Read a stored session (used internally by backend commands)
+pub fn get_stored_session(
+    app: &tauri::AppHandle,
+    session_id: &str,
+) -> Result<Option<serde_json::Value>, String> {
+    session_store::load(app, session_id)
+}
+
 #[tauri::command]
 pub async fn load_chat_session(
     app: tauri::AppHandle,
     session_id: String,
 ) -> Result<Option<serde_json::Value>, String> {
-    let store = app
-        .store(SESSIONS_STORE_PATH)
-        .map_err(|e| e.to_string())?;
+    session_store::load(&app, &session_id)
+}
 
-    Ok(store.get(&session_id))
+/// All stored sessions (as JSON)
+pub fn stored_sessions(app: &tauri::AppHandle) -> Result<Vec<serde_json::Value>, String> {
+    session_store::list(app, 0, None)
 }
 
-#[tauri::command]
-pub async fn list_chat_sessions(app: tauri::AppHandle) -> Result<Vec<serde_json::Value>, String> {
-    let store = app
-        .store(SESSIONS_STORE_PATH)
-        .map_err(|e| e.to_string())?;
+/// Delete a stored session along with its recordings, snapshots and draft
+pub fn delete_stored_session(app: &tauri::AppHandle, session_id: &str) -> Result<(), String> {
+    session_store::delete(app, session_id)?;
 
-    let entries: Vec<serde_json::Value> = store
-        .entries()
-        .into_iter()
-        .map(|(_, v)| v)
-        .collect();
+    voice_turns::delete_session_audio(app, session_id);
+    snapshots::delete_session_snapshots(app, session_id);
+    drafts::delete_session_draft(app, session_id);
 
-    Ok(entries)
+    Ok(())
+}
+
+/// Stored sessions, most recently updated first. Pass `limit` (and
+/// `offset`) to page through them; without a limit all are returned.
+#[tauri::command]
+pub async fn list_chat_sessions(
+    app: tauri::AppHandle,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> Result<Vec<serde_json::Value>, String> {
+    session_store::list(&app, offset.unwrap_or(0), limit)
+}
+
+/// Number of stored sessions, for paging `list_chat_sessions`
+#[tauri::command]
+pub async fn count_chat_sessions(app: tauri::AppHandle) -> Result<u32, String> {
+    session_store::count(&app)
 }
 
 #[tauri::command]
@@ -149,33 +339,27 @@ pub async fn delete_chat_session(
     app: tauri::AppHandle,
     session_id: String,
 ) -> Result<(), String> {
-    let store = app
-        .store(SESSIONS_STORE_PATH)
-        .map_err(|e| e.to_string())?;
-
-    let _ = store.delete(&session_id);
-    store.save().map_err(|e| e.to_string())?;
-
+    delete_stored_session(&app, &session_id)?;
+    #[cfg(desktop)]
+    tray::refresh_recent_sessions(&app);
     Ok(())
 }
 
 #[tauri::command]
 pub async fn clear_chat_sessions_store(app: tauri::AppHandle) -> Result<(), String> {
-    let store = app
-        .store(SESSIONS_STORE_PATH)
-        .map_err(|e| e.to_string())?;
+    session_store::clear(&app)?;
 
-    store.clear();
-    store.save().map_err(|e| e.to_string())?;
+    voice_turns::delete_all_audio(&app);
+    snapshots::delete_all_snapshots(&app);
+    drafts::delete_all_drafts(&app);
+    #[cfg(desktop)]
+    tray::refresh_recent_sessions(&app);
 
     Ok(())
 }
 
-#[tauri::command]
-pub async fn export_chat_to_html(
-    app: tauri::AppHandle,
-    html_content: String,
-) -> Result<String, String> {
+/// Path for a new export file in the app's exports directory
+fn export_file_path(app: &tauri::AppHandle, extension: &str) -> Result<PathBuf, String> {
     // Get the app's data directory for temp files
     let app_data_dir = app
         .path()
@@ -188,8 +372,30 @@ pub async fn export_chat_to_html(
 
     // Generate filename with timestamp
     let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
-    let filename = format!("chat-export-{}.html", timestamp);
-    let file_path: PathBuf = exports_dir.join(&filename);
+    Ok(exports_dir.join(format!("chat-export-{}.{}", timestamp, extension)))
+}
+
+/// Write the HTML the frontend rendered for a chat and open it. With
+/// `session_id`, the session's stored thinking transcripts are appended.
+#[tauri::command]
+pub async fn export_chat_to_html(
+    app: tauri::AppHandle,
+    html_content: String,
+    session_id: Option<String>,
+) -> Result<String, String> {
+    let file_path = export_file_path(&app, "html")?;
+
+    let mut html_content = code_languages::highlight_html_code_blocks(&html_content);
+    if let Some(session) = match &session_id {
+        Some(id) => get_stored_session(&app, id)?,
+        None => None,
+    } {
+        let section = thinking_transcripts::html_section(&session);
+        match html_content.rfind("</body>") {
+            Some(index) => html_content.insert_str(index, &section),
+            None => html_content.push_str(&section),
+        }
+    }
 
     // Write the HTML file
     fs::write(&file_path, &html_content).map_err(|e| e.to_string())?;
@@ -204,6 +410,46 @@ pub async fn export_chat_to_html(
     Ok(result)
 }
 
+/// Render a stored session as Markdown
+fn session_to_markdown(session: &serde_json::Value, include_thinking: bool) -> String {
+    let mut markdown = format!("# {}\n", session["title"].as_str().unwrap_or("Chat"));
+    for message in session["messages"].as_array().into_iter().flatten() {
+        let speaker = match message["role"].as_str() {
+            Some("assistant") => "Assistant",
+            _ => "You",
+        };
+        markdown.push_str(&format!("\n## {}\n\n", speaker));
+        let content = message["content"].as_str().unwrap_or_default();
+        markdown.push_str(code_languages::annotate_markdown_fences(content).trim_end());
+        markdown.push('\n');
+        for attachment in message["attachments"].as_array().into_iter().flatten() {
+            if let Some(name) = attachment["name"].as_str() {
+                markdown.push_str(&format!("\n_Attachment: {}_\n", name));
+            }
+        }
+    }
+    if include_thinking {
+        markdown.push_str(&thinking_transcripts::markdown_section(session));
+    }
+    markdown
+}
+
+/// Export a stored session as a Markdown file and return its path. Thinking
+/// transcripts are included unless `include_thinking` is false.
+#[tauri::command]
+pub async fn export_chat_to_markdown(
+    app: tauri::AppHandle,
+    session_id: String,
+    include_thinking: Option<bool>,
+) -> Result<String, String> {
+    let session = get_stored_session(&app, &session_id)?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    let file_path = export_file_path(&app, "md")?;
+    fs::write(&file_path, session_to_markdown(&session, include_thinking.unwrap_or(true)))
+        .map_err(|e| e.to_string())?;
+    Ok(file_path.to_string_lossy().to_string())
+}
+
 #[tauri::command]
 pub async fn print_webview(webview: tauri::Webview) -> Result<(), String> {
     webview.print().map_err(|e: tauri::Error| e.to_string())?;
@@ -217,41 +463,239 @@ pub async fn transcribe_audio_bytes(
     filename: &str,
     mime_type: &str,
 ) -> Result<String, String> {
-    let api_key = secure_storage::get_api_key_secure(app, "openai").await?;
+    let api_key = get_api_key_async(app, "openai").await?;
+
+    if audio_bytes.len() <= OPENAI_MAX_UPLOAD_BYTES {
+        return transcribe_openai_request(app, &api_key, audio_bytes, filename, mime_type, TranscriptTimestamps::Off).await;
+    }
+
+    // Over the upload limit: transcribe overlapping pieces a few at a time,
+    // keeping their order, and stitch the transcripts back together
+    let pieces = split_audio(audio_bytes, mime_type, OPENAI_SPLIT);
+    let total = pieces.len();
+    let mut requests = futures::stream::iter(pieces.into_iter().map(|piece| {
+        transcribe_openai_request(app, &api_key, piece, filename, mime_type, TranscriptTimestamps::Off)
+    }))
+    .buffered(TRANSCRIPTION_CONCURRENCY);
+
+    let mut transcripts = Vec::with_capacity(total);
+    while let Some(transcript) = requests.next().await {
+        transcripts.push(transcript?);
+        emit_transcription_progress(app, transcripts.len(), total);
+    }
+
+    Ok(stitch_transcripts(&transcripts))
+}
+
+/// OpenAI rejects uploads over 25MB
+const OPENAI_MAX_UPLOAD_BYTES: usize = 24 * 1024 * 1024;
+
+/// Pieces for recordings over the upload limit (and the per-request length
+/// limit of the gpt-4o transcription models)
+const OPENAI_SPLIT: SplitOptions = SplitOptions {
+    chunk_secs: 600,
+    overlap_secs: 3,
+    max_bytes: OPENAI_MAX_UPLOAD_BYTES,
+};
+
+/// Pieces of a long recording transcribed at the same time
+const TRANSCRIPTION_CONCURRENCY: usize = 3;
+
+/// Attempts per transcription request; transient failures (network errors,
+/// rate limits, server errors) are retried with a growing delay
+const TRANSCRIPTION_ATTEMPTS: u32 = 3;
+
+/// A failed transcription attempt
+struct TranscriptionAttemptError {
+    message: String,
+    transient: bool,
+}
+
+/// A single transcription request to the OpenAI API. Returns the transcript
+/// text, or the `verbose_json` body when timestamps are requested.
+async fn transcribe_openai_request(
+    app: &tauri::AppHandle,
+    api_key: &str,
+    audio_bytes: Vec<u8>,
+    filename: &str,
+    mime_type: &str,
+    timestamps: TranscriptTimestamps,
+) -> Result<String, String> {
+    let mut attempt = 1;
+    loop {
+        match transcribe_openai_attempt(app, api_key, audio_bytes.clone(), filename, mime_type, timestamps).await {
+            Ok(transcript) => return Ok(transcript),
+            Err(e) if e.transient && attempt < TRANSCRIPTION_ATTEMPTS => {
+                eprintln!("Transcription attempt {} failed, retrying: {}", attempt, e.message);
+                tokio::time::sleep(std::time::Duration::from_secs(u64::from(attempt))).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e.message),
+        }
+    }
+}
+
+async fn transcribe_openai_attempt(
+    app: &tauri::AppHandle,
+    api_key: &str,
+    audio_bytes: Vec<u8>,
+    filename: &str,
+    mime_type: &str,
+    timestamps: TranscriptTimestamps,
+) -> Result<String, TranscriptionAttemptError> {
+    let fatal = |message: String| TranscriptionAttemptError {
+        message,
+        transient: false,
+    };
+
+    let model = match timestamps {
+        TranscriptTimestamps::Off => settings::openai_transcription_model(app),
+        TranscriptTimestamps::Segment | TranscriptTimestamps::Word => settings::openai_timestamp_model(app),
+    };
+    audit_log::record_bytes("openai", &model, "audio/transcriptions", &audio_bytes);
 
     // Create multipart form with audio file
     let audio_part = reqwest::multipart::Part::bytes(audio_bytes)
         .file_name(filename.to_string())
         .mime_str(mime_type)
-        .map_err(|e| format!("Failed to create audio part: {}", e))?;
+        .map_err(|e| fatal(format!("Failed to create audio part: {}", e)))?;
 
-    let form = reqwest::multipart::Form::new()
-        .text("model", "gpt-4o-mini-transcribe")
-        .text("response_format", "text")
+    let mut form = reqwest::multipart::Form::new()
+        .text("model", model)
         .part("file", audio_part);
+    form = match timestamps {
+        TranscriptTimestamps::Off => form.text("response_format", "text"),
+        TranscriptTimestamps::Segment | TranscriptTimestamps::Word => {
+            form = form
+                .text("response_format", "verbose_json")
+                .text("timestamp_granularities[]", "segment");
+            if timestamps == TranscriptTimestamps::Word {
+                form = form.text("timestamp_granularities[]", "word");
+            }
+            form
+        }
+    };
+
+    // Without a language hint the API auto-detects, which is noticeably
+    // less accurate for non-English dictation
+    if let Some(language) = settings::transcription_language(app) {
+        form = form.text("language", language);
+    }
 
     // Send request to OpenAI Whisper API
-    let client = reqwest::Client::new();
-    let response = client
-        .post("https://api.openai.com/v1/audio/transcriptions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .multipart(form)
+    let client = proxy::client();
+    let response = provider_endpoints::request(
+        &client,
+        reqwest::Method::POST,
+        "openai",
+        "/audio/transcriptions",
+        api_key,
+    )
+    .multipart(form)
         .send()
         .await
-        .map_err(|e| format!("Transcription request failed: {}", e))?;
+        .map_err(|e| TranscriptionAttemptError {
+            message: format!("Transcription request failed: {}", e),
+            transient: true,
+        })?;
 
-    if !response.status().is_success() {
+    let status = response.status();
+    if !status.is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Transcription API error: {}", error_text));
+        return Err(TranscriptionAttemptError {
+            message: format!("Transcription API error ({}): {}", status.as_u16(), error_text),
+            transient: status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+        });
     }
 
-    // Response is plain text when response_format is "text"
-    let transcript = response.text().await
-        .map_err(|e| format!("Failed to read transcript: {}", e))?;
+    // Response is plain text when response_format is "text", JSON otherwise
+    let transcript = response.text().await.map_err(|e| TranscriptionAttemptError {
+        message: format!("Failed to read transcript: {}", e),
+        transient: true,
+    })?;
 
     Ok(transcript.trim().to_string())
 }
 
+/// A stretch of a timestamped transcript; times are in seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// A single word of a timestamped transcript; times are in seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptWord {
+    pub word: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Transcript with timing, parsed from OpenAI's `verbose_json` response
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimedTranscript {
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub segments: Vec<TranscriptSegment>,
+    /// Only filled in when word timestamps were requested
+    #[serde(default)]
+    pub words: Vec<TranscriptWord>,
+}
+
+impl TimedTranscript {
+    fn parse(body: &str) -> Result<Self, String> {
+        let mut transcript: TimedTranscript = serde_json::from_str(body)
+            .map_err(|e| format!("Failed to parse timestamped transcript: {}", e))?;
+        transcript.text = transcript.text.trim().to_string();
+        for segment in &mut transcript.segments {
+            segment.text = segment.text.trim().to_string();
+        }
+        transcript.segments.retain(|segment| !segment.text.is_empty());
+        for word in &mut transcript.words {
+            word.word = word.word.trim().to_string();
+        }
+        Ok(transcript)
+    }
+
+    /// Move all timestamps later by `secs`, e.g. to place a recording segment
+    /// within a longer meeting
+    pub fn shift(&mut self, secs: f64) {
+        for segment in &mut self.segments {
+            segment.start += secs;
+            segment.end += secs;
+        }
+        for word in &mut self.words {
+            word.start += secs;
+            word.end += secs;
+        }
+    }
+}
+
+/// Transcribe audio with segment (and optionally word) timestamps via the
+/// OpenAI API. The audio must fit in a single upload.
+pub async fn transcribe_audio_timed(
+    app: &tauri::AppHandle,
+    audio_bytes: Vec<u8>,
+    filename: &str,
+    mime_type: &str,
+    word_timestamps: bool,
+) -> Result<TimedTranscript, String> {
+    if audio_bytes.len() > OPENAI_MAX_UPLOAD_BYTES {
+        return Err("Audio is too long for a timestamped transcription".to_string());
+    }
+    let api_key = get_api_key_async(app, "openai").await?;
+    let timestamps = if word_timestamps {
+        TranscriptTimestamps::Word
+    } else {
+        TranscriptTimestamps::Segment
+    };
+    let body = transcribe_openai_request(app, &api_key, audio_bytes, filename, mime_type, timestamps).await?;
+    TimedTranscript::parse(&body)
+}
+
 /// Response from downloading a file from provider APIs
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DownloadedFile {
@@ -261,18 +705,7 @@ pub struct DownloadedFile {
 }
 
 /// Generic file download helper that handles the common download logic
-async fn download_file_from_url(
-    url: &str,
-    headers: Vec<(&str, String)>,
-    filename: &str,
-) -> Result<DownloadedFile, String> {
-    let client = reqwest::Client::new();
-    let mut request = client.get(url);
-
-    for (key, value) in headers {
-        request = request.header(key, value);
-    }
-
+async fn download_file(request: reqwest::RequestBuilder, filename: &str) -> Result<DownloadedFile, String> {
     let response = request
         .send()
         .await
@@ -324,12 +757,20 @@ pub async fn fetch_image_url_bytes(url: String) -> Result<DownloadedFile, String
         .unwrap_or("image")
         .to_string();
 
-    download_file_from_url(
-        &url,
-        vec![("user-agent", "Mozilla/5.0".to_string())],
-        &filename,
-    )
-    .await
+    let request = proxy::client().get(&url).header("user-agent", "Mozilla/5.0");
+    download_file(request, &filename).await
+}
+
+/// A GET request to Anthropic's Files API (through any endpoint override)
+fn anthropic_file_request(client: &reqwest::Client, api_key: &str, path: &str) -> reqwest::RequestBuilder {
+    let request = provider_endpoints::request(client, reqwest::Method::GET, "anthropic", path, api_key)
+        .header("anthropic-version", "2023-06-01");
+    anthropic_betas::with_betas(request, &[BetaFeature::FilesApi])
+}
+
+/// A GET request to OpenAI's Containers API (through any endpoint override)
+fn openai_container_request(client: &reqwest::Client, api_key: &str, path: &str) -> reqwest::RequestBuilder {
+    provider_endpoints::request(client, reqwest::Method::GET, "openai", path, api_key)
 }
 
 /// Download a file from Anthropic's Files API
@@ -340,18 +781,9 @@ pub async fn download_anthropic_file(
     filename: String,
 ) -> Result<DownloadedFile, String> {
     let api_key = get_api_key_async(&app, "anthropic").await?;
-    let url = format!("https://api.anthropic.com/v1/files/{}/content", file_id);
-
-    download_file_from_url(
-        &url,
-        vec![
-            ("x-api-key", api_key),
-            ("anthropic-version", "2023-06-01".to_string()),
-            ("anthropic-beta", "files-api-2025-04-14".to_string()),
-        ],
-        &filename,
-    )
-    .await
+    let path = format!("/files/{}/content", file_id);
+
+    download_file(anthropic_file_request(&proxy::client(), &api_key, &path), &filename).await
 }
 
 /// Download a file from OpenAI's Containers API (for code interpreter files)
@@ -363,17 +795,66 @@ pub async fn download_openai_file(
     filename: String,
 ) -> Result<DownloadedFile, String> {
     let api_key = get_api_key_async(&app, "openai").await?;
-    let url = format!(
-        "https://api.openai.com/v1/containers/{}/files/{}/content",
-        container_id, file_id
-    );
-
-    download_file_from_url(
-        &url,
-        vec![("Authorization", format!("Bearer {}", api_key))],
-        &filename,
-    )
-    .await
+    let path = format!("/containers/{}/files/{}/content", container_id, file_id);
+
+    download_file(openai_container_request(&proxy::client(), &api_key, &path), &filename).await
+}
+
+/// A file listed in an OpenAI code interpreter container
+struct ContainerFileEntry {
+    id: String,
+    /// Absolute path inside the container, e.g. "/mnt/data/chart.png"
+    path: String,
+}
+
+/// List every file in an OpenAI container, following pagination
+async fn list_openai_container_files(
+    client: &reqwest::Client,
+    api_key: &str,
+    container_id: &str,
+) -> Result<Vec<ContainerFileEntry>, String> {
+    let list_path = format!("/containers/{}/files", container_id);
+    let mut entries = Vec::new();
+    let mut after: Option<String> = None;
+
+    loop {
+        let mut request = openai_container_request(client, api_key, &list_path).query(&[("limit", "100")]);
+        if let Some(ref cursor) = after {
+            request = request.query(&[("after", cursor.as_str())]);
+        }
+
+        let list_response = request
+            .send()
+            .await
+            .map_err(|e| format!("Container file listing request failed: {}", e))?;
+
+        if !list_response.status().is_success() {
+            let error_text = list_response.text().await.unwrap_or_default();
+            return Err(format!("Container file listing API error: {}", error_text));
+        }
+
+        let list_body: serde_json::Value = list_response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse file listing: {}", e))?;
+
+        // Response format: { "data": [{ "id": "...", "path": "/mnt/data/filename.ext", ... }], "has_more": bool, "last_id": "..." }
+        if let Some(files) = list_body["data"].as_array() {
+            entries.extend(files.iter().filter_map(|f| {
+                Some(ContainerFileEntry {
+                    id: f["id"].as_str()?.to_string(),
+                    path: f["path"].as_str().unwrap_or("").to_string(),
+                })
+            }));
+        }
+
+        match (list_body["has_more"].as_bool(), list_body["last_id"].as_str()) {
+            (Some(true), Some(last_id)) => after = Some(last_id.to_string()),
+            _ => break,
+        }
+    }
+
+    Ok(entries)
 }
 
 /// Download a file from OpenAI container by filename (resolves file_id via container file listing)
@@ -385,52 +866,165 @@ pub async fn download_openai_file_by_name(
     filename: String,
 ) -> Result<DownloadedFile, String> {
     let api_key = get_api_key_async(&app, "openai").await?;
-    let client = reqwest::Client::new();
+    let client = proxy::client();
 
     // First, list files in the container to find the file_id
-    let list_url = format!(
-        "https://api.openai.com/v1/containers/{}/files",
-        container_id
-    );
-
-    let list_response = client
-        .get(&list_url)
-        .header("Authorization", format!("Bearer {}", api_key))
-        .send()
-        .await
-        .map_err(|e| format!("Container file listing request failed: {}", e))?;
+    let entries = list_openai_container_files(&client, &api_key, &container_id).await?;
 
-    if !list_response.status().is_success() {
-        let error_text = list_response.text().await.unwrap_or_default();
-        return Err(format!("Container file listing API error: {}", error_text));
-    }
-
-    let list_body: serde_json::Value = list_response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse file listing: {}", e))?;
-
-    // Find the file by path
-    // Response format: { "data": [{ "id": "...", "path": "/mnt/data/filename.ext", ... }] }
-    let file_id = list_body["data"]
-        .as_array()
-        .and_then(|files| {
-            files.iter().find_map(|f| {
-                let path = f["path"].as_str().unwrap_or("");
-                // Match by path ending with the filename
-                if path.ends_with(&format!("/{}", filename)) || path == format!("/mnt/data/{}", filename) {
-                    f["id"].as_str().map(|s| s.to_string())
-                } else {
-                    None
-                }
-            })
-        })
+    // Match by path ending with the filename
+    let file_id = entries
+        .into_iter()
+        .find(|f| f.path.ends_with(&format!("/{}", filename)) || f.path == format!("/mnt/data/{}", filename))
+        .map(|f| f.id)
         .ok_or_else(|| format!("File '{}' not found in container", filename))?;
 
     // Now download using the resolved file_id
     download_openai_file(app, container_id, file_id, filename).await
 }
 
+/// A code-execution file the frontend already knows about (from a turn's generated files)
+#[derive(Debug, Deserialize)]
+pub struct ContainerFileRef {
+    pub file_id: String,
+    pub filename: String,
+}
+
+/// A file written to disk by `export_container_files`
+#[derive(Debug, Serialize)]
+pub struct ExportedFile {
+    pub filename: String,
+    pub path: String,
+    pub size: u64,
+}
+
+/// A container file queued for export
+struct PendingDownload {
+    request: reqwest::RequestBuilder,
+    /// Path inside the container (or bare filename), used to name the local copy
+    container_path: String,
+}
+
+/// Download every file from a turn's code-execution container into a local folder.
+///
+/// For OpenAI the container is enumerated directly, so scripts and intermediate
+/// data are exported along with the files the model mentioned, keeping their
+/// relative paths under /mnt/data. Anthropic has no per-container listing, so the
+/// caller passes the turn's generated files. Existing files are never overwritten;
+/// a " (n)" suffix is added instead.
+#[tauri::command]
+pub async fn export_container_files(
+    app: tauri::AppHandle,
+    provider: String,
+    container_id: Option<String>,
+    files: Option<Vec<ContainerFileRef>>,
+    destination_dir: String,
+) -> Result<Vec<ExportedFile>, String> {
+    let destination = PathBuf::from(&destination_dir);
+    if !destination.is_dir() {
+        return Err(format!("Destination is not a folder: {}", destination_dir));
+    }
+
+    let mut downloads: Vec<PendingDownload> = Vec::new();
+
+    match provider.as_str() {
+        "openai" => {
+            let container_id = container_id.ok_or("An OpenAI container id is required")?;
+            let api_key = get_api_key_async(&app, "openai").await?;
+            let client = proxy::client();
+            for entry in list_openai_container_files(&client, &api_key, &container_id).await? {
+                let path = format!("/containers/{}/files/{}/content", container_id, entry.id);
+                downloads.push(PendingDownload {
+                    request: openai_container_request(&client, &api_key, &path),
+                    container_path: entry.path,
+                });
+            }
+        }
+        "anthropic" => {
+            let files = files.ok_or("Anthropic exports need the turn's file list")?;
+            let api_key = get_api_key_async(&app, "anthropic").await?;
+            let client = proxy::client();
+            for f in files {
+                let path = format!("/files/{}/content", f.file_id);
+                downloads.push(PendingDownload {
+                    request: anthropic_file_request(&client, &api_key, &path),
+                    container_path: f.filename,
+                });
+            }
+        }
+        _ => return Err(format!("Container export is not supported for provider: {}", provider)),
+    }
+
+    let mut exported = Vec::new();
+    for download in downloads {
+        let Some(relative) = container_relative_path(&download.container_path) else {
+            eprintln!("Skipping container file with unusable path: {}", download.container_path);
+            continue;
+        };
+        let name = relative.to_string_lossy().to_string();
+        let downloaded = download_file(download.request, &name).await?;
+
+        let target = unique_destination(&destination.join(&relative));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create folder {}: {}", parent.display(), e))?;
+        }
+        fs::write(&target, &downloaded.data)
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+
+        exported.push(ExportedFile {
+            filename: name,
+            path: target.to_string_lossy().to_string(),
+            size: downloaded.data.len() as u64,
+        });
+    }
+
+    Ok(exported)
+}
+
+/// Turn a container path ("/mnt/data/out/chart.png" or "chart.png") into a safe
+/// relative path, preserving subfolders. Returns None for paths that would
+/// escape the destination folder or have no file name.
+fn container_relative_path(container_path: &str) -> Option<PathBuf> {
+    let trimmed = container_path
+        .strip_prefix("/mnt/data/")
+        .unwrap_or(container_path)
+        .trim_start_matches('/');
+
+    let mut relative = PathBuf::new();
+    for part in trimmed.split(['/', '\\']) {
+        match part {
+            "" | "." => continue,
+            ".." => return None,
+            _ => relative.push(part),
+        }
+    }
+
+    if relative.as_os_str().is_empty() {
+        None
+    } else {
+        Some(relative)
+    }
+}
+
+/// Pick a path that doesn't exist yet by appending " (n)" to the file stem
+fn unique_destination(target: &std::path::Path) -> PathBuf {
+    if !target.exists() {
+        return target.to_path_buf();
+    }
+    let stem = target.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = target.extension().map(|e| e.to_string_lossy().to_string());
+    (1..)
+        .map(|n| {
+            let name = match &extension {
+                Some(ext) => format!("{} ({}).{}", stem, n, ext),
+                None => format!("{} ({})", stem, n),
+            };
+            target.with_file_name(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .unwrap_or_else(|| target.to_path_buf())
+}
+
 /// Add or fix file extension based on mime type
 fn fix_filename_extension(filename: &str, mime_type: Option<&str>) -> String {
     // If filename already has a recognized extension, keep it
@@ -457,3 +1051,40 @@ fn fix_filename_extension(filename: &str, mime_type: Option<&str>) -> String {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn container_relative_path_keeps_subfolders_and_rejects_escapes() {
+        assert_eq!(
+            container_relative_path("/mnt/data/out/chart.png"),
+            Some(PathBuf::from("out").join("chart.png"))
+        );
+        assert_eq!(container_relative_path("report.csv"), Some(PathBuf::from("report.csv")));
+        assert_eq!(container_relative_path("/mnt/data/../etc/passwd"), None);
+        assert_eq!(container_relative_path("/mnt/data/"), None);
+    }
+
+    #[test]
+    fn parses_and_shifts_timed_transcript() {
+        let body = r#"{
+            "task": "transcribe", "language": "english", "duration": 4.2,
+            "text": " Hello there. Next item. ",
+            "segments": [
+                {"id": 0, "seek": 0, "start": 0.0, "end": 1.5, "text": " Hello there.", "avg_logprob": -0.2},
+                {"id": 1, "seek": 0, "start": 1.5, "end": 1.6, "text": " "},
+                {"id": 2, "seek": 0, "start": 2.0, "end": 4.2, "text": " Next item."}
+            ],
+            "words": [{"word": "Hello", "start": 0.0, "end": 0.6}]
+        }"#;
+        let mut transcript = TimedTranscript::parse(body).unwrap();
+        assert_eq!(transcript.text, "Hello there. Next item.");
+        assert_eq!(transcript.segments.len(), 2);
+        assert_eq!(transcript.segments[1].text, "Next item.");
+
+        transcript.shift(30.0);
+        assert_eq!(transcript.segments[1].start, 32.0);
+        assert_eq!(transcript.words[0].end, 30.6);
+    }
+}
@@ -0,0 +1,429 @@
+//! Pre-send content filters
+//!
+//! Before a chat turn goes to a provider, the text of its user messages and
+//! text attachments runs through the enabled filters: two built-in detectors
+//! (API keys and similar secrets, credit card numbers) and any number of
+//! custom regex redactions. A filter either masks what it finds, replacing
+//! it with `[REDACTED: <name>]`, or warns: the turn is not sent and the
+//! frontend is told what was found, so the user can edit the message (or
+//! switch the filter to masking) first. Warnings only consider the message
+//! being sent; masking applies to the whole conversation, since earlier
+//! messages go out again with every turn. Other requests built from a
+//! conversation (discovery, one-shot and structured completions such as
+//! session titles, image generation prompts) mask with every enabled filter;
+//! a voice turn's text history masks as a chat turn would (its audio can't
+//! be filtered). Findings are reported with `content-filter-findings`.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use tauri::Emitter;
+
+use crate::llm::ChatMessage;
+use crate::settings;
+
+const CONTENT_FILTERS_KEY: &str = "content_filters";
+
+const API_KEYS_FILTER_ID: &str = "api_keys";
+const CREDIT_CARDS_FILTER_ID: &str = "credit_cards";
+
+/// Provider and service keys (Anthropic/OpenAI/OpenRouter `sk-`, Google,
+/// AWS, GitHub, Slack) and PEM private keys
+const API_KEY_PATTERN: &str = r"\b(?:sk-[A-Za-z0-9_\-]{20,}|AIza[0-9A-Za-z_\-]{35}|AKIA[0-9A-Z]{16}|gh[pousr]_[A-Za-z0-9]{36,}|xox[abprs]-[A-Za-z0-9\-]{10,})|-----BEGIN [A-Z ]*PRIVATE KEY-----";
+
+/// 13-19 digits, optionally grouped by spaces or dashes; matches are kept
+/// only if they pass the Luhn check
+const CREDIT_CARD_PATTERN: &str = r"\b\d(?:[ \-]?\d){12,18}\b";
+
+/// Error prefix when a warning filter stops a turn, for the frontend to match
+const WARNING_ERROR_PREFIX: &str = "content_filter_warning";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterAction {
+    /// Stop the turn and tell the user
+    Warn,
+    /// Replace matches before sending
+    Mask,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentFilter {
+    /// "api_keys" and "credit_cards" for the built-in detectors
+    pub id: String,
+    pub name: String,
+    /// Regex of a custom filter; None for the built-in detectors
+    pub pattern: Option<String>,
+    pub enabled: bool,
+    pub action: FilterAction,
+}
+
+/// What one filter found in a turn
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterFinding {
+    pub filter_id: String,
+    pub name: String,
+    pub action: FilterAction,
+    pub count: usize,
+}
+
+/// Event payload listing what the filters found in a turn
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContentFilterEvent {
+    pub turn_id: String,
+    pub findings: Vec<FilterFinding>,
+    /// The turn was stopped by a warning filter
+    pub blocked: bool,
+}
+
+fn builtin_filters() -> Vec<ContentFilter> {
+    vec![
+        ContentFilter {
+            id: API_KEYS_FILTER_ID.to_string(),
+            name: "API key".to_string(),
+            pattern: None,
+            enabled: false,
+            action: FilterAction::Warn,
+        },
+        ContentFilter {
+            id: CREDIT_CARDS_FILTER_ID.to_string(),
+            name: "Credit card number".to_string(),
+            pattern: None,
+            enabled: false,
+            action: FilterAction::Mask,
+        },
+    ]
+}
+
+/// The built-in filters (with their saved settings) followed by the custom ones
+fn load_filters(app: &tauri::AppHandle) -> Vec<ContentFilter> {
+    let saved: Vec<ContentFilter> = settings::get_setting(app, CONTENT_FILTERS_KEY).unwrap_or_default();
+    let mut filters: Vec<ContentFilter> = builtin_filters()
+        .into_iter()
+        .map(|builtin| {
+            saved
+                .iter()
+                .find(|f| f.id == builtin.id)
+                .map(|f| ContentFilter {
+                    enabled: f.enabled,
+                    action: f.action,
+                    ..builtin.clone()
+                })
+                .unwrap_or(builtin)
+        })
+        .collect();
+    filters.extend(saved.into_iter().filter(|f| f.pattern.is_some()));
+    filters
+}
+
+// ============================================================================
+// Matching
+// ============================================================================
+
+struct CompiledFilter {
+    filter: ContentFilter,
+    regex: Regex,
+    luhn: bool,
+}
+
+fn builtin_regex(cell: &'static OnceLock<Regex>, pattern: &str) -> Regex {
+    cell.get_or_init(|| Regex::new(pattern).unwrap()).clone()
+}
+
+fn compile(filter: ContentFilter) -> Result<CompiledFilter, String> {
+    static API_KEYS: OnceLock<Regex> = OnceLock::new();
+    static CREDIT_CARDS: OnceLock<Regex> = OnceLock::new();
+    let (regex, luhn) = match (filter.id.as_str(), &filter.pattern) {
+        (API_KEYS_FILTER_ID, None) => (builtin_regex(&API_KEYS, API_KEY_PATTERN), false),
+        (CREDIT_CARDS_FILTER_ID, None) => (builtin_regex(&CREDIT_CARDS, CREDIT_CARD_PATTERN), true),
+        (_, Some(pattern)) => (
+            Regex::new(pattern).map_err(|e| format!("Invalid pattern for filter \"{}\": {}", filter.name, e))?,
+            false,
+        ),
+        (id, None) => return Err(format!("Filter {} has no pattern", id)),
+    };
+    Ok(CompiledFilter { filter, regex, luhn })
+}
+
+fn enabled_filters(app: &tauri::AppHandle) -> Vec<CompiledFilter> {
+    load_filters(app)
+        .into_iter()
+        .filter(|f| f.enabled)
+        .filter_map(|f| compile(f).inspect_err(|e| eprintln!("Skipping content filter: {}", e)).ok())
+        .collect()
+}
+
+/// Whether a digit string passes the Luhn checksum used by card numbers
+fn luhn_valid(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (!i.is_multiple_of(2), d * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => d,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Run the filters over one text. Matches of masking filters (or of every
+/// filter with `mask_all`) are replaced; the count per filter index is
+/// added to `counts`.
+fn filter_text(text: &str, filters: &[CompiledFilter], mask_all: bool, counts: &mut BTreeMap<usize, usize>) -> String {
+    let mut text = text.to_string();
+    for (index, compiled) in filters.iter().enumerate() {
+        let mask = mask_all || compiled.filter.action == FilterAction::Mask;
+        let replacement = format!("[REDACTED: {}]", compiled.filter.name);
+        let mut found = 0;
+        let filtered = compiled.regex.replace_all(&text, |captures: &regex::Captures| {
+            let matched = &captures[0];
+            if compiled.luhn && !luhn_valid(matched) {
+                return matched.to_string();
+            }
+            found += 1;
+            if mask {
+                replacement.clone()
+            } else {
+                matched.to_string()
+            }
+        });
+        let filtered = filtered.into_owned();
+        if found > 0 {
+            *counts.entry(index).or_default() += found;
+            text = filtered;
+        }
+    }
+    text
+}
+
+/// Filter the text of a content block: text blocks, plain-text documents
+/// and base64 documents with a text media type. Other attachments (images,
+/// PDFs) pass through unchanged.
+fn filter_block(block: &serde_json::Value, filters: &[CompiledFilter], counts: &mut BTreeMap<usize, usize>) -> serde_json::Value {
+    let mut block = block.clone();
+    if let Some(text) = block["text"].as_str().filter(|_| block["type"] == "text") {
+        block["text"] = serde_json::json!(filter_text(text, filters, false, counts));
+        return block;
+    }
+    if !matches!(block["type"].as_str(), Some("document") | Some("file")) {
+        return block;
+    }
+    let source = &block["source"];
+    let is_text_media = source["media_type"].as_str().is_some_and(|m| m.starts_with("text/"));
+    match (source["type"].as_str(), source["data"].as_str()) {
+        (Some("text"), Some(data)) => {
+            block["source"]["data"] = serde_json::json!(filter_text(data, filters, false, counts));
+        }
+        (Some("base64"), Some(data)) if is_text_media => {
+            let decoded = BASE64.decode(data).ok().and_then(|bytes| String::from_utf8(bytes).ok());
+            if let Some(decoded) = decoded {
+                let filtered = filter_text(&decoded, filters, false, counts);
+                block["source"]["data"] = serde_json::json!(BASE64.encode(filtered));
+            }
+        }
+        _ => {}
+    }
+    block
+}
+
+fn filter_content(content: &serde_json::Value, filters: &[CompiledFilter], counts: &mut BTreeMap<usize, usize>) -> serde_json::Value {
+    match content {
+        serde_json::Value::String(text) => serde_json::json!(filter_text(text, filters, false, counts)),
+        serde_json::Value::Array(blocks) => blocks.iter().map(|b| filter_block(b, filters, counts)).collect(),
+        other => other.clone(),
+    }
+}
+
+fn findings(filters: &[CompiledFilter], counts: &BTreeMap<usize, usize>) -> Vec<FilterFinding> {
+    counts
+        .iter()
+        .map(|(&index, &count)| FilterFinding {
+            filter_id: filters[index].filter.id.clone(),
+            name: filters[index].filter.name.clone(),
+            action: filters[index].filter.action,
+            count,
+        })
+        .collect()
+}
+
+// ============================================================================
+// Pipeline
+// ============================================================================
+
+/// Run the enabled filters over a turn's user messages. Returns the messages
+/// with masked text, or an error when a warning filter matched the message
+/// being sent.
+pub fn apply_content_filters(
+    app: &tauri::AppHandle,
+    window: &tauri::Window,
+    turn_id: &str,
+    messages: Vec<ChatMessage>,
+) -> Result<Vec<ChatMessage>, String> {
+    let filters = enabled_filters(app);
+    if filters.is_empty() {
+        return Ok(messages);
+    }
+
+    let last_user = messages.iter().rposition(|m| m.role == "user");
+    let mut counts = BTreeMap::new();
+    let mut latest_counts = BTreeMap::new();
+    let messages: Vec<ChatMessage> = messages
+        .into_iter()
+        .enumerate()
+        .map(|(index, message)| {
+            if message.role != "user" {
+                return message;
+            }
+            let counts = if Some(index) == last_user { &mut latest_counts } else { &mut counts };
+            ChatMessage {
+                content: filter_content(&message.content, &filters, counts),
+                ..message
+            }
+        })
+        .collect();
+
+    let warnings: Vec<FilterFinding> = findings(&filters, &latest_counts)
+        .into_iter()
+        .filter(|f| f.action == FilterAction::Warn)
+        .collect();
+    for (index, count) in latest_counts {
+        *counts.entry(index).or_default() += count;
+    }
+    let all_findings = findings(&filters, &counts);
+    if all_findings.is_empty() {
+        return Ok(messages);
+    }
+
+    let event = ContentFilterEvent {
+        turn_id: turn_id.to_string(),
+        findings: all_findings,
+        blocked: !warnings.is_empty(),
+    };
+    if let Err(err) = window.emit("content-filter-findings", event) {
+        eprintln!("Failed to emit content-filter-findings event: {}", err);
+    }
+
+    if !warnings.is_empty() {
+        let found: Vec<String> = warnings.iter().map(|f| format!("{} ({})", f.name, f.count)).collect();
+        return Err(format!(
+            "{}: The message was not sent because it appears to contain: {}",
+            WARNING_ERROR_PREFIX,
+            found.join(", ")
+        ));
+    }
+    Ok(messages)
+}
+
+/// Apply the masking filters to a conversation's user messages, as a turn
+/// would, without reporting findings
+pub fn mask_messages(app: &tauri::AppHandle, messages: Vec<ChatMessage>) -> Vec<ChatMessage> {
+    let filters = enabled_filters(app);
+    if filters.is_empty() {
+        return messages;
+    }
+    messages
+        .into_iter()
+        .map(|message| match message.role.as_str() {
+            "user" => ChatMessage {
+                content: filter_content(&message.content, &filters, &mut BTreeMap::new()),
+                ..message
+            },
+            _ => message,
+        })
+        .collect()
+}
+
+/// Mask everything the enabled filters find in `text`, whatever their
+/// action. For requests that carry conversation text outside a chat turn.
+pub fn mask_text(app: &tauri::AppHandle, text: &str) -> String {
+    let filters = enabled_filters(app);
+    if filters.is_empty() {
+        return text.to_string();
+    }
+    filter_text(text, &filters, true, &mut BTreeMap::new())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_content_filters(app: tauri::AppHandle) -> Vec<ContentFilter> {
+    load_filters(&app)
+}
+
+/// Save the filter list. Custom filters need a valid pattern and are given
+/// an id when they have none.
+#[tauri::command]
+pub fn save_content_filters(app: tauri::AppHandle, filters: Vec<ContentFilter>) -> Result<Vec<ContentFilter>, String> {
+    let builtin_ids = [API_KEYS_FILTER_ID, CREDIT_CARDS_FILTER_ID];
+    let mut saved = Vec::new();
+    for mut filter in filters {
+        filter.name = filter.name.trim().to_string();
+        if builtin_ids.contains(&filter.id.as_str()) {
+            filter.pattern = None;
+        } else {
+            if filter.name.is_empty() {
+                return Err("Filter name cannot be empty".to_string());
+            }
+            if filter.pattern.as_deref().is_none_or(|p| p.trim().is_empty()) {
+                return Err(format!("Filter \"{}\" needs a pattern", filter.name));
+            }
+            if filter.id.is_empty() {
+                filter.id = crate::commands::new_session_id();
+            }
+        }
+        compile(filter.clone())?;
+        saved.push(filter);
+    }
+    settings::set_setting(&app, CONTENT_FILTERS_KEY, &saved)?;
+    Ok(load_filters(&app))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(id: &str, pattern: Option<&str>, action: FilterAction) -> CompiledFilter {
+        compile(ContentFilter {
+            id: id.to_string(),
+            name: id.to_string(),
+            pattern: pattern.map(str::to_string),
+            enabled: true,
+            action,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn masks_secrets_and_valid_card_numbers() {
+        let filters = [
+            filter(API_KEYS_FILTER_ID, None, FilterAction::Warn),
+            filter(CREDIT_CARDS_FILTER_ID, None, FilterAction::Mask),
+            filter("codename", Some(r"(?i)project falcon"), FilterAction::Mask),
+        ];
+        let mut counts = BTreeMap::new();
+        let text = "Key sk-ant-REDACTED, card 4111 1111 1111 1111, \
+                    order 1234 5678 9012 3456, about Project Falcon";
+        let filtered = filter_text(text, &filters, false, &mut counts);
+        assert_eq!(
+            filtered,
+            "Key sk-ant-REDACTED, card [REDACTED: credit_cards], \
+             order 1234 5678 9012 3456, about [REDACTED: codename]"
+        );
+        assert_eq!(counts, BTreeMap::from([(0, 1), (1, 1), (2, 1)]));
+
+        let masked = filter_text(text, &filters, true, &mut BTreeMap::new());
+        assert!(masked.starts_with("Key [REDACTED: api_keys], card"));
+    }
+}
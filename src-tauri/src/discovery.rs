@@ -1,12 +1,22 @@
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
-use tauri::Emitter;
+use serde_json::Value;
+use tauri::Manager;
+use tokio_util::sync::CancellationToken;
 
 use crate::commands::get_api_key_async;
+use crate::content_filters;
+use crate::discovery_batch;
+use crate::discovery_context::{self, DiscoveryContext};
+use crate::discovery_profiles::{self, DomainFilter};
+use crate::llm::{get_provider_for_model, StreamState};
 use crate::llm_logger;
+use crate::session_events;
+use crate::settings;
+use crate::structured::validate_against_schema;
 use crate::providers::anthropic::{
     parse_sse_event as anthropic_parse_sse_event, AnthropicClient, AnthropicStreamEvent,
-    DiscoveryRequestConfig as AnthropicDiscoveryRequestConfig,
+    DiscoveryRequestConfig as AnthropicDiscoveryRequestConfig, DISCOVERY_TOOL_NAME,
 };
 use crate::providers::openai::{
     parse_sse_event as openai_parse_sse_event, OpenAIClient, OpenAIStreamEvent,
@@ -53,178 +63,249 @@ pub struct DiscoveryErrorEvent {
     pub error: String,
 }
 
-/// State for incremental JSON parsing
-struct JsonParseState {
-    found_items_key: bool,
-    in_items_array: bool,
-    brace_depth: i32,
-    bracket_depth: i32,
-    current_item: String,
-    in_string: bool,
-    escape_next: bool,
-    recent_chars: String,
+/// The system prompt for a discovery run and the source domains its items
+/// may come from
+pub struct DiscoveryPrompt {
+    pub system_prompt: String,
+    pub domains: DomainFilter,
 }
 
-impl JsonParseState {
-    fn new() -> Self {
-        Self {
-            found_items_key: false,
-            in_items_array: false,
-            brace_depth: 0,
-            bracket_depth: 0,
-            current_item: String::new(),
-            in_string: false,
-            escape_next: false,
-            recent_chars: String::new(),
-        }
-    }
-}
-
-/// Extract complete JSON objects from a buffer as they become available
-fn extract_items_from_buffer(buffer: &str, state: &mut JsonParseState) -> Vec<DiscoveryItem> {
-    let mut items = Vec::new();
-
-    for c in buffer.chars() {
-        // Track recent characters to detect "items" key
-        if !state.found_items_key {
-            state.recent_chars.push(c);
-            if state.recent_chars.len() > 20 {
-                state.recent_chars.remove(0);
-            }
-            if state.recent_chars.contains("\"items\"")
-                && (state.recent_chars.ends_with('[')
-                    || state.recent_chars.ends_with(": [")
-                    || state.recent_chars.ends_with(":["))
-            {
-                state.found_items_key = true;
-                state.in_items_array = true;
-                continue;
+/// JSON Schema of a discovery reply: `{"items": [DiscoveryItem, ...]}`.
+/// Sent as the Anthropic tool input schema and the OpenAI / Gemini output
+/// format, so replies are data rather than text to scan for items.
+pub fn items_schema() -> Value {
+    let string = serde_json::json!({"type": "string"});
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "items": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "title": string,
+                        "oneLiner": string,
+                        "fullSummary": string,
+                        "relevanceExplanation": string,
+                        "sourceUrl": string,
+                        "sourceDomain": string,
+                        "category": string,
+                        "relevanceScore": {"type": "integer"}
+                    },
+                    "required": [
+                        "title", "oneLiner", "fullSummary", "relevanceExplanation",
+                        "sourceUrl", "sourceDomain", "category", "relevanceScore"
+                    ],
+                    "additionalProperties": false
+                }
             }
-        }
+        },
+        "required": ["items"],
+        "additionalProperties": false
+    })
+}
 
-        if !state.in_items_array {
-            continue;
-        }
+/// The items in a structured discovery reply, from sources the filter allows
+pub fn parse_items(output: &Value, domains: &DomainFilter) -> Result<Vec<DiscoveryItem>, String> {
+    #[derive(Deserialize)]
+    struct Reply {
+        items: Vec<DiscoveryItem>,
+    }
 
-        // Handle string escaping
-        if state.escape_next {
-            state.escape_next = false;
-            if state.brace_depth > 0 {
-                state.current_item.push(c);
-            }
-            continue;
-        }
+    validate_against_schema(&items_schema(), output, "")
+        .map_err(|e| format!("Discovery reply didn't match the schema: {}", e))?;
+    let reply: Reply = serde_json::from_value(output.clone()).map_err(|e| format!("Invalid discovery reply: {}", e))?;
+    Ok(reply.items.into_iter().filter(|item| domains.allows(item)).collect())
+}
 
-        if c == '\\' && state.in_string {
-            state.escape_next = true;
-            if state.brace_depth > 0 {
-                state.current_item.push(c);
-            }
-            continue;
-        }
+/// The discovery tool's input in an Anthropic message's content
+pub fn tool_output(message: &Value) -> Option<Value> {
+    message["content"]
+        .as_array()?
+        .iter()
+        .find(|block| block["type"] == "tool_use" && block["name"] == DISCOVERY_TOOL_NAME)
+        .map(|block| block["input"].clone())
+}
 
-        // Toggle string mode on unescaped quotes
-        if c == '"' {
-            state.in_string = !state.in_string;
-            if state.brace_depth > 0 {
-                state.current_item.push(c);
-            }
-            continue;
+/// Emit the items of a finished reply (`output` is its structured JSON),
+/// then `discovery-done`; or `discovery-error` if it can't be read
+fn finish_discovery(window: &tauri::Window, turn_id: &str, output: &str, domains: &DomainFilter) -> Result<(), String> {
+    llm_logger::log_response_complete("discovery", output);
+    let items = serde_json::from_str(output)
+        .map_err(|e| format!("Discovery reply wasn't valid JSON: {}", e))
+        .and_then(|output| parse_items(&output, domains));
+    let items = match items {
+        Ok(items) => items,
+        Err(error) => {
+            emit_error(window, turn_id, error.clone());
+            return Err(error);
         }
-
-        // Skip processing special chars inside strings
-        if state.in_string {
-            if state.brace_depth > 0 {
-                state.current_item.push(c);
-            }
-            continue;
+    };
+    for item in items {
+        if let Err(err) = session_events::emit(
+            window,
+            turn_id,
+            "discovery-item",
+            DiscoveryItemEvent {
+                turn_id: turn_id.to_string(),
+                item,
+            },
+        ) {
+            eprintln!("Failed to emit discovery-item event: {}", err);
         }
+    }
+    emit_done(window, turn_id);
+    Ok(())
+}
 
-        // Outside of strings, look for structural characters
-        match c {
-            '{' => {
-                state.brace_depth += 1;
-                state.current_item.push(c);
-            }
-            '}' => {
-                state.current_item.push(c);
-                state.brace_depth -= 1;
-
-                if state.brace_depth == 0 && !state.current_item.is_empty() {
-                    let trimmed = state.current_item.trim();
-                    if let Ok(item) = serde_json::from_str::<DiscoveryItem>(trimmed) {
-                        items.push(item);
-                    }
-                    state.current_item.clear();
-                }
-            }
-            '[' => {
-                if state.brace_depth > 0 {
-                    state.current_item.push(c);
-                    state.bracket_depth += 1;
-                }
-            }
-            ']' => {
-                if state.brace_depth > 0 {
-                    state.current_item.push(c);
-                    state.bracket_depth -= 1;
-                } else {
-                    state.in_items_array = false;
-                }
-            }
-            _ => {
-                if state.brace_depth > 0 {
-                    state.current_item.push(c);
-                }
-            }
-        }
+pub fn emit_done(window: &tauri::Window, turn_id: &str) {
+    if let Err(err) = session_events::emit(
+        window,
+        turn_id,
+        "discovery-done",
+        DiscoveryDoneEvent {
+            turn_id: turn_id.to_string(),
+        },
+    ) {
+        eprintln!("Failed to emit discovery-done event: {}", err);
     }
+}
 
-    items
+fn emit_error(window: &tauri::Window, turn_id: &str, error: String) {
+    if let Err(err) = session_events::emit(
+        window,
+        turn_id,
+        "discovery-error",
+        DiscoveryErrorEvent {
+            turn_id: turn_id.to_string(),
+            error,
+        },
+    ) {
+        eprintln!("Failed to emit discovery-error event: {}", err);
+    }
 }
 
-/// Determine which provider to use based on model name
-fn get_provider_for_model(model: &str) -> &'static str {
-    if model.starts_with("gpt") || model.starts_with("o3") || model.starts_with("o4") {
-        "openai"
-    } else if model.starts_with("gemini") {
-        "google"
-    } else {
-        "anthropic" // Default for claude-* and unknown models
+/// Register a discovery run: it can be cancelled and its events are routed
+/// to `session_id`'s windows until `end_run`
+fn begin_run(window: &tauri::Window, turn_id: &str, session_id: Option<&str>) -> CancellationToken {
+    if let Some(session_id) = session_id {
+        session_events::bind_turn(window, turn_id, session_id);
     }
+    window.state::<StreamState>().begin_discovery(turn_id)
 }
 
+/// End a discovery run started by `begin_run`
+pub fn end_run(window: &tauri::Window, turn_id: &str) {
+    window.state::<StreamState>().end_discovery(turn_id);
+    session_events::unbind_turn(window, turn_id);
+}
+
+/// Run discovery for a turn. `cancel_chat_stream` with the turn's id stops
+/// it, ending it with `discovery-done` and the items found so far. Its events
+/// go to the windows showing `session_id` (see `session_events`).
 #[tauri::command]
 pub async fn discover_resources(
     app: tauri::AppHandle,
     window: tauri::Window,
     turn_id: String,
-    model: String,
-    conversation: String,
-    system_prompt: String,
+    session_id: Option<String>,
+    model: Option<String>, // Falls back to the discovery model default when omitted
+    messages: Option<Vec<Value>>, // Session messages as the frontend holds them; context is built from them
+    conversation: Option<String>, // Preformatted conversation, used when no messages are given
+    system_prompt: Option<String>, // Ignored when a profile is given
+    profile_id: Option<String>,
     _max_results: u32,
     // Provider-specific thinking parameters
     extended_thinking_enabled: Option<bool>,
     reasoning_level: Option<String>,
     gemini_thinking_level: Option<String>,
 ) -> Result<(), String> {
+    let profile = profile_id
+        .map(|id| discovery_profiles::get_profile(&app, &id))
+        .transpose()?;
+    // Between the turns of a profile's cadence the run ends without items
+    if let (Some(profile), Some(messages)) = (&profile, &messages) {
+        let user_turns = messages.iter().filter(|m| m["role"] == "user").count();
+        if !profile.is_due(user_turns) {
+            begin_run(&window, &turn_id, session_id.as_deref());
+            emit_done(&window, &turn_id);
+            end_run(&window, &turn_id);
+            return Ok(());
+        }
+    }
+    let prompt = match &profile {
+        Some(profile) => DiscoveryPrompt {
+            system_prompt: profile.system_prompt(),
+            domains: profile.domain_filter(),
+        },
+        None => DiscoveryPrompt {
+            system_prompt: system_prompt.ok_or("Discovery needs a profile or a system prompt")?,
+            domains: DomainFilter::default(),
+        },
+    };
+    let mut context = match (messages, conversation) {
+        (Some(messages), _) => discovery_context::messages_context(&messages, &turn_id),
+        (None, Some(conversation)) => DiscoveryContext {
+            conversation,
+            images: Vec::new(),
+        },
+        (None, None) => return Err("Discovery needs the conversation's messages or text".to_string()),
+    };
+    context.conversation = content_filters::mask_text(&app, &context.conversation);
+    let model = profile
+        .and_then(|p| p.model)
+        .or(model)
+        .unwrap_or_else(|| settings::model_defaults(&app).discovery);
+
     // Route to the appropriate provider based on model
     let provider = get_provider_for_model(&model);
-
-    match provider {
-        "openai" => {
-            discover_resources_openai(&app, &window, turn_id, model, conversation, system_prompt, reasoning_level)
-                .await
+    let cancel_token = begin_run(&window, &turn_id, session_id.as_deref());
+
+    // A batch is polled in the background, which ends the run when it finishes
+    if provider == "anthropic" && discovery_batch::batch_mode(&app) {
+        let result = discovery_batch::submit(
+            &app,
+            &window,
+            turn_id.clone(),
+            model,
+            context,
+            prompt,
+            extended_thinking_enabled,
+            cancel_token,
+        )
+        .await;
+        if result.is_err() {
+            end_run(&window, &turn_id);
         }
-        "google" => {
-            discover_resources_gemini(&app, &window, turn_id, model, conversation, system_prompt, gemini_thinking_level)
-                .await
+        return result;
+    }
+
+    let run = async {
+        match provider {
+            "openai" => {
+                discover_resources_openai(&app, &window, turn_id.clone(), model, context, prompt, reasoning_level)
+                    .await
+            }
+            "google" => {
+                discover_resources_gemini(&app, &window, turn_id.clone(), model, context, prompt, gemini_thinking_level)
+                    .await
+            }
+            "openrouter" => Err("Discovery isn't supported for OpenRouter models".to_string()),
+            "anthropic" | _ => {
+                discover_resources_anthropic(&app, &window, turn_id.clone(), model, context, prompt, extended_thinking_enabled)
+                    .await
+            }
         }
-        "anthropic" | _ => {
-            discover_resources_anthropic(&app, &window, turn_id, model, conversation, system_prompt, extended_thinking_enabled)
-                .await
+    };
+    let result = tokio::select! {
+        result = run => result,
+        _ = cancel_token.cancelled() => {
+            emit_done(&window, &turn_id);
+            Ok(())
         }
-    }
+    };
+    end_run(&window, &turn_id);
+    result
 }
 
 /// Discovery using Anthropic API
@@ -233,8 +314,8 @@ async fn discover_resources_anthropic(
     window: &tauri::Window,
     turn_id: String,
     model: String,
-    conversation: String,
-    system_prompt: String,
+    context: DiscoveryContext,
+    prompt: DiscoveryPrompt,
     extended_thinking_enabled: Option<bool>,
 ) -> Result<(), String> {
     let api_key = get_api_key_async(app, "anthropic").await?;
@@ -243,9 +324,11 @@ async fn discover_resources_anthropic(
     // Build request using provider
     let config = AnthropicDiscoveryRequestConfig {
         model: model.clone(),
-        system_prompt,
-        conversation,
+        system_prompt: prompt.system_prompt,
+        conversation: context.conversation,
+        images: context.images,
         extended_thinking_enabled,
+        output_schema: items_schema(),
     };
     let body = client.build_discovery_request(&config);
 
@@ -253,23 +336,15 @@ async fn discover_resources_anthropic(
 
     let response = client.send_streaming_request(&body).await.map_err(|e| {
         llm_logger::log_error("discovery", &e);
-        window
-            .emit(
-                "discovery-error",
-                DiscoveryErrorEvent {
-                    turn_id: turn_id.clone(),
-                    error: e.clone(),
-                },
-            )
-            .unwrap_or_else(|err| eprintln!("Failed to emit discovery-error event: {}", err));
+        emit_error(window, &turn_id, e.to_string());
         e
     })?;
 
-    // Stream the response
+    // Stream the response; the items arrive as the discovery tool's input
     let mut stream = response.bytes_stream();
     let mut sse_buffer = String::new();
-    let mut full_response = String::new();
-    let mut parse_state = JsonParseState::new();
+    let mut output = String::new();
+    let mut in_items_tool = false;
 
     while let Some(chunk) = stream.next().await {
         let chunk = match chunk {
@@ -277,16 +352,8 @@ async fn discover_resources_anthropic(
             Err(e) => {
                 let error_msg = e.to_string();
                 llm_logger::log_error("discovery", &error_msg);
-                if let Err(err) = window.emit(
-                    "discovery-error",
-                    DiscoveryErrorEvent {
-                        turn_id: turn_id.clone(),
-                        error: error_msg,
-                    },
-                ) {
-                    eprintln!("Failed to emit discovery-error event: {}", err);
-                }
-                break;
+                emit_error(window, &turn_id, error_msg.clone());
+                return Err(error_msg);
             }
         };
 
@@ -302,38 +369,15 @@ async fn discover_resources_anthropic(
                 if let Some(data) = line.strip_prefix("data: ") {
                     match anthropic_parse_sse_event(data) {
                         AnthropicStreamEvent::Done | AnthropicStreamEvent::MessageStop => {
-                            llm_logger::log_response_complete("discovery", &full_response);
-                            if let Err(err) = window.emit(
-                                "discovery-done",
-                                DiscoveryDoneEvent {
-                                    turn_id: turn_id.clone(),
-                                },
-                            ) {
-                                eprintln!("Failed to emit discovery-done event: {}", err);
-                            }
-                            return Ok(());
+                            return finish_discovery(window, &turn_id, &output, &prompt.domains);
+                        }
+                        AnthropicStreamEvent::ContentBlockStart { block_type, content_block } => {
+                            in_items_tool = block_type == "tool_use" && content_block["name"] == DISCOVERY_TOOL_NAME;
                         }
-                        AnthropicStreamEvent::ContentBlockDelta { text, thinking: _, citation: _, input_json: _ } => {
-                            if let Some(delta_text) = text {
-                                full_response.push_str(&delta_text);
-
-                                // Extract complete items from the delta
-                                let items =
-                                    extract_items_from_buffer(&delta_text, &mut parse_state);
-
-                                for item in items {
-                                    if let Err(err) = window.emit(
-                                        "discovery-item",
-                                        DiscoveryItemEvent {
-                                            turn_id: turn_id.clone(),
-                                            item,
-                                        },
-                                    ) {
-                                        eprintln!("Failed to emit discovery-item event: {}", err);
-                                    }
-                                }
-                            }
+                        AnthropicStreamEvent::ContentBlockDelta { input_json: Some(json), .. } if in_items_tool => {
+                            output.push_str(&json);
                         }
+                        AnthropicStreamEvent::ContentBlockStop => in_items_tool = false,
                         _ => {}
                     }
                 }
@@ -341,16 +385,7 @@ async fn discover_resources_anthropic(
         }
     }
 
-    llm_logger::log_response_complete("discovery", &full_response);
-    if let Err(err) = window.emit(
-        "discovery-done",
-        DiscoveryDoneEvent {
-            turn_id: turn_id.clone(),
-        },
-    ) {
-        eprintln!("Failed to emit discovery-done event: {}", err);
-    }
-    Ok(())
+    finish_discovery(window, &turn_id, &output, &prompt.domains)
 }
 
 /// Discovery using OpenAI Responses API
@@ -359,8 +394,8 @@ async fn discover_resources_openai(
     window: &tauri::Window,
     turn_id: String,
     model: String,
-    conversation: String,
-    system_prompt: String,
+    context: DiscoveryContext,
+    prompt: DiscoveryPrompt,
     reasoning_level: Option<String>,
 ) -> Result<(), String> {
     let api_key = get_api_key_async(app, "openai").await?;
@@ -369,10 +404,12 @@ async fn discover_resources_openai(
     // Build request using provider
     let config = OpenAIDiscoveryRequestConfig {
         model: model.clone(),
-        system_prompt,
-        conversation,
+        system_prompt: prompt.system_prompt,
+        conversation: context.conversation,
+        images: context.images,
         prompt_cache_key: Some("discovery".to_string()),
         reasoning_level,
+        output_schema: items_schema(),
     };
     let body = client.build_discovery_request(&config);
 
@@ -380,23 +417,14 @@ async fn discover_resources_openai(
 
     let response = client.send_streaming_request(&body).await.map_err(|e| {
         llm_logger::log_error("discovery", &e);
-        window
-            .emit(
-                "discovery-error",
-                DiscoveryErrorEvent {
-                    turn_id: turn_id.clone(),
-                    error: e.clone(),
-                },
-            )
-            .unwrap_or_else(|err| eprintln!("Failed to emit discovery-error event: {}", err));
+        emit_error(window, &turn_id, e.to_string());
         e
     })?;
 
-    // Stream the response
+    // Stream the response; its text is JSON following the items schema
     let mut stream = response.bytes_stream();
     let mut sse_buffer = String::new();
-    let mut full_response = String::new();
-    let mut parse_state = JsonParseState::new();
+    let mut output = String::new();
 
     while let Some(chunk) = stream.next().await {
         let chunk = match chunk {
@@ -404,16 +432,8 @@ async fn discover_resources_openai(
             Err(e) => {
                 let error_msg = e.to_string();
                 llm_logger::log_error("discovery", &error_msg);
-                if let Err(err) = window.emit(
-                    "discovery-error",
-                    DiscoveryErrorEvent {
-                        turn_id: turn_id.clone(),
-                        error: error_msg,
-                    },
-                ) {
-                    eprintln!("Failed to emit discovery-error event: {}", err);
-                }
-                break;
+                emit_error(window, &turn_id, error_msg.clone());
+                return Err(error_msg);
             }
         };
 
@@ -428,47 +448,15 @@ async fn discover_resources_openai(
             for line in event.lines() {
                 if let Some(data) = line.strip_prefix("data: ") {
                     match openai_parse_sse_event(data) {
-                        OpenAIStreamEvent::Done | OpenAIStreamEvent::ResponseCompleted => {
-                            llm_logger::log_response_complete("discovery", &full_response);
-                            if let Err(err) = window.emit(
-                                "discovery-done",
-                                DiscoveryDoneEvent {
-                                    turn_id: turn_id.clone(),
-                                },
-                            ) {
-                                eprintln!("Failed to emit discovery-done event: {}", err);
-                            }
-                            return Ok(());
+                        OpenAIStreamEvent::Done | OpenAIStreamEvent::ResponseCompleted { .. } => {
+                            return finish_discovery(window, &turn_id, &output, &prompt.domains);
                         }
                         OpenAIStreamEvent::TextDelta { text: delta_text } => {
-                            full_response.push_str(&delta_text);
-
-                            // Extract complete items from the delta
-                            let items = extract_items_from_buffer(&delta_text, &mut parse_state);
-
-                            for item in items {
-                                if let Err(err) = window.emit(
-                                    "discovery-item",
-                                    DiscoveryItemEvent {
-                                        turn_id: turn_id.clone(),
-                                        item,
-                                    },
-                                ) {
-                                    eprintln!("Failed to emit discovery-item event: {}", err);
-                                }
-                            }
+                            output.push_str(&delta_text);
                         }
                         OpenAIStreamEvent::Error { message } => {
                             llm_logger::log_error("discovery", &message);
-                            if let Err(err) = window.emit(
-                                "discovery-error",
-                                DiscoveryErrorEvent {
-                                    turn_id: turn_id.clone(),
-                                    error: message.clone(),
-                                },
-                            ) {
-                                eprintln!("Failed to emit discovery-error event: {}", err);
-                            }
+                            emit_error(window, &turn_id, message.clone());
                             return Err(message);
                         }
                         _ => {}
@@ -478,16 +466,7 @@ async fn discover_resources_openai(
         }
     }
 
-    llm_logger::log_response_complete("discovery", &full_response);
-    if let Err(err) = window.emit(
-        "discovery-done",
-        DiscoveryDoneEvent {
-            turn_id: turn_id.clone(),
-        },
-    ) {
-        eprintln!("Failed to emit discovery-done event: {}", err);
-    }
-    Ok(())
+    finish_discovery(window, &turn_id, &output, &prompt.domains)
 }
 
 /// Discovery using Google Gemini API
@@ -496,8 +475,8 @@ async fn discover_resources_gemini(
     window: &tauri::Window,
     turn_id: String,
     model: String,
-    conversation: String,
-    system_prompt: String,
+    context: DiscoveryContext,
+    prompt: DiscoveryPrompt,
     gemini_thinking_level: Option<String>,
 ) -> Result<(), String> {
     // TEMPORARY: Gemini 3.1 Pro Preview has a bug where google_search + structured JSON output
@@ -521,9 +500,11 @@ async fn discover_resources_gemini(
     // eprintln!("[DISCOVERY-GEMINI] Resolved thinking level: {:?}, config is_some: {}", thinking_level, thinking_config.is_some());
 
     let config = GeminiDiscoveryRequestConfig {
-        system_prompt,
-        conversation,
+        system_prompt: prompt.system_prompt,
+        conversation: context.conversation,
+        images: context.images,
         thinking_config,
+        output_schema: items_schema(),
     };
     let body = client.build_discovery_request(&config);
 
@@ -540,30 +521,19 @@ async fn discover_resources_gemini(
         .map_err(|e| {
             // eprintln!("[DISCOVERY-GEMINI] *** HTTP ERROR: {} ***", e);
             llm_logger::log_error("discovery", &e);
-            window
-                .emit(
-                    "discovery-error",
-                    DiscoveryErrorEvent {
-                        turn_id: turn_id.clone(),
-                        error: e.clone(),
-                    },
-                )
-                .unwrap_or_else(|err| eprintln!("Failed to emit discovery-error event: {}", err));
+            emit_error(window, &turn_id, e.to_string());
             e
         })?;
 
     // eprintln!("[DISCOVERY-GEMINI] Got streaming response, starting to read chunks...");
 
-    // Stream the response
+    // Stream the response; its text is JSON following the items schema
     let mut stream = response.bytes_stream();
     let mut sse_buffer = String::new();
-    let mut full_response = String::new();
-    let mut parse_state = JsonParseState::new();
+    let mut output = String::new();
     let mut accumulated_text = String::new();
     // let mut chunk_count: u32 = 0;
     // let mut sse_event_count: u32 = 0;
-    // let mut text_delta_count: u32 = 0;
-    // let mut items_found: u32 = 0;
 
     while let Some(chunk) = stream.next().await {
         let chunk = match chunk {
@@ -572,16 +542,8 @@ async fn discover_resources_gemini(
                 let error_msg = e.to_string();
                 // eprintln!("[DISCOVERY-GEMINI] *** STREAM CHUNK ERROR: {} ***", error_msg);
                 llm_logger::log_error("discovery", &error_msg);
-                if let Err(err) = window.emit(
-                    "discovery-error",
-                    DiscoveryErrorEvent {
-                        turn_id: turn_id.clone(),
-                        error: error_msg,
-                    },
-                ) {
-                    eprintln!("Failed to emit discovery-error event: {}", err);
-                }
-                break;
+                emit_error(window, &turn_id, error_msg.clone());
+                return Err(error_msg);
             }
         };
 
@@ -605,116 +567,73 @@ async fn discover_resources_gemini(
                 // }
 
                 // parse_sse_event returns Vec since one SSE can have multiple parts
-                let events = gemini_parse_sse_event(data);
-                // for event in &events {
-                //     match event {
-                //         GeminiStreamEvent::ResponseComplete => eprintln!("[DISCOVERY-GEMINI] Event: ResponseComplete"),
-                //         GeminiStreamEvent::TextDelta { text } => eprintln!("[DISCOVERY-GEMINI] Event: TextDelta ({} chars)", text.len()),
-                //         GeminiStreamEvent::ThinkingDelta { text } => eprintln!("[DISCOVERY-GEMINI] Event: ThinkingDelta ({} chars)", text.len()),
-                //         GeminiStreamEvent::Error { message } => eprintln!("[DISCOVERY-GEMINI] Event: Error({})", message),
-                //         GeminiStreamEvent::GroundingMetadata { .. } => eprintln!("[DISCOVERY-GEMINI] Event: GroundingMetadata"),
-                //         GeminiStreamEvent::ExecutableCode { .. } => eprintln!("[DISCOVERY-GEMINI] Event: ExecutableCode"),
-                //         GeminiStreamEvent::CodeExecutionResult { .. } => eprintln!("[DISCOVERY-GEMINI] Event: CodeExecutionResult"),
-                //         GeminiStreamEvent::InlineData { .. } => eprintln!("[DISCOVERY-GEMINI] Event: InlineData"),
-                //         GeminiStreamEvent::Unknown => eprintln!("[DISCOVERY-GEMINI] Event: Unknown"),
-                //     }
-                // }
-
-                for event in events {
-                match event {
-                    GeminiStreamEvent::ResponseComplete { .. } => {
-                        // eprintln!("[DISCOVERY-GEMINI] === COMPLETE === chunks:{}, sse_events:{}, text_deltas:{}, items:{}", chunk_count, sse_event_count, text_delta_count, items_found);
-                        // eprintln!("[DISCOVERY-GEMINI] Full response length: {} chars", full_response.len());
-                        // if full_response.len() <= 1000 {
-                        //     eprintln!("[DISCOVERY-GEMINI] Full response:\n{}", full_response);
-                        // } else {
-                        //     eprintln!("[DISCOVERY-GEMINI] Full response (first 500 + last 500):\n{}...\n...{}", &full_response[..500], &full_response[full_response.len()-500..]);
-                        // }
-                        llm_logger::log_response_complete("discovery", &full_response);
-                        if let Err(err) = window.emit(
-                            "discovery-done",
-                            DiscoveryDoneEvent {
-                                turn_id: turn_id.clone(),
-                            },
-                        ) {
-                            eprintln!("Failed to emit discovery-done event: {}", err);
+                for event in gemini_parse_sse_event(data) {
+                    match event {
+                        GeminiStreamEvent::ResponseComplete { .. } => {
+                            // eprintln!("[DISCOVERY-GEMINI] === COMPLETE === chunks:{}, sse_events:{}", chunk_count, sse_event_count);
+                            return finish_discovery(window, &turn_id, &output, &prompt.domains);
                         }
-                        return Ok(());
-                    }
-                    GeminiStreamEvent::TextDelta { text: delta_text } => {
-                        // text_delta_count += 1;
-                        // Gemini sends complete text in each chunk, need to diff
-                        let new_text = if delta_text.starts_with(&accumulated_text) {
-                            delta_text[accumulated_text.len()..].to_string()
-                        } else {
-                            // Reset - new response
-                            accumulated_text.clear();
-                            delta_text.clone()
-                        };
-                        accumulated_text = delta_text;
-
-                        if !new_text.is_empty() {
-                            full_response.push_str(&new_text);
-
-                            // Extract complete items from the delta
-                            let items = extract_items_from_buffer(&new_text, &mut parse_state);
-
-                            // if !items.is_empty() {
-                            //     items_found += items.len() as u32;
-                            //     eprintln!("[DISCOVERY-GEMINI] Extracted {} items (total: {})", items.len(), items_found);
-                            // }
-
-                            for item in items {
-                                if let Err(err) = window.emit(
-                                    "discovery-item",
-                                    DiscoveryItemEvent {
-                                        turn_id: turn_id.clone(),
-                                        item,
-                                    },
-                                ) {
-                                    eprintln!("Failed to emit discovery-item event: {}", err);
-                                }
-                            }
+                        GeminiStreamEvent::TextDelta { text: delta_text } => {
+                            // Gemini sends complete text in each chunk, need to diff
+                            let new_text = if delta_text.starts_with(&accumulated_text) {
+                                delta_text[accumulated_text.len()..].to_string()
+                            } else {
+                                // Reset - new response
+                                accumulated_text.clear();
+                                delta_text.clone()
+                            };
+                            accumulated_text = delta_text;
+                            output.push_str(&new_text);
                         }
-                    }
-                    GeminiStreamEvent::Error { message } => {
-                        // eprintln!("[DISCOVERY-GEMINI] *** STREAM ERROR EVENT: {} ***", message);
-                        llm_logger::log_error("discovery", &message);
-                        if let Err(err) = window.emit(
-                            "discovery-error",
-                            DiscoveryErrorEvent {
-                                turn_id: turn_id.clone(),
-                                error: message.clone(),
-                            },
-                        ) {
-                            eprintln!("Failed to emit discovery-error event: {}", err);
+                        GeminiStreamEvent::Error { message } => {
+                            // eprintln!("[DISCOVERY-GEMINI] *** STREAM ERROR EVENT: {} ***", message);
+                            llm_logger::log_error("discovery", &message);
+                            emit_error(window, &turn_id, message.clone());
+                            return Err(message);
                         }
-                        return Err(message);
+                        _ => {}
                     }
-                    _ => {}
                 }
-                } // end for event in events
             }
         }
     }
 
-    // eprintln!("[DISCOVERY-GEMINI] === STREAM ENDED (no ResponseComplete) === chunks:{}, sse_events:{}, text_deltas:{}, items:{}", chunk_count, sse_event_count, text_delta_count, items_found);
-    // eprintln!("[DISCOVERY-GEMINI] Full response length: {} chars", full_response.len());
-    // if !full_response.is_empty() && full_response.len() <= 1000 {
-    //     eprintln!("[DISCOVERY-GEMINI] Full response:\n{}", full_response);
-    // }
+    // eprintln!("[DISCOVERY-GEMINI] === STREAM ENDED (no ResponseComplete) === chunks:{}, sse_events:{}", chunk_count, sse_event_count);
     // if !sse_buffer.is_empty() {
     //     eprintln!("[DISCOVERY-GEMINI] Remaining SSE buffer ({} chars): {}", sse_buffer.len(), &sse_buffer[..sse_buffer.len().min(300)]);
     // }
 
-    llm_logger::log_response_complete("discovery", &full_response);
-    if let Err(err) = window.emit(
-        "discovery-done",
-        DiscoveryDoneEvent {
-            turn_id: turn_id.clone(),
-        },
-    ) {
-        eprintln!("Failed to emit discovery-done event: {}", err);
+    finish_discovery(window, &turn_id, &output, &prompt.domains)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_items_from_structured_replies_only() {
+        let item = serde_json::json!({
+            "title": "The Rust Book",
+            "oneLiner": "Official guide",
+            "fullSummary": "Covers the language end to end.",
+            "relevanceExplanation": "The user is learning Rust.",
+            "sourceUrl": "https://doc.rust-lang.org/book/",
+            "sourceDomain": "doc.rust-lang.org",
+            "category": "Documentation",
+            "relevanceScore": 90
+        });
+        let items = parse_items(&serde_json::json!({"items": [item]}), &DomainFilter::default()).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "The Rust Book");
+
+        let mut incomplete = item.clone();
+        incomplete.as_object_mut().unwrap().remove("sourceUrl");
+        assert!(parse_items(&serde_json::json!({"items": [incomplete]}), &DomainFilter::default()).is_err());
+
+        let message = serde_json::json!({"content": [
+            {"type": "text", "text": "{\"items\": []}"},
+            {"type": "tool_use", "name": DISCOVERY_TOOL_NAME, "input": {"items": [item]}}
+        ]});
+        assert_eq!(tool_output(&message).unwrap()["items"].as_array().unwrap().len(), 1);
     }
-    Ok(())
 }
@@ -0,0 +1,252 @@
+//! Batch discovery (Anthropic)
+//!
+//! Discovery results aren't read as they stream, so when batch mode is on,
+//! Anthropic discovery runs are submitted through the Message Batches API
+//! instead, at half the token cost. `discover_resources` returns once the
+//! batch is accepted (emitting `discovery-batch-submitted` with the batch
+//! id); a background task polls the batch and, when it ends, emits the
+//! usual `discovery-item` events followed by `discovery-done`, or
+//! `discovery-error` if the request failed or expired. Cancelling the run
+//! stops the polling. Batches can take minutes to finish, so this suits runs
+//! the user isn't waiting on.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::commands::get_api_key_async;
+use crate::discovery::{self, emit_done, DiscoveryErrorEvent, DiscoveryItemEvent, DiscoveryPrompt};
+use crate::discovery_context::DiscoveryContext;
+use crate::discovery_profiles::DomainFilter;
+use crate::llm_logger;
+use crate::providers::anthropic::{AnthropicClient, DiscoveryRequestConfig as AnthropicDiscoveryRequestConfig};
+use crate::session_events;
+use crate::settings;
+
+const DISCOVERY_BATCH_MODE_KEY: &str = "discovery_batch_mode";
+
+/// Each batch holds a single discovery request under this id
+const CUSTOM_ID: &str = "discovery";
+
+/// How often a submitted batch is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Consecutive failed status checks before a batch is given up on
+const MAX_POLL_FAILURES: u32 = 5;
+
+/// Payload for discovery-batch-submitted event
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveryBatchSubmittedEvent {
+    pub turn_id: String,
+    pub batch_id: String,
+}
+
+pub fn batch_mode(app: &tauri::AppHandle) -> bool {
+    settings::get_setting(app, DISCOVERY_BATCH_MODE_KEY).unwrap_or(false)
+}
+
+/// The structured reply (the discovery tool's input) for our request in a
+/// batch's results, or why there is none
+fn batch_outcome(results: &[Value]) -> Result<Value, String> {
+    let result = results
+        .iter()
+        .find(|r| r["custom_id"].as_str() == Some(CUSTOM_ID))
+        .map(|r| &r["result"])
+        .ok_or("Discovery batch finished without a result")?;
+    match result["type"].as_str() {
+        Some("succeeded") => discovery::tool_output(&result["message"])
+            .ok_or_else(|| "Discovery batch reply didn't report any items".to_string()),
+        Some("errored") => Err(format!(
+            "Discovery batch request failed: {}",
+            result["error"]["error"]["message"].as_str().unwrap_or("unknown error")
+        )),
+        Some("expired") => Err("Discovery batch expired before it was processed".to_string()),
+        Some("canceled") => Err("Discovery batch was canceled".to_string()),
+        _ => Err("Discovery batch returned an unrecognized result".to_string()),
+    }
+}
+
+fn emit_error(window: &tauri::Window, turn_id: &str, error: String) {
+    llm_logger::log_error("discovery", &error);
+    if let Err(err) = session_events::emit(
+        window,
+        turn_id,
+        "discovery-error",
+        DiscoveryErrorEvent {
+            turn_id: turn_id.to_string(),
+            error,
+        },
+    ) {
+        eprintln!("Failed to emit discovery-error event: {}", err);
+    }
+}
+
+/// Submit a discovery run as a batch and poll it in the background until it
+/// ends or `cancel_token` is cancelled
+#[allow(clippy::too_many_arguments)]
+pub async fn submit(
+    app: &tauri::AppHandle,
+    window: &tauri::Window,
+    turn_id: String,
+    model: String,
+    context: DiscoveryContext,
+    prompt: DiscoveryPrompt,
+    extended_thinking_enabled: Option<bool>,
+    cancel_token: CancellationToken,
+) -> Result<(), String> {
+    let api_key = get_api_key_async(app, "anthropic").await?;
+    let client = AnthropicClient::new(api_key);
+
+    let config = AnthropicDiscoveryRequestConfig {
+        model: model.clone(),
+        system_prompt: prompt.system_prompt,
+        conversation: context.conversation,
+        images: context.images,
+        extended_thinking_enabled,
+        output_schema: discovery::items_schema(),
+    };
+    let mut body = client.build_discovery_request(&config);
+    // Batched requests can't stream
+    if let Some(fields) = body.as_object_mut() {
+        fields.remove("stream");
+    }
+
+    llm_logger::log_request("discovery", &model, &body);
+
+    let batch = match client.create_message_batch(&[(CUSTOM_ID.to_string(), body)]).await {
+        Ok(batch) => batch,
+        Err(e) => {
+            emit_error(window, &turn_id, e.to_string());
+            return Err(e.into());
+        }
+    };
+    let batch_id = batch["id"].as_str().ok_or("Batch response has no id")?.to_string();
+    if let Err(err) = session_events::emit(
+        window,
+        &turn_id,
+        "discovery-batch-submitted",
+        DiscoveryBatchSubmittedEvent {
+            turn_id: turn_id.clone(),
+            batch_id: batch_id.clone(),
+        },
+    ) {
+        eprintln!("Failed to emit discovery-batch-submitted event: {}", err);
+    }
+
+    let window = window.clone();
+    let domains = prompt.domains;
+    tauri::async_runtime::spawn(async move {
+        poll_batch(&client, &window, &turn_id, &batch_id, &domains, &cancel_token).await;
+        discovery::end_run(&window, &turn_id);
+    });
+
+    Ok(())
+}
+
+/// Wait for a batch to end and emit its items, or stop waiting (emitting
+/// `discovery-done` without items) when the run is cancelled
+async fn poll_batch(
+    client: &AnthropicClient,
+    window: &tauri::Window,
+    turn_id: &str,
+    batch_id: &str,
+    domains: &DomainFilter,
+    cancel_token: &CancellationToken,
+) {
+    let mut failures = 0;
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = cancel_token.cancelled() => {
+                emit_done(window, turn_id);
+                return;
+            }
+        }
+        match client.get_message_batch(batch_id).await {
+            Ok(batch) if batch["processing_status"].as_str() == Some("ended") => break,
+            Ok(_) => failures = 0,
+            Err(e) => {
+                failures += 1;
+                if failures >= MAX_POLL_FAILURES {
+                    emit_error(window, turn_id, format!("Failed to check discovery batch: {}", e));
+                    return;
+                }
+            }
+        }
+    }
+
+    let items = match client.get_message_batch_results(batch_id).await {
+        Ok(results) => batch_outcome(&results),
+        Err(e) => Err(format!("Failed to fetch discovery batch results: {}", e)),
+    }
+    .and_then(|output| {
+        llm_logger::log_response_complete("discovery", &output.to_string());
+        discovery::parse_items(&output, domains)
+    });
+    let items = match items {
+        Ok(items) => items,
+        Err(error) => {
+            emit_error(window, turn_id, error);
+            return;
+        }
+    };
+
+    for item in items {
+        if let Err(err) = session_events::emit(
+            window,
+            turn_id,
+            "discovery-item",
+            DiscoveryItemEvent {
+                turn_id: turn_id.to_string(),
+                item,
+            },
+        ) {
+            eprintln!("Failed to emit discovery-item event: {}", err);
+        }
+    }
+    emit_done(window, turn_id);
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_discovery_batch_mode(app: tauri::AppHandle) -> bool {
+    batch_mode(&app)
+}
+
+/// Send Anthropic discovery runs as batches (cheaper, but not immediate)
+#[tauri::command]
+pub fn set_discovery_batch_mode(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set_setting(&app, DISCOVERY_BATCH_MODE_KEY, &enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_our_result_from_batch_results() {
+        let succeeded = serde_json::json!([{
+            "custom_id": "discovery",
+            "result": {"type": "succeeded", "message": {"content": [
+                {"type": "server_tool_use", "name": "web_search", "input": {"query": "rust"}},
+                {"type": "tool_use", "name": "report_discovery_items", "input": {"items": []}}
+            ]}}
+        }]);
+        assert_eq!(batch_outcome(succeeded.as_array().unwrap()), Ok(serde_json::json!({"items": []})));
+
+        let errored = serde_json::json!([{
+            "custom_id": "discovery",
+            "result": {"type": "errored", "error": {"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}}
+        }]);
+        assert_eq!(
+            batch_outcome(errored.as_array().unwrap()),
+            Err("Discovery batch request failed: Overloaded".to_string())
+        );
+        assert!(batch_outcome(&[]).is_err());
+    }
+}
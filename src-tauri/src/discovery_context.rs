@@ -0,0 +1,284 @@
+//! Conversation context for discovery
+//!
+//! Discovery used to get the whole conversation flattened by the frontend.
+//! The context is now built here from the session's messages, which the
+//! frontend passes as it holds them (the reply that just finished may not be
+//! stored yet): the latest turn is always included, earlier turns are ranked by the keywords they share with
+//! it (ties going to the more recent) and added in condensed form until the
+//! token budget is spent. Selected messages keep their original numbers and
+//! appear in conversation order, with a note where turns were left out.
+//!
+//! Images attached to the selected messages (a chart or screenshot the user
+//! shared) go along too, most recent first up to a small limit, so vision
+//! models can discover against them.
+
+use regex::Regex;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use serde_json::Value;
+
+use crate::attachments::estimate_tokens;
+
+/// Tokens of conversation sent with a discovery request
+const CONTEXT_TOKEN_BUDGET: u32 = 3000;
+
+/// Per-message cap for earlier turns; the latest turn may use up to half the
+/// budget
+const EARLIER_MESSAGE_TOKENS: u32 = 250;
+
+/// Most images sent with a discovery request
+const MAX_IMAGES: usize = 3;
+
+/// Image types all discovery providers accept
+const IMAGE_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+const STOPWORDS: &[&str] = &[
+    "about", "after", "also", "been", "before", "being", "could", "does", "from", "have", "here", "into",
+    "just", "like", "more", "most", "much", "only", "other", "over", "really", "same", "should", "some",
+    "such", "than", "that", "their", "them", "then", "there", "these", "they", "this", "those", "very",
+    "want", "were", "what", "when", "where", "which", "while", "will", "with", "would", "your",
+];
+
+/// An image attachment sent with a discovery request
+#[derive(Debug, Clone)]
+pub struct DiscoveryImage {
+    /// Which message it came from, e.g. "Image attached to message #3 (chart.png)"
+    pub label: String,
+    pub mime_type: String,
+    /// Base64, without a data: prefix
+    pub data: String,
+}
+
+/// The conversation and images a discovery request is run against
+#[derive(Debug, Default)]
+pub struct DiscoveryContext {
+    pub conversation: String,
+    pub images: Vec<DiscoveryImage>,
+}
+
+struct Message {
+    /// 1-based position in the conversation
+    number: usize,
+    role: String,
+    text: String,
+    images: Vec<DiscoveryImage>,
+}
+
+fn code_block_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)```.*?(```|$)").unwrap())
+}
+
+pub fn message_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn keywords(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 4)
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Shorten a message to about `max_tokens`: code blocks become a
+/// placeholder, whitespace is collapsed and long text is cut at a sentence
+/// (or word) boundary
+pub fn condense(text: &str, max_tokens: u32) -> String {
+    let text = code_block_regex().replace_all(text, " [code block] ");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if estimate_tokens(&text) <= max_tokens {
+        return text;
+    }
+    let max_chars = max_tokens as usize * 4;
+    let cut: String = text.chars().take(max_chars).collect();
+    let end = cut
+        .rfind(". ")
+        .map(|i| i + 1)
+        .filter(|&i| i > cut.len() / 2)
+        .or_else(|| cut.rfind(' '))
+        .unwrap_or(cut.len());
+    format!("{} …", &cut[..end])
+}
+
+fn render(message: &Message, max_tokens: u32) -> String {
+    let mut text = format!(
+        "MESSAGE #{} ({}):\n{}",
+        message.number,
+        message.role.to_uppercase(),
+        condense(&message.text, max_tokens)
+    );
+    for image in &message.images {
+        text.push_str(&format!("\n[{}]", image.label));
+    }
+    text
+}
+
+/// Image attachments of a message that discovery providers accept
+fn message_images(message: &Value, number: usize) -> Vec<DiscoveryImage> {
+    message["attachments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|a| a["type"].as_str() == Some("image"))
+        .filter_map(|a| {
+            let mime_type = a["mimeType"].as_str().filter(|m| IMAGE_MIME_TYPES.contains(m))?;
+            let data = a["data"].as_str().filter(|d| !d.is_empty())?;
+            // Stored data may carry a data: URL prefix
+            let data = data.split_once(";base64,").map_or(data, |(_, d)| d);
+            Some(DiscoveryImage {
+                label: format!(
+                    "Image attached to message #{} ({})",
+                    number,
+                    a["name"].as_str().unwrap_or("image")
+                ),
+                mime_type: mime_type.to_string(),
+                data: data.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The conversation up to and including `turn_id`'s turn, grouped into
+/// turns (a user message and the replies that follow it)
+fn session_turns(messages: &[Value], turn_id: &str) -> Vec<Vec<Message>> {
+    let mut messages: Vec<&Value> = messages.iter().collect();
+    if let Some(last) = messages.iter().rposition(|m| m["turnId"].as_str() == Some(turn_id)) {
+        messages.truncate(last + 1);
+    }
+
+    let mut turns: Vec<Vec<Message>> = Vec::new();
+    for (index, message) in messages.into_iter().enumerate() {
+        let role = message["role"].as_str().unwrap_or("user").to_string();
+        let text = message_text(&message["content"]);
+        let images = message_images(message, index + 1);
+        if text.trim().is_empty() && images.is_empty() {
+            continue;
+        }
+        let message = Message {
+            number: index + 1,
+            role,
+            text,
+            images,
+        };
+        match turns.last_mut() {
+            Some(turn) if message.role != "user" => turn.push(message),
+            _ => turns.push(vec![message]),
+        }
+    }
+    turns
+}
+
+/// Discovery context for a session's messages, within `budget` tokens of text
+fn build_context(messages: &[Value], turn_id: &str, budget: u32) -> DiscoveryContext {
+    let turns = session_turns(messages, turn_id);
+    let Some((latest, earlier)) = turns.split_last() else {
+        return DiscoveryContext::default();
+    };
+
+    let latest_cap = budget / 2 / latest.len() as u32;
+    let latest_text: Vec<String> = latest.iter().map(|m| render(m, latest_cap)).collect();
+    let mut remaining = budget.saturating_sub(latest_text.iter().map(|t| estimate_tokens(t)).sum());
+
+    let latest_keywords = keywords(&latest.iter().map(|m| m.text.as_str()).collect::<Vec<_>>().join(" "));
+    let mut ranked: Vec<(usize, usize)> = earlier
+        .iter()
+        .enumerate()
+        .map(|(index, turn)| {
+            let words = keywords(&turn.iter().map(|m| m.text.as_str()).collect::<Vec<_>>().join(" "));
+            (index, words.intersection(&latest_keywords).count())
+        })
+        .collect();
+    ranked.sort_by(|(a, x), (b, y)| y.cmp(x).then(b.cmp(a)));
+
+    let mut selected: Vec<(usize, Vec<String>)> = Vec::new();
+    for (index, _) in ranked {
+        let text: Vec<String> = earlier[index].iter().map(|m| render(m, EARLIER_MESSAGE_TOKENS)).collect();
+        let tokens: u32 = text.iter().map(|t| estimate_tokens(t)).sum();
+        if tokens <= remaining {
+            remaining -= tokens;
+            selected.push((index, text));
+        }
+    }
+    selected.sort_by_key(|(index, _)| *index);
+    selected.push((earlier.len(), latest_text));
+
+    let mut images: Vec<DiscoveryImage> = selected
+        .iter()
+        .rev()
+        .flat_map(|(index, _)| turns[*index].iter().rev())
+        .flat_map(|m| m.images.iter().rev().cloned())
+        .take(MAX_IMAGES)
+        .collect();
+    images.reverse();
+
+    let mut sections = Vec::new();
+    let mut next_number = 1;
+    for (index, text) in selected {
+        let first = turns[index][0].number;
+        if first > next_number {
+            let skipped = first - next_number;
+            sections.push(format!("[{} earlier message{} omitted]", skipped, if skipped == 1 { "" } else { "s" }));
+        }
+        next_number = turns[index].last().map_or(first, |m| m.number) + 1;
+        sections.extend(text);
+    }
+    DiscoveryContext {
+        conversation: sections.join("\n\n"),
+        images,
+    }
+}
+
+/// Discovery context for `turn_id`, from the session's messages
+pub fn messages_context(messages: &[Value], turn_id: &str) -> DiscoveryContext {
+    build_context(messages, turn_id, CONTEXT_TOKEN_BUDGET)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_latest_turn_and_most_related_earlier_turns_with_their_images() {
+        let long = "Sourdough starters need feeding. ".repeat(200);
+        let messages = serde_json::json!([
+                {"role": "user", "turnId": "t1", "content": "How do I plan a garden for tomatoes?", "attachments": [
+                    {"type": "image", "name": "chart.png", "mimeType": "image/png", "data": "data:image/png;base64,AAAA"},
+                    {"type": "image", "name": "scan.tiff", "mimeType": "image/tiff", "data": "BBBB"}
+                ]},
+                {"role": "assistant", "turnId": "t1", "content": "Tomatoes need sun. ```\nplot = 4x8\n```"},
+                {"role": "user", "turnId": "t2", "content": "Unrelated: recommend a sourdough book"},
+                {"role": "assistant", "turnId": "t2", "content": long},
+                {"role": "user", "turnId": "t3", "content": "Which tomatoes grow best in a small garden?"},
+                {"role": "assistant", "turnId": "t3", "content": "Cherry tomatoes suit small gardens."},
+                {"role": "user", "turnId": "t4", "content": "Later question", "attachments": [
+                    {"type": "image", "name": "later.png", "mimeType": "image/png", "data": "CCCC"}
+                ]}
+        ]);
+        let messages = messages.as_array().unwrap();
+
+        let DiscoveryContext { conversation: context, images } = build_context(messages, "t3", 200);
+        assert!(context.starts_with("MESSAGE #1 (USER):\nHow do I plan a garden"));
+        assert!(context.contains("tomatoes?\n[Image attached to message #1 (chart.png)]"));
+        assert!(context.contains("Tomatoes need sun. [code block]"));
+        assert!(context.contains("[2 earlier messages omitted]\n\nMESSAGE #5 (USER)"));
+        assert!(context.ends_with("Cherry tomatoes suit small gardens."));
+        assert!(!context.contains("Later question"));
+        assert_eq!(images.len(), 1);
+        assert_eq!((images[0].mime_type.as_str(), images[0].data.as_str()), ("image/png", "AAAA"));
+
+        let roomy = build_context(messages, "t3", 3000).conversation;
+        assert!(roomy.contains("MESSAGE #4 (ASSISTANT):\nSourdough starters need feeding."));
+        assert!(roomy.contains(" …\n\nMESSAGE #5"));
+        assert_eq!(condense("One. Two three four five six seven", 3), "One. Two …");
+    }
+}
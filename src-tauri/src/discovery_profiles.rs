@@ -0,0 +1,267 @@
+//! Discovery profiles
+//!
+//! A profile is a named, saved discovery setup: the topic to watch for,
+//! preferred item categories, domains to restrict or exclude sources to, a
+//! model and how often to run. `discover_resources` takes a `profile_id` and
+//! builds the system prompt from the profile, so the frontend no longer sends
+//! prompt text on every run. Domain filters are also enforced on the items
+//! that come back, since models don't always follow them.
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::new_session_id;
+use crate::discovery::DiscoveryItem;
+use crate::settings;
+
+const DISCOVERY_PROFILES_SETTING_KEY: &str = "discovery_profiles";
+
+/// Categories the discovery prompt asks items to use
+const CATEGORIES: &[&str] = &["tool", "article", "video", "paper", "discussion", "other"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveryProfile {
+    /// Assigned on first save when empty
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// What to look for, in the user's words
+    pub topic: String,
+    /// Categories to favour (see `CATEGORIES`); empty means no preference
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Only keep sources from these domains (and their subdomains)
+    #[serde(default)]
+    pub include_domains: Vec<String>,
+    /// Never keep sources from these domains (and their subdomains)
+    #[serde(default)]
+    pub exclude_domains: Vec<String>,
+    /// Falls back to the model the request names, then the discovery default
+    pub model: Option<String>,
+    /// Run discovery every this many chat turns; every turn when unset
+    pub cadence_turns: Option<u32>,
+}
+
+/// Source domain restrictions applied to discovered items
+#[derive(Debug, Clone, Default)]
+pub struct DomainFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+/// `host` is `domain` or one of its subdomains
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+/// Lowercased host without scheme, path or `www.` (`https://www.Example.com/a`
+/// → `example.com`)
+fn normalize_domain(domain: &str) -> String {
+    let domain = domain.trim().to_lowercase();
+    let domain = domain.rsplit("://").next().unwrap_or_default();
+    let host = domain.split(['/', '?', '#']).next().unwrap_or_default();
+    host.trim_start_matches("www.").to_string()
+}
+
+impl DomainFilter {
+    /// Whether an item's source passes the filter. Items without a source
+    /// (from the model's own knowledge) always pass.
+    pub fn allows(&self, item: &DiscoveryItem) -> bool {
+        let source = if item.source_domain.trim().is_empty() {
+            &item.source_url
+        } else {
+            &item.source_domain
+        };
+        let host = normalize_domain(source);
+        if host.is_empty() {
+            return true;
+        }
+        if self.exclude.iter().any(|d| domain_matches(&host, d)) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(|d| domain_matches(&host, d))
+    }
+}
+
+impl DiscoveryProfile {
+    /// Whether discovery runs after the conversation's `user_turns`th turn:
+    /// every `cadence_turns`th turn, or every turn without a cadence
+    pub fn is_due(&self, user_turns: usize) -> bool {
+        match self.cadence_turns {
+            Some(cadence) if cadence > 1 => user_turns.is_multiple_of(cadence as usize),
+            _ => true,
+        }
+    }
+
+    pub fn domain_filter(&self) -> DomainFilter {
+        DomainFilter {
+            include: self.include_domains.iter().map(|d| normalize_domain(d)).collect(),
+            exclude: self.exclude_domains.iter().map(|d| normalize_domain(d)).collect(),
+        }
+    }
+
+    /// The discovery system prompt for this profile
+    pub fn system_prompt(&self) -> String {
+        let mut prompt = format!(
+            "You are a model \"looking over the shoulder\" of a conversation between a user and an AI model. \
+             The user has asked you to watch for this:\n\n{}\n\n\
+             Based on the conversation, search the web for resources, tools, discussions and information \
+             related to that topic that the user would find novel and useful. Skip items already mentioned \
+             in the conversation and the obvious first-page results everyone knows.",
+            self.topic.trim()
+        );
+        if !self.categories.is_empty() {
+            prompt.push_str(&format!("\n\nPrefer items of these kinds: {}.", self.categories.join(", ")));
+        }
+        if !self.include_domains.is_empty() {
+            prompt.push_str(&format!("\n\nOnly use sources from: {}.", self.include_domains.join(", ")));
+        }
+        if !self.exclude_domains.is_empty() {
+            prompt.push_str(&format!("\n\nNever use sources from: {}.", self.exclude_domains.join(", ")));
+        }
+        prompt.push_str(&format!(
+            "\n\nIf you didn't come up with anything worth showing, return an empty items array. \
+             If there is no specific URL or source, leave sourceUrl and sourceDomain as empty strings.\n\n\
+             Return your response as valid JSON:\n\
+             {{\n  \"items\": [\n    {{\n\
+             \x20     \"title\": \"Short descriptive title\",\n\
+             \x20     \"oneLiner\": \"Compelling one-line hook\",\n\
+             \x20     \"fullSummary\": \"2-3 sentence explanation\",\n\
+             \x20     \"relevanceExplanation\": \"How this connects to the conversation\",\n\
+             \x20     \"sourceUrl\": \"https://...\",\n\
+             \x20     \"sourceDomain\": \"example.com\",\n\
+             \x20     \"category\": \"{}\",\n\
+             \x20     \"relevanceScore\": 85\n    }}\n  ]\n}}",
+            CATEGORIES.join("|")
+        ));
+        prompt
+    }
+}
+
+fn load_profiles(app: &tauri::AppHandle) -> Vec<DiscoveryProfile> {
+    settings::get_setting(app, DISCOVERY_PROFILES_SETTING_KEY).unwrap_or_default()
+}
+
+fn save_profiles(app: &tauri::AppHandle, profiles: &[DiscoveryProfile]) -> Result<(), String> {
+    settings::set_setting(app, DISCOVERY_PROFILES_SETTING_KEY, &profiles)
+}
+
+pub fn get_profile(app: &tauri::AppHandle, profile_id: &str) -> Result<DiscoveryProfile, String> {
+    load_profiles(app)
+        .into_iter()
+        .find(|p| p.id == profile_id)
+        .ok_or_else(|| format!("Discovery profile not found: {}", profile_id))
+}
+
+#[tauri::command]
+pub fn list_discovery_profiles(app: tauri::AppHandle) -> Vec<DiscoveryProfile> {
+    load_profiles(&app)
+}
+
+/// Create or update a discovery profile (matched by id). Returns the saved
+/// profile, with domains normalized.
+#[tauri::command]
+pub fn save_discovery_profile(app: tauri::AppHandle, profile: DiscoveryProfile) -> Result<DiscoveryProfile, String> {
+    let mut profile = profile;
+    if profile.name.trim().is_empty() {
+        return Err("Discovery profile name cannot be empty".to_string());
+    }
+    if profile.topic.trim().is_empty() {
+        return Err("Discovery profile topic cannot be empty".to_string());
+    }
+    if let Some(category) = profile.categories.iter().find(|c| !CATEGORIES.contains(&c.as_str())) {
+        return Err(format!("Invalid discovery category: {}", category));
+    }
+    if profile.cadence_turns == Some(0) {
+        return Err("Discovery cadence must be at least one turn".to_string());
+    }
+    for domains in [&mut profile.include_domains, &mut profile.exclude_domains] {
+        *domains = domains
+            .iter()
+            .map(|d| normalize_domain(d))
+            .filter(|d| !d.is_empty())
+            .collect();
+    }
+    if profile.id.is_empty() {
+        profile.id = new_session_id();
+    }
+
+    let mut profiles = load_profiles(&app);
+    match profiles.iter_mut().find(|p| p.id == profile.id) {
+        Some(existing) => *existing = profile.clone(),
+        None => profiles.push(profile.clone()),
+    }
+    save_profiles(&app, &profiles)?;
+
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn delete_discovery_profile(app: tauri::AppHandle, profile_id: String) -> Result<(), String> {
+    let mut profiles = load_profiles(&app);
+    profiles.retain(|p| p.id != profile_id);
+    save_profiles(&app, &profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(source_url: &str, source_domain: &str) -> DiscoveryItem {
+        DiscoveryItem {
+            title: "t".to_string(),
+            one_liner: String::new(),
+            full_summary: String::new(),
+            relevance_explanation: String::new(),
+            source_url: source_url.to_string(),
+            source_domain: source_domain.to_string(),
+            category: "article".to_string(),
+            relevance_score: 80,
+        }
+    }
+
+    #[test]
+    fn domain_filter_matches_subdomains_and_passes_sourceless_items() {
+        let profile = DiscoveryProfile {
+            id: "p".to_string(),
+            name: "Rust".to_string(),
+            topic: "Async Rust".to_string(),
+            categories: vec!["paper".to_string()],
+            include_domains: vec!["https://www.GitHub.com/".to_string(), "rust-lang.org".to_string()],
+            exclude_domains: vec!["gist.github.com".to_string()],
+            model: None,
+            cadence_turns: None,
+        };
+        let filter = profile.domain_filter();
+
+        assert!(filter.allows(&item("https://github.com/tokio-rs/tokio", "")));
+        assert!(filter.allows(&item("", "blog.rust-lang.org")));
+        assert!(!filter.allows(&item("https://gist.github.com/x", "gist.github.com")));
+        assert!(!filter.allows(&item("https://notgithub.com/", "")));
+        assert!(filter.allows(&item("", "")));
+
+        let prompt = profile.system_prompt();
+        assert!(prompt.contains("Async Rust"));
+        assert!(prompt.contains("Prefer items of these kinds: paper."));
+        assert!(prompt.contains("\"category\": \"tool|article|video|paper|discussion|other\""));
+    }
+
+    #[test]
+    fn cadence_runs_every_nth_turn() {
+        let mut profile = DiscoveryProfile {
+            id: "p".to_string(),
+            name: "Rust".to_string(),
+            topic: "Async Rust".to_string(),
+            categories: Vec::new(),
+            include_domains: Vec::new(),
+            exclude_domains: Vec::new(),
+            model: None,
+            cadence_turns: None,
+        };
+        assert!(profile.is_due(1));
+
+        profile.cadence_turns = Some(3);
+        let due: Vec<usize> = (1..=7).filter(|turns| profile.is_due(*turns)).collect();
+        assert_eq!(due, vec![3, 6]);
+    }
+}
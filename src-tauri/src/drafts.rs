@@ -0,0 +1,152 @@
+//! Drafts of unsent messages
+//!
+//! The frontend calls `save_draft` as the user types. Drafts are kept in memory
+//! immediately and written to `drafts.json` once typing pauses, so half-written
+//! prompts (with their attachments) survive restarts and crashes without a
+//! disk write per keystroke. Saving an empty draft deletes it.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+const DRAFTS_STORE_PATH: &str = "drafts.json";
+
+/// How long typing must pause before a draft is written to disk
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(750);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Draft {
+    pub content: String,
+    /// Attachments in the frontend's `Attachment` shape (data inline as base64)
+    #[serde(default)]
+    pub attachments: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+impl Draft {
+    fn is_empty(&self) -> bool {
+        self.content.trim().is_empty() && self.attachments.is_empty()
+    }
+}
+
+/// Drafts waiting for their debounced write, with a per-session generation so
+/// only the latest save is flushed
+#[derive(Default)]
+pub struct DraftState {
+    pending: Arc<Mutex<HashMap<String, (u64, Draft)>>>,
+}
+
+impl DraftState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn write_draft(app: &tauri::AppHandle, session_id: &str, draft: &Draft) -> Result<(), String> {
+    let store = app
+        .store(DRAFTS_STORE_PATH)
+        .map_err(|e| e.to_string())?;
+
+    if draft.is_empty() {
+        let _ = store.delete(session_id);
+    } else {
+        let value = serde_json::to_value(draft).map_err(|e| e.to_string())?;
+        store.set(session_id, value);
+    }
+    store.save().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Remove a session's draft (called when the session is deleted)
+pub fn delete_session_draft(app: &tauri::AppHandle, session_id: &str) {
+    app.state::<DraftState>().pending.lock().remove(session_id);
+    if let Err(e) = write_draft(app, session_id, &Draft::default()) {
+        eprintln!("Failed to delete draft for {}: {}", session_id, e);
+    }
+}
+
+/// Remove all drafts (called when the session store is cleared)
+pub fn delete_all_drafts(app: &tauri::AppHandle) {
+    app.state::<DraftState>().pending.lock().clear();
+    if let Ok(store) = app.store(DRAFTS_STORE_PATH) {
+        store.clear();
+        if let Err(e) = store.save() {
+            eprintln!("Failed to clear drafts: {}", e);
+        }
+    }
+}
+
+/// Save the draft for a session (or a not-yet-saved chat's key). The write to
+/// disk is debounced; an empty draft deletes the stored one.
+#[tauri::command]
+pub fn save_draft(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, DraftState>,
+    session_id: String,
+    content: String,
+    attachments: Option<Vec<serde_json::Value>>,
+) -> Result<(), String> {
+    if session_id.is_empty() {
+        return Err("Session id cannot be empty".to_string());
+    }
+
+    let draft = Draft {
+        content,
+        attachments: attachments.unwrap_or_default(),
+        updated_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let generation = {
+        let mut pending = state.pending.lock();
+        let generation = pending.get(&session_id).map(|(g, _)| g + 1).unwrap_or(0);
+        pending.insert(session_id.clone(), (generation, draft));
+        generation
+    };
+
+    let pending = state.pending.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_DEBOUNCE).await;
+
+        // Only the most recent save for this session is written
+        let draft = {
+            let mut pending = pending.lock();
+            match pending.get(&session_id) {
+                Some((g, _)) if *g == generation => pending.remove(&session_id).map(|(_, d)| d),
+                _ => None,
+            }
+        };
+        if let Some(draft) = draft {
+            if let Err(e) = write_draft(&app, &session_id, &draft) {
+                eprintln!("Failed to save draft for {}: {}", session_id, e);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// The draft for a session, including one still waiting to be written
+#[tauri::command]
+pub fn get_draft(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, DraftState>,
+    session_id: String,
+) -> Result<Option<Draft>, String> {
+    if let Some((_, draft)) = state.pending.lock().get(&session_id) {
+        return Ok(Some(draft.clone()).filter(|d| !d.is_empty()));
+    }
+
+    let store = app
+        .store(DRAFTS_STORE_PATH)
+        .map_err(|e| e.to_string())?;
+    Ok(store
+        .get(&session_id)
+        .and_then(|value| serde_json::from_value(value).ok()))
+}
@@ -0,0 +1,227 @@
+//! Structured errors for provider requests
+//!
+//! `SidestreamError` classifies what went wrong talking to a model provider,
+//! so the frontend can show an actionable message (check your key, wait N
+//! seconds, trim the conversation) instead of raw API text; `SessionBusy`
+//! rejects a send while the session already has a turn streaming (see
+//! `send_locks`), `SessionLocked` rejects changes to a read-only session
+//! (see `session_lock`), and `InvalidRequest` stops a request that breaks a
+//! provider rule before it is sent (see `request_validation`). It
+//! serializes as
+//! `{"kind": "rate_limited", "retryAfter": 30, "message": "..."}`.
+//!
+//! Errors from the rest of the app stay plain strings: `From<String>` wraps
+//! them as a `ProviderError` without a code, and `From<SidestreamError> for
+//! String` lets callers that return `Result<_, String>` keep using `?`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Longest raw response body kept in a message
+const MAX_BODY_CHARS: usize = 500;
+
+/// Phrases providers use when a request exceeds the model's context window
+const CONTEXT_TOO_LONG_PATTERNS: &[&str] = &[
+    "prompt is too long",
+    "context_length_exceeded",
+    "maximum context length",
+    "context window",
+    "exceeds the maximum number of tokens",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum SidestreamError {
+    /// The provider rejected the API key
+    AuthError { message: String },
+    /// Too many requests (or out of quota); `retry_after` is in seconds when
+    /// the provider said
+    RateLimited { retry_after: Option<u64>, message: String },
+    /// The conversation doesn't fit the model's context window
+    ContextTooLong { message: String },
+    /// The provider couldn't be reached or the connection dropped
+    NetworkError { message: String },
+    /// Anything else; `code` is the HTTP status when there was one
+    ProviderError { code: Option<u16>, message: String },
+    /// The session already has a turn in flight; `turn_id` is that turn
+    SessionBusy { turn_id: String, message: String },
+    /// The session is locked (read-only) until it is unlocked
+    SessionLocked { session_id: String, message: String },
+    /// The request breaks a provider limit or rule and wasn't sent (see
+    /// `request_validation`)
+    InvalidRequest { message: String },
+}
+
+impl SidestreamError {
+    /// Classify a failed HTTP response from its status, `retry-after` header
+    /// and body
+    pub fn from_status(status: u16, retry_after: Option<&str>, body: &str) -> Self {
+        let message = error_message(body);
+        match status {
+            401 | 403 => Self::AuthError { message },
+            400 if body.contains("API_KEY_INVALID") => Self::AuthError { message },
+            429 => Self::RateLimited {
+                retry_after: retry_after.and_then(|s| s.trim().parse().ok()),
+                message,
+            },
+            400 | 413 if is_context_too_long(body) => Self::ContextTooLong { message },
+            _ => Self::ProviderError {
+                code: Some(status),
+                message,
+            },
+        }
+    }
+
+    /// Classify a failed response, reading its body
+    pub async fn from_response(response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = response.text().await.unwrap_or_default();
+        Self::from_status(status, retry_after.as_deref(), &body)
+    }
+
+    /// Classify an error reported inside a stream, which has no status
+    pub fn from_stream_message(message: String) -> Self {
+        let lower = message.to_lowercase();
+        if is_context_too_long(&lower) {
+            Self::ContextTooLong { message }
+        } else if lower.contains("rate limit") || lower.contains("rate_limit") {
+            Self::RateLimited {
+                retry_after: None,
+                message,
+            }
+        } else {
+            Self::ProviderError { code: None, message }
+        }
+    }
+}
+
+fn is_context_too_long(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    CONTEXT_TOO_LONG_PATTERNS.iter().any(|p| lower.contains(p))
+}
+
+/// The message in a provider's JSON error body (`error.message` for every
+/// provider; Gemini's streaming endpoint wraps it in an array), or the start
+/// of the raw body
+fn error_message(body: &str) -> String {
+    let parsed: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    let error = parsed.get(0).unwrap_or(&parsed);
+    match error["error"]["message"].as_str() {
+        Some(message) => message.to_string(),
+        None => body.trim().chars().take(MAX_BODY_CHARS).collect(),
+    }
+}
+
+impl fmt::Display for SidestreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AuthError { message } => write!(f, "Authentication failed: {}", message),
+            Self::RateLimited {
+                retry_after: Some(seconds),
+                message,
+            } => write!(f, "Rate limited (retry after {}s): {}", seconds, message),
+            Self::RateLimited { message, .. } => write!(f, "Rate limited: {}", message),
+            Self::ContextTooLong { message } => write!(f, "Context too long: {}", message),
+            Self::NetworkError { message } => write!(f, "Network error: {}", message),
+            Self::ProviderError {
+                code: Some(code),
+                message,
+            } => write!(f, "API error ({}): {}", code, message),
+            Self::ProviderError { code: None, message } => f.write_str(message),
+            Self::SessionBusy { message, .. } => write!(f, "Session busy: {}", message),
+            Self::SessionLocked { message, .. } => write!(f, "Session locked: {}", message),
+            Self::InvalidRequest { message } => write!(f, "Invalid request: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SidestreamError {}
+
+impl From<String> for SidestreamError {
+    fn from(message: String) -> Self {
+        Self::ProviderError { code: None, message }
+    }
+}
+
+impl From<&str> for SidestreamError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+impl From<reqwest::Error> for SidestreamError {
+    fn from(error: reqwest::Error) -> Self {
+        match error.status() {
+            Some(status) => Self::ProviderError {
+                code: Some(status.as_u16()),
+                message: error.to_string(),
+            },
+            None if error.is_decode() => Self::ProviderError {
+                code: None,
+                message: format!("Failed to parse response: {}", error),
+            },
+            None => Self::NetworkError {
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl From<SidestreamError> for String {
+    fn from(error: SidestreamError) -> Self {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_provider_errors() {
+        assert_eq!(
+            SidestreamError::from_status(401, None, r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#),
+            SidestreamError::AuthError {
+                message: "invalid x-api-key".to_string()
+            }
+        );
+        assert_eq!(
+            SidestreamError::from_status(429, Some("30"), r#"{"error":{"message":"Slow down"}}"#),
+            SidestreamError::RateLimited {
+                retry_after: Some(30),
+                message: "Slow down".to_string()
+            }
+        );
+        assert!(matches!(
+            SidestreamError::from_status(400, None, r#"{"error":{"message":"prompt is too long: 210000 tokens > 200000 maximum"}}"#),
+            SidestreamError::ContextTooLong { .. }
+        ));
+        assert!(matches!(
+            SidestreamError::from_status(400, None, r#"[{"error":{"code":400,"message":"API key not valid.","status":"INVALID_ARGUMENT","details":[{"reason":"API_KEY_INVALID"}]}}]"#),
+            SidestreamError::AuthError { .. }
+        ));
+        assert_eq!(
+            SidestreamError::from_status(529, None, "overloaded").to_string(),
+            "API error (529): overloaded"
+        );
+
+        let json = serde_json::to_value(SidestreamError::RateLimited {
+            retry_after: None,
+            message: "x".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json, serde_json::json!({"kind": "rate_limited", "retryAfter": null, "message": "x"}));
+
+        let json = serde_json::to_value(SidestreamError::SessionLocked {
+            session_id: "s1".to_string(),
+            message: "x".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json, serde_json::json!({"kind": "session_locked", "sessionId": "s1", "message": "x"}));
+    }
+}
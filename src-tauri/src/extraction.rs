@@ -0,0 +1,438 @@
+//! Text extraction from attachments
+//!
+//! Converts non-text attachments into plain text before they are sent, so
+//! screenshots don't cost vision tokens on every turn and their contents can be
+//! searched. Results are cached in the attachment cache by content hash.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use std::fs;
+
+use crate::attachments::{attachment_cache_dir, content_key, decode_base64_payload};
+use crate::commands::{get_api_key_async, has_api_key_async};
+use crate::providers::anthropic::{extract_response_text, AnthropicClient};
+use crate::providers::gemini::GeminiClient;
+use crate::providers::openai::{extract_output_text, OpenAIClient};
+
+/// Small, fast vision models used for OCR on each provider
+const OCR_GEMINI_MODEL: &str = "gemini-3.5-flash";
+const OCR_OPENAI_MODEL: &str = "gpt-5.4-mini";
+const OCR_ANTHROPIC_MODEL: &str = "claude-haiku-4-5-20251001";
+
+const OCR_INSTRUCTION: &str = "Transcribe all text visible in this image exactly as written, preserving line breaks, lists and table layout (use Markdown tables for tables). Output ONLY the transcribed text with no commentary. If the image contains no text, output nothing.";
+
+/// Pick the provider to use for a vision call: the requested one, or the first
+/// configured provider (Gemini is preferred as the cheapest for OCR)
+async fn pick_vision_provider(
+    app: &tauri::AppHandle,
+    requested: Option<String>,
+) -> Result<String, String> {
+    if let Some(provider) = requested {
+        return Ok(provider);
+    }
+    for provider in ["google", "openai", "anthropic"] {
+        if has_api_key_async(app, provider).await {
+            return Ok(provider.to_string());
+        }
+    }
+    Err("No API key configured for text extraction".to_string())
+}
+
+/// Extract the mime type from a data URL ("data:image/png;base64,...")
+fn data_url_mime_type(data: &str) -> Option<String> {
+    data.strip_prefix("data:")?
+        .split([';', ','])
+        .next()
+        .filter(|m| !m.is_empty())
+        .map(|m| m.to_string())
+}
+
+async fn ocr_with_provider(
+    app: &tauri::AppHandle,
+    provider: &str,
+    image_base64: &str,
+    mime_type: &str,
+) -> Result<String, String> {
+    match provider {
+        "google" => {
+            let client = GeminiClient::new(get_api_key_async(app, "google").await?);
+            let body = serde_json::json!({
+                "contents": [{
+                    "role": "user",
+                    "parts": [
+                        {"inlineData": {"mimeType": mime_type, "data": image_base64}},
+                        {"text": OCR_INSTRUCTION}
+                    ]
+                }]
+            });
+            Ok(client.send_request(OCR_GEMINI_MODEL, &body).await?)
+        }
+        "openai" => {
+            let client = OpenAIClient::new(get_api_key_async(app, "openai").await?);
+            let body = serde_json::json!({
+                "model": OCR_OPENAI_MODEL,
+                "input": [{
+                    "role": "user",
+                    "content": [
+                        {"type": "input_image", "image_url": format!("data:{};base64,{}", mime_type, image_base64)},
+                        {"type": "input_text", "text": OCR_INSTRUCTION}
+                    ]
+                }]
+            });
+            let response = client.send_request(&body).await?;
+            Ok(extract_output_text(&response))
+        }
+        "anthropic" => {
+            let client = AnthropicClient::new(get_api_key_async(app, "anthropic").await?);
+            let body = serde_json::json!({
+                "model": OCR_ANTHROPIC_MODEL,
+                "max_tokens": 4096,
+                "messages": [{
+                    "role": "user",
+                    "content": [
+                        {"type": "image", "source": {"type": "base64", "media_type": mime_type, "data": image_base64}},
+                        {"type": "text", "text": OCR_INSTRUCTION}
+                    ]
+                }]
+            });
+            let response = client.send_request(&body).await?;
+            Ok(extract_response_text(&response))
+        }
+        _ => Err(format!("Unsupported provider for text extraction: {}", provider)),
+    }
+}
+
+/// Extract the text shown in an image (screenshot, photo of a document, ...)
+/// using a provider vision model. Accepts raw base64 or a data URL.
+#[tauri::command]
+pub async fn extract_text_from_image(
+    app: tauri::AppHandle,
+    data: String,
+    mime_type: Option<String>,
+    provider: Option<String>,
+) -> Result<String, String> {
+    let image_bytes = decode_base64_payload(&data)?;
+    let key = content_key(&image_bytes);
+
+    let cache_dir = attachment_cache_dir(&app)?.join("ocr");
+    let cache_path = cache_dir.join(format!("{}.txt", key));
+    if let Ok(cached) = fs::read_to_string(&cache_path) {
+        return Ok(cached);
+    }
+
+    let mime_type = mime_type
+        .or_else(|| data_url_mime_type(&data))
+        .unwrap_or_else(|| "image/png".to_string());
+    let provider = pick_vision_provider(&app, provider).await?;
+
+    let text = ocr_with_provider(&app, &provider, &BASE64.encode(&image_bytes), &mime_type)
+        .await?
+        .trim()
+        .to_string();
+
+    if fs::create_dir_all(&cache_dir).is_ok() {
+        if let Err(e) = fs::write(&cache_path, &text) {
+            eprintln!("Failed to cache extracted text {}: {}", key, e);
+        }
+    }
+
+    Ok(text)
+}
+
+// ============================================================================
+// Office Documents
+// ============================================================================
+
+/// Rows per sheet included when converting a spreadsheet; beyond this the
+/// sheet is truncated with a note so one huge workbook can't blow the context
+const MAX_SPREADSHEET_ROWS: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OfficeFormat {
+    Spreadsheet,
+    WordDocument,
+}
+
+/// Identify office formats that providers reject as document blocks
+fn office_format(mime_type: &str, filename: Option<&str>) -> Option<OfficeFormat> {
+    match mime_type {
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+        | "application/vnd.ms-excel"
+        | "application/vnd.ms-excel.sheet.macroEnabled.12"
+        | "application/vnd.oasis.opendocument.spreadsheet" => return Some(OfficeFormat::Spreadsheet),
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
+            return Some(OfficeFormat::WordDocument)
+        }
+        _ => {}
+    }
+
+    // Some pickers report office files as application/octet-stream; fall back to the extension
+    let extension = filename?.rsplit_once('.')?.1.to_lowercase();
+    match extension.as_str() {
+        "xlsx" | "xlsm" | "xls" | "ods" => Some(OfficeFormat::Spreadsheet),
+        "docx" => Some(OfficeFormat::WordDocument),
+        _ => None,
+    }
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Convert every sheet of a workbook (XLSX, XLS, ODS) to CSV sections
+fn spreadsheet_to_text(bytes: &[u8]) -> Result<String, String> {
+    use calamine::Reader;
+
+    let mut workbook = calamine::open_workbook_auto_from_rs(std::io::Cursor::new(bytes))
+        .map_err(|e| format!("Failed to open spreadsheet: {}", e))?;
+
+    let mut output = String::new();
+    for name in workbook.sheet_names() {
+        let range = match workbook.worksheet_range(&name) {
+            Ok(range) => range,
+            Err(e) => {
+                output.push_str(&format!("## Sheet: {}\n(could not be read: {})\n\n", name, e));
+                continue;
+            }
+        };
+
+        output.push_str(&format!("## Sheet: {}\n```csv\n", name));
+        let total_rows = range.height();
+        for row in range.rows().take(MAX_SPREADSHEET_ROWS) {
+            let line = row
+                .iter()
+                .map(|cell| csv_field(&cell.to_string()))
+                .collect::<Vec<_>>()
+                .join(",");
+            output.push_str(&line);
+            output.push('\n');
+        }
+        output.push_str("```\n");
+        if total_rows > MAX_SPREADSHEET_ROWS {
+            output.push_str(&format!(
+                "(Truncated: showing {} of {} rows)\n",
+                MAX_SPREADSHEET_ROWS, total_rows
+            ));
+        }
+        output.push('\n');
+    }
+
+    Ok(output.trim_end().to_string())
+}
+
+fn docx_paragraph_text(paragraph: &docx_rs::Paragraph) -> String {
+    fn run_text(run: &docx_rs::Run, out: &mut String) {
+        for child in &run.children {
+            match child {
+                docx_rs::RunChild::Text(t) => out.push_str(&t.text),
+                docx_rs::RunChild::Tab(_) => out.push('\t'),
+                docx_rs::RunChild::Break(_) => out.push('\n'),
+                _ => {}
+            }
+        }
+    }
+
+    fn children_text(children: &[docx_rs::ParagraphChild], out: &mut String) {
+        for child in children {
+            match child {
+                docx_rs::ParagraphChild::Run(run) => run_text(run, out),
+                docx_rs::ParagraphChild::Hyperlink(link) => children_text(&link.children, out),
+                docx_rs::ParagraphChild::Insert(insert) => {
+                    for c in &insert.children {
+                        if let docx_rs::InsertChild::Run(run) = c {
+                            run_text(run, out);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut text = String::new();
+    children_text(&paragraph.children, &mut text);
+    text
+}
+
+/// Render a DOCX table as Markdown-style rows ("a | b | c")
+fn docx_table_text(table: &docx_rs::Table) -> String {
+    table
+        .rows
+        .iter()
+        .map(|docx_rs::TableChild::TableRow(row)| {
+            let cells: Vec<String> = row
+                .cells
+                .iter()
+                .map(|docx_rs::TableRowChild::TableCell(cell)| {
+                    cell.children
+                        .iter()
+                        .filter_map(|c| match c {
+                            docx_rs::TableCellContent::Paragraph(p) => Some(docx_paragraph_text(p)),
+                            docx_rs::TableCellContent::Table(t) => Some(docx_table_text(t)),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect();
+            format!("| {} |", cells.join(" | "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Extract the body text of a DOCX, keeping paragraph breaks and tables
+fn docx_to_text(bytes: &[u8]) -> Result<String, String> {
+    let docx = docx_rs::read_docx(bytes).map_err(|e| format!("Failed to read DOCX: {}", e))?;
+
+    let blocks: Vec<String> = docx
+        .document
+        .children
+        .iter()
+        .filter_map(|child| match child {
+            docx_rs::DocumentChild::Paragraph(p) => Some(docx_paragraph_text(p)),
+            docx_rs::DocumentChild::Table(t) => Some(docx_table_text(t)),
+            _ => None,
+        })
+        .collect();
+
+    Ok(blocks.join("\n").trim().to_string())
+}
+
+/// Convert an office document block into a text block with a note explaining
+/// the conversion. Conversion failures become a text note too, so one bad
+/// attachment doesn't fail the whole request at the API.
+fn convert_office_block(block: &serde_json::Value, format: OfficeFormat) -> serde_json::Value {
+    let filename = block["filename"].as_str().unwrap_or("attachment");
+    let data = block["source"]["data"].as_str().unwrap_or("");
+
+    let (kind, converted) = match format {
+        OfficeFormat::Spreadsheet => (
+            "spreadsheet",
+            decode_base64_payload(data).and_then(|bytes| spreadsheet_to_text(&bytes)),
+        ),
+        OfficeFormat::WordDocument => (
+            "Word document",
+            decode_base64_payload(data).and_then(|bytes| docx_to_text(&bytes)),
+        ),
+    };
+
+    let text = match converted {
+        Ok(text) => format!(
+            "[Attached {} \"{}\", converted to text because this file type can't be sent as a document]\n\n{}",
+            kind, filename, text
+        ),
+        Err(e) => format!(
+            "[Attached {} \"{}\" could not be converted to text: {}]",
+            kind, filename, e
+        ),
+    };
+
+    serde_json::json!({"type": "text", "text": text})
+}
+
+/// Extract the text of an office file by its filename extension.
+/// Returns None when the file isn't a supported office format.
+pub fn office_file_to_text(bytes: &[u8], filename: &str) -> Option<Result<String, String>> {
+    match office_format("", Some(filename))? {
+        OfficeFormat::Spreadsheet => Some(spreadsheet_to_text(bytes)),
+        OfficeFormat::WordDocument => Some(docx_to_text(bytes)),
+    }
+}
+
+/// Replace XLSX/XLS/ODS/DOCX document blocks in a message's content with
+/// extracted text blocks. Other content is returned unchanged.
+pub fn convert_office_documents(content: &serde_json::Value) -> serde_json::Value {
+    let Some(blocks) = content.as_array() else {
+        return content.clone();
+    };
+
+    let converted: Vec<serde_json::Value> = blocks
+        .iter()
+        .map(|block| {
+            let is_document = matches!(block["type"].as_str(), Some("document") | Some("file"));
+            let format = block["source"]["media_type"]
+                .as_str()
+                .filter(|_| is_document)
+                .and_then(|mime| office_format(mime, block["filename"].as_str()));
+            match format {
+                Some(format) => convert_office_block(block, format),
+                None => block.clone(),
+            }
+        })
+        .collect();
+
+    serde_json::Value::Array(converted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn docx_paragraphs_and_tables_are_extracted() {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        docx_rs::Docx::new()
+            .add_paragraph(docx_rs::Paragraph::new().add_run(docx_rs::Run::new().add_text("Quarterly report")))
+            .add_table(docx_rs::Table::new(vec![docx_rs::TableRow::new(vec![
+                docx_rs::TableCell::new().add_paragraph(docx_rs::Paragraph::new().add_run(docx_rs::Run::new().add_text("Q1"))),
+                docx_rs::TableCell::new().add_paragraph(docx_rs::Paragraph::new().add_run(docx_rs::Run::new().add_text("42"))),
+            ])]))
+            .build()
+            .pack(&mut buf)
+            .unwrap();
+
+        assert_eq!(docx_to_text(buf.get_ref()).unwrap(), "Quarterly report\n| Q1 | 42 |");
+    }
+
+    #[test]
+    fn office_document_blocks_from_the_frontend_become_text() {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        docx_rs::Docx::new()
+            .add_paragraph(docx_rs::Paragraph::new().add_run(docx_rs::Run::new().add_text("Quarterly report")))
+            .build()
+            .pack(&mut buf)
+            .unwrap();
+
+        // Shape sent by formatMessageContent (useChat.ts): attachments first, then the user's text
+        let content = serde_json::json!([
+            {
+                "type": "document",
+                "filename": "report.docx",
+                "source": {
+                    "type": "base64",
+                    "media_type": "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+                    "data": BASE64.encode(buf.get_ref()),
+                }
+            },
+            {"type": "text", "text": "Summarize this"}
+        ]);
+
+        let converted = convert_office_documents(&content);
+        assert_eq!(converted[0]["type"], "text");
+        let text = converted[0]["text"].as_str().unwrap();
+        assert!(text.starts_with("[Attached Word document \"report.docx\""));
+        assert!(text.ends_with("Quarterly report"));
+        assert_eq!(converted[1], content[1]);
+    }
+
+    #[test]
+    fn non_office_blocks_are_left_alone() {
+        let content = serde_json::json!([
+            {"type": "text", "text": "hi"},
+            {"type": "document", "source": {"type": "base64", "media_type": "application/pdf", "data": "JVBERi0="}}
+        ]);
+        assert_eq!(convert_office_documents(&content), content);
+    }
+}
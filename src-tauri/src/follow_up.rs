@@ -0,0 +1,271 @@
+//! Follow-up digests
+//!
+//! Sessions can be tagged "follow up". When the weekly digest is enabled, a
+//! background task reviews the tagged sessions once a week, asks a cheap
+//! model for a short digest of the threads still open in them, stores the
+//! digest as a new session and announces it with a `follow-up-digest-ready`
+//! event (and a local notification on mobile).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::commands::{
+    new_session_id, new_session_json, session_message, store_session, stored_sessions, update_stored_session,
+};
+use crate::discovery_context::{condense, message_text};
+use crate::llm::complete_prompt;
+use crate::settings;
+
+/// Session field set while the session is tagged for follow-up (the time it
+/// was tagged)
+pub const FOLLOW_UP_FIELD: &str = "followUpAt";
+
+const DIGEST_SETTINGS_KEY: &str = "follow_up_digest";
+const DIGEST_LAST_RUN_KEY: &str = "follow_up_digest_last_run";
+
+/// Delay before the first check, so startup isn't slowed down
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(120);
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const DIGEST_INTERVAL_DAYS: i64 = 7;
+
+/// Most sessions reviewed in one digest (the most recently updated)
+const MAX_SESSIONS: usize = 20;
+
+/// Latest messages of each session included, and their size
+const MESSAGES_PER_SESSION: usize = 6;
+const MESSAGE_TOKENS: u32 = 150;
+
+const DIGEST_SYSTEM_PROMPT: &str = "You review conversations the user tagged for follow-up. \
+    For each conversation, say in one or two short bullets what is still open: unanswered questions, \
+    next steps the user planned, decisions not yet made. Skip conversations that look resolved. \
+    Use the conversation titles as headings. Be brief.";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FollowUpDigestSettings {
+    pub enabled: bool,
+    /// Falls back to the title generation model default
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FollowUpDigestEvent {
+    session_id: String,
+    reviewed_sessions: usize,
+}
+
+fn load_settings(app: &tauri::AppHandle) -> FollowUpDigestSettings {
+    settings::get_setting(app, DIGEST_SETTINGS_KEY).unwrap_or_default()
+}
+
+fn last_run(app: &tauri::AppHandle) -> Option<DateTime<Utc>> {
+    settings::get_setting::<String>(app, DIGEST_LAST_RUN_KEY)
+        .and_then(|date| DateTime::parse_from_rfc3339(&date).ok())
+        .map(|date| date.with_timezone(&Utc))
+}
+
+fn digest_due(last_run: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    last_run.is_none_or(|last| now - last >= chrono::Duration::days(DIGEST_INTERVAL_DAYS))
+}
+
+fn is_follow_up(session: &serde_json::Value) -> bool {
+    session[FOLLOW_UP_FIELD].is_string()
+}
+
+/// The digest prompt for the tagged sessions, most recently updated first.
+/// None when no session is tagged.
+fn digest_prompt(sessions: &[serde_json::Value]) -> Option<String> {
+    let mut tagged: Vec<&serde_json::Value> = sessions.iter().filter(|s| is_follow_up(s)).collect();
+    if tagged.is_empty() {
+        return None;
+    }
+    tagged.sort_by(|a, b| b["updatedAt"].as_str().cmp(&a["updatedAt"].as_str()));
+
+    let sections: Vec<String> = tagged
+        .into_iter()
+        .take(MAX_SESSIONS)
+        .map(|session| {
+            let messages: Vec<&serde_json::Value> = session["messages"].as_array().into_iter().flatten().collect();
+            let recent = &messages[messages.len().saturating_sub(MESSAGES_PER_SESSION)..];
+            let mut section = format!(
+                "## {} (last updated {})",
+                session["title"].as_str().unwrap_or("Untitled"),
+                session["updatedAt"].as_str().unwrap_or("unknown")
+            );
+            for message in recent {
+                let text = message_text(&message["content"]);
+                if !text.trim().is_empty() {
+                    section.push_str(&format!(
+                        "\n{}: {}",
+                        message["role"].as_str().unwrap_or("user").to_uppercase(),
+                        condense(&text, MESSAGE_TOKENS)
+                    ));
+                }
+            }
+            section
+        })
+        .collect();
+
+    Some(format!(
+        "Here are the latest messages of the conversations I tagged for follow-up. \
+         What is still open in them?\n\n{}",
+        sections.join("\n\n")
+    ))
+}
+
+#[cfg(mobile)]
+fn post_notification(app: &tauri::AppHandle, reviewed_sessions: usize) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let result = app
+        .notification()
+        .builder()
+        .title("Follow-up digest ready")
+        .body(format!("Open threads from {} tagged conversation(s)", reviewed_sessions))
+        .show();
+    if let Err(err) = result {
+        eprintln!("Failed to post follow-up digest notification: {}", err);
+    }
+}
+
+#[cfg(not(mobile))]
+fn post_notification(_app: &tauri::AppHandle, _reviewed_sessions: usize) {}
+
+/// Build a digest of the tagged sessions and store it as a new session.
+/// Returns the new session id, or None when no session is tagged.
+async fn run_digest(app: &tauri::AppHandle) -> Result<Option<String>, String> {
+    let sessions = stored_sessions(app)?;
+    let Some(prompt) = digest_prompt(&sessions) else {
+        return Ok(None);
+    };
+    let reviewed_sessions = sessions.iter().filter(|s| is_follow_up(s)).count().min(MAX_SESSIONS);
+
+    let model = load_settings(app)
+        .model
+        .unwrap_or_else(|| settings::model_defaults(app).title_generation);
+    let reply = complete_prompt(app, &model, Some(DIGEST_SYSTEM_PROMPT), &prompt, false).await?;
+
+    let turn_id = new_session_id();
+    let title = format!("Follow-up digest — {}", chrono::Local::now().format("%Y-%m-%d"));
+    let session = new_session_json(
+        &title,
+        &model,
+        false,
+        vec![
+            session_message("user", &prompt, &turn_id),
+            session_message("assistant", &reply, &turn_id),
+        ],
+    );
+    let session_id = session["id"].as_str().unwrap_or_default().to_string();
+    store_session(app, session)?;
+
+    post_notification(app, reviewed_sessions);
+    let event = FollowUpDigestEvent {
+        session_id: session_id.clone(),
+        reviewed_sessions,
+    };
+    if let Err(err) = app.emit("follow-up-digest-ready", event) {
+        eprintln!("Failed to emit follow-up-digest-ready event: {}", err);
+    }
+
+    Ok(Some(session_id))
+}
+
+/// Run the digest and record when it ran
+async fn run_and_record(app: &tauri::AppHandle) -> Result<Option<String>, String> {
+    let result = run_digest(app).await;
+    if result.is_ok() {
+        if let Err(err) = settings::set_setting(app, DIGEST_LAST_RUN_KEY, &Utc::now().to_rfc3339()) {
+            eprintln!("Failed to save follow-up digest run: {}", err);
+        }
+    }
+    result
+}
+
+/// Background task producing the weekly digest while it is enabled.
+/// Spawned once from `setup`.
+pub async fn run_digest_scheduler(app: tauri::AppHandle) {
+    tokio::time::sleep(FIRST_CHECK_DELAY).await;
+    loop {
+        if load_settings(&app).enabled && digest_due(last_run(&app), Utc::now()) {
+            if let Err(e) = run_and_record(&app).await {
+                eprintln!("Follow-up digest failed: {}", e);
+            }
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Tag or untag a session for follow-up
+#[tauri::command]
+pub fn set_follow_up(app: tauri::AppHandle, session_id: String, follow_up: bool) -> Result<(), String> {
+    update_stored_session(&app, &session_id, |fields| {
+        if !follow_up {
+            fields.remove(FOLLOW_UP_FIELD);
+        } else if !fields.contains_key(FOLLOW_UP_FIELD) {
+            fields.insert(FOLLOW_UP_FIELD.to_string(), Utc::now().to_rfc3339().into());
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_follow_up_digest_settings(app: tauri::AppHandle) -> FollowUpDigestSettings {
+    load_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_follow_up_digest_settings(app: tauri::AppHandle, digest_settings: FollowUpDigestSettings) -> Result<(), String> {
+    settings::set_setting(&app, DIGEST_SETTINGS_KEY, &digest_settings)
+}
+
+/// Produce the digest immediately. Returns the id of the digest session, or
+/// None when no session is tagged for follow-up.
+#[tauri::command]
+pub async fn run_follow_up_digest_now(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    run_and_record(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_covers_tagged_sessions_weekly() {
+        let sessions = vec![
+            serde_json::json!({"title": "Untagged", "updatedAt": "2026-10-10T00:00:00Z", "messages": []}),
+            serde_json::json!({
+                "title": "Trip planning", "updatedAt": "2026-10-01T00:00:00Z", "followUpAt": "2026-10-01T00:00:00Z",
+                "messages": [
+                    {"role": "user", "content": "Which train to Lyon?"},
+                    {"role": "assistant", "content": "The 9:04 TGV. ```\ntimes\n```"}
+                ]
+            }),
+            serde_json::json!({
+                "title": "Tax questions", "updatedAt": "2026-10-12T00:00:00Z", "followUpAt": "2026-10-12T00:00:00Z",
+                "messages": []
+            }),
+        ];
+
+        let prompt = digest_prompt(&sessions).unwrap();
+        assert!(!prompt.contains("Untagged"));
+        assert!(prompt.find("## Tax questions").unwrap() < prompt.find("## Trip planning").unwrap());
+        assert!(prompt.contains("USER: Which train to Lyon?\nASSISTANT: The 9:04 TGV. [code block]"));
+        assert!(digest_prompt(&sessions[..1]).is_none());
+
+        let now = Utc::now();
+        assert!(digest_due(None, now));
+        assert!(!digest_due(Some(now - chrono::Duration::days(6)), now));
+        assert!(digest_due(Some(now - chrono::Duration::days(7)), now));
+    }
+}
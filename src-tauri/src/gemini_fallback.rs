@@ -0,0 +1,199 @@
+//! Gemini feature fallback
+//!
+//! Gemini models accept different feature combinations: 2.x models reject
+//! `thinkingLevel` (they take a `thinkingBudget`), some reject thinking
+//! altogether, and older ones refuse `code_execution` or `url_context`
+//! alongside search. When a request fails with a 400 naming one of these,
+//! the request is adjusted per the compatibility table below and sent once
+//! more, and a `request-adjusted` event tells the frontend what changed.
+//!
+//! | Error mentions                           | Adjustment                                |
+//! |------------------------------------------|-------------------------------------------|
+//! | `thinking_level` / thinking level        | thinkingLevel → equivalent thinkingBudget |
+//! | `thinking_budget` / thinking budget      | thinkingBudget → nearest thinkingLevel    |
+//! | thinking not supported / includeThoughts | drop thinkingConfig                       |
+//! | `code_execution` / multiple tools        | drop the code_execution tool              |
+//! | `url_context` / url context              | drop the url_context tool                 |
+
+use crate::error::SidestreamError;
+use crate::llm_logger;
+use crate::providers::gemini::GeminiClient;
+use crate::request_adjustments::{emit_request_adjusted, AdjustmentKind, RequestAdjustment};
+
+/// One row of the compatibility table
+struct Fallback {
+    /// Lowercase phrases in the error message, any of which selects this row
+    patterns: &'static [&'static str],
+    /// Shown to the user when applied
+    change: &'static str,
+    /// Adjust the request body; false when there was nothing to change
+    apply: fn(&mut serde_json::Value) -> bool,
+}
+
+const FALLBACKS: &[Fallback] = &[
+    Fallback {
+        patterns: &["thinking_level", "thinkinglevel", "thinking level"],
+        change: "Used a thinking budget instead of a thinking level, which this model doesn't support",
+        apply: level_to_budget,
+    },
+    Fallback {
+        patterns: &["thinking_budget", "thinkingbudget", "thinking budget"],
+        change: "Used a thinking level instead of a thinking budget, which this model doesn't support",
+        apply: budget_to_level,
+    },
+    Fallback {
+        patterns: &["thinking is not supported", "does not support thinking", "include_thoughts", "includethoughts"],
+        change: "Turned off thinking, which this model doesn't support",
+        apply: drop_thinking,
+    },
+    Fallback {
+        patterns: &["code_execution", "code execution", "multiple tools"],
+        change: "Turned off code execution, which this model doesn't support with these tools",
+        apply: |body| drop_tool(body, "code_execution"),
+    },
+    Fallback {
+        patterns: &["url_context", "url context"],
+        change: "Turned off reading web pages (URL context), which this model doesn't support",
+        apply: |body| drop_tool(body, "url_context"),
+    },
+];
+
+fn thinking_config(body: &mut serde_json::Value) -> Option<&mut serde_json::Map<String, serde_json::Value>> {
+    body.get_mut("generationConfig")?.get_mut("thinkingConfig")?.as_object_mut()
+}
+
+fn level_to_budget(body: &mut serde_json::Value) -> bool {
+    let Some(config) = thinking_config(body) else {
+        return false;
+    };
+    let Some(level) = config.remove("thinkingLevel") else {
+        return false;
+    };
+    let budget = match level.as_str().unwrap_or_default().to_lowercase().as_str() {
+        "minimal" => 512,
+        "low" => 1024,
+        "medium" => 8192,
+        // Let the model decide
+        _ => -1,
+    };
+    config.insert("thinkingBudget".to_string(), serde_json::json!(budget));
+    true
+}
+
+fn budget_to_level(body: &mut serde_json::Value) -> bool {
+    let Some(config) = thinking_config(body) else {
+        return false;
+    };
+    let Some(budget) = config.remove("thinkingBudget") else {
+        return false;
+    };
+    let level = match budget.as_i64().unwrap_or(-1) {
+        0..=4096 => "LOW",
+        _ => "HIGH",
+    };
+    config.insert("thinkingLevel".to_string(), serde_json::json!(level));
+    true
+}
+
+fn drop_thinking(body: &mut serde_json::Value) -> bool {
+    body.get_mut("generationConfig")
+        .and_then(|config| config.as_object_mut())
+        .is_some_and(|config| config.remove("thinkingConfig").is_some())
+}
+
+fn drop_tool(body: &mut serde_json::Value, tool: &str) -> bool {
+    let Some(tools) = body.get_mut("tools").and_then(|tools| tools.as_array_mut()) else {
+        return false;
+    };
+    let before = tools.len();
+    tools.retain(|t| t.get(tool).is_none());
+    let changed = tools.len() != before;
+    if tools.is_empty() {
+        body.as_object_mut().map(|body| body.remove("tools"));
+    }
+    changed
+}
+
+/// Adjust `body` for the feature a 400 error rejected, returning what was
+/// changed; empty when the error isn't one the table covers
+fn adjust(body: &mut serde_json::Value, error: &SidestreamError) -> Vec<RequestAdjustment> {
+    let SidestreamError::ProviderError {
+        code: Some(400),
+        message,
+    } = error
+    else {
+        return Vec::new();
+    };
+    let message = message.to_lowercase();
+    FALLBACKS
+        .iter()
+        .filter(|fallback| fallback.patterns.iter().any(|p| message.contains(p)))
+        .filter(|fallback| (fallback.apply)(body))
+        .map(|fallback| RequestAdjustment::new(AdjustmentKind::FeatureChanged, fallback.change))
+        .collect()
+}
+
+/// Send a streaming Gemini request, retrying once with an adjusted request
+/// if the model rejects one of its features. `module` names the log.
+pub async fn send_streaming_request(
+    client: &GeminiClient,
+    window: &tauri::Window,
+    module: &str,
+    turn_id: &str,
+    model: &str,
+    body: &serde_json::Value,
+) -> Result<reqwest::Response, SidestreamError> {
+    let error = match client.send_streaming_request(model, body).await {
+        Ok(response) => return Ok(response),
+        Err(error) => error,
+    };
+    let mut adjusted = body.clone();
+    let adjustments = adjust(&mut adjusted, &error);
+    if adjustments.is_empty() {
+        return Err(error);
+    }
+
+    let changes: Vec<&str> = adjustments.iter().map(|a| a.description.as_str()).collect();
+    llm_logger::log_error(module, format!("{} (retrying: {})", error, changes.join("; ")));
+    emit_request_adjusted(window, turn_id, adjustments);
+    llm_logger::log_request(module, model, &adjusted);
+    client.send_streaming_request(model, &adjusted).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adjusts_rejected_features_per_the_table() {
+        let body = serde_json::json!({
+            "generationConfig": {"thinkingConfig": {"thinkingLevel": "HIGH", "includeThoughts": true}},
+            "tools": [{"google_search": {}}, {"url_context": {}}, {"code_execution": {}}]
+        });
+        let error = |message: &str| SidestreamError::ProviderError {
+            code: Some(400),
+            message: message.to_string(),
+        };
+
+        let mut adjusted = body.clone();
+        let changes = adjust(&mut adjusted, &error("thinking_level is not supported for this model."));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            adjusted["generationConfig"]["thinkingConfig"],
+            serde_json::json!({"thinkingBudget": -1, "includeThoughts": true})
+        );
+
+        let mut adjusted = body.clone();
+        adjust(&mut adjusted, &error("Tool use with code execution is unsupported"));
+        assert_eq!(adjusted["tools"], serde_json::json!([{"google_search": {}}, {"url_context": {}}]));
+
+        let mut adjusted = body.clone();
+        assert!(adjust(&mut adjusted, &error("Invalid JSON payload")).is_empty());
+        assert!(adjust(&mut adjusted, &SidestreamError::ProviderError {
+            code: Some(500),
+            message: "thinking_level".to_string(),
+        })
+        .is_empty());
+        assert_eq!(adjusted, body);
+    }
+}
@@ -0,0 +1,148 @@
+//! Stream heartbeats
+//!
+//! Web searches, code execution and long thinking can leave a stream silent
+//! for many seconds, which looks like a frozen UI. Each turn tracks the
+//! phase its response is in, from the blocks and deltas the providers
+//! stream, and while the stream is quiet a `chat-stream-heartbeat` event
+//! reports that phase every couple of seconds until the turn ends.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::llm::{ExecutionStatus, StreamDelta, StreamState};
+use crate::session_events;
+
+/// How long a stream is quiet before a heartbeat, and the time between them
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What a turn's response is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamPhase {
+    /// Waiting on the model (before its first output, or after a tool)
+    #[default]
+    Waiting,
+    Thinking,
+    Searching,
+    Executing,
+    Writing,
+}
+
+/// Event payload while a stream is quiet
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamHeartbeatEvent {
+    pub turn_id: String,
+    pub phase: StreamPhase,
+    /// Time since the turn started
+    pub elapsed_ms: u64,
+}
+
+/// A turn's phase and when it last streamed anything
+#[derive(Debug, Clone)]
+pub struct PhaseTracker {
+    pub phase: StreamPhase,
+    started: Instant,
+    last_activity: Instant,
+}
+
+impl PhaseTracker {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self {
+            phase: StreamPhase::Waiting,
+            started: now,
+            last_activity: now,
+        }
+    }
+
+    /// Note stream activity, moving to `phase` if it is known
+    pub fn record(&mut self, phase: Option<StreamPhase>) {
+        self.last_activity = Instant::now();
+        if let Some(phase) = phase {
+            self.phase = phase;
+        }
+    }
+
+    /// The heartbeat due now, if the stream has been quiet long enough
+    pub fn heartbeat(&self, turn_id: &str) -> Option<StreamHeartbeatEvent> {
+        (self.last_activity.elapsed() >= HEARTBEAT_INTERVAL).then(|| StreamHeartbeatEvent {
+            turn_id: turn_id.to_string(),
+            phase: self.phase,
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+/// The phase a stream delta shows the response is in
+pub fn delta_phase(delta: &StreamDelta) -> Option<StreamPhase> {
+    if let Some(execution) = &delta.execution {
+        return Some(match execution.status {
+            ExecutionStatus::Started | ExecutionStatus::Running => StreamPhase::Executing,
+            ExecutionStatus::Completed | ExecutionStatus::Failed { .. } => StreamPhase::Waiting,
+        });
+    }
+    if delta.thinking.as_deref().is_some_and(|t| !t.is_empty()) {
+        Some(StreamPhase::Thinking)
+    } else if !delta.text.is_empty() {
+        Some(StreamPhase::Writing)
+    } else {
+        None
+    }
+}
+
+/// The phase an Anthropic content block starts (`tool_name` for tool uses)
+pub fn anthropic_block_phase(block_type: &str, tool_name: &str) -> StreamPhase {
+    match (block_type, tool_name) {
+        ("thinking" | "redacted_thinking", _) => StreamPhase::Thinking,
+        ("server_tool_use", "web_search" | "web_fetch") => StreamPhase::Searching,
+        ("server_tool_use", _) => StreamPhase::Executing,
+        ("text", _) => StreamPhase::Writing,
+        // Tool results, and user tools the frontend runs after the response
+        _ => StreamPhase::Waiting,
+    }
+}
+
+/// Send heartbeats for a turn while it is quiet, until it ends. Spawned
+/// alongside the turn's stream.
+pub async fn run(window: tauri::Window, turn_id: String) {
+    let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let Some(tracker) = window.state::<StreamState>().turn_phase(&turn_id) else {
+            return;
+        };
+        if let Some(event) = tracker.heartbeat(&turn_id) {
+            if let Err(err) = session_events::emit(&window, &turn_id, "chat-stream-heartbeat", event) {
+                eprintln!("Failed to emit chat-stream-heartbeat event: {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phases_follow_blocks_and_deltas() {
+        assert_eq!(anthropic_block_phase("server_tool_use", "web_search"), StreamPhase::Searching);
+        assert_eq!(anthropic_block_phase("server_tool_use", "bash_code_execution"), StreamPhase::Executing);
+        assert_eq!(anthropic_block_phase("web_search_tool_result", ""), StreamPhase::Waiting);
+
+        let delta = StreamDelta {
+            turn_id: "t1".to_string(),
+            text: String::new(),
+            citations: None,
+            inline_citations: None,
+            thinking: Some("Considering".to_string()),
+            execution: None,
+        };
+        assert_eq!(delta_phase(&delta), Some(StreamPhase::Thinking));
+        assert_eq!(delta_phase(&StreamDelta { thinking: None, ..delta }), None);
+
+        let tracker = PhaseTracker::start();
+        assert!(tracker.heartbeat("t1").is_none());
+    }
+}
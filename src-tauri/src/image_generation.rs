@@ -0,0 +1,187 @@
+//! Image generation
+//!
+//! `generate_image` runs a prompt through OpenAI's Images API (`gpt-image-*`,
+//! `dall-e-*`) or Gemini image generation (Imagen, or a Gemini image model),
+//! depending on the model. It reports on the turn like a code execution
+//! tool: an execution delta when it starts, one with the generated files when
+//! it finishes (or fails), then `chat-stream-done`, so the images render in
+//! chat the same way code interpreter output does.
+
+use crate::commands::get_api_key_async;
+use crate::content_filters;
+use crate::llm::{
+    emit_stream_delta, emit_stream_done, tool_names, ExecutionDelta, ExecutionStatus,
+    GeneratedFile, StreamDelta,
+};
+use crate::llm_logger;
+use crate::mime_utils;
+use crate::providers::gemini::GeminiClient;
+use crate::providers::openai::OpenAIClient;
+use crate::settings;
+
+/// Report image generation progress on the turn
+fn emit_progress(window: &tauri::Window, turn_id: &str, execution: ExecutionDelta) {
+    let delta = StreamDelta {
+        turn_id: turn_id.to_string(),
+        text: String::new(),
+        citations: None,
+        inline_citations: None,
+        thinking: None,
+        execution: Some(execution),
+    };
+    if let Err(err) = emit_stream_delta(window, delta) {
+        eprintln!("Failed to emit chat-stream-delta event: {}", err);
+    }
+}
+
+/// Generated images, as (mime type, base64 data), in the shape code
+/// execution files take
+fn generated_files(images: Vec<(String, String)>, timestamp: u64) -> Vec<GeneratedFile> {
+    images
+        .into_iter()
+        .enumerate()
+        .map(|(index, (mime_type, data))| {
+            let extension = mime_utils::mime_to_extension_or_subtype(&mime_type);
+            GeneratedFile {
+                file_id: format!("image-{}-{}", timestamp, index),
+                filename: format!("image-{}-{}.{}", timestamp, index, extension),
+                image_preview: Some(format!("data:{};base64,{}", mime_type, data)),
+                mime_type: Some(mime_type),
+                inline_data: Some(data),
+            }
+        })
+        .collect()
+}
+
+/// The provider that serves an image model: OpenAI for `gpt-image-*` and
+/// `dall-e-*`, Google for Imagen and Gemini image models. Chat model routing
+/// (`get_provider_for_model`) doesn't know these names.
+fn image_provider(model: &str) -> Option<&'static str> {
+    if model.starts_with("gpt-image") || model.starts_with("dall-e") {
+        Some("openai")
+    } else if model.starts_with("imagen") || (model.starts_with("gemini") && model.contains("image")) {
+        Some("google")
+    } else {
+        None
+    }
+}
+
+/// Generate an image for a turn. `size` (e.g. "1024x1536") applies to
+/// OpenAI models. Returns the generated files.
+#[tauri::command]
+pub async fn generate_image(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    turn_id: String,
+    prompt: String,
+    model: Option<String>, // Falls back to the image generation model default when omitted
+    size: Option<String>,
+) -> Result<Vec<GeneratedFile>, String> {
+    let model = model.unwrap_or_else(|| settings::model_defaults(&app).image_generation);
+    let provider = image_provider(&model).ok_or_else(|| format!("Image generation isn't supported for {}", model))?;
+    let prompt = content_filters::mask_text(&app, &prompt);
+
+    emit_progress(
+        &window,
+        &turn_id,
+        ExecutionDelta {
+            tool_name: tool_names::IMAGE_GENERATION.to_string(),
+            stdout: None,
+            stderr: None,
+            status: ExecutionStatus::Started,
+            code: Some(prompt.clone()),
+            files: None,
+        },
+    );
+
+    let images = match provider {
+        "openai" => {
+            let client = OpenAIClient::new(get_api_key_async(&app, "openai").await?);
+            client.generate_image(&model, &prompt, size.as_deref()).await
+        }
+        _ => {
+            let client = GeminiClient::new(get_api_key_async(&app, "google").await?);
+            client.generate_image(&model, &prompt).await
+        }
+    }
+    .and_then(|images| {
+        if images.is_empty() {
+            Err("The model returned no image".into())
+        } else {
+            Ok(images)
+        }
+    });
+
+    let (status, files) = match images {
+        Ok(images) => {
+            let timestamp = chrono::Utc::now().timestamp_millis() as u64;
+            (ExecutionStatus::Completed, generated_files(images, timestamp))
+        }
+        Err(error) => {
+            llm_logger::log_error("image", &error);
+            emit_progress(
+                &window,
+                &turn_id,
+                ExecutionDelta {
+                    tool_name: tool_names::IMAGE_GENERATION.to_string(),
+                    stdout: None,
+                    stderr: None,
+                    status: ExecutionStatus::Failed { error: error.to_string() },
+                    code: None,
+                    files: None,
+                },
+            );
+            return Err(error.into());
+        }
+    };
+    llm_logger::log_feature_used("image", &format!("{} image(s) generated with {}", files.len(), model));
+
+    emit_progress(
+        &window,
+        &turn_id,
+        ExecutionDelta {
+            tool_name: tool_names::IMAGE_GENERATION.to_string(),
+            stdout: None,
+            stderr: None,
+            status,
+            code: None,
+            files: Some(files.clone()),
+        },
+    );
+    if let Err(err) = emit_stream_done(&window, &turn_id) {
+        eprintln!("Failed to emit chat-stream-done event: {}", err);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_models_route_by_prefix() {
+        assert_eq!(image_provider("dall-e-3"), Some("openai"));
+        assert_eq!(image_provider("gpt-image-1"), Some("openai"));
+        assert_eq!(image_provider("imagen-4.0-generate-001"), Some("google"));
+        assert_eq!(image_provider("gemini-2.5-flash-image"), Some("google"));
+        assert_eq!(image_provider("gemini-2.5-pro"), None);
+        assert_eq!(image_provider("claude-sonnet-4-6"), None);
+    }
+
+    #[test]
+    fn generated_files_get_previews_and_extensions() {
+        let files = generated_files(
+            vec![
+                ("image/png".to_string(), "AAAA".to_string()),
+                ("image/jpeg".to_string(), "BBBB".to_string()),
+            ],
+            42,
+        );
+
+        assert_eq!(files[0].file_id, "image-42-0");
+        assert_eq!(files[0].filename, "image-42-0.png");
+        assert_eq!(files[0].image_preview.as_deref(), Some("data:image/png;base64,AAAA"));
+        assert_eq!(files[1].filename, "image-42-1.jpg");
+        assert_eq!(files[1].inline_data.as_deref(), Some("BBBB"));
+    }
+}
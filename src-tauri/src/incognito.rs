@@ -0,0 +1,62 @@
+//! Incognito turns
+//!
+//! A chat turn sent with `incognito: true` leaves no trace on disk: nothing
+//! is stored on its session (thinking transcripts, response metadata), the
+//! model isn't counted in usage, and neither the LLM debug log nor stream
+//! recording captures it. The flag is scoped to the turn's task, so logging
+//! deep inside the provider modules can check it without threading it
+//! through every call. Once the turn ends, `chat-incognito-complete` confirms
+//! what was skipped. The audit log is a compliance record and still notes
+//! the request when it is enabled; the event says so.
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+use crate::audit_log;
+use crate::session_events;
+
+tokio::task_local! {
+    static INCOGNITO: bool;
+}
+
+/// Event payload confirming an incognito turn wrote nothing
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IncognitoTurnEvent {
+    pub turn_id: String,
+    /// True if the turn's requests went to the (enabled) audit log
+    pub audit_logged: bool,
+}
+
+/// Run `future` (a turn's task) with incognito on or off
+pub async fn scope<F: Future>(incognito: bool, future: F) -> F::Output {
+    INCOGNITO.scope(incognito, future).await
+}
+
+/// Whether the current task is an incognito turn
+pub fn is_active() -> bool {
+    INCOGNITO.try_with(|incognito| *incognito).unwrap_or(false)
+}
+
+/// Confirm to the frontend that an incognito turn has ended without writes
+pub fn emit_complete(window: &tauri::Window, turn_id: &str) {
+    let event = IncognitoTurnEvent {
+        turn_id: turn_id.to_string(),
+        audit_logged: audit_log::get_audit_log_enabled(),
+    };
+    if let Err(err) = session_events::emit(window, turn_id, "chat-incognito-complete", event) {
+        eprintln!("Failed to emit chat-incognito-complete event: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_is_scoped_to_the_turn() {
+        assert!(!is_active());
+        assert!(INCOGNITO.sync_scope(true, is_active));
+        assert!(!INCOGNITO.sync_scope(false, is_active));
+        assert!(!is_active());
+    }
+}
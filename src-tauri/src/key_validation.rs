@@ -0,0 +1,124 @@
+//! API key validation
+//!
+//! `validate_api_key` checks a key with a cheap authenticated call (each
+//! provider's model list, or OpenRouter's key info) and reports a structured
+//! status, so a bad key shows up when it is saved rather than on the first
+//! chat.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::commands::get_api_key_async;
+use crate::provider_endpoints;
+use crate::proxy;
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Characters of an unexpected error body kept in the message
+const MAX_MESSAGE_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyStatus {
+    Valid,
+    /// The provider rejected the key
+    Invalid,
+    /// The key is accepted but currently rate limited (or out of quota)
+    RateLimited,
+    /// The provider couldn't be reached
+    NetworkError,
+    /// Any other response, e.g. a provider outage
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyValidation {
+    pub provider: String,
+    pub status: KeyStatus,
+    /// Details for anything but a valid key
+    pub message: Option<String>,
+}
+
+/// Status for a provider's response to the check. Google answers an unknown
+/// key with 400 API_KEY_INVALID rather than 401.
+fn status_for_response(status: u16, body: &str) -> KeyStatus {
+    match status {
+        200..=299 => KeyStatus::Valid,
+        401 | 403 => KeyStatus::Invalid,
+        400 if body.contains("API_KEY_INVALID") || body.contains("API key not valid") => KeyStatus::Invalid,
+        429 => KeyStatus::RateLimited,
+        _ => KeyStatus::Error,
+    }
+}
+
+fn check_request(client: &reqwest::Client, provider: &str, key: &str) -> Result<reqwest::RequestBuilder, String> {
+    let path = match provider {
+        "anthropic" | "openai" | "google" => "/models",
+        "openrouter" => "/key",
+        _ => return Err(format!("Invalid provider: {}", provider)),
+    };
+    let request = provider_endpoints::request(client, reqwest::Method::GET, provider, path, key);
+    Ok(match provider {
+        "anthropic" => request.header("anthropic-version", ANTHROPIC_VERSION),
+        _ => request,
+    })
+}
+
+/// Check an API key with a cheap authenticated request. Checks `key` when
+/// given (before saving it), otherwise the saved key.
+#[tauri::command]
+pub async fn validate_api_key(
+    app: tauri::AppHandle,
+    provider: String,
+    key: Option<String>,
+) -> Result<KeyValidation, String> {
+    let key = match key.filter(|k| !k.trim().is_empty()) {
+        Some(key) => key.trim().to_string(),
+        None => get_api_key_async(&app, &provider).await?,
+    };
+    let client = proxy::client_builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let (status, message) = match check_request(&client, &provider, &key)?.send().await {
+        Ok(response) => {
+            let code = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            let status = status_for_response(code, &body);
+            let message = (status != KeyStatus::Valid).then(|| {
+                let body: String = body.trim().chars().take(MAX_MESSAGE_CHARS).collect();
+                format!("HTTP {}: {}", code, body)
+            });
+            (status, message)
+        }
+        Err(e) => (KeyStatus::NetworkError, Some(e.to_string())),
+    };
+
+    Ok(KeyValidation {
+        provider,
+        status,
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_provider_responses_to_statuses() {
+        assert_eq!(status_for_response(200, "{}"), KeyStatus::Valid);
+        assert_eq!(status_for_response(401, "invalid x-api-key"), KeyStatus::Invalid);
+        assert_eq!(
+            status_for_response(400, r#"{"error":{"details":[{"reason":"API_KEY_INVALID"}]}}"#),
+            KeyStatus::Invalid
+        );
+        assert_eq!(status_for_response(400, "bad request"), KeyStatus::Error);
+        assert_eq!(status_for_response(429, ""), KeyStatus::RateLimited);
+        assert_eq!(status_for_response(529, "overloaded"), KeyStatus::Error);
+    }
+}
@@ -1,46 +1,242 @@
+mod anthropic_betas;
+mod attachment_elision;
+mod attachments;
 mod audio;
+mod audio_chunks;
+#[cfg(mobile)]
+mod audio_mobile;
+mod audit_log;
+mod automations;
+mod background;
+mod cache_warmup;
+mod calendar;
+mod citations;
+mod code_languages;
 mod commands;
+mod content_filters;
 mod discovery;
+mod discovery_batch;
+mod discovery_context;
+mod discovery_profiles;
+mod drafts;
+mod error;
+mod extraction;
+mod follow_up;
+mod gemini_fallback;
+mod heartbeat;
+mod image_generation;
+mod incognito;
+mod key_validation;
 mod llm;
 mod llm_anthropic;
 mod llm_gemini;
 mod llm_logger;
 mod llm_openai;
+mod llm_openrouter;
 mod llm_voice;
+mod local_transcription;
+mod meeting;
+mod message_format;
 mod mime_utils;
+mod model_routing;
+mod model_usage;
+mod personas;
+mod pins;
+mod prompts;
+mod provider_endpoints;
+mod provider_mappings;
 mod providers;
+mod proxy;
+mod read_aloud;
+#[cfg(desktop)]
+mod push_to_talk;
+mod request_adjustments;
+mod request_validation;
+mod retention;
 mod secure_storage;
+#[cfg(mobile)]
+mod secure_storage_mobile;
+mod send_locks;
+mod session_appearance;
+mod session_branches;
+mod session_duplicate;
+mod session_events;
+mod session_import;
+mod session_lock;
+mod session_stats;
+mod session_store;
+mod settings;
+mod sharing;
+mod snapshots;
+mod snippets;
+mod speech_playback;
+mod sse;
+mod sse_recording;
+mod structured;
+mod system_prompts;
+mod tables;
+mod thinking_transcripts;
+mod token_count;
+mod tool_images;
+#[cfg(desktop)]
+mod tray;
+mod tts;
+mod turn_metrics;
+mod voice_intents;
+mod voice_turns;
+mod watch_folder;
+mod web_search_cache;
+mod workflows;
+mod workspaces;
 
-use std::sync::Arc;
-
+use anthropic_betas::{get_anthropic_betas, set_anthropic_beta_enabled};
+use attachment_elision::{
+    get_attachment_elision_settings, set_attachment_elision_settings, set_attachment_live,
+};
+use attachments::{
+    generate_thumbnail, get_paste_threshold, get_thumbnail, prepare_pasted_text, set_paste_threshold,
+};
 use audio::{
-    cancel_audio_recording, get_audio_devices, get_recording_state, start_audio_recording,
-    stop_audio_recording, stop_audio_recording_raw, AudioState,
+    cancel_audio_recording, get_audio_devices, get_preferred_input_device, get_recording_state, retry_last_transcription,
+    start_audio_recording, stop_audio_recording, stop_audio_recording_raw, AudioState,
 };
+use audit_log::{
+    export_audit_log, get_audit_log_enabled, set_audit_log_enabled, verify_audit_log,
+};
+use automations::{delete_automation, list_automations, run_automation_now, save_automation};
+use background::{take_background_completions, BackgroundState};
+use cache_warmup::{get_prompt_cache_warmup, set_prompt_cache_warmup, warm_prompt_cache};
+use calendar::{add_calendar_items, extract_calendar_items};
+use citations::resolve_citation;
 use commands::{
-    clear_chat_sessions_store, delete_api_key, delete_chat_session, download_anthropic_file,
-    download_openai_file, download_openai_file_by_name, export_chat_to_html, fetch_image_url_bytes,
+    clear_chat_sessions_store, count_chat_sessions, delete_api_key, delete_chat_session, download_anthropic_file,
+    download_openai_file, download_openai_file_by_name, export_chat_to_html, export_chat_to_markdown, export_container_files, fetch_image_url_bytes,
     get_configured_providers, has_api_key,
     list_chat_sessions, load_chat_session, log_debug, log_frontend_debug, log_frontend_error,
     print_webview, save_api_key, save_chat_session,
 };
+use content_filters::{get_content_filters, save_content_filters};
 use discovery::discover_resources;
-use llm::{cancel_chat_stream, send_chat_message, send_voice_message, transcribe_audio_gemini, StreamState};
+use discovery_batch::{get_discovery_batch_mode, set_discovery_batch_mode};
+use discovery_profiles::{delete_discovery_profile, list_discovery_profiles, save_discovery_profile};
+use drafts::{get_draft, save_draft, DraftState};
+use extraction::extract_text_from_image;
+use follow_up::{
+    get_follow_up_digest_settings, run_follow_up_digest_now, set_follow_up, set_follow_up_digest_settings,
+};
+use image_generation::generate_image;
+use key_validation::validate_api_key;
+use llm::{
+    cancel_and_keep, cancel_chat_stream, generate_session_title, list_user_tools, register_user_tools,
+    send_chat_message, send_voice_message, set_session_gemini_thinking_budget, submit_tool_result, summarize_text,
+    transcribe_audio_gemini, StreamState,
+};
+use llm_openrouter::list_openrouter_models;
+use local_transcription::{
+    delete_whisper_model, download_whisper_model, get_transcription_settings, list_whisper_models,
+    local_transcription_available, set_transcription_settings,
+};
+use model_routing::{get_auto_routing_settings, set_auto_routing_settings};
+use meeting::{get_active_meeting, start_meeting, stop_meeting, MeetingState};
+use model_usage::{get_model_usage, set_model_favorite};
+use personas::{delete_persona, list_personas, save_persona, set_session_persona};
+use provider_endpoints::{get_provider_endpoints, set_provider_endpoint};
+use provider_mappings::{get_model_provider_mappings, set_model_provider_mapping};
+use proxy::{get_proxy_settings, set_proxy_settings, test_proxy};
+#[cfg(desktop)]
+use push_to_talk::{get_push_to_talk_settings, set_push_to_talk_settings, PushToTalkState};
+use retention::{apply_retention_now, get_retention_policy, set_retention_policy};
+use send_locks::SendLocks;
+use session_appearance::{get_session_appearance, set_session_appearance};
+use session_branches::{fork_chat_session, get_session_branches, regenerate_chat_turn};
+use session_duplicate::duplicate_session;
+use session_events::{subscribe_session_events, unsubscribe_session_events, SessionSubscriptions};
+use session_import::import_chat_sessions;
+use session_lock::{is_session_locked, lock_session, unlock_session};
+use session_stats::{get_model_pricing, get_session_stats, set_model_pricing};
+use pins::{list_pins, pin_message, unpin_message};
+use prompts::{
+    delete_prompt_template, list_prompt_templates, render_prompt_template, save_prompt_template,
+    set_session_prompt_variables,
+};
+use settings::{
+    get_model_defaults, get_transcript_timestamps, get_transcription_language, set_model_defaults,
+    set_transcript_timestamps, set_transcription_language,
+};
+use sharing::{import_shared_session, share_session};
+use snapshots::{get_citation_snapshots, snapshot_citations};
+use snippets::{delete_snippet, list_snippets, save_snippet, search_snippets};
+use read_aloud::{
+    pause_read_aloud, read_aloud_finish, read_aloud_push, resume_read_aloud, skip_read_aloud_sentence, stop_read_aloud,
+    ReadAloudState,
+};
+use speech_playback::{speak_text, stop_speaking, SpeechState};
+use sse::{get_stream_parse_warnings, reset_stream_parse_warnings};
+#[cfg(debug_assertions)]
+use sse_recording::replay_stream;
+use sse_recording::{get_stream_recording, list_stream_recordings, set_stream_recording};
+use structured::structured_request;
+use system_prompts::{
+    get_date_context_settings, get_global_system_prompt, get_response_language,
+    get_session_system_prompt, get_workspace_system_prompt, list_workspace_system_prompts,
+    set_date_context_settings, set_global_system_prompt, set_response_language,
+    set_session_response_language, set_session_system_prompt, set_workspace_system_prompt,
+};
+use tables::export_table;
+use thinking_transcripts::{get_persist_thinking, set_persist_thinking};
+use token_count::count_tokens;
+use tool_images::{get_reattach_tool_images, set_reattach_tool_images};
+use tts::{
+    get_readback_settings, set_readback_settings, set_session_readback_settings, synthesize_speech,
+};
+use voice_turns::{get_voice_turn_audio, retranscribe_voice_turn, save_voice_turn};
+use watch_folder::{get_watch_folder_settings, set_watch_folder_settings};
+use workflows::{delete_workflow, list_workflows, run_workflow, save_workflow};
+use workspaces::{
+    delete_workspace, get_current_workspace, list_workspace_documents, list_workspace_sessions, list_workspaces,
+    read_workspace_document, save_workspace, set_current_workspace, set_session_workspace,
+};
+use tauri::Manager;
 use tauri::menu::{AboutMetadata, MenuBuilder, MenuItemBuilder, PredefinedMenuItem, SubmenuBuilder};
-use tokio::sync::Mutex;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let builder = tauri::Builder::default()
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_store::Builder::default().build())
-        .manage(StreamState {
-            cancel_token: Arc::new(Mutex::new(None)),
-        })
+        .plugin(tauri_plugin_store::Builder::default().build());
+    // Local notifications for replies that finish in the background, and
+    // the native plugins for microphone capture and the platform keystore
+    #[cfg(mobile)]
+    let builder = builder
+        .plugin(tauri_plugin_notification::init())
+        .plugin(audio_mobile::init())
+        .plugin(secure_storage_mobile::init());
+    // System-wide push-to-talk hotkey
+    #[cfg(desktop)]
+    let builder = builder
+        .plugin(tauri_plugin_global_shortcut::Builder::new().with_handler(push_to_talk::handle_shortcut).build())
+        .manage(PushToTalkState::new());
+
+    builder
+        .manage(StreamState::new())
         .manage(AudioState::new())
+        .manage(SpeechState::new())
+        .manage(ReadAloudState::new())
+        .manage(DraftState::new())
+        .manage(MeetingState::new())
+        .manage(BackgroundState::new())
+        .manage(SendLocks::new())
+        .manage(SessionSubscriptions::new())
+        .on_window_event(|window, event| {
+            match event {
+                tauri::WindowEvent::Focused(focused) => background::set_focused(window.app_handle(), *focused),
+                tauri::WindowEvent::Destroyed => window.state::<SessionSubscriptions>().remove_window(window.label()),
+                _ => {}
+            }
+        })
         .setup(|app| {
             // Set up the application menu with About metadata
             // Only set short_version to avoid duplicate "(version)" display on macOS
@@ -91,12 +287,42 @@ pub fn run() {
 
             app.set_menu(menu)?;
 
+            // Open the session database (importing chat-sessions.json the first time)
+            session_store::init(app.handle())?;
+            // Tray icon with quick actions and recent sessions
+            #[cfg(desktop)]
+            tray::init(app.handle())?;
+
+            // Start recording outbound requests if the audit log is turned on
+            audit_log::init(app.handle());
+            // Load user-defined model → provider routing
+            provider_mappings::init(app.handle());
+            // Route provider requests through the configured proxy
+            proxy::init(app.handle());
+            // Load which Anthropic beta features are switched off
+            anthropic_betas::init(app.handle());
+            // Load custom provider endpoints (Azure OpenAI, gateways)
+            provider_endpoints::init(app.handle());
+            // Register the push-to-talk hotkey
+            #[cfg(desktop)]
+            push_to_talk::init(app.handle());
+
+            // Run scheduled automations in the background while the app is open
+            tauri::async_runtime::spawn(automations::run_scheduler(app.handle().clone()));
+            // Ingest files dropped into the watch folder, if one is configured
+            tauri::async_runtime::spawn(watch_folder::run_watcher(app.handle().clone()));
+            // Delete old sessions, logs and caches per the retention policy
+            tauri::async_runtime::spawn(retention::run_retention(app.handle().clone()));
+            // Produce the weekly follow-up digest, if it is turned on
+            tauri::async_runtime::spawn(follow_up::run_digest_scheduler(app.handle().clone()));
+
             // Handle custom menu events
             app.on_menu_event(move |app_handle, event| {
                 match event.id().as_ref() {
-                    "quit" | "close" => {
+                    "quit" => {
                         app_handle.exit(0);
                     }
+                    "close" => close_focused_window(app_handle),
                     _ => {}
                 }
             });
@@ -108,34 +334,276 @@ pub fn run() {
             has_api_key,
             delete_api_key,
             get_configured_providers,
+            validate_api_key,
             send_chat_message,
             send_voice_message,
             cancel_chat_stream,
+            cancel_and_keep,
+            take_background_completions,
+            set_session_gemini_thinking_budget,
+            list_openrouter_models,
             discover_resources,
+            generate_image,
+            // Discovery profiles
+            list_discovery_profiles,
+            save_discovery_profile,
+            delete_discovery_profile,
+            get_discovery_batch_mode,
+            set_discovery_batch_mode,
+            generate_session_title,
+            summarize_text,
+            structured_request,
+            get_model_defaults,
+            set_model_defaults,
+            get_auto_routing_settings,
+            set_auto_routing_settings,
+            get_model_provider_mappings,
+            set_model_provider_mapping,
+            get_model_usage,
+            set_model_favorite,
+            // User-defined tools
+            register_user_tools,
+            list_user_tools,
+            submit_tool_result,
+            // Personas
+            list_personas,
+            save_persona,
+            delete_persona,
+            set_session_persona,
+            // Prompt snippets
+            list_snippets,
+            search_snippets,
+            save_snippet,
+            delete_snippet,
+            // Pinned messages
+            pin_message,
+            unpin_message,
+            list_pins,
+            // Attachment elision
+            get_attachment_elision_settings,
+            set_attachment_elision_settings,
+            set_attachment_live,
+            // Prompt cache warmup
+            warm_prompt_cache,
+            get_prompt_cache_warmup,
+            set_prompt_cache_warmup,
+            // Prompt templates
+            list_prompt_templates,
+            save_prompt_template,
+            delete_prompt_template,
+            render_prompt_template,
+            set_session_prompt_variables,
+            // Layered system prompts
+            get_global_system_prompt,
+            set_global_system_prompt,
+            list_workspace_system_prompts,
+            get_workspace_system_prompt,
+            set_workspace_system_prompt,
+            get_session_system_prompt,
+            set_session_system_prompt,
+            get_response_language,
+            set_response_language,
+            set_session_response_language,
+            get_date_context_settings,
+            set_date_context_settings,
             save_chat_session,
             load_chat_session,
             list_chat_sessions,
+            count_chat_sessions,
             delete_chat_session,
+            duplicate_session,
+            fork_chat_session,
+            regenerate_chat_turn,
+            get_session_branches,
             clear_chat_sessions_store,
+            subscribe_session_events,
+            unsubscribe_session_events,
+            share_session,
+            import_shared_session,
+            import_chat_sessions,
+            save_draft,
+            get_draft,
             export_chat_to_html,
+            export_chat_to_markdown,
+            export_table,
+            get_persist_thinking,
+            set_persist_thinking,
+            get_reattach_tool_images,
+            set_reattach_tool_images,
             print_webview,
             log_frontend_error,
             log_frontend_debug,
             log_debug,
+            // Stream recording and replay (debugging)
+            get_stream_recording,
+            set_stream_recording,
+            list_stream_recordings,
+            #[cfg(debug_assertions)]
+            replay_stream,
+            get_stream_parse_warnings,
+            reset_stream_parse_warnings,
             // Native audio capture commands
             start_audio_recording,
             stop_audio_recording,
             stop_audio_recording_raw,
+            retry_last_transcription,
             cancel_audio_recording,
             get_audio_devices,
+            get_preferred_input_device,
             get_recording_state,
             transcribe_audio_gemini,
+            get_transcription_language,
+            set_transcription_language,
+            get_transcript_timestamps,
+            set_transcript_timestamps,
+            // Local transcription (whisper.cpp)
+            local_transcription_available,
+            get_transcription_settings,
+            set_transcription_settings,
+            list_whisper_models,
+            download_whisper_model,
+            delete_whisper_model,
+            // Push-to-talk hotkey
+            #[cfg(desktop)]
+            get_push_to_talk_settings,
+            #[cfg(desktop)]
+            set_push_to_talk_settings,
+            // Meeting mode
+            start_meeting,
+            stop_meeting,
+            get_active_meeting,
+            // Voice turn persistence
+            save_voice_turn,
+            get_voice_turn_audio,
+            retranscribe_voice_turn,
+            // Readback (text-to-speech)
+            get_readback_settings,
+            set_readback_settings,
+            set_session_readback_settings,
+            synthesize_speech,
+            speak_text,
+            stop_speaking,
+            // Read-aloud queue
+            read_aloud_push,
+            read_aloud_finish,
+            pause_read_aloud,
+            resume_read_aloud,
+            skip_read_aloud_sentence,
+            stop_read_aloud,
+            // Scheduled automations
+            list_automations,
+            save_automation,
+            delete_automation,
+            run_automation_now,
+            // Chained prompt workflows
+            list_workflows,
+            save_workflow,
+            delete_workflow,
+            run_workflow,
+            // Watch-folder ingestion
+            get_watch_folder_settings,
+            set_watch_folder_settings,
+            // Calendar and reminder extraction
+            extract_calendar_items,
+            add_calendar_items,
+            // Outbound request audit log
+            get_audit_log_enabled,
+            set_audit_log_enabled,
+            verify_audit_log,
+            export_audit_log,
+            // Pre-send content filters
+            get_content_filters,
+            save_content_filters,
+            // Outbound proxy
+            get_proxy_settings,
+            set_proxy_settings,
+            test_proxy,
+            // Anthropic beta features
+            get_anthropic_betas,
+            set_anthropic_beta_enabled,
+            // Custom provider endpoints
+            get_provider_endpoints,
+            set_provider_endpoint,
+            // Data retention
+            get_retention_policy,
+            set_retention_policy,
+            apply_retention_now,
+            // Read-only sessions
+            lock_session,
+            unlock_session,
+            is_session_locked,
+            // Workspaces
+            list_workspaces,
+            save_workspace,
+            delete_workspace,
+            get_current_workspace,
+            set_current_workspace,
+            set_session_workspace,
+            list_workspace_sessions,
+            list_workspace_documents,
+            read_workspace_document,
+            // Follow-up tags and digests
+            set_follow_up,
+            get_follow_up_digest_settings,
+            set_follow_up_digest_settings,
+            run_follow_up_digest_now,
+            // Session appearance
+            set_session_appearance,
+            get_session_appearance,
+            // Conversation statistics
+            get_session_stats,
+            get_model_pricing,
+            set_model_pricing,
+            // Token counting
+            count_tokens,
             // File download commands
             download_anthropic_file,
             download_openai_file,
             download_openai_file_by_name,
+            export_container_files,
+            // Attachment cache
+            generate_thumbnail,
+            get_thumbnail,
+            prepare_pasted_text,
+            get_paste_threshold,
+            set_paste_threshold,
+            extract_text_from_image,
+            resolve_citation,
+            snapshot_citations,
+            get_citation_snapshots,
             fetch_image_url_bytes,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // Clicking the dock icon brings back a window hidden by Close Window
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Reopen { has_visible_windows: false, .. } = event {
+                for window in app_handle.webview_windows().values() {
+                    if let Err(e) = window.show().and_then(|_| window.set_focus()) {
+                        eprintln!("Failed to show window: {}", e);
+                    }
+                }
+            }
+            #[cfg(not(target_os = "macos"))]
+            let _ = (app_handle, event);
+        });
+}
+
+/// Close Window: close the focused window. On macOS, where apps keep running
+/// without windows, the last window is hidden instead so background tasks
+/// (automations, watch folder, retention) carry on.
+fn close_focused_window(app: &tauri::AppHandle) {
+    let windows = app.webview_windows();
+    let Some(window) = windows.values().find(|w| w.is_focused().unwrap_or(false)) else {
+        return;
+    };
+    let result = if cfg!(target_os = "macos") && windows.len() == 1 {
+        window.hide()
+    } else {
+        window.close()
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to close window: {}", e);
+    }
 }
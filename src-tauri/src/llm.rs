@@ -9,17 +9,59 @@
 //! - `llm_gemini` - Google Gemini API
 //! - `llm_voice` - Voice message handling (Gemini-based)
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::time::Duration;
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tokio::sync::oneshot;
 use tokio_util::sync::CancellationToken;
 
+use crate::attachment_elision;
+use crate::background;
+use crate::citations::CitationNormalizer;
+use crate::commands::{get_api_key_async, get_stored_session, update_stored_session};
+use crate::content_filters;
+use crate::error::SidestreamError;
+use crate::extraction::convert_office_documents;
+use crate::heartbeat::{self, PhaseTracker, StreamPhase};
+use crate::incognito;
 use crate::llm_anthropic::send_chat_message_anthropic;
 use crate::llm_gemini::send_chat_message_gemini;
 use crate::llm_openai::send_chat_message_openai;
-use crate::llm_voice::{send_voice_message_impl, transcribe_audio_gemini_impl};
-use crate::providers::anthropic::{Citation, InlineCitation};
+use crate::llm_openrouter::{openrouter_messages, send_chat_message_openrouter};
+use crate::llm_logger;
+use crate::llm_voice::{
+    send_voice_message_impl, transcribe_audio_file_gemini_impl, transcribe_audio_gemini_impl,
+};
+use crate::mime_utils::audio_mime_type;
+use crate::model_routing::{self, AUTO_MODEL};
+use crate::model_usage;
+use crate::pins;
+use crate::provider_mappings;
+use crate::providers::anthropic::{extract_response_text, AnthropicClient, Citation, InlineCitation};
+use crate::providers::gemini::GeminiClient;
+use crate::providers::openai::{extract_output_text, OpenAIClient};
+use crate::providers::openrouter::{
+    ChatRequestConfig as OpenRouterChatRequestConfig, OpenRouterClient, OPENROUTER_MODEL_PREFIX,
+};
+use crate::request_adjustments;
+use crate::send_locks::SendLocks;
+use crate::session_events;
+use crate::session_lock;
+use crate::settings;
+use crate::snippets;
+use crate::structured::structured_completion;
+use crate::system_prompts;
+use crate::tables;
+use crate::tool_images;
+#[cfg(desktop)]
+use crate::tray;
+use crate::turn_metrics::{TurnMetrics, TurnTiming};
+use crate::thinking_transcripts;
+use crate::voice_intents;
+use crate::workspaces;
 
 /// Tool name constants for code execution across providers
 pub mod tool_names {
@@ -31,26 +73,342 @@ pub mod tool_names {
     pub const TEXT_EDITOR_CODE_EXECUTION: &str = "text_editor_code_execution";
     /// Gemini code execution tool
     pub const GEMINI_CODE_EXECUTION: &str = "gemini_code_execution";
+    /// Image generation (see `image_generation`)
+    pub const IMAGE_GENERATION: &str = "image_generation";
 }
 
-/// Shared state for managing stream cancellation
+/// What a turn has streamed so far
+#[derive(Default)]
+pub struct TurnOutput {
+    /// Reply text, so a stopped response can be kept
+    pub text: String,
+    /// Thinking/reasoning-summary text (see `thinking_transcripts`)
+    pub thinking: String,
+    /// Identifiers the provider returned for the response
+    pub metadata: Option<ResponseMetadata>,
+}
+
+/// An in-flight turn
+struct ActiveTurn {
+    cancel_token: CancellationToken,
+    output: TurnOutput,
+    citations: CitationNormalizer,
+    timing: TurnTiming,
+    /// What the response is doing, for heartbeats (see `heartbeat`)
+    phase: PhaseTracker,
+    /// The last `chat-usage` event emitted, repeated with metrics at the end
+    usage: Option<ChatUsageEvent>,
+}
+
+/// Shared state for managing stream cancellation. Turns are tracked by id so
+/// concurrent streams can be cancelled independently.
+#[derive(Default)]
 pub struct StreamState {
-    pub cancel_token: Arc<Mutex<Option<CancellationToken>>>,
+    turns: parking_lot::Mutex<HashMap<String, ActiveTurn>>,
+    /// Cancellation tokens of in-flight discovery runs, by the id of the turn
+    /// they run for
+    discoveries: parking_lot::Mutex<HashMap<String, CancellationToken>>,
+    /// User tool calls waiting on the frontend, by tool use id
+    tool_calls: parking_lot::Mutex<HashMap<String, oneshot::Sender<ToolResult>>>,
+}
+
+impl StreamState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new turn; returns the token that cancels it
+    fn begin_turn(&self, turn_id: &str) -> CancellationToken {
+        let cancel_token = CancellationToken::new();
+        self.turns.lock().insert(
+            turn_id.to_string(),
+            ActiveTurn {
+                cancel_token: cancel_token.clone(),
+                output: TurnOutput::default(),
+                citations: CitationNormalizer::default(),
+                timing: TurnTiming::start(),
+                phase: PhaseTracker::start(),
+                usage: None,
+            },
+        );
+        cancel_token
+    }
+
+    /// Forget a finished turn; returns what it streamed
+    fn end_turn(&self, turn_id: &str) -> Option<TurnOutput> {
+        self.turns.lock().remove(turn_id).map(|turn| turn.output)
+    }
+
+    /// Register a discovery run for a turn; returns the token that cancels it
+    pub fn begin_discovery(&self, turn_id: &str) -> CancellationToken {
+        let cancel_token = CancellationToken::new();
+        self.discoveries.lock().insert(turn_id.to_string(), cancel_token.clone());
+        cancel_token
+    }
+
+    /// Forget a finished discovery run
+    pub fn end_discovery(&self, turn_id: &str) {
+        self.discoveries.lock().remove(turn_id);
+    }
+
+    /// Text streamed so far for a turn that is still in flight
+    pub fn turn_text(&self, turn_id: &str) -> Option<String> {
+        self.turns.lock().get(turn_id).map(|turn| turn.output.text.clone())
+    }
+
+    fn merge_metadata(&self, turn_id: &str, metadata: ResponseMetadata) {
+        if let Some(turn) = self.turns.lock().get_mut(turn_id) {
+            turn.output.metadata.get_or_insert_with(Default::default).merge(metadata);
+        }
+    }
+
+    fn turn_metadata(&self, turn_id: &str) -> Option<ResponseMetadata> {
+        self.turns.lock().get(turn_id).and_then(|turn| turn.output.metadata.clone())
+    }
+
+    /// Clean inline citations and merge their titles with those the turn
+    /// already emitted (streams without a registered turn, like replays,
+    /// only get the batch cleaned)
+    fn normalize_citations(&self, turn_id: &str, citations: Vec<InlineCitation>) -> Vec<InlineCitation> {
+        match self.turns.lock().get_mut(turn_id) {
+            Some(turn) => turn.citations.normalize(citations),
+            None => CitationNormalizer::default().normalize(citations),
+        }
+    }
+
+    fn append_delta(&self, delta: &StreamDelta) {
+        if let Some(turn) = self.turns.lock().get_mut(&delta.turn_id) {
+            turn.timing.record_output();
+            turn.output.text.push_str(&delta.text);
+            if let Some(thinking) = &delta.thinking {
+                turn.output.thinking.push_str(thinking);
+            }
+        }
+    }
+
+    /// Phase and activity of a turn that is still in flight
+    pub fn turn_phase(&self, turn_id: &str) -> Option<PhaseTracker> {
+        self.turns.lock().get(turn_id).map(|turn| turn.phase.clone())
+    }
+
+    fn record_activity(&self, turn_id: &str, phase: Option<StreamPhase>) {
+        if let Some(turn) = self.turns.lock().get_mut(turn_id) {
+            turn.phase.record(phase);
+        }
+    }
+
+    fn record_usage(&self, usage: &ChatUsageEvent) {
+        if let Some(turn) = self.turns.lock().get_mut(&usage.turn_id) {
+            turn.usage = Some(usage.clone());
+        }
+    }
+
+    fn add_output_tokens(&self, turn_id: &str, tokens: u64) {
+        if let Some(turn) = self.turns.lock().get_mut(turn_id) {
+            turn.timing.add_output_tokens(tokens);
+        }
+    }
+
+    /// The turn's last usage event (or an empty one) with its metrics so far
+    fn final_usage(&self, turn_id: &str) -> Option<ChatUsageEvent> {
+        let turns = self.turns.lock();
+        let turn = turns.get(turn_id)?;
+        let metrics = turn.timing.finish(turn.output.text.len() + turn.output.thinking.len());
+        let mut usage = turn.usage.clone().unwrap_or_else(|| ChatUsageEvent {
+            turn_id: turn_id.to_string(),
+            provider: turn.output.metadata.as_ref().map(|m| m.provider.clone()).unwrap_or_default(),
+            input_tokens: 0,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+            metrics: None,
+        });
+        usage.metrics = Some(metrics);
+        Some(usage)
+    }
+}
+
+/// Emit a `chat-usage` event, keeping it to repeat with the turn's metrics
+/// when it ends
+pub fn emit_usage(window: &tauri::Window, usage: ChatUsageEvent) {
+    if let Some(state) = window.try_state::<StreamState>() {
+        state.record_usage(&usage);
+    }
+    let turn_id = usage.turn_id.clone();
+    if let Err(err) = session_events::emit(window, &turn_id, "chat-usage", usage) {
+        eprintln!("Failed to emit chat-usage event: {}", err);
+    }
+}
+
+/// Note that a turn's response has moved to `phase` (a search or tool
+/// starting), for heartbeats while it is quiet
+pub fn set_stream_phase(window: &tauri::Window, turn_id: &str, phase: StreamPhase) {
+    if let Some(state) = window.try_state::<StreamState>() {
+        state.record_activity(turn_id, Some(phase));
+    }
+}
+
+/// Record output tokens a provider reported for one of a turn's responses
+pub fn record_output_tokens(window: &tauri::Window, turn_id: &str, tokens: u64) {
+    if let Some(state) = window.try_state::<StreamState>() {
+        state.add_output_tokens(turn_id, tokens);
+    }
+}
+
+/// Emit a `chat-stream-delta` event, recording its text for `cancel_and_keep`
+/// and its thinking for `thinking_transcripts`. Inline citations are
+/// normalized per turn (see `citations::CitationNormalizer`).
+pub fn emit_stream_delta(window: &tauri::Window, mut delta: StreamDelta) -> tauri::Result<()> {
+    if let Some(state) = window.try_state::<StreamState>() {
+        state.record_activity(&delta.turn_id, heartbeat::delta_phase(&delta));
+        if let Some(citations) = delta.inline_citations.take() {
+            let citations = state.normalize_citations(&delta.turn_id, citations);
+            delta.inline_citations = (!citations.is_empty()).then_some(citations);
+        }
+        if !delta.text.is_empty() || delta.thinking.is_some() {
+            state.append_delta(&delta);
+        }
+    }
+    let turn_id = delta.turn_id.clone();
+    session_events::emit(window, &turn_id, "chat-stream-delta", delta)
+}
+
+/// Record identifiers a provider returned for a turn (from response headers
+/// or stream events); they are reported with `chat-stream-done`
+pub fn record_response_metadata(window: &tauri::Window, turn_id: &str, metadata: ResponseMetadata) {
+    if let Some(state) = window.try_state::<StreamState>() {
+        state.merge_metadata(turn_id, metadata);
+    }
+}
+
+/// Emit a `chat-stream-done` event with the turn's response metadata,
+/// preceded by the turn's final `chat-usage` event with its metrics
+pub fn emit_stream_done(window: &tauri::Window, turn_id: &str) -> tauri::Result<()> {
+    let state = window.try_state::<StreamState>();
+    if let Some(usage) = state.as_ref().and_then(|state| state.final_usage(turn_id)) {
+        if let Some(metrics) = &usage.metrics {
+            llm_logger::log_turn_metrics("chat", metrics);
+        }
+        if let Err(err) = session_events::emit(window, turn_id, "chat-usage", usage) {
+            eprintln!("Failed to emit chat-usage event: {}", err);
+        }
+    }
+    let metadata = state.and_then(|state| state.turn_metadata(turn_id));
+    session_events::emit(
+        window,
+        turn_id,
+        "chat-stream-done",
+        StreamDoneEvent {
+            turn_id: turn_id.to_string(),
+            metadata,
+        },
+    )
 }
 
+/// Message of a task that failed to complete
+fn join_error_message(error: tauri::Error) -> String {
+    let tauri::Error::JoinError(error) = error else {
+        return error.to_string();
+    };
+    if !error.is_panic() {
+        return "Chat task was aborted".to_string();
+    }
+    let payload = error.into_panic();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    format!("Chat task panicked: {}", message)
+}
+
+/// Run a turn's streaming as its own task, so it completes even if the app is
+/// backgrounded mid-stream (see `background`). If the task panics or is
+/// aborted, `chat-stream-error` is emitted with the text streamed so far, so
+/// the UI never waits on a stream that will not finish. The caller ends the
+/// turn afterwards, clearing its cancellation token.
+async fn supervise_turn<F>(
+    state: &StreamState,
+    window: &tauri::Window,
+    turn_id: &str,
+    task: F,
+) -> Result<(), SidestreamError>
+where
+    F: std::future::Future<Output = Result<(), SidestreamError>> + Send + 'static,
+{
+    let error = match tauri::async_runtime::spawn(task).await {
+        Ok(result) => return result,
+        Err(e) => join_error_message(e),
+    };
+    eprintln!("[LLM] Turn {} failed: {}", turn_id, error);
+
+    let event = StreamErrorEvent {
+        turn_id: turn_id.to_string(),
+        error: error.clone(),
+        partial_text: state.turn_text(turn_id).unwrap_or_default(),
+    };
+    if let Err(err) = session_events::emit(window, turn_id, "chat-stream-error", event) {
+        eprintln!("Failed to emit chat-stream-error event: {}", err);
+    }
+    Err(error.into())
+}
+
+/// Stop the stream and discovery run for a turn, or every in-flight turn
+/// and discovery run when `turn_id` is omitted
 #[tauri::command]
-pub async fn cancel_chat_stream(state: tauri::State<'_, StreamState>) -> Result<(), String> {
-    let mut token_guard = state.cancel_token.lock().await;
-    if let Some(token) = token_guard.take() {
-        token.cancel();
+pub async fn cancel_chat_stream(
+    state: tauri::State<'_, StreamState>,
+    turn_id: Option<String>,
+) -> Result<(), String> {
+    let turns = state.turns.lock();
+    let discoveries = state.discoveries.lock();
+    match turn_id {
+        Some(turn_id) => {
+            if let Some(turn) = turns.get(&turn_id) {
+                turn.cancel_token.cancel();
+            }
+            if let Some(cancel_token) = discoveries.get(&turn_id) {
+                cancel_token.cancel();
+            }
+        }
+        None => {
+            turns.values().for_each(|turn| turn.cancel_token.cancel());
+            discoveries.values().for_each(CancellationToken::cancel);
+        }
     }
     Ok(())
 }
 
+/// Stop the stream for a turn and return the text it produced so far, so the
+/// frontend can keep it as a (truncated) assistant message instead of
+/// discarding it. The turn stays registered until its task ends, so the
+/// rest of its output (thinking, metadata, tables) is still recorded.
+#[tauri::command]
+pub async fn cancel_and_keep(
+    state: tauri::State<'_, StreamState>,
+    turn_id: String,
+) -> Result<String, String> {
+    Ok(state
+        .turns
+        .lock()
+        .get(&turn_id)
+        .map(|turn| {
+            turn.cancel_token.cancel();
+            turn.output.text.clone()
+        })
+        .unwrap_or_default())
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ChatMessage {
+    /// Frontend message id, used to keep pinned messages in context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub role: String,
     pub content: serde_json::Value,
+    /// Anthropic thinking blocks captured from this (assistant) message's
+    /// stream via `chat-thinking-blocks`, replayed ahead of the content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking_blocks: Option<Vec<serde_json::Value>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -115,6 +473,67 @@ pub struct StreamEvent {
     pub turn_id: String,
 }
 
+/// Identifiers a provider returned for a response: the exact model snapshot
+/// that served it and the ids to quote when filing an issue with the provider
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseMetadata {
+    pub provider: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ResponseMetadata {
+    /// The request id from a provider's response headers
+    pub fn from_headers(provider: &str, headers: &reqwest::header::HeaderMap) -> Self {
+        let request_id = ["request-id", "x-request-id"]
+            .iter()
+            .find_map(|name| headers.get(*name)?.to_str().ok())
+            .map(|id| id.to_string());
+        Self {
+            provider: provider.to_string(),
+            request_id,
+            ..Default::default()
+        }
+    }
+
+    /// Fill in the fields `other` knows, keeping the rest
+    fn merge(&mut self, other: ResponseMetadata) {
+        if !other.provider.is_empty() {
+            self.provider = other.provider;
+        }
+        if other.model.is_some() {
+            self.model = other.model;
+        }
+        if other.response_id.is_some() {
+            self.response_id = other.response_id;
+        }
+        if other.request_id.is_some() {
+            self.request_id = other.request_id;
+        }
+    }
+}
+
+/// Event payload for stream completion
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamDoneEvent {
+    pub turn_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ResponseMetadata>,
+}
+
+/// Event payload when a turn's task panicked or was aborted
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamErrorEvent {
+    pub turn_id: String,
+    pub error: String,
+    /// Text streamed before the failure
+    pub partial_text: String,
+}
+
 /// Event payload for container ID updates (Claude code execution)
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ContainerIdEvent {
@@ -122,9 +541,66 @@ pub struct ContainerIdEvent {
     pub container_id: String,
 }
 
-/// Determine which provider to use based on model name
-fn get_provider_for_model(model: &str) -> &'static str {
-    if model.starts_with("gpt") || model.starts_with("o3") || model.starts_with("o4") {
+/// Event payload carrying the complete thinking blocks (with signatures) of
+/// an Anthropic response, so the frontend can store them on the message and
+/// send them back in `ChatMessage::thinking_blocks`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ThinkingBlocksEvent {
+    pub turn_id: String,
+    pub blocks: Vec<serde_json::Value>,
+}
+
+/// Event payload for partial tool-call arguments as they stream in, so the UI
+/// can show a tool call forming before its input is complete
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ToolInputDeltaEvent {
+    pub turn_id: String,
+    pub tool_use_id: String,
+    pub tool_name: String,
+    pub partial_json: String,
+}
+
+/// Event payload with a request's input token usage, including prompt cache
+/// writes and reads, so users can see whether caching is paying off. Sent
+/// again with `metrics` when the turn ends (see `turn_metrics`).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatUsageEvent {
+    pub turn_id: String,
+    pub provider: String,
+    pub input_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    /// Latency and throughput, on the event repeated when the turn ends
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<TurnMetrics>,
+}
+
+/// Event payload for a web search the model runs, so the UI can show what
+/// is being searched for during the tool phase
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchQueryEvent {
+    pub turn_id: String,
+    pub query: String,
+}
+
+/// Event payload asking the frontend to run a user-defined tool and answer
+/// with `submit_tool_result`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ToolCallRequestEvent {
+    pub turn_id: String,
+    pub tool_use_id: String,
+    pub tool_name: String,
+    pub input: serde_json::Value,
+}
+
+/// Determine which provider to use based on model name. User mappings (see
+/// `provider_mappings`) take precedence over the built-in prefixes.
+pub fn get_provider_for_model(model: &str) -> &'static str {
+    if let Some(provider) = provider_mappings::mapped_provider(model) {
+        provider
+    } else if model.starts_with(OPENROUTER_MODEL_PREFIX) {
+        "openrouter"
+    } else if model.starts_with("gpt") || model.starts_with("o3") || model.starts_with("o4") {
         "openai"
     } else if model.starts_with("gemini") {
         "google"
@@ -133,12 +609,201 @@ fn get_provider_for_model(model: &str) -> &'static str {
     }
 }
 
+/// Run a single prompt to completion without streaming and return the reply
+/// text. Used by backend-driven features (automations, workflows) that have
+/// no window to stream into.
+pub async fn complete_prompt(
+    app: &tauri::AppHandle,
+    model: &str,
+    system_prompt: Option<&str>,
+    prompt: &str,
+    web_search_enabled: bool,
+) -> Result<String, SidestreamError> {
+    let system_prompt = system_prompt.filter(|s| !s.trim().is_empty());
+    let prompt = &content_filters::mask_text(app, prompt);
+
+    match get_provider_for_model(model) {
+        "openai" => {
+            let client = OpenAIClient::new(get_api_key_async(app, "openai").await?);
+            let mut input = Vec::new();
+            if let Some(system) = system_prompt {
+                input.push(serde_json::json!({"type": "message", "role": "system", "content": system}));
+            }
+            input.push(serde_json::json!({"type": "message", "role": "user", "content": prompt}));
+            let mut body = serde_json::json!({"model": model, "input": input});
+            if web_search_enabled {
+                body["tools"] = serde_json::json!([{"type": "web_search"}]);
+            }
+            let response = client.send_request(&body).await?;
+            Ok(extract_output_text(&response))
+        }
+        "google" => {
+            let client = GeminiClient::new(get_api_key_async(app, "google").await?);
+            let mut body = serde_json::json!({
+                "contents": [{"role": "user", "parts": [{"text": prompt}]}]
+            });
+            if let Some(system) = system_prompt {
+                body["systemInstruction"] = serde_json::json!({"parts": [{"text": system}]});
+            }
+            if web_search_enabled {
+                body["tools"] = serde_json::json!([{"google_search": {}}]);
+            }
+            client.send_request(model, &body).await
+        }
+        "openrouter" => {
+            let client = OpenRouterClient::new(get_api_key_async(app, "openrouter").await?);
+            let mut messages = Vec::new();
+            if let Some(system) = system_prompt {
+                messages.push(serde_json::json!({"role": "system", "content": system}));
+            }
+            messages.push(serde_json::json!({"role": "user", "content": prompt}));
+            let body = client.build_chat_request(&OpenRouterChatRequestConfig {
+                model: model.to_string(),
+                messages,
+                system_prompt: None,
+                web_search_enabled,
+            });
+            client.send_request(&body).await
+        }
+        _ => {
+            let client = AnthropicClient::new(get_api_key_async(app, "anthropic").await?);
+            let mut body = serde_json::json!({
+                "model": model,
+                "max_tokens": 16000,
+                "messages": [{"role": "user", "content": prompt}]
+            });
+            if let Some(system) = system_prompt {
+                body["system"] = serde_json::json!(system);
+            }
+            if web_search_enabled {
+                body["tools"] = serde_json::json!([{"type": "web_search_20250305", "name": "web_search"}]);
+            }
+            let response = client.send_request(&body).await?;
+            Ok(extract_response_text(&response))
+        }
+    }
+}
+
+/// Normalize attachments before they reach a provider: office documents
+/// (XLSX/DOCX) that providers reject as document blocks become text blocks
+pub fn prepare_attachments(messages: Vec<ChatMessage>) -> Vec<ChatMessage> {
+    messages
+        .into_iter()
+        .map(|m| ChatMessage {
+            id: m.id,
+            content: convert_office_documents(&m.content),
+            role: m.role,
+            thinking_blocks: m.thinking_blocks,
+        })
+        .collect()
+}
+
+const GEMINI_THINKING_BUDGET_FIELD: &str = "geminiThinkingBudget";
+
+fn stored_gemini_thinking_budget(app: &tauri::AppHandle, session_id: &str) -> Option<i32> {
+    get_stored_session(app, session_id)
+        .ok()
+        .flatten()
+        .and_then(|session| session[GEMINI_THINKING_BUDGET_FIELD].as_i64())
+        .map(|budget| budget as i32)
+}
+
+/// The Gemini thinking setting to send: what the request asked for (a
+/// budget, else a named level, "off" included), or else the stored budget
+fn pick_gemini_thinking(
+    budget: Option<i32>,
+    level: Option<String>,
+    stored: impl FnOnce() -> Option<i32>,
+) -> Option<String> {
+    budget
+        .map(|budget| budget.to_string())
+        .or(level)
+        .or_else(|| stored().map(|budget| budget.to_string()))
+}
+
+/// Pick the Gemini thinking setting for a request (see
+/// `pick_gemini_thinking`). A budget passed by the frontend is remembered
+/// on the session when it differs from the stored one, except in incognito
+/// turns.
+fn resolve_gemini_thinking(
+    app: &tauri::AppHandle,
+    session_id: Option<&str>,
+    budget: Option<i32>,
+    level: Option<String>,
+    incognito: bool,
+) -> Option<String> {
+    if let (Some(session_id), Some(budget)) = (session_id.filter(|_| !incognito), budget) {
+        if stored_gemini_thinking_budget(app, session_id) != Some(budget) {
+            if let Err(err) = store_gemini_thinking_budget(app, session_id, Some(budget)) {
+                // The session may not have been saved yet; the budget still applies to this request
+                eprintln!("Failed to store Gemini thinking budget: {}", err);
+            }
+        }
+    }
+    pick_gemini_thinking(budget, level, || {
+        session_id.and_then(|session_id| stored_gemini_thinking_budget(app, session_id))
+    })
+}
+
+fn store_gemini_thinking_budget(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    budget: Option<i32>,
+) -> Result<(), String> {
+    update_stored_session(app, session_id, |fields| {
+        match budget {
+            Some(budget) => {
+                fields.insert(GEMINI_THINKING_BUDGET_FIELD.to_string(), serde_json::json!(budget));
+            }
+            None => {
+                fields.remove(GEMINI_THINKING_BUDGET_FIELD);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Set or clear (None) a session's Gemini thinking budget
+#[tauri::command]
+pub fn set_session_gemini_thinking_budget(
+    app: tauri::AppHandle,
+    session_id: String,
+    budget: Option<i32>,
+) -> Result<(), String> {
+    store_gemini_thinking_budget(&app, &session_id, budget)
+}
+
+/// Session field holding each turn's `ResponseMetadata`, keyed by turn id
+pub const RESPONSE_METADATA_FIELD: &str = "responseMetadata";
+
+fn store_response_metadata(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    turn_id: &str,
+    metadata: &ResponseMetadata,
+) {
+    let stored = update_stored_session(app, session_id, |fields| {
+        let by_turn = fields
+            .entry(RESPONSE_METADATA_FIELD)
+            .or_insert_with(|| serde_json::json!({}));
+        if !by_turn.is_object() {
+            *by_turn = serde_json::json!({});
+        }
+        by_turn[turn_id] = serde_json::to_value(metadata).map_err(|e| e.to_string())?;
+        Ok(())
+    });
+    if let Err(e) = stored {
+        eprintln!("Failed to store response metadata: {}", e);
+    }
+}
+
 #[tauri::command]
 pub async fn send_chat_message(
     app: tauri::AppHandle,
     window: tauri::Window,
     state: tauri::State<'_, StreamState>,
-    model: String,
+    model: Option<String>,                  // Falls back to the chat model default when omitted; "auto" routes by prompt
     messages: Vec<ChatMessage>,
     system_prompt: Option<String>,
     opus46_thinking_level: Option<String>,  // Adaptive thinking effort for Opus 4.8 / Opus 4.6 / Sonnet 4.6: "off", "low", "medium", "high", "xhigh", "max", "adaptive". (Param name kept for serde compat with the JS-side `opus46ThinkingLevel`.)
@@ -146,70 +811,157 @@ pub async fn send_chat_message(
     code_execution_enabled: bool,           // For Anthropic/OpenAI code execution
     reasoning_level: Option<String>,        // For OpenAI: "off", "low", "medium", "high"
     gemini_thinking_level: Option<String>,  // For Gemini: "off", "on", "low", "medium", "high"
+    gemini_thinking_budget: Option<i32>,    // For Gemini 2.5: explicit thinking token budget (persisted per session)
     session_id: Option<String>,             // For OpenAI prompt caching
     turn_id: String,                        // Unique ID for this conversation turn
     anthropic_container_id: Option<String>, // Claude code execution container ID for sandbox persistence
     openai_container_id: Option<String>,    // OpenAI code interpreter container ID for file persistence
-) -> Result<(), String> {
-    // Create a cancellation token for this stream
-    let cancel_token = CancellationToken::new();
-    {
-        let mut token_guard = state.cancel_token.lock().await;
-        *token_guard = Some(cancel_token.clone());
+    incognito: Option<bool>,                // Write nothing for this turn (see `incognito`)
+) -> Result<(), SidestreamError> {
+    let incognito = incognito.unwrap_or(false);
+    if let Some(session_id) = &session_id {
+        session_lock::ensure_unlocked(&app, session_id)?;
     }
+    // One turn at a time per session; held until this turn ends
+    let send_locks = window.state::<SendLocks>();
+    let _send_guard = match &session_id {
+        Some(session_id) => Some(send_locks.acquire(session_id, &turn_id)?),
+        None => None,
+    };
+    let model = model.unwrap_or_else(|| workspaces::default_chat_model(&app));
+    let messages = prepare_attachments(snippets::expand_messages(&app, messages));
+    // Mask (or stop on) secrets and other filtered text before it leaves
+    let messages = content_filters::apply_content_filters(&app, &window, &turn_id, messages)?;
+    // Show the model the images its previous reply generated, if enabled
+    let messages = tool_images::reattach_tool_images(&app, session_id.as_deref(), messages);
+    // Send attachments from long ago as placeholders, if enabled
+    let (messages, elided) = attachment_elision::elide_old_attachments(&app, session_id.as_deref(), messages);
+    // Trim long conversations to the context budget, keeping pinned messages
+    let message_count = messages.len();
+    let messages = pins::assemble_context(&app, session_id.as_deref(), messages);
+    let trimmed = request_adjustments::context_trimmed(message_count, messages.len());
+    request_adjustments::emit_request_adjusted(&window, &turn_id, elided.into_iter().chain(trimmed).collect());
+
+    // Resolve "auto" to a concrete model and tell the frontend which one it was
+    let model = if model == AUTO_MODEL {
+        let routed = model_routing::route_model(&app, &turn_id, &messages).await;
+        let model = routed.model.clone();
+        if let Err(err) = session_events::emit(&window, &turn_id, "chat-model-routed", routed) {
+            eprintln!("Failed to emit model-routed event: {}", err);
+        }
+        model
+    } else {
+        model
+    };
+    if !incognito {
+        model_usage::record_model_use(&app, &model);
+    }
+    let system_prompt =
+        system_prompts::layered_system_prompt(&app, system_prompt.as_deref(), session_id.as_deref());
+
+    // The request's budget or level wins; the session's stored budget fills in
+    let gemini_thinking_level = resolve_gemini_thinking(
+        &app,
+        session_id.as_deref(),
+        gemini_thinking_budget,
+        gemini_thinking_level,
+        incognito,
+    );
+
+    // Register the turn so it can be cancelled on its own
+    let cancel_token = state.begin_turn(&turn_id);
+    tauri::async_runtime::spawn(heartbeat::run(window.clone(), turn_id.clone()));
 
     // Route to the appropriate provider based on model
     let provider = get_provider_for_model(&model);
 
-    match provider {
-        "openai" => {
-            send_chat_message_openai(
-                &app,
-                &window,
-                cancel_token,
-                model,
-                messages,
-                system_prompt,
-                web_search_enabled,
-                code_execution_enabled,
-                reasoning_level,
-                session_id,
-                turn_id,
-                openai_container_id,
-            )
-            .await
-        }
-        "google" => {
-            send_chat_message_gemini(
-                &app,
-                &window,
-                cancel_token,
-                model,
-                messages,
-                system_prompt,
-                web_search_enabled,
-                gemini_thinking_level,
-                turn_id,
-            )
-            .await
+    background::ensure_notification_permission(&app);
+    let turn_model = model.clone();
+    let task_app = app.clone();
+    let task_window = window.clone();
+    let task_turn_id = turn_id.clone();
+    let task_session_id = session_id.clone();
+    let turn = async move {
+        let window = task_window;
+        let app = task_app;
+        let turn_id = task_turn_id;
+        let session_id = task_session_id;
+        match provider {
+            "openai" => {
+                send_chat_message_openai(
+                    &app,
+                    &window,
+                    cancel_token,
+                    model,
+                    messages,
+                    system_prompt,
+                    web_search_enabled,
+                    code_execution_enabled,
+                    reasoning_level,
+                    session_id,
+                    turn_id,
+                    openai_container_id,
+                )
+                .await
+            }
+            "openrouter" => {
+                let config = OpenRouterChatRequestConfig {
+                    messages: openrouter_messages(&messages),
+                    model,
+                    system_prompt,
+                    web_search_enabled,
+                };
+                send_chat_message_openrouter(&app, &window, cancel_token, config, turn_id).await
+            }
+            "google" => {
+                send_chat_message_gemini(
+                    &app,
+                    &window,
+                    cancel_token,
+                    model,
+                    messages,
+                    system_prompt,
+                    web_search_enabled,
+                    gemini_thinking_level,
+                    turn_id,
+                )
+                .await
+            }
+            "anthropic" | _ => {
+                send_chat_message_anthropic(
+                    &app,
+                    &window,
+                    cancel_token,
+                    model,
+                    messages,
+                    system_prompt,
+                    opus46_thinking_level,
+                    web_search_enabled,
+                    code_execution_enabled,
+                    turn_id,
+                    anthropic_container_id,
+                )
+                .await
+            }
         }
-        "anthropic" | _ => {
-            send_chat_message_anthropic(
-                &app,
-                &window,
-                cancel_token,
-                model,
-                messages,
-                system_prompt,
-                opus46_thinking_level,
-                web_search_enabled,
-                code_execution_enabled,
-                turn_id,
-                anthropic_container_id,
-            )
-            .await
+    };
+    let result = supervise_turn(&state, &window, &turn_id, incognito::scope(incognito, turn)).await;
+
+    let output = state.end_turn(&turn_id).unwrap_or_default();
+    if let Some(session_id) = session_id.as_ref().filter(|_| !incognito) {
+        thinking_transcripts::record(&app, session_id, &turn_id, &turn_model, &output.thinking);
+        if let Some(metadata) = &output.metadata {
+            store_response_metadata(&app, session_id, &turn_id, metadata);
         }
     }
+    background::finish_turn(&app, &turn_id, session_id.as_deref(), &output.text, &result, incognito);
+    if result.is_ok() && !incognito {
+        tables::record_turn(&window, &turn_id, &output.text);
+    }
+    if incognito {
+        incognito::emit_complete(&window, &turn_id);
+    }
+    result
 }
 
 /// Send a voice message with native audio to Gemini
@@ -227,34 +979,352 @@ pub async fn send_voice_message(
     web_search_enabled: bool,
     gemini_thinking_level: Option<String>,
     turn_id: String,
-) -> Result<(), String> {
-    // Create a cancellation token for this stream
-    let cancel_token = CancellationToken::new();
-    {
-        let mut token_guard = state.cancel_token.lock().await;
-        *token_guard = Some(cancel_token.clone());
+    session_id: Option<String>, // Voice turns share the session's send lock with text turns
+) -> Result<(), SidestreamError> {
+    if let Some(session_id) = &session_id {
+        session_lock::ensure_unlocked(&app, session_id)?;
     }
+    let send_locks = window.state::<SendLocks>();
+    let _send_guard = match &session_id {
+        Some(session_id) => Some(send_locks.acquire(session_id, &turn_id)?),
+        None => None,
+    };
+    let messages = prepare_attachments(messages);
+    let system_prompt = system_prompts::layered_system_prompt(&app, system_prompt.as_deref(), None);
+    model_usage::record_model_use(&app, &model);
 
-    send_voice_message_impl(
-        &app,
-        &window,
-        cancel_token,
-        model,
-        messages,
-        audio_base64,
-        system_prompt,
-        web_search_enabled,
-        gemini_thinking_level,
-        turn_id,
-    )
-    .await
+    // Register the turn so it can be cancelled on its own
+    let cancel_token = state.begin_turn(&turn_id);
+    tauri::async_runtime::spawn(heartbeat::run(window.clone(), turn_id.clone()));
+
+    let task_window = window.clone();
+    let task_turn_id = turn_id.clone();
+    let turn = async move {
+        send_voice_message_impl(
+            &app,
+            &task_window,
+            cancel_token,
+            model,
+            messages,
+            audio_base64,
+            system_prompt,
+            web_search_enabled,
+            gemini_thinking_level,
+            task_turn_id,
+        )
+        .await
+    };
+    let result = supervise_turn(&state, &window, &turn_id, turn).await;
+    state.end_turn(&turn_id);
+    result
 }
 
-/// Transcribe audio using Gemini (transcription only, no chat response)
+/// Transcribe audio using Gemini (transcription only, no chat response).
+/// Either recorded mic audio (`audio_base64`, WAV unless `mime_type` says
+/// otherwise) or an audio file on disk (`file_path`: mp3, m4a, ogg, ...).
+/// Voice commands are only detected in mic recordings.
 #[tauri::command]
 pub async fn transcribe_audio_gemini(
     app: tauri::AppHandle,
-    audio_base64: String,
-) -> Result<String, String> {
-    transcribe_audio_gemini_impl(&app, audio_base64).await
+    audio_base64: Option<String>,
+    file_path: Option<String>,
+    mime_type: Option<String>,
+) -> Result<String, SidestreamError> {
+    if let Some(path) = file_path {
+        let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read audio file: {}", e))?;
+        let mime_type = mime_type.unwrap_or_else(|| audio_mime_type(&path).to_string());
+        return transcribe_audio_file_gemini_impl(&app, bytes, &mime_type).await;
+    }
+
+    let audio_base64 = audio_base64.ok_or("No audio to transcribe")?;
+    match mime_type {
+        Some(mime_type) if mime_type != "audio/wav" => {
+            let bytes = BASE64
+                .decode(&audio_base64)
+                .map_err(|e| format!("Invalid audio data: {}", e))?;
+            transcribe_audio_file_gemini_impl(&app, bytes, &mime_type).await
+        }
+        _ => {
+            let transcription = transcribe_audio_gemini_impl(&app, audio_base64).await?;
+            Ok(voice_intents::process_transcription(&app, transcription))
+        }
+    }
+}
+
+// ============================================================================
+// User-defined tools
+// ============================================================================
+
+/// Names of the server-side tools, which user tools can't shadow
+const RESERVED_TOOL_NAMES: &[&str] = &["web_search", "web_fetch", "code_execution"];
+
+/// How long a tool call waits for the frontend's result
+const TOOL_RESULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A tool the frontend implements, described by a JSON schema. Registered
+/// tools are offered to Anthropic models on every chat request; a call
+/// pauses the turn until the frontend submits the result.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserTool {
+    pub name: String,
+    pub description: String,
+    pub input_schema: serde_json::Value,
+}
+
+/// A call the model made to a user tool
+#[derive(Debug, Clone)]
+pub struct UserToolCall {
+    pub id: String,
+    pub name: String,
+    pub input: serde_json::Value,
+}
+
+/// The frontend's answer to a tool call
+#[derive(Debug)]
+pub struct ToolResult {
+    content: String,
+    is_error: bool,
+}
+
+static USER_TOOLS: parking_lot::RwLock<Vec<UserTool>> = parking_lot::RwLock::new(Vec::new());
+
+fn valid_tool_name(name: &str) -> bool {
+    (1..=64).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && !RESERVED_TOOL_NAMES.contains(&name)
+}
+
+/// Definitions of the registered user tools, in Anthropic's tool format
+pub fn user_tool_definitions() -> Vec<serde_json::Value> {
+    tool_definitions(&USER_TOOLS.read())
+}
+
+fn tool_definitions(tools: &[UserTool]) -> Vec<serde_json::Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            serde_json::json!({
+                "name": tool.name,
+                "description": tool.description,
+                "input_schema": tool.input_schema,
+            })
+        })
+        .collect()
+}
+
+/// Ask the frontend to run each call and collect `tool_result` blocks for the
+/// next request. Calls to unregistered tools, and calls the frontend doesn't
+/// answer in time, get an error result. Returns None if the turn is
+/// cancelled while waiting.
+pub async fn run_user_tool_calls(
+    window: &tauri::Window,
+    turn_id: &str,
+    calls: &[UserToolCall],
+    cancel_token: &CancellationToken,
+) -> Option<Vec<serde_json::Value>> {
+    let state = window.state::<StreamState>();
+    let mut pending = Vec::new();
+    for call in calls {
+        if !USER_TOOLS.read().iter().any(|tool| tool.name == call.name) {
+            let result = ToolResult {
+                content: format!("Unknown tool: {}", call.name),
+                is_error: true,
+            };
+            pending.push((call.id.clone(), Err(result)));
+            continue;
+        }
+        let (sender, receiver) = oneshot::channel();
+        state.tool_calls.lock().insert(call.id.clone(), sender);
+        let event = ToolCallRequestEvent {
+            turn_id: turn_id.to_string(),
+            tool_use_id: call.id.clone(),
+            tool_name: call.name.clone(),
+            input: call.input.clone(),
+        };
+        if let Err(err) = window.emit("tool-call-request", event) {
+            eprintln!("Failed to emit tool-call-request event: {}", err);
+        }
+        pending.push((call.id.clone(), Ok(receiver)));
+    }
+
+    let mut results = Vec::new();
+    for (tool_use_id, receiver) in pending {
+        let result = match receiver {
+            Err(result) => result,
+            Ok(receiver) => {
+                let answer = tokio::select! {
+                    _ = cancel_token.cancelled() => None,
+                    answer = tokio::time::timeout(TOOL_RESULT_TIMEOUT, receiver) => Some(answer),
+                };
+                let Some(answer) = answer else {
+                    let mut waiting = state.tool_calls.lock();
+                    for call in calls {
+                        waiting.remove(&call.id);
+                    }
+                    return None;
+                };
+                state.tool_calls.lock().remove(&tool_use_id);
+                answer.ok().and_then(Result::ok).unwrap_or(ToolResult {
+                    content: "The tool did not return a result".to_string(),
+                    is_error: true,
+                })
+            }
+        };
+        results.push(serde_json::json!({
+            "type": "tool_result",
+            "tool_use_id": tool_use_id,
+            "content": result.content,
+            "is_error": result.is_error,
+        }));
+    }
+    Some(results)
+}
+
+fn validate_user_tools(tools: &[UserTool]) -> Result<(), String> {
+    if let Some(tool) = tools.iter().find(|tool| !valid_tool_name(&tool.name)) {
+        return Err(format!("Invalid tool name: {}", tool.name));
+    }
+    if !tools.iter().all(|tool| tool.input_schema.is_object()) {
+        return Err("Tool input schemas must be JSON objects".to_string());
+    }
+    Ok(())
+}
+
+/// Replace the registered user tools
+#[tauri::command]
+pub fn register_user_tools(tools: Vec<UserTool>) -> Result<(), String> {
+    validate_user_tools(&tools)?;
+    *USER_TOOLS.write() = tools;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_user_tools() -> Vec<UserTool> {
+    USER_TOOLS.read().clone()
+}
+
+/// Answer a `tool-call-request`; the paused turn continues with the result
+#[tauri::command]
+pub fn submit_tool_result(
+    state: tauri::State<'_, StreamState>,
+    tool_use_id: String,
+    content: String,
+    is_error: Option<bool>,
+) -> Result<(), String> {
+    let sender = state
+        .tool_calls
+        .lock()
+        .remove(&tool_use_id)
+        .ok_or_else(|| format!("No tool call waiting: {}", tool_use_id))?;
+    sender
+        .send(ToolResult {
+            content,
+            is_error: is_error.unwrap_or(false),
+        })
+        .map_err(|_| "The turn is no longer waiting for this tool call".to_string())
+}
+
+// ============================================================================
+// Utility Completions
+// ============================================================================
+
+const TITLE_INSTRUCTION: &str = "Write a short title (at most 6 words) for a conversation that starts with the message below. Reply with the title only: no quotes, no trailing punctuation.";
+
+const SUMMARY_INSTRUCTION: &str = "Summarize the following text concisely, keeping key facts, decisions and open questions. Reply with the summary only.";
+
+/// Generate a short session title from the opening message. Uses the title
+/// generation model default unless a model is given. With a session id, the
+/// title is also stored on that session.
+#[tauri::command]
+pub async fn generate_session_title(
+    app: tauri::AppHandle,
+    text: String,
+    model: Option<String>,
+    session_id: Option<String>,
+) -> Result<String, SidestreamError> {
+    let model = model.unwrap_or_else(|| settings::model_defaults(&app).title_generation);
+    let schema = serde_json::json!({
+        "type": "object",
+        "required": ["title"],
+        "properties": {"title": {"type": "string"}}
+    });
+    let reply = structured_completion(&app, &model, Some(TITLE_INSTRUCTION), &text, &schema).await?;
+    let title = reply["title"].as_str().unwrap_or_default().trim().to_string();
+    if let Some(session_id) = session_id.filter(|_| !title.is_empty()) {
+        update_stored_session(&app, &session_id, |fields| {
+            fields.insert("title".to_string(), serde_json::Value::String(title.clone()));
+            Ok(())
+        })?;
+        #[cfg(desktop)]
+        tray::refresh_recent_sessions(&app);
+    }
+    Ok(title)
+}
+
+/// Summarize text (a conversation, a document, ...). Uses the summarization
+/// model default unless a model is given.
+#[tauri::command]
+pub async fn summarize_text(
+    app: tauri::AppHandle,
+    text: String,
+    model: Option<String>,
+) -> Result<String, SidestreamError> {
+    let model = model.unwrap_or_else(|| settings::model_defaults(&app).summarization);
+    let summary = complete_prompt(&app, &model, Some(SUMMARY_INSTRUCTION), &text, false).await?;
+    Ok(summary.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requested_gemini_thinking_wins_over_the_stored_budget() {
+        let stored = || Some(2048);
+        assert_eq!(pick_gemini_thinking(Some(512), Some("high".to_string()), stored).as_deref(), Some("512"));
+        assert_eq!(pick_gemini_thinking(None, Some("off".to_string()), stored).as_deref(), Some("off"));
+        assert_eq!(pick_gemini_thinking(None, None, stored).as_deref(), Some("2048"));
+        assert_eq!(pick_gemini_thinking(None, None, || None), None);
+    }
+
+    #[test]
+    fn response_metadata_merges_headers_and_stream_fields() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-request-id", "req_123".parse().unwrap());
+        let mut metadata = ResponseMetadata::from_headers("openai", &headers);
+        metadata.merge(ResponseMetadata {
+            provider: "openai".to_string(),
+            model: Some("gpt-5-2025-08-07".to_string()),
+            response_id: Some("resp_abc".to_string()),
+            request_id: None,
+        });
+
+        assert_eq!(metadata.request_id.as_deref(), Some("req_123"));
+        assert_eq!(metadata.model.as_deref(), Some("gpt-5-2025-08-07"));
+        assert_eq!(metadata.response_id.as_deref(), Some("resp_abc"));
+        assert_eq!(ResponseMetadata::from_headers("google", &Default::default()).request_id, None);
+    }
+
+    #[test]
+    fn user_tools_are_validated_and_offered_in_anthropic_format() {
+        let tool = |name: &str, schema: serde_json::Value| UserTool {
+            name: name.to_string(),
+            description: "Look up an order".to_string(),
+            input_schema: schema,
+        };
+        let schema = serde_json::json!({"type": "object", "properties": {"id": {"type": "string"}}});
+
+        assert!(validate_user_tools(&[tool("web_search", schema.clone())]).is_err());
+        assert!(validate_user_tools(&[tool("order lookup", schema.clone())]).is_err());
+        assert!(validate_user_tools(&[tool("order_lookup", serde_json::json!("object"))]).is_err());
+        let tools = [tool("order_lookup", schema.clone())];
+        validate_user_tools(&tools).unwrap();
+
+        assert_eq!(
+            tool_definitions(&tools),
+            [serde_json::json!({"name": "order_lookup", "description": "Look up an order", "input_schema": schema})]
+        );
+    }
 }
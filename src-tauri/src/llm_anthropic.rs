@@ -1,17 +1,27 @@
 use futures::StreamExt;
-use tauri::Emitter;
 use tokio_util::sync::CancellationToken;
 
+use crate::anthropic_betas::{self, BetaFeature};
 use crate::commands::get_api_key_async;
-use crate::llm::{tool_names, ChatMessage, ContainerIdEvent, ExecutionDelta, ExecutionStatus, GeneratedFile, StreamDelta, StreamEvent};
+use crate::error::SidestreamError;
+use crate::heartbeat;
+use crate::llm::{emit_stream_delta, emit_stream_done, emit_usage, record_output_tokens, record_response_metadata, run_user_tool_calls, set_stream_phase, tool_names, user_tool_definitions, ChatMessage, ChatUsageEvent, ContainerIdEvent, ExecutionDelta, ExecutionStatus, GeneratedFile, ResponseMetadata, SearchQueryEvent, StreamDelta, StreamEvent, ThinkingBlocksEvent, ToolInputDeltaEvent, UserToolCall};
 use crate::llm_logger;
+use crate::message_format::{self, anthropic as anthropic_format};
 use crate::mime_utils;
 use crate::providers::anthropic::{
-    add_cache_control_to_last_message, calculate_max_tokens as anthropic_calculate_max_tokens,
+    add_cache_control_to_last_message, assistant_content_with_thinking, calculate_max_tokens as anthropic_calculate_max_tokens,
     fetch_file_metadata, fetch_file_content_base64, is_code_execution_block, is_code_execution_result, parse_code_execution_result,
     parse_sse_event as anthropic_parse_sse_event, AnthropicClient, AnthropicStreamEvent,
     ChatRequestConfig as AnthropicChatRequestConfig, InlineCitation, ThinkingConfig,
 };
+use crate::session_events;
+use crate::sse::SseDecoder;
+use crate::sse_recording::{self, SseByteStream};
+use crate::web_search_cache;
+
+/// Most rounds of user tool calls in one turn
+const MAX_TOOL_ROUNDS: usize = 8;
 
 /// Send chat message using Anthropic API
 pub async fn send_chat_message_anthropic(
@@ -26,28 +36,139 @@ pub async fn send_chat_message_anthropic(
     code_execution_enabled: bool,
     turn_id: String,
     container_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), SidestreamError> {
     let api_key = get_api_key_async(app, "anthropic").await?;
     let client = AnthropicClient::new(api_key.clone());
 
+    let (mut config, beta_header) = chat_request_config(
+        &model,
+        &messages,
+        system_prompt,
+        opus46_thinking_level.as_deref(),
+        web_search_enabled,
+        code_execution_enabled,
+        container_id,
+    );
+
+    // Replay this conversation's recent searches rather than paying for
+    // them again (see `web_search_cache`)
+    let conversation = web_search_cache::conversation_key(&config.messages);
+    if web_search_enabled && config.extended_thinking.is_none() {
+        if let Some(prefill) = web_search_cache::cached_prefill(window, &turn_id, conversation) {
+            config.messages.push(prefill);
+        }
+    }
+
+    // Each response that ends in user tool calls is followed by another
+    // request carrying the tool results, until Claude answers without tools
+    let mut tool_rounds = 0;
+    let result: Result<(), SidestreamError> = async {
+        loop {
+            let body = client.build_chat_request(&config);
+
+            llm_logger::log_request("chat", &model, &body);
+
+            let response = client
+                .send_streaming_request_with_beta(&body, beta_header.as_deref())
+                .await
+                .inspect_err(|e| llm_logger::log_error("chat", e))?;
+
+            record_response_metadata(window, &turn_id, ResponseMetadata::from_headers("anthropic", response.headers()));
+
+            // Stream the response
+            let stream = sse_recording::response_stream(app, "anthropic", &model, response);
+            let StreamEnd::ToolUse { content, calls } =
+                stream_anthropic_message(window, cancel_token.clone(), &api_key, turn_id.clone(), stream).await?
+            else {
+                return Ok(());
+            };
+
+            if tool_rounds > MAX_TOOL_ROUNDS {
+                return Err("Claude kept calling tools after the tool call limit".into());
+            }
+            let results = if tool_rounds == MAX_TOOL_ROUNDS {
+                // Out of rounds: have Claude answer with what it has
+                calls
+                    .iter()
+                    .map(|call| {
+                        serde_json::json!({
+                            "type": "tool_result",
+                            "tool_use_id": call.id,
+                            "content": "Tool call limit reached for this turn. Answer without further tool calls.",
+                            "is_error": true,
+                        })
+                    })
+                    .collect()
+            } else {
+                match run_user_tool_calls(window, &turn_id, &calls, &cancel_token).await {
+                    Some(results) => results,
+                    None => {
+                        if let Err(err) = session_events::emit(window, &turn_id, "chat-stream-cancelled", StreamEvent { turn_id: turn_id.clone() }) {
+                            eprintln!("Failed to emit chat-stream-cancelled event: {}", err);
+                        }
+                        return Ok(());
+                    }
+                }
+            };
+            tool_rounds += 1;
+            // Keep the text before and after the tool calls apart
+            if content.iter().any(|block| block["type"] == "text") {
+                let delta = StreamDelta {
+                    turn_id: turn_id.clone(),
+                    text: "\n\n".to_string(),
+                    citations: None,
+                    inline_citations: None,
+                    thinking: None,
+                    execution: None,
+                };
+                if let Err(err) = emit_stream_delta(window, delta) {
+                    eprintln!("Failed to emit chat-stream-delta event: {}", err);
+                }
+            }
+            config.messages.push(serde_json::json!({"role": "assistant", "content": content}));
+            config.messages.push(serde_json::json!({"role": "user", "content": results}));
+        }
+    }
+    .await;
+    web_search_cache::finish_turn(&turn_id, result.is_ok().then_some(conversation));
+    result
+}
+
+/// The request config and anthropic-beta header for a chat turn. Also used
+/// to warm the prompt cache (see `cache_warmup`), which only helps if the
+/// prefix matches the turn's request exactly.
+pub fn chat_request_config(
+    model: &str,
+    messages: &[ChatMessage],
+    system_prompt: Option<String>,
+    thinking_level: Option<&str>,
+    web_search_enabled: bool,
+    code_execution_enabled: bool,
+    container_id: Option<String>,
+) -> (AnthropicChatRequestConfig, Option<String>) {
     // Build messages with cache breakpoint on the last message
-    // Transform 'file' blocks to 'document' blocks for Anthropic API compatibility
     let mut api_messages: Vec<serde_json::Value> = messages
         .iter()
         .map(|m| {
-            let content = transform_file_blocks_for_anthropic(&m.content);
+            let mut content = anthropic_format::content(&message_format::parse_content(&m.content));
+            if m.role == "assistant" {
+                if let Some(blocks) = &m.thinking_blocks {
+                    content = assistant_content_with_thinking(&content, blocks);
+                }
+            }
             serde_json::json!({"role": m.role, "content": content})
         })
         .collect();
 
     add_cache_control_to_last_message(&mut api_messages);
 
-    let level = opus46_thinking_level.as_deref().unwrap_or("off");
+    let level = thinking_level.unwrap_or("off");
     let thinking_enabled = level != "off";
-    let max_tokens = anthropic_calculate_max_tokens(&model, Some(level));
+    let max_tokens = anthropic_calculate_max_tokens(model, Some(level));
 
+    let user_tools = user_tool_definitions();
     let config = AnthropicChatRequestConfig {
-        model: model.clone(),
+        model: model.to_string(),
         messages: api_messages,
         system_prompt,
         max_tokens,
@@ -59,41 +180,131 @@ pub async fn send_chat_message_anthropic(
         web_search_enabled,
         code_execution_enabled,
         container_id: container_id.clone(),
+        user_tools,
     };
-    let body = client.build_chat_request(&config);
-
-    llm_logger::log_request("chat", &model, &body);
 
-    // Build the anthropic-beta header from features enabled this turn.
-    // - code-execution-2025-08-25: when code execution is on, or we're reusing a
-    //   container (container persistence requires the beta).
-    // - web-fetch-2025-09-10: paired with web_search — we register the web_fetch
-    //   tool whenever web_search is enabled so Claude can read specific pages,
-    //   not just see snippets (see providers/anthropic.rs).
-    let mut beta_parts: Vec<&'static str> = Vec::new();
+    // Beta features this turn needs (see `anthropic_betas`):
+    // - code execution: when it is on, or we're reusing a container
+    //   (container persistence requires the beta).
+    // - web fetch: paired with web_search — we register the web_fetch tool
+    //   whenever web_search is enabled so Claude can read specific pages, not
+    //   just see snippets (see providers/anthropic.rs).
+    // - fine-grained tool streaming: added when the request is sent, whenever
+    //   the body registers any tool (see `anthropic_betas`). Inputs may then
+    //   be partial or invalid JSON if the response is cut off, so parsing
+    //   below stays lenient. (Token-efficient tool use is built into Claude 4
+    //   models; no beta needed.)
+    // - interleaved thinking: when thinking is on and tools are registered,
+    //   so Claude can reason about search results before the next search
+    //   rather than only up front.
+    let any_tools = code_execution_enabled || web_search_enabled || !config.user_tools.is_empty();
+    let mut betas = Vec::new();
     if code_execution_enabled || container_id.is_some() {
-        beta_parts.push("code-execution-2025-08-25");
+        betas.push(BetaFeature::CodeExecution);
     }
     if web_search_enabled {
-        beta_parts.push("web-fetch-2025-09-10");
+        betas.push(BetaFeature::WebFetch);
     }
-    let beta_header_str = beta_parts.join(",");
-    let beta_header = if beta_header_str.is_empty() {
-        None
-    } else {
-        Some(beta_header_str.as_str())
-    };
-    let response = client
-        .send_streaming_request_with_beta(&body, beta_header)
-        .await
-        .map_err(|e| {
-            llm_logger::log_error("chat", &e);
-            e
-        })?;
-
-    // Stream the response
-    let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
+    if thinking_enabled && any_tools {
+        betas.push(BetaFeature::InterleavedThinking);
+    }
+    (config, anthropic_betas::beta_header(&betas))
+}
+
+/// The content blocks of a response as streamed, kept so a response that
+/// calls user tools can be sent back unchanged: thinking with its
+/// signature, server tool calls and their results, text and tool calls, in
+/// their original order
+#[derive(Default)]
+struct ResponseBlocks {
+    blocks: Vec<serde_json::Value>,
+}
+
+impl ResponseBlocks {
+    fn start(&mut self, block: &serde_json::Value) {
+        let block = match block["type"].as_str() {
+            // Citations can't be replayed from the parsed deltas; plain text is accepted
+            Some("text") => serde_json::json!({"type": "text", "text": ""}),
+            _ => block.clone(),
+        };
+        self.blocks.push(block);
+    }
+
+    /// Append a delta to a string field of the block being streamed
+    fn append(&mut self, field: &str, delta: &str) {
+        if let Some(block) = self.blocks.last_mut() {
+            let text = format!("{}{}", block[field].as_str().unwrap_or_default(), delta);
+            block[field] = serde_json::Value::String(text);
+        }
+    }
+
+    /// Finish the block being streamed; tool calls get their input
+    fn stop(&mut self, input_json: &str) {
+        let Some(block) = self.blocks.last_mut() else {
+            return;
+        };
+        if matches!(block["type"].as_str(), Some("tool_use" | "server_tool_use")) {
+            // Inputs cut off mid-stream count as empty
+            block["input"] = serde_json::from_str::<serde_json::Value>(input_json)
+                .ok()
+                .filter(|input| input.is_object())
+                .unwrap_or_else(|| serde_json::json!({}));
+        }
+    }
+
+    /// The blocks to send back, leaving out empty text blocks (which the
+    /// API rejects)
+    fn into_content(self) -> Vec<serde_json::Value> {
+        self.blocks
+            .into_iter()
+            .filter(|block| !(block["type"] == "text" && block["text"].as_str().unwrap_or_default().is_empty()))
+            .collect()
+    }
+}
+
+/// How an Anthropic response stream ended
+enum StreamEnd {
+    /// The turn is over (`chat-stream-done` or `chat-stream-cancelled` was emitted)
+    Finished,
+    /// Claude called user tools. `content` is the response to send back
+    /// ahead of the tool results.
+    ToolUse {
+        content: Vec<serde_json::Value>,
+        calls: Vec<UserToolCall>,
+    },
+}
+
+/// Handle a recorded Anthropic response stream, emitting chat events to the
+/// window (see `sse_recording`). Debug builds only.
+#[cfg(debug_assertions)]
+pub async fn stream_anthropic_response(
+    window: &tauri::Window,
+    cancel_token: CancellationToken,
+    api_key: &str,
+    turn_id: String,
+    stream: SseByteStream,
+) -> Result<(), SidestreamError> {
+    // A replay can't run tools, so its turn ends where the recording does
+    let end = stream_anthropic_message(window, cancel_token, api_key, turn_id.clone(), stream).await;
+    // Replayed searches aren't cached
+    web_search_cache::finish_turn(&turn_id, None);
+    if let StreamEnd::ToolUse { .. } = end? {
+        if let Err(err) = emit_stream_done(window, &turn_id) {
+            eprintln!("Failed to emit chat-stream-done event: {}", err);
+        }
+    }
+    Ok(())
+}
+
+/// Stream one response. Ends the turn, unless the response calls user tools.
+async fn stream_anthropic_message(
+    window: &tauri::Window,
+    cancel_token: CancellationToken,
+    api_key: &str,
+    turn_id: String,
+    mut stream: SseByteStream,
+) -> Result<StreamEnd, SidestreamError> {
+    let mut decoder = SseDecoder::new();
     let mut full_response = String::new();
     let mut current_block_type: Option<String> = None;
     let mut previous_block_type: Option<String> = None;
@@ -101,390 +312,518 @@ pub async fn send_chat_message_anthropic(
     let mut current_execution_tool_name: Option<String> = None;
     // Accumulate input JSON for tool use blocks (code comes via input_json_delta)
     let mut pending_tool_input_json: String = String::new();
+    // (id, name) of the tool use block whose input is currently streaming
+    let mut current_tool_use: Option<(String, String)> = None;
+    // Complete thinking blocks (text + signature) for replay in later requests;
+    // with interleaved thinking there can be several per response
+    let mut thinking_blocks: Vec<serde_json::Value> = Vec::new();
+    let mut current_thinking = String::new();
+    let mut current_signature = String::new();
+    // Calls to frontend-implemented tools, run once the response ends
+    let mut user_tool_calls: Vec<UserToolCall> = Vec::new();
+    let mut response_blocks = ResponseBlocks::default();
 
     loop {
         tokio::select! {
             // Check for cancellation
             _ = cancel_token.cancelled() => {
-                if let Err(err) = window.emit("chat-stream-cancelled", StreamEvent { turn_id: turn_id.clone() }) {
+                if let Err(err) = session_events::emit(window, &turn_id, "chat-stream-cancelled", StreamEvent { turn_id: turn_id.clone() }) {
                     eprintln!("Failed to emit chat-stream-cancelled event: {}", err);
                 }
-                return Ok(());
+                return Ok(StreamEnd::Finished);
             }
             // Process next chunk from stream
             chunk = stream.next() => {
-                match chunk {
-                    Some(Ok(bytes)) => {
-                        let text = String::from_utf8_lossy(&bytes);
-                        buffer.push_str(&text);
-
-                        // Parse SSE events
-                        while let Some(event_end) = buffer.find("\n\n") {
-                            let event = buffer[..event_end].to_string();
-                            buffer = buffer[event_end + 2..].to_string();
-
-                            for line in event.lines() {
-                                if let Some(data) = line.strip_prefix("data: ") {
-                                    match anthropic_parse_sse_event(data) {
-                                        AnthropicStreamEvent::Done => {
-                                            llm_logger::log_response_complete("chat", &full_response);
-                                            if let Err(err) = window.emit("chat-stream-done", StreamEvent { turn_id: turn_id.clone() }) {
-                                                eprintln!("Failed to emit chat-stream-done event: {}", err);
-                                            }
-                                            return Ok(());
-                                        }
-                                        AnthropicStreamEvent::MessageStart { container_id } => {
-                                            // Emit container ID to frontend for sandbox persistence
-                                            if let Some(id) = container_id {
-                                                llm_logger::log_feature_used("chat", &format!("Container ID received: {}", id));
-                                                if let Err(err) = window.emit("chat-container-id", ContainerIdEvent {
-                                                    turn_id: turn_id.clone(),
-                                                    container_id: id,
-                                                }) {
-                                                    eprintln!("Failed to emit chat-container-id event: {}", err);
-                                                }
-                                            }
+                let (events, ended) = match chunk {
+                    Some(Ok(bytes)) => (decoder.push(&bytes), false),
+                    Some(Err(message)) => return Err(SidestreamError::NetworkError { message }),
+                    // A last event without its closing blank line still counts
+                    None => (decoder.finish(), true),
+                };
+
+                for data in events {
+                    match anthropic_parse_sse_event(&data) {
+                        AnthropicStreamEvent::Done => {
+                            return Ok(end_response(window, &turn_id, &full_response, response_blocks, &mut thinking_blocks, user_tool_calls));
+                        }
+                        AnthropicStreamEvent::MessageStart { container_id, message_id, model, usage } => {
+                            record_response_metadata(window, &turn_id, ResponseMetadata {
+                                provider: "anthropic".to_string(),
+                                model,
+                                response_id: message_id,
+                                request_id: None,
+                            });
+                            if let Some(usage) = usage {
+                                emit_usage(window, ChatUsageEvent {
+                                    turn_id: turn_id.clone(),
+                                    provider: "anthropic".to_string(),
+                                    input_tokens: usage.input_tokens,
+                                    cache_creation_input_tokens: usage.cache_creation_input_tokens,
+                                    cache_read_input_tokens: usage.cache_read_input_tokens,
+                                    metrics: None,
+                                });
+                            }
+                            // Emit container ID to frontend for sandbox persistence
+                            if let Some(id) = container_id {
+                                llm_logger::log_feature_used("chat", &format!("Container ID received: {}", id));
+                                if let Err(err) = session_events::emit(window, &turn_id, "chat-container-id", ContainerIdEvent {
+                                    turn_id: turn_id.clone(),
+                                    container_id: id,
+                                }) {
+                                    eprintln!("Failed to emit chat-container-id event: {}", err);
+                                }
+                            }
+                        }
+                        AnthropicStreamEvent::ContentBlockStart { block_type, content_block } => {
+                            response_blocks.start(&content_block);
+                            current_block_type = Some(block_type.clone());
+                            current_tool_use = if matches!(block_type.as_str(), "tool_use" | "server_tool_use") {
+                                Some((
+                                    content_block["id"].as_str().unwrap_or_default().to_string(),
+                                    content_block["name"].as_str().unwrap_or_default().to_string(),
+                                ))
+                            } else {
+                                None
+                            };
+                            let tool_name = current_tool_use.as_ref().map_or("", |(_, name)| name.as_str());
+                            set_stream_phase(window, &turn_id, heartbeat::anthropic_block_phase(&block_type, tool_name));
+
+                            if block_type == "tool_use" {
+                                pending_tool_input_json.clear();
+                            }
+
+                            // Check for code execution tool use
+                            if is_code_execution_block(&block_type, &content_block) {
+                                // Just note the tool name - actual input comes via input_json_delta
+                                let name = content_block["name"].as_str().unwrap_or("").to_string();
+                                llm_logger::log_feature_used("chat", &format!("Code execution started: {}", name));
+                                current_execution_tool_name = Some(name);
+                                // Reset input JSON accumulator for this tool use
+                                pending_tool_input_json.clear();
+                            }
+                            // Check for code execution result
+                            else if is_code_execution_result(&block_type) {
+                                if let Some(result) = parse_code_execution_result(&block_type, &content_block) {
+                                    llm_logger::log_feature_used("chat", &format!("Code execution completed: {} files generated", result.files.len()));
+
+                                    // Determine status
+                                    let status = if let Some(ref error) = result.error {
+                                        ExecutionStatus::Failed { error: error.clone() }
+                                    } else if result.return_code.map(|c| c != 0).unwrap_or(false) {
+                                        ExecutionStatus::Failed {
+                                            error: format!("Exit code: {}", result.return_code.unwrap_or(-1))
                                         }
-                                        AnthropicStreamEvent::ContentBlockStart { block_type, content_block } => {
-                                            current_block_type = Some(block_type.clone());
-
-                                            // Check for code execution tool use
-                                            if is_code_execution_block(&block_type, &content_block) {
-                                                // Just note the tool name - actual input comes via input_json_delta
-                                                let name = content_block["name"].as_str().unwrap_or("").to_string();
-                                                llm_logger::log_feature_used("chat", &format!("Code execution started: {}", name));
-                                                current_execution_tool_name = Some(name);
-                                                // Reset input JSON accumulator for this tool use
-                                                pending_tool_input_json.clear();
-                                            }
-                                            // Check for code execution result
-                                            else if is_code_execution_result(&block_type) {
-                                                if let Some(result) = parse_code_execution_result(&block_type, &content_block) {
-                                                    llm_logger::log_feature_used("chat", &format!("Code execution completed: {} files generated", result.files.len()));
-
-                                                    // Determine status
-                                                    let status = if let Some(ref error) = result.error {
-                                                        ExecutionStatus::Failed { error: error.clone() }
-                                                    } else if result.return_code.map(|c| c != 0).unwrap_or(false) {
-                                                        ExecutionStatus::Failed {
-                                                            error: format!("Exit code: {}", result.return_code.unwrap_or(-1))
-                                                        }
-                                                    } else {
-                                                        ExecutionStatus::Completed
-                                                    };
-
-                                                    // Convert files to GeneratedFile, fetching metadata and content for persistence
-                                                    let mut files: Vec<GeneratedFile> = Vec::new();
-                                                    for f in result.files {
-                                                        // Try to fetch metadata to get the correct mime_type and filename
-                                                        let (final_filename, final_mime_type) = match fetch_file_metadata(&api_key, &f.file_id).await {
-                                                            Ok(metadata) => {
-                                                                // Use filename from metadata if it has an extension, otherwise construct it
-                                                                let filename = if metadata.filename.contains('.') {
-                                                                    metadata.filename
-                                                                } else {
-                                                                    // Add extension based on mime_type using shared utility
-                                                                    let ext = mime_utils::mime_to_extension_or_subtype(&metadata.mime_type);
-                                                                    format!("{}.{}", metadata.filename, ext)
-                                                                };
-                                                                (filename, Some(metadata.mime_type))
-                                                            }
-                                                            Err(e) => {
-                                                                eprintln!("Failed to fetch file metadata for {}: {}", f.file_id, e);
-                                                                // Fall back to original values
-                                                                (f.filename, f.mime_type)
-                                                            }
-                                                        };
-
-                                                        // Fetch file content for persistent storage
-                                                        let inline_data = match fetch_file_content_base64(&api_key, &f.file_id).await {
-                                                            Ok(data) => Some(data),
-                                                            Err(e) => {
-                                                                eprintln!("Failed to fetch file content for {}: {}", f.file_id, e);
-                                                                None
-                                                            }
-                                                        };
-
-                                                        // Generate image preview for image files
-                                                        let image_preview = if final_mime_type.as_ref().map(|m| m.starts_with("image/")).unwrap_or(false) {
-                                                            inline_data.as_ref().map(|data| {
-                                                                format!("data:{};base64,{}", final_mime_type.as_ref().unwrap(), data)
-                                                            })
-                                                        } else {
-                                                            None
-                                                        };
-
-                                                        files.push(GeneratedFile {
-                                                            file_id: f.file_id,
-                                                            filename: final_filename,
-                                                            mime_type: final_mime_type,
-                                                            image_preview,
-                                                            inline_data,
-                                                        });
-                                                    }
-
-                                                    // Emit execution completed delta
-                                                    let delta = StreamDelta {
-                                                        turn_id: turn_id.clone(),
-                                                        text: String::new(),
-                                                        citations: None,
-                                                        inline_citations: None,
-                                                        thinking: None,
-                                                        execution: Some(ExecutionDelta {
-                                                            tool_name: current_execution_tool_name.clone().unwrap_or_else(|| result.tool_name),
-                                                            stdout: result.stdout,
-                                                            stderr: result.stderr,
-                                                            status,
-                                                            code: None,
-                                                            files: if files.is_empty() { None } else { Some(files) },
-                                                        }),
-                                                    };
-                                                    if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                        eprintln!("Failed to emit chat-stream-delta event: {}", err);
-                                                    }
-
-                                                    // Clear current execution tracking
-                                                    current_execution_tool_name = None;
-                                                }
+                                    } else {
+                                        ExecutionStatus::Completed
+                                    };
+
+                                    // Convert files to GeneratedFile, fetching metadata and content for persistence
+                                    let mut files: Vec<GeneratedFile> = Vec::new();
+                                    for f in result.files {
+                                        // Try to fetch metadata to get the correct mime_type and filename
+                                        let (final_filename, final_mime_type) = match fetch_file_metadata(api_key, &f.file_id).await {
+                                            Ok(metadata) => {
+                                                // Use filename from metadata if it has an extension, otherwise construct it
+                                                let filename = if metadata.filename.contains('.') {
+                                                    metadata.filename
+                                                } else {
+                                                    // Add extension based on mime_type using shared utility
+                                                    let ext = mime_utils::mime_to_extension_or_subtype(&metadata.mime_type);
+                                                    format!("{}.{}", metadata.filename, ext)
+                                                };
+                                                (filename, Some(metadata.mime_type))
                                             }
-                                            else {
-                                                match block_type.as_str() {
-                                                    "thinking" => {
-                                                        llm_logger::log_feature_used("chat", "Extended Thinking block started");
-                                                    }
-                                                    "server_tool_use" => {
-                                                        // Server-side tool initiation — could be web_search, web_fetch, or another
-                                                        // Anthropic-hosted tool. Label by the actual `name` field so the log isn't
-                                                        // misleading.
-                                                        let tool_name = content_block["name"].as_str().unwrap_or("unknown");
-                                                        llm_logger::log_feature_used("chat", &format!("Server tool initiated: {}", tool_name));
-                                                        llm_logger::log_tool_event("chat", &format!("{} block start", tool_name), &content_block);
-                                                    }
-                                                    "web_search_tool_result" => {
-                                                        llm_logger::log_feature_used("chat", "Web Search results received");
-                                                        llm_logger::log_tool_event("chat", "web_search_tool_result content", &content_block);
-                                                        // We no longer emit these as source citations - we only use inline citations
-                                                    }
-                                                    "web_fetch_tool_result" => {
-                                                        // web_fetch returns plaintext page content (unlike web_search,
-                                                        // which returns encrypted snippet tokens). Logged for debug
-                                                        // visibility into what Claude is actually reading.
-                                                        llm_logger::log_feature_used("chat", "Web Fetch results received");
-                                                        llm_logger::log_tool_event("chat", "web_fetch_tool_result content", &content_block);
-                                                    }
-                                                    "text" => {
-                                                        // Insert paragraph break if previous block was non-text
-                                                        if let Some(prev) = &previous_block_type {
-                                                            if matches!(prev.as_str(), "thinking" | "server_tool_use" | "web_search_tool_result"
-                                                                | "bash_code_execution_tool_result" | "text_editor_code_execution_tool_result") {
-                                                                full_response.push_str("\n\n");
-                                                                let delta = StreamDelta {
-                                                                    turn_id: turn_id.clone(),
-                                                                    text: "\n\n".to_string(),
-                                                                    citations: None,
-                                                                    inline_citations: None,
-                                                                    thinking: None,
-                                                                    execution: None,
-                                                                };
-                                                                if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                                    eprintln!("Failed to emit chat-stream-delta event: {}", err);
-                                                                }
-                                                            }
-                                                        }
-                                                        // Citations will arrive via citations_delta events during streaming
-                                                        // and will be collected in pending_block_citations
-                                                    }
-                                                    _ => {}
-                                                }
+                                            Err(e) => {
+                                                eprintln!("Failed to fetch file metadata for {}: {}", f.file_id, e);
+                                                // Fall back to original values
+                                                (f.filename, f.mime_type)
                                             }
-                                        }
-                                        AnthropicStreamEvent::ContentBlockDelta { text, thinking, citation, input_json } => {
-                                            if let Some(t) = text {
-                                                full_response.push_str(&t);
-                                                let delta = StreamDelta {
-                                                    turn_id: turn_id.clone(),
-                                                    text: t,
-                                                    citations: None,
-                                                    inline_citations: None,
-                                                    thinking: None,
-                                                    execution: None,
-                                                };
-                                                if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                    eprintln!("Failed to emit chat-stream-delta event: {}", err);
-                                                }
+                                        };
+
+                                        // Fetch file content for persistent storage
+                                        let inline_data = match fetch_file_content_base64(api_key, &f.file_id).await {
+                                            Ok(data) => Some(data),
+                                            Err(e) => {
+                                                eprintln!("Failed to fetch file content for {}: {}", f.file_id, e);
+                                                None
                                             }
-                                            // Emit thinking deltas for ephemeral UI display
-                                            if let Some(thinking_text) = thinking {
+                                        };
+
+                                        // Generate image preview for image files
+                                        let image_preview = if final_mime_type.as_ref().map(|m| m.starts_with("image/")).unwrap_or(false) {
+                                            inline_data.as_ref().map(|data| {
+                                                format!("data:{};base64,{}", final_mime_type.as_ref().unwrap(), data)
+                                            })
+                                        } else {
+                                            None
+                                        };
+
+                                        files.push(GeneratedFile {
+                                            file_id: f.file_id,
+                                            filename: final_filename,
+                                            mime_type: final_mime_type,
+                                            image_preview,
+                                            inline_data,
+                                        });
+                                    }
+
+                                    // Emit execution completed delta
+                                    let delta = StreamDelta {
+                                        turn_id: turn_id.clone(),
+                                        text: String::new(),
+                                        citations: None,
+                                        inline_citations: None,
+                                        thinking: None,
+                                        execution: Some(ExecutionDelta {
+                                            tool_name: current_execution_tool_name.clone().unwrap_or_else(|| result.tool_name),
+                                            stdout: result.stdout,
+                                            stderr: result.stderr,
+                                            status,
+                                            code: None,
+                                            files: if files.is_empty() { None } else { Some(files) },
+                                        }),
+                                    };
+                                    if let Err(err) = emit_stream_delta(window, delta) {
+                                        eprintln!("Failed to emit chat-stream-delta event: {}", err);
+                                    }
+
+                                    // Clear current execution tracking
+                                    current_execution_tool_name = None;
+                                }
+                            }
+                            else {
+                                match block_type.as_str() {
+                                    "thinking" => {
+                                        llm_logger::log_feature_used("chat", "Extended Thinking block started");
+                                        current_thinking.clear();
+                                        current_signature.clear();
+                                    }
+                                    "redacted_thinking" => {
+                                        // Encrypted thinking; nothing to display, but it must be replayed
+                                        thinking_blocks.push(content_block.clone());
+                                    }
+                                    "server_tool_use" => {
+                                        // Server-side tool initiation — could be web_search, web_fetch, or another
+                                        // Anthropic-hosted tool. Label by the actual `name` field so the log isn't
+                                        // misleading.
+                                        let tool_name = content_block["name"].as_str().unwrap_or("unknown");
+                                        llm_logger::log_feature_used("chat", &format!("Server tool initiated: {}", tool_name));
+                                        llm_logger::log_tool_event("chat", &format!("{} block start", tool_name), &content_block);
+                                    }
+                                    "web_search_tool_result" => {
+                                        web_search_cache::record_result(&turn_id, &content_block);
+                                        llm_logger::log_feature_used("chat", "Web Search results received");
+                                        llm_logger::log_tool_event("chat", "web_search_tool_result content", &content_block);
+                                        // We no longer emit these as source citations - we only use inline citations
+                                    }
+                                    "web_fetch_tool_result" => {
+                                        // web_fetch returns plaintext page content (unlike web_search,
+                                        // which returns encrypted snippet tokens). Logged for debug
+                                        // visibility into what Claude is actually reading.
+                                        llm_logger::log_feature_used("chat", "Web Fetch results received");
+                                        llm_logger::log_tool_event("chat", "web_fetch_tool_result content", &content_block);
+                                    }
+                                    "text" => {
+                                        // Insert paragraph break if previous block was non-text
+                                        if let Some(prev) = &previous_block_type {
+                                            if matches!(prev.as_str(), "thinking" | "server_tool_use" | "web_search_tool_result"
+                                                | "bash_code_execution_tool_result" | "text_editor_code_execution_tool_result") {
+                                                full_response.push_str("\n\n");
                                                 let delta = StreamDelta {
                                                     turn_id: turn_id.clone(),
-                                                    text: String::new(),
+                                                    text: "\n\n".to_string(),
                                                     citations: None,
                                                     inline_citations: None,
-                                                    thinking: Some(thinking_text),
-                                                    execution: None,
-                                                };
-                                                if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                    eprintln!("Failed to emit chat-stream-delta event: {}", err);
-                                                }
-                                            }
-                                            // Emit citations immediately when they arrive
-                                            // The frontend will snap to word boundaries
-                                            if let Some(c) = citation {
-                                                let inline_citation = InlineCitation {
-                                                    url: c.url,
-                                                    title: c.title,
-                                                    cited_text: c.cited_text,
-                                                    char_offset: full_response.len(),
-                                                };
-                                                let delta = StreamDelta {
-                                                    turn_id: turn_id.clone(),
-                                                    text: String::new(),
-                                                    citations: None,
-                                                    inline_citations: Some(vec![inline_citation]),
                                                     thinking: None,
                                                     execution: None,
                                                 };
-                                                if let Err(err) = window.emit("chat-stream-delta", delta) {
+                                                if let Err(err) = emit_stream_delta(window, delta) {
                                                     eprintln!("Failed to emit chat-stream-delta event: {}", err);
                                                 }
                                             }
-                                            // Accumulate input_json for tool use blocks
-                                            if let Some(json_chunk) = input_json {
-                                                pending_tool_input_json.push_str(&json_chunk);
-                                            }
                                         }
-                                        AnthropicStreamEvent::ContentBlockStop => {
-                                            // If we just finished a code execution tool use block, emit the execution started event
-                                            if let Some(ref block_type) = current_block_type {
-                                                if block_type == "server_tool_use" && current_execution_tool_name.is_some() && !pending_tool_input_json.is_empty() {
-                                                    // Parse the accumulated input JSON
-                                                    if let Ok(input_obj) = serde_json::from_str::<serde_json::Value>(&pending_tool_input_json) {
-                                                        let tool_name = current_execution_tool_name.as_ref().unwrap();
-                                                        let code = match tool_name.as_str() {
-                                                            tool_names::BASH_CODE_EXECUTION => {
-                                                                input_obj["command"].as_str().map(|s| s.to_string())
-                                                            }
-                                                            tool_names::TEXT_EDITOR_CODE_EXECUTION => {
-                                                                let command = input_obj["command"].as_str().unwrap_or("");
-                                                                let path = input_obj["path"].as_str().unwrap_or("");
-                                                                let file_text = input_obj["file_text"].as_str();
-                                                                if let Some(content) = file_text {
-                                                                    Some(format!("# {} {}\n{}", command, path, content))
-                                                                } else {
-                                                                    Some(format!("# {} {}", command, path))
-                                                                }
-                                                            }
-                                                            _ => None,
-                                                        };
-
-                                                        // Emit execution started delta with actual code
-                                                        let delta = StreamDelta {
-                                                            turn_id: turn_id.clone(),
-                                                            text: String::new(),
-                                                            citations: None,
-                                                            inline_citations: None,
-                                                            thinking: None,
-                                                            execution: Some(ExecutionDelta {
-                                                                tool_name: tool_name.clone(),
-                                                                stdout: None,
-                                                                stderr: None,
-                                                                status: ExecutionStatus::Started,
-                                                                code,
-                                                                files: None,
-                                                            }),
-                                                        };
-                                                        if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                            eprintln!("Failed to emit chat-stream-delta event: {}", err);
-                                                        }
-                                                    }
-                                                    // Clear the accumulated JSON
-                                                    pending_tool_input_json.clear();
-                                                } else if block_type == "server_tool_use"
-                                                    && current_execution_tool_name.is_none()
-                                                    && !pending_tool_input_json.is_empty()
-                                                {
-                                                    // Non-code-execution server_tool_use (web_search or web_fetch). Log
-                                                    // the accumulated input JSON for debug visibility, then clear it
-                                                    // so a subsequent block's input doesn't accumulate stale bytes.
-                                                    let parsed = serde_json::from_str::<serde_json::Value>(&pending_tool_input_json)
-                                                        .unwrap_or_else(|_| serde_json::Value::String(pending_tool_input_json.clone()));
-                                                    // Generic label — the JSON itself reveals whether this was a
-                                                    // web_search ("query": ...) or web_fetch ("url": ...) call.
-                                                    llm_logger::log_tool_event("chat", "server_tool_use input", &parsed);
-                                                    pending_tool_input_json.clear();
-                                                }
-                                            }
-                                            previous_block_type = current_block_type.take();
+                                        // Citations will arrive via citations_delta events during streaming
+                                        // and will be collected in pending_block_citations
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        AnthropicStreamEvent::ContentBlockDelta { text, thinking, citation, input_json, signature } => {
+                            if let Some(sig) = signature {
+                                response_blocks.append("signature", &sig);
+                                current_signature.push_str(&sig);
+                            }
+                            if let Some(t) = text {
+                                response_blocks.append("text", &t);
+                                full_response.push_str(&t);
+                                let delta = StreamDelta {
+                                    turn_id: turn_id.clone(),
+                                    text: t,
+                                    citations: None,
+                                    inline_citations: None,
+                                    thinking: None,
+                                    execution: None,
+                                };
+                                if let Err(err) = emit_stream_delta(window, delta) {
+                                    eprintln!("Failed to emit chat-stream-delta event: {}", err);
+                                }
+                            }
+                            // Emit thinking deltas for ephemeral UI display
+                            if let Some(thinking_text) = thinking {
+                                response_blocks.append("thinking", &thinking_text);
+                                current_thinking.push_str(&thinking_text);
+                                let delta = StreamDelta {
+                                    turn_id: turn_id.clone(),
+                                    text: String::new(),
+                                    citations: None,
+                                    inline_citations: None,
+                                    thinking: Some(thinking_text),
+                                    execution: None,
+                                };
+                                if let Err(err) = emit_stream_delta(window, delta) {
+                                    eprintln!("Failed to emit chat-stream-delta event: {}", err);
+                                }
+                            }
+                            // Emit citations immediately when they arrive
+                            // The frontend will snap to word boundaries
+                            if let Some(c) = citation {
+                                let inline_citation = InlineCitation {
+                                    url: c.url,
+                                    title: c.title,
+                                    cited_text: c.cited_text,
+                                    char_offset: full_response.len(),
+                                };
+                                let delta = StreamDelta {
+                                    turn_id: turn_id.clone(),
+                                    text: String::new(),
+                                    citations: None,
+                                    inline_citations: Some(vec![inline_citation]),
+                                    thinking: None,
+                                    execution: None,
+                                };
+                                if let Err(err) = emit_stream_delta(window, delta) {
+                                    eprintln!("Failed to emit chat-stream-delta event: {}", err);
+                                }
+                            }
+                            // Accumulate input_json for tool use blocks and forward it
+                            // so the UI can render the call as it forms
+                            if let Some(json_chunk) = input_json {
+                                if let Some((tool_use_id, tool_name)) = &current_tool_use {
+                                    if !json_chunk.is_empty() {
+                                        if let Err(err) = session_events::emit(window, &turn_id, "chat-tool-input-delta", ToolInputDeltaEvent {
+                                            turn_id: turn_id.clone(),
+                                            tool_use_id: tool_use_id.clone(),
+                                            tool_name: tool_name.clone(),
+                                            partial_json: json_chunk.clone(),
+                                        }) {
+                                            eprintln!("Failed to emit chat-tool-input-delta event: {}", err);
                                         }
-                                        AnthropicStreamEvent::MessageDelta { container_id } => {
-                                            // Container ID arrives in message_delta for streaming responses
-                                            if let Some(id) = container_id {
-                                                llm_logger::log_feature_used("chat", &format!("Container ID received: {}", id));
-                                                if let Err(err) = window.emit("chat-container-id", ContainerIdEvent {
-                                                    turn_id: turn_id.clone(),
-                                                    container_id: id,
-                                                }) {
-                                                    eprintln!("Failed to emit chat-container-id event: {}", err);
+                                    }
+                                }
+                                pending_tool_input_json.push_str(&json_chunk);
+                            }
+                        }
+                        AnthropicStreamEvent::ContentBlockStop => {
+                            response_blocks.stop(&pending_tool_input_json);
+                            if current_block_type.as_deref() == Some("thinking") {
+                                thinking_blocks.push(serde_json::json!({
+                                    "type": "thinking",
+                                    "thinking": std::mem::take(&mut current_thinking),
+                                    "signature": std::mem::take(&mut current_signature),
+                                }));
+                            }
+                            // A finished user tool call; inputs cut off mid-stream count as empty
+                            if current_block_type.as_deref() == Some("tool_use") {
+                                if let Some((id, name)) = current_tool_use.clone() {
+                                    let input = serde_json::from_str::<serde_json::Value>(&pending_tool_input_json)
+                                        .ok()
+                                        .filter(|input| input.is_object())
+                                        .unwrap_or_else(|| serde_json::json!({}));
+                                    user_tool_calls.push(UserToolCall { id, name, input });
+                                }
+                                pending_tool_input_json.clear();
+                            }
+                            // If we just finished a code execution tool use block, emit the execution started event
+                            if let Some(ref block_type) = current_block_type {
+                                if block_type == "server_tool_use" && current_execution_tool_name.is_some() && !pending_tool_input_json.is_empty() {
+                                    // Parse the accumulated input JSON
+                                    if let Ok(input_obj) = serde_json::from_str::<serde_json::Value>(&pending_tool_input_json) {
+                                        let tool_name = current_execution_tool_name.as_ref().unwrap();
+                                        let code = match tool_name.as_str() {
+                                            tool_names::BASH_CODE_EXECUTION => {
+                                                input_obj["command"].as_str().map(|s| s.to_string())
+                                            }
+                                            tool_names::TEXT_EDITOR_CODE_EXECUTION => {
+                                                let command = input_obj["command"].as_str().unwrap_or("");
+                                                let path = input_obj["path"].as_str().unwrap_or("");
+                                                let file_text = input_obj["file_text"].as_str();
+                                                if let Some(content) = file_text {
+                                                    Some(format!("# {} {}\n{}", command, path, content))
+                                                } else {
+                                                    Some(format!("# {} {}", command, path))
                                                 }
                                             }
+                                            _ => None,
+                                        };
+
+                                        // Emit execution started delta with actual code
+                                        let delta = StreamDelta {
+                                            turn_id: turn_id.clone(),
+                                            text: String::new(),
+                                            citations: None,
+                                            inline_citations: None,
+                                            thinking: None,
+                                            execution: Some(ExecutionDelta {
+                                                tool_name: tool_name.clone(),
+                                                stdout: None,
+                                                stderr: None,
+                                                status: ExecutionStatus::Started,
+                                                code,
+                                                files: None,
+                                            }),
+                                        };
+                                        if let Err(err) = emit_stream_delta(window, delta) {
+                                            eprintln!("Failed to emit chat-stream-delta event: {}", err);
                                         }
-                                        AnthropicStreamEvent::MessageStop => {
-                                            llm_logger::log_response_complete("chat", &full_response);
-                                            if let Err(err) = window.emit("chat-stream-done", StreamEvent { turn_id: turn_id.clone() }) {
-                                                eprintln!("Failed to emit chat-stream-done event: {}", err);
-                                            }
-                                            return Ok(());
+                                    }
+                                    // Clear the accumulated JSON
+                                    pending_tool_input_json.clear();
+                                } else if block_type == "server_tool_use"
+                                    && current_execution_tool_name.is_none()
+                                    && !pending_tool_input_json.is_empty()
+                                {
+                                    // Non-code-execution server_tool_use (web_search or web_fetch). Log
+                                    // the accumulated input JSON for debug visibility, then clear it
+                                    // so a subsequent block's input doesn't accumulate stale bytes.
+                                    let parsed = serde_json::from_str::<serde_json::Value>(&pending_tool_input_json)
+                                        .unwrap_or_else(|_| serde_json::Value::String(pending_tool_input_json.clone()));
+                                    // Generic label — the JSON itself reveals whether this was a
+                                    // web_search ("query": ...) or web_fetch ("url": ...) call.
+                                    llm_logger::log_tool_event("chat", "server_tool_use input", &parsed);
+                                    let web_search_id = current_tool_use
+                                        .as_ref()
+                                        .filter(|(_, name)| name == "web_search")
+                                        .map(|(id, _)| id.as_str());
+                                    if let (Some(id), Some(query)) = (web_search_id, parsed["query"].as_str()) {
+                                        web_search_cache::record_query(&turn_id, id, query);
+                                        if let Err(err) = session_events::emit(window, &turn_id, "chat-search-query", SearchQueryEvent {
+                                            turn_id: turn_id.clone(),
+                                            query: query.to_string(),
+                                        }) {
+                                            eprintln!("Failed to emit chat-search-query event: {}", err);
                                         }
-                                        AnthropicStreamEvent::Unknown => {}
                                     }
+                                    pending_tool_input_json.clear();
                                 }
                             }
+                            previous_block_type = current_block_type.take();
+                            current_tool_use = None;
                         }
+                        AnthropicStreamEvent::MessageDelta { container_id, output_tokens } => {
+                            if let Some(tokens) = output_tokens {
+                                record_output_tokens(window, &turn_id, tokens);
+                            }
+                            // Container ID arrives in message_delta for streaming responses
+                            if let Some(id) = container_id {
+                                llm_logger::log_feature_used("chat", &format!("Container ID received: {}", id));
+                                if let Err(err) = session_events::emit(window, &turn_id, "chat-container-id", ContainerIdEvent {
+                                    turn_id: turn_id.clone(),
+                                    container_id: id,
+                                }) {
+                                    eprintln!("Failed to emit chat-container-id event: {}", err);
+                                }
+                            }
+                        }
+                        AnthropicStreamEvent::MessageStop => {
+                            return Ok(end_response(window, &turn_id, &full_response, response_blocks, &mut thinking_blocks, user_tool_calls));
+                        }
+                        AnthropicStreamEvent::Error { message } => {
+                            llm_logger::log_error("chat", &message);
+                            return Err(SidestreamError::from_stream_message(message));
+                        }
+                        AnthropicStreamEvent::Unknown => {}
                     }
-                    Some(Err(e)) => return Err(e.to_string()),
-                    None => break,
+                }
+                if ended {
+                    break;
                 }
             }
         }
     }
 
-    llm_logger::log_response_complete("chat", &full_response);
-    if let Err(err) = window.emit("chat-stream-done", StreamEvent { turn_id }) {
+    Ok(end_response(window, &turn_id, &full_response, response_blocks, &mut thinking_blocks, user_tool_calls))
+}
+
+/// Finish a response: hand user tool calls back to the caller along with the
+/// response content to replay, or end the turn
+fn end_response(
+    window: &tauri::Window,
+    turn_id: &str,
+    full_response: &str,
+    response_blocks: ResponseBlocks,
+    thinking_blocks: &mut Vec<serde_json::Value>,
+    user_tool_calls: Vec<UserToolCall>,
+) -> StreamEnd {
+    llm_logger::log_response_complete("chat", full_response);
+    if !user_tool_calls.is_empty() {
+        return StreamEnd::ToolUse {
+            content: response_blocks.into_content(),
+            calls: user_tool_calls,
+        };
+    }
+    emit_thinking_blocks(window, turn_id, thinking_blocks);
+    if let Err(err) = emit_stream_done(window, turn_id) {
         eprintln!("Failed to emit chat-stream-done event: {}", err);
     }
-    Ok(())
+    StreamEnd::Finished
 }
 
-/// Transform 'file' blocks to 'document' blocks for Anthropic API.
-/// We send all files as document blocks and let the API return an error
-/// if the file type isn't supported.
-fn transform_file_blocks_for_anthropic(content: &serde_json::Value) -> serde_json::Value {
-    // If content is a string, return as-is
-    if content.is_string() {
-        return content.clone();
+/// Send the response's captured thinking blocks to the frontend (once)
+fn emit_thinking_blocks(window: &tauri::Window, turn_id: &str, blocks: &mut Vec<serde_json::Value>) {
+    if blocks.is_empty() {
+        return;
     }
-
-    // If content is an array, transform any 'file' blocks to 'document' blocks
-    if let Some(arr) = content.as_array() {
-        let transformed: Vec<serde_json::Value> = arr
-            .iter()
-            .map(|block| {
-                if let Some(block_type) = block.get("type").and_then(|t| t.as_str()) {
-                    if block_type == "file" {
-                        // Convert 'file' to 'document' block
-                        let source = &block["source"];
-                        return serde_json::json!({
-                            "type": "document",
-                            "source": source.clone()
-                        });
-                    }
-                }
-                block.clone()
-            })
-            .collect();
-        return serde_json::json!(transformed);
+    let event = ThinkingBlocksEvent {
+        turn_id: turn_id.to_string(),
+        blocks: std::mem::take(blocks),
+    };
+    if let Err(err) = session_events::emit(window, turn_id, "chat-thinking-blocks", event) {
+        eprintln!("Failed to emit chat-thinking-blocks event: {}", err);
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Fallback: return as-is
-    content.clone()
+    #[test]
+    fn tool_use_responses_replay_every_block_in_order() {
+        let mut blocks = ResponseBlocks::default();
+        blocks.start(&serde_json::json!({"type": "thinking", "thinking": "", "signature": ""}));
+        blocks.append("thinking", "Search first");
+        blocks.append("signature", "sig");
+        blocks.stop("");
+        blocks.start(&serde_json::json!({"type": "server_tool_use", "id": "srvtoolu_1", "name": "web_search", "input": {}}));
+        blocks.stop(r#"{"query": "order status"}"#);
+        let results = serde_json::json!({"type": "web_search_tool_result", "tool_use_id": "srvtoolu_1", "content": []});
+        blocks.start(&results);
+        blocks.stop("");
+        blocks.start(&serde_json::json!({"type": "text", "text": "", "citations": null}));
+        blocks.append("text", "Checking the order.");
+        blocks.stop("");
+        blocks.start(&serde_json::json!({"type": "text", "text": ""}));
+        blocks.stop("");
+        blocks.start(&serde_json::json!({"type": "tool_use", "id": "toolu_1", "name": "order_lookup", "input": {}}));
+        blocks.stop(r#"{"id": "#);
+
+        let content = blocks.into_content();
+        let types: Vec<_> = content.iter().map(|block| block["type"].as_str().unwrap()).collect();
+        assert_eq!(types, ["thinking", "server_tool_use", "web_search_tool_result", "text", "tool_use"]);
+        assert_eq!(content[0]["signature"], "sig");
+        assert_eq!(content[1]["input"]["query"], "order status");
+        assert_eq!(content[2], results);
+        assert_eq!(content[3], serde_json::json!({"type": "text", "text": "Checking the order."}));
+        assert_eq!(content[4]["input"], serde_json::json!({}));
+    }
 }
@@ -1,20 +1,29 @@
 use futures::StreamExt;
-use tauri::Emitter;
+use tauri::async_runtime::JoinHandle;
 use tokio_util::sync::CancellationToken;
 
+use std::collections::HashSet;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::citations;
 use crate::commands::get_api_key_async;
-use crate::llm::{tool_names, ChatMessage, ExecutionDelta, ExecutionStatus, GeneratedFile, StreamDelta, StreamEvent};
+use crate::error::SidestreamError;
+use crate::gemini_fallback;
+use crate::heartbeat::StreamPhase;
+use crate::llm::{emit_stream_delta, emit_stream_done, record_output_tokens, record_response_metadata, set_stream_phase, tool_names, ChatMessage, ExecutionDelta, ExecutionStatus, GeneratedFile, ResponseMetadata, SearchQueryEvent, StreamDelta, StreamEvent};
 use crate::llm_logger;
+use crate::request_adjustments::{emit_request_adjusted, AdjustmentKind, RequestAdjustment};
 use crate::providers::anthropic::InlineCitation;
 use crate::providers::gemini::{
     extract_inline_citations_from_grounding, extract_referenced_filenames, extract_saved_filenames,
     mime_to_extension, parse_sse_event as gemini_parse_sse_event, pick_filename_index_for_mime,
     string_to_thinking_config, supports_thinking as gemini_supports_thinking,
-    ChatRequestConfig as GeminiChatRequestConfig, GeminiClient, GeminiStreamEvent,
+    ChatRequestConfig as GeminiChatRequestConfig, GeminiClient, GeminiStreamEvent, ThinkingLevel,
     UrlContextEntry,
 };
+use crate::session_events;
+use crate::sse::SseDecoder;
+use crate::sse_recording::{self, SseByteStream};
 
 /// Pure selection: from all buffered (filename, file) pairs and the final response
 /// text, return only the user-ready file(s).
@@ -89,7 +98,7 @@ fn emit_user_ready_files(
             files: Some(files),
         }),
     };
-    if let Err(err) = window.emit("chat-stream-delta", delta) {
+    if let Err(err) = emit_stream_delta(window, delta) {
         eprintln!("Failed to emit user-ready files delta: {}", err);
     }
 }
@@ -139,35 +148,48 @@ fn emit_text_note(window: &tauri::Window, turn_id: &str, text: &str) {
         thinking: None,
         execution: None,
     };
-    if let Err(err) = window.emit("chat-stream-delta", delta) {
+    if let Err(err) = emit_stream_delta(window, delta) {
         eprintln!("Failed to emit note delta: {}", err);
     }
 }
 
-/// Terminal sequence shared by the normal-finish and interrupted code paths: emit the
-/// user-ready file(s), optionally append an explanatory note, log, and signal done.
-fn finalize_chat_response(
+/// Terminal sequence shared by the normal-finish and interrupted code paths: wait for
+/// citations still being resolved, emit the user-ready file(s), optionally append an
+/// explanatory note, log, and signal done.
+async fn finalize_chat_response(
     window: &tauri::Window,
     turn_id: &str,
+    pending_citations: &mut Vec<JoinHandle<()>>,
     buffered_files: Vec<(String, GeneratedFile)>,
     full_response: &str,
     note: Option<&str>,
 ) {
+    citations::finish_citation_deltas(pending_citations).await;
     emit_user_ready_files(window, turn_id, buffered_files, full_response);
     if let Some(note) = note {
         emit_text_note(window, turn_id, note);
     }
     llm_logger::log_response_complete("chat", full_response);
-    if let Err(err) = window.emit(
-        "chat-stream-done",
-        StreamEvent {
-            turn_id: turn_id.to_string(),
-        },
-    ) {
+    if let Err(err) = emit_stream_done(window, turn_id) {
         eprintln!("Failed to emit chat-stream-done event: {}", err);
     }
 }
 
+/// The adjustment when a `medium` thinking level became `low`, which only
+/// Gemini 3.x Flash supports (see `string_to_thinking_config`)
+pub fn medium_thinking_adjustment(
+    requested: Option<&str>,
+    thinking_config: Option<ThinkingLevel>,
+) -> Option<RequestAdjustment> {
+    let medium = requested.is_some_and(|level| level.eq_ignore_ascii_case("medium"));
+    (medium && thinking_config == Some(ThinkingLevel::Low)).then(|| {
+        RequestAdjustment::new(
+            AdjustmentKind::EffortChanged,
+            "Used low thinking: this Gemini model doesn't support medium",
+        )
+    })
+}
+
 /// Send chat message using Google Gemini API
 pub async fn send_chat_message_gemini(
     app: &tauri::AppHandle,
@@ -179,7 +201,7 @@ pub async fn send_chat_message_gemini(
     web_search_enabled: bool,
     thinking_level: Option<String>,
     turn_id: String,
-) -> Result<(), String> {
+) -> Result<(), SidestreamError> {
     let api_key = get_api_key_async(app, "google").await?;
     let client = GeminiClient::new(api_key);
 
@@ -197,6 +219,9 @@ pub async fn send_chat_message_gemini(
     } else {
         None
     };
+    if let Some(adjustment) = medium_thinking_adjustment(thinking_level.as_deref(), thinking_config) {
+        emit_request_adjusted(window, &turn_id, vec![adjustment]);
+    }
 
     // Build request using Gemini provider
     let config = GeminiChatRequestConfig {
@@ -210,17 +235,33 @@ pub async fn send_chat_message_gemini(
 
     llm_logger::log_request("chat", &model, &body);
 
-    let response = client
-        .send_streaming_request(&model, &body)
+    let response = gemini_fallback::send_streaming_request(&client, window, "chat", &turn_id, &model, &body)
         .await
         .map_err(|e| {
             llm_logger::log_error("chat", &e);
             e
         })?;
 
+    // Grounding searches run before the first output. (Code execution's phase
+    // comes from its execution deltas.)
+    if web_search_enabled {
+        set_stream_phase(window, &turn_id, StreamPhase::Searching);
+    }
+
     // Stream the response
-    let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
+    let stream = sse_recording::response_stream(app, "google", &model, response);
+    stream_gemini_response(window, cancel_token, turn_id, stream).await
+}
+
+/// Handle a Gemini response stream, emitting chat events to the window.
+/// Also used to replay recorded streams (see `sse_recording`).
+pub async fn stream_gemini_response(
+    window: &tauri::Window,
+    cancel_token: CancellationToken,
+    turn_id: String,
+    mut stream: SseByteStream,
+) -> Result<(), SidestreamError> {
+    let mut decoder = SseDecoder::line_delimited();
     let mut full_response = String::new();
     let mut accumulated_text = String::new();
     let mut accumulated_thinking = String::new();
@@ -230,253 +271,264 @@ pub async fn send_chat_message_gemini(
     // can emit only the user-ready one(s). See emit_user_ready_files.
     let mut pending_filenames: Vec<String> = Vec::new();
     let mut buffered_files: Vec<(String, GeneratedFile)> = Vec::new();
+    // Grounding metadata can repeat; each search query is emitted once
+    let mut searched_queries: HashSet<String> = HashSet::new();
+    // Citation deltas waiting on their grounding redirects
+    let mut pending_citations: Vec<JoinHandle<()>> = Vec::new();
 
     loop {
         tokio::select! {
             // Check for cancellation
             _ = cancel_token.cancelled() => {
-                if let Err(err) = window.emit("chat-stream-cancelled", StreamEvent { turn_id: turn_id.clone() }) {
+                if let Err(err) = session_events::emit(window, &turn_id, "chat-stream-cancelled", StreamEvent { turn_id: turn_id.clone() }) {
                     eprintln!("Failed to emit chat-stream-cancelled event: {}", err);
                 }
                 return Ok(());
             }
             // Process next chunk from stream
             chunk = stream.next() => {
-                match chunk {
-                    Some(Ok(bytes)) => {
-                        let text = String::from_utf8_lossy(&bytes);
-                        buffer.push_str(&text);
-
-                        // Gemini streams each SSE event on its own line (data: {...}\r\n)
-                        // without double-newline separators. Parse line by line.
-
-                        // Process complete lines from buffer
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim_end_matches('\r').to_string();
-                            buffer = buffer[line_end + 1..].to_string();
-
-                            if let Some(data) = line.strip_prefix("data: ") {
-                                // parse_sse_event returns Vec since one SSE can have multiple parts
-                                let events = gemini_parse_sse_event(data);
-
-                                for event in events {
-                                match event {
-                                    GeminiStreamEvent::TextDelta { text: t } => {
-                                        // Gemini sends complete text in each chunk, need to diff
-                                        let new_text = if t.starts_with(&accumulated_text) {
-                                            t[accumulated_text.len()..].to_string()
-                                        } else {
-                                            // Reset - new response
-                                            accumulated_text.clear();
-                                            t.clone()
-                                        };
-                                        accumulated_text = t;
-
-                                        if !new_text.is_empty() {
-                                            full_response.push_str(&new_text);
-                                            let delta = StreamDelta {
-                                                turn_id: turn_id.clone(),
-                                                text: new_text,
-                                                citations: None,
-                                                inline_citations: None,
-                                                thinking: None,
-                                                execution: None,
-                                            };
-                                            if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                eprintln!("Failed to emit chat-stream-delta event: {}", err);
-                                            }
-                                        }
-                                    }
-                                    GeminiStreamEvent::ThinkingDelta { text: thinking_text } => {
-                                        // Gemini sends cumulative thinking text, need to diff
-                                        let new_thinking = if thinking_text.starts_with(&accumulated_thinking) {
-                                            thinking_text[accumulated_thinking.len()..].to_string()
-                                        } else {
-                                            // Reset - new thinking block
-                                            accumulated_thinking.clear();
-                                            thinking_text.clone()
-                                        };
-                                        accumulated_thinking = thinking_text;
-
-                                        // Emit thinking delta for ephemeral UI display
-                                        if !new_thinking.is_empty() {
-                                            let delta = StreamDelta {
-                                                turn_id: turn_id.clone(),
-                                                text: String::new(),
-                                                citations: None,
-                                                inline_citations: None,
-                                                thinking: Some(new_thinking),
-                                                execution: None,
-                                            };
-                                            if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                eprintln!("Failed to emit chat-stream-delta event: {}", err);
-                                            }
-                                        }
-                                    }
-                                    GeminiStreamEvent::GroundingMetadata { metadata } => {
-                                        llm_logger::log_feature_used("chat", "Gemini Google Search");
-                                        // Extract inline citations with proper character offsets
-                                        let gemini_citations = extract_inline_citations_from_grounding(&metadata, &full_response);
-                                        if !gemini_citations.is_empty() {
-                                            // Convert Gemini InlineCitation to Anthropic InlineCitation type
-                                            let inline_citations: Vec<InlineCitation> = gemini_citations
-                                                .into_iter()
-                                                .map(|c| InlineCitation {
-                                                    url: c.url,
-                                                    title: c.title,
-                                                    cited_text: c.cited_text,
-                                                    char_offset: c.char_offset,
-                                                })
-                                                .collect();
-                                            let delta = StreamDelta {
-                                                turn_id: turn_id.clone(),
-                                                text: String::new(),
-                                                citations: None,
-                                                inline_citations: Some(inline_citations),
-                                                thinking: None,
-                                                execution: None,
-                                            };
-                                            if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                eprintln!("Failed to emit chat-stream-delta event: {}", err);
-                                            }
-                                        }
-                                    }
-                                    GeminiStreamEvent::ResponseComplete { finish_reason } => {
-                                        let has_content = !full_response.trim().is_empty();
-                                        // A non-STOP reason (MAX_TOKENS, SAFETY, …) with no answer at all
-                                        // is surfaced as an error so the user sees why and discovery is
-                                        // skipped. Otherwise we keep what we have (appending a note if it
-                                        // ended abnormally) and complete normally.
-                                        if finish_reason != "STOP" && !has_content {
-                                            let msg = finish_reason_error(&finish_reason);
-                                            llm_logger::log_error("chat", &msg);
-                                            return Err(msg);
-                                        }
-                                        let note = (finish_reason != "STOP")
-                                            .then(|| finish_reason_note(&finish_reason));
-                                        finalize_chat_response(
-                                            window,
-                                            &turn_id,
-                                            std::mem::take(&mut buffered_files),
-                                            &full_response,
-                                            note.as_deref(),
-                                        );
-                                        return Ok(());
-                                    }
-                                    GeminiStreamEvent::Error { message } => {
-                                        llm_logger::log_error("chat", &message);
-                                        return Err(message);
-                                    }
-                                    GeminiStreamEvent::ExecutableCode { code } => {
-                                        llm_logger::log_feature_used("chat", "Gemini Code Execution Started");
-                                        // Recover the filenames this block writes so we can name the
-                                        // (anonymous) inlineData parts that follow it.
-                                        for name in extract_saved_filenames(&code) {
-                                            pending_filenames.push(name);
-                                        }
-                                        // Emit execution started with code
-                                        let delta = StreamDelta {
-                                            turn_id: turn_id.clone(),
-                                            text: String::new(),
-                                            citations: None,
-                                            inline_citations: None,
-                                            thinking: None,
-                                            execution: Some(ExecutionDelta {
-                                                tool_name: tool_names::GEMINI_CODE_EXECUTION.to_string(),
-                                                stdout: None,
-                                                stderr: None,
-                                                status: ExecutionStatus::Started,
-                                                code: Some(code),
-                                                files: None,
-                                            }),
-                                        };
-                                        if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                            eprintln!("Failed to emit execution started delta: {}", err);
-                                        }
-                                    }
-                                    GeminiStreamEvent::CodeExecutionResult { output } => {
-                                        // Emit execution output
-                                        let delta = StreamDelta {
-                                            turn_id: turn_id.clone(),
-                                            text: String::new(),
-                                            citations: None,
-                                            inline_citations: None,
-                                            thinking: None,
-                                            execution: Some(ExecutionDelta {
-                                                tool_name: tool_names::GEMINI_CODE_EXECUTION.to_string(),
-                                                stdout: Some(output),
-                                                stderr: None,
-                                                status: ExecutionStatus::Completed,
-                                                code: None,
-                                                files: None,
-                                            }),
-                                        };
-                                        if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                            eprintln!("Failed to emit execution result delta: {}", err);
-                                        }
-                                    }
-                                    GeminiStreamEvent::InlineData { mime_type, data } => {
-                                        let timestamp = SystemTime::now()
-                                            .duration_since(UNIX_EPOCH)
-                                            .unwrap_or_default()
-                                            .as_millis();
-
-                                        let extension = mime_to_extension(&mime_type);
-                                        let file_id = format!("gemini-{}-{}", timestamp, generated_file_count);
-                                        // Pair this file with a filename recovered from code BY CONTENT TYPE,
-                                        // not by order: the sandbox can return files in a different order than
-                                        // the code saved them, so a positional match swaps names (e.g. a PNG
-                                        // getting a .json name). The model references this name in its prose,
-                                        // so it must match the real content. Fall back to a synthetic name
-                                        // (with the correct extension) when nothing suitable was saved.
-                                        let filename = match pick_filename_index_for_mime(&pending_filenames, &mime_type) {
-                                            Some(i) => pending_filenames.remove(i),
-                                            None => format!("generated-{}.{}", timestamp, extension),
-                                        };
-                                        generated_file_count += 1;
-
-                                        // Create data URL for image preview (if it's an image)
-                                        let image_preview = if mime_type.starts_with("image/") {
-                                            Some(format!("data:{};base64,{}", mime_type, data))
-                                        } else {
-                                            None
-                                        };
-
-                                        let file = GeneratedFile {
-                                            file_id,
-                                            filename: filename.clone(),
-                                            mime_type: Some(mime_type.clone()),
-                                            image_preview,
-                                            inline_data: Some(data),
-                                        };
-
-                                        llm_logger::log_feature_used("chat", &format!("Gemini File Generated: {}", mime_type));
-
-                                        // Buffer rather than emit: Gemini streams every intermediate plot
-                                        // as it iterates. emit_user_ready_files (at stream end) keeps only
-                                        // the file(s) the model actually presents in its final response.
-                                        buffered_files.push((filename, file));
-                                    }
-                                    GeminiStreamEvent::UrlContextUsed { entries } => {
-                                        // Diagnostic only. Lets the chat log show whether
-                                        // url_context fired and which URLs were fetched (with
-                                        // each URL's retrieval status). No frontend effect.
-                                        llm_logger::log_feature_used(
-                                            "chat",
-                                            &format!(
-                                                "Gemini URL Context: {}",
-                                                format_url_context_entries(&entries)
-                                            ),
-                                        );
+                // Gemini streams each SSE event on its own line (data: {...}\r\n)
+                // without double-newline separators, so every data line is an event
+                let (payloads, ended) = match chunk {
+                    Some(Ok(bytes)) => (decoder.push(&bytes), false),
+                    Some(Err(message)) => return Err(SidestreamError::NetworkError { message }),
+                    None => (decoder.finish(), true),
+                };
+
+                for data in payloads {
+                    // parse_sse_event returns Vec since one SSE can have multiple parts
+                    let events = gemini_parse_sse_event(&data);
+
+                    for event in events {
+                    match event {
+                        GeminiStreamEvent::TextDelta { text: t } => {
+                            // Gemini sends complete text in each chunk, need to diff
+                            let new_text = if t.starts_with(&accumulated_text) {
+                                t[accumulated_text.len()..].to_string()
+                            } else {
+                                // Reset - new response
+                                accumulated_text.clear();
+                                t.clone()
+                            };
+                            accumulated_text = t;
+
+                            if !new_text.is_empty() {
+                                full_response.push_str(&new_text);
+                                let delta = StreamDelta {
+                                    turn_id: turn_id.clone(),
+                                    text: new_text,
+                                    citations: None,
+                                    inline_citations: None,
+                                    thinking: None,
+                                    execution: None,
+                                };
+                                if let Err(err) = emit_stream_delta(window, delta) {
+                                    eprintln!("Failed to emit chat-stream-delta event: {}", err);
+                                }
+                            }
+                        }
+                        GeminiStreamEvent::ThinkingDelta { text: thinking_text } => {
+                            // Gemini sends cumulative thinking text, need to diff
+                            let new_thinking = if thinking_text.starts_with(&accumulated_thinking) {
+                                thinking_text[accumulated_thinking.len()..].to_string()
+                            } else {
+                                // Reset - new thinking block
+                                accumulated_thinking.clear();
+                                thinking_text.clone()
+                            };
+                            accumulated_thinking = thinking_text;
+
+                            // Emit thinking delta for ephemeral UI display
+                            if !new_thinking.is_empty() {
+                                let delta = StreamDelta {
+                                    turn_id: turn_id.clone(),
+                                    text: String::new(),
+                                    citations: None,
+                                    inline_citations: None,
+                                    thinking: Some(new_thinking),
+                                    execution: None,
+                                };
+                                if let Err(err) = emit_stream_delta(window, delta) {
+                                    eprintln!("Failed to emit chat-stream-delta event: {}", err);
+                                }
+                            }
+                        }
+                        GeminiStreamEvent::GroundingMetadata { metadata } => {
+                            llm_logger::log_feature_used("chat", "Gemini Google Search");
+                            for query in &metadata.web_search_queries {
+                                if searched_queries.insert(query.clone()) {
+                                    set_stream_phase(window, &turn_id, StreamPhase::Searching);
+                                    if let Err(err) = session_events::emit(window, &turn_id, "chat-search-query", SearchQueryEvent {
+                                        turn_id: turn_id.clone(),
+                                        query: query.clone(),
+                                    }) {
+                                        eprintln!("Failed to emit chat-search-query event: {}", err);
                                     }
-                                    GeminiStreamEvent::Unknown => {}
                                 }
-                                } // end for event in events
                             }
+                            // Extract inline citations with proper character offsets
+                            let gemini_citations = extract_inline_citations_from_grounding(&metadata, &full_response);
+                            if !gemini_citations.is_empty() {
+                                // Convert Gemini InlineCitation to Anthropic InlineCitation type
+                                let inline_citations: Vec<InlineCitation> = gemini_citations
+                                    .into_iter()
+                                    .map(|c| InlineCitation {
+                                        url: c.url,
+                                        title: c.title,
+                                        cited_text: c.cited_text,
+                                        char_offset: c.char_offset,
+                                    })
+                                    .collect();
+                                pending_citations.push(citations::spawn_citation_delta(window, &turn_id, inline_citations));
+                            }
+                        }
+                        GeminiStreamEvent::ResponseComplete { finish_reason, output_tokens } => {
+                            if let Some(tokens) = output_tokens {
+                                record_output_tokens(window, &turn_id, tokens);
+                            }
+                            let has_content = !full_response.trim().is_empty();
+                            // A non-STOP reason (MAX_TOKENS, SAFETY, …) with no answer at all
+                            // is surfaced as an error so the user sees why and discovery is
+                            // skipped. Otherwise we keep what we have (appending a note if it
+                            // ended abnormally) and complete normally.
+                            if finish_reason != "STOP" && !has_content {
+                                let msg = finish_reason_error(&finish_reason);
+                                llm_logger::log_error("chat", &msg);
+                                return Err(msg.into());
+                            }
+                            let note = (finish_reason != "STOP")
+                                .then(|| finish_reason_note(&finish_reason));
+                            finalize_chat_response(
+                                window,
+                                &turn_id,
+                                &mut pending_citations,
+                                std::mem::take(&mut buffered_files),
+                                &full_response,
+                                note.as_deref(),
+                            )
+                            .await;
+                            return Ok(());
+                        }
+                        GeminiStreamEvent::Error { message } => {
+                            llm_logger::log_error("chat", &message);
+                            return Err(SidestreamError::from_stream_message(message));
                         }
+                        GeminiStreamEvent::ExecutableCode { code } => {
+                            llm_logger::log_feature_used("chat", "Gemini Code Execution Started");
+                            // Recover the filenames this block writes so we can name the
+                            // (anonymous) inlineData parts that follow it.
+                            for name in extract_saved_filenames(&code) {
+                                pending_filenames.push(name);
+                            }
+                            // Emit execution started with code
+                            let delta = StreamDelta {
+                                turn_id: turn_id.clone(),
+                                text: String::new(),
+                                citations: None,
+                                inline_citations: None,
+                                thinking: None,
+                                execution: Some(ExecutionDelta {
+                                    tool_name: tool_names::GEMINI_CODE_EXECUTION.to_string(),
+                                    stdout: None,
+                                    stderr: None,
+                                    status: ExecutionStatus::Started,
+                                    code: Some(code),
+                                    files: None,
+                                }),
+                            };
+                            if let Err(err) = emit_stream_delta(window, delta) {
+                                eprintln!("Failed to emit execution started delta: {}", err);
+                            }
+                        }
+                        GeminiStreamEvent::CodeExecutionResult { output } => {
+                            // Emit execution output
+                            let delta = StreamDelta {
+                                turn_id: turn_id.clone(),
+                                text: String::new(),
+                                citations: None,
+                                inline_citations: None,
+                                thinking: None,
+                                execution: Some(ExecutionDelta {
+                                    tool_name: tool_names::GEMINI_CODE_EXECUTION.to_string(),
+                                    stdout: Some(output),
+                                    stderr: None,
+                                    status: ExecutionStatus::Completed,
+                                    code: None,
+                                    files: None,
+                                }),
+                            };
+                            if let Err(err) = emit_stream_delta(window, delta) {
+                                eprintln!("Failed to emit execution result delta: {}", err);
+                            }
+                        }
+                        GeminiStreamEvent::InlineData { mime_type, data } => {
+                            let timestamp = SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_millis();
+
+                            let extension = mime_to_extension(&mime_type);
+                            let file_id = format!("gemini-{}-{}", timestamp, generated_file_count);
+                            // Pair this file with a filename recovered from code BY CONTENT TYPE,
+                            // not by order: the sandbox can return files in a different order than
+                            // the code saved them, so a positional match swaps names (e.g. a PNG
+                            // getting a .json name). The model references this name in its prose,
+                            // so it must match the real content. Fall back to a synthetic name
+                            // (with the correct extension) when nothing suitable was saved.
+                            let filename = match pick_filename_index_for_mime(&pending_filenames, &mime_type) {
+                                Some(i) => pending_filenames.remove(i),
+                                None => format!("generated-{}.{}", timestamp, extension),
+                            };
+                            generated_file_count += 1;
+
+                            // Create data URL for image preview (if it's an image)
+                            let image_preview = if mime_type.starts_with("image/") {
+                                Some(format!("data:{};base64,{}", mime_type, data))
+                            } else {
+                                None
+                            };
+
+                            let file = GeneratedFile {
+                                file_id,
+                                filename: filename.clone(),
+                                mime_type: Some(mime_type.clone()),
+                                image_preview,
+                                inline_data: Some(data),
+                            };
+
+                            llm_logger::log_feature_used("chat", &format!("Gemini File Generated: {}", mime_type));
+
+                            // Buffer rather than emit: Gemini streams every intermediate plot
+                            // as it iterates. emit_user_ready_files (at stream end) keeps only
+                            // the file(s) the model actually presents in its final response.
+                            buffered_files.push((filename, file));
+                        }
+                        GeminiStreamEvent::UrlContextUsed { entries } => {
+                            // Diagnostic only. Lets the chat log show whether
+                            // url_context fired and which URLs were fetched (with
+                            // each URL's retrieval status). No frontend effect.
+                            llm_logger::log_feature_used(
+                                "chat",
+                                &format!(
+                                    "Gemini URL Context: {}",
+                                    format_url_context_entries(&entries)
+                                ),
+                            );
+                        }
+                        GeminiStreamEvent::ResponseInfo { response_id, model_version } => {
+                            record_response_metadata(window, &turn_id, ResponseMetadata {
+                                provider: "google".to_string(),
+                                model: model_version,
+                                response_id,
+                                request_id: None,
+                            });
+                        }
+                        GeminiStreamEvent::Unknown => {}
                     }
-                    Some(Err(e)) => return Err(e.to_string()),
-                    None => break,
+                    } // end for event in events
+                }
+                if ended {
+                    break;
                 }
             }
         }
@@ -488,15 +540,19 @@ pub async fn send_chat_message_gemini(
     // an error so the user knows to retry and discovery doesn't run on an empty turn.
     if full_response.trim().is_empty() {
         llm_logger::log_error("chat", INTERRUPTED_ERROR);
-        return Err(INTERRUPTED_ERROR.to_string());
+        return Err(SidestreamError::NetworkError {
+            message: INTERRUPTED_ERROR.to_string(),
+        });
     }
     finalize_chat_response(
         window,
         &turn_id,
+        &mut pending_citations,
         std::mem::take(&mut buffered_files),
         &full_response,
         Some(INTERRUPTED_NOTE),
-    );
+    )
+    .await;
     Ok(())
 }
 
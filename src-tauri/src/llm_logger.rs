@@ -4,15 +4,22 @@ use std::io::Write;
 use std::path::PathBuf;
 use std::sync::OnceLock;
 
+use crate::incognito;
+
 /// Set to true to enable detailed LLM request/response logging to files.
 /// Logs are written to the `logs/` directory with timestamped filenames.
 /// Useful for debugging but disabled by default to avoid disk usage.
 const LOGGING_ENABLED: bool = false;
 
+/// Logging is on and the current turn isn't incognito
+fn enabled() -> bool {
+    LOGGING_ENABLED && !incognito::is_active()
+}
+
 static CHAT_LOG_FILE_PATH: OnceLock<PathBuf> = OnceLock::new();
 static DISCOVERY_LOG_FILE_PATH: OnceLock<PathBuf> = OnceLock::new();
 
-fn get_log_dir() -> PathBuf {
+pub fn get_log_dir() -> PathBuf {
     // Use a logs folder in the project root (go up one level from src-tauri during dev)
     let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
 
@@ -52,7 +59,7 @@ fn format_json_pretty(value: &serde_json::Value) -> String {
 }
 
 pub fn log_request(module: &str, model: &str, body: &serde_json::Value) {
-    if !LOGGING_ENABLED {
+    if !enabled() {
         return;
     }
     let log_path = get_log_file_path(module);
@@ -191,7 +198,7 @@ pub fn log_request(module: &str, model: &str, body: &serde_json::Value) {
 }
 
 pub fn log_response_complete(module: &str, content: &str) {
-    if !LOGGING_ENABLED {
+    if !enabled() {
         return;
     }
     let log_path = get_log_file_path(module);
@@ -229,8 +236,8 @@ pub fn log_response_complete(module: &str, content: &str) {
     }
 }
 
-pub fn log_error(module: &str, error: &str) {
-    if !LOGGING_ENABLED {
+pub fn log_error(module: &str, error: impl std::fmt::Display) {
+    if !enabled() {
         return;
     }
     let log_path = get_log_file_path(module);
@@ -264,7 +271,7 @@ pub fn log_error(module: &str, error: &str) {
 /// can see whether the model has real image URLs to lift or is constructing
 /// them from memory.
 pub fn log_tool_event(module: &str, label: &str, payload: &serde_json::Value) {
-    if !LOGGING_ENABLED {
+    if !enabled() {
         return;
     }
     let log_path = get_log_file_path(module);
@@ -280,7 +287,7 @@ pub fn log_tool_event(module: &str, label: &str, payload: &serde_json::Value) {
 
 /// Log when a special feature is detected in the stream (extended thinking, web search)
 pub fn log_feature_used(module: &str, feature: &str) {
-    if !LOGGING_ENABLED {
+    if !enabled() {
         return;
     }
     let log_path = get_log_file_path(module);
@@ -290,3 +297,16 @@ pub fn log_feature_used(module: &str, feature: &str) {
         writeln!(file, "\n**[{}] Feature detected:** {}", timestamp, feature).ok();
     }
 }
+
+/// Log a completed turn's latency and throughput (see `turn_metrics`)
+pub fn log_turn_metrics(module: &str, metrics: impl std::fmt::Display) {
+    if !enabled() {
+        return;
+    }
+    let log_path = get_log_file_path(module);
+
+    if let Ok(mut file) = OpenOptions::new().append(true).open(log_path) {
+        let timestamp = Local::now().format("%H:%M:%S%.3f");
+        writeln!(file, "\n**[{}] Turn metrics:** {}", timestamp, metrics).ok();
+    }
+}
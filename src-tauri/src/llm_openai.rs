@@ -3,17 +3,24 @@ use tauri::Emitter;
 use tokio_util::sync::CancellationToken;
 
 use crate::commands::get_api_key_async;
+use crate::error::SidestreamError;
+use crate::heartbeat::StreamPhase;
 use crate::llm::{
+    emit_stream_delta, emit_stream_done, record_output_tokens, record_response_metadata, set_stream_phase,
     tool_names, ChatMessage, ContainerIdEvent, ExecutionDelta, ExecutionStatus, GeneratedFile,
-    StreamDelta, StreamEvent,
+    ResponseMetadata, StreamDelta, StreamEvent,
 };
 use crate::llm_logger;
+use crate::request_adjustments::{emit_request_adjusted, AdjustmentKind, RequestAdjustment};
 use crate::providers::anthropic::InlineCitation;
 use crate::providers::openai::{
     parse_sse_event as openai_parse_sse_event, string_to_reasoning_effort, supports_reasoning,
     fetch_file_content_base64, ChatRequestConfig as OpenAIChatRequestConfig, OpenAIClient, OpenAIStreamEvent,
     ReasoningEffort,
 };
+use crate::session_events;
+use crate::sse::SseDecoder;
+use crate::sse_recording::{self, SseByteStream};
 
 /// Send chat message using OpenAI Responses API
 pub async fn send_chat_message_openai(
@@ -29,7 +36,7 @@ pub async fn send_chat_message_openai(
     session_id: Option<String>,
     turn_id: String,
     openai_container_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), SidestreamError> {
     let api_key = get_api_key_async(app, "openai").await?;
     let client = OpenAIClient::new(api_key.clone());
 
@@ -48,6 +55,11 @@ pub async fn send_chat_message_openai(
             let effort = string_to_reasoning_effort(level);
             // OpenAI API rejects "minimal" when web_search is enabled (per official docs)
             if web_search_enabled && matches!(effort, ReasoningEffort::Minimal) {
+                let adjustment = RequestAdjustment::new(
+                    AdjustmentKind::EffortChanged,
+                    "Used low reasoning effort: OpenAI doesn't allow minimal with web search",
+                );
+                emit_request_adjusted(window, &turn_id, vec![adjustment]);
                 ReasoningEffort::Low
             } else {
                 effort
@@ -57,10 +69,6 @@ pub async fn send_chat_message_openai(
         None
     };
 
-    // Track current container ID (will be updated if we receive a new one)
-    // Used to associate files extracted from sandbox URLs with the correct container
-    let mut current_container_id: Option<String> = openai_container_id.clone();
-
     // Build request using OpenAI provider
     // Let OpenAI use its model defaults for max output tokens
     let config = OpenAIChatRequestConfig {
@@ -71,7 +79,7 @@ pub async fn send_chat_message_openai(
         web_search_enabled,
         prompt_cache_key: session_id.map(|id| format!("chat-{}", id)),
         code_interpreter_enabled: code_execution_enabled,
-        container_id: openai_container_id,
+        container_id: openai_container_id.clone(),
     };
     let body = client.build_chat_request(&config);
 
@@ -82,9 +90,28 @@ pub async fn send_chat_message_openai(
         e
     })?;
 
+    record_response_metadata(window, &turn_id, ResponseMetadata::from_headers("openai", response.headers()));
+
     // Stream the response
-    let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
+    let stream = sse_recording::response_stream(app, "openai", &model, response);
+    stream_openai_response(window, cancel_token, &api_key, turn_id, openai_container_id, stream).await
+}
+
+/// Handle an OpenAI response stream, emitting chat events to the window.
+/// Also used to replay recorded streams (see `sse_recording`).
+pub async fn stream_openai_response(
+    window: &tauri::Window,
+    cancel_token: CancellationToken,
+    api_key: &str,
+    turn_id: String,
+    container_id: Option<String>,
+    mut stream: SseByteStream,
+) -> Result<(), SidestreamError> {
+    // Track current container ID (will be updated if we receive a new one)
+    // Used to associate files extracted from sandbox URLs with the correct container
+    let mut current_container_id: Option<String> = container_id;
+
+    let mut decoder = SseDecoder::new();
     let mut full_response = String::new();
 
     // State tracking for code interpreter
@@ -101,321 +128,330 @@ pub async fn send_chat_message_openai(
         tokio::select! {
             // Check for cancellation
             _ = cancel_token.cancelled() => {
-                if let Err(err) = window.emit("chat-stream-cancelled", StreamEvent { turn_id: turn_id.clone() }) {
+                if let Err(err) = session_events::emit(window, &turn_id, "chat-stream-cancelled", StreamEvent { turn_id: turn_id.clone() }) {
                     eprintln!("Failed to emit chat-stream-cancelled event: {}", err);
                 }
                 return Ok(());
             }
             // Process next chunk from stream
             chunk = stream.next() => {
-                match chunk {
-                    Some(Ok(bytes)) => {
-                        let text = String::from_utf8_lossy(&bytes);
-                        buffer.push_str(&text);
-
-                        // Parse SSE events
-                        while let Some(event_end) = buffer.find("\n\n") {
-                            let event = buffer[..event_end].to_string();
-                            buffer = buffer[event_end + 2..].to_string();
-
-                            for line in event.lines() {
-                                if let Some(data) = line.strip_prefix("data: ") {
-                                    let parsed_event = openai_parse_sse_event(data);
-                                    match parsed_event {
-                                        OpenAIStreamEvent::Done | OpenAIStreamEvent::ResponseCompleted => {
-                                            llm_logger::log_response_complete("chat", &full_response);
-                                            // Emit the deduped files before done so the frontend
-                                            // includes them when it finalizes the message.
-                                            emit_generated_files(window, &turn_id, std::mem::take(&mut buffered_files), &full_response);
-                                            if let Err(err) = window.emit("chat-stream-done", StreamEvent { turn_id: turn_id.clone() }) {
-                                                eprintln!("Failed to emit chat-stream-done event: {}", err);
-                                            }
-                                            return Ok(());
-                                        }
-                                        OpenAIStreamEvent::TextDelta { text: t } => {
-                                            full_response.push_str(&t);
-                                            let delta = StreamDelta {
-                                                turn_id: turn_id.clone(),
-                                                text: t,
-                                                citations: None,
-                                                inline_citations: None,
-                                                thinking: None,
-                                                execution: None,
-                                            };
-                                            if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                eprintln!("Failed to emit chat-stream-delta event: {}", err);
-                                            }
-                                        }
-                                        OpenAIStreamEvent::ReasoningSummary { text: thinking_text } => {
-                                            // Emit reasoning summary as thinking delta for ephemeral UI
-                                            let delta = StreamDelta {
-                                                turn_id: turn_id.clone(),
-                                                text: String::new(),
-                                                citations: None,
-                                                inline_citations: None,
-                                                thinking: Some(thinking_text),
-                                                execution: None,
-                                            };
-                                            if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                eprintln!("Failed to emit chat-stream-delta event: {}", err);
-                                            }
-                                        }
-                                        OpenAIStreamEvent::TextDone { text: _, annotations, file_citations } => {
-                                            // Convert OpenAI URL citations to common format
-                                            // OpenAI doesn't provide position info, so we use end-of-message citations
-                                            if !annotations.is_empty() {
-                                                let offset = full_response.len();
-                                                let inline_citations: Vec<InlineCitation> = annotations
-                                                    .into_iter()
-                                                    .map(|a| InlineCitation {
-                                                        url: a.url,
-                                                        title: a.title,
-                                                        cited_text: String::new(),
-                                                        char_offset: offset,
-                                                    })
-                                                    .collect();
-                                                let delta = StreamDelta {
-                                                    turn_id: turn_id.clone(),
-                                                    text: String::new(),
-                                                    citations: None,
-                                                    inline_citations: Some(inline_citations),
-                                                    thinking: None,
-                                                    execution: None,
-                                                };
-                                                if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                    eprintln!("Failed to emit chat-stream-delta event: {}", err);
-                                                }
-                                            }
+                let (events, ended) = match chunk {
+                    Some(Ok(bytes)) => (decoder.push(&bytes), false),
+                    Some(Err(message)) => return Err(SidestreamError::NetworkError { message }),
+                    // A last event without its closing blank line still counts
+                    None => (decoder.finish(), true),
+                };
 
-                                            // Emit container file citations as generated files
-                                            // These come from text annotations when model references files in markdown
-                                            if !file_citations.is_empty() {
-                                                // Emit container ID - from file citation or from tracked container_id
-                                                let effective_container_id = file_citations.first()
-                                                    .filter(|f| !f.container_id.is_empty())
-                                                    .map(|f| f.container_id.clone())
-                                                    .or_else(|| current_container_id.clone());
+                for data in events {
+                    let parsed_event = openai_parse_sse_event(&data);
+                    match parsed_event {
+                        OpenAIStreamEvent::Done | OpenAIStreamEvent::ResponseCompleted { .. } => {
+                            if let OpenAIStreamEvent::ResponseCompleted { output_tokens: Some(tokens) } = parsed_event {
+                                record_output_tokens(window, &turn_id, tokens);
+                            }
+                            llm_logger::log_response_complete("chat", &full_response);
+                            // Emit the deduped files before done so the frontend
+                            // includes them when it finalizes the message.
+                            emit_generated_files(window, &turn_id, std::mem::take(&mut buffered_files), &full_response);
+                            if let Err(err) = emit_stream_done(window, &turn_id) {
+                                eprintln!("Failed to emit chat-stream-done event: {}", err);
+                            }
+                            return Ok(());
+                        }
+                        OpenAIStreamEvent::ResponseCreated { response_id, model } => {
+                            record_response_metadata(window, &turn_id, ResponseMetadata {
+                                provider: "openai".to_string(),
+                                model,
+                                response_id,
+                                request_id: None,
+                            });
+                        }
+                        OpenAIStreamEvent::TextDelta { text: t } => {
+                            full_response.push_str(&t);
+                            let delta = StreamDelta {
+                                turn_id: turn_id.clone(),
+                                text: t,
+                                citations: None,
+                                inline_citations: None,
+                                thinking: None,
+                                execution: None,
+                            };
+                            if let Err(err) = emit_stream_delta(window, delta) {
+                                eprintln!("Failed to emit chat-stream-delta event: {}", err);
+                            }
+                        }
+                        OpenAIStreamEvent::ReasoningSummary { text: thinking_text } => {
+                            // Emit reasoning summary as thinking delta for ephemeral UI
+                            let delta = StreamDelta {
+                                turn_id: turn_id.clone(),
+                                text: String::new(),
+                                citations: None,
+                                inline_citations: None,
+                                thinking: Some(thinking_text),
+                                execution: None,
+                            };
+                            if let Err(err) = emit_stream_delta(window, delta) {
+                                eprintln!("Failed to emit chat-stream-delta event: {}", err);
+                            }
+                        }
+                        OpenAIStreamEvent::TextDone { text: _, annotations, file_citations } => {
+                            // Convert OpenAI URL citations to common format
+                            // OpenAI doesn't provide position info, so we use end-of-message citations
+                            if !annotations.is_empty() {
+                                let offset = full_response.len();
+                                let inline_citations: Vec<InlineCitation> = annotations
+                                    .into_iter()
+                                    .map(|a| InlineCitation {
+                                        url: a.url,
+                                        title: a.title,
+                                        cited_text: String::new(),
+                                        char_offset: offset,
+                                    })
+                                    .collect();
+                                let delta = StreamDelta {
+                                    turn_id: turn_id.clone(),
+                                    text: String::new(),
+                                    citations: None,
+                                    inline_citations: Some(inline_citations),
+                                    thinking: None,
+                                    execution: None,
+                                };
+                                if let Err(err) = emit_stream_delta(window, delta) {
+                                    eprintln!("Failed to emit chat-stream-delta event: {}", err);
+                                }
+                            }
 
-                                                if let Some(ref cid) = effective_container_id {
-                                                    if let Err(err) = window.emit(
-                                                        "chat-container-id",
-                                                        ContainerIdEvent {
-                                                            turn_id: turn_id.clone(),
-                                                            container_id: cid.clone(),
-                                                        },
-                                                    ) {
-                                                        eprintln!("Failed to emit container ID: {}", err);
-                                                    }
-                                                }
+                            // Emit container file citations as generated files
+                            // These come from text annotations when model references files in markdown
+                            if !file_citations.is_empty() {
+                                // Emit container ID - from file citation or from tracked container_id
+                                let effective_container_id = file_citations.first()
+                                    .filter(|f| !f.container_id.is_empty())
+                                    .map(|f| f.container_id.clone())
+                                    .or_else(|| current_container_id.clone());
 
-                                                // Fetch file content for persistence
-                                                let mut generated_files: Vec<GeneratedFile> = Vec::new();
-                                                for f in file_citations {
-                                                    // Use container_id from file citation or effective_container_id
-                                                    let cid = if !f.container_id.is_empty() {
-                                                        Some(f.container_id.clone())
-                                                    } else {
-                                                        effective_container_id.clone()
-                                                    };
+                                if let Some(ref cid) = effective_container_id {
+                                    if let Err(err) = window.emit(
+                                        "chat-container-id",
+                                        ContainerIdEvent {
+                                            turn_id: turn_id.clone(),
+                                            container_id: cid.clone(),
+                                        },
+                                    ) {
+                                        eprintln!("Failed to emit container ID: {}", err);
+                                    }
+                                }
 
-                                                    let (inline_data, image_preview, mime_type) = if let Some(ref container_id) = cid {
-                                                        if f.file_id.starts_with("sandbox:") {
-                                                            // Placeholder from output_text.done; the real cfile_
-                                                            // id arrives via content_part.done and replaces this
-                                                            // entry through merge_generated_file. Don't attempt
-                                                            // the fetch — the Containers API rejects sandbox:
-                                                            // paths and the noise is misleading.
-                                                            (None, None, None)
-                                                        } else {
-                                                            match fetch_file_content_base64(&api_key, container_id, &f.file_id).await {
-                                                                Ok(data) => {
-                                                                    // Guess mime type from filename extension
-                                                                    let mime = crate::mime_utils::extension_to_mime(&f.filename);
-                                                                    let preview = mime.as_ref()
-                                                                        .filter(|m| m.starts_with("image/"))
-                                                                        .map(|m| format!("data:{};base64,{}", m, data));
-                                                                    (Some(data), preview, mime.map(|s| s.to_string()))
-                                                                }
-                                                                Err(e) => {
-                                                                    eprintln!("Failed to fetch file content for {}: {}", f.file_id, e);
-                                                                    (None, None, None)
-                                                                }
-                                                            }
-                                                        }
-                                                    } else {
-                                                        (None, None, None)
-                                                    };
+                                // Fetch file content for persistence
+                                let mut generated_files: Vec<GeneratedFile> = Vec::new();
+                                for f in file_citations {
+                                    // Use container_id from file citation or effective_container_id
+                                    let cid = if !f.container_id.is_empty() {
+                                        Some(f.container_id.clone())
+                                    } else {
+                                        effective_container_id.clone()
+                                    };
 
-                                                    generated_files.push(GeneratedFile {
-                                                        file_id: f.file_id,
-                                                        filename: f.filename,
-                                                        mime_type,
-                                                        image_preview,
-                                                        inline_data,
-                                                    });
+                                    let (inline_data, image_preview, mime_type) = if let Some(ref container_id) = cid {
+                                        if f.file_id.starts_with("sandbox:") {
+                                            // Placeholder from output_text.done; the real cfile_
+                                            // id arrives via content_part.done and replaces this
+                                            // entry through merge_generated_file. Don't attempt
+                                            // the fetch — the Containers API rejects sandbox:
+                                            // paths and the noise is misleading.
+                                            (None, None, None)
+                                        } else {
+                                            match fetch_file_content_base64(api_key, container_id, &f.file_id).await {
+                                                Ok(data) => {
+                                                    // Guess mime type from filename extension
+                                                    let mime = crate::mime_utils::extension_to_mime(&f.filename);
+                                                    let preview = mime.as_ref()
+                                                        .filter(|m| m.starts_with("image/"))
+                                                        .map(|m| format!("data:{};base64,{}", m, data));
+                                                    (Some(data), preview, mime.map(|s| s.to_string()))
                                                 }
-
-                                                // Buffer rather than emit: the same files arrive again via
-                                                // response.content_part.done, and output_text.done usually
-                                                // only has sandbox placeholders. Dedupe and emit once at end.
-                                                for f in generated_files {
-                                                    crate::providers::openai::merge_generated_file(
-                                                        &mut buffered_files,
-                                                        f,
-                                                    );
+                                                Err(e) => {
+                                                    eprintln!("Failed to fetch file content for {}: {}", f.file_id, e);
+                                                    (None, None, None)
                                                 }
                                             }
                                         }
-                                        OpenAIStreamEvent::WebSearchStarted { action_kind, detail } => {
-                                            let kind = action_kind.as_deref().unwrap_or("?");
-                                            let label = match detail.as_deref() {
-                                                Some(d) if !d.is_empty() => format!("OpenAI web_search_call action={} {}", kind, d),
-                                                _ => format!("OpenAI web_search_call action={}", kind),
-                                            };
-                                            llm_logger::log_feature_used("chat", &label);
-                                        }
-                                        // Code interpreter events - reuse same ExecutionDelta pattern as Anthropic
-                                        OpenAIStreamEvent::CodeInterpreterStarted { call_id: _ } => {
-                                            llm_logger::log_feature_used("chat", "OpenAI Code Interpreter started");
-                                            pending_code.clear();
-                                        }
-                                        OpenAIStreamEvent::CodeInterpreterCodeDelta { call_id: _, code } => {
-                                            pending_code.push_str(&code);
-                                        }
-                                        OpenAIStreamEvent::CodeInterpreterCodeDone { call_id: _, code } => {
-                                            // Emit execution started with full code
-                                            let final_code = if code.is_empty() { pending_code.clone() } else { code };
-                                            let delta = StreamDelta {
-                                                turn_id: turn_id.clone(),
-                                                text: String::new(),
-                                                citations: None,
-                                                inline_citations: None,
-                                                thinking: None,
-                                                execution: Some(ExecutionDelta {
-                                                    tool_name: tool_names::CODE_INTERPRETER.to_string(),
-                                                    stdout: None,
-                                                    stderr: None,
-                                                    status: ExecutionStatus::Started,
-                                                    code: Some(final_code),
-                                                    files: None,
-                                                }),
-                                            };
-                                            if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                eprintln!("Failed to emit execution started delta: {}", err);
-                                            }
-                                        }
-                                        OpenAIStreamEvent::CodeInterpreterResult {
-                                            call_id: _,
-                                            container_id,
-                                            stdout,
-                                            stderr,
-                                            files,
-                                        } => {
-                                            // Track container ID for later use in TextDone
-                                            if container_id.is_some() {
-                                                current_container_id = container_id.clone();
-                                            }
+                                    } else {
+                                        (None, None, None)
+                                    };
 
-                                            // Emit container ID for persistence (reuse same event as Anthropic)
-                                            if let Some(ref cid) = container_id {
-                                                if let Err(err) = window.emit(
-                                                    "chat-container-id",
-                                                    ContainerIdEvent {
-                                                        turn_id: turn_id.clone(),
-                                                        container_id: cid.clone(),
-                                                    },
-                                                ) {
-                                                    eprintln!("Failed to emit container ID: {}", err);
-                                                }
-                                            }
+                                    generated_files.push(GeneratedFile {
+                                        file_id: f.file_id,
+                                        filename: f.filename,
+                                        mime_type,
+                                        image_preview,
+                                        inline_data,
+                                    });
+                                }
 
-                                            // Convert files to GeneratedFile format, fetching content for persistence
-                                            let mut generated_files: Vec<GeneratedFile> = Vec::new();
-                                            for f in files {
-                                                let (inline_data, image_preview, mime_type) = if let Some(ref cid) = container_id {
-                                                    if f.file_id.starts_with("sandbox:") {
-                                                        // See sibling site above: sandbox: placeholders are
-                                                        // superseded by the real cfile_ id via merge_generated_file.
-                                                        (None, None, None)
-                                                    } else {
-                                                        match fetch_file_content_base64(&api_key, cid, &f.file_id).await {
-                                                            Ok(data) => {
-                                                                // Guess mime type from filename extension
-                                                                let mime = crate::mime_utils::extension_to_mime(&f.filename);
-                                                                let preview = mime.as_ref()
-                                                                    .filter(|m| m.starts_with("image/"))
-                                                                    .map(|m| format!("data:{};base64,{}", m, data));
-                                                                (Some(data), preview, mime.map(|s| s.to_string()))
-                                                            }
-                                                            Err(e) => {
-                                                                eprintln!("Failed to fetch file content for {}: {}", f.file_id, e);
-                                                                (None, None, None)
-                                                            }
-                                                        }
-                                                    }
-                                                } else {
-                                                    (None, None, None)
-                                                };
+                                // Buffer rather than emit: the same files arrive again via
+                                // response.content_part.done, and output_text.done usually
+                                // only has sandbox placeholders. Dedupe and emit once at end.
+                                for f in generated_files {
+                                    crate::providers::openai::merge_generated_file(
+                                        &mut buffered_files,
+                                        f,
+                                    );
+                                }
+                            }
+                        }
+                        OpenAIStreamEvent::WebSearchStarted { action_kind, detail } => {
+                            let kind = action_kind.as_deref().unwrap_or("?");
+                            let label = match detail.as_deref() {
+                                Some(d) if !d.is_empty() => format!("OpenAI web_search_call action={} {}", kind, d),
+                                _ => format!("OpenAI web_search_call action={}", kind),
+                            };
+                            llm_logger::log_feature_used("chat", &label);
+                            set_stream_phase(window, &turn_id, StreamPhase::Searching);
+                        }
+                        // Code interpreter events - reuse same ExecutionDelta pattern as Anthropic
+                        OpenAIStreamEvent::CodeInterpreterStarted { call_id: _ } => {
+                            llm_logger::log_feature_used("chat", "OpenAI Code Interpreter started");
+                            set_stream_phase(window, &turn_id, StreamPhase::Executing);
+                            pending_code.clear();
+                        }
+                        OpenAIStreamEvent::CodeInterpreterInterpreting { call_id: _ } => {
+                            emit_execution_progress(window, &turn_id);
+                        }
+                        OpenAIStreamEvent::CodeInterpreterCodeDelta { call_id: _, code } => {
+                            pending_code.push_str(&code);
+                        }
+                        OpenAIStreamEvent::CodeInterpreterCodeDone { call_id: _, code } => {
+                            // Emit execution started with full code
+                            let final_code = if code.is_empty() { pending_code.clone() } else { code };
+                            let delta = StreamDelta {
+                                turn_id: turn_id.clone(),
+                                text: String::new(),
+                                citations: None,
+                                inline_citations: None,
+                                thinking: None,
+                                execution: Some(ExecutionDelta {
+                                    tool_name: tool_names::CODE_INTERPRETER.to_string(),
+                                    stdout: None,
+                                    stderr: None,
+                                    status: ExecutionStatus::Started,
+                                    code: Some(final_code),
+                                    files: None,
+                                }),
+                            };
+                            if let Err(err) = emit_stream_delta(window, delta) {
+                                eprintln!("Failed to emit execution started delta: {}", err);
+                            }
+                        }
+                        OpenAIStreamEvent::CodeInterpreterResult {
+                            call_id: _,
+                            container_id,
+                            stdout,
+                            stderr,
+                            files,
+                        } => {
+                            // Track container ID for later use in TextDone
+                            if container_id.is_some() {
+                                current_container_id = container_id.clone();
+                            }
 
-                                                generated_files.push(GeneratedFile {
-                                                    file_id: f.file_id,
-                                                    filename: f.filename,
-                                                    mime_type,
-                                                    image_preview,
-                                                    inline_data,
-                                                });
-                                            }
+                            // Emit container ID for persistence (reuse same event as Anthropic)
+                            if let Some(ref cid) = container_id {
+                                if let Err(err) = window.emit(
+                                    "chat-container-id",
+                                    ContainerIdEvent {
+                                        turn_id: turn_id.clone(),
+                                        container_id: cid.clone(),
+                                    },
+                                ) {
+                                    eprintln!("Failed to emit container ID: {}", err);
+                                }
+                            }
 
-                                            // Buffer files; they're emitted deduped at stream end alongside
-                                            // the ones referenced in the final message text.
-                                            for f in generated_files {
-                                                crate::providers::openai::merge_generated_file(
-                                                    &mut buffered_files,
-                                                    f,
-                                                );
+                            // Convert files to GeneratedFile format, fetching content for persistence
+                            let mut generated_files: Vec<GeneratedFile> = Vec::new();
+                            for f in files {
+                                let (inline_data, image_preview, mime_type) = if let Some(ref cid) = container_id {
+                                    if f.file_id.starts_with("sandbox:") {
+                                        // See sibling site above: sandbox: placeholders are
+                                        // superseded by the real cfile_ id via merge_generated_file.
+                                        (None, None, None)
+                                    } else {
+                                        match fetch_file_content_base64(api_key, cid, &f.file_id).await {
+                                            Ok(data) => {
+                                                // Guess mime type from filename extension
+                                                let mime = crate::mime_utils::extension_to_mime(&f.filename);
+                                                let preview = mime.as_ref()
+                                                    .filter(|m| m.starts_with("image/"))
+                                                    .map(|m| format!("data:{};base64,{}", m, data));
+                                                (Some(data), preview, mime.map(|s| s.to_string()))
                                             }
-
-                                            // Determine status based on stderr
-                                            let status = if stderr.is_some() {
-                                                ExecutionStatus::Failed {
-                                                    error: stderr.clone().unwrap_or_default(),
-                                                }
-                                            } else {
-                                                ExecutionStatus::Completed
-                                            };
-
-                                            let delta = StreamDelta {
-                                                turn_id: turn_id.clone(),
-                                                text: String::new(),
-                                                citations: None,
-                                                inline_citations: None,
-                                                thinking: None,
-                                                execution: Some(ExecutionDelta {
-                                                    tool_name: tool_names::CODE_INTERPRETER.to_string(),
-                                                    stdout,
-                                                    stderr,
-                                                    status,
-                                                    code: None,
-                                                    // Files were buffered above; emitted deduped at stream end.
-                                                    files: None,
-                                                }),
-                                            };
-                                            if let Err(err) = window.emit("chat-stream-delta", delta) {
-                                                eprintln!("Failed to emit execution result delta: {}", err);
+                                            Err(e) => {
+                                                eprintln!("Failed to fetch file content for {}: {}", f.file_id, e);
+                                                (None, None, None)
                                             }
                                         }
-                                        OpenAIStreamEvent::Error { message } => {
-                                            llm_logger::log_error("chat", &message);
-                                            return Err(message);
-                                        }
-                                        OpenAIStreamEvent::Unknown => {}
                                     }
+                                } else {
+                                    (None, None, None)
+                                };
+
+                                generated_files.push(GeneratedFile {
+                                    file_id: f.file_id,
+                                    filename: f.filename,
+                                    mime_type,
+                                    image_preview,
+                                    inline_data,
+                                });
+                            }
+
+                            // Buffer files; they're emitted deduped at stream end alongside
+                            // the ones referenced in the final message text.
+                            for f in generated_files {
+                                crate::providers::openai::merge_generated_file(
+                                    &mut buffered_files,
+                                    f,
+                                );
+                            }
+
+                            // Determine status based on stderr
+                            let status = if stderr.is_some() {
+                                ExecutionStatus::Failed {
+                                    error: stderr.clone().unwrap_or_default(),
                                 }
+                            } else {
+                                ExecutionStatus::Completed
+                            };
+
+                            let delta = StreamDelta {
+                                turn_id: turn_id.clone(),
+                                text: String::new(),
+                                citations: None,
+                                inline_citations: None,
+                                thinking: None,
+                                execution: Some(ExecutionDelta {
+                                    tool_name: tool_names::CODE_INTERPRETER.to_string(),
+                                    stdout,
+                                    stderr,
+                                    status,
+                                    code: None,
+                                    // Files were buffered above; emitted deduped at stream end.
+                                    files: None,
+                                }),
+                            };
+                            if let Err(err) = emit_stream_delta(window, delta) {
+                                eprintln!("Failed to emit execution result delta: {}", err);
                             }
                         }
+                        OpenAIStreamEvent::Error { message } => {
+                            llm_logger::log_error("chat", &message);
+                            return Err(SidestreamError::from_stream_message(message));
+                        }
+                        OpenAIStreamEvent::Unknown => {}
                     }
-                    Some(Err(e)) => return Err(e.to_string()),
-                    None => break,
+                }
+                if ended {
+                    break;
                 }
             }
         }
@@ -423,12 +459,35 @@ pub async fn send_chat_message_openai(
 
     llm_logger::log_response_complete("chat", &full_response);
     emit_generated_files(window, &turn_id, std::mem::take(&mut buffered_files), &full_response);
-    if let Err(err) = window.emit("chat-stream-done", StreamEvent { turn_id }) {
+    if let Err(err) = emit_stream_done(window, &turn_id) {
         eprintln!("Failed to emit chat-stream-done event: {}", err);
     }
     Ok(())
 }
 
+/// Emit a running-status execution delta so long-running interpreter calls
+/// show progress before they finish.
+fn emit_execution_progress(window: &tauri::Window, turn_id: &str) {
+    let delta = StreamDelta {
+        turn_id: turn_id.to_string(),
+        text: String::new(),
+        citations: None,
+        inline_citations: None,
+        thinking: None,
+        execution: Some(ExecutionDelta {
+            tool_name: tool_names::CODE_INTERPRETER.to_string(),
+            stdout: None,
+            stderr: None,
+            status: ExecutionStatus::Running,
+            code: None,
+            files: None,
+        }),
+    };
+    if let Err(err) = emit_stream_delta(window, delta) {
+        eprintln!("Failed to emit execution progress delta: {}", err);
+    }
+}
+
 /// Emit the deduped, display-selected set of generated files as a single
 /// execution-completed delta. Must be called before `chat-stream-done` so the
 /// frontend includes the files when it finalizes the streaming message.
@@ -458,7 +517,7 @@ fn emit_generated_files(
             files: Some(files),
         }),
     };
-    if let Err(err) = window.emit("chat-stream-delta", delta) {
+    if let Err(err) = emit_stream_delta(window, delta) {
         eprintln!("Failed to emit generated files delta: {}", err);
     }
 }
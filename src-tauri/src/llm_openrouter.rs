@@ -0,0 +1,199 @@
+use futures::StreamExt;
+use parking_lot::Mutex;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::commands::get_api_key_async;
+use crate::error::SidestreamError;
+use crate::heartbeat::StreamPhase;
+use crate::llm::{
+    emit_stream_delta, emit_stream_done, record_response_metadata, set_stream_phase, ChatMessage, ResponseMetadata,
+    StreamDelta, StreamEvent,
+};
+use crate::llm_logger;
+use crate::providers::anthropic::InlineCitation;
+use crate::providers::openrouter::{
+    parse_sse_event as openrouter_parse_sse_event, ChatRequestConfig as OpenRouterChatRequestConfig,
+    OpenRouterClient, OpenRouterModel, OpenRouterStreamEvent,
+};
+use crate::session_events;
+use crate::sse::SseDecoder;
+use crate::sse_recording::{self, SseByteStream};
+
+/// How long a fetched model catalog is reused
+const CATALOG_TTL: Duration = Duration::from_secs(60 * 60);
+
+static CATALOG: Mutex<Option<(Instant, Vec<OpenRouterModel>)>> = Mutex::new(None);
+
+/// Messages in the shape `ChatRequestConfig` expects
+pub fn openrouter_messages(messages: &[ChatMessage]) -> Vec<serde_json::Value> {
+    messages
+        .iter()
+        .map(|m| serde_json::json!({"role": m.role, "content": m.content}))
+        .collect()
+}
+
+/// Send chat message using OpenRouter's Chat Completions API
+pub async fn send_chat_message_openrouter(
+    app: &tauri::AppHandle,
+    window: &tauri::Window,
+    cancel_token: CancellationToken,
+    config: OpenRouterChatRequestConfig,
+    turn_id: String,
+) -> Result<(), SidestreamError> {
+    let api_key = get_api_key_async(app, "openrouter").await?;
+    let client = OpenRouterClient::new(api_key);
+    let body = client.build_chat_request(&config);
+
+    llm_logger::log_request("chat", &config.model, &body);
+
+    let response = client
+        .send_streaming_request(&body)
+        .await
+        .inspect_err(|e| llm_logger::log_error("chat", e))?;
+
+    record_response_metadata(window, &turn_id, ResponseMetadata::from_headers("openrouter", response.headers()));
+    // The web plugin searches before the model's first output
+    if config.web_search_enabled {
+        set_stream_phase(window, &turn_id, StreamPhase::Searching);
+    }
+    let stream = sse_recording::response_stream(app, "openrouter", &config.model, response);
+    stream_openrouter_response(window, cancel_token, turn_id, stream).await
+}
+
+/// Handle an OpenRouter response stream, emitting chat events to the window.
+/// Also used to replay recorded streams (see `sse_recording`).
+pub async fn stream_openrouter_response(
+    window: &tauri::Window,
+    cancel_token: CancellationToken,
+    turn_id: String,
+    mut stream: SseByteStream,
+) -> Result<(), SidestreamError> {
+    let mut decoder = SseDecoder::new();
+    let mut full_response = String::new();
+
+    loop {
+        tokio::select! {
+            _ = cancel_token.cancelled() => {
+                if let Err(err) = session_events::emit(window, &turn_id, "chat-stream-cancelled", StreamEvent { turn_id: turn_id.clone() }) {
+                    eprintln!("Failed to emit chat-stream-cancelled event: {}", err);
+                }
+                return Ok(());
+            }
+            chunk = stream.next() => {
+                let (payloads, ended) = match chunk {
+                    Some(Ok(bytes)) => (decoder.push(&bytes), false),
+                    Some(Err(message)) => return Err(SidestreamError::NetworkError { message }),
+                    None => (decoder.finish(), true),
+                };
+
+                for data in payloads {
+                    for event in openrouter_parse_sse_event(&data) {
+                        let delta = match event {
+                            OpenRouterStreamEvent::ResponseInfo { id, model } => {
+                                record_response_metadata(window, &turn_id, ResponseMetadata {
+                                    provider: "openrouter".to_string(),
+                                    model,
+                                    response_id: id,
+                                    request_id: None,
+                                });
+                                continue;
+                            }
+                            OpenRouterStreamEvent::TextDelta { text } => {
+                                full_response.push_str(&text);
+                                StreamDelta {
+                                    turn_id: turn_id.clone(),
+                                    text,
+                                    citations: None,
+                                    inline_citations: None,
+                                    thinking: None,
+                                    execution: None,
+                                }
+                            }
+                            OpenRouterStreamEvent::ReasoningDelta { text } => StreamDelta {
+                                turn_id: turn_id.clone(),
+                                text: String::new(),
+                                citations: None,
+                                inline_citations: None,
+                                thinking: Some(text),
+                                execution: None,
+                            },
+                            OpenRouterStreamEvent::Citations { citations } => {
+                                // Placed at the end of the text so far, like OpenAI's
+                                let offset = full_response.chars().count();
+                                let inline_citations = citations
+                                    .into_iter()
+                                    .map(|c| InlineCitation {
+                                        url: c.url,
+                                        title: c.title,
+                                        cited_text: c.cited_text,
+                                        char_offset: offset,
+                                    })
+                                    .collect();
+                                StreamDelta {
+                                    turn_id: turn_id.clone(),
+                                    text: String::new(),
+                                    citations: None,
+                                    inline_citations: Some(inline_citations),
+                                    thinking: None,
+                                    execution: None,
+                                }
+                            }
+                            OpenRouterStreamEvent::Finished { finish_reason } => {
+                                if finish_reason != "stop" {
+                                    llm_logger::log_feature_used("chat", &format!("OpenRouter finish reason: {}", finish_reason));
+                                }
+                                continue;
+                            }
+                            OpenRouterStreamEvent::Done => {
+                                llm_logger::log_response_complete("chat", &full_response);
+                                if let Err(err) = emit_stream_done(window, &turn_id) {
+                                    eprintln!("Failed to emit chat-stream-done event: {}", err);
+                                }
+                                return Ok(());
+                            }
+                            OpenRouterStreamEvent::Error { message } => {
+                                llm_logger::log_error("chat", &message);
+                                return Err(SidestreamError::from_stream_message(message));
+                            }
+                            OpenRouterStreamEvent::Unknown => continue,
+                        };
+                        if let Err(err) = emit_stream_delta(window, delta) {
+                            eprintln!("Failed to emit chat-stream-delta event: {}", err);
+                        }
+                    }
+                }
+                if ended {
+                    break;
+                }
+            }
+        }
+    }
+
+    llm_logger::log_response_complete("chat", &full_response);
+    if let Err(err) = emit_stream_done(window, &turn_id) {
+        eprintln!("Failed to emit chat-stream-done event: {}", err);
+    }
+    Ok(())
+}
+
+/// Models available through OpenRouter, for the model picker. The catalog is
+/// cached for an hour unless `refresh` is set.
+#[tauri::command]
+pub async fn list_openrouter_models(
+    app: tauri::AppHandle,
+    refresh: Option<bool>,
+) -> Result<Vec<OpenRouterModel>, SidestreamError> {
+    if !refresh.unwrap_or(false) {
+        if let Some((fetched_at, models)) = CATALOG.lock().as_ref() {
+            if fetched_at.elapsed() < CATALOG_TTL {
+                return Ok(models.clone());
+            }
+        }
+    }
+
+    let client = OpenRouterClient::new(get_api_key_async(&app, "openrouter").await?);
+    let models = client.list_models().await?;
+    *CATALOG.lock() = Some((Instant::now(), models.clone()));
+    Ok(models)
+}
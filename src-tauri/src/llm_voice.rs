@@ -1,17 +1,31 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
-use tauri::Emitter;
 use tokio_util::sync::CancellationToken;
 
+use crate::audio_chunks::{emit_transcription_progress, split_audio, stitch_transcripts, SplitOptions};
+use crate::citations;
+use crate::content_filters;
 use crate::commands::get_api_key_async;
-use crate::llm::{ChatMessage, StreamDelta, StreamEvent};
+use crate::error::SidestreamError;
+use crate::gemini_fallback;
+use crate::llm::{
+    emit_stream_delta, emit_stream_done, record_output_tokens, record_response_metadata, ChatMessage,
+    ResponseMetadata, StreamDelta, StreamEvent,
+};
+use crate::llm_gemini::medium_thinking_adjustment;
 use crate::llm_logger;
+use crate::request_adjustments::emit_request_adjusted;
 use crate::providers::anthropic::InlineCitation;
 use crate::providers::gemini::{
     extract_inline_citations_from_grounding, parse_sse_event as gemini_parse_sse_event,
     string_to_thinking_config, supports_thinking as gemini_supports_thinking,
-    GeminiClient, GeminiStreamEvent, VoiceChatRequestConfig as GeminiVoiceChatRequestConfig,
+    GeminiClient, GeminiStreamEvent, GroundingInfo,
+    VoiceChatRequestConfig as GeminiVoiceChatRequestConfig,
 };
+use crate::session_events;
+use crate::settings;
+use crate::sse::SseDecoder;
 
 /// Event emitted when transcription is extracted from a voice message response
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -33,11 +47,12 @@ pub async fn send_voice_message_impl(
     web_search_enabled: bool,
     gemini_thinking_level: Option<String>,
     turn_id: String,
-) -> Result<(), String> {
+) -> Result<(), SidestreamError> {
     let api_key = get_api_key_async(app, "google").await?;
     let client = GeminiClient::new(api_key);
 
     // Build messages for Gemini (previous conversation)
+    let messages = content_filters::mask_messages(app, messages);
     let api_messages: Vec<serde_json::Value> = messages
         .iter()
         .map(|m| serde_json::json!({"role": m.role, "content": m.content}))
@@ -51,6 +66,9 @@ pub async fn send_voice_message_impl(
     } else {
         None
     };
+    if let Some(adjustment) = medium_thinking_adjustment(gemini_thinking_level.as_deref(), thinking_config) {
+        emit_request_adjusted(window, &turn_id, vec![adjustment]);
+    }
 
     // Build voice chat request with audio
     let config = GeminiVoiceChatRequestConfig {
@@ -64,8 +82,7 @@ pub async fn send_voice_message_impl(
 
     llm_logger::log_request("voice-chat", &model, &body);
 
-    let response = client
-        .send_streaming_request(&model, &body)
+    let response = gemini_fallback::send_streaming_request(&client, window, "voice-chat", &turn_id, &model, &body)
         .await
         .map_err(|e| {
             llm_logger::log_error("voice-chat", &e);
@@ -74,156 +91,192 @@ pub async fn send_voice_message_impl(
 
     // Stream the response and extract transcription
     let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
-    let mut full_response = String::new();
+    let mut decoder = SseDecoder::line_delimited();
+    let mut voice_response = VoiceResponse::default();
     let mut accumulated_text = String::new();
-    let mut transcription_emitted = false;
+    // Citation deltas waiting on their grounding redirects
+    let mut pending_citations = Vec::new();
 
     loop {
         tokio::select! {
             _ = cancel_token.cancelled() => {
-                window.emit("chat-stream-cancelled", StreamEvent { turn_id: turn_id.clone() }).ok();
+                session_events::emit(window, &turn_id, "chat-stream-cancelled", StreamEvent { turn_id: turn_id.clone() }).ok();
                 return Ok(());
             }
             chunk = stream.next() => {
-                match chunk {
-                    Some(Ok(bytes)) => {
-                        let text = String::from_utf8_lossy(&bytes);
-                        buffer.push_str(&text);
-
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim_end_matches('\r').to_string();
-                            buffer = buffer[line_end + 1..].to_string();
-
-                            if let Some(data) = line.strip_prefix("data: ") {
-                                // parse_sse_event returns Vec since one SSE can have multiple parts
-                                for event in gemini_parse_sse_event(data) {
-                                match event {
-                                    GeminiStreamEvent::TextDelta { text: t } => {
-                                        // Gemini sends complete text in each chunk, need to diff
-                                        let new_text = if t.starts_with(&accumulated_text) {
-                                            t[accumulated_text.len()..].to_string()
-                                        } else {
-                                            accumulated_text.clear();
-                                            t.clone()
-                                        };
-                                        accumulated_text = t;
-
-                                        if !new_text.is_empty() {
-                                            full_response.push_str(&new_text);
-
-                                            // Check if we have a complete transcription to extract
-                                            if !transcription_emitted {
-                                                if let Some(transcription) = extract_transcription(&full_response) {
-                                                    transcription_emitted = true;
-                                                    window.emit("voice-transcription", VoiceTranscriptionEvent {
-                                                        transcription: transcription.clone(),
-                                                    }).ok();
-
-                                                    // Remove transcription from the response we're streaming
-                                                    // Find where the transcription block ends and get the rest
-                                                    if let Some(end_idx) = full_response.find("[/TRANSCRIPTION]") {
-                                                        let after_transcription = &full_response[end_idx + "[/TRANSCRIPTION]".len()..];
-                                                        let clean_start = after_transcription.trim_start();
-                                                        if !clean_start.is_empty() {
-                                                            let delta = StreamDelta {
-                                                                turn_id: turn_id.clone(),
-                                                                text: clean_start.to_string(),
-                                                                citations: None,
-                                                                inline_citations: None,
-                                                                thinking: None,
-                                                                execution: None,
-                                                            };
-                                                            window.emit("chat-stream-delta", delta).ok();
-                                                        }
-                                                    }
-                                                    continue;
-                                                }
-                                            }
-
-                                            // Only emit deltas for content after transcription
-                                            if transcription_emitted {
-                                                let delta = StreamDelta {
-                                                    turn_id: turn_id.clone(),
-                                                    text: new_text,
-                                                    citations: None,
-                                                    inline_citations: None,
-                                                    thinking: None,
-                                                    execution: None,
-                                                };
-                                                window.emit("chat-stream-delta", delta).ok();
-                                            }
-                                        }
-                                    }
-                                    GeminiStreamEvent::ThinkingDelta { text: _ } => {
-                                        // Voice messages don't display thinking UI
-                                    }
-                                    GeminiStreamEvent::GroundingMetadata { metadata } => {
-                                        llm_logger::log_feature_used("voice-chat", "Gemini Google Search");
-                                        let gemini_citations = extract_inline_citations_from_grounding(&metadata, &full_response);
-                                        if !gemini_citations.is_empty() {
-                                            let inline_citations: Vec<InlineCitation> = gemini_citations
-                                                .into_iter()
-                                                .map(|c| InlineCitation {
-                                                    url: c.url,
-                                                    title: c.title,
-                                                    cited_text: c.cited_text,
-                                                    char_offset: c.char_offset,
-                                                })
-                                                .collect();
-                                            let delta = StreamDelta {
-                                                turn_id: turn_id.clone(),
-                                                text: String::new(),
-                                                citations: None,
-                                                inline_citations: Some(inline_citations),
-                                                thinking: None,
-                                                execution: None,
-                                            };
-                                            window.emit("chat-stream-delta", delta).ok();
-                                        }
-                                    }
-                                    GeminiStreamEvent::ResponseComplete { .. } => {
-                                        llm_logger::log_response_complete("voice-chat", &full_response);
-                                        window.emit("chat-stream-done", StreamEvent { turn_id: turn_id.clone() }).ok();
-                                        return Ok(());
-                                    }
-                                    GeminiStreamEvent::Error { message } => {
-                                        llm_logger::log_error("voice-chat", &message);
-                                        return Err(message);
-                                    }
-                                    // Code execution events not applicable to voice transcription
-                                    GeminiStreamEvent::ExecutableCode { .. } => {}
-                                    GeminiStreamEvent::CodeExecutionResult { .. } => {}
-                                    GeminiStreamEvent::InlineData { .. } => {}
-                                    GeminiStreamEvent::UrlContextUsed { .. } => {}
-                                    GeminiStreamEvent::Unknown => {}
+                let (payloads, ended) = match chunk {
+                    Some(Ok(bytes)) => (decoder.push(&bytes), false),
+                    Some(Err(e)) => return Err(e.into()),
+                    None => (decoder.finish(), true),
+                };
+
+                for data in payloads {
+                    // parse_sse_event returns Vec since one SSE can have multiple parts
+                    for event in gemini_parse_sse_event(&data) {
+                        match event {
+                            GeminiStreamEvent::TextDelta { text: t } => {
+                                // Gemini sends complete text in each chunk, need to diff
+                                let new_text = if t.starts_with(&accumulated_text) {
+                                    t[accumulated_text.len()..].to_string()
+                                } else {
+                                    accumulated_text.clear();
+                                    t.clone()
+                                };
+                                accumulated_text = t;
+
+                                let (transcription, reply_text) = voice_response.push(&new_text);
+                                if let Some(transcription) = transcription {
+                                    session_events::emit(
+                                        window,
+                                        &turn_id,
+                                        "voice-transcription",
+                                        VoiceTranscriptionEvent { transcription },
+                                    )
+                                    .ok();
+                                }
+                                // Only the reply after the transcription block is streamed
+                                if !reply_text.is_empty() {
+                                    let delta = StreamDelta {
+                                        turn_id: turn_id.clone(),
+                                        text: reply_text,
+                                        citations: None,
+                                        inline_citations: None,
+                                        thinking: None,
+                                        execution: None,
+                                    };
+                                    emit_stream_delta(window, delta).ok();
                                 }
-                                } // end for event in events
                             }
+                            GeminiStreamEvent::ThinkingDelta { text: _ } => {
+                                // Voice messages don't display thinking UI
+                            }
+                            GeminiStreamEvent::GroundingMetadata { metadata } => {
+                                llm_logger::log_feature_used("voice-chat", "Gemini Google Search");
+                                let inline_citations = voice_response.reply_citations(&metadata);
+                                if !inline_citations.is_empty() {
+                                    pending_citations.push(citations::spawn_citation_delta(window, &turn_id, inline_citations));
+                                }
+                            }
+                            GeminiStreamEvent::ResponseComplete { output_tokens, .. } => {
+                                if let Some(tokens) = output_tokens {
+                                    record_output_tokens(window, &turn_id, tokens);
+                                }
+                                citations::finish_citation_deltas(&mut pending_citations).await;
+                                llm_logger::log_response_complete("voice-chat", &voice_response.raw);
+                                emit_stream_done(window, &turn_id).ok();
+                                return Ok(());
+                            }
+                            GeminiStreamEvent::Error { message } => {
+                                llm_logger::log_error("voice-chat", &message);
+                                return Err(SidestreamError::from_stream_message(message));
+                            }
+                            // Code execution events not applicable to voice transcription
+                            GeminiStreamEvent::ExecutableCode { .. } => {}
+                            GeminiStreamEvent::CodeExecutionResult { .. } => {}
+                            GeminiStreamEvent::InlineData { .. } => {}
+                            GeminiStreamEvent::UrlContextUsed { .. } => {}
+                            GeminiStreamEvent::ResponseInfo { response_id, model_version } => {
+                                record_response_metadata(window, &turn_id, ResponseMetadata {
+                                    provider: "google".to_string(),
+                                    model: model_version,
+                                    response_id,
+                                    request_id: None,
+                                });
+                            }
+                            GeminiStreamEvent::Unknown => {}
                         }
                     }
-                    Some(Err(e)) => return Err(e.to_string()),
-                    None => break,
+                }
+
+                if ended {
+                    break;
                 }
             }
         }
     }
 
-    llm_logger::log_response_complete("voice-chat", &full_response);
-    window.emit("chat-stream-done", StreamEvent { turn_id }).ok();
+    citations::finish_citation_deltas(&mut pending_citations).await;
+    llm_logger::log_response_complete("voice-chat", &voice_response.raw);
+    emit_stream_done(window, &turn_id).ok();
     Ok(())
 }
 
+const TRANSCRIPTION_START_TAG: &str = "[TRANSCRIPTION]";
+const TRANSCRIPTION_END_TAG: &str = "[/TRANSCRIPTION]";
+
+/// A voice reply as it streams: Gemini first writes what it heard in a
+/// `[TRANSCRIPTION]` block, then the reply shown to the user. Grounding
+/// offsets refer to the whole text, so they are shifted to the reply.
+#[derive(Debug, Default)]
+struct VoiceResponse {
+    /// Everything Gemini has sent
+    raw: String,
+    /// Byte index in `raw` where the reply starts, once the block is complete
+    reply_start: Option<usize>,
+    /// Bytes of `raw` already returned as reply text
+    emitted: usize,
+}
+
+impl VoiceResponse {
+    /// Add streamed text. Returns the transcription when its block has just
+    /// completed, and any new reply text.
+    fn push(&mut self, text: &str) -> (Option<String>, String) {
+        self.raw.push_str(text);
+
+        let mut transcription = None;
+        if self.reply_start.is_none() {
+            let Some(heard) = extract_transcription(&self.raw) else {
+                return (None, String::new());
+            };
+            let Some(end_idx) = self.raw.find(TRANSCRIPTION_END_TAG) else {
+                return (None, String::new());
+            };
+            let reply_start = end_idx + TRANSCRIPTION_END_TAG.len();
+            self.reply_start = Some(reply_start);
+            self.emitted = reply_start;
+            transcription = Some(heard);
+        }
+
+        // Whitespace between the block and the reply isn't part of the reply
+        if let Some(reply_start) = self.reply_start.filter(|&start| start == self.emitted) {
+            let rest = &self.raw[reply_start..];
+            let skipped = rest.len() - rest.trim_start().len();
+            self.reply_start = Some(reply_start + skipped);
+            self.emitted = reply_start + skipped;
+        }
+
+        let reply_text = self.raw[self.emitted..].to_string();
+        self.emitted = self.raw.len();
+        (transcription, reply_text)
+    }
+
+    /// Inline citations with offsets into the reply; citations inside the
+    /// transcription block (or before it is complete) are dropped
+    fn reply_citations(&self, metadata: &GroundingInfo) -> Vec<InlineCitation> {
+        let Some(reply_start) = self.reply_start else {
+            return Vec::new();
+        };
+        let reply_start_chars = self.raw[..reply_start].chars().count();
+        extract_inline_citations_from_grounding(metadata, &self.raw)
+            .into_iter()
+            .filter(|citation| citation.char_offset >= reply_start_chars)
+            .map(|citation| InlineCitation {
+                url: citation.url,
+                title: citation.title,
+                cited_text: citation.cited_text,
+                char_offset: citation.char_offset - reply_start_chars,
+            })
+            .collect()
+    }
+}
+
 /// Extract transcription from response text if complete
 fn extract_transcription(text: &str) -> Option<String> {
-    let start_tag = "[TRANSCRIPTION]";
-    let end_tag = "[/TRANSCRIPTION]";
-
-    let start_idx = text.find(start_tag)?;
-    let end_idx = text.find(end_tag)?;
+    let start_idx = text.find(TRANSCRIPTION_START_TAG)?;
+    let end_idx = text.find(TRANSCRIPTION_END_TAG)?;
 
     if end_idx > start_idx {
-        let transcription = &text[start_idx + start_tag.len()..end_idx];
+        let transcription = &text[start_idx + TRANSCRIPTION_START_TAG.len()..end_idx];
         Some(transcription.trim().to_string())
     } else {
         None
@@ -234,18 +287,94 @@ fn extract_transcription(text: &str) -> Option<String> {
 pub async fn transcribe_audio_gemini_impl(
     app: &tauri::AppHandle,
     audio_base64: String,
-) -> Result<String, String> {
+) -> Result<String, SidestreamError> {
     let api_key = get_api_key_async(app, "google").await?;
     let client = GeminiClient::new(api_key);
 
-    // Use a fast model for transcription
-    let model = "gemini-2.0-flash";
+    // A fast model by default; configurable via the transcription model default
+    let model = settings::gemini_transcription_model(app);
 
     // Build transcription-only request
-    let body = client.build_transcription_request(&audio_base64);
+    let language = settings::transcription_language(app);
+    let body = client.build_transcription_request(&audio_base64, "audio/wav", language.as_deref());
 
     // Send non-streaming request and get transcription
-    let transcription = client.send_request(model, &body).await?;
+    let transcription = client.send_request(&model, &body).await?;
 
     Ok(transcription.trim().to_string())
 }
+
+/// Pieces of long files: short enough that each transcript stays well inside
+/// the model's output limit, overlapping so no word is lost at a cut
+const FILE_SPLIT: SplitOptions = SplitOptions {
+    chunk_secs: 600,
+    overlap_secs: 3,
+    max_bytes: usize::MAX,
+};
+
+/// Pieces larger than this are uploaded with the Files API instead of being
+/// sent inline (requests are capped at 20MB including base64 overhead)
+const MAX_INLINE_AUDIO_BYTES: usize = 14 * 1024 * 1024;
+
+/// Transcribe an audio file of any Gemini-supported type (mp3, m4a, ogg, ...).
+/// Long WAV and MP3 files are transcribed in pieces and the transcripts
+/// stitched, with `transcription-progress` events after each piece.
+pub async fn transcribe_audio_file_gemini_impl(
+    app: &tauri::AppHandle,
+    bytes: Vec<u8>,
+    mime_type: &str,
+) -> Result<String, SidestreamError> {
+    let api_key = get_api_key_async(app, "google").await?;
+    let client = GeminiClient::new(api_key);
+    let model = settings::gemini_transcription_model(app);
+    let language = settings::transcription_language(app);
+
+    let pieces = split_audio(bytes, mime_type, FILE_SPLIT);
+    let total = pieces.len();
+    let mut transcripts = Vec::new();
+    for (index, piece) in pieces.into_iter().enumerate() {
+        let body = if piece.len() > MAX_INLINE_AUDIO_BYTES {
+            let display_name = format!("sidestream-transcription-{}", index + 1);
+            let file_uri = client.upload_file(piece, mime_type, &display_name).await?;
+            client.build_file_transcription_request(&file_uri, mime_type, language.as_deref())
+        } else {
+            client.build_transcription_request(&BASE64.encode(&piece), mime_type, language.as_deref())
+        };
+        let transcript = client.send_request(&model, &body).await?;
+        transcripts.push(transcript);
+        emit_transcription_progress(app, index + 1, total);
+    }
+
+    Ok(stitch_transcripts(&transcripts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voice_reply_excludes_transcription_and_shifts_citations() {
+        let mut response = VoiceResponse::default();
+        assert_eq!(response.push("[TRANSCRIPTION]What's né"), (None, String::new()));
+        assert_eq!(
+            response.push("w?[/TRANSCRIPTION]\n"),
+            (Some("What's néw?".to_string()), String::new())
+        );
+        assert_eq!(response.push("\nRust 1.90 is out."), (None, "Rust 1.90 is out.".to_string()));
+
+        // Grounding byte offsets cover the whole text, transcription included
+        let reply_end = response.raw.len();
+        let metadata: GroundingInfo = serde_json::from_value(serde_json::json!({
+            "webSearchQueries": ["rust release"],
+            "groundingChunks": [{"web": {"uri": "https://blog.rust-lang.org", "title": "Rust Blog"}}],
+            "groundingSupports": [
+                {"segment": {"endIndex": 5}, "groundingChunkIndices": [0]},
+                {"segment": {"endIndex": reply_end}, "groundingChunkIndices": [0]}
+            ]
+        }))
+        .unwrap();
+        let citations = response.reply_citations(&metadata);
+        assert_eq!(citations.len(), 1);
+        assert_eq!(citations[0].char_offset, "Rust 1.90 is out.".chars().count());
+    }
+}
@@ -0,0 +1,371 @@
+//! Local transcription with whisper.cpp
+//!
+//! Recordings are normally transcribed by OpenAI. With the transcription
+//! backend set to `local`, `stop_audio_recording` (and retries) run
+//! whisper.cpp on the device instead, so no audio leaves it. Whisper models
+//! are downloaded on request into `app_data_dir/whisper-models` as ggml
+//! files; `download_whisper_model` reports `whisper-model-download-progress`
+//! events while it runs. Multilingual models transcribe in the configured
+//! transcription language, or detect it. The engine itself is compiled in only with the
+//! `local-transcription` cargo feature (it needs CMake and a C++ toolchain);
+//! without it, choosing the local backend is refused.
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager};
+use tokio::io::AsyncWriteExt;
+
+use crate::proxy;
+use crate::settings;
+
+const TRANSCRIPTION_SETTINGS_KEY: &str = "transcription";
+
+const MODELS_DIR: &str = "whisper-models";
+
+/// ggml model files published with whisper.cpp
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// Whisper takes 16kHz mono audio
+const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+/// Bytes downloaded between progress events
+const PROGRESS_STEP: u64 = 4 * 1024 * 1024;
+
+/// Models offered for download: (name, approximate size in MB). `.en`
+/// models only understand English but are more accurate at it.
+const MODELS: &[(&str, u32)] = &[
+    ("tiny.en", 75),
+    ("base.en", 142),
+    ("base", 142),
+    ("small.en", 466),
+    ("small", 466),
+    ("large-v3-turbo", 1624),
+];
+
+const DEFAULT_MODEL: &str = "base";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptionBackend {
+    #[default]
+    Openai,
+    Local,
+}
+
+/// Which engine transcribes recordings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionSettings {
+    pub backend: TranscriptionBackend,
+    /// Whisper model used by the local backend
+    pub model: String,
+}
+
+impl Default for TranscriptionSettings {
+    fn default() -> Self {
+        Self {
+            backend: TranscriptionBackend::default(),
+            model: DEFAULT_MODEL.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhisperModelInfo {
+    pub name: String,
+    pub size_mb: u32,
+    pub downloaded: bool,
+}
+
+/// Payload for whisper-model-download-progress event
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WhisperModelDownloadProgressEvent {
+    pub name: String,
+    pub downloaded_bytes: u64,
+    /// None when the server doesn't send a length
+    pub total_bytes: Option<u64>,
+}
+
+pub fn transcription_settings(app: &tauri::AppHandle) -> TranscriptionSettings {
+    settings::get_setting(app, TRANSCRIPTION_SETTINGS_KEY).unwrap_or_default()
+}
+
+fn check_model_name(name: &str) -> Result<(), String> {
+    if MODELS.iter().any(|(model, _)| *model == name) {
+        Ok(())
+    } else {
+        Err(format!("Unknown Whisper model: {}", name))
+    }
+}
+
+fn models_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join(MODELS_DIR))
+}
+
+fn model_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("ggml-{}.bin", name))
+}
+
+// ============================================================================
+// Transcription
+// ============================================================================
+
+/// A WAV recording as 16kHz mono samples (-1.0..1.0) for Whisper: channels
+/// are averaged and the rate converted by linear interpolation
+fn whisper_input(wav_bytes: &[u8]) -> Result<Vec<f32>, String> {
+    let mut reader = hound::WavReader::new(Cursor::new(wav_bytes)).map_err(|e| format!("Failed to read recording: {}", e))?;
+    let spec = reader.spec();
+    if spec.sample_format != hound::SampleFormat::Int || spec.bits_per_sample != 16 {
+        return Err("Local transcription needs 16-bit PCM audio".to_string());
+    }
+    let samples: Vec<i16> = reader
+        .samples::<i16>()
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read recording: {}", e))?;
+
+    let channels = usize::from(spec.channels.max(1));
+    let mono: Vec<f32> = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().map(|&s| f32::from(s)).sum::<f32>() / (frame.len() as f32 * 32768.0))
+        .collect();
+
+    if spec.sample_rate == WHISPER_SAMPLE_RATE || mono.is_empty() {
+        return Ok(mono);
+    }
+    let step = f64::from(spec.sample_rate) / f64::from(WHISPER_SAMPLE_RATE);
+    let out_len = (mono.len() as f64 / step) as usize;
+    Ok((0..out_len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            let a = mono[index];
+            let b = mono.get(index + 1).copied().unwrap_or(a);
+            a + (b - a) * fraction
+        })
+        .collect())
+}
+
+#[cfg(feature = "local-transcription")]
+fn run_whisper(model_path: &Path, audio: &[f32], language: Option<&str>) -> Result<String, String> {
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    /// The last model loaded, kept for the next recording
+    static CONTEXT: Mutex<Option<(PathBuf, Arc<WhisperContext>)>> = Mutex::new(None);
+
+    let context = {
+        let mut cached = CONTEXT.lock();
+        match cached.as_ref() {
+            Some((path, context)) if path == model_path => context.clone(),
+            _ => {
+                let path = model_path.to_str().ok_or("Whisper model path isn't valid UTF-8")?;
+                let context = WhisperContext::new_with_params(path, WhisperContextParameters::default())
+                    .map_err(|e| format!("Failed to load Whisper model: {}", e))?;
+                let context = Arc::new(context);
+                *cached = Some((model_path.to_path_buf(), context.clone()));
+                context
+            }
+        }
+    };
+
+    let mut state = context
+        .create_state()
+        .map_err(|e| format!("Failed to start Whisper: {}", e))?;
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_language(Some(language.unwrap_or("auto")));
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get().min(8));
+    params.set_n_threads(threads as i32);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_special(false);
+    params.set_print_timestamps(false);
+    state
+        .full(params, audio)
+        .map_err(|e| format!("Whisper transcription failed: {}", e))?;
+
+    let segments = state
+        .full_n_segments()
+        .map_err(|e| format!("Whisper transcription failed: {}", e))?;
+    let mut text = String::new();
+    for segment in 0..segments {
+        let segment_text = state
+            .full_get_segment_text(segment)
+            .map_err(|e| format!("Failed to read Whisper transcript: {}", e))?;
+        text.push_str(&segment_text);
+    }
+    Ok(text.trim().to_string())
+}
+
+#[cfg(not(feature = "local-transcription"))]
+fn run_whisper(_model_path: &Path, _audio: &[f32], _language: Option<&str>) -> Result<String, String> {
+    Err("This build of Sidestream doesn't include local transcription".to_string())
+}
+
+/// Transcribe a WAV recording on the device with Whisper model `model`
+pub async fn transcribe_wav(app: &tauri::AppHandle, model: &str, wav_bytes: Vec<u8>) -> Result<String, String> {
+    check_model_name(model)?;
+    let path = model_path(&models_dir(app)?, model);
+    if !path.exists() {
+        return Err(format!(
+            "The Whisper model \"{}\" isn't downloaded. Download it in Settings or switch back to OpenAI transcription.",
+            model
+        ));
+    }
+    // English-only models can't transcribe anything else; the others use the
+    // configured transcription language, or detect it
+    let language = if model.ends_with(".en") {
+        Some("en".to_string())
+    } else {
+        settings::transcription_language(app)
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let audio = whisper_input(&wav_bytes)?;
+        run_whisper(&path, &audio, language.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Local transcription task failed: {}", e))?
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Whether this build includes the whisper.cpp engine
+#[tauri::command]
+pub fn local_transcription_available() -> bool {
+    cfg!(feature = "local-transcription")
+}
+
+#[tauri::command]
+pub fn get_transcription_settings(app: tauri::AppHandle) -> TranscriptionSettings {
+    transcription_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_transcription_settings(app: tauri::AppHandle, settings: TranscriptionSettings) -> Result<(), String> {
+    check_model_name(&settings.model)?;
+    if settings.backend == TranscriptionBackend::Local && !cfg!(feature = "local-transcription") {
+        return Err("This build of Sidestream doesn't include local transcription".to_string());
+    }
+    settings::set_setting(&app, TRANSCRIPTION_SETTINGS_KEY, &settings)
+}
+
+/// Whisper models that can be downloaded, and whether each one is
+#[tauri::command]
+pub fn list_whisper_models(app: tauri::AppHandle) -> Result<Vec<WhisperModelInfo>, String> {
+    let dir = models_dir(&app)?;
+    Ok(MODELS
+        .iter()
+        .map(|(name, size_mb)| WhisperModelInfo {
+            name: name.to_string(),
+            size_mb: *size_mb,
+            downloaded: model_path(&dir, name).exists(),
+        })
+        .collect())
+}
+
+/// Download a Whisper model into the app data directory. The file only
+/// takes its final name once complete, so an interrupted download never
+/// looks like a usable model.
+#[tauri::command]
+pub async fn download_whisper_model(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    check_model_name(&name)?;
+    let dir = models_dir(&app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create models directory: {}", e))?;
+
+    let url = format!("{}/ggml-{}.bin", MODEL_BASE_URL, name);
+    let response = proxy::client()
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download Whisper model: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download Whisper model: HTTP {}", response.status()));
+    }
+    let total_bytes = response.content_length();
+
+    let final_path = model_path(&dir, &name);
+    let partial_path = final_path.with_extension("bin.part");
+    let mut file = tokio::fs::File::create(&partial_path)
+        .await
+        .map_err(|e| format!("Failed to create model file: {}", e))?;
+
+    let mut downloaded_bytes = 0u64;
+    let mut reported_bytes = 0u64;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Failed to download Whisper model: {}", e))?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write model file: {}", e))?;
+        downloaded_bytes += chunk.len() as u64;
+        if downloaded_bytes - reported_bytes >= PROGRESS_STEP {
+            reported_bytes = downloaded_bytes;
+            if let Err(err) = app.emit(
+                "whisper-model-download-progress",
+                WhisperModelDownloadProgressEvent {
+                    name: name.clone(),
+                    downloaded_bytes,
+                    total_bytes,
+                },
+            ) {
+                eprintln!("Failed to emit whisper-model-download-progress event: {}", err);
+            }
+        }
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write model file: {}", e))?;
+    drop(file);
+
+    if total_bytes.is_some_and(|total| total != downloaded_bytes) {
+        let _ = fs::remove_file(&partial_path);
+        return Err("Whisper model download was incomplete".to_string());
+    }
+    fs::rename(&partial_path, &final_path).map_err(|e| format!("Failed to save model file: {}", e))
+}
+
+#[tauri::command]
+pub fn delete_whisper_model(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    check_model_name(&name)?;
+    let path = model_path(&models_dir(&app)?, &name);
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to delete Whisper model: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::encode_wav;
+
+    #[test]
+    fn converts_recordings_to_whisper_input() {
+        // Stereo 32kHz: each frame averages to one mono sample, then every
+        // other sample is kept
+        let wav = encode_wav(&[16384, 16384, -16384, 0, 0, 0, 8192, 8192], 32000, 2).unwrap();
+        assert_eq!(whisper_input(&wav).unwrap(), [0.5, 0.0]);
+
+        let wav = encode_wav(&[0, 16384, -16384], 16000, 1).unwrap();
+        assert_eq!(whisper_input(&wav).unwrap(), [0.0, 0.5, -0.5]);
+
+        // Upsampling interpolates between samples
+        let wav = encode_wav(&[0, 16384], 8000, 1).unwrap();
+        assert_eq!(whisper_input(&wav).unwrap(), [0.0, 0.25, 0.5, 0.5]);
+
+        assert!(check_model_name("base.en").is_ok());
+        assert!(check_model_name("../secrets").is_err());
+    }
+}
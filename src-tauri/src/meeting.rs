@@ -0,0 +1,411 @@
+//! Meeting mode
+//!
+//! Records from an input device for the length of a meeting, transcribing
+//! every half minute into a running transcript session and summarizing it
+//! every few minutes. To capture the other participants, record system audio
+//! (loopback of the default output device, on Windows) or, elsewhere, pick a
+//! loopback input device that carries it (e.g. BlackHole on macOS).
+//! Stopping the meeting transcribes the tail, then adds a final summary with
+//! action items as an assistant message.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tokio_util::sync::CancellationToken;
+
+use crate::audio::{
+    encode_wav, preferred_input_device, run_recording_thread, run_system_audio_recording_thread, RecordingState,
+    SharedRecordingData,
+};
+use crate::commands::{
+    has_api_key_async, new_session_id, new_session_json, session_message, store_session, transcribe_audio_bytes,
+    transcribe_audio_timed, update_stored_session, TimedTranscript, TranscriptSegment, TranscriptWord,
+};
+use crate::llm::complete_prompt;
+use crate::llm_voice::transcribe_audio_file_gemini_impl;
+use crate::settings::{self, TranscriptTimestamps};
+use crate::workspaces;
+
+/// How much audio is collected before it is transcribed
+const SEGMENT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the running summary is refreshed
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Segments shorter than this (mostly the tail after stopping) are skipped
+const MIN_SEGMENT_SECS: usize = 1;
+
+/// Session field holding the latest running summary
+const MEETING_SUMMARY_FIELD: &str = "meetingSummary";
+
+const RUNNING_SUMMARY_INSTRUCTION: &str = "The text below is the transcript so far of a meeting that is still going on. Summarize it in a few bullet points: topics discussed, decisions made and open questions. Reply with the bullet points only.";
+
+const FINAL_SUMMARY_INSTRUCTION: &str = "The text below is the full transcript of a meeting. Write a markdown report with two sections: \"## Summary\" (a concise summary of what was discussed and decided) and \"## Action items\" (a checklist of \"- [ ] task (owner, due date)\" lines, leaving out owner or due date when the transcript doesn't say). If there are no action items, say so.";
+
+/// A meeting in progress
+struct ActiveMeeting {
+    session_id: String,
+    recording: Arc<Mutex<SharedRecordingData>>,
+    stop: CancellationToken,
+    task: tauri::async_runtime::JoinHandle<Result<String, String>>,
+}
+
+/// Tauri-managed meeting state; at most one meeting runs at a time
+#[derive(Default)]
+pub struct MeetingState {
+    active: tokio::sync::Mutex<Option<ActiveMeeting>>,
+}
+
+impl MeetingState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Event payload for a newly transcribed stretch of the meeting
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MeetingTranscriptEvent {
+    pub session_id: String,
+    pub text: String,
+    /// Seconds since the meeting started, at the start of this stretch
+    pub offset_secs: u64,
+    /// Timed segments, relative to the meeting start, when transcript
+    /// timestamps are turned on
+    pub segments: Vec<TranscriptSegment>,
+    /// Timed words, relative to the meeting start, with word timestamps on
+    pub words: Vec<TranscriptWord>,
+}
+
+/// Event payload for the running summary and the final report
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MeetingSummaryEvent {
+    pub session_id: String,
+    pub summary: String,
+}
+
+/// Event payload for a failure that doesn't end the meeting
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MeetingErrorEvent {
+    pub session_id: String,
+    pub error: String,
+}
+
+fn emit_meeting_error(app: &tauri::AppHandle, session_id: &str, error: String) {
+    eprintln!("Meeting {}: {}", session_id, error);
+    if let Err(err) = app.emit(
+        "meeting-error",
+        MeetingErrorEvent {
+            session_id: session_id.to_string(),
+            error,
+        },
+    ) {
+        eprintln!("Failed to emit meeting-error event: {}", err);
+    }
+}
+
+/// "[mm:ss]" for a time since the meeting started
+fn timestamp_label(secs: u64) -> String {
+    format!("[{:02}:{:02}]", secs / 60, secs % 60)
+}
+
+/// Running transcript of a meeting, mirrored into the session's first message
+struct Transcript {
+    session_id: String,
+    turn_id: String,
+    started: Instant,
+    segment_started: Instant,
+    text: String,
+}
+
+impl Transcript {
+    /// Transcribe the audio captured since the last segment and append it
+    async fn transcribe_pending(
+        &mut self,
+        app: &tauri::AppHandle,
+        recording: &Mutex<SharedRecordingData>,
+    ) -> Result<(), String> {
+        let (samples, sample_rate, channels) = {
+            let mut guard = recording.lock();
+            if let Some(error) = guard.error.take() {
+                return Err(error);
+            }
+            (std::mem::take(&mut guard.samples), guard.sample_rate, guard.channels)
+        };
+        let offset_secs = self.segment_started.duration_since(self.started).as_secs();
+        self.segment_started = Instant::now();
+
+        let frames_per_sec = sample_rate as usize * channels.max(1) as usize;
+        if samples.len() < frames_per_sec * MIN_SEGMENT_SECS {
+            return Ok(());
+        }
+
+        let wav = encode_wav(&samples, sample_rate, channels)?;
+        let timed = if has_api_key_async(app, "openai").await {
+            match settings::transcript_timestamps(app) {
+                TranscriptTimestamps::Off => TimedTranscript {
+                    text: transcribe_audio_bytes(app, wav, "meeting.wav", "audio/wav").await?,
+                    ..Default::default()
+                },
+                timestamps => {
+                    let word_timestamps = timestamps == TranscriptTimestamps::Word;
+                    let mut timed =
+                        transcribe_audio_timed(app, wav, "meeting.wav", "audio/wav", word_timestamps).await?;
+                    timed.shift(offset_secs as f64);
+                    timed
+                }
+            }
+        } else {
+            TimedTranscript {
+                text: transcribe_audio_file_gemini_impl(app, wav, "audio/wav").await?,
+                ..Default::default()
+            }
+        };
+        let text = timed.text.trim();
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        // One line per timed segment, or one for the whole stretch
+        let line = if timed.segments.is_empty() {
+            format!("{} {}", timestamp_label(offset_secs), text)
+        } else {
+            timed
+                .segments
+                .iter()
+                .map(|segment| format!("{} {}", timestamp_label(segment.start as u64), segment.text))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        self.text.push_str(&line);
+
+        let content = self.text.clone();
+        let turn_id = self.turn_id.clone();
+        update_stored_session(app, &self.session_id, |fields| {
+            let messages = fields
+                .entry("messages")
+                .or_insert_with(|| serde_json::json!([]))
+                .as_array_mut()
+                .ok_or("Session messages is not an array")?;
+            match messages.first_mut() {
+                Some(first) => first["content"] = serde_json::Value::String(content.clone()),
+                None => messages.push(session_message("user", &content, &turn_id)),
+            }
+            fields.insert(
+                "updatedAt".to_string(),
+                serde_json::Value::String(chrono::Utc::now().to_rfc3339()),
+            );
+            Ok(())
+        })?;
+
+        if let Err(err) = app.emit(
+            "meeting-transcript",
+            MeetingTranscriptEvent {
+                session_id: self.session_id.clone(),
+                text: line,
+                offset_secs,
+                segments: timed.segments,
+                words: timed.words,
+            },
+        ) {
+            eprintln!("Failed to emit meeting-transcript event: {}", err);
+        }
+        Ok(())
+    }
+}
+
+async fn summarize(app: &tauri::AppHandle, instruction: &str, transcript: &str) -> Result<String, String> {
+    let model = settings::model_defaults(app).summarization;
+    let summary = complete_prompt(app, &model, Some(instruction), transcript, false).await?;
+    Ok(summary.trim().to_string())
+}
+
+/// Drive a meeting until `stop` is cancelled. Returns the final report.
+async fn run_meeting(
+    app: tauri::AppHandle,
+    session_id: String,
+    recording: Arc<Mutex<SharedRecordingData>>,
+    stop: CancellationToken,
+) -> Result<String, String> {
+    let now = Instant::now();
+    let mut transcript = Transcript {
+        session_id: session_id.clone(),
+        turn_id: new_session_id(),
+        started: now,
+        segment_started: now,
+        text: String::new(),
+    };
+    let mut summarized_len = 0;
+
+    let mut segment_tick = tokio::time::interval(SEGMENT_INTERVAL);
+    let mut summary_tick = tokio::time::interval(SUMMARY_INTERVAL);
+    // Both intervals fire immediately; skip that first tick
+    segment_tick.tick().await;
+    summary_tick.tick().await;
+
+    loop {
+        tokio::select! {
+            _ = stop.cancelled() => break,
+            _ = segment_tick.tick() => {
+                if let Err(e) = transcript.transcribe_pending(&app, &recording).await {
+                    emit_meeting_error(&app, &session_id, e);
+                }
+            }
+            _ = summary_tick.tick() => {
+                if transcript.text.len() == summarized_len {
+                    continue;
+                }
+                summarized_len = transcript.text.len();
+                match summarize(&app, RUNNING_SUMMARY_INSTRUCTION, &transcript.text).await {
+                    Ok(summary) => {
+                        let stored = update_stored_session(&app, &session_id, |fields| {
+                            fields.insert(MEETING_SUMMARY_FIELD.to_string(), serde_json::Value::String(summary.clone()));
+                            Ok(())
+                        });
+                        if let Err(e) = stored {
+                            eprintln!("Failed to store meeting summary: {}", e);
+                        }
+                        if let Err(err) = app.emit("meeting-summary", MeetingSummaryEvent { session_id: session_id.clone(), summary }) {
+                            eprintln!("Failed to emit meeting-summary event: {}", err);
+                        }
+                    }
+                    Err(e) => emit_meeting_error(&app, &session_id, e),
+                }
+            }
+        }
+    }
+
+    // Let the recording thread release the device, then transcribe the tail
+    while recording.lock().state != RecordingState::Idle {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    if let Err(e) = transcript.transcribe_pending(&app, &recording).await {
+        emit_meeting_error(&app, &session_id, e);
+    }
+
+    if transcript.text.is_empty() {
+        return Ok(String::new());
+    }
+
+    let report = summarize(&app, FINAL_SUMMARY_INSTRUCTION, &transcript.text).await?;
+    let message = session_message("assistant", &report, &transcript.turn_id);
+    update_stored_session(&app, &session_id, |fields| {
+        fields
+            .entry("messages")
+            .or_insert_with(|| serde_json::json!([]))
+            .as_array_mut()
+            .ok_or("Session messages is not an array")?
+            .push(message);
+        fields.insert(MEETING_SUMMARY_FIELD.to_string(), serde_json::Value::String(report.clone()));
+        Ok(())
+    })?;
+
+    Ok(report)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Start a meeting: create its transcript session and start recording from
+/// `device_name` (the preferred or default input when omitted), or from the
+/// system audio when `system_audio` is set. Returns the session id.
+#[tauri::command]
+pub async fn start_meeting(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, MeetingState>,
+    title: Option<String>,
+    device_name: Option<String>,
+    system_audio: Option<bool>,
+) -> Result<String, String> {
+    let mut active = state.active.lock().await;
+    if active.is_some() {
+        return Err("A meeting is already in progress".to_string());
+    }
+
+    let recording = Arc::new(Mutex::new(SharedRecordingData {
+        state: RecordingState::Recording,
+        ..Default::default()
+    }));
+    let thread_recording = recording.clone();
+    if system_audio.unwrap_or(false) {
+        std::thread::spawn(move || run_system_audio_recording_thread(thread_recording));
+    } else {
+        let device_name = device_name.or_else(|| preferred_input_device(&app));
+        std::thread::spawn(move || run_recording_thread(thread_recording, device_name));
+    }
+
+    // Surface device errors right away rather than at the first segment
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    if let Some(error) = recording.lock().error.take() {
+        return Err(error);
+    }
+
+    let title = title
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| format!("Meeting {}", chrono::Local::now().format("%Y-%m-%d %H:%M")));
+    let session = new_session_json(&title, &workspaces::default_chat_model(&app), false, Vec::new());
+    let session_id = session["id"].as_str().unwrap_or_default().to_string();
+    store_session(&app, session)?;
+
+    let stop = CancellationToken::new();
+    let task = tauri::async_runtime::spawn(run_meeting(
+        app.clone(),
+        session_id.clone(),
+        recording.clone(),
+        stop.clone(),
+    ));
+
+    *active = Some(ActiveMeeting {
+        session_id: session_id.clone(),
+        recording,
+        stop,
+        task,
+    });
+
+    Ok(session_id)
+}
+
+/// Stop the current meeting and wait for the final transcript and report.
+/// Emits `meeting-ended` and returns the report (summary and action items).
+#[tauri::command]
+pub async fn stop_meeting(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, MeetingState>,
+) -> Result<String, String> {
+    let meeting = state
+        .active
+        .lock()
+        .await
+        .take()
+        .ok_or("No meeting in progress")?;
+
+    meeting.recording.lock().should_stop = true;
+    meeting.stop.cancel();
+    let report = meeting
+        .task
+        .await
+        .map_err(|e| format!("Meeting task failed: {}", e))??;
+
+    if let Err(err) = app.emit(
+        "meeting-ended",
+        MeetingSummaryEvent {
+            session_id: meeting.session_id,
+            summary: report.clone(),
+        },
+    ) {
+        eprintln!("Failed to emit meeting-ended event: {}", err);
+    }
+
+    Ok(report)
+}
+
+/// Session id of the meeting in progress, if any
+#[tauri::command]
+pub async fn get_active_meeting(state: tauri::State<'_, MeetingState>) -> Result<Option<String>, String> {
+    Ok(state.active.lock().await.as_ref().map(|m| m.session_id.clone()))
+}
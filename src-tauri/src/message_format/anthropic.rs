@@ -0,0 +1,38 @@
+//! Anthropic adapter: Messages API content blocks
+//!
+//! The stored form is already Anthropic's, apart from `file` attachments,
+//! which are sent as `document` blocks (the API says if it can't read the
+//! type), plain text documents, which go as `text` sources, and filenames,
+//! which become the document `title`.
+
+use serde_json::Value;
+
+use super::{stored_block, to_stored, Attachment, AttachmentKind, Part};
+
+fn attachment_block(attachment: &Attachment) -> Value {
+    let mut block = stored_block(&Part::Attachment(Attachment {
+        filename: None,
+        ..attachment.clone()
+    }));
+    if attachment.kind != AttachmentKind::Image {
+        block["type"] = Value::String("document".to_string());
+        if let Some(text) = attachment.plain_text() {
+            block["source"] = serde_json::json!({"type": "text", "media_type": "text/plain", "data": text});
+        }
+        if let Some(filename) = &attachment.filename {
+            block["title"] = Value::String(filename.clone());
+        }
+    }
+    block
+}
+
+/// Message content: a string for plain text, blocks otherwise
+pub fn content(parts: &[Part]) -> Value {
+    let mut content = to_stored(parts);
+    for (block, part) in content.as_array_mut().into_iter().flatten().zip(parts) {
+        if let Part::Attachment(attachment) = part {
+            *block = attachment_block(attachment);
+        }
+    }
+    content
+}
@@ -0,0 +1,57 @@
+//! Gemini adapter: `contents` entries
+//!
+//! Attachments go as `inline_data` with their MIME type. Gemini can't
+//! fetch arbitrary URLs or Anthropic's file store, so those attachments are
+//! left out, as are Anthropic-only blocks.
+//!
+//! Gemini needs user and model turns to alternate, starting with the user.
+//! A reply that failed leaves a user turn without one, so `alternating`
+//! merges repeated turns and drops model turns before the first user turn.
+
+use serde_json::Value;
+
+use super::{Message, Part, Role, Source};
+
+fn part(part: &Part) -> Option<Value> {
+    match part {
+        Part::Text(text) => Some(serde_json::json!({"text": text})),
+        Part::Attachment(attachment) => match &attachment.source {
+            Source::Base64 { media_type, data } => Some(serde_json::json!({
+                "inline_data": {"mime_type": media_type, "data": data}
+            })),
+            Source::Url(_) | Source::FileId(_) => None,
+        },
+        Part::Other(_) => None,
+    }
+}
+
+/// A `contents` entry for the message (Gemini calls the assistant `model`)
+pub fn message(message: &Message) -> Value {
+    let role = match message.role {
+        Role::User => "user",
+        Role::Assistant => "model",
+    };
+    let parts: Vec<Value> = message.parts.iter().filter_map(part).collect();
+    serde_json::json!({"role": role, "parts": parts})
+}
+
+/// `contents` with consecutive turns of the same role merged into one, and
+/// any model turns before the first user turn dropped
+pub fn alternating(contents: Vec<Value>) -> Vec<Value> {
+    let mut merged: Vec<Value> = Vec::with_capacity(contents.len());
+    for content in contents {
+        let role = content["role"].as_str().unwrap_or("user");
+        if merged.is_empty() && role != "user" {
+            continue;
+        }
+        match merged.last_mut() {
+            Some(last) if last["role"].as_str().unwrap_or("user") == role => {
+                if let (Some(parts), Some(more)) = (last["parts"].as_array_mut(), content["parts"].as_array()) {
+                    parts.extend(more.iter().cloned());
+                }
+            }
+            _ => merged.push(content),
+        }
+    }
+    merged
+}
@@ -0,0 +1,316 @@
+//! Canonical conversation format
+//!
+//! Stored messages hold content the way the frontend sends it: a string, or
+//! Anthropic-style blocks (`text`, `image`, `document`, `file`). Instead of
+//! each provider reading those blocks its own way, content is parsed once
+//! into provider-neutral [`Part`]s, and an adapter per provider (the
+//! submodules) writes them out in that provider's shape. Every adapter
+//! handles every kind of part, so what a provider can't take (say, an
+//! attachment uploaded to Anthropic's Files API, sent to Gemini) is left
+//! out deliberately in one place rather than by whichever builder forgot it.
+//!
+//! Plain text documents (long pastes, `.txt` files) are base64 `text/plain`
+//! document blocks. Anthropic reads them as text documents and Gemini
+//! inline; OpenAI and OpenRouter get the text itself, as they only read
+//! PDFs as files.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::Value;
+
+pub mod anthropic;
+pub mod gemini;
+pub mod openai;
+pub mod openrouter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+impl Role {
+    pub fn parse(role: &str) -> Self {
+        if role == "assistant" {
+            Role::Assistant
+        } else {
+            Role::User
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        }
+    }
+}
+
+/// Where an attachment's bytes are
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Base64 { media_type: String, data: String },
+    Url(String),
+    /// Uploaded with Anthropic's Files API
+    FileId(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentKind {
+    Image,
+    /// A PDF, which providers read natively
+    Document,
+    /// Any other file, sent with its own MIME type
+    File,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub kind: AttachmentKind,
+    pub source: Source,
+    pub filename: Option<String>,
+}
+
+impl Attachment {
+    /// `data:` URL for a base64 attachment
+    pub fn data_url(&self) -> Option<String> {
+        match &self.source {
+            Source::Base64 { media_type, data } => Some(format!("data:{};base64,{}", media_type, data)),
+            _ => None,
+        }
+    }
+
+    /// The text of a plain text document
+    pub fn plain_text(&self) -> Option<String> {
+        match &self.source {
+            Source::Base64 { media_type, data } if media_type.starts_with("text/plain") => {
+                String::from_utf8(BASE64.decode(data).ok()?).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// A plain text document as a message text part, for providers that
+    /// don't take text files
+    pub fn plain_text_part(&self) -> Option<String> {
+        let text = self.plain_text()?;
+        let name = self.filename.as_deref().unwrap_or("document.txt");
+        Some(format!("--- File: {} ---\n{}\n--- End of {} ---", name, text, name))
+    }
+
+    /// The filename, or a generic one for providers that require it
+    pub fn filename_or_default(&self) -> &str {
+        let default = match self.kind {
+            AttachmentKind::Document => "document.pdf",
+            _ => "file",
+        };
+        self.filename.as_deref().unwrap_or(default)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Part {
+    Text(String),
+    Attachment(Attachment),
+    /// A block only Anthropic understands (tool use, search results, ...),
+    /// passed through to it as-is and left out for other providers
+    Other(Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub role: Role,
+    pub parts: Vec<Part>,
+}
+
+impl Message {
+    pub fn new(role: &str, content: &Value) -> Self {
+        Self {
+            role: Role::parse(role),
+            parts: parse_content(content),
+        }
+    }
+
+    /// Parse a stored `{role, content}` message
+    pub fn from_stored(message: &Value) -> Self {
+        Self::new(message["role"].as_str().unwrap_or("user"), &message["content"])
+    }
+}
+
+fn parse_source(source: &Value, kind: AttachmentKind) -> Option<Source> {
+    match source["type"].as_str()? {
+        "base64" => {
+            let media_type = match (source["media_type"].as_str(), kind) {
+                (Some(media_type), _) => media_type,
+                (None, AttachmentKind::Document) => "application/pdf",
+                (None, _) => return None,
+            };
+            Some(Source::Base64 {
+                media_type: media_type.to_string(),
+                data: source["data"].as_str()?.to_string(),
+            })
+        }
+        "url" => Some(Source::Url(source["url"].as_str()?.to_string())),
+        "file" => Some(Source::FileId(source["file_id"].as_str()?.to_string())),
+        _ => None,
+    }
+}
+
+fn parse_block(block: &Value) -> Part {
+    let kind = match block["type"].as_str() {
+        Some("text") => {
+            if let Some(text) = block["text"].as_str() {
+                return Part::Text(text.to_string());
+            }
+            return Part::Other(block.clone());
+        }
+        Some("image") => AttachmentKind::Image,
+        Some("document") => AttachmentKind::Document,
+        Some("file") => AttachmentKind::File,
+        _ => return Part::Other(block.clone()),
+    };
+    let source = &block["source"];
+    let Some(parsed) = parse_source(source, kind) else {
+        return Part::Other(block.clone());
+    };
+    let filename = block["filename"]
+        .as_str()
+        .or_else(|| source["filename"].as_str())
+        .map(str::to_string);
+    Part::Attachment(Attachment {
+        kind,
+        source: parsed,
+        filename,
+    })
+}
+
+/// Parse stored content: a string, or a list of blocks
+pub fn parse_content(content: &Value) -> Vec<Part> {
+    match content {
+        Value::String(text) => vec![Part::Text(text.clone())],
+        Value::Array(blocks) => blocks.iter().map(parse_block).collect(),
+        _ => vec![Part::Text(String::new())],
+    }
+}
+
+fn stored_source(source: &Source) -> Value {
+    match source {
+        Source::Base64 { media_type, data } => {
+            serde_json::json!({"type": "base64", "media_type": media_type, "data": data})
+        }
+        Source::Url(url) => serde_json::json!({"type": "url", "url": url}),
+        Source::FileId(file_id) => serde_json::json!({"type": "file", "file_id": file_id}),
+    }
+}
+
+/// A part as a stored content block
+pub fn stored_block(part: &Part) -> Value {
+    match part {
+        Part::Text(text) => serde_json::json!({"type": "text", "text": text}),
+        Part::Attachment(attachment) => {
+            let kind = match attachment.kind {
+                AttachmentKind::Image => "image",
+                AttachmentKind::Document => "document",
+                AttachmentKind::File => "file",
+            };
+            let mut block = serde_json::json!({"type": kind, "source": stored_source(&attachment.source)});
+            if let Some(filename) = &attachment.filename {
+                block["filename"] = Value::String(filename.clone());
+            }
+            block
+        }
+        Part::Other(block) => block.clone(),
+    }
+}
+
+/// Write parts back in the stored form: a string for plain text, blocks
+/// otherwise
+pub fn to_stored(parts: &[Part]) -> Value {
+    if let [Part::Text(text)] = parts {
+        return Value::String(text.clone());
+    }
+    Value::Array(parts.iter().map(stored_block).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored_content() -> Value {
+        serde_json::json!([
+            {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}},
+            {"type": "document", "filename": "report.pdf", "source": {"type": "base64", "media_type": "application/pdf", "data": "BBBB"}},
+            {"type": "file", "filename": "data.csv", "source": {"type": "base64", "media_type": "text/csv", "data": "CCCC"}},
+            {"type": "image", "source": {"type": "url", "url": "https://example.com/a.png"}},
+            {"type": "document", "source": {"type": "file", "file_id": "file_011"}},
+            {"type": "tool_result", "tool_use_id": "toolu_1", "content": "42"},
+            {"type": "text", "text": "Compare these"}
+        ])
+    }
+
+    #[test]
+    fn gemini_turns_are_merged_to_alternate() {
+        let contents = vec![
+            serde_json::json!({"role": "model", "parts": [{"text": "Welcome"}]}),
+            serde_json::json!({"role": "user", "parts": [{"text": "First try"}]}),
+            serde_json::json!({"role": "user", "parts": [{"text": "Second try"}]}),
+            serde_json::json!({"role": "model", "parts": [{"text": "Answer"}]}),
+        ];
+        assert_eq!(
+            gemini::alternating(contents),
+            vec![
+                serde_json::json!({"role": "user", "parts": [{"text": "First try"}, {"text": "Second try"}]}),
+                serde_json::json!({"role": "model", "parts": [{"text": "Answer"}]}),
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_text_documents_are_sent_as_text_where_files_are_pdf_only() {
+        let content = serde_json::json!([
+            {"type": "document", "filename": "Pasted text.txt", "source": {"type": "base64", "media_type": "text/plain", "data": "bG9uZyBwYXN0ZQ=="}},
+            {"type": "text", "text": "Summarize"}
+        ]);
+        let message = Message::new("user", &content);
+
+        assert_eq!(
+            anthropic::content(&message.parts)[0],
+            serde_json::json!({
+                "type": "document",
+                "source": {"type": "text", "media_type": "text/plain", "data": "long paste"},
+                "title": "Pasted text.txt"
+            })
+        );
+        let expected = "--- File: Pasted text.txt ---\nlong paste\n--- End of Pasted text.txt ---";
+        assert_eq!(openai::message(&message)["content"][0]["text"], expected);
+        assert_eq!(openrouter::message(&message)["content"][0]["text"], expected);
+        assert_eq!(gemini::message(&message)["parts"][0]["inline_data"]["mime_type"], "text/plain");
+    }
+
+    #[test]
+    fn stored_content_round_trips() {
+        let content = stored_content();
+        let parts = parse_content(&content);
+        assert_eq!(parts.len(), 7);
+        assert_eq!(to_stored(&parts), content);
+        assert_eq!(parse_content(&to_stored(&parts)), parts);
+
+        let text = Value::String("Hello".to_string());
+        assert_eq!(to_stored(&parse_content(&text)), text);
+        assert_eq!(parse_content(&Value::Null), vec![Part::Text(String::new())]);
+    }
+
+    #[test]
+    fn adapters_keep_every_inline_attachment() {
+        let message = Message::new("user", &stored_content());
+        // Three base64 attachments and the text, for every provider; URL and
+        // file-store sources and Anthropic-only blocks vary
+        assert_eq!(anthropic::content(&message.parts).as_array().unwrap().len(), 7);
+        assert_eq!(openai::message(&message)["content"].as_array().unwrap().len(), 5);
+        assert_eq!(gemini::message(&message)["parts"].as_array().unwrap().len(), 4);
+        assert_eq!(openrouter::message(&message)["content"].as_array().unwrap().len(), 5);
+
+        let reply = Message::new("assistant", &Value::String("Done".to_string()));
+        assert_eq!(openai::message(&reply), serde_json::json!({"type": "message", "role": "assistant", "content": "Done"}));
+        assert_eq!(gemini::message(&reply), serde_json::json!({"role": "model", "parts": [{"text": "Done"}]}));
+    }
+}
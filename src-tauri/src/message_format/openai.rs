@@ -0,0 +1,45 @@
+//! OpenAI adapter: Responses API input messages
+//!
+//! Attachments go inline as `input_image` / `input_file` with a `data:` URL
+//! (or the URL itself), and plain text documents as text. Files uploaded to
+//! Anthropic's file store and Anthropic-only blocks are left out.
+
+use serde_json::Value;
+
+use super::{Attachment, AttachmentKind, Message, Part, Source};
+
+fn attachment_part(attachment: &Attachment) -> Option<Value> {
+    if let Some(text) = attachment.plain_text_part() {
+        return Some(serde_json::json!({"type": "input_text", "text": text}));
+    }
+    let url = match &attachment.source {
+        Source::Base64 { .. } => attachment.data_url()?,
+        Source::Url(url) => url.clone(),
+        Source::FileId(_) => return None,
+    };
+    Some(match (attachment.kind, &attachment.source) {
+        (AttachmentKind::Image, _) => serde_json::json!({"type": "input_image", "image_url": url}),
+        (_, Source::Url(_)) => serde_json::json!({"type": "input_file", "file_url": url}),
+        _ => serde_json::json!({
+            "type": "input_file",
+            "filename": attachment.filename_or_default(),
+            "file_data": url
+        }),
+    })
+}
+
+/// An input item for the message
+pub fn message(message: &Message) -> Value {
+    let content = match message.parts.as_slice() {
+        [Part::Text(text)] => Value::String(text.clone()),
+        parts => parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text(text) => Some(serde_json::json!({"type": "input_text", "text": text})),
+                Part::Attachment(attachment) => attachment_part(attachment),
+                Part::Other(_) => None,
+            })
+            .collect(),
+    };
+    serde_json::json!({"type": "message", "role": message.role.as_str(), "content": content})
+}
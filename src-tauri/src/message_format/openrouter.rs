@@ -0,0 +1,43 @@
+//! OpenRouter adapter: Chat Completions messages
+//!
+//! Images go as `image_url` and other attachments as `file` parts, inline
+//! or by URL; plain text documents go as text. Files uploaded to
+//! Anthropic's file store and Anthropic-only blocks are left out.
+
+use serde_json::Value;
+
+use super::{Attachment, AttachmentKind, Message, Part, Source};
+
+fn attachment_part(attachment: &Attachment) -> Option<Value> {
+    if let Some(text) = attachment.plain_text_part() {
+        return Some(serde_json::json!({"type": "text", "text": text}));
+    }
+    let url = match &attachment.source {
+        Source::Base64 { .. } => attachment.data_url()?,
+        Source::Url(url) => url.clone(),
+        Source::FileId(_) => return None,
+    };
+    Some(match attachment.kind {
+        AttachmentKind::Image => serde_json::json!({"type": "image_url", "image_url": {"url": url}}),
+        AttachmentKind::Document | AttachmentKind::File => serde_json::json!({
+            "type": "file",
+            "file": {"filename": attachment.filename_or_default(), "file_data": url}
+        }),
+    })
+}
+
+/// A chat message for the message
+pub fn message(message: &Message) -> Value {
+    let content = match message.parts.as_slice() {
+        [Part::Text(text)] => Value::String(text.clone()),
+        parts => parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text(text) => Some(serde_json::json!({"type": "text", "text": text})),
+                Part::Attachment(attachment) => attachment_part(attachment),
+                Part::Other(_) => None,
+            })
+            .collect(),
+    };
+    serde_json::json!({"role": message.role.as_str(), "content": content})
+}
@@ -57,3 +57,15 @@ pub fn extension_to_mime(filename: &str) -> Option<&'static str> {
         _ => None,
     }
 }
+
+/// MIME type of an audio file from its extension, defaulting to WAV
+pub fn audio_mime_type(filename: &str) -> &'static str {
+    match filename.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).as_deref() {
+        Some("mp3") | Some("mpeg") | Some("mpga") => "audio/mpeg",
+        Some("m4a") | Some("mp4") => "audio/mp4",
+        Some("ogg") => "audio/ogg",
+        Some("webm") => "audio/webm",
+        Some("flac") => "audio/flac",
+        _ => "audio/wav",
+    }
+}
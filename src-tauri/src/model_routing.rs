@@ -0,0 +1,275 @@
+//! "auto" model routing
+//!
+//! Selecting the "auto" model lets the backend pick per request: short, plain
+//! prompts go to a cheap fast model, while long conversations, code,
+//! attachments and prompts that ask for careful reasoning go to a stronger
+//! one. The choice is emitted as `chat-model-routed` so the UI can show which
+//! model answered.
+//!
+//! Until the models are configured, the router uses the built-in fast and
+//! strong models of the first provider with an API key (Anthropic, OpenAI,
+//! then Google). OpenRouter has no built-in pair, so OpenRouter-only setups
+//! need to set the models with `set_auto_routing_settings`.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::commands::has_api_key_async;
+use crate::llm::ChatMessage;
+use crate::settings;
+
+/// Model id the frontend sends to request routing
+pub const AUTO_MODEL: &str = "auto";
+
+const AUTO_ROUTING_KEY: &str = "auto_routing";
+
+/// Providers with built-in models, in the order their keys are checked
+const DEFAULT_PROVIDERS: &[&str] = &["anthropic", "openai", "google"];
+
+/// Models and thresholds used by the router
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoRoutingSettings {
+    pub fast_model: String,
+    pub strong_model: String,
+    /// Conversations with more text than this (in characters) go to the strong model
+    pub long_context_chars: usize,
+    /// Prompts longer than this (in characters) go to the strong model
+    pub long_prompt_chars: usize,
+}
+
+impl Default for AutoRoutingSettings {
+    fn default() -> Self {
+        Self {
+            fast_model: "claude-haiku-4-5-20251001".to_string(),
+            strong_model: "claude-opus-4-8".to_string(),
+            long_context_chars: 24_000,
+            long_prompt_chars: 2_000,
+        }
+    }
+}
+
+impl AutoRoutingSettings {
+    /// The built-in fast and strong models of a provider, if it has them
+    fn for_provider(provider: &str) -> Option<Self> {
+        let (fast_model, strong_model) = match provider {
+            "anthropic" => ("claude-haiku-4-5-20251001", "claude-opus-4-8"),
+            "openai" => ("gpt-5.4-mini", "gpt-5.5"),
+            "google" => ("gemini-3.5-flash", "gemini-3.1-pro-preview"),
+            _ => return None,
+        };
+        Some(Self {
+            fast_model: fast_model.to_string(),
+            strong_model: strong_model.to_string(),
+            ..Self::default()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteTier {
+    Fast,
+    Strong,
+}
+
+/// Event payload telling the frontend which model an "auto" turn was sent to
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelRoutedEvent {
+    pub turn_id: String,
+    pub model: String,
+    pub tier: RouteTier,
+    /// Why the strong model was chosen (empty for the fast model)
+    pub reasons: Vec<String>,
+}
+
+/// The configured router settings, or the built-in models of the first
+/// provider with an API key
+pub async fn auto_routing_settings(app: &tauri::AppHandle) -> AutoRoutingSettings {
+    if let Some(stored) = settings::get_setting(app, AUTO_ROUTING_KEY) {
+        return stored;
+    }
+    for provider in DEFAULT_PROVIDERS {
+        if has_api_key_async(app, provider).await {
+            if let Some(defaults) = AutoRoutingSettings::for_provider(provider) {
+                return defaults;
+            }
+        }
+    }
+    AutoRoutingSettings::default()
+}
+
+/// Text of a message's content, whether a plain string or a list of blocks
+fn content_text(content: &serde_json::Value) -> String {
+    match content {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn has_attachment(content: &serde_json::Value) -> bool {
+    content.as_array().is_some_and(|blocks| {
+        blocks.iter().any(|block| {
+            matches!(
+                block["type"].as_str(),
+                Some("image") | Some("document") | Some("file") | Some("input_image") | Some("input_file")
+            )
+        })
+    })
+}
+
+fn code_line_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(?m)^\s*(fn |pub |def |class |import |from \S+ import|#include|function |const |let |var |return |SELECT |if \(|for \(|\}|\{)",
+        )
+        .unwrap()
+    })
+}
+
+/// Whether text looks like it contains source code
+fn looks_like_code(text: &str) -> bool {
+    text.contains("```") || code_line_regex().find_iter(text).count() >= 3
+}
+
+/// Phrases that signal a request for careful, multi-step work
+const COMPLEX_TASK_MARKERS: &[&str] = &[
+    "step by step",
+    "prove",
+    "derive",
+    "analyze",
+    "analyse",
+    "debug",
+    "refactor",
+    "architecture",
+    "trade-off",
+    "tradeoff",
+    "in depth",
+    "in detail",
+    "compare",
+];
+
+/// Decide which tier a conversation needs. Returns the tier and the reasons
+/// for choosing the strong model.
+fn classify(messages: &[ChatMessage], config: &AutoRoutingSettings) -> (RouteTier, Vec<String>) {
+    let mut reasons = Vec::new();
+
+    let total_chars: usize = messages.iter().map(|m| content_text(&m.content).len()).sum();
+    if total_chars > config.long_context_chars {
+        reasons.push("long context".to_string());
+    }
+
+    if messages.iter().any(|m| has_attachment(&m.content)) {
+        reasons.push("attachments".to_string());
+    }
+
+    if let Some(prompt) = messages.iter().rev().find(|m| m.role == "user") {
+        let text = content_text(&prompt.content);
+        if text.len() > config.long_prompt_chars {
+            reasons.push("long prompt".to_string());
+        }
+        if looks_like_code(&text) {
+            reasons.push("code".to_string());
+        }
+        let lower = text.to_lowercase();
+        if COMPLEX_TASK_MARKERS.iter().any(|marker| lower.contains(marker)) {
+            reasons.push("complex task".to_string());
+        }
+    }
+
+    if reasons.is_empty() {
+        (RouteTier::Fast, reasons)
+    } else {
+        (RouteTier::Strong, reasons)
+    }
+}
+
+/// Resolve the "auto" model for a request to a concrete model
+pub async fn route_model(app: &tauri::AppHandle, turn_id: &str, messages: &[ChatMessage]) -> ModelRoutedEvent {
+    let config = auto_routing_settings(app).await;
+    let (tier, reasons) = classify(messages, &config);
+    let model = match tier {
+        RouteTier::Fast => config.fast_model,
+        RouteTier::Strong => config.strong_model,
+    };
+    ModelRoutedEvent {
+        turn_id: turn_id.to_string(),
+        model,
+        tier,
+        reasons,
+    }
+}
+
+#[tauri::command]
+pub async fn get_auto_routing_settings(app: tauri::AppHandle) -> AutoRoutingSettings {
+    auto_routing_settings(&app).await
+}
+
+#[tauri::command]
+pub fn set_auto_routing_settings(
+    app: tauri::AppHandle,
+    settings: AutoRoutingSettings,
+) -> Result<(), String> {
+    if settings.fast_model == AUTO_MODEL || settings.strong_model == AUTO_MODEL {
+        return Err("Auto routing targets must be concrete models".to_string());
+    }
+    settings::set_setting(&app, AUTO_ROUTING_KEY, &settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: serde_json::Value) -> ChatMessage {
+        ChatMessage {
+            id: None,
+            role: role.to_string(),
+            content,
+            thinking_blocks: None,
+        }
+    }
+
+    #[test]
+    fn short_plain_prompts_take_the_fast_model() {
+        let messages = vec![message("user", serde_json::json!("What's the capital of Peru?"))];
+        let (tier, reasons) = classify(&messages, &AutoRoutingSettings::default());
+        assert_eq!(tier, RouteTier::Fast);
+        assert!(reasons.is_empty());
+    }
+
+    #[test]
+    fn code_and_attachments_take_the_strong_model() {
+        let code = message("user", serde_json::json!("Why does this panic?\n```rust\nlet v = vec![];\nv[0];\n```"));
+        let (tier, reasons) = classify(&[code], &AutoRoutingSettings::default());
+        assert_eq!(tier, RouteTier::Strong);
+        assert_eq!(reasons, vec!["code"]);
+
+        let image = message(
+            "user",
+            serde_json::json!([
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": ""}},
+                {"type": "text", "text": "What is this?"}
+            ]),
+        );
+        let (tier, reasons) = classify(&[image], &AutoRoutingSettings::default());
+        assert_eq!(tier, RouteTier::Strong);
+        assert_eq!(reasons, vec!["attachments"]);
+    }
+
+    #[test]
+    fn built_in_models_stay_with_their_provider() {
+        for provider in DEFAULT_PROVIDERS {
+            let defaults = AutoRoutingSettings::for_provider(provider).unwrap();
+            assert_eq!(crate::llm::get_provider_for_model(&defaults.fast_model), *provider);
+            assert_eq!(crate::llm::get_provider_for_model(&defaults.strong_model), *provider);
+        }
+        assert!(AutoRoutingSettings::for_provider("openrouter").is_none());
+    }
+}
@@ -0,0 +1,105 @@
+//! Model usage and favorites
+//!
+//! Counts how often each model answers a chat turn and remembers which
+//! models the user starred, so the model picker can put favorites first and
+//! order the rest by how much they're used.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::settings;
+
+const MODEL_USAGE_KEY: &str = "model_usage";
+
+/// Stored usage of one model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct UsageRecord {
+    count: u64,
+    last_used_at: Option<String>,
+    favorite: bool,
+}
+
+/// Usage of one model, as returned to the picker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelUsage {
+    pub model: String,
+    pub count: u64,
+    pub last_used_at: Option<String>,
+    pub favorite: bool,
+}
+
+fn load_usage(app: &tauri::AppHandle) -> BTreeMap<String, UsageRecord> {
+    settings::get_setting(app, MODEL_USAGE_KEY).unwrap_or_default()
+}
+
+/// Favorites first, then by use count, then most recently used
+fn sorted_usage(usage: BTreeMap<String, UsageRecord>) -> Vec<ModelUsage> {
+    let mut models: Vec<ModelUsage> = usage
+        .into_iter()
+        .map(|(model, record)| ModelUsage {
+            model,
+            count: record.count,
+            last_used_at: record.last_used_at,
+            favorite: record.favorite,
+        })
+        .collect();
+    models.sort_by(|a, b| {
+        b.favorite
+            .cmp(&a.favorite)
+            .then(b.count.cmp(&a.count))
+            .then(b.last_used_at.cmp(&a.last_used_at))
+    });
+    models
+}
+
+/// Count a chat turn answered by `model`
+pub fn record_model_use(app: &tauri::AppHandle, model: &str) {
+    let mut usage = load_usage(app);
+    let record = usage.entry(model.to_string()).or_default();
+    record.count += 1;
+    record.last_used_at = Some(chrono::Utc::now().to_rfc3339());
+    if let Err(e) = settings::set_setting(app, MODEL_USAGE_KEY, &usage) {
+        eprintln!("Failed to record model usage: {}", e);
+    }
+}
+
+/// Models that have been used or starred, in picker order
+#[tauri::command]
+pub fn get_model_usage(app: tauri::AppHandle) -> Vec<ModelUsage> {
+    sorted_usage(load_usage(&app))
+}
+
+#[tauri::command]
+pub fn set_model_favorite(app: tauri::AppHandle, model: String, favorite: bool) -> Result<(), String> {
+    let mut usage = load_usage(&app);
+    let record = usage.entry(model.clone()).or_default();
+    record.favorite = favorite;
+    if !favorite && record.count == 0 {
+        usage.remove(&model);
+    }
+    settings::set_setting(&app, MODEL_USAGE_KEY, &usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn favorites_come_first_then_most_used() {
+        let record = |count, last: &str, favorite| UsageRecord {
+            count,
+            last_used_at: Some(last.to_string()),
+            favorite,
+        };
+        let usage = BTreeMap::from([
+            ("a".to_string(), record(10, "2026-01-01", false)),
+            ("b".to_string(), record(1, "2026-01-02", true)),
+            ("c".to_string(), record(10, "2026-02-01", false)),
+            ("d".to_string(), record(3, "2026-03-01", false)),
+        ]);
+        let order: Vec<String> = sorted_usage(usage).into_iter().map(|m| m.model).collect();
+        assert_eq!(order, vec!["b", "c", "a", "d"]);
+    }
+}
@@ -0,0 +1,139 @@
+//! Personas
+//!
+//! A persona bundles a system prompt with a model, thinking parameters and a
+//! readback voice ("coding assistant", "writing editor", ...). Assigning one
+//! to a session adds its prompt as a system prompt layer and its voice to
+//! readback; the frontend applies the model and parameters it returns.
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::{get_stored_session, new_session_id, update_stored_session};
+use crate::settings;
+
+const PERSONAS_SETTING_KEY: &str = "personas";
+
+/// Session field holding the assigned persona's id
+pub const PERSONA_ID_FIELD: &str = "personaId";
+
+/// Model parameters a persona sets; unset fields keep the current values
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersonaParameters {
+    pub opus46_thinking_level: Option<String>,
+    pub reasoning_level: Option<String>,
+    pub gemini_thinking_level: Option<String>,
+    pub web_search_enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Persona {
+    /// Assigned on first save when empty
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub system_prompt: String,
+    pub model: Option<String>,
+    #[serde(default)]
+    pub parameters: PersonaParameters,
+    /// Readback voice used for sessions with this persona
+    pub voice: Option<String>,
+}
+
+pub fn load_personas(app: &tauri::AppHandle) -> Vec<Persona> {
+    settings::get_setting(app, PERSONAS_SETTING_KEY).unwrap_or_default()
+}
+
+fn save_personas(app: &tauri::AppHandle, personas: &[Persona]) -> Result<(), String> {
+    settings::set_setting(app, PERSONAS_SETTING_KEY, &personas)
+}
+
+/// The persona assigned to a stored session, if any
+pub fn session_persona(app: &tauri::AppHandle, session_id: &str) -> Option<Persona> {
+    let session = get_stored_session(app, session_id).ok().flatten()?;
+    let persona_id = session[PERSONA_ID_FIELD].as_str()?;
+    load_personas(app).into_iter().find(|p| p.id == persona_id)
+}
+
+/// Add a persona that arrived with an imported session, unless one with the
+/// same id already exists locally
+pub fn import_persona(app: &tauri::AppHandle, persona: Persona) -> Result<(), String> {
+    let mut personas = load_personas(app);
+    if personas.iter().any(|p| p.id == persona.id) {
+        return Ok(());
+    }
+    personas.push(persona);
+    save_personas(app, &personas)
+}
+
+#[tauri::command]
+pub fn list_personas(app: tauri::AppHandle) -> Vec<Persona> {
+    load_personas(&app)
+}
+
+/// Create or update a persona (matched by id). Returns the saved persona.
+#[tauri::command]
+pub fn save_persona(app: tauri::AppHandle, persona: Persona) -> Result<Persona, String> {
+    if persona.name.trim().is_empty() {
+        return Err("Persona name cannot be empty".to_string());
+    }
+
+    let mut persona = persona;
+    if persona.id.is_empty() {
+        persona.id = new_session_id();
+    }
+
+    let mut personas = load_personas(&app);
+    match personas.iter_mut().find(|p| p.id == persona.id) {
+        Some(existing) => *existing = persona.clone(),
+        None => personas.push(persona.clone()),
+    }
+    save_personas(&app, &personas)?;
+
+    Ok(persona)
+}
+
+/// Delete a persona. Sessions that used it simply fall back to no persona.
+#[tauri::command]
+pub fn delete_persona(app: tauri::AppHandle, persona_id: String) -> Result<(), String> {
+    let mut personas = load_personas(&app);
+    personas.retain(|p| p.id != persona_id);
+    save_personas(&app, &personas)
+}
+
+/// Assign a persona to a session (None clears it). Returns the persona so the
+/// frontend can switch the session's model and parameters in one step.
+#[tauri::command]
+pub fn set_session_persona(
+    app: tauri::AppHandle,
+    session_id: String,
+    persona_id: Option<String>,
+) -> Result<Option<Persona>, String> {
+    let persona = match &persona_id {
+        Some(id) => Some(
+            load_personas(&app)
+                .into_iter()
+                .find(|p| &p.id == id)
+                .ok_or_else(|| format!("Persona not found: {}", id))?,
+        ),
+        None => None,
+    };
+
+    update_stored_session(&app, &session_id, |fields| {
+        match &persona {
+            Some(persona) => {
+                fields.insert(
+                    PERSONA_ID_FIELD.to_string(),
+                    serde_json::Value::String(persona.id.clone()),
+                );
+            }
+            None => {
+                fields.remove(PERSONA_ID_FIELD);
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(persona)
+}
@@ -0,0 +1,224 @@
+//! Pinned messages
+//!
+//! Users pin messages that must stay in the model's context however long the
+//! conversation gets (a spec, a schema, ground rules). Pins are stored on the
+//! session as message ids. When a conversation outgrows the context budget,
+//! `assemble_context` drops the oldest turns first but always keeps turns
+//! that contain a pinned message, along with the latest turn.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::commands::{get_stored_session, update_stored_session};
+use crate::llm::ChatMessage;
+
+/// Session field holding the ids of pinned messages, in pin order
+pub const PINNED_MESSAGES_FIELD: &str = "pinnedMessageIds";
+
+/// Context budget in characters (~150k tokens), leaving room for the
+/// system prompt and the reply in every supported model's window
+const MAX_CONTEXT_CHARS: usize = 600_000;
+
+/// Characters an image counts for against the budget (~1600 tokens)
+const IMAGE_CHARS: usize = 6_400;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinnedMessage {
+    pub message_id: String,
+    pub role: String,
+    pub content: String,
+    pub timestamp: Option<String>,
+}
+
+pub fn pinned_ids(session: &serde_json::Value) -> Vec<String> {
+    session[PINNED_MESSAGES_FIELD]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|id| id.as_str().map(str::to_string))
+        .collect()
+}
+
+/// Approximate size of a message's content as sent to a provider
+fn content_chars(content: &serde_json::Value) -> usize {
+    match content {
+        serde_json::Value::String(text) => text.len(),
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .map(|block| match block["type"].as_str() {
+                Some("text") => block["text"].as_str().map_or(0, str::len),
+                Some("image") => IMAGE_CHARS,
+                // Base64 documents: roughly one token per three source bytes
+                _ => block.to_string().len() / 4,
+            })
+            .sum(),
+        other => other.to_string().len(),
+    }
+}
+
+/// Drop the oldest turns until the conversation fits `max_chars`. A turn is a
+/// user message with the replies that follow it, so roles keep alternating.
+/// The latest turn and turns containing a pinned message are always kept.
+fn retain_within_budget(
+    messages: Vec<ChatMessage>,
+    pinned: &HashSet<String>,
+    max_chars: usize,
+) -> Vec<ChatMessage> {
+    let mut turns: Vec<Vec<ChatMessage>> = Vec::new();
+    for message in messages {
+        match turns.last_mut() {
+            Some(turn) if message.role != "user" => turn.push(message),
+            _ => turns.push(vec![message]),
+        }
+    }
+
+    let turn_chars = |turn: &[ChatMessage]| turn.iter().map(|m| content_chars(&m.content)).sum::<usize>();
+    let is_pinned = |turn: &[ChatMessage]| {
+        turn.iter()
+            .any(|m| m.id.as_ref().is_some_and(|id| pinned.contains(id)))
+    };
+
+    let last = turns.len().saturating_sub(1);
+    let mut keep = vec![false; turns.len()];
+    let mut used = 0;
+    for (index, turn) in turns.iter().enumerate() {
+        if index == last || is_pinned(turn) {
+            keep[index] = true;
+            used += turn_chars(turn);
+        }
+    }
+    // Fill the remaining budget with the most recent turns
+    for index in (0..turns.len()).rev() {
+        if keep[index] {
+            continue;
+        }
+        let chars = turn_chars(&turns[index]);
+        if used + chars > max_chars {
+            break;
+        }
+        keep[index] = true;
+        used += chars;
+    }
+
+    turns
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(turn, keep)| keep.then_some(turn))
+        .flatten()
+        .collect()
+}
+
+/// Fit a conversation into the context budget, keeping the session's pins
+pub fn assemble_context(
+    app: &tauri::AppHandle,
+    session_id: Option<&str>,
+    messages: Vec<ChatMessage>,
+) -> Vec<ChatMessage> {
+    let pinned: HashSet<String> = session_id
+        .and_then(|id| get_stored_session(app, id).ok().flatten())
+        .map(|session| pinned_ids(&session).into_iter().collect())
+        .unwrap_or_default();
+    retain_within_budget(messages, &pinned, MAX_CONTEXT_CHARS)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn pin_message(app: tauri::AppHandle, session_id: String, message_id: String) -> Result<(), String> {
+    update_stored_session(&app, &session_id, |fields| {
+        let exists = fields
+            .get("messages")
+            .and_then(|m| m.as_array())
+            .is_some_and(|messages| messages.iter().any(|m| m["id"].as_str() == Some(&message_id)));
+        if !exists {
+            return Err(format!("Message not found: {}", message_id));
+        }
+        let pins = fields
+            .entry(PINNED_MESSAGES_FIELD)
+            .or_insert_with(|| serde_json::json!([]))
+            .as_array_mut()
+            .ok_or("Pinned messages is not an array")?;
+        if !pins.iter().any(|id| id.as_str() == Some(&message_id)) {
+            pins.push(serde_json::Value::String(message_id.clone()));
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unpin_message(app: tauri::AppHandle, session_id: String, message_id: String) -> Result<(), String> {
+    update_stored_session(&app, &session_id, |fields| {
+        if let Some(pins) = fields.get_mut(PINNED_MESSAGES_FIELD).and_then(|p| p.as_array_mut()) {
+            pins.retain(|id| id.as_str() != Some(&message_id));
+            if pins.is_empty() {
+                fields.remove(PINNED_MESSAGES_FIELD);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Pinned messages of a session, in conversation order. Pins whose message
+/// was deleted are skipped.
+#[tauri::command]
+pub fn list_pins(app: tauri::AppHandle, session_id: String) -> Result<Vec<PinnedMessage>, String> {
+    let Some(session) = get_stored_session(&app, &session_id)? else {
+        return Ok(Vec::new());
+    };
+    let pinned: HashSet<String> = pinned_ids(&session).into_iter().collect();
+
+    Ok(session["messages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|message| {
+            let id = message["id"].as_str().filter(|id| pinned.contains(*id))?;
+            Some(PinnedMessage {
+                message_id: id.to_string(),
+                role: message["role"].as_str().unwrap_or("user").to_string(),
+                content: message["content"].as_str().unwrap_or_default().to_string(),
+                timestamp: message["timestamp"].as_str().map(str::to_string),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, role: &str, text: &str) -> ChatMessage {
+        ChatMessage {
+            id: Some(id.to_string()),
+            role: role.to_string(),
+            content: serde_json::Value::String(text.to_string()),
+            thinking_blocks: None,
+        }
+    }
+
+    #[test]
+    fn keeps_pinned_and_recent_turns_within_budget() {
+        let messages = vec![
+            message("1", "user", &"a".repeat(100)),
+            message("2", "assistant", &"b".repeat(100)),
+            message("3", "user", &"c".repeat(100)),
+            message("4", "assistant", &"d".repeat(100)),
+            message("5", "user", &"e".repeat(100)),
+            message("6", "assistant", &"f".repeat(100)),
+            message("7", "user", "latest"),
+        ];
+        let pinned = HashSet::from(["2".to_string()]);
+
+        let kept: Vec<String> = retain_within_budget(messages, &pinned, 450)
+            .into_iter()
+            .filter_map(|m| m.id)
+            .collect();
+        // The pinned turn (1-2), the latest turn, and one recent turn that fits
+        assert_eq!(kept, vec!["1", "2", "5", "6", "7"]);
+    }
+}
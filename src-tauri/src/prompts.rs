@@ -0,0 +1,190 @@
+//! System prompt templates
+//!
+//! A library of reusable system prompts with `{{variable}}` placeholders
+//! ("You are reviewing {{language}} code for {{team}}"), kept in the session
+//! database next to the chats that use them. Rendering fills each variable
+//! from, in order: values passed to `render_prompt_template`, the session's
+//! own defaults (set with `set_session_prompt_variables`), then the
+//! template's defaults. A variable with no value anywhere is an error, so a
+//! half-filled prompt is never sent.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crate::commands::{get_stored_session, new_session_id, update_stored_session};
+use crate::session_store;
+
+/// Session field holding the session's default variable values
+pub const PROMPT_VARIABLES_FIELD: &str = "promptVariables";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    /// Assigned on first save when empty
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub content: String,
+    /// Values used when neither the call nor the session sets one
+    #[serde(default)]
+    pub defaults: BTreeMap<String, String>,
+    /// Variables the content references, in order of first use (filled in
+    /// on save)
+    #[serde(default)]
+    pub variables: Vec<String>,
+}
+
+fn placeholder_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z0-9_.-]+)\s*\}\}").unwrap())
+}
+
+/// The variables `content` references, each once, in order of first use
+fn template_variables(content: &str) -> Vec<String> {
+    let mut variables: Vec<String> = Vec::new();
+    for captures in placeholder_regex().captures_iter(content) {
+        let name = &captures[1];
+        if !variables.iter().any(|v| v == name) {
+            variables.push(name.to_string());
+        }
+    }
+    variables
+}
+
+/// Substitute every `{{variable}}` in `content`. Errors naming the variables
+/// that have no value.
+fn render(content: &str, values: &BTreeMap<String, String>) -> Result<String, String> {
+    let missing: Vec<String> = template_variables(content)
+        .into_iter()
+        .filter(|name| !values.contains_key(name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing values for template variables: {}", missing.join(", ")));
+    }
+    Ok(placeholder_regex()
+        .replace_all(content, |captures: &regex::Captures| values[&captures[1]].clone())
+        .into_owned())
+}
+
+fn load_templates(app: &tauri::AppHandle) -> Result<Vec<PromptTemplate>, String> {
+    Ok(session_store::list_prompt_templates(app)?
+        .into_iter()
+        .filter_map(|template| match serde_json::from_value::<PromptTemplate>(template) {
+            Ok(mut template) => {
+                template.variables = template_variables(&template.content);
+                Some(template)
+            }
+            Err(e) => {
+                eprintln!("Skipping invalid prompt template: {}", e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// A session's default variable values (empty for unknown sessions)
+fn session_variables(app: &tauri::AppHandle, session_id: &str) -> BTreeMap<String, String> {
+    get_stored_session(app, session_id)
+        .ok()
+        .flatten()
+        .and_then(|session| serde_json::from_value(session[PROMPT_VARIABLES_FIELD].clone()).ok())
+        .unwrap_or_default()
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn list_prompt_templates(app: tauri::AppHandle) -> Result<Vec<PromptTemplate>, String> {
+    load_templates(&app)
+}
+
+/// Create or update a template (matched by id). Returns the saved template.
+#[tauri::command]
+pub fn save_prompt_template(app: tauri::AppHandle, template: PromptTemplate) -> Result<PromptTemplate, String> {
+    if template.name.trim().is_empty() {
+        return Err("Template name cannot be empty".to_string());
+    }
+
+    let mut template = template;
+    if template.id.is_empty() {
+        template.id = new_session_id();
+    }
+    template.variables = template_variables(&template.content);
+
+    let data = serde_json::to_value(&template).map_err(|e| format!("Failed to serialize template: {}", e))?;
+    session_store::save_prompt_template(&app, &template.id, &template.name, &data)?;
+    Ok(template)
+}
+
+#[tauri::command]
+pub fn delete_prompt_template(app: tauri::AppHandle, template_id: String) -> Result<(), String> {
+    session_store::delete_prompt_template(&app, &template_id)
+}
+
+/// Render a template to a system prompt. `variables` override the session's
+/// defaults, which override the template's.
+#[tauri::command]
+pub fn render_prompt_template(
+    app: tauri::AppHandle,
+    template_id: String,
+    session_id: Option<String>,
+    variables: Option<BTreeMap<String, String>>,
+) -> Result<String, String> {
+    let template = load_templates(&app)?
+        .into_iter()
+        .find(|t| t.id == template_id)
+        .ok_or_else(|| format!("Prompt template not found: {}", template_id))?;
+
+    let mut values = template.defaults;
+    if let Some(session_id) = &session_id {
+        values.extend(session_variables(&app, session_id));
+    }
+    values.extend(variables.unwrap_or_default());
+    render(&template.content, &values)
+}
+
+/// Set a session's default template variable values (an empty map clears them)
+#[tauri::command]
+pub fn set_session_prompt_variables(
+    app: tauri::AppHandle,
+    session_id: String,
+    variables: BTreeMap<String, String>,
+) -> Result<(), String> {
+    update_stored_session(&app, &session_id, |fields| {
+        if variables.is_empty() {
+            fields.remove(PROMPT_VARIABLES_FIELD);
+        } else {
+            let value = serde_json::to_value(&variables).map_err(|e| e.to_string())?;
+            fields.insert(PROMPT_VARIABLES_FIELD.to_string(), value);
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_variables_and_reports_missing_ones() {
+        let content = "Review {{ language }} code for {{team}}. Prefer idiomatic {{language}}.";
+        assert_eq!(template_variables(content), ["language", "team"]);
+
+        let mut values = BTreeMap::from([("language".to_string(), "Rust".to_string())]);
+        assert_eq!(
+            render(content, &values),
+            Err("Missing values for template variables: team".to_string())
+        );
+        values.insert("team".to_string(), "platform".to_string());
+        assert_eq!(
+            render(content, &values).unwrap(),
+            "Review Rust code for platform. Prefer idiomatic Rust."
+        );
+        assert_eq!(render("No {{ }} placeholders {here}", &values).unwrap(), "No {{ }} placeholders {here}");
+    }
+}
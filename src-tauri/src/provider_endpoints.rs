@@ -0,0 +1,176 @@
+//! Custom provider endpoints
+//!
+//! Each provider's API root can be overridden so requests go to Azure
+//! OpenAI, a LiteLLM or corporate gateway, or another compatible server.
+//! An override can also move the API key to a different header (Azure's
+//! `api-key`) and add headers and query parameters (`api-version`) to every
+//! request. Every provider request uses the override, file and container
+//! downloads included, except Gemini's resumable file uploads, which go to
+//! Google's separate upload host. Overrides are kept in memory for the provider
+//! clients, which have no app handle, and persisted in settings.
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::settings;
+
+const PROVIDER_ENDPOINTS_KEY: &str = "provider_endpoints";
+
+/// Provider → override
+static ENDPOINTS: RwLock<BTreeMap<String, ProviderEndpoint>> = RwLock::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderEndpoint {
+    /// Replaces the API root, e.g. `https://my-resource.openai.azure.com/openai/v1`
+    pub base_url: Option<String>,
+    /// Header carrying the API key as-is, instead of the provider's own
+    pub auth_header: Option<String>,
+    /// Added to every request
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Query parameters added to every request
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+}
+
+/// The API root requests are made against when not overridden
+fn default_base_url(provider: &str) -> &'static str {
+    match provider {
+        "openai" => "https://api.openai.com/v1",
+        "google" => "https://generativelanguage.googleapis.com/v1beta",
+        "openrouter" => "https://openrouter.ai/api/v1",
+        _ => "https://api.anthropic.com/v1",
+    }
+}
+
+fn endpoint_url(endpoint: Option<&ProviderEndpoint>, provider: &str, path: &str) -> String {
+    let base = endpoint
+        .and_then(|e| e.base_url.as_deref())
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .unwrap_or(default_base_url(provider));
+    format!("{}{}", base.trim_end_matches('/'), path)
+}
+
+fn validate(endpoint: &ProviderEndpoint) -> Result<(), String> {
+    if let Some(base_url) = endpoint.base_url.as_deref().filter(|url| !url.trim().is_empty()) {
+        let url = reqwest::Url::parse(base_url.trim()).map_err(|e| format!("Invalid base URL: {}", e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("Base URL must be http or https".to_string());
+        }
+    }
+    let header_names = endpoint.auth_header.iter().chain(endpoint.headers.keys());
+    for name in header_names {
+        reqwest::header::HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| format!("Invalid header name: {}", name))?;
+    }
+    for value in endpoint.headers.values() {
+        reqwest::header::HeaderValue::from_str(value).map_err(|_| format!("Invalid header value: {}", value))?;
+    }
+    Ok(())
+}
+
+/// Load the stored overrides. Called once at startup.
+pub fn init(app: &tauri::AppHandle) {
+    *ENDPOINTS.write() = settings::get_setting(app, PROVIDER_ENDPOINTS_KEY).unwrap_or_default();
+}
+
+/// A request to `path` under the provider's API root (e.g. `/messages`),
+/// authenticated with `api_key` and carrying the override's extra headers
+/// and query parameters
+pub fn request(
+    client: &reqwest::Client,
+    method: reqwest::Method,
+    provider: &str,
+    path: &str,
+    api_key: &str,
+) -> reqwest::RequestBuilder {
+    let endpoints = ENDPOINTS.read();
+    let endpoint = endpoints.get(provider);
+    let request = client.request(method, endpoint_url(endpoint, provider, path));
+
+    let auth_header = endpoint.and_then(|e| e.auth_header.as_deref()).map(str::trim).filter(|h| !h.is_empty());
+    let mut request = match (auth_header, provider) {
+        (Some(header), _) => request.header(header, api_key),
+        (None, "anthropic") => request.header("x-api-key", api_key),
+        (None, "google") => request.header("x-goog-api-key", api_key),
+        (None, _) => request.bearer_auth(api_key),
+    };
+    if let Some(endpoint) = endpoint {
+        for (name, value) in &endpoint.headers {
+            request = request.header(name.trim(), value);
+        }
+        if !endpoint.query.is_empty() {
+            request = request.query(&endpoint.query);
+        }
+    }
+    request
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_provider_endpoints() -> BTreeMap<String, ProviderEndpoint> {
+    ENDPOINTS.read().clone()
+}
+
+/// Override a provider's endpoint; None restores the default
+#[tauri::command]
+pub fn set_provider_endpoint(
+    app: tauri::AppHandle,
+    provider: String,
+    endpoint: Option<ProviderEndpoint>,
+) -> Result<BTreeMap<String, ProviderEndpoint>, String> {
+    if !matches!(provider.as_str(), "anthropic" | "openai" | "google" | "openrouter") {
+        return Err(format!("Invalid provider: {}", provider));
+    }
+    if let Some(endpoint) = &endpoint {
+        validate(endpoint)?;
+    }
+
+    let mut endpoints = ENDPOINTS.read().clone();
+    match endpoint {
+        Some(endpoint) => endpoints.insert(provider, endpoint),
+        None => endpoints.remove(&provider),
+    };
+    settings::set_setting(&app, PROVIDER_ENDPOINTS_KEY, &endpoints)?;
+    *ENDPOINTS.write() = endpoints.clone();
+    Ok(endpoints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_replace_the_api_root() {
+        assert_eq!(endpoint_url(None, "anthropic", "/messages"), "https://api.anthropic.com/v1/messages");
+        let azure = ProviderEndpoint {
+            base_url: Some("https://acme.openai.azure.com/openai/v1/".to_string()),
+            auth_header: Some("api-key".to_string()),
+            query: BTreeMap::from([("api-version".to_string(), "preview".to_string())]),
+            ..Default::default()
+        };
+        assert_eq!(
+            endpoint_url(Some(&azure), "openai", "/responses"),
+            "https://acme.openai.azure.com/openai/v1/responses"
+        );
+        assert!(validate(&azure).is_ok());
+
+        let blank = ProviderEndpoint {
+            base_url: Some(" ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(endpoint_url(Some(&blank), "openrouter", "/models"), "https://openrouter.ai/api/v1/models");
+
+        let bad_header = ProviderEndpoint {
+            headers: BTreeMap::from([("bad header".to_string(), "x".to_string())]),
+            ..Default::default()
+        };
+        assert_eq!(validate(&bad_header), Err("Invalid header name: bad header".to_string()));
+    }
+}
@@ -0,0 +1,108 @@
+//! User-defined model → provider routing
+//!
+//! `get_provider_for_model` recognizes the built-in model families by
+//! prefix. Custom and fine-tuned model names (`ft:gpt-4o:acme::abc`,
+//! `my-claude-proxy`) can be mapped to a provider here; a pattern is either
+//! an exact model name or a prefix ending in `*`, and the most specific
+//! match wins. Mappings are kept in memory for the synchronous lookup and
+//! persisted in settings.
+
+use parking_lot::RwLock;
+use std::collections::BTreeMap;
+
+use crate::settings;
+
+const MODEL_PROVIDER_MAPPINGS_KEY: &str = "model_provider_mappings";
+
+/// Providers a model can be routed to
+const PROVIDERS: &[&str] = &["anthropic", "openai", "google", "openrouter"];
+
+/// Pattern → provider
+static MAPPINGS: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+
+/// Load the stored mappings. Called once at startup.
+pub fn init(app: &tauri::AppHandle) {
+    *MAPPINGS.write() = settings::get_setting(app, MODEL_PROVIDER_MAPPINGS_KEY).unwrap_or_default();
+}
+
+/// The provider of the most specific mapping matching `model`: an exact
+/// match, else the longest matching prefix pattern
+fn match_mapping(mappings: &BTreeMap<String, String>, model: &str) -> Option<&'static str> {
+    let provider = mappings.get(model).or_else(|| {
+        mappings
+            .iter()
+            .filter_map(|(pattern, provider)| {
+                let prefix = pattern.strip_suffix('*')?;
+                model.starts_with(prefix).then_some((prefix.len(), provider))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, provider)| provider)
+    })?;
+    PROVIDERS.iter().copied().find(|p| p == provider)
+}
+
+/// The provider a user mapping routes `model` to, if any
+pub fn mapped_provider(model: &str) -> Option<&'static str> {
+    match_mapping(&MAPPINGS.read(), model)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_model_provider_mappings() -> BTreeMap<String, String> {
+    MAPPINGS.read().clone()
+}
+
+/// Route `pattern` (a model name, or a prefix ending in `*`) to `provider`;
+/// no provider removes the mapping
+#[tauri::command]
+pub fn set_model_provider_mapping(
+    app: tauri::AppHandle,
+    pattern: String,
+    provider: Option<String>,
+) -> Result<BTreeMap<String, String>, String> {
+    let pattern = pattern.trim().to_string();
+    if pattern.is_empty() || pattern == "*" {
+        return Err("Model pattern can't be empty".to_string());
+    }
+    if let Some(provider) = &provider {
+        if !PROVIDERS.contains(&provider.as_str()) {
+            return Err(format!("Invalid provider: {}", provider));
+        }
+    }
+
+    let mut mappings = MAPPINGS.read().clone();
+    match provider {
+        Some(provider) => mappings.insert(pattern, provider),
+        None => mappings.remove(&pattern),
+    };
+    settings::set_setting(&app, MODEL_PROVIDER_MAPPINGS_KEY, &mappings)?;
+    *MAPPINGS.write() = mappings.clone();
+    Ok(mappings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_names_beat_prefixes_and_longer_prefixes_win() {
+        let mappings: BTreeMap<String, String> = [
+            ("ft:*", "openai"),
+            ("ft:gemini*", "google"),
+            ("ft:gemini-custom", "anthropic"),
+            ("typo*", "not-a-provider"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        assert_eq!(match_mapping(&mappings, "ft:gpt-4o:acme::abc"), Some("openai"));
+        assert_eq!(match_mapping(&mappings, "ft:gemini-2.5-flash:acme"), Some("google"));
+        assert_eq!(match_mapping(&mappings, "ft:gemini-custom"), Some("anthropic"));
+        assert_eq!(match_mapping(&mappings, "typo-model"), None);
+        assert_eq!(match_mapping(&mappings, "claude-sonnet-4-5"), None);
+    }
+}
@@ -1,9 +1,16 @@
 use serde::{Deserialize, Serialize};
 
+use crate::anthropic_betas::{self, BetaFeature};
+use crate::audit_log;
+use crate::discovery_context::DiscoveryImage;
+use crate::error::SidestreamError;
 use crate::llm::tool_names;
 use crate::mime_utils;
+use crate::provider_endpoints;
+use crate::proxy;
+use crate::request_validation;
+use crate::sse::record_parse_warning;
 
-const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
 
 /// Appended to the system prompt whenever code_execution is enabled.
@@ -89,6 +96,8 @@ pub struct ChatRequestConfig {
     pub web_search_enabled: bool,
     pub code_execution_enabled: bool,
     pub container_id: Option<String>,
+    /// Frontend-implemented tool definitions (see `llm::user_tool_definitions`)
+    pub user_tools: Vec<serde_json::Value>,
 }
 
 /// Configuration for adaptive extended thinking (Opus 4.8 / Opus 4.6 / Sonnet 4.6)
@@ -96,12 +105,19 @@ pub struct ThinkingConfig {
     pub effort_level: String,  // "off", "low", "medium", "high", "xhigh", "max", "adaptive"
 }
 
+/// Tool a discovery reply reports its items with
+pub const DISCOVERY_TOOL_NAME: &str = "report_discovery_items";
+
 /// Configuration for a discovery request
 pub struct DiscoveryRequestConfig {
     pub model: String,
     pub system_prompt: String,
     pub conversation: String,
+    /// Images from the conversation, sent after its text
+    pub images: Vec<DiscoveryImage>,
     pub extended_thinking_enabled: Option<bool>,
+    /// Input schema of the discovery tool
+    pub output_schema: serde_json::Value,
 }
 
 /// Parsed SSE events from Anthropic's streaming API
@@ -109,9 +125,13 @@ pub struct DiscoveryRequestConfig {
 pub enum AnthropicStreamEvent {
     MessageStart {
         container_id: Option<String>, // Container ID for code execution sandbox persistence
+        message_id: Option<String>,
+        model: Option<String>, // Exact model snapshot serving the request
+        usage: Option<InputUsage>,
     },
     MessageDelta {
         container_id: Option<String>, // Container ID appears here in streaming responses
+        output_tokens: Option<u64>,   // Cumulative output tokens of the response
     },
     ContentBlockStart {
         block_type: String,
@@ -122,13 +142,27 @@ pub enum AnthropicStreamEvent {
         thinking: Option<String>,
         citation: Option<Citation>, // citations_delta events
         input_json: Option<String>, // input_json_delta for tool use
+        signature: Option<String>,  // signature_delta closing a thinking block
     },
     ContentBlockStop,
     MessageStop,
+    /// An `error` event mid-stream (e.g. overloaded_error)
+    Error {
+        message: String,
+    },
     Done,
     Unknown,
 }
 
+/// Input token usage of a request from `message_start`, including how much
+/// of the prompt was written to or read from the prompt cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputUsage {
+    pub input_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+}
+
 /// Result from code execution
 #[derive(Debug, Clone)]
 pub struct CodeExecutionResult {
@@ -168,7 +202,7 @@ pub struct InlineCitation {
 impl AnthropicClient {
     pub fn new(api_key: String) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client: proxy::client(),
             api_key,
         }
     }
@@ -229,6 +263,8 @@ impl AnthropicClient {
             }));
         }
 
+        tools.extend(config.user_tools.iter().cloned());
+
         if !tools.is_empty() {
             body["tools"] = serde_json::json!(tools);
         }
@@ -262,6 +298,19 @@ impl AnthropicClient {
 
     /// Build the request body for a discovery request
     pub fn build_discovery_request(&self, config: &DiscoveryRequestConfig) -> serde_json::Value {
+        let mut content = vec![serde_json::json!({
+            "type": "text",
+            "text": config.conversation,
+            "cache_control": {"type": "ephemeral"}
+        })];
+        for image in &config.images {
+            content.push(serde_json::json!({"type": "text", "text": image.label}));
+            content.push(serde_json::json!({
+                "type": "image",
+                "source": {"type": "base64", "media_type": image.mime_type, "data": image.data}
+            }));
+        }
+
         let mut body = serde_json::json!({
             "model": config.model,
             "max_tokens": 4096,
@@ -269,24 +318,31 @@ impl AnthropicClient {
             "system": [
                 {
                     "type": "text",
-                    "text": config.system_prompt,
+                    "text": format!(
+                        "{}\n\nReport your results by calling the {} tool; don't write them as text.",
+                        config.system_prompt, DISCOVERY_TOOL_NAME
+                    ),
                     "cache_control": {"type": "ephemeral"}
                 }
             ],
-            "tools": [{
-                "type": "web_search_20250305",
-                "name": "web_search"
-            }],
+            // The items come back as the discovery tool's input. It can't be
+            // forced with tool_choice: the model has to search first, and
+            // thinking only allows "auto".
+            "tools": [
+                {
+                    "type": "web_search_20250305",
+                    "name": "web_search"
+                },
+                {
+                    "name": DISCOVERY_TOOL_NAME,
+                    "description": "Report the discovered resources.",
+                    "input_schema": config.output_schema
+                }
+            ],
             "messages": [
                 {
                     "role": "user",
-                    "content": [
-                        {
-                            "type": "text",
-                            "text": config.conversation,
-                            "cache_control": {"type": "ephemeral"}
-                        }
-                    ]
+                    "content": content
                 }
             ]
         });
@@ -317,7 +373,7 @@ impl AnthropicClient {
     pub async fn send_streaming_request(
         &self,
         body: &serde_json::Value,
-    ) -> Result<reqwest::Response, String> {
+    ) -> Result<reqwest::Response, SidestreamError> {
         self.send_streaming_request_with_beta(body, None).await
     }
 
@@ -326,32 +382,164 @@ impl AnthropicClient {
         &self,
         body: &serde_json::Value,
         beta_header: Option<&str>,
-    ) -> Result<reqwest::Response, String> {
-        let mut request = self
-            .client
-            .post(ANTHROPIC_API_URL)
-            .header("x-api-key", &self.api_key)
+    ) -> Result<reqwest::Response, SidestreamError> {
+        request_validation::validate("anthropic", body)?;
+        audit_log::record_json("anthropic", body["model"].as_str().unwrap_or_default(), "messages", body);
+        let mut request =
+            provider_endpoints::request(&self.client, reqwest::Method::POST, "anthropic", "/messages", &self.api_key)
             .header("anthropic-version", ANTHROPIC_VERSION)
             .header("content-type", "application/json");
 
-        // Add beta header if provided (e.g., for code execution)
-        if let Some(beta) = beta_header {
+        // Add beta header if provided (e.g., for code execution), with tool
+        // streaming whenever the request has tools
+        if let Some(beta) = anthropic_betas::streaming_beta_header(beta_header, body) {
             request = request.header("anthropic-beta", beta);
         }
 
         let response = request
             .json(body)
             .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(format!("API error: {}", error_text));
+            return Err(SidestreamError::from_response(response).await);
         }
 
         Ok(response)
     }
+
+    /// Send a non-streaming request and return the parsed response JSON
+    pub async fn send_request(&self, body: &serde_json::Value) -> Result<serde_json::Value, SidestreamError> {
+        self.send_request_with_beta(body, None).await
+    }
+
+    /// Send a non-streaming request with optional beta header
+    pub async fn send_request_with_beta(
+        &self,
+        body: &serde_json::Value,
+        beta_header: Option<&str>,
+    ) -> Result<serde_json::Value, SidestreamError> {
+        request_validation::validate("anthropic", body)?;
+        audit_log::record_json("anthropic", body["model"].as_str().unwrap_or_default(), "messages", body);
+        let mut request =
+            provider_endpoints::request(&self.client, reqwest::Method::POST, "anthropic", "/messages", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header("content-type", "application/json");
+        if let Some(beta) = beta_header {
+            request = request.header("anthropic-beta", beta);
+        }
+
+        let response = request.json(body).send().await?;
+
+        if !response.status().is_success() {
+            return Err(SidestreamError::from_response(response).await);
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e).into())
+    }
+
+    /// Count a request's input tokens with the count_tokens endpoint. `body`
+    /// takes the Messages API fields that count (model, system, messages,
+    /// tools).
+    pub async fn count_tokens(&self, body: &serde_json::Value) -> Result<u64, SidestreamError> {
+        audit_log::record_json("anthropic", body["model"].as_str().unwrap_or_default(), "messages/count_tokens", body);
+        let response = provider_endpoints::request(
+            &self.client,
+            reqwest::Method::POST,
+            "anthropic",
+            "/messages/count_tokens",
+            &self.api_key,
+        )
+        .header("anthropic-version", ANTHROPIC_VERSION)
+        .header("content-type", "application/json")
+        .json(body)
+        .send()
+        .await?;
+        let counted = Self::json_response(response).await?;
+        counted["input_tokens"]
+            .as_u64()
+            .ok_or_else(|| "count_tokens response has no input_tokens".into())
+    }
+
+    /// Submit a Message Batch of `(custom_id, params)` requests and return
+    /// the batch object
+    pub async fn create_message_batch(
+        &self,
+        requests: &[(String, serde_json::Value)],
+    ) -> Result<serde_json::Value, SidestreamError> {
+        for (_, params) in requests {
+            audit_log::record_json("anthropic", params["model"].as_str().unwrap_or_default(), "messages/batches", params);
+        }
+        let requests: Vec<serde_json::Value> = requests
+            .iter()
+            .map(|(custom_id, params)| serde_json::json!({"custom_id": custom_id, "params": params}))
+            .collect();
+        let response =
+            provider_endpoints::request(&self.client, reqwest::Method::POST, "anthropic", "/messages/batches", &self.api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION)
+                .header("content-type", "application/json")
+                .json(&serde_json::json!({"requests": requests}))
+                .send()
+                .await?;
+        Self::json_response(response).await
+    }
+
+    /// Fetch a Message Batch's status
+    pub async fn get_message_batch(&self, batch_id: &str) -> Result<serde_json::Value, SidestreamError> {
+        let path = format!("/messages/batches/{}", batch_id);
+        let response = provider_endpoints::request(&self.client, reqwest::Method::GET, "anthropic", &path, &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .send()
+            .await?;
+        Self::json_response(response).await
+    }
+
+    /// Fetch the results of an ended Message Batch, one entry per request
+    pub async fn get_message_batch_results(&self, batch_id: &str) -> Result<Vec<serde_json::Value>, SidestreamError> {
+        let path = format!("/messages/batches/{}/results", batch_id);
+        let response = provider_endpoints::request(&self.client, reqwest::Method::GET, "anthropic", &path, &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(SidestreamError::from_response(response).await);
+        }
+        // Results are JSON Lines
+        let text = response.text().await?;
+        Ok(text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    async fn json_response(response: reqwest::Response) -> Result<serde_json::Value, SidestreamError> {
+        if !response.status().is_success() {
+            return Err(SidestreamError::from_response(response).await);
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e).into())
+    }
+}
+
+/// Concatenate the text blocks of a non-streaming Messages API response
+pub fn extract_response_text(response: &serde_json::Value) -> String {
+    response["content"]
+        .as_array()
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter(|b| b["type"].as_str() == Some("text"))
+                .filter_map(|b| b["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("")
+        })
+        .unwrap_or_default()
 }
 
 /// Parse a single SSE data payload into an AnthropicStreamEvent
@@ -362,7 +550,10 @@ pub fn parse_sse_event(data: &str) -> AnthropicStreamEvent {
 
     let parsed: serde_json::Value = match serde_json::from_str(data) {
         Ok(v) => v,
-        Err(_) => return AnthropicStreamEvent::Unknown,
+        Err(_) => {
+            record_parse_warning("anthropic", "invalid JSON");
+            return AnthropicStreamEvent::Unknown;
+        }
     };
 
     let event_type = parsed["type"].as_str().unwrap_or("");
@@ -374,13 +565,28 @@ pub fn parse_sse_event(data: &str) -> AnthropicStreamEvent {
             let container_id = parsed["message"]["container"]["id"]
                 .as_str()
                 .map(|s| s.to_string());
-            AnthropicStreamEvent::MessageStart { container_id }
+            AnthropicStreamEvent::MessageStart {
+                container_id,
+                message_id: parsed["message"]["id"].as_str().map(|s| s.to_string()),
+                model: parsed["message"]["model"].as_str().map(|s| s.to_string()),
+                usage: parsed["message"]["usage"].as_object().map(|usage| {
+                    let tokens = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+                    InputUsage {
+                        input_tokens: tokens("input_tokens"),
+                        cache_creation_input_tokens: tokens("cache_creation_input_tokens"),
+                        cache_read_input_tokens: tokens("cache_read_input_tokens"),
+                    }
+                }),
+            }
         }
         "content_block_start" => {
             let block_type = parsed["content_block"]["type"]
                 .as_str()
                 .unwrap_or("")
                 .to_string();
+            if block_type.is_empty() {
+                record_parse_warning("anthropic", "content_block_start without a block type");
+            }
             let content_block = parsed["content_block"].clone();
             AnthropicStreamEvent::ContentBlockStart {
                 block_type,
@@ -414,6 +620,7 @@ pub fn parse_sse_event(data: &str) -> AnthropicStreamEvent {
                         thinking: None,
                         citation,
                         input_json: None,
+                        signature: None,
                     }
                 }
                 "input_json_delta" => {
@@ -424,10 +631,21 @@ pub fn parse_sse_event(data: &str) -> AnthropicStreamEvent {
                         thinking: None,
                         citation: None,
                         input_json: partial_json,
+                        signature: None,
                     }
                 }
+                "signature_delta" => AnthropicStreamEvent::ContentBlockDelta {
+                    text: None,
+                    thinking: None,
+                    citation: None,
+                    input_json: None,
+                    signature: parsed["delta"]["signature"].as_str().map(|s| s.to_string()),
+                },
                 _ => {
                     // text_delta or thinking_delta
+                    if !matches!(delta_type, "text_delta" | "thinking_delta") {
+                        record_parse_warning("anthropic", format!("unhandled delta type: {}", delta_type));
+                    }
                     let text = parsed["delta"]["text"].as_str().map(|s| s.to_string());
                     let thinking = parsed["delta"]["thinking"].as_str().map(|s| s.to_string());
                     AnthropicStreamEvent::ContentBlockDelta {
@@ -435,6 +653,7 @@ pub fn parse_sse_event(data: &str) -> AnthropicStreamEvent {
                         thinking,
                         citation: None,
                         input_json: None,
+                        signature: None,
                     }
                 }
             }
@@ -445,11 +664,54 @@ pub fn parse_sse_event(data: &str) -> AnthropicStreamEvent {
             let container_id = parsed["delta"]["container"]["id"]
                 .as_str()
                 .map(|s| s.to_string());
-            AnthropicStreamEvent::MessageDelta { container_id }
+            let output_tokens = parsed["usage"]["output_tokens"].as_u64();
+            AnthropicStreamEvent::MessageDelta { container_id, output_tokens }
         }
         "message_stop" => AnthropicStreamEvent::MessageStop,
-        _ => AnthropicStreamEvent::Unknown,
+        "error" => {
+            let message = parsed["error"]["message"]
+                .as_str()
+                .unwrap_or("Unknown error")
+                .to_string();
+            AnthropicStreamEvent::Error { message }
+        }
+        "ping" => AnthropicStreamEvent::Unknown,
+        other => {
+            record_parse_warning("anthropic", format!("unhandled event type: {}", other));
+            AnthropicStreamEvent::Unknown
+        }
+    }
+}
+
+/// Build the content of a replayed assistant message, putting its captured
+/// thinking blocks back in front of the text. Thinking blocks without a
+/// signature are dropped: the API rejects them, and they can only come from a
+/// stream that was cut off before the block finished.
+pub fn assistant_content_with_thinking(
+    content: &serde_json::Value,
+    thinking_blocks: &[serde_json::Value],
+) -> serde_json::Value {
+    let mut blocks: Vec<serde_json::Value> = thinking_blocks
+        .iter()
+        .filter(|b| match b["type"].as_str() {
+            Some("thinking") => b["signature"].as_str().is_some_and(|s| !s.is_empty()),
+            Some("redacted_thinking") => b["data"].is_string(),
+            _ => false,
+        })
+        .cloned()
+        .collect();
+    if blocks.is_empty() {
+        return content.clone();
+    }
+
+    match content {
+        serde_json::Value::String(text) if !text.is_empty() => {
+            blocks.push(serde_json::json!({"type": "text", "text": text}));
+        }
+        serde_json::Value::Array(parts) => blocks.extend(parts.iter().cloned()),
+        _ => {}
     }
+    serde_json::Value::Array(blocks)
 }
 
 /// Check if a content block is a code execution tool use
@@ -608,22 +870,18 @@ pub struct FileMetadata {
 }
 
 /// Fetch file metadata from Anthropic Files API to get mime_type
-pub async fn fetch_file_metadata(api_key: &str, file_id: &str) -> Result<FileMetadata, String> {
-    let client = reqwest::Client::new();
-    let url = format!("https://api.anthropic.com/v1/files/{}", file_id);
+pub async fn fetch_file_metadata(api_key: &str, file_id: &str) -> Result<FileMetadata, SidestreamError> {
+    let client = proxy::client();
+    let path = format!("/files/{}", file_id);
 
-    let response = client
-        .get(&url)
-        .header("x-api-key", api_key)
-        .header("anthropic-version", ANTHROPIC_VERSION)
-        .header("anthropic-beta", "files-api-2025-04-14")
+    let request = provider_endpoints::request(&client, reqwest::Method::GET, "anthropic", &path, api_key)
+        .header("anthropic-version", ANTHROPIC_VERSION);
+    let response = anthropic_betas::with_betas(request, &[BetaFeature::FilesApi])
         .send()
-        .await
-        .map_err(|e| format!("Failed to fetch file metadata: {}", e))?;
+        .await?;
 
     if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("File metadata API error: {}", error_text));
+        return Err(SidestreamError::from_response(response).await);
     }
 
     let metadata: FileMetadata = response
@@ -635,22 +893,18 @@ pub async fn fetch_file_metadata(api_key: &str, file_id: &str) -> Result<FileMet
 }
 
 /// Fetch file content from Anthropic Files API and return as base64
-pub async fn fetch_file_content_base64(api_key: &str, file_id: &str) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    let url = format!("https://api.anthropic.com/v1/files/{}/content", file_id);
+pub async fn fetch_file_content_base64(api_key: &str, file_id: &str) -> Result<String, SidestreamError> {
+    let client = proxy::client();
+    let path = format!("/files/{}/content", file_id);
 
-    let response = client
-        .get(&url)
-        .header("x-api-key", api_key)
-        .header("anthropic-version", ANTHROPIC_VERSION)
-        .header("anthropic-beta", "files-api-2025-04-14")
+    let request = provider_endpoints::request(&client, reqwest::Method::GET, "anthropic", &path, api_key)
+        .header("anthropic-version", ANTHROPIC_VERSION);
+    let response = anthropic_betas::with_betas(request, &[BetaFeature::FilesApi])
         .send()
-        .await
-        .map_err(|e| format!("Failed to fetch file content: {}", e))?;
+        .await?;
 
     if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("File content API error: {}", error_text));
+        return Err(SidestreamError::from_response(response).await);
     }
 
     let bytes = response
@@ -661,3 +915,62 @@ pub async fn fetch_file_content_base64(api_key: &str, file_id: &str) -> Result<S
     use base64::Engine;
     Ok(base64::engine::general_purpose::STANDARD.encode(&bytes))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cache_usage_from_message_start() {
+        let event = parse_sse_event(
+            r#"{"type":"message_start","message":{"id":"msg_1","model":"claude-sonnet-4-6","usage":{"input_tokens":12,"cache_creation_input_tokens":0,"cache_read_input_tokens":2048,"output_tokens":1}}}"#,
+        );
+        match event {
+            AnthropicStreamEvent::MessageStart { usage, .. } => assert_eq!(
+                usage,
+                Some(InputUsage {
+                    input_tokens: 12,
+                    cache_creation_input_tokens: 0,
+                    cache_read_input_tokens: 2048,
+                })
+            ),
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_signature_deltas() {
+        let event = parse_sse_event(
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"EqQB"}}"#,
+        );
+        match event {
+            AnthropicStreamEvent::ContentBlockDelta { signature, text, .. } => {
+                assert_eq!(signature.as_deref(), Some("EqQB"));
+                assert!(text.is_none());
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn replayed_thinking_precedes_text_and_drops_unsigned_blocks() {
+        let blocks = vec![
+            serde_json::json!({"type": "thinking", "thinking": "plan", "signature": "sig"}),
+            serde_json::json!({"type": "thinking", "thinking": "cut off", "signature": ""}),
+            serde_json::json!({"type": "redacted_thinking", "data": "opaque"}),
+        ];
+        let content = assistant_content_with_thinking(&serde_json::json!("Answer"), &blocks);
+        assert_eq!(
+            content,
+            serde_json::json!([
+                {"type": "thinking", "thinking": "plan", "signature": "sig"},
+                {"type": "redacted_thinking", "data": "opaque"},
+                {"type": "text", "text": "Answer"}
+            ])
+        );
+        assert_eq!(
+            assistant_content_with_thinking(&serde_json::json!("Answer"), &[]),
+            serde_json::json!("Answer")
+        );
+    }
+}
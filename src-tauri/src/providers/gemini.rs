@@ -1,7 +1,18 @@
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-const GEMINI_API_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+use crate::audit_log;
+use crate::discovery_context::DiscoveryImage;
+use crate::error::SidestreamError;
+use crate::message_format::{gemini as gemini_format, Message};
+use crate::provider_endpoints;
+use crate::proxy;
+use crate::request_validation;
+use crate::sse::record_parse_warning;
+
+/// Resumable uploads go to Google's upload host, which an endpoint override
+/// (see `provider_endpoints`) doesn't cover
+const GEMINI_UPLOAD_URL: &str = "https://generativelanguage.googleapis.com/upload/v1beta/files";
 
 /// Appended to the system instruction whenever code execution is enabled
 /// (Part B in notes/prompt_compositions.md).
@@ -104,22 +115,31 @@ pub struct ChatRequestConfig {
 
 /// Thinking level for Gemini 3.x models (serialized as `thinkingLevel`).
 /// 3.1 Pro supports Low/High; 3.5 Flash also supports Minimal/Medium.
-#[derive(Clone, Copy, Debug)]
+/// Gemini 2.5 models take an explicit token budget instead (`thinkingBudget`).
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ThinkingLevel {
     Minimal, // Gemini 3.x Flash only
     Low,
     Medium, // Gemini 3.x Flash only
     High,
+    /// Explicit thinking token budget; -1 lets the model decide
+    Budget(i32),
 }
 
+/// Largest thinking budget Gemini accepts (2.5 Pro)
+pub const MAX_THINKING_BUDGET: i32 = 32768;
+
 impl ThinkingLevel {
-    pub fn as_str(&self) -> &'static str {
-        match self {
+    /// The `thinkingConfig` fields selecting this level
+    pub fn as_config(&self) -> serde_json::Value {
+        let level = match self {
+            ThinkingLevel::Budget(budget) => return serde_json::json!({"thinkingBudget": budget}),
             ThinkingLevel::Minimal => "minimal",
             ThinkingLevel::Low => "LOW",
             ThinkingLevel::Medium => "medium",
             ThinkingLevel::High => "HIGH",
-        }
+        };
+        serde_json::json!({"thinkingLevel": level})
     }
 }
 
@@ -127,7 +147,11 @@ impl ThinkingLevel {
 pub struct DiscoveryRequestConfig {
     pub system_prompt: String,
     pub conversation: String,
+    /// Images from the conversation, sent after its text
+    pub images: Vec<DiscoveryImage>,
     pub thinking_config: Option<ThinkingLevel>,
+    /// JSON Schema the reply text must follow
+    pub output_schema: serde_json::Value,
 }
 
 /// Configuration for a voice chat request (native multimodal audio)
@@ -149,8 +173,9 @@ pub enum GeminiStreamEvent {
     ThinkingDelta { text: String },
     /// Response complete. `finish_reason` is Gemini's reason (e.g. "STOP",
     /// "MAX_TOKENS", "SAFETY", "RECITATION") so the handler can distinguish a
-    /// clean finish from a truncated/blocked one.
-    ResponseComplete { finish_reason: String },
+    /// clean finish from a truncated/blocked one. `output_tokens` is the
+    /// response's reported output (answer and thinking) when it came with it.
+    ResponseComplete { finish_reason: String, output_tokens: Option<u64> },
     /// Grounding metadata (search results)
     GroundingMetadata { metadata: GroundingInfo },
     /// Error occurred
@@ -165,6 +190,11 @@ pub enum GeminiStreamEvent {
     /// per-URL retrieval status (e.g. URL_RETRIEVAL_STATUS_SUCCESS) so the
     /// log can show whether the model actually used the page-fetch path.
     UrlContextUsed { entries: Vec<UrlContextEntry> },
+    /// Response id and exact model version (repeated on every chunk)
+    ResponseInfo {
+        response_id: Option<String>,
+        model_version: Option<String>,
+    },
     /// Unknown/unhandled event
     Unknown,
 }
@@ -223,17 +253,15 @@ pub struct WebChunk {
 impl GeminiClient {
     pub fn new(api_key: String) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client: proxy::client(),
             api_key,
         }
     }
 
-    /// Build the streaming endpoint URL for a model
-    fn build_stream_url(&self, model: &str) -> String {
-        format!(
-            "{}/{}:streamGenerateContent?alt=sse&key={}",
-            GEMINI_API_URL, model, self.api_key
-        )
+    /// Start a POST to a model method (`generateContent`, `predict`, ...)
+    fn model_request(&self, model: &str, method: &str) -> reqwest::RequestBuilder {
+        let path = format!("/models/{}:{}", model, method);
+        provider_endpoints::request(&self.client, reqwest::Method::POST, "google", &path, &self.api_key)
     }
 
     /// Build the request body for a chat message
@@ -243,79 +271,11 @@ impl GeminiClient {
         let mut contents: Vec<serde_json::Value> = Vec::new();
 
         for msg in &config.messages {
-            let role = msg["role"].as_str().unwrap_or("user");
-            let content = &msg["content"];
-
-            // Map roles: Gemini uses "user" and "model" (not "assistant")
-            let gemini_role = if role == "assistant" { "model" } else { role };
-
-            // Build parts array
-            let parts = if let Some(text) = content.as_str() {
-                // Simple string content
-                serde_json::json!([{"text": text}])
-            } else if let Some(arr) = content.as_array() {
-                // Array of content blocks - convert to Gemini format
-                let converted: Vec<serde_json::Value> = arr
-                    .iter()
-                    .filter_map(|block| {
-                        let block_type = block["type"].as_str()?;
-                        match block_type {
-                            "text" => {
-                                let text = block["text"].as_str()?;
-                                Some(serde_json::json!({"text": text}))
-                            }
-                            "image" => {
-                                // Convert to Gemini inline_data format
-                                let source = &block["source"];
-                                let media_type = source["media_type"].as_str()?;
-                                let data = source["data"].as_str()?;
-                                Some(serde_json::json!({
-                                    "inline_data": {
-                                        "mime_type": media_type,
-                                        "data": data
-                                    }
-                                }))
-                            }
-                            "document" => {
-                                // PDF as inline_data
-                                let source = &block["source"];
-                                let data = source["data"].as_str()?;
-                                Some(serde_json::json!({
-                                    "inline_data": {
-                                        "mime_type": "application/pdf",
-                                        "data": data
-                                    }
-                                }))
-                            }
-                            "file" => {
-                                // Generic file: send as inline_data with original MIME type
-                                let source = &block["source"];
-                                let media_type = source["media_type"].as_str()?;
-                                let data = source["data"].as_str()?;
-                                Some(serde_json::json!({
-                                    "inline_data": {
-                                        "mime_type": media_type,
-                                        "data": data
-                                    }
-                                }))
-                            }
-                            _ => None,
-                        }
-                    })
-                    .collect();
-                serde_json::json!(converted)
-            } else {
-                serde_json::json!([{"text": ""}])
-            };
-
-            contents.push(serde_json::json!({
-                "role": gemini_role,
-                "parts": parts
-            }));
+            contents.push(gemini_format::message(&Message::from_stored(msg)));
         }
 
         let mut body = serde_json::json!({
-            "contents": contents
+            "contents": gemini_format::alternating(contents)
         });
 
         // Add system instruction. Layer three Gemini-specific addenda on top:
@@ -346,12 +306,9 @@ impl GeminiClient {
         // Add thinking configuration if enabled.
         // Gemini 3.x uses thinkingLevel; includeThoughts returns thinking summaries.
         if let Some(level) = &config.thinking_config {
-            body["generationConfig"] = serde_json::json!({
-                "thinkingConfig": {
-                    "thinkingLevel": level.as_str(),
-                    "includeThoughts": true
-                }
-            });
+            let mut thinking_config = level.as_config();
+            thinking_config["includeThoughts"] = serde_json::json!(true);
+            body["generationConfig"] = serde_json::json!({"thinkingConfig": thinking_config});
         }
 
         // Build tools array
@@ -387,25 +344,35 @@ impl GeminiClient {
 
     /// Build the request body for a discovery request
     pub fn build_discovery_request(&self, config: &DiscoveryRequestConfig) -> serde_json::Value {
+        let mut parts = vec![serde_json::json!({"text": config.conversation})];
+        for image in &config.images {
+            parts.push(serde_json::json!({"text": image.label}));
+            parts.push(serde_json::json!({
+                "inlineData": {"mimeType": image.mime_type, "data": image.data}
+            }));
+        }
+
         let mut body = serde_json::json!({
             "systemInstruction": {
                 "parts": [{"text": config.system_prompt}]
             },
             "contents": [{
                 "role": "user",
-                "parts": [{"text": config.conversation}]
+                "parts": parts
             }],
             "tools": [{
                 "google_search": {}
-            }]
+            }],
+            "generationConfig": {
+                "responseMimeType": "application/json",
+                "responseJsonSchema": config.output_schema
+            }
         });
 
         // Add thinking configuration if enabled.
         // Gemini 3.x uses thinkingLevel; no thinking summaries for internal discovery.
         if let Some(level) = &config.thinking_config {
-            body["generationConfig"] = serde_json::json!({
-                "thinkingConfig": {"thinkingLevel": level.as_str()}
-            });
+            body["generationConfig"]["thinkingConfig"] = level.as_config();
         }
 
         body
@@ -419,56 +386,7 @@ impl GeminiClient {
         let mut contents: Vec<serde_json::Value> = Vec::new();
 
         for msg in &config.messages {
-            let role = msg["role"].as_str().unwrap_or("user");
-            let content = &msg["content"];
-            let gemini_role = if role == "assistant" { "model" } else { role };
-
-            let parts = if let Some(text) = content.as_str() {
-                serde_json::json!([{"text": text}])
-            } else if let Some(arr) = content.as_array() {
-                let converted: Vec<serde_json::Value> = arr
-                    .iter()
-                    .filter_map(|block| {
-                        let block_type = block["type"].as_str()?;
-                        match block_type {
-                            "text" => {
-                                let text = block["text"].as_str()?;
-                                Some(serde_json::json!({"text": text}))
-                            }
-                            "image" => {
-                                let source = &block["source"];
-                                let media_type = source["media_type"].as_str()?;
-                                let data = source["data"].as_str()?;
-                                Some(serde_json::json!({
-                                    "inline_data": {
-                                        "mime_type": media_type,
-                                        "data": data
-                                    }
-                                }))
-                            }
-                            "document" => {
-                                let source = &block["source"];
-                                let data = source["data"].as_str()?;
-                                Some(serde_json::json!({
-                                    "inline_data": {
-                                        "mime_type": "application/pdf",
-                                        "data": data
-                                    }
-                                }))
-                            }
-                            _ => None,
-                        }
-                    })
-                    .collect();
-                serde_json::json!(converted)
-            } else {
-                serde_json::json!([{"text": ""}])
-            };
-
-            contents.push(serde_json::json!({
-                "role": gemini_role,
-                "parts": parts
-            }));
+            contents.push(gemini_format::message(&Message::from_stored(msg)));
         }
 
         // Add the new user message with audio as inlineData
@@ -492,7 +410,7 @@ impl GeminiClient {
         };
 
         let mut body = serde_json::json!({
-            "contents": contents,
+            "contents": gemini_format::alternating(contents),
             "systemInstruction": {
                 "parts": [{"text": system_prompt}]
             }
@@ -501,12 +419,9 @@ impl GeminiClient {
         // Add thinking configuration if enabled.
         // Include thinking summaries for voice chat (user-facing).
         if let Some(level) = &config.thinking_config {
-            body["generationConfig"] = serde_json::json!({
-                "thinkingConfig": {
-                    "thinkingLevel": level.as_str(),
-                    "includeThoughts": true
-                }
-            });
+            let mut thinking_config = level.as_config();
+            thinking_config["includeThoughts"] = serde_json::json!(true);
+            body["generationConfig"] = serde_json::json!({"thinkingConfig": thinking_config});
         }
 
         // Add Google Search tool if enabled
@@ -524,80 +439,297 @@ impl GeminiClient {
         &self,
         model: &str,
         body: &serde_json::Value,
-    ) -> Result<reqwest::Response, String> {
-        let url = self.build_stream_url(model);
+    ) -> Result<reqwest::Response, SidestreamError> {
+        request_validation::validate("google", body)?;
+        audit_log::record_json("google", model, "streamGenerateContent", body);
 
         let response = self
-            .client
-            .post(&url)
+            .model_request(model, "streamGenerateContent")
+            .query(&[("alt", "sse")])
             .header("Content-Type", "application/json")
             .json(body)
             .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(format!("API error ({}): {}", status, error_text));
+            return Err(SidestreamError::from_response(response).await);
         }
 
         Ok(response)
     }
 
-    /// Build the non-streaming endpoint URL for a model
-    fn build_url(&self, model: &str) -> String {
-        format!(
-            "{}/{}:generateContent?key={}",
-            GEMINI_API_URL, model, self.api_key
+    /// Build the request body for transcription-only (no chat response)
+    /// `language` is an ISO-639-1 hint; None lets the model detect the language.
+    pub fn build_transcription_request(
+        &self,
+        audio_base64: &str,
+        mime_type: &str,
+        language: Option<&str>,
+    ) -> serde_json::Value {
+        self.transcription_request_with_part(
+            serde_json::json!({"inlineData": {"mimeType": mime_type, "data": audio_base64}}),
+            language,
         )
     }
 
-    /// Build the request body for transcription-only (no chat response)
-    pub fn build_transcription_request(&self, audio_base64: &str) -> serde_json::Value {
+    /// Build a transcription request for audio uploaded with `upload_file`
+    pub fn build_file_transcription_request(
+        &self,
+        file_uri: &str,
+        mime_type: &str,
+        language: Option<&str>,
+    ) -> serde_json::Value {
+        self.transcription_request_with_part(
+            serde_json::json!({"fileData": {"mimeType": mime_type, "fileUri": file_uri}}),
+            language,
+        )
+    }
+
+    fn transcription_request_with_part(
+        &self,
+        audio_part: serde_json::Value,
+        language: Option<&str>,
+    ) -> serde_json::Value {
+        let mut instruction = String::from("Output ONLY the exact transcription of the audio. Do not add any other text, commentary, or formatting.");
+        match language {
+            Some(language) => instruction.push_str(&format!(
+                " The audio is spoken in the language with ISO-639-1 code \"{}\".",
+                language
+            )),
+            None => instruction.push_str(" Transcribe in the language that is spoken; do not translate."),
+        }
+
         serde_json::json!({
             "contents": [{
                 "role": "user",
-                "parts": [{
-                    "inlineData": {
-                        "mimeType": "audio/wav",
-                        "data": audio_base64
-                    }
-                }]
+                "parts": [audio_part]
             }],
             "systemInstruction": {
-                "parts": [{"text": "Output ONLY the exact transcription of the audio. Do not add any other text, commentary, or formatting."}]
+                "parts": [{"text": instruction}]
             }
         })
     }
 
-    /// Send a non-streaming request and return the response text
-    pub async fn send_request(
+    /// Build the request body for speech synthesis with a prebuilt voice
+    pub fn build_speech_request(&self, text: &str, voice: &str) -> serde_json::Value {
+        serde_json::json!({
+            "contents": [{
+                "role": "user",
+                "parts": [{"text": text}]
+            }],
+            "generationConfig": {
+                "responseModalities": ["AUDIO"],
+                "speechConfig": {
+                    "voiceConfig": {
+                        "prebuiltVoiceConfig": {"voiceName": voice}
+                    }
+                }
+            }
+        })
+    }
+
+    /// Send a speech synthesis request and return the (mime type, base64 data)
+    /// of the first audio part. Gemini returns raw PCM (audio/L16).
+    pub async fn send_speech_request(
         &self,
         model: &str,
         body: &serde_json::Value,
-    ) -> Result<String, String> {
-        let url = self.build_url(model);
+    ) -> Result<(String, String), SidestreamError> {
+        let json = self.post_json(model, body).await?;
+
+        let inline_data = json["candidates"]
+            .as_array()
+            .and_then(|c| c.first())
+            .and_then(|c| c["content"]["parts"].as_array())
+            .and_then(|parts| parts.iter().find(|p| p.get("inlineData").is_some()))
+            .map(|p| &p["inlineData"])
+            .ok_or("Speech response contained no audio")?;
+
+        let mime_type = inline_data["mimeType"].as_str().unwrap_or("audio/L16").to_string();
+        let data = inline_data["data"]
+            .as_str()
+            .ok_or("Speech response audio had no data")?
+            .to_string();
+
+        Ok((mime_type, data))
+    }
+
+    /// Upload a file with the Files API (for media too large to send inline)
+    /// and wait until it is ready to use. Returns the file URI.
+    pub async fn upload_file(
+        &self,
+        bytes: Vec<u8>,
+        mime_type: &str,
+        display_name: &str,
+    ) -> Result<String, SidestreamError> {
+        audit_log::record_bytes("google", "", "files", &bytes);
+
+        // Resumable upload: the start request returns the URL to send bytes to
+        let start = self
+            .client
+            .post(format!("{}?key={}", GEMINI_UPLOAD_URL, self.api_key))
+            .header("X-Goog-Upload-Protocol", "resumable")
+            .header("X-Goog-Upload-Command", "start")
+            .header("X-Goog-Upload-Header-Content-Length", bytes.len().to_string())
+            .header("X-Goog-Upload-Header-Content-Type", mime_type)
+            .json(&serde_json::json!({"file": {"display_name": display_name}}))
+            .send()
+            .await?;
+        if !start.status().is_success() {
+            return Err(SidestreamError::from_response(start).await);
+        }
+        let upload_url = start
+            .headers()
+            .get("x-goog-upload-url")
+            .and_then(|v| v.to_str().ok())
+            .ok_or("Upload response had no upload URL")?
+            .to_string();
 
         let response = self
             .client
-            .post(&url)
+            .post(&upload_url)
+            .header("X-Goog-Upload-Offset", "0")
+            .header("X-Goog-Upload-Command", "upload, finalize")
+            .body(bytes)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(SidestreamError::from_response(response).await);
+        }
+        let mut file: serde_json::Value = response
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| format!("Failed to parse upload response: {}", e))?["file"]
+            .take();
+
+        // Media is processed after upload; it can't be used until ACTIVE
+        for _ in 0..60 {
+            match file["state"].as_str() {
+                Some("PROCESSING") => {
+                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                    let name = file["name"].as_str().ok_or("Uploaded file had no name")?;
+                    let path = format!("/{}", name);
+                    file = provider_endpoints::request(
+                        &self.client,
+                        reqwest::Method::GET,
+                        "google",
+                        &path,
+                        &self.api_key,
+                    )
+                    .send()
+                    .await?
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse file status: {}", e))?;
+                }
+                Some("FAILED") => return Err("Gemini failed to process the uploaded file".into()),
+                _ => break,
+            }
+        }
+
+        file["uri"]
+            .as_str()
+            .map(|uri| uri.to_string())
+            .ok_or_else(|| "Uploaded file had no URI".into())
+    }
+
+    /// POST a non-streaming generateContent request and return the parsed JSON
+    async fn post_json(
+        &self,
+        model: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, SidestreamError> {
+        request_validation::validate("google", body)?;
+        audit_log::record_json("google", model, "generateContent", body);
+
+        let response = self
+            .model_request(model, "generateContent")
             .header("Content-Type", "application/json")
             .json(body)
             .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(SidestreamError::from_response(response).await);
+        }
+
+        response
+            .json()
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| format!("Failed to parse response: {}", e).into())
+    }
+
+    /// Generate images, with Imagen's predict endpoint for `imagen-*` models
+    /// and generateContent (image output) for Gemini image models. Returns
+    /// (mime type, base64 data) per image.
+    pub async fn generate_image(&self, model: &str, prompt: &str) -> Result<Vec<(String, String)>, SidestreamError> {
+        if !model.starts_with("imagen") {
+            let body = serde_json::json!({
+                "contents": [{
+                    "role": "user",
+                    "parts": [{"text": prompt}]
+                }],
+                "generationConfig": {
+                    "responseModalities": ["TEXT", "IMAGE"]
+                }
+            });
+            let json = self.post_json(model, &body).await?;
+            return Ok(json["candidates"]
+                .as_array()
+                .and_then(|c| c.first())
+                .and_then(|c| c["content"]["parts"].as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|part| {
+                    let inline_data = part.get("inlineData")?;
+                    Some((
+                        inline_data["mimeType"].as_str().unwrap_or("image/png").to_string(),
+                        inline_data["data"].as_str()?.to_string(),
+                    ))
+                })
+                .collect());
+        }
+
+        let body = serde_json::json!({
+            "instances": [{"prompt": prompt}],
+            "parameters": {"sampleCount": 1}
+        });
+        audit_log::record_json("google", model, "predict", &body);
+        let response = self
+            .model_request(model, "predict")
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(format!("API error ({}): {}", status, error_text));
+            return Err(SidestreamError::from_response(response).await);
         }
 
         let json: serde_json::Value = response
             .json()
             .await
             .map_err(|e| format!("Failed to parse response: {}", e))?;
+        Ok(json["predictions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|prediction| {
+                Some((
+                    prediction["mimeType"].as_str().unwrap_or("image/png").to_string(),
+                    prediction["bytesBase64Encoded"].as_str()?.to_string(),
+                ))
+            })
+            .collect())
+    }
+
+    /// Send a non-streaming request and return the response text
+    pub async fn send_request(
+        &self,
+        model: &str,
+        body: &serde_json::Value,
+    ) -> Result<String, SidestreamError> {
+        let json = self.post_json(model, body).await?;
 
         // Extract text from the response
         // Response format: { "candidates": [{ "content": { "parts": [{ "text": "..." }] } }] }
@@ -620,7 +752,10 @@ impl GeminiClient {
 pub fn parse_sse_event(data: &str) -> Vec<GeminiStreamEvent> {
     let parsed: serde_json::Value = match serde_json::from_str(data) {
         Ok(v) => v,
-        Err(_) => return vec![GeminiStreamEvent::Unknown],
+        Err(_) => {
+            record_parse_warning("google", "invalid JSON");
+            return vec![GeminiStreamEvent::Unknown];
+        }
     };
 
     let mut events = Vec::new();
@@ -809,18 +944,35 @@ pub fn parse_sse_event(data: &str) -> Vec<GeminiStreamEvent> {
                 .get("finishReason")
                 .and_then(|v| v.as_str())
             {
+                // usageMetadata is cumulative, so the last chunk carries the totals
+                let usage = &parsed["usageMetadata"];
+                let output_tokens = usage["candidatesTokenCount"]
+                    .as_u64()
+                    .map(|tokens| tokens + usage["thoughtsTokenCount"].as_u64().unwrap_or(0));
                 events.push(GeminiStreamEvent::ResponseComplete {
                     finish_reason: finish_reason.to_string(),
+                    output_tokens,
                 });
             }
         }
     }
 
     if events.is_empty() {
-        vec![GeminiStreamEvent::Unknown]
-    } else {
-        events
+        // Chunks carrying only usage metadata are expected between parts
+        if parsed.get("usageMetadata").is_none() {
+            record_parse_warning("google", "chunk with no recognized content");
+        }
+        events.push(GeminiStreamEvent::Unknown);
     }
+
+    // Every chunk repeats the response id and model version, so they are
+    // added after the check above rather than counting as content
+    let response_id = parsed["responseId"].as_str().map(|s| s.to_string());
+    let model_version = parsed["modelVersion"].as_str().map(|s| s.to_string());
+    if response_id.is_some() || model_version.is_some() {
+        events.push(GeminiStreamEvent::ResponseInfo { response_id, model_version });
+    }
+    events
 }
 
 /// Map MIME type to file extension
@@ -1014,9 +1166,13 @@ fn byte_offset_to_char_offset(text: &str, byte_offset: usize) -> usize {
 }
 
 /// Convert a thinking level string from the frontend to a [`ThinkingLevel`].
-/// Frontend sends "minimal", "low", "medium", or "high" (Gemini 3.x). Anything
-/// else ("off"/"none"/unknown) disables thinking.
+/// Frontend sends "minimal", "low", "medium", or "high" (Gemini 3.x), or a
+/// numeric token budget such as "8192" (clamped to Gemini's range; "-1" is
+/// dynamic). Anything else ("off"/"none"/unknown) disables thinking.
 pub fn string_to_thinking_config(level: &str, model: &str) -> Option<ThinkingLevel> {
+    if let Ok(budget) = level.trim().parse::<i32>() {
+        return Some(ThinkingLevel::Budget(budget.clamp(-1, MAX_THINKING_BUDGET)));
+    }
     match level.to_lowercase().as_str() {
         "minimal" => Some(ThinkingLevel::Minimal),
         "low" => Some(ThinkingLevel::Low),
@@ -1040,8 +1196,8 @@ pub fn is_gemini_3_flash_model(model: &str) -> bool {
 
 /// Check if model supports thinking/reasoning
 pub fn supports_thinking(model: &str) -> bool {
-    // Gemini 3.x models support thinking
-    model.contains("gemini-3") || model.contains("gemini3")
+    // Gemini 3.x models support thinking levels, 2.5 models thinking budgets
+    model.contains("gemini-3") || model.contains("gemini3") || model.contains("gemini-2.5")
 }
 
 #[cfg(test)]
@@ -1105,11 +1261,36 @@ mod filename_tests {
         let events = super::parse_sse_event(data);
         let captured = events.iter().any(|e| matches!(
             e,
-            super::GeminiStreamEvent::ResponseComplete { finish_reason } if finish_reason == "MAX_TOKENS"
+            super::GeminiStreamEvent::ResponseComplete { finish_reason, .. } if finish_reason == "MAX_TOKENS"
         ));
         assert!(captured, "expected ResponseComplete {{ MAX_TOKENS }}, got {:?}", events);
     }
 
+    #[test]
+    fn parser_reads_output_tokens_from_final_usage() {
+        let data = r#"{"candidates":[{"content":{"parts":[{"text":"done"}]},"finishReason":"STOP"}],"usageMetadata":{"promptTokenCount":12,"candidatesTokenCount":40,"thoughtsTokenCount":25,"totalTokenCount":77}}"#;
+        let events = super::parse_sse_event(data);
+        let output_tokens = events.iter().find_map(|e| match e {
+            super::GeminiStreamEvent::ResponseComplete { output_tokens, .. } => Some(*output_tokens),
+            _ => None,
+        });
+        assert_eq!(output_tokens, Some(Some(65)));
+    }
+
+    #[test]
+    fn response_info_alone_is_not_content() {
+        let data = r#"{"responseId":"r1","modelVersion":"gemini-2.5-pro"}"#;
+        let events = super::parse_sse_event(data);
+        assert!(
+            matches!(
+                events.as_slice(),
+                [super::GeminiStreamEvent::Unknown, super::GeminiStreamEvent::ResponseInfo { .. }]
+            ),
+            "got {:?}",
+            events
+        );
+    }
+
     #[test]
     fn saved_filenames_basic_savefig() {
         assert_eq!(
@@ -1158,3 +1339,21 @@ mod filename_tests {
         assert!(refs.contains("chart.png"));
     }
 }
+
+#[cfg(test)]
+mod thinking_budget_tests {
+    use super::*;
+
+    #[test]
+    fn numeric_thinking_levels_become_clamped_budgets() {
+        let model = "gemini-2.5-flash";
+        assert_eq!(string_to_thinking_config("8192", model), Some(ThinkingLevel::Budget(8192)));
+        assert_eq!(string_to_thinking_config("-1", model), Some(ThinkingLevel::Budget(-1)));
+        assert_eq!(string_to_thinking_config("100000", model), Some(ThinkingLevel::Budget(MAX_THINKING_BUDGET)));
+        assert_eq!(string_to_thinking_config("off", model), None);
+        assert_eq!(
+            ThinkingLevel::Budget(2048).as_config(),
+            serde_json::json!({"thinkingBudget": 2048})
+        );
+    }
+}
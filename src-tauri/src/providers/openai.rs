@@ -1,8 +1,14 @@
 use serde::{Deserialize, Serialize};
 
+use crate::audit_log;
+use crate::discovery_context::DiscoveryImage;
+use crate::error::SidestreamError;
 use crate::llm::GeneratedFile;
-
-const OPENAI_API_URL: &str = "https://api.openai.com/v1/responses";
+use crate::message_format::{openai as openai_format, Message};
+use crate::provider_endpoints;
+use crate::proxy;
+use crate::request_validation;
+use crate::sse::record_parse_warning;
 
 /// Appended to the system prompt whenever code_interpreter is enabled.
 ///
@@ -137,10 +143,30 @@ pub struct DiscoveryRequestConfig {
     pub model: String,
     pub system_prompt: String,
     pub conversation: String,
+    /// Images from the conversation, sent after its text
+    pub images: Vec<DiscoveryImage>,
     pub prompt_cache_key: Option<String>,
     pub reasoning_level: Option<String>,
+    /// JSON Schema the reply text must follow
+    pub output_schema: serde_json::Value,
 }
 
+/// Lifecycle events that carry nothing the app needs; anything else that
+/// falls through `parse_sse_event` is counted as a parse warning
+const IGNORED_EVENT_TYPES: &[&str] = &[
+    "response.in_progress",
+    "response.content_part.added",
+    "response.output_text.annotation.added",
+    "response.reasoning_summary_part.added",
+    "response.reasoning_summary_part.done",
+    "response.reasoning_summary_text.done",
+    "response.web_search_call.in_progress",
+    "response.web_search_call.searching",
+    "response.web_search_call.completed",
+    "response.code_interpreter_call.in_progress",
+    "response.code_interpreter_call.completed",
+];
+
 /// Parsed SSE events from OpenAI's streaming Responses API
 #[derive(Debug, Clone)]
 pub enum OpenAIStreamEvent {
@@ -182,6 +208,11 @@ pub enum OpenAIStreamEvent {
         call_id: String,
         code: String,
     },
+    /// Code interpreter began running the code
+    CodeInterpreterInterpreting {
+        #[allow(dead_code)]
+        call_id: String,
+    },
     /// Code interpreter execution result
     CodeInterpreterResult {
         #[allow(dead_code)]
@@ -191,8 +222,13 @@ pub enum OpenAIStreamEvent {
         stderr: Option<String>,
         files: Vec<ContainerFileCitation>,
     },
-    /// Response completed
-    ResponseCompleted,
+    /// Response created: its id and the exact model snapshot serving it
+    ResponseCreated {
+        response_id: Option<String>,
+        model: Option<String>,
+    },
+    /// Response completed, with its output token count
+    ResponseCompleted { output_tokens: Option<u64> },
     /// Stream finished
     Done,
     /// Error occurred
@@ -218,11 +254,10 @@ pub struct UrlCitation {
     pub end_index: Option<u32>,
 }
 
-
 impl OpenAIClient {
     pub fn new(api_key: String) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client: proxy::client(),
             api_key,
         }
     }
@@ -260,78 +295,7 @@ impl OpenAIClient {
 
         // Convert each message to OpenAI format
         for msg in &config.messages {
-            let role = msg["role"].as_str().unwrap_or("user");
-            let content = &msg["content"];
-
-            // Handle content - can be string or array of content blocks
-            let formatted_content = if let Some(text) = content.as_str() {
-                // Simple string content
-                serde_json::json!(text)
-            } else if let Some(arr) = content.as_array() {
-                // Array of content blocks - convert to OpenAI format
-                let converted: Vec<serde_json::Value> = arr
-                    .iter()
-                    .filter_map(|block| {
-                        let block_type = block["type"].as_str()?;
-                        match block_type {
-                            "text" => {
-                                let text = block["text"].as_str()?;
-                                Some(serde_json::json!({
-                                    "type": "input_text",
-                                    "text": text
-                                }))
-                            }
-                            "image" => {
-                                // Convert Anthropic image format to OpenAI
-                                let source = &block["source"];
-                                let media_type = source["media_type"].as_str()?;
-                                let data = source["data"].as_str()?;
-                                Some(serde_json::json!({
-                                    "type": "input_image",
-                                    "image_url": format!("data:{};base64,{}", media_type, data)
-                                }))
-                            }
-                            "document" => {
-                                // OpenAI handles PDFs as base64 with filename
-                                let source = &block["source"];
-                                let data = source["data"].as_str()?;
-                                // Get filename if available, default to "document.pdf"
-                                let filename = block["filename"].as_str()
-                                    .or_else(|| source["filename"].as_str())
-                                    .unwrap_or("document.pdf");
-                                Some(serde_json::json!({
-                                    "type": "input_file",
-                                    "filename": filename,
-                                    "file_data": format!("data:application/pdf;base64,{}", data)
-                                }))
-                            }
-                            "file" => {
-                                // Generic file: send as input_file with original MIME type
-                                let source = &block["source"];
-                                let media_type = source["media_type"].as_str()?;
-                                let data = source["data"].as_str()?;
-                                let filename = block["filename"].as_str().unwrap_or("file");
-                                Some(serde_json::json!({
-                                    "type": "input_file",
-                                    "filename": filename,
-                                    "file_data": format!("data:{};base64,{}", media_type, data)
-                                }))
-                            }
-                            _ => None,
-                        }
-                    })
-                    .collect();
-                serde_json::json!(converted)
-            } else {
-                // Fallback to empty string
-                serde_json::json!("")
-            };
-
-            input_items.push(serde_json::json!({
-                "type": "message",
-                "role": role,
-                "content": formatted_content
-            }));
+            input_items.push(openai_format::message(&Message::from_stored(msg)));
         }
 
         let mut body = serde_json::json!({
@@ -390,6 +354,19 @@ impl OpenAIClient {
 
     /// Build the request body for a discovery request
     pub fn build_discovery_request(&self, config: &DiscoveryRequestConfig) -> serde_json::Value {
+        let user_content = if config.images.is_empty() {
+            serde_json::json!(config.conversation)
+        } else {
+            let mut parts = vec![serde_json::json!({"type": "input_text", "text": config.conversation})];
+            for image in &config.images {
+                parts.push(serde_json::json!({"type": "input_text", "text": image.label}));
+                parts.push(serde_json::json!({
+                    "type": "input_image",
+                    "image_url": format!("data:{};base64,{}", image.mime_type, image.data)
+                }));
+            }
+            serde_json::json!(parts)
+        };
         let input_items = vec![
             serde_json::json!({
                 "type": "message",
@@ -399,7 +376,7 @@ impl OpenAIClient {
             serde_json::json!({
                 "type": "message",
                 "role": "user",
-                "content": config.conversation
+                "content": user_content
             }),
         ];
 
@@ -423,7 +400,15 @@ impl OpenAIClient {
             },
             "tools": [{
                 "type": "web_search"
-            }]
+            }],
+            "text": {
+                "format": {
+                    "type": "json_schema",
+                    "name": "discovery_items",
+                    "schema": config.output_schema,
+                    "strict": true
+                }
+            }
         });
 
         // Add prompt cache key if provided
@@ -438,25 +423,107 @@ impl OpenAIClient {
     pub async fn send_streaming_request(
         &self,
         body: &serde_json::Value,
-    ) -> Result<reqwest::Response, String> {
-        let response = self
-            .client
-            .post(OPENAI_API_URL)
-            .header("Authorization", format!("Bearer {}", self.api_key))
+    ) -> Result<reqwest::Response, SidestreamError> {
+        request_validation::validate("openai", body)?;
+        audit_log::record_json("openai", body["model"].as_str().unwrap_or_default(), "responses", body);
+        let response = provider_endpoints::request(&self.client, reqwest::Method::POST, "openai", "/responses", &self.api_key)
             .header("Content-Type", "application/json")
             .json(body)
             .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(format!("API error ({}): {}", status, error_text));
+            return Err(SidestreamError::from_response(response).await);
         }
 
         Ok(response)
     }
+
+    /// Send a non-streaming request and return the parsed response JSON
+    pub async fn send_request(&self, body: &serde_json::Value) -> Result<serde_json::Value, SidestreamError> {
+        request_validation::validate("openai", body)?;
+        audit_log::record_json("openai", body["model"].as_str().unwrap_or_default(), "responses", body);
+        let response = provider_endpoints::request(&self.client, reqwest::Method::POST, "openai", "/responses", &self.api_key)
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(SidestreamError::from_response(response).await);
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e).into())
+    }
+
+    /// Generate images with the Images API. Returns (mime type, base64 data)
+    /// per image.
+    pub async fn generate_image(
+        &self,
+        model: &str,
+        prompt: &str,
+        size: Option<&str>,
+    ) -> Result<Vec<(String, String)>, SidestreamError> {
+        let mut body = serde_json::json!({
+            "model": model,
+            "prompt": prompt,
+            "n": 1,
+        });
+        if let Some(size) = size {
+            body["size"] = serde_json::json!(size);
+        }
+        // gpt-image models always return base64; DALL·E returns URLs unless asked
+        if model.starts_with("dall-e") {
+            body["response_format"] = serde_json::json!("b64_json");
+        }
+        audit_log::record_json("openai", model, "images", &body);
+
+        let response =
+            provider_endpoints::request(&self.client, reqwest::Method::POST, "openai", "/images/generations", &self.api_key)
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(SidestreamError::from_response(response).await);
+        }
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let mime_type = format!("image/{}", json["output_format"].as_str().unwrap_or("png"));
+        Ok(json["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|image| image["b64_json"].as_str())
+            .map(|data| (mime_type.clone(), data.to_string()))
+            .collect())
+    }
+}
+
+/// Concatenate the output_text parts of a non-streaming Responses API response.
+/// (`output_text` on the response object is an SDK convenience, not part of the JSON.)
+pub fn extract_output_text(response: &serde_json::Value) -> String {
+    response["output"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter(|item| item["type"].as_str() == Some("message"))
+                .filter_map(|item| item["content"].as_array())
+                .flatten()
+                .filter(|part| part["type"].as_str() == Some("output_text"))
+                .filter_map(|part| part["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("")
+        })
+        .unwrap_or_default()
 }
 
 /// Parse a single SSE data payload into an OpenAIStreamEvent
@@ -467,7 +534,10 @@ pub fn parse_sse_event(data: &str) -> OpenAIStreamEvent {
 
     let parsed: serde_json::Value = match serde_json::from_str(data) {
         Ok(v) => v,
-        Err(_) => return OpenAIStreamEvent::Unknown,
+        Err(_) => {
+            record_parse_warning("openai", "invalid JSON");
+            return OpenAIStreamEvent::Unknown;
+        }
     };
 
     let event_type = parsed["type"].as_str().unwrap_or("");
@@ -564,6 +634,12 @@ pub fn parse_sse_event(data: &str) -> OpenAIStreamEvent {
             OpenAIStreamEvent::CodeInterpreterCodeDone { call_id, code }
         }
 
+        // Code interpreter is executing the code (progress indicator for long runs)
+        "response.code_interpreter_call.interpreting" => {
+            let call_id = parsed["item_id"].as_str().unwrap_or("").to_string();
+            OpenAIStreamEvent::CodeInterpreterInterpreting { call_id }
+        }
+
         // Output item done — for code interpreter (execution results/files) and
         // for web_search_call (where the `action` object is finally populated).
         "response.output_item.done" => {
@@ -583,7 +659,17 @@ pub fn parse_sse_event(data: &str) -> OpenAIStreamEvent {
 
                 // Parse output results
                 let output = &parsed["item"]["output"];
-                let stdout = output["logs"].as_str().map(|s| s.to_string());
+                // The Responses API reports logs as `{"type": "logs"}` entries of the
+                // item's `outputs` array; interpreter output isn't streamed before this.
+                let stdout = output["logs"].as_str().map(|s| s.to_string()).or_else(|| {
+                    let logs: Vec<&str> = parsed["item"]["outputs"]
+                        .as_array()?
+                        .iter()
+                        .filter(|o| o["type"].as_str() == Some("logs"))
+                        .filter_map(|o| o["logs"].as_str())
+                        .collect();
+                    (!logs.is_empty()).then(|| logs.concat())
+                });
                 let stderr = output["error"].as_str().map(|s| s.to_string());
 
                 // Parse file citations from annotations in output
@@ -611,8 +697,15 @@ pub fn parse_sse_event(data: &str) -> OpenAIStreamEvent {
             }
         }
 
+        "response.created" => OpenAIStreamEvent::ResponseCreated {
+            response_id: parsed["response"]["id"].as_str().map(|s| s.to_string()),
+            model: parsed["response"]["model"].as_str().map(|s| s.to_string()),
+        },
+
         // Response completed
-        "response.completed" => OpenAIStreamEvent::ResponseCompleted,
+        "response.completed" => OpenAIStreamEvent::ResponseCompleted {
+            output_tokens: parsed["response"]["usage"]["output_tokens"].as_u64(),
+        },
 
         // Error event
         "error" => {
@@ -623,7 +716,12 @@ pub fn parse_sse_event(data: &str) -> OpenAIStreamEvent {
             OpenAIStreamEvent::Error { message }
         }
 
-        _ => OpenAIStreamEvent::Unknown,
+        other => {
+            if !IGNORED_EVENT_TYPES.contains(&other) {
+                record_parse_warning("openai", format!("unhandled event type: {}", other));
+            }
+            OpenAIStreamEvent::Unknown
+        }
     }
 }
 
@@ -844,23 +942,16 @@ pub fn supports_reasoning(model: &str) -> bool {
 }
 
 /// Fetch file content from OpenAI Containers API and return as base64
-pub async fn fetch_file_content_base64(api_key: &str, container_id: &str, file_id: &str) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    let url = format!(
-        "https://api.openai.com/v1/containers/{}/files/{}/content",
-        container_id, file_id
-    );
-
-    let response = client
-        .get(&url)
-        .header("Authorization", format!("Bearer {}", api_key))
+pub async fn fetch_file_content_base64(api_key: &str, container_id: &str, file_id: &str) -> Result<String, SidestreamError> {
+    let client = proxy::client();
+    let path = format!("/containers/{}/files/{}/content", container_id, file_id);
+
+    let response = provider_endpoints::request(&client, reqwest::Method::GET, "openai", &path, api_key)
         .send()
-        .await
-        .map_err(|e| format!("Failed to fetch file content: {}", e))?;
+        .await?;
 
     if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("File content API error: {}", error_text));
+        return Err(SidestreamError::from_response(response).await);
     }
 
     let bytes = response
@@ -1067,5 +1158,41 @@ mod tests {
         assert_eq!(kept.len(), 1);
         assert_eq!(kept[0].file_id, "cfile_saved");
     }
-}
 
+    /// Events of a code interpreter call as the Responses API streams them
+    #[test]
+    fn parses_code_interpreter_event_sequence() {
+        let fixture = [
+            r#"{"type":"response.output_item.added","output_index":0,"item":{"id":"ci_1","type":"code_interpreter_call","status":"in_progress","code":"","container_id":"cntr_1","outputs":null}}"#,
+            r#"{"type":"response.code_interpreter_call.in_progress","output_index":0,"item_id":"ci_1"}"#,
+            r#"{"type":"response.code_interpreter_call_code.delta","output_index":0,"item_id":"ci_1","delta":"print(1"}"#,
+            r#"{"type":"response.code_interpreter_call_code.delta","output_index":0,"item_id":"ci_1","delta":" + 1)"}"#,
+            r#"{"type":"response.code_interpreter_call_code.done","output_index":0,"item_id":"ci_1","code":"print(1 + 1)"}"#,
+            r#"{"type":"response.code_interpreter_call.interpreting","output_index":0,"item_id":"ci_1"}"#,
+            r#"{"type":"response.code_interpreter_call.completed","output_index":0,"item_id":"ci_1"}"#,
+            r#"{"type":"response.output_item.done","output_index":0,"item":{"id":"ci_1","type":"code_interpreter_call","status":"completed","code":"print(1 + 1)","container_id":"cntr_1","outputs":[{"type":"logs","logs":"2\n"}]}}"#,
+        ];
+        let events: Vec<OpenAIStreamEvent> = fixture.iter().map(|data| parse_sse_event(data)).collect();
+
+        assert!(matches!(&events[0], OpenAIStreamEvent::CodeInterpreterStarted { call_id } if call_id == "ci_1"));
+        assert!(matches!(events[1], OpenAIStreamEvent::Unknown));
+        let code: String = events[2..4]
+            .iter()
+            .map(|event| match event {
+                OpenAIStreamEvent::CodeInterpreterCodeDelta { code, .. } => code.as_str(),
+                other => panic!("expected a code delta, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(code, "print(1 + 1)");
+        assert!(matches!(&events[4], OpenAIStreamEvent::CodeInterpreterCodeDone { code, .. } if code == "print(1 + 1)"));
+        assert!(matches!(&events[5], OpenAIStreamEvent::CodeInterpreterInterpreting { call_id } if call_id == "ci_1"));
+        assert!(matches!(events[6], OpenAIStreamEvent::Unknown));
+        match &events[7] {
+            OpenAIStreamEvent::CodeInterpreterResult { container_id, stdout, .. } => {
+                assert_eq!(container_id.as_deref(), Some("cntr_1"));
+                assert_eq!(stdout.as_deref(), Some("2\n"));
+            }
+            other => panic!("expected a code interpreter result, got {:?}", other),
+        }
+    }
+}
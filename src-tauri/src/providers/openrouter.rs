@@ -0,0 +1,291 @@
+//! OpenRouter API client
+//!
+//! OpenRouter serves many vendors' models behind one OpenAI-style Chat
+//! Completions API, so new models can be tried as soon as they are listed.
+//! Models are addressed in the app as `openrouter/<vendor>/<model>`; the
+//! prefix routes the request here and is stripped before it is sent.
+
+use serde::{Deserialize, Serialize};
+
+use crate::audit_log;
+use crate::error::SidestreamError;
+use crate::message_format::{openrouter as openrouter_format, Message};
+use crate::provider_endpoints;
+use crate::proxy;
+use crate::sse::record_parse_warning;
+
+/// Prefix of app model ids served through OpenRouter
+pub const OPENROUTER_MODEL_PREFIX: &str = "openrouter/";
+
+/// The model id OpenRouter knows, without the app's routing prefix
+pub fn api_model_id(model: &str) -> &str {
+    model.strip_prefix(OPENROUTER_MODEL_PREFIX).unwrap_or(model)
+}
+
+/// OpenRouter API client
+pub struct OpenRouterClient {
+    client: reqwest::Client,
+    api_key: String,
+}
+
+/// Configuration for a chat request
+pub struct ChatRequestConfig {
+    /// App model id (with or without the `openrouter/` prefix)
+    pub model: String,
+    pub messages: Vec<serde_json::Value>,
+    pub system_prompt: Option<String>,
+    pub web_search_enabled: bool,
+}
+
+/// A model from OpenRouter's catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenRouterModel {
+    /// App model id, including the `openrouter/` prefix
+    pub id: String,
+    pub name: String,
+    pub context_length: Option<u64>,
+    /// USD per million input tokens
+    pub prompt_price: Option<f64>,
+    /// USD per million output tokens
+    pub completion_price: Option<f64>,
+}
+
+/// Parsed SSE events from OpenRouter's streaming API
+#[derive(Debug, Clone)]
+pub enum OpenRouterStreamEvent {
+    /// Generation id and the model that actually served the request
+    /// (repeated on every chunk)
+    ResponseInfo { id: Option<String>, model: Option<String> },
+    /// Text delta - incremental text content
+    TextDelta { text: String },
+    /// Reasoning delta, for models that expose their reasoning
+    ReasoningDelta { text: String },
+    /// URL citations from the web plugin
+    Citations { citations: Vec<UrlCitation> },
+    /// A choice finished (e.g. "stop", "length", "content_filter")
+    Finished { finish_reason: String },
+    /// End of the stream
+    Done,
+    /// Error occurred mid-stream
+    Error { message: String },
+    /// Unknown/unhandled event
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct UrlCitation {
+    pub url: String,
+    pub title: String,
+    pub cited_text: String,
+}
+
+/// Parse a price per token (OpenRouter sends decimal strings) into USD per
+/// million tokens
+fn price_per_million(value: &serde_json::Value) -> Option<f64> {
+    let per_token: f64 = value.as_str()?.parse().ok()?;
+    (per_token >= 0.0).then_some(per_token * 1_000_000.0)
+}
+
+impl OpenRouterClient {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: proxy::client(),
+            api_key,
+        }
+    }
+
+    /// Build the request body for a streaming chat completion
+    pub fn build_chat_request(&self, config: &ChatRequestConfig) -> serde_json::Value {
+        let mut messages = Vec::new();
+        if let Some(system) = &config.system_prompt {
+            messages.push(serde_json::json!({"role": "system", "content": system}));
+        }
+        for msg in &config.messages {
+            messages.push(openrouter_format::message(&Message::from_stored(msg)));
+        }
+
+        let mut body = serde_json::json!({
+            "model": api_model_id(&config.model),
+            "messages": messages,
+            "stream": true
+        });
+        if config.web_search_enabled {
+            body["plugins"] = serde_json::json!([{"id": "web"}]);
+        }
+        body
+    }
+
+    async fn post(&self, body: &serde_json::Value) -> Result<reqwest::Response, SidestreamError> {
+        audit_log::record_json("openrouter", body["model"].as_str().unwrap_or_default(), "chat/completions", body);
+        let response =
+            provider_endpoints::request(&self.client, reqwest::Method::POST, "openrouter", "/chat/completions", &self.api_key)
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(SidestreamError::from_response(response).await);
+        }
+        Ok(response)
+    }
+
+    /// Send a streaming request and return the response for SSE processing
+    pub async fn send_streaming_request(&self, body: &serde_json::Value) -> Result<reqwest::Response, SidestreamError> {
+        self.post(body).await
+    }
+
+    /// Send a non-streaming request and return the reply text
+    pub async fn send_request(&self, body: &serde_json::Value) -> Result<String, SidestreamError> {
+        let mut body = body.clone();
+        body["stream"] = serde_json::json!(false);
+        let response: serde_json::Value = self
+            .post(&body)
+            .await?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        Ok(response["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    /// Fetch the catalog of models OpenRouter currently serves
+    pub async fn list_models(&self) -> Result<Vec<OpenRouterModel>, SidestreamError> {
+        let response = provider_endpoints::request(&self.client, reqwest::Method::GET, "openrouter", "/models", &self.api_key)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(SidestreamError::from_response(response).await);
+        }
+        let catalog: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse model list: {}", e))?;
+        Ok(parse_model_list(&catalog))
+    }
+}
+
+fn parse_model_list(catalog: &serde_json::Value) -> Vec<OpenRouterModel> {
+    catalog["data"]
+        .as_array()
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|model| {
+                    let id = model["id"].as_str()?;
+                    Some(OpenRouterModel {
+                        id: format!("{}{}", OPENROUTER_MODEL_PREFIX, id),
+                        name: model["name"].as_str().unwrap_or(id).to_string(),
+                        context_length: model["context_length"].as_u64(),
+                        prompt_price: price_per_million(&model["pricing"]["prompt"]),
+                        completion_price: price_per_million(&model["pricing"]["completion"]),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parse an SSE data payload from the streaming API. One chunk can carry
+/// text, reasoning and a finish reason at once.
+pub fn parse_sse_event(data: &str) -> Vec<OpenRouterStreamEvent> {
+    if data == "[DONE]" {
+        return vec![OpenRouterStreamEvent::Done];
+    }
+    let parsed: serde_json::Value = match serde_json::from_str(data) {
+        Ok(v) => v,
+        Err(_) => {
+            record_parse_warning("openrouter", "invalid JSON");
+            return vec![OpenRouterStreamEvent::Unknown];
+        }
+    };
+
+    if let Some(error) = parsed.get("error") {
+        let message = error["message"].as_str().unwrap_or("Unknown OpenRouter error").to_string();
+        return vec![OpenRouterStreamEvent::Error { message }];
+    }
+
+    let Some(choice) = parsed["choices"].get(0) else {
+        // Usage-only chunks at the end of the stream have no choices
+        if parsed.get("usage").is_none() {
+            record_parse_warning("openrouter", "chunk without choices");
+        }
+        return vec![OpenRouterStreamEvent::Unknown];
+    };
+
+    let delta = &choice["delta"];
+    let mut events = Vec::new();
+    if let Some(reasoning) = delta["reasoning"].as_str().filter(|r| !r.is_empty()) {
+        events.push(OpenRouterStreamEvent::ReasoningDelta { text: reasoning.to_string() });
+    }
+    if let Some(text) = delta["content"].as_str().filter(|t| !t.is_empty()) {
+        events.push(OpenRouterStreamEvent::TextDelta { text: text.to_string() });
+    }
+    let citations: Vec<UrlCitation> = delta["annotations"]
+        .as_array()
+        .map(|annotations| {
+            annotations
+                .iter()
+                .filter(|a| a["type"].as_str() == Some("url_citation"))
+                .filter_map(|a| {
+                    let citation = &a["url_citation"];
+                    Some(UrlCitation {
+                        url: citation["url"].as_str()?.to_string(),
+                        title: citation["title"].as_str().unwrap_or_default().to_string(),
+                        cited_text: citation["content"].as_str().unwrap_or_default().to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if !citations.is_empty() {
+        events.push(OpenRouterStreamEvent::Citations { citations });
+    }
+    if let Some(reason) = choice["finish_reason"].as_str() {
+        events.push(OpenRouterStreamEvent::Finished { finish_reason: reason.to_string() });
+    }
+    if events.is_empty() {
+        events.push(OpenRouterStreamEvent::Unknown);
+    }
+
+    // Every chunk repeats the id and model, so they don't count as content
+    let id = parsed["id"].as_str().map(|s| s.to_string());
+    let model = parsed["model"].as_str().map(|s| s.to_string());
+    if id.is_some() || model.is_some() {
+        events.push(OpenRouterStreamEvent::ResponseInfo { id, model });
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stream_chunks_and_model_catalog() {
+        let events = parse_sse_event(
+            r#"{"choices":[{"delta":{"content":"Hi","reasoning":"hmm"},"finish_reason":"stop"}]}"#,
+        );
+        assert!(matches!(&events[0], OpenRouterStreamEvent::ReasoningDelta { text } if text == "hmm"));
+        assert!(matches!(&events[1], OpenRouterStreamEvent::TextDelta { text } if text == "Hi"));
+        assert!(matches!(&events[2], OpenRouterStreamEvent::Finished { finish_reason } if finish_reason == "stop"));
+        assert!(matches!(
+            parse_sse_event(r#"{"error":{"message":"Rate limited","code":429}}"#)[0],
+            OpenRouterStreamEvent::Error { .. }
+        ));
+
+        let models = parse_model_list(&serde_json::json!({"data": [{
+            "id": "meta-llama/llama-3.3-70b-instruct",
+            "name": "Llama 3.3 70B",
+            "context_length": 131072,
+            "pricing": {"prompt": "0.0000001", "completion": "-1"}
+        }]}));
+        assert_eq!(models[0].id, "openrouter/meta-llama/llama-3.3-70b-instruct");
+        assert_eq!(api_model_id(&models[0].id), "meta-llama/llama-3.3-70b-instruct");
+        assert!((models[0].prompt_price.unwrap() - 0.1).abs() < 1e-9);
+        assert_eq!(models[0].completion_price, None);
+    }
+}
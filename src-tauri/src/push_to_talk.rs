@@ -0,0 +1,172 @@
+//! Push-to-talk hotkey (desktop)
+//!
+//! A system-wide shortcut records from the microphone while it is held,
+//! from any app. Pressing it brings Sidestream forward, starts recording
+//! and emits `push-to-talk-pressed`; releasing it emits
+//! `push-to-talk-released` once the recording is running, and the frontend
+//! stops the recording and sends it the way its voice mode does
+//! (`stop_audio_recording` or `stop_audio_recording_raw`). If recording
+//! can't start (e.g. the in-app microphone button is already recording),
+//! `push-to-talk-failed` carries the reason.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+use tokio::sync::Notify;
+
+use crate::audio::{start_audio_recording, AudioState};
+use crate::settings;
+use crate::tray;
+
+const PUSH_TO_TALK_KEY: &str = "push_to_talk";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PushToTalkSettings {
+    pub enabled: bool,
+    /// Accelerator, e.g. `CommandOrControl+Shift+Space`
+    pub shortcut: String,
+}
+
+impl Default for PushToTalkSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            shortcut: "CommandOrControl+Shift+Space".to_string(),
+        }
+    }
+}
+
+/// Tauri-managed hotkey state
+pub struct PushToTalkState {
+    /// The registered shortcut, if push-to-talk is on
+    registered: Mutex<Option<Shortcut>>,
+    /// Signalled when the key is released; set while it is held
+    release: Mutex<Option<Arc<Notify>>>,
+}
+
+impl PushToTalkState {
+    pub fn new() -> Self {
+        Self {
+            registered: Mutex::new(None),
+            release: Mutex::new(None),
+        }
+    }
+}
+
+impl Default for PushToTalkState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_shortcut(shortcut: &str) -> Result<Shortcut, String> {
+    shortcut
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid shortcut \"{}\": {}", shortcut, e))
+}
+
+/// Register the shortcut `settings` asks for, replacing the current one. The
+/// new shortcut is registered first, so if it can't be (e.g. another app
+/// holds it) the current one stays in place.
+fn apply(app: &tauri::AppHandle, settings: &PushToTalkSettings) -> Result<(), String> {
+    let shortcut = parse_shortcut(&settings.shortcut)?;
+    let state = app.state::<PushToTalkState>();
+    let mut registered = state.registered.lock();
+    let wanted = settings.enabled.then_some(shortcut);
+    if *registered == wanted {
+        return Ok(());
+    }
+    if let Some(shortcut) = wanted {
+        app.global_shortcut()
+            .register(shortcut)
+            .map_err(|e| format!("Failed to register shortcut: {}", e))?;
+    }
+    if let Some(previous) = std::mem::replace(&mut *registered, wanted) {
+        if let Err(e) = app.global_shortcut().unregister(previous) {
+            eprintln!("Failed to unregister the previous push-to-talk shortcut: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Register the saved shortcut. Called once at startup.
+pub fn init(app: &tauri::AppHandle) {
+    let settings: PushToTalkSettings = settings::get_setting(app, PUSH_TO_TALK_KEY).unwrap_or_default();
+    if let Err(err) = apply(app, &settings) {
+        eprintln!("Failed to set up push-to-talk: {}", err);
+    }
+}
+
+/// Global shortcut handler: record while the key is held
+pub fn handle_shortcut(app: &tauri::AppHandle, _shortcut: &Shortcut, event: ShortcutEvent) {
+    let state = app.state::<PushToTalkState>();
+    match event.state {
+        ShortcutState::Pressed => {
+            let release = Arc::new(Notify::new());
+            {
+                let mut current = state.release.lock();
+                // Key repeat while held
+                if current.is_some() {
+                    return;
+                }
+                *current = Some(release.clone());
+            }
+            tray::show_main_window(app);
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(err) = start_audio_recording(app.clone(), app.state::<AudioState>(), None).await {
+                    app.state::<PushToTalkState>().release.lock().take();
+                    if let Err(err) = app.emit("push-to-talk-failed", err) {
+                        eprintln!("Failed to emit push-to-talk-failed event: {}", err);
+                    }
+                    return;
+                }
+                if let Err(err) = app.emit("push-to-talk-pressed", ()) {
+                    eprintln!("Failed to emit push-to-talk-pressed event: {}", err);
+                }
+                // Stored if the key was already released while recording started
+                release.notified().await;
+                if let Err(err) = app.emit("push-to-talk-released", ()) {
+                    eprintln!("Failed to emit push-to-talk-released event: {}", err);
+                }
+            });
+        }
+        ShortcutState::Released => {
+            if let Some(release) = state.release.lock().take() {
+                release.notify_one();
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_push_to_talk_settings(app: tauri::AppHandle) -> PushToTalkSettings {
+    settings::get_setting(&app, PUSH_TO_TALK_KEY).unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn set_push_to_talk_settings(app: tauri::AppHandle, push_to_talk: PushToTalkSettings) -> Result<(), String> {
+    apply(&app, &push_to_talk)?;
+    settings::set_setting(&app, PUSH_TO_TALK_KEY, &push_to_talk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_accelerators() {
+        let default = PushToTalkSettings::default();
+        assert!(parse_shortcut(&default.shortcut).is_ok());
+        assert!(parse_shortcut(" Alt+F9 ").is_ok());
+        assert!(parse_shortcut("Ctrl+Nope").unwrap_err().starts_with("Invalid shortcut \"Ctrl+Nope\""));
+    }
+}
@@ -0,0 +1,406 @@
+//! Read-aloud queue
+//!
+//! Reads a reply aloud while it is still streaming. The frontend passes each
+//! text delta to `read_aloud_push`; the text is cut into sentences as they
+//! complete, and a background task synthesizes and plays them one at a time
+//! in order (through `speech_playback`), so speech starts after the first
+//! sentence instead of the whole reply. `read_aloud_finish` flushes the last
+//! partial sentence once the stream ends. Playback can be paused, resumed,
+//! skipped a sentence at a time, or stopped. A push for a different turn
+//! replaces whatever was being read.
+//!
+//! Events: `read-aloud-sentence` when a sentence starts playing,
+//! `read-aloud-done` when the finished queue has played out, and
+//! `read-aloud-error` if synthesis or playback fails (the rest of the queue
+//! is dropped).
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::speech_playback::{play_segments, Playback, MAX_SEGMENT_CHARS};
+use crate::tts::{effective_readback_settings, ReadbackSettings};
+
+/// Sentences shorter than this are joined to the next, so each speech
+/// request has enough text to sound natural
+const MIN_SENTENCE_CHARS: usize = 20;
+
+/// How often an empty queue is checked for new sentences
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Closing quotes and brackets that may follow a sentence's punctuation
+const CLOSERS: [char; 6] = ['"', '\'', ')', ']', '\u{201D}', '\u{2019}'];
+
+// ============================================================================
+// Sentence Splitting
+// ============================================================================
+
+/// Byte index just past the first sentence end in `text` that leaves at
+/// least `MIN_SENTENCE_CHARS` before it: a line break, or `.`/`!`/`?`
+/// (plus any closing quotes) followed by whitespace. Punctuation at the very
+/// end doesn't count, since the next delta may continue it ("3." → "3.5").
+fn sentence_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let end = match c {
+            '\n' => index + 1,
+            '.' | '!' | '?' => {
+                while chars.peek().is_some_and(|&(_, next)| CLOSERS.contains(&next)) {
+                    chars.next();
+                }
+                match chars.peek() {
+                    Some(&(next_index, next)) if next.is_whitespace() => next_index,
+                    _ => continue,
+                }
+            }
+            _ => continue,
+        };
+        if text[..end].trim().chars().count() >= MIN_SENTENCE_CHARS {
+            return Some(end);
+        }
+    }
+    None
+}
+
+/// Cuts streamed text into sentences
+#[derive(Debug, Default)]
+struct SentenceSplitter {
+    pending: String,
+}
+
+impl SentenceSplitter {
+    /// Add a text delta and return the sentences it completes
+    fn push(&mut self, text: &str) -> Vec<String> {
+        self.pending.push_str(text);
+        let mut sentences = Vec::new();
+        while let Some(end) = sentence_end(&self.pending) {
+            sentences.push(self.take(end));
+        }
+        // A run with no sentence end is cut at a word once it is too long
+        // for one request
+        while let Some((limit, _)) = self.pending.char_indices().nth(MAX_SEGMENT_CHARS) {
+            let end = self.pending[..limit].rfind(' ').filter(|&i| i > 0).unwrap_or(limit);
+            sentences.push(self.take(end));
+        }
+        sentences.retain(|s| !s.is_empty());
+        sentences
+    }
+
+    /// The rest of the text, once the stream has ended
+    fn finish(&mut self) -> Option<String> {
+        let rest = self.take(self.pending.len());
+        (!rest.is_empty()).then_some(rest)
+    }
+
+    /// Remove and trim the first `end` bytes
+    fn take(&mut self, end: usize) -> String {
+        let rest = self.pending.split_off(end);
+        std::mem::replace(&mut self.pending, rest).trim().to_string()
+    }
+}
+
+// ============================================================================
+// State Types
+// ============================================================================
+
+#[derive(Default)]
+struct Queue {
+    /// The turn being read, if any
+    turn_id: Option<String>,
+    settings: ReadbackSettings,
+    splitter: SentenceSplitter,
+    sentences: VecDeque<String>,
+    /// Index of the next sentence to play
+    next_index: usize,
+    /// No more text will be pushed for this turn
+    input_finished: bool,
+    paused: bool,
+    /// The sentence playing now
+    current: Option<Arc<Mutex<Playback>>>,
+    worker_running: bool,
+    /// Bumped whenever the queue is reset, so a worker for an older queue
+    /// knows to exit
+    generation: u64,
+}
+
+impl Queue {
+    /// Stop playback and start over for `turn_id`
+    fn reset(&mut self, turn_id: Option<String>, settings: ReadbackSettings) {
+        if let Some(playback) = self.current.take() {
+            playback.lock().stop();
+        }
+        *self = Queue {
+            turn_id,
+            settings,
+            generation: self.generation + 1,
+            ..Queue::default()
+        };
+    }
+}
+
+/// Tauri-managed read-aloud state: the sentence queue for the turn being read
+pub struct ReadAloudState {
+    queue: Mutex<Queue>,
+}
+
+impl ReadAloudState {
+    pub fn new() -> Self {
+        Self {
+            queue: Mutex::new(Queue::default()),
+        }
+    }
+}
+
+impl Default for ReadAloudState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Payload for read-aloud-sentence event
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadAloudSentenceEvent {
+    pub turn_id: String,
+    pub index: usize,
+    pub text: String,
+}
+
+/// Payload for read-aloud-done event
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadAloudDoneEvent {
+    pub turn_id: String,
+}
+
+/// Payload for read-aloud-error event
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadAloudErrorEvent {
+    pub turn_id: String,
+    pub error: String,
+}
+
+// ============================================================================
+// Playback Worker
+// ============================================================================
+
+/// What the worker should do next
+enum Next {
+    Play {
+        turn_id: String,
+        index: usize,
+        text: String,
+        settings: ReadbackSettings,
+        playback: Arc<Mutex<Playback>>,
+    },
+    Wait,
+    Done(String),
+    Exit,
+}
+
+fn next_sentence(state: &ReadAloudState, generation: u64) -> Next {
+    let mut queue = state.queue.lock();
+    if queue.generation != generation {
+        return Next::Exit;
+    }
+    let turn_id = queue.turn_id.clone().unwrap_or_default();
+    match queue.sentences.pop_front() {
+        Some(text) => {
+            let mut playback = Playback::default();
+            playback.set_paused(queue.paused);
+            let playback = Arc::new(Mutex::new(playback));
+            queue.current = Some(playback.clone());
+            let index = queue.next_index;
+            queue.next_index += 1;
+            Next::Play {
+                turn_id,
+                index,
+                text,
+                settings: queue.settings.clone(),
+                playback,
+            }
+        }
+        None if queue.input_finished => {
+            queue.worker_running = false;
+            Next::Done(turn_id)
+        }
+        None => Next::Wait,
+    }
+}
+
+/// Play queued sentences in order until the queue is finished or reset
+async fn run_queue(app: tauri::AppHandle, window: tauri::Window, generation: u64) {
+    let state = app.state::<ReadAloudState>();
+    loop {
+        let (turn_id, index, text, settings, playback) = match next_sentence(&state, generation) {
+            Next::Play {
+                turn_id,
+                index,
+                text,
+                settings,
+                playback,
+            } => (turn_id, index, text, settings, playback),
+            Next::Wait => {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+            Next::Done(turn_id) => {
+                if let Err(err) = window.emit("read-aloud-done", ReadAloudDoneEvent { turn_id }) {
+                    eprintln!("Failed to emit read-aloud-done event: {}", err);
+                }
+                return;
+            }
+            Next::Exit => return,
+        };
+
+        if let Err(err) = window.emit(
+            "read-aloud-sentence",
+            ReadAloudSentenceEvent {
+                turn_id: turn_id.clone(),
+                index,
+                text: text.clone(),
+            },
+        ) {
+            eprintln!("Failed to emit read-aloud-sentence event: {}", err);
+        }
+
+        let result = play_segments(&app, &[text], &settings, &playback).await;
+
+        let mut queue = state.queue.lock();
+        if queue.generation != generation {
+            return;
+        }
+        queue.current = None;
+        if let Err(error) = result {
+            queue.reset(None, ReadbackSettings::default());
+            drop(queue);
+            if let Err(err) = window.emit("read-aloud-error", ReadAloudErrorEvent { turn_id, error }) {
+                eprintln!("Failed to emit read-aloud-error event: {}", err);
+            }
+            return;
+        }
+    }
+}
+
+fn start_worker(app: &tauri::AppHandle, window: &tauri::Window, queue: &mut Queue) {
+    if queue.worker_running {
+        return;
+    }
+    queue.worker_running = true;
+    let app = app.clone();
+    let window = window.clone();
+    let generation = queue.generation;
+    tauri::async_runtime::spawn(run_queue(app, window, generation));
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Queue a streamed text delta of `turn_id` to be read aloud. A different
+/// turn replaces the one being read.
+#[tauri::command]
+pub fn read_aloud_push(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: tauri::State<'_, ReadAloudState>,
+    turn_id: String,
+    text: String,
+    session_id: Option<String>,
+) {
+    let mut queue = state.queue.lock();
+    if queue.turn_id.as_deref() != Some(turn_id.as_str()) {
+        let settings = effective_readback_settings(&app, session_id.as_deref());
+        queue.reset(Some(turn_id), settings);
+    }
+    if queue.input_finished {
+        return;
+    }
+    let sentences = queue.splitter.push(&text);
+    queue.sentences.extend(sentences);
+    if !queue.sentences.is_empty() {
+        start_worker(&app, &window, &mut queue);
+    }
+}
+
+/// The stream for `turn_id` has ended: read the rest and finish
+#[tauri::command]
+pub fn read_aloud_finish(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: tauri::State<'_, ReadAloudState>,
+    turn_id: String,
+) {
+    let mut queue = state.queue.lock();
+    if queue.turn_id.as_deref() != Some(turn_id.as_str()) {
+        return;
+    }
+    if let Some(rest) = queue.splitter.finish() {
+        queue.sentences.push_back(rest);
+    }
+    queue.input_finished = true;
+    // Starting the worker even with nothing queued lets it emit done
+    start_worker(&app, &window, &mut queue);
+}
+
+#[tauri::command]
+pub fn pause_read_aloud(state: tauri::State<'_, ReadAloudState>) {
+    let mut queue = state.queue.lock();
+    queue.paused = true;
+    if let Some(playback) = &queue.current {
+        playback.lock().set_paused(true);
+    }
+}
+
+#[tauri::command]
+pub fn resume_read_aloud(state: tauri::State<'_, ReadAloudState>) {
+    let mut queue = state.queue.lock();
+    queue.paused = false;
+    if let Some(playback) = &queue.current {
+        playback.lock().set_paused(false);
+    }
+}
+
+/// Skip the sentence playing now; the next one starts right away
+#[tauri::command]
+pub fn skip_read_aloud_sentence(state: tauri::State<'_, ReadAloudState>) {
+    if let Some(playback) = &state.queue.lock().current {
+        playback.lock().stop();
+    }
+}
+
+/// Stop reading and drop the queue (no-op when nothing is being read)
+#[tauri::command]
+pub fn stop_read_aloud(state: tauri::State<'_, ReadAloudState>) {
+    state.queue.lock().reset(None, ReadbackSettings::default());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_streamed_text_into_sentences() {
+        let mut splitter = SentenceSplitter::default();
+        assert!(splitter.push("The answer is 3.").is_empty());
+        assert!(splitter.push("5 meters, roughly").is_empty());
+        assert_eq!(
+            splitter.push(". Is that really \"enough?\" It"),
+            ["The answer is 3.5 meters, roughly.", "Is that really \"enough?\""]
+        );
+        // Short sentences wait to be joined to the next
+        assert!(splitter.push(" is. Mostly. ").is_empty());
+        assert_eq!(splitter.push("Done here\n- next"), ["It is. Mostly. Done here"]);
+        assert_eq!(splitter.finish(), Some("- next".to_string()));
+        assert_eq!(splitter.finish(), None);
+
+        let long = "word ".repeat(MAX_SEGMENT_CHARS);
+        let pieces = splitter.push(&long);
+        assert!(pieces.iter().all(|p| p.chars().count() <= MAX_SEGMENT_CHARS));
+        assert!(!pieces.is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+//! Request adjustments
+//!
+//! Some requests are changed on their way out so the provider accepts them:
+//! OpenAI's `minimal` reasoning is bumped to `low` when web search is on,
+//! Gemini Pro takes `low` for a `medium` thinking level, long conversations
+//! lose their oldest turns to fit the context budget, old attachments can be
+//! sent as placeholders (see `attachment_elision`), and a feature a Gemini
+//! model rejects is turned off for a retry (see `gemini_fallback`).
+//! Each change is reported in a `request-adjusted` event for the turn, so
+//! a reply that behaves differently from what was asked isn't a surprise.
+
+use serde::{Deserialize, Serialize};
+
+use crate::session_events;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentKind {
+    /// Reasoning or thinking effort changed to one the model accepts
+    EffortChanged,
+    /// Older messages left out to fit the context budget
+    ContextTrimmed,
+    /// A feature the model rejected was turned off or swapped for another
+    FeatureChanged,
+    /// Attachments from earlier turns sent as placeholders
+    AttachmentsElided,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestAdjustment {
+    pub kind: AdjustmentKind,
+    /// What changed, for the user
+    pub description: String,
+}
+
+impl RequestAdjustment {
+    pub fn new(kind: AdjustmentKind, description: impl Into<String>) -> Self {
+        Self {
+            kind,
+            description: description.into(),
+        }
+    }
+}
+
+/// Event payload listing a turn's adjustments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestAdjustedEvent {
+    pub turn_id: String,
+    pub adjustments: Vec<RequestAdjustment>,
+}
+
+/// Tell the frontend how a turn's request was changed; nothing is sent when
+/// there are no adjustments
+pub fn emit_request_adjusted(window: &tauri::Window, turn_id: &str, adjustments: Vec<RequestAdjustment>) {
+    if adjustments.is_empty() {
+        return;
+    }
+    let event = RequestAdjustedEvent {
+        turn_id: turn_id.to_string(),
+        adjustments,
+    };
+    if let Err(err) = session_events::emit(window, turn_id, "request-adjusted", event) {
+        eprintln!("Failed to emit request-adjusted event: {}", err);
+    }
+}
+
+/// The adjustment for a conversation trimmed from `before` to `after` messages
+pub fn context_trimmed(before: usize, after: usize) -> Option<RequestAdjustment> {
+    let dropped = before.checked_sub(after).filter(|dropped| *dropped > 0)?;
+    let noun = if dropped == 1 { "message" } else { "messages" };
+    Some(RequestAdjustment::new(
+        AdjustmentKind::ContextTrimmed,
+        format!("Left out the {} oldest {} to fit the context budget", dropped, noun),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trimming_is_reported_only_when_messages_were_dropped() {
+        assert_eq!(context_trimmed(4, 4), None);
+        assert_eq!(
+            context_trimmed(10, 7).map(|a| a.description),
+            Some("Left out the 3 oldest messages to fit the context budget".to_string())
+        );
+        assert_eq!(
+            serde_json::to_value(context_trimmed(2, 1).unwrap()).unwrap(),
+            serde_json::json!({
+                "kind": "context_trimmed",
+                "description": "Left out the 1 oldest message to fit the context budget"
+            })
+        );
+    }
+}
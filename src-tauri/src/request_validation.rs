@@ -0,0 +1,253 @@
+//! Request validation
+//!
+//! Built requests are checked against each provider's limits and rules
+//! before they are sent, so the user gets an error that says what to change
+//! instead of the provider's raw 400 body: too many images or PDF pages,
+//! tool and thinking combinations the API rejects, and (for Gemini) user
+//! and model turns that don't alternate (which built requests avoid by
+//! merging them, see `message_format::gemini::alternating`). Failures are
+//! `SidestreamError::InvalidRequest`. OpenRouter's limits depend on the
+//! model behind it, so its requests aren't checked.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use regex::bytes::Regex;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+use crate::error::SidestreamError;
+
+/// Attachment limits per request
+struct Limits {
+    provider: &'static str,
+    max_images: usize,
+    max_pdf_pages: usize,
+}
+
+fn limits(provider: &str) -> Option<Limits> {
+    let (provider, max_images, max_pdf_pages) = match provider {
+        "anthropic" => ("Claude", 100, 100),
+        "openai" => ("OpenAI", 500, 100),
+        "google" => ("Gemini", 3000, 1000),
+        _ => return None,
+    };
+    Some(Limits {
+        provider,
+        max_images,
+        max_pdf_pages,
+    })
+}
+
+/// Attachments found in a request
+#[derive(Debug, Default, PartialEq)]
+struct Attachments {
+    images: usize,
+    /// Pages of each PDF, where they could be counted
+    pdf_pages: Vec<usize>,
+}
+
+impl Attachments {
+    fn add_pdf(&mut self, base64_data: &str) {
+        if let Some(pages) = pdf_page_count(base64_data) {
+            self.pdf_pages.push(pages);
+        }
+    }
+}
+
+/// Page objects in a base64 PDF. PDFs that keep their objects in
+/// compressed streams can't be counted this way and give None.
+pub fn pdf_page_count(base64_data: &str) -> Option<usize> {
+    static PAGE: OnceLock<Regex> = OnceLock::new();
+    let bytes = BASE64.decode(base64_data.trim()).ok()?;
+    let page = PAGE.get_or_init(|| Regex::new(r"/Type\s*/Page\b").unwrap());
+    let pages = page.find_iter(&bytes).count();
+    (pages > 0).then_some(pages)
+}
+
+fn content_blocks<'a>(messages: &'a Value, content_key: &'a str) -> impl Iterator<Item = &'a Value> {
+    messages
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(move |m| m[content_key].as_array())
+        .flatten()
+}
+
+fn anthropic_attachments(body: &Value) -> Attachments {
+    let mut found = Attachments::default();
+    for block in content_blocks(&body["messages"], "content") {
+        match block["type"].as_str() {
+            Some("image") => found.images += 1,
+            Some("document") if block["source"]["media_type"].as_str() == Some("application/pdf") => {
+                found.add_pdf(block["source"]["data"].as_str().unwrap_or_default());
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+fn openai_attachments(body: &Value) -> Attachments {
+    let mut found = Attachments::default();
+    for part in content_blocks(&body["input"], "content") {
+        match part["type"].as_str() {
+            Some("input_image") => found.images += 1,
+            Some("input_file") => {
+                if let Some(data) = part["file_data"].as_str().and_then(|d| d.strip_prefix("data:application/pdf;base64,")) {
+                    found.add_pdf(data);
+                }
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+fn gemini_attachments(body: &Value) -> Attachments {
+    let mut found = Attachments::default();
+    for part in content_blocks(&body["contents"], "parts") {
+        let inline = part.get("inline_data").or_else(|| part.get("inlineData"));
+        let Some(inline) = inline else {
+            continue;
+        };
+        let mime_type = inline.get("mime_type").or_else(|| inline.get("mimeType")).and_then(Value::as_str);
+        match mime_type {
+            Some(mime) if mime.starts_with("image/") => found.images += 1,
+            Some("application/pdf") => found.add_pdf(inline["data"].as_str().unwrap_or_default()),
+            _ => {}
+        }
+    }
+    found
+}
+
+fn check_attachments(found: &Attachments, limits: &Limits) -> Result<(), String> {
+    if found.images > limits.max_images {
+        return Err(format!(
+            "This conversation has {} images, but {} accepts at most {} per request. Remove some images or start a new chat.",
+            found.images, limits.provider, limits.max_images
+        ));
+    }
+    if let Some(pages) = found.pdf_pages.iter().copied().find(|pages| *pages > limits.max_pdf_pages) {
+        return Err(format!(
+            "A PDF in this conversation has {} pages, but {} reads at most {}. Split it or attach only the pages you need.",
+            pages, limits.provider, limits.max_pdf_pages
+        ));
+    }
+    Ok(())
+}
+
+fn anthropic_rules(body: &Value) -> Result<(), String> {
+    let thinking = body["thinking"]["type"].as_str().is_some_and(|t| t != "disabled");
+    let last_role = body["messages"].as_array().and_then(|m| m.last()).and_then(|m| m["role"].as_str());
+    if thinking && last_role == Some("assistant") {
+        return Err("Extended thinking can't continue a partial reply. Turn thinking off for this message.".to_string());
+    }
+    let mut names: Vec<&str> = body["tools"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|tool| tool["name"].as_str())
+        .collect();
+    names.sort_unstable();
+    if let Some(pair) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(format!(
+            "Two tools are named \"{}\". Rename one of your tools.",
+            pair[0]
+        ));
+    }
+    Ok(())
+}
+
+fn openai_rules(body: &Value) -> Result<(), String> {
+    let web_search = body["tools"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|tool| tool["type"].as_str() == Some("web_search"));
+    if web_search && body["reasoning"]["effort"].as_str() == Some("minimal") {
+        return Err("Minimal reasoning can't be used with web search. Choose a higher reasoning level or turn web search off.".to_string());
+    }
+    Ok(())
+}
+
+fn gemini_rules(body: &Value) -> Result<(), String> {
+    let thinking = &body["generationConfig"]["thinkingConfig"];
+    if thinking.get("thinkingLevel").is_some() && thinking.get("thinkingBudget").is_some() {
+        return Err("Gemini takes a thinking level or a thinking budget, not both. Clear one of them.".to_string());
+    }
+
+    let roles: Vec<&str> = body["contents"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|content| content["role"].as_str().unwrap_or("user"))
+        .collect();
+    if roles.first().is_some_and(|role| *role != "user") {
+        return Err("Gemini conversations must start with a user message. Delete the first reply.".to_string());
+    }
+    if let Some(index) = roles.windows(2).position(|pair| pair[0] == pair[1]) {
+        let who = if roles[index] == "user" { "you" } else { "the model" };
+        return Err(format!(
+            "Messages {} and {} are both from {}, but Gemini needs turns to alternate. Delete one of them or regenerate the missing reply.",
+            index + 1,
+            index + 2,
+            who
+        ));
+    }
+    Ok(())
+}
+
+/// Check a built request before it is sent to `provider`
+pub fn validate(provider: &str, body: &Value) -> Result<(), SidestreamError> {
+    let (attachments, rules) = match provider {
+        "anthropic" => (anthropic_attachments(body), anthropic_rules(body)),
+        "openai" => (openai_attachments(body), openai_rules(body)),
+        "google" => (gemini_attachments(body), gemini_rules(body)),
+        _ => return Ok(()),
+    };
+    let result = match limits(provider) {
+        Some(limits) => check_attachments(&attachments, &limits).and(rules),
+        None => rules,
+    };
+    result.map_err(|message| SidestreamError::InvalidRequest { message })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_what_providers_would() {
+        let pdf = BASE64.encode(b"%PDF-1.4 1 0 obj << /Type /Pages /Count 2 >> 2 0 obj << /Type /Page >> 3 0 obj << /Type/Page >>");
+        assert_eq!(pdf_page_count(&pdf), Some(2));
+        assert_eq!(pdf_page_count(&BASE64.encode(b"%PDF-1.7 compressed")), None);
+
+        let image = serde_json::json!({"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}});
+        let many_images = serde_json::json!({"messages": [{"role": "user", "content": vec![image; 101]}]});
+        let err = validate("anthropic", &many_images).unwrap_err();
+        assert!(err.to_string().contains("101 images, but Claude accepts at most 100"));
+
+        let prefill = serde_json::json!({
+            "thinking": {"type": "adaptive"},
+            "messages": [{"role": "user", "content": "Hi"}, {"role": "assistant", "content": "Hel"}]
+        });
+        assert!(validate("anthropic", &prefill).is_err());
+
+        let alternating = serde_json::json!({"contents": [
+            {"role": "user", "parts": [{"text": "a"}]},
+            {"role": "model", "parts": [{"text": "b"}]},
+            {"role": "user", "parts": [{"text": "c"}]}
+        ]});
+        assert!(validate("google", &alternating).is_ok());
+        let repeated = serde_json::json!({"contents": [
+            {"role": "user", "parts": [{"text": "a"}]},
+            {"role": "user", "parts": [{"text": "b"}]}
+        ]});
+        assert_eq!(
+            validate("google", &repeated),
+            Err(SidestreamError::InvalidRequest {
+                message: "Messages 1 and 2 are both from you, but Gemini needs turns to alternate. Delete one of them or regenerate the missing reply.".to_string()
+            })
+        );
+        assert!(validate("openrouter", &repeated).is_ok());
+    }
+}
@@ -0,0 +1,321 @@
+//! Data retention
+//!
+//! Optionally deletes sessions, stream recordings and caches older than a
+//! number of days. A background task applies the policy shortly after launch
+//! and then every few hours; `apply_retention_now` runs it on demand.
+//! Sessions that are locked, have pinned messages or carry a color/emoji/icon
+//! can be kept regardless of age. Only files the app wrote are deleted: each
+//! pruned directory is under the app data directory, only its own files
+//! (matched by name) are removed, and subdirectories are left alone. The
+//! audit log and the development logs (`llm_logger`, which live outside the
+//! app data directory) are never pruned.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tauri::{Emitter, Manager};
+
+use crate::attachments;
+use crate::citations;
+use crate::commands::{delete_stored_session, stored_sessions};
+use crate::pins::PINNED_MESSAGES_FIELD;
+use crate::session_appearance::APPEARANCE_FIELD;
+use crate::session_lock;
+use crate::settings;
+use crate::sse_recording;
+
+const RETENTION_POLICY_KEY: &str = "retention_policy";
+
+/// Delay before the first pass, so startup isn't slowed down
+const FIRST_PASS_DELAY: Duration = Duration::from_secs(60);
+
+const PASS_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetentionPolicy {
+    /// Delete sessions not updated for this many days; None keeps them all
+    pub session_days: Option<u32>,
+    /// Delete stream recordings older than this
+    pub log_days: Option<u32>,
+    /// Delete cached thumbnails and citation metadata older than this
+    pub cache_days: Option<u32>,
+    pub keep_locked_sessions: bool,
+    /// Keep sessions with at least one pinned message
+    pub keep_pinned_sessions: bool,
+    /// Keep sessions with a color, emoji or icon
+    pub keep_marked_sessions: bool,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            session_days: None,
+            log_days: None,
+            cache_days: None,
+            keep_locked_sessions: true,
+            keep_pinned_sessions: true,
+            keep_marked_sessions: true,
+        }
+    }
+}
+
+/// What a retention pass deleted
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionReport {
+    pub deleted_session_ids: Vec<String>,
+    pub deleted_files: usize,
+}
+
+fn load_policy(app: &tauri::AppHandle) -> RetentionPolicy {
+    settings::get_setting(app, RETENTION_POLICY_KEY).unwrap_or_default()
+}
+
+/// Whether a stored session is past the cutoff and not excluded by the policy
+fn session_expired(session: &serde_json::Value, policy: &RetentionPolicy, cutoff: chrono::DateTime<chrono::Utc>) -> bool {
+    if policy.keep_locked_sessions && session_lock::is_locked(session) {
+        return false;
+    }
+    if policy.keep_pinned_sessions
+        && session[PINNED_MESSAGES_FIELD].as_array().is_some_and(|pins| !pins.is_empty())
+    {
+        return false;
+    }
+    if policy.keep_marked_sessions && session[APPEARANCE_FIELD].is_object() {
+        return false;
+    }
+
+    // Sessions without a readable date are kept
+    session["updatedAt"]
+        .as_str()
+        .or_else(|| session["createdAt"].as_str())
+        .and_then(|date| chrono::DateTime::parse_from_rfc3339(date).ok())
+        .is_some_and(|date| date < cutoff)
+}
+
+/// A directory the app writes, and which of its files are the app's
+struct PrunableDir {
+    dir: PathBuf,
+    is_ours: fn(&str) -> bool,
+}
+
+/// A hex SHA-256 (`attachments::content_key`) followed by `extension`
+fn is_content_file(name: &str, extension: &str) -> bool {
+    name.strip_suffix(extension)
+        .is_some_and(|key| key.len() == 64 && key.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+fn is_recording(name: &str) -> bool {
+    name.ends_with(".jsonl")
+}
+
+fn is_thumbnail(name: &str) -> bool {
+    is_content_file(name, ".webp")
+}
+
+fn is_citation_metadata(name: &str) -> bool {
+    is_content_file(name, ".json")
+}
+
+/// Delete the app's files directly in `dir` last modified before `cutoff`;
+/// returns how many
+fn prune_files(prunable: &PrunableDir, cutoff: SystemTime) -> usize {
+    let Ok(entries) = fs::read_dir(&prunable.dir) else {
+        return 0;
+    };
+    let mut deleted = 0;
+    for entry in entries.flatten() {
+        // Subdirectories and symlinks are never followed
+        if !entry.file_type().is_ok_and(|t| t.is_file()) {
+            continue;
+        }
+        if !entry.file_name().to_str().is_some_and(prunable.is_ours) {
+            continue;
+        }
+        let modified = entry.metadata().and_then(|m| m.modified());
+        if modified.is_ok_and(|modified| modified < cutoff) && fs::remove_file(entry.path()).is_ok() {
+            deleted += 1;
+        }
+    }
+    deleted
+}
+
+/// Keep only directories inside the app data directory
+fn owned_dirs(app: &tauri::AppHandle, dirs: Vec<PrunableDir>) -> Vec<PrunableDir> {
+    let Ok(app_data_dir) = app.path().app_data_dir() else {
+        return Vec::new();
+    };
+    dirs.into_iter()
+        .filter(|prunable| prunable.dir.starts_with(&app_data_dir) && prunable.dir != app_data_dir)
+        .collect()
+}
+
+fn log_dirs(app: &tauri::AppHandle) -> Vec<PrunableDir> {
+    let dirs = sse_recording::recordings_dir(app)
+        .map(|dir| PrunableDir {
+            dir,
+            is_ours: is_recording,
+        })
+        .into_iter()
+        .collect();
+    owned_dirs(app, dirs)
+}
+
+fn cache_dirs(app: &tauri::AppHandle) -> Vec<PrunableDir> {
+    let mut dirs = Vec::new();
+    if let Ok(dir) = attachments::thumbnail_dir(app) {
+        dirs.push(PrunableDir {
+            dir,
+            is_ours: is_thumbnail,
+        });
+    }
+    if let Ok(dir) = citations::citation_cache_dir(app) {
+        dirs.push(PrunableDir {
+            dir,
+            is_ours: is_citation_metadata,
+        });
+    }
+    owned_dirs(app, dirs)
+}
+
+fn cutoff_time(days: u32) -> SystemTime {
+    SystemTime::now() - Duration::from_secs(u64::from(days) * SECS_PER_DAY)
+}
+
+/// Apply the stored policy once
+fn apply_policy(app: &tauri::AppHandle) -> Result<RetentionReport, String> {
+    let policy = load_policy(app);
+    let mut report = RetentionReport::default();
+
+    if let Some(days) = policy.session_days {
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+        for session in stored_sessions(app)? {
+            if !session_expired(&session, &policy, cutoff) {
+                continue;
+            }
+            if let Some(id) = session["id"].as_str() {
+                delete_stored_session(app, id)?;
+                report.deleted_session_ids.push(id.to_string());
+            }
+        }
+    }
+    if let Some(days) = policy.log_days {
+        let cutoff = cutoff_time(days);
+        report.deleted_files += log_dirs(app).iter().map(|prunable| prune_files(prunable, cutoff)).sum::<usize>();
+    }
+    if let Some(days) = policy.cache_days {
+        let cutoff = cutoff_time(days);
+        report.deleted_files += cache_dirs(app).iter().map(|prunable| prune_files(prunable, cutoff)).sum::<usize>();
+    }
+
+    if !report.deleted_session_ids.is_empty() {
+        if let Err(err) = app.emit("sessions-pruned", &report.deleted_session_ids) {
+            eprintln!("Failed to emit sessions-pruned event: {}", err);
+        }
+    }
+    Ok(report)
+}
+
+/// Background task applying the retention policy periodically
+pub async fn run_retention(app: tauri::AppHandle) {
+    tokio::time::sleep(FIRST_PASS_DELAY).await;
+    loop {
+        if let Err(e) = apply_policy(&app) {
+            eprintln!("Retention pass failed: {}", e);
+        }
+        tokio::time::sleep(PASS_INTERVAL).await;
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_retention_policy(app: tauri::AppHandle) -> RetentionPolicy {
+    load_policy(&app)
+}
+
+#[tauri::command]
+pub fn set_retention_policy(app: tauri::AppHandle, policy: RetentionPolicy) -> Result<(), String> {
+    if [policy.session_days, policy.log_days, policy.cache_days].contains(&Some(0)) {
+        return Err("Retention periods must be at least one day".to_string());
+    }
+    settings::set_setting(&app, RETENTION_POLICY_KEY, &policy)
+}
+
+/// Apply the retention policy immediately instead of waiting for the next pass
+#[tauri::command]
+pub fn apply_retention_now(app: tauri::AppHandle) -> Result<RetentionReport, String> {
+    apply_policy(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expires_old_sessions_unless_excluded() {
+        let cutoff = chrono::DateTime::parse_from_rfc3339("2026-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let policy = RetentionPolicy {
+            session_days: Some(30),
+            ..Default::default()
+        };
+        let session = |extra: serde_json::Value| {
+            let mut session = serde_json::json!({"id": "s", "updatedAt": "2026-05-01T00:00:00Z"});
+            session.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            session
+        };
+
+        assert!(session_expired(&session(serde_json::json!({})), &policy, cutoff));
+        assert!(!session_expired(
+            &session(serde_json::json!({"updatedAt": "2026-06-02T00:00:00Z"})),
+            &policy,
+            cutoff
+        ));
+        assert!(!session_expired(&session(serde_json::json!({"lockedAt": "2026-04-01T00:00:00Z"})), &policy, cutoff));
+        assert!(!session_expired(&session(serde_json::json!({"pinnedMessageIds": ["m1"]})), &policy, cutoff));
+        assert!(!session_expired(&session(serde_json::json!({"appearance": {"color": "red"}})), &policy, cutoff));
+
+        let keep_nothing = RetentionPolicy {
+            keep_locked_sessions: false,
+            ..policy
+        };
+        assert!(session_expired(
+            &session(serde_json::json!({"lockedAt": "2026-04-01T00:00:00Z"})),
+            &keep_nothing,
+            cutoff
+        ));
+    }
+
+    #[test]
+    fn prunes_only_the_apps_own_files() {
+        let key = "ab".repeat(32);
+        assert!(is_thumbnail(&format!("{}.webp", key)));
+        assert!(!is_thumbnail("holiday.webp"));
+        assert!(is_citation_metadata(&format!("{}.json", key)));
+        assert!(!is_citation_metadata("grounding_redirects.json"));
+        assert!(!is_citation_metadata(&format!("{}.json", "zz".repeat(32))));
+
+        let dir = std::env::temp_dir().join(format!("sidestream-retention-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("20260101T000000.000-openai.jsonl"), "").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        fs::write(dir.join("nested").join("old.jsonl"), "").unwrap();
+        let prunable = PrunableDir {
+            dir: dir.clone(),
+            is_ours: is_recording,
+        };
+        let future = SystemTime::now() + Duration::from_secs(60);
+        assert_eq!(prune_files(&prunable, future), 1);
+        assert!(dir.join("notes.txt").exists());
+        assert!(dir.join("nested").join("old.jsonl").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -10,8 +10,15 @@ use std::path::PathBuf;
 use std::sync::{OnceLock, RwLock};
 use tauri::Manager;
 
+#[cfg(mobile)]
+use crate::secure_storage_mobile;
+
 const NONCE_SIZE: usize = 12;
 
+/// Keystore item holding the API keys (as JSON) on mobile
+#[cfg(mobile)]
+const KEYSTORE_ITEM: &str = "api_keys";
+
 /// Cached encryption key - computed once on first use
 static DERIVED_KEY: OnceLock<[u8; 32]> = OnceLock::new();
 
@@ -126,8 +133,8 @@ fn get_keys_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     Ok(app_data_dir.join("keys.enc"))
 }
 
-/// Encrypt data using AES-256-GCM
-fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+/// Encrypt data using AES-256-GCM (nonce is prepended to the ciphertext)
+pub fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
     let cipher = Aes256Gcm::new_from_slice(key)
         .map_err(|e| format!("Failed to create cipher: {}", e))?;
 
@@ -148,7 +155,7 @@ fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
 }
 
 /// Decrypt data using AES-256-GCM
-fn decrypt(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, String> {
+pub fn decrypt(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, String> {
     if data.len() < NONCE_SIZE {
         return Err("Data too short".to_string());
     }
@@ -226,6 +233,36 @@ fn load_keys_from_disk(app: &tauri::AppHandle) -> Result<HashMap<String, String>
     }
 }
 
+/// Load all API keys from the encrypted file
+#[cfg(desktop)]
+fn load_keys(app: &tauri::AppHandle) -> Result<HashMap<String, String>, String> {
+    load_keys_from_disk(app)
+}
+
+/// Load all API keys from the platform keystore. Keys in an encrypted file
+/// (written before mobile builds used the keystore) are moved into it.
+#[cfg(mobile)]
+fn load_keys(app: &tauri::AppHandle) -> Result<HashMap<String, String>, String> {
+    if let Some(json_str) = secure_storage_mobile::read_item(app, KEYSTORE_ITEM)? {
+        return serde_json::from_str(&json_str).map_err(|e| format!("Failed to parse keys: {}", e));
+    }
+
+    let path = get_keys_path(app)?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let keys = load_keys_from_disk(app)?;
+    if !keys.is_empty() {
+        eprintln!("[SecureStorage] Migrating keys to the platform keystore");
+        write_keys(app, &keys)?;
+    }
+    // Only remove the file once its keys are safely in the keystore
+    if let Err(e) = fs::remove_file(&path) {
+        eprintln!("[SecureStorage] Failed to remove migrated keys file: {}", e);
+    }
+    Ok(keys)
+}
+
 /// Get cached keys, loading from disk on first access
 fn get_cached_keys(app: &tauri::AppHandle) -> Result<HashMap<String, String>, String> {
     // Try to read from cache first
@@ -236,8 +273,8 @@ fn get_cached_keys(app: &tauri::AppHandle) -> Result<HashMap<String, String>, St
         }
     }
 
-    // Cache miss - load from disk and populate cache
-    let keys = load_keys_from_disk(app)?;
+    // Cache miss - load from storage and populate cache
+    let keys = load_keys(app)?;
     {
         let mut cache = KEYS_CACHE.write().map_err(|e| format!("Cache lock error: {}", e))?;
         *cache = Some(keys.clone());
@@ -245,14 +282,27 @@ fn get_cached_keys(app: &tauri::AppHandle) -> Result<HashMap<String, String>, St
     Ok(keys)
 }
 
-/// Save all API keys to the encrypted file and update cache
-fn save_keys(app: &tauri::AppHandle, keys: &HashMap<String, String>) -> Result<(), String> {
+/// Write all API keys to the encrypted file
+#[cfg(desktop)]
+fn write_keys(app: &tauri::AppHandle, keys: &HashMap<String, String>) -> Result<(), String> {
     let path = get_keys_path(app)?;
     let json_str = serde_json::to_string(keys).map_err(|e| format!("Failed to serialize: {}", e))?;
     let key = derive_key();
     let encrypted = encrypt(&key, json_str.as_bytes())?;
 
-    fs::write(&path, encrypted).map_err(|e| format!("Failed to write keys file: {}", e))?;
+    fs::write(&path, encrypted).map_err(|e| format!("Failed to write keys file: {}", e))
+}
+
+/// Write all API keys to the platform keystore
+#[cfg(mobile)]
+fn write_keys(app: &tauri::AppHandle, keys: &HashMap<String, String>) -> Result<(), String> {
+    let json_str = serde_json::to_string(keys).map_err(|e| format!("Failed to serialize: {}", e))?;
+    secure_storage_mobile::write_item(app, KEYSTORE_ITEM, &json_str)
+}
+
+/// Save all API keys and update cache
+fn save_keys(app: &tauri::AppHandle, keys: &HashMap<String, String>) -> Result<(), String> {
+    write_keys(app, keys)?;
 
     // Update the cache
     {
@@ -302,3 +352,18 @@ pub async fn delete_api_key_secure(
 pub async fn has_api_key_secure(app: &tauri::AppHandle, provider: &str) -> bool {
     get_api_key_secure(app, provider).await.is_ok()
 }
+
+/// Get a secret other than an API key (such as the proxy password)
+pub fn get_secret(app: &tauri::AppHandle, name: &str) -> Option<String> {
+    get_cached_keys(app).ok()?.get(name).cloned()
+}
+
+/// Store a secret other than an API key, or remove it with `None`
+pub fn set_secret(app: &tauri::AppHandle, name: &str, value: Option<&str>) -> Result<(), String> {
+    let mut keys = get_cached_keys(app)?;
+    match value {
+        Some(value) => keys.insert(name.to_string(), value.to_string()),
+        None => keys.remove(name),
+    };
+    save_keys(app, &keys)
+}
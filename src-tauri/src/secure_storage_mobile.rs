@@ -0,0 +1,72 @@
+//! Platform keystores for mobile builds
+//!
+//! There is no stable machine identity to derive an encryption key from on
+//! Android and iOS, so `secure_storage` keeps its keys in the platform store
+//! instead: the Keychain on iOS and an Android Keystore-encrypted preference
+//! on Android. Both go through the app's `secure-storage` native plugin
+//! (`SecureStoragePlugin.kt` / `SecureStoragePlugin.swift` in the generated
+//! mobile projects), which stores string values by name.
+
+use serde::{Deserialize, Serialize};
+use tauri::plugin::{Builder, PluginHandle, TauriPlugin};
+use tauri::{Manager, Wry};
+
+#[cfg(target_os = "ios")]
+tauri::ios_plugin_binding!(init_plugin_secure_storage);
+
+/// Handle to the registered native plugin
+struct MobileKeystore(PluginHandle<Wry>);
+
+#[derive(Serialize)]
+struct ItemRequest<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct ItemResponse {
+    value: Option<String>,
+}
+
+/// The `secure-storage` plugin; registers the native side on startup
+pub fn init() -> TauriPlugin<Wry> {
+    Builder::new("secure-storage")
+        .setup(|app, api| {
+            #[cfg(target_os = "android")]
+            let handle = api.register_android_plugin("com.sidestream", "SecureStoragePlugin")?;
+            #[cfg(target_os = "ios")]
+            let handle = api.register_ios_plugin(init_plugin_secure_storage)?;
+            app.manage(MobileKeystore(handle));
+            Ok(())
+        })
+        .build()
+}
+
+fn plugin(app: &tauri::AppHandle) -> Result<PluginHandle<Wry>, String> {
+    app.try_state::<MobileKeystore>()
+        .map(|keystore| keystore.0.clone())
+        .ok_or_else(|| "Secure storage is not available".to_string())
+}
+
+/// Read a value from the keystore
+pub fn read_item(app: &tauri::AppHandle, name: &str) -> Result<Option<String>, String> {
+    plugin(app)?
+        .run_mobile_plugin::<ItemResponse>("getItem", ItemRequest { name, value: None })
+        .map(|response| response.value)
+        .map_err(|e| format!("Failed to read from keystore: {}", e))
+}
+
+/// Write a value to the keystore, replacing any existing one
+pub fn write_item(app: &tauri::AppHandle, name: &str, value: &str) -> Result<(), String> {
+    plugin(app)?
+        .run_mobile_plugin::<serde_json::Value>(
+            "setItem",
+            ItemRequest {
+                name,
+                value: Some(value),
+            },
+        )
+        .map(|_| ())
+        .map_err(|e| format!("Failed to write to keystore: {}", e))
+}
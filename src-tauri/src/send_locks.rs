@@ -0,0 +1,80 @@
+//! Per-session send locks
+//!
+//! Only one turn streams into a session at a time. A send (text or voice)
+//! takes the session's lock for the length of its turn; a second send while
+//! it is held — a double-clicked send button, voice racing a typed message —
+//! fails straight away with a `session_busy` error naming the turn in
+//! flight, rather than interleaving two streams into one conversation.
+
+use std::collections::HashMap;
+
+use crate::error::SidestreamError;
+
+/// Sessions with a turn in flight (managed state), mapped to that turn's id
+#[derive(Default)]
+pub struct SendLocks {
+    sessions: parking_lot::Mutex<HashMap<String, String>>,
+}
+
+/// Held for the length of a turn; releases the session's lock when dropped,
+/// however the turn ends
+pub struct SendGuard<'a> {
+    locks: &'a SendLocks,
+    session_id: String,
+}
+
+impl SendLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take `session_id`'s lock for `turn_id`, or fail with `SessionBusy` if
+    /// another turn holds it
+    pub fn acquire(&self, session_id: &str, turn_id: &str) -> Result<SendGuard<'_>, SidestreamError> {
+        let mut sessions = self.sessions.lock();
+        if let Some(active_turn_id) = sessions.get(session_id) {
+            return Err(SidestreamError::SessionBusy {
+                turn_id: active_turn_id.clone(),
+                message: format!("A response is still streaming in session {}", session_id),
+            });
+        }
+        sessions.insert(session_id.to_string(), turn_id.to_string());
+        Ok(SendGuard {
+            locks: self,
+            session_id: session_id.to_string(),
+        })
+    }
+
+    /// The session `turn_id` is streaming into, while it holds the lock
+    pub fn session_of_turn(&self, turn_id: &str) -> Option<String> {
+        self.sessions
+            .lock()
+            .iter()
+            .find(|(_, active_turn_id)| *active_turn_id == turn_id)
+            .map(|(session_id, _)| session_id.clone())
+    }
+}
+
+impl Drop for SendGuard<'_> {
+    fn drop(&mut self) {
+        self.locks.sessions.lock().remove(&self.session_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_send_is_busy_until_the_first_ends() {
+        let locks = SendLocks::new();
+        let guard = locks.acquire("s1", "turn-1").unwrap();
+        assert!(matches!(
+            locks.acquire("s1", "turn-2"),
+            Err(SidestreamError::SessionBusy { turn_id, .. }) if turn_id == "turn-1"
+        ));
+        assert!(locks.acquire("s2", "turn-3").is_ok());
+        drop(guard);
+        assert!(locks.acquire("s1", "turn-2").is_ok());
+    }
+}
@@ -0,0 +1,126 @@
+//! Session appearance
+//!
+//! A color, emoji and/or icon per session so a long session list can be
+//! scanned at a glance. Stored on the session under `appearance`, which
+//! carries it into list results, search results and exports along with the
+//! rest of the session.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::commands::{get_stored_session, update_stored_session};
+
+/// Session field holding the appearance
+pub const APPEARANCE_FIELD: &str = "appearance";
+
+/// Named colors the frontend has swatches for; hex colors are also accepted
+const NAMED_COLORS: &[&str] = &[
+    "red", "orange", "amber", "yellow", "lime", "green", "teal", "cyan", "blue", "indigo",
+    "violet", "pink", "stone",
+];
+
+/// Longest accepted emoji, in chars (flags and ZWJ sequences span several)
+const MAX_EMOJI_CHARS: usize = 10;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionAppearance {
+    /// A named color or "#rrggbb"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<String>,
+    /// Icon name from the frontend's icon set (lowercase, dash-separated)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+fn hex_color_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^#[0-9a-fA-F]{6}$").unwrap())
+}
+
+fn icon_name_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^[a-z0-9]+(-[a-z0-9]+)*$").unwrap())
+}
+
+/// Trim fields, drop empty ones and reject values the sidebar can't show
+fn normalize(appearance: SessionAppearance) -> Result<SessionAppearance, String> {
+    let clean = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let appearance = SessionAppearance {
+        color: clean(appearance.color).map(|c| c.to_lowercase()),
+        emoji: clean(appearance.emoji),
+        icon: clean(appearance.icon),
+    };
+
+    if let Some(color) = &appearance.color {
+        if !NAMED_COLORS.contains(&color.as_str()) && !hex_color_regex().is_match(color) {
+            return Err(format!("Unsupported color: {}", color));
+        }
+    }
+    if let Some(emoji) = &appearance.emoji {
+        if emoji.chars().count() > MAX_EMOJI_CHARS || emoji.chars().any(|c| c.is_alphanumeric()) {
+            return Err(format!("Not a single emoji: {}", emoji));
+        }
+    }
+    if let Some(icon) = &appearance.icon {
+        if !icon_name_regex().is_match(icon) {
+            return Err(format!("Invalid icon name: {}", icon));
+        }
+    }
+    Ok(appearance)
+}
+
+/// Set a session's appearance; fields left empty are cleared
+#[tauri::command]
+pub fn set_session_appearance(
+    app: tauri::AppHandle,
+    session_id: String,
+    appearance: SessionAppearance,
+) -> Result<SessionAppearance, String> {
+    let appearance = normalize(appearance)?;
+    update_stored_session(&app, &session_id, |fields| {
+        if appearance == SessionAppearance::default() {
+            fields.remove(APPEARANCE_FIELD);
+        } else {
+            let value = serde_json::to_value(&appearance).map_err(|e| e.to_string())?;
+            fields.insert(APPEARANCE_FIELD.to_string(), value);
+        }
+        Ok(())
+    })?;
+    Ok(appearance)
+}
+
+#[tauri::command]
+pub fn get_session_appearance(
+    app: tauri::AppHandle,
+    session_id: String,
+) -> Result<SessionAppearance, String> {
+    Ok(get_stored_session(&app, &session_id)?
+        .and_then(|session| serde_json::from_value(session[APPEARANCE_FIELD].clone()).ok())
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_and_validates_fields() {
+        let appearance = normalize(SessionAppearance {
+            color: Some(" #A1B2C3 ".to_string()),
+            emoji: Some("🚀".to_string()),
+            icon: Some("".to_string()),
+        })
+        .unwrap();
+        assert_eq!(appearance.color.as_deref(), Some("#a1b2c3"));
+        assert_eq!(appearance.icon, None);
+
+        let bad = |appearance: SessionAppearance| normalize(appearance).is_err();
+        assert!(bad(SessionAppearance { color: Some("chartreuse".into()), ..Default::default() }));
+        assert!(bad(SessionAppearance { emoji: Some("ok".into()), ..Default::default() }));
+        assert!(bad(SessionAppearance { icon: Some("Book Open".into()), ..Default::default() }));
+    }
+}
@@ -0,0 +1,201 @@
+//! Conversation branches
+//!
+//! `fork_chat_session` copies a session up to the end of one of its turns
+//! (a user message and the replies to it) so the conversation can carry on
+//! a different way from there. `regenerate_chat_turn` does the same but
+//! stops before the turn's user message and switches the copy to another
+//! model; the frontend then sends that message in the copy to get the new
+//! reply, so the copy holds it once.
+//! Each branch records where it came from (`branchOf`), and the session it
+//! was taken from lists its branches (`branches`), so they stay linked.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::commands::{get_stored_session, update_stored_session};
+use crate::session_duplicate::{store_duplicate, KeepMessages};
+
+/// Session field recording the session and turn a branch was taken from
+pub const BRANCH_OF_FIELD: &str = "branchOf";
+
+/// Session field listing the ids of a session's branches
+pub const BRANCHES_FIELD: &str = "branches";
+
+/// Where a branch was taken from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchOf {
+    pub session_id: String,
+    pub turn_index: usize,
+    /// Model the turn was regenerated with, for regenerated branches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// A linked session, as listed by `get_session_branches`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchSummary {
+    pub id: String,
+    pub title: String,
+    /// Turn the branch was taken at
+    pub turn_index: Option<usize>,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBranches {
+    /// The session this one was branched from, if it still exists
+    pub parent: Option<BranchSummary>,
+    pub branches: Vec<BranchSummary>,
+}
+
+/// The user message starting turn `turn_index` (0-based) and the last
+/// message of that turn
+fn turn_bounds(messages: &[Value], turn_index: usize) -> Option<(&str, &str)> {
+    let mut user_messages = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m["role"].as_str() == Some("user"))
+        .map(|(i, _)| i)
+        .skip(turn_index);
+    let start = user_messages.next()?;
+    let end = user_messages.next().unwrap_or(messages.len()) - 1;
+    Some((messages[start]["id"].as_str()?, messages[end]["id"].as_str()?))
+}
+
+fn branch_of(session: &Value) -> Option<BranchOf> {
+    serde_json::from_value(session[BRANCH_OF_FIELD].clone()).ok()
+}
+
+fn title(session: &Value) -> String {
+    session["title"].as_str().unwrap_or("Untitled").to_string()
+}
+
+/// Copy the messages of `session_id` that `keep` selects as a branch
+/// taken at `turn_index`, and list it on the original
+fn store_branch(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    keep: KeepMessages,
+    turn_index: usize,
+    model: Option<String>,
+) -> Result<Value, String> {
+    let branch = store_duplicate(app, session_id, keep, |original, fields| {
+        fields.insert("title".to_string(), Value::String(format!("{} (branch)", title(original))));
+        if let Some(model) = &model {
+            let settings = fields
+                .entry("settings")
+                .or_insert_with(|| Value::Object(Default::default()));
+            if !settings.is_object() {
+                *settings = Value::Object(Default::default());
+            }
+            settings["frontierModel"] = Value::String(model.clone());
+        }
+        let branch_of = BranchOf {
+            session_id: session_id.to_string(),
+            turn_index,
+            model,
+        };
+        fields.insert(BRANCH_OF_FIELD.to_string(), serde_json::json!(branch_of));
+    })?;
+    let branch_id = branch["id"].clone();
+    update_stored_session(app, session_id, |fields| {
+        let branches = fields
+            .entry(BRANCHES_FIELD)
+            .or_insert_with(|| Value::Array(Vec::new()));
+        if let Some(branches) = branches.as_array_mut() {
+            branches.push(branch_id);
+        }
+        Ok(())
+    })?;
+    Ok(branch)
+}
+
+fn stored_messages(app: &tauri::AppHandle, session_id: &str) -> Result<Vec<Value>, String> {
+    let session = get_stored_session(app, session_id)?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    Ok(session["messages"].as_array().cloned().unwrap_or_default())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Branch a session after turn `turn_index` (0-based), keeping that turn's
+/// replies. Returns the new session.
+#[tauri::command]
+pub async fn fork_chat_session(app: tauri::AppHandle, session_id: String, turn_index: usize) -> Result<Value, String> {
+    let messages = stored_messages(&app, &session_id)?;
+    let (_, end) = turn_bounds(&messages, turn_index).ok_or_else(|| format!("Turn not found: {}", turn_index))?;
+    store_branch(&app, &session_id, KeepMessages::Through(end), turn_index, None)
+}
+
+/// Branch a session just before turn `turn_index`, switched to `model`.
+/// Returns the new session; the caller sends the turn's user message in it.
+#[tauri::command]
+pub async fn regenerate_chat_turn(
+    app: tauri::AppHandle,
+    session_id: String,
+    turn_index: usize,
+    model: String,
+) -> Result<Value, String> {
+    let messages = stored_messages(&app, &session_id)?;
+    let (start, _) = turn_bounds(&messages, turn_index).ok_or_else(|| format!("Turn not found: {}", turn_index))?;
+    store_branch(&app, &session_id, KeepMessages::Before(start), turn_index, Some(model))
+}
+
+/// The session a session was branched from and its own branches, leaving
+/// out any that have been deleted
+#[tauri::command]
+pub async fn get_session_branches(app: tauri::AppHandle, session_id: String) -> Result<SessionBranches, String> {
+    let session = get_stored_session(&app, &session_id)?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+
+    let parent = match branch_of(&session) {
+        Some(origin) => get_stored_session(&app, &origin.session_id)?.map(|parent| BranchSummary {
+            id: origin.session_id.clone(),
+            title: title(&parent),
+            turn_index: Some(origin.turn_index),
+            model: origin.model.clone(),
+        }),
+        None => None,
+    };
+
+    let mut branches = Vec::new();
+    for id in session[BRANCHES_FIELD].as_array().into_iter().flatten().filter_map(Value::as_str) {
+        let Some(branch) = get_stored_session(&app, id)? else {
+            continue;
+        };
+        let origin = branch_of(&branch);
+        branches.push(BranchSummary {
+            id: id.to_string(),
+            title: title(&branch),
+            turn_index: origin.as_ref().map(|o| o.turn_index),
+            model: origin.and_then(|o| o.model),
+        });
+    }
+    Ok(SessionBranches { parent, branches })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns_run_from_a_user_message_to_the_next() {
+        let messages = vec![
+            serde_json::json!({"id": "m1", "role": "user"}),
+            serde_json::json!({"id": "m2", "role": "assistant"}),
+            serde_json::json!({"id": "m3", "role": "assistant"}),
+            serde_json::json!({"id": "m4", "role": "user"}),
+            serde_json::json!({"id": "m5", "role": "assistant"}),
+            serde_json::json!({"id": "m6", "role": "user"}),
+        ];
+        assert_eq!(turn_bounds(&messages, 0), Some(("m1", "m3")));
+        assert_eq!(turn_bounds(&messages, 1), Some(("m4", "m5")));
+        assert_eq!(turn_bounds(&messages, 2), Some(("m6", "m6")));
+        assert_eq!(turn_bounds(&messages, 3), None);
+    }
+}
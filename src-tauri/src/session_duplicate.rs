@@ -0,0 +1,250 @@
+//! Session duplication
+//!
+//! `duplicate_session` copies a conversation, optionally only up to a given
+//! message, so a different question can be tried from a midpoint while the
+//! original stays untouched. The copy gets fresh session, message, turn and
+//! attachment ids; backend fields keyed by those ids (thinking transcripts,
+//! response metadata, voice turns, pins, live attachments) are re-keyed to
+//! match, and the copy's voice audio and page snapshots are written under
+//! its own id. A copy is a session of its own: it keeps no lock and no
+//! branch links.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::attachment_elision::LIVE_ATTACHMENTS_FIELD;
+use crate::commands::{get_stored_session, new_session_id, store_session};
+use crate::llm::RESPONSE_METADATA_FIELD;
+use crate::pins::PINNED_MESSAGES_FIELD;
+use crate::session_branches::{BRANCHES_FIELD, BRANCH_OF_FIELD};
+use crate::session_lock::LOCKED_AT_FIELD;
+use crate::snapshots;
+use crate::thinking_transcripts::THINKING_TRANSCRIPTS_FIELD;
+use crate::voice_turns;
+
+/// How much of a session's conversation a copy keeps
+#[derive(Debug, Clone, Copy)]
+pub enum KeepMessages<'a> {
+    All,
+    /// Up to and including this message
+    Through(&'a str),
+    /// Up to, but not including, this message
+    Before(&'a str),
+}
+
+/// A duplicated session and the old → new turn ids it was re-keyed with
+struct Duplicate {
+    session: Value,
+    turn_ids: HashMap<String, String>,
+}
+
+/// The new id for `old`, allocating one the first time it is seen
+fn rekey(ids: &mut HashMap<String, String>, old: &str, new_id: &mut impl FnMut() -> String) -> String {
+    ids.entry(old.to_string()).or_insert_with(new_id).clone()
+}
+
+/// Keep the entries of a turn-keyed object whose turns were copied, under
+/// their new turn ids
+fn rekey_turn_map(value: &Value, turn_ids: &HashMap<String, String>) -> Value {
+    let entries = value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(turn_id, entry)| Some((turn_ids.get(turn_id)?.clone(), entry.clone())))
+        .collect();
+    Value::Object(entries)
+}
+
+/// Copy `session` with fresh ids, keeping the messages `keep` selects
+fn duplicate_session_json(
+    session: &Value,
+    keep: KeepMessages,
+    mut new_id: impl FnMut() -> String,
+) -> Result<Duplicate, String> {
+    let mut messages = session["messages"].as_array().cloned().unwrap_or_default();
+    let position = |message_id: &str| {
+        messages
+            .iter()
+            .position(|m| m["id"].as_str() == Some(message_id))
+            .ok_or_else(|| format!("Message not found: {}", message_id))
+    };
+    let len = match keep {
+        KeepMessages::All => messages.len(),
+        KeepMessages::Through(message_id) => position(message_id)? + 1,
+        KeepMessages::Before(message_id) => position(message_id)?,
+    };
+    messages.truncate(len);
+
+    let mut message_ids = HashMap::new();
+    let mut turn_ids = HashMap::new();
+    let mut attachment_ids = HashMap::new();
+    for message in &mut messages {
+        if let Some(id) = message["id"].as_str().map(str::to_string) {
+            message["id"] = Value::String(rekey(&mut message_ids, &id, &mut new_id));
+        }
+        if let Some(turn_id) = message["turnId"].as_str().map(str::to_string) {
+            message["turnId"] = Value::String(rekey(&mut turn_ids, &turn_id, &mut new_id));
+        }
+        for attachment in message["attachments"].as_array_mut().into_iter().flatten() {
+            if let Some(id) = attachment["id"].as_str().map(str::to_string) {
+                attachment["id"] = Value::String(rekey(&mut attachment_ids, &id, &mut new_id));
+            }
+        }
+    }
+
+    let mut copy = session.clone();
+    let fields = copy.as_object_mut().ok_or("Stored session is not an object")?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let title = session["title"].as_str().unwrap_or("Untitled");
+    fields.insert("id".to_string(), Value::String(new_id()));
+    fields.insert("title".to_string(), Value::String(format!("{} (copy)", title)));
+    fields.insert("createdAt".to_string(), Value::String(now.clone()));
+    fields.insert("updatedAt".to_string(), Value::String(now));
+    fields.insert("messages".to_string(), Value::Array(messages));
+
+    // The copy starts editable, unlinked from any branches, and with its
+    // own code execution sandboxes
+    fields.remove(LOCKED_AT_FIELD);
+    fields.remove(BRANCHES_FIELD);
+    fields.remove(BRANCH_OF_FIELD);
+    if let Some(settings) = fields.get_mut("settings").and_then(|s| s.as_object_mut()) {
+        settings.remove("anthropicContainerId");
+        settings.remove("openaiContainerId");
+    }
+
+    if let Some(items) = fields.get_mut("discoveryItems").and_then(|d| d.as_array_mut()) {
+        items.retain(|item| item["turnId"].as_str().is_some_and(|t| turn_ids.contains_key(t)));
+        for item in items.iter_mut() {
+            let turn_id = turn_ids[item["turnId"].as_str().unwrap_or_default()].clone();
+            item["turnId"] = Value::String(turn_id);
+            item["id"] = Value::String(new_id());
+        }
+    }
+    for field in [THINKING_TRANSCRIPTS_FIELD, RESPONSE_METADATA_FIELD] {
+        if let Some(value) = fields.get_mut(field) {
+            *value = rekey_turn_map(value, &turn_ids);
+        }
+    }
+    if let Some(voice_turns) = fields.get_mut("voiceTurns").and_then(|v| v.as_array_mut()) {
+        voice_turns.retain(|turn| turn["turnId"].as_str().is_some_and(|t| turn_ids.contains_key(t)));
+        for turn in voice_turns.iter_mut() {
+            let turn_id = turn_ids[turn["turnId"].as_str().unwrap_or_default()].clone();
+            turn["audioFile"] = Value::String(format!("{}.wav", turn_id));
+            turn["turnId"] = Value::String(turn_id);
+        }
+    }
+    for (field, ids) in [(PINNED_MESSAGES_FIELD, &message_ids), (LIVE_ATTACHMENTS_FIELD, &attachment_ids)] {
+        if let Some(kept) = fields.get_mut(field).and_then(|p| p.as_array_mut()) {
+            *kept = kept
+                .iter()
+                .filter_map(|id| ids.get(id.as_str()?).cloned().map(Value::String))
+                .collect();
+        }
+    }
+
+    Ok(Duplicate { session: copy, turn_ids })
+}
+
+/// Copy a stored session, keeping the messages `keep` selects, letting
+/// `prepare` edit the copy (given the original) before it is stored.
+/// Returns the stored copy.
+pub fn store_duplicate(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    keep: KeepMessages,
+    prepare: impl FnOnce(&Value, &mut serde_json::Map<String, Value>),
+) -> Result<Value, String> {
+    let session = get_stored_session(app, session_id)?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    let Duplicate { session: mut copy, turn_ids } =
+        duplicate_session_json(&session, keep, new_session_id)?;
+    if let Some(fields) = copy.as_object_mut() {
+        prepare(&session, fields);
+    }
+    let copy_id = copy["id"].as_str().unwrap_or_default().to_string();
+
+    let audio: Vec<(String, Vec<u8>)> = voice_turns::read_session_audio(app, session_id)
+        .into_iter()
+        .filter_map(|(name, bytes)| {
+            let turn_id = turn_ids.get(name.strip_suffix(".wav")?)?;
+            Some((format!("{}.wav", turn_id), bytes))
+        })
+        .collect();
+    voice_turns::write_session_audio(app, &copy_id, &audio)?;
+    snapshots::write_session_snapshots(app, &copy_id, &snapshots::read_session_snapshots(app, session_id))?;
+
+    store_session(app, copy.clone())?;
+    Ok(copy)
+}
+
+/// Copy a session as a new one, optionally only up to and including the
+/// message `up_to_message`, and return the copy
+#[tauri::command]
+pub async fn duplicate_session(
+    app: tauri::AppHandle,
+    session_id: String,
+    up_to_message: Option<String>,
+) -> Result<Value, String> {
+    let keep = match up_to_message.as_deref() {
+        Some(message_id) => KeepMessages::Through(message_id),
+        None => KeepMessages::All,
+    };
+    store_duplicate(&app, &session_id, keep, |_, _| {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_rekeys_ids_and_drops_later_turns() {
+        let session = serde_json::json!({
+            "id": "s1",
+            "title": "Trip",
+            "messages": [
+                {"id": "m1", "role": "user", "turnId": "t1", "attachments": [{"id": "a1", "data": "x"}]},
+                {"id": "m2", "role": "assistant", "turnId": "t1"},
+                {"id": "m3", "role": "user", "turnId": "t2"},
+                {"id": "m4", "role": "assistant", "turnId": "t2"}
+            ],
+            "discoveryItems": [{"id": "d1", "turnId": "t1"}, {"id": "d2", "turnId": "t2"}],
+            "settings": {"frontierModel": "m", "anthropicContainerId": "c"},
+            "thinkingTranscripts": {"t1": {"text": "a"}, "t2": {"text": "b"}},
+            "voiceTurns": [{"turnId": "t1", "audioFile": "t1.wav"}],
+            "pinnedMessageIds": ["m2", "m4"],
+            "liveAttachments": ["a1"],
+            "lockedAt": "2026-01-01T00:00:00Z",
+            "branchOf": {"sessionId": "s0", "turnIndex": 0},
+            "branches": ["s2"]
+        });
+        let mut next = 0;
+        let duplicate = duplicate_session_json(&session, KeepMessages::Through("m2"), || {
+            next += 1;
+            format!("n{}", next)
+        })
+        .unwrap();
+        let copy = &duplicate.session;
+
+        assert_eq!(copy["messages"].as_array().unwrap().len(), 2);
+        assert_eq!(copy["messages"][0]["id"], "n1");
+        assert_eq!(copy["messages"][0]["turnId"], "n2");
+        assert_eq!(copy["messages"][0]["attachments"][0]["id"], "n3");
+        assert_eq!(copy["messages"][1]["turnId"], "n2");
+        assert_eq!(copy["title"], "Trip (copy)");
+        assert_eq!(copy["discoveryItems"].as_array().unwrap().len(), 1);
+        assert_eq!(copy["thinkingTranscripts"], serde_json::json!({"n2": {"text": "a"}}));
+        assert_eq!(copy["voiceTurns"][0]["audioFile"], "n2.wav");
+        assert_eq!(copy["pinnedMessageIds"], serde_json::json!(["n4"]));
+        assert_eq!(copy["liveAttachments"], serde_json::json!(["n3"]));
+        assert!(copy.get("lockedAt").is_none());
+        assert!(copy.get("branchOf").is_none() && copy.get("branches").is_none());
+        assert!(copy["settings"].get("anthropicContainerId").is_none());
+        assert_eq!(duplicate.turn_ids["t1"], "n2");
+        assert_eq!(session["messages"][0]["id"], "m1");
+        assert!(duplicate_session_json(&session, KeepMessages::Through("missing"), new_session_id).is_err());
+
+        let before = duplicate_session_json(&session, KeepMessages::Before("m1"), new_session_id).unwrap();
+        assert!(before.session["messages"].as_array().unwrap().is_empty());
+    }
+}
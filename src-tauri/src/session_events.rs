@@ -0,0 +1,172 @@
+//! Session event routing
+//!
+//! A turn's stream events (`chat-stream-*`, `chat-usage` and the rest of the
+//! `chat-*` events) are emitted from the window that sent it, and Tauri
+//! delivers them to every window. A window that subscribes to a session
+//! (`subscribe_session_events`) opts into routing: events of a turn
+//! streaming into that session go only to the windows subscribed to it and
+//! the window that sent the turn (which finishes and saves it, even after
+//! moving on to another session), so a second window showing another
+//! conversation doesn't receive them. Turns in sessions no window
+//! subscribed to, and turns without a session, are still delivered
+//! everywhere. A turn's session is the one whose send lock it holds (see
+//! `send_locks`), or for work that outlives the turn's send lock (discovery)
+//! the session it was bound to with `bind_turn`.
+//!
+//! Routing can only narrow delivery for window-scoped listeners
+//! (`getCurrentWebviewWindow().listen`); a global `listen` hears every
+//! event. A window's subscriptions end when it is destroyed.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use tauri::{Emitter, EventTarget, Manager};
+
+use crate::send_locks::SendLocks;
+
+/// Tauri-managed subscriptions: session id → labels of the windows showing
+/// it, plus the sessions of bound turns
+#[derive(Default)]
+pub struct SessionSubscriptions {
+    sessions: Mutex<HashMap<String, BTreeSet<String>>>,
+    /// Turn id → session id, for turns routed without a send lock
+    turns: Mutex<HashMap<String, String>>,
+}
+
+impl SessionSubscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn subscribe(&self, session_id: &str, label: &str) {
+        self.sessions
+            .lock()
+            .entry(session_id.to_string())
+            .or_default()
+            .insert(label.to_string());
+    }
+
+    fn unsubscribe(&self, session_id: &str, label: &str) {
+        let mut sessions = self.sessions.lock();
+        if let Some(labels) = sessions.get_mut(session_id) {
+            labels.remove(label);
+            if labels.is_empty() {
+                sessions.remove(session_id);
+            }
+        }
+    }
+
+    /// Drop every subscription of a closed window
+    pub fn remove_window(&self, label: &str) {
+        let mut sessions = self.sessions.lock();
+        for labels in sessions.values_mut() {
+            labels.remove(label);
+        }
+        sessions.retain(|_, labels| !labels.is_empty());
+    }
+
+    fn subscribers(&self, session_id: &str) -> BTreeSet<String> {
+        self.sessions.lock().get(session_id).cloned().unwrap_or_default()
+    }
+
+    fn turn_session(&self, turn_id: &str) -> Option<String> {
+        self.turns.lock().get(turn_id).cloned()
+    }
+}
+
+/// Route a turn's events to `session_id`'s windows even after the turn's
+/// send lock is released, until `unbind_turn`
+pub fn bind_turn(window: &tauri::Window, turn_id: &str, session_id: &str) {
+    if let Some(subscriptions) = window.try_state::<SessionSubscriptions>() {
+        subscriptions.turns.lock().insert(turn_id.to_string(), session_id.to_string());
+    }
+}
+
+pub fn unbind_turn(window: &tauri::Window, turn_id: &str) {
+    if let Some(subscriptions) = window.try_state::<SessionSubscriptions>() {
+        subscriptions.turns.lock().remove(turn_id);
+    }
+}
+
+fn target_label(target: &EventTarget) -> Option<&str> {
+    match target {
+        EventTarget::AnyLabel { label }
+        | EventTarget::Window { label }
+        | EventTarget::Webview { label }
+        | EventTarget::WebviewWindow { label } => Some(label),
+        _ => None,
+    }
+}
+
+/// Windows a turn's events should reach; empty means every window
+fn turn_subscribers(window: &tauri::Window, turn_id: &str) -> BTreeSet<String> {
+    let Some(subscriptions) = window.try_state::<SessionSubscriptions>() else {
+        return BTreeSet::new();
+    };
+    let session_id = window
+        .try_state::<SendLocks>()
+        .and_then(|locks| locks.session_of_turn(turn_id))
+        .or_else(|| subscriptions.turn_session(turn_id));
+    match session_id {
+        Some(session_id) => subscriptions.subscribers(&session_id),
+        None => BTreeSet::new(),
+    }
+}
+
+/// Emit a turn's event to the windows subscribed to its session and the
+/// window that sent it, or to every window when none are subscribed
+pub fn emit<S: Serialize + Clone>(window: &tauri::Window, turn_id: &str, event: &str, payload: S) -> tauri::Result<()> {
+    let mut labels = turn_subscribers(window, turn_id);
+    if labels.is_empty() {
+        return window.emit(event, payload);
+    }
+    labels.insert(window.label().to_string());
+    window.emit_filter(event, payload, |target| {
+        target_label(target).is_some_and(|label| labels.contains(label))
+    })
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Route events of turns in `session_id` to this window
+#[tauri::command]
+pub fn subscribe_session_events(
+    window: tauri::Window,
+    state: tauri::State<'_, SessionSubscriptions>,
+    session_id: String,
+) {
+    state.subscribe(&session_id, window.label());
+}
+
+/// Stop routing events of turns in `session_id` to this window
+#[tauri::command]
+pub fn unsubscribe_session_events(
+    window: tauri::Window,
+    state: tauri::State<'_, SessionSubscriptions>,
+    session_id: String,
+) {
+    state.unsubscribe(&session_id, window.label());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_windows_per_session() {
+        let subscriptions = SessionSubscriptions::new();
+        subscriptions.subscribe("s1", "main");
+        subscriptions.subscribe("s1", "chat-2");
+        subscriptions.subscribe("s2", "chat-2");
+        assert_eq!(subscriptions.subscribers("s1").len(), 2);
+
+        subscriptions.unsubscribe("s1", "main");
+        assert_eq!(subscriptions.subscribers("s1"), BTreeSet::from(["chat-2".to_string()]));
+
+        subscriptions.remove_window("chat-2");
+        assert!(subscriptions.subscribers("s1").is_empty());
+        assert!(subscriptions.sessions.lock().is_empty());
+    }
+}
@@ -0,0 +1,468 @@
+//! Importing sessions from JSON exports
+//!
+//! `import_chat_sessions` reads one of:
+//! - a Sidestream JSON export (`{"version": 1, "sessions": [...]}`), written
+//!   by Settings → Saved Chats → Export; each session is checked for an id
+//!   and a readable message list, and stored under a fresh id;
+//! - a ChatGPT export's `conversations.json`, where each conversation is a
+//!   tree of message nodes; the branch ending at `current_node` (the one the
+//!   user last saw) is imported;
+//! - a Claude.ai export's `conversations.json`, a flat `chat_messages` list
+//!   per conversation.
+//!
+//! Imports are deduplicated by conversation id: every imported session
+//! records its origin in `importedFrom` (and a Sidestream session whose
+//! original id is still stored is skipped), so importing the same export
+//! twice adds nothing. Conversations that can't be read are skipped and
+//! counted rather than failing the whole import. Both apps ship their
+//! exports as zip files, which are read directly: the `conversations.json`
+//! inside is what gets imported.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Cursor, Read};
+
+use crate::commands::{new_session_id, new_session_json, session_message, store_session, stored_sessions};
+use crate::settings;
+#[cfg(desktop)]
+use crate::tray;
+
+/// Session field recording where an imported session came from
+pub const IMPORTED_FROM_FIELD: &str = "importedFrom";
+
+/// Newest Sidestream export version this build reads
+const EXPORT_VERSION: u64 = 1;
+
+const SIDESTREAM_SOURCE: &str = "sidestream";
+const CHATGPT_SOURCE: &str = "chatgpt";
+const CLAUDE_SOURCE: &str = "claude";
+
+/// Entry of a ChatGPT / Claude.ai zip export holding the conversations
+const CONVERSATIONS_ENTRY: &str = "conversations.json";
+
+/// A conversation from another app, reduced to its visible text
+#[derive(Debug, Clone, PartialEq)]
+struct Conversation {
+    source: &'static str,
+    id: String,
+    title: String,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    /// (role, content, timestamp) in order; roles are "user" and "assistant"
+    messages: Vec<(String, String, Option<String>)>,
+}
+
+#[derive(Debug)]
+enum ImportedSession {
+    Sidestream(Value),
+    Conversation(Conversation),
+}
+
+/// The readable sessions of an export, and how many weren't
+#[derive(Debug)]
+struct ParsedExport {
+    sessions: Vec<ImportedSession>,
+    invalid: usize,
+}
+
+/// What an import added
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    /// Ids of the sessions created
+    pub session_ids: Vec<String>,
+    /// Conversations already imported (or already stored)
+    pub skipped: usize,
+    /// Conversations that couldn't be read
+    pub invalid: usize,
+}
+
+/// ChatGPT stores times as Unix seconds (with a fraction)
+fn unix_time(value: &Value) -> Option<String> {
+    let seconds = value.as_f64()?;
+    chrono::DateTime::from_timestamp_millis((seconds * 1000.0) as i64).map(|time| time.to_rfc3339())
+}
+
+/// The text of a ChatGPT message; non-text parts (images, tool payloads)
+/// are dropped
+fn chatgpt_text(message: &Value) -> String {
+    let parts = message["content"]["parts"].as_array().map(Vec::as_slice).unwrap_or_default();
+    parts
+        .iter()
+        .filter_map(Value::as_str)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+fn parse_chatgpt(conversation: &Value) -> Option<Conversation> {
+    let mapping = conversation["mapping"].as_object()?;
+    let mut messages = Vec::new();
+    let mut node_id = conversation["current_node"].as_str();
+    // Walk up from the last visible message; the visited set guards
+    // against malformed parent cycles
+    let mut visited = HashSet::new();
+    while let Some(id) = node_id.filter(|id| visited.insert(*id)) {
+        let Some(node) = mapping.get(id) else {
+            break;
+        };
+        let message = &node["message"];
+        let role = message["author"]["role"].as_str().unwrap_or_default();
+        let hidden = message["metadata"]["is_visually_hidden_from_conversation"].as_bool() == Some(true);
+        let text = chatgpt_text(message);
+        if matches!(role, "user" | "assistant") && !hidden && !text.is_empty() {
+            messages.push((role.to_string(), text, unix_time(&message["create_time"])));
+        }
+        node_id = node["parent"].as_str();
+    }
+    messages.reverse();
+
+    Some(Conversation {
+        source: CHATGPT_SOURCE,
+        id: conversation["conversation_id"].as_str().or(conversation["id"].as_str())?.to_string(),
+        title: conversation["title"].as_str().unwrap_or_default().to_string(),
+        created_at: unix_time(&conversation["create_time"]),
+        updated_at: unix_time(&conversation["update_time"]),
+        messages,
+    })
+}
+
+/// The text of a Claude.ai message: `text`, or its text content blocks
+fn claude_text(message: &Value) -> String {
+    match message["text"].as_str().map(str::trim).filter(|t| !t.is_empty()) {
+        Some(text) => text.to_string(),
+        None => message["content"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|block| block["type"] == "text")
+            .filter_map(|block| block["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string(),
+    }
+}
+
+fn parse_claude(conversation: &Value) -> Option<Conversation> {
+    let messages = conversation["chat_messages"]
+        .as_array()?
+        .iter()
+        .filter_map(|message| {
+            let role = match message["sender"].as_str()? {
+                "human" => "user",
+                "assistant" => "assistant",
+                _ => return None,
+            };
+            let text = claude_text(message);
+            (!text.is_empty()).then(|| (role.to_string(), text, message["created_at"].as_str().map(str::to_string)))
+        })
+        .collect();
+
+    Some(Conversation {
+        source: CLAUDE_SOURCE,
+        id: conversation["uuid"].as_str()?.to_string(),
+        title: conversation["name"].as_str().unwrap_or_default().to_string(),
+        created_at: conversation["created_at"].as_str().map(str::to_string),
+        updated_at: conversation["updated_at"].as_str().map(str::to_string),
+        messages,
+    })
+}
+
+/// Whether a session from a Sidestream export has an id and a message list
+/// the app can show
+pub fn valid_sidestream_session(session: &Value) -> bool {
+    let has_id = session["id"].as_str().is_some_and(|id| !id.trim().is_empty());
+    let messages_valid = session["messages"].as_array().is_some_and(|messages| {
+        messages.iter().all(|message| {
+            matches!(message["role"].as_str(), Some("user" | "assistant")) && message["content"].is_string()
+        })
+    });
+    has_id && messages_valid
+}
+
+/// Recognize an export and read its sessions. Unreadable conversations are
+/// counted; only an export with nothing recognizable in it is an error.
+fn parse_export(data: Value) -> Result<ParsedExport, String> {
+    if let Some(sessions) = data.get("sessions").and_then(Value::as_array) {
+        if data["version"].as_u64().unwrap_or(0) > EXPORT_VERSION {
+            return Err("This export was created by a newer version of Sidestream".to_string());
+        }
+        let (valid, invalid): (Vec<_>, Vec<_>) = sessions.iter().cloned().partition(valid_sidestream_session);
+        return Ok(ParsedExport {
+            sessions: valid.into_iter().map(ImportedSession::Sidestream).collect(),
+            invalid: invalid.len(),
+        });
+    }
+
+    let conversations = match data {
+        Value::Array(conversations) => conversations,
+        conversation @ Value::Object(_) => vec![conversation],
+        _ => Vec::new(),
+    };
+    let mut parsed = ParsedExport {
+        sessions: Vec::new(),
+        invalid: 0,
+    };
+    let mut recognized = false;
+    for conversation in &conversations {
+        let conversation = if conversation.get("mapping").is_some() {
+            recognized = true;
+            parse_chatgpt(conversation)
+        } else if conversation.get("chat_messages").is_some() {
+            recognized = true;
+            parse_claude(conversation)
+        } else {
+            None
+        };
+        match conversation {
+            Some(conversation) => parsed.sessions.push(ImportedSession::Conversation(conversation)),
+            None => parsed.invalid += 1,
+        }
+    }
+    if !recognized {
+        return Err("Not a Sidestream, ChatGPT or Claude.ai export".to_string());
+    }
+    Ok(parsed)
+}
+
+/// The JSON of an export file: the file itself, or for a zip export its
+/// `conversations.json` (or its only JSON entry)
+fn read_export(raw: &[u8]) -> Result<Value, String> {
+    if !raw.starts_with(b"PK\x03\x04") {
+        return serde_json::from_slice(raw).map_err(|e| format!("Invalid export file: {}", e));
+    }
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(raw)).map_err(|e| format!("Invalid zip export: {}", e))?;
+    let json_entries: Vec<String> = archive
+        .file_names()
+        .filter(|name| name.ends_with(".json"))
+        .map(str::to_string)
+        .collect();
+    let entry = json_entries
+        .iter()
+        .find(|name| name.rsplit('/').next() == Some(CONVERSATIONS_ENTRY))
+        .or(match json_entries.as_slice() {
+            [only] => Some(only),
+            _ => None,
+        })
+        .ok_or_else(|| format!("No {} in the zip export", CONVERSATIONS_ENTRY))?;
+    let mut json = Vec::new();
+    archive
+        .by_name(entry)
+        .and_then(|mut file| file.read_to_end(&mut json).map_err(Into::into))
+        .map_err(|e| format!("Failed to read {} from the zip export: {}", entry, e))?;
+    serde_json::from_slice(&json).map_err(|e| format!("Invalid export file: {}", e))
+}
+
+/// A session from a Sidestream export, stored under a fresh id so it can't
+/// overwrite another session; the original id is kept in `importedFrom`
+fn sidestream_session(mut session: Value) -> Value {
+    let original_id = session["id"].as_str().unwrap_or_default().to_string();
+    let id = new_session_id();
+    if let Some(items) = session.get_mut("discoveryItems").and_then(Value::as_array_mut) {
+        for item in items.iter_mut().filter(|item| item.is_object()) {
+            item["sessionId"] = Value::String(id.clone());
+        }
+    }
+    session["id"] = Value::String(id);
+    session[IMPORTED_FROM_FIELD] = serde_json::json!({
+        "source": SIDESTREAM_SOURCE,
+        "conversationId": original_id,
+    });
+    session
+}
+
+/// A Sidestream session for a conversation from another app. Each user
+/// message starts a turn that the replies after it share.
+fn conversation_session(conversation: &Conversation, model: &str) -> Value {
+    let mut turn_id = new_session_id();
+    let messages = conversation
+        .messages
+        .iter()
+        .map(|(role, content, timestamp)| {
+            if role == "user" {
+                turn_id = new_session_id();
+            }
+            let mut message = session_message(role, content, &turn_id);
+            if let Some(timestamp) = timestamp {
+                message["timestamp"] = Value::String(timestamp.clone());
+            }
+            message
+        })
+        .collect();
+
+    let title = if conversation.title.trim().is_empty() {
+        "Imported chat"
+    } else {
+        conversation.title.trim()
+    };
+    let mut session = new_session_json(title, model, false, messages);
+    if let Some(created_at) = &conversation.created_at {
+        session["createdAt"] = Value::String(created_at.clone());
+    }
+    if let Some(updated_at) = conversation.updated_at.as_ref().or(conversation.created_at.as_ref()) {
+        session["updatedAt"] = Value::String(updated_at.clone());
+    }
+    session[IMPORTED_FROM_FIELD] = serde_json::json!({
+        "source": conversation.source,
+        "conversationId": conversation.id,
+    });
+    session
+}
+
+/// Dedup key of a stored session that came from another app
+fn imported_key(session: &Value) -> Option<(String, String)> {
+    let origin = &session[IMPORTED_FROM_FIELD];
+    Some((
+        origin["source"].as_str()?.to_string(),
+        origin["conversationId"].as_str()?.to_string(),
+    ))
+}
+
+/// Import the sessions in a Sidestream JSON export or a ChatGPT / Claude.ai
+/// export (zip or `conversations.json`), skipping conversations already
+/// imported
+#[tauri::command]
+pub async fn import_chat_sessions(app: tauri::AppHandle, path: String) -> Result<ImportSummary, String> {
+    let raw = fs::read(&path).map_err(|e| format!("Failed to read export: {}", e))?;
+    let imported = parse_export(read_export(&raw)?)?;
+
+    let stored = stored_sessions(&app)?;
+    let session_ids: HashSet<String> = stored
+        .iter()
+        .filter_map(|session| session["id"].as_str().map(str::to_string))
+        .collect();
+    let mut imported_keys: HashSet<(String, String)> = stored.iter().filter_map(imported_key).collect();
+    let model = settings::model_defaults(&app).chat;
+
+    let mut summary = ImportSummary {
+        session_ids: Vec::new(),
+        skipped: 0,
+        invalid: imported.invalid,
+    };
+    for session in imported.sessions {
+        let session = match session {
+            ImportedSession::Sidestream(session) => {
+                let id = session["id"].as_str().unwrap_or_default().to_string();
+                if session_ids.contains(&id) || !imported_keys.insert((SIDESTREAM_SOURCE.to_string(), id)) {
+                    summary.skipped += 1;
+                    continue;
+                }
+                sidestream_session(session)
+            }
+            ImportedSession::Conversation(conversation) => {
+                if !imported_keys.insert((conversation.source.to_string(), conversation.id.clone())) {
+                    summary.skipped += 1;
+                    continue;
+                }
+                conversation_session(&conversation, &model)
+            }
+        };
+        if let Some(id) = session["id"].as_str() {
+            summary.session_ids.push(id.to_string());
+        }
+        store_session(&app, session)?;
+    }
+    #[cfg(desktop)]
+    if !summary.session_ids.is_empty() {
+        tray::refresh_recent_sessions(&app);
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_chatgpt_and_claude_exports() {
+        let chatgpt = serde_json::json!([{
+            "id": "c1",
+            "title": "Regex help",
+            "create_time": 1760000000.5,
+            "current_node": "n3",
+            "mapping": {
+                "root": {"id": "root", "message": null, "parent": null, "children": ["n1"]},
+                "n1": {"id": "n1", "parent": "root", "message": {"author": {"role": "system"}, "content": {"content_type": "text", "parts": [""]}}},
+                "n2": {"id": "n2", "parent": "n1", "message": {"author": {"role": "user"}, "content": {"content_type": "text", "parts": ["Match a date"]}}},
+                "n2b": {"id": "n2b", "parent": "n2", "message": {"author": {"role": "assistant"}, "content": {"content_type": "text", "parts": ["Abandoned branch"]}}},
+                "n3": {"id": "n3", "parent": "n2", "message": {"author": {"role": "assistant"}, "content": {"content_type": "text", "parts": ["\\d{4}-\\d{2}-\\d{2}"]}}}
+            }
+        }]);
+        let parsed = parse_export(chatgpt).map(|parsed| parsed.sessions);
+        let Ok([ImportedSession::Conversation(conversation)]) = parsed.as_deref() else {
+            panic!("expected one ChatGPT conversation");
+        };
+        assert_eq!((conversation.source, conversation.id.as_str()), ("chatgpt", "c1"));
+        assert_eq!(conversation.created_at.as_deref(), Some("2025-10-09T08:53:20.500+00:00"));
+        let texts: Vec<_> = conversation.messages.iter().map(|(role, text, _)| (role.as_str(), text.as_str())).collect();
+        assert_eq!(texts, [("user", "Match a date"), ("assistant", "\\d{4}-\\d{2}-\\d{2}")]);
+
+        let claude = serde_json::json!([{
+            "uuid": "u1",
+            "name": "",
+            "chat_messages": [
+                {"sender": "human", "text": "Hi", "created_at": "2026-01-01T00:00:00Z"},
+                {"sender": "assistant", "text": "", "content": [{"type": "text", "text": "Hello!"}]}
+            ]
+        }]);
+        let parsed = parse_export(claude).map(|parsed| parsed.sessions);
+        let Ok([ImportedSession::Conversation(conversation)]) = parsed.as_deref() else {
+            panic!("expected one Claude.ai conversation");
+        };
+        let session = conversation_session(conversation, "claude-sonnet-4-6");
+        assert_eq!(session["title"], "Imported chat");
+        assert_eq!(imported_key(&session), Some(("claude".to_string(), "u1".to_string())));
+        assert_eq!(session["messages"][0]["turnId"], session["messages"][1]["turnId"]);
+        assert_eq!(session["messages"][1]["content"], "Hello!");
+
+        assert!(parse_export(serde_json::json!({"foo": 1})).is_err());
+    }
+
+    #[test]
+    fn skips_unreadable_conversations_and_reads_zips() {
+        use std::io::Write;
+
+        let export = serde_json::json!([
+            {"uuid": "u1", "chat_messages": [{"sender": "human", "text": "Hi"}]},
+            {"name": "no uuid", "chat_messages": []}
+        ]);
+        let mut buffer = Cursor::new(Vec::new());
+        let mut writer = zip::ZipWriter::new(&mut buffer);
+        let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        writer.start_file("users.json", options).unwrap();
+        writer.write_all(b"[]").unwrap();
+        writer.start_file("conversations.json", options).unwrap();
+        writer.write_all(export.to_string().as_bytes()).unwrap();
+        writer.finish().unwrap();
+
+        let parsed = parse_export(read_export(buffer.get_ref()).unwrap()).unwrap();
+        assert_eq!((parsed.sessions.len(), parsed.invalid), (1, 1));
+    }
+
+    #[test]
+    fn validates_sidestream_sessions_and_assigns_fresh_ids() {
+        let export = serde_json::json!({"version": 1, "sessions": [
+            {"id": "s1", "title": "Kept", "messages": [{"role": "user", "content": "Hi", "turnId": "t1"}],
+             "discoveryItems": [{"id": "d1", "turnId": "t1", "sessionId": "s1"}]},
+            {"id": "", "messages": []},
+            {"id": "s3", "messages": [{"role": "tool", "content": "?"}]},
+            {"id": "s4"}
+        ]});
+        let parsed = parse_export(export).unwrap();
+        assert_eq!(parsed.invalid, 3);
+        let [ImportedSession::Sidestream(session)] = parsed.sessions.as_slice() else {
+            panic!("expected one Sidestream session");
+        };
+
+        let session = sidestream_session(session.clone());
+        assert_ne!(session["id"], "s1");
+        assert_eq!(session["discoveryItems"][0]["sessionId"], session["id"]);
+        assert_eq!(imported_key(&session), Some(("sidestream".to_string(), "s1".to_string())));
+    }
+}
@@ -0,0 +1,56 @@
+//! Read-only sessions
+//!
+//! Locking a session marks it as reference material: saving it or sending
+//! new turns in it fails with a `SessionLocked` error until it is unlocked.
+//! Metadata kept by backend commands (appearance, pins) can still change.
+
+use crate::commands::{get_stored_session, update_stored_session};
+use crate::error::SidestreamError;
+
+/// Session field set while the session is locked (the time it was locked)
+pub const LOCKED_AT_FIELD: &str = "lockedAt";
+
+fn locked_error(session_id: &str) -> SidestreamError {
+    SidestreamError::SessionLocked {
+        session_id: session_id.to_string(),
+        message: format!("Session {} is locked (read-only). Unlock it to make changes.", session_id),
+    }
+}
+
+/// Whether a stored session (as JSON) is locked
+pub fn is_locked(session: &serde_json::Value) -> bool {
+    session[LOCKED_AT_FIELD].is_string()
+}
+
+/// Fail with a `SessionLocked` error if the session is locked
+pub fn ensure_unlocked(app: &tauri::AppHandle, session_id: &str) -> Result<(), SidestreamError> {
+    match get_stored_session(app, session_id)? {
+        Some(session) if is_locked(&session) => Err(locked_error(session_id)),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn lock_session(app: tauri::AppHandle, session_id: String) -> Result<(), String> {
+    update_stored_session(&app, &session_id, |fields| {
+        fields
+            .entry(LOCKED_AT_FIELD)
+            .or_insert_with(|| serde_json::Value::String(chrono::Utc::now().to_rfc3339()));
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unlock_session(app: tauri::AppHandle, session_id: String) -> Result<(), String> {
+    update_stored_session(&app, &session_id, |fields| {
+        fields.remove(LOCKED_AT_FIELD);
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn is_session_locked(app: tauri::AppHandle, session_id: String) -> Result<bool, String> {
+    Ok(get_stored_session(&app, &session_id)?.is_some_and(|session| is_locked(&session)))
+}
@@ -0,0 +1,271 @@
+//! Conversation statistics
+//!
+//! `get_session_stats` summarizes a stored session for the info panel and
+//! exports: message counts, token and cost estimates, models, tool use,
+//! attachments and duration. Providers' exact usage isn't stored with
+//! messages, so tokens are estimated from text length (about four characters
+//! per token) and cost from a per-model price table that users can override.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::commands::get_stored_session;
+use crate::settings;
+
+const MODEL_PRICING_KEY: &str = "model_pricing";
+
+/// Rough tokens per image attachment (a ~1000px image)
+const IMAGE_TOKENS: u64 = 1_600;
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// Built-in list prices, matched by model id prefix (most specific first)
+const BUILTIN_PRICES: &[(&str, f64, f64)] = &[
+    ("claude-opus", 5.0, 25.0),
+    ("claude-sonnet", 3.0, 15.0),
+    ("claude-haiku", 1.0, 5.0),
+    ("gpt-5.5-pro", 30.0, 180.0),
+    ("gpt-5.4-mini", 0.75, 4.5),
+    ("gpt-5.5", 5.0, 30.0),
+    ("gpt-5.4", 2.5, 15.0),
+    ("gemini-3.1-pro", 2.0, 12.0),
+    ("gemini-3.5-flash", 0.5, 3.0),
+    ("gemini-2.5-pro", 1.25, 10.0),
+    ("gemini-2.5-flash", 0.3, 2.5),
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentStats {
+    pub images: usize,
+    pub documents: usize,
+    /// Decoded size of all attachments
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStats {
+    pub message_count: usize,
+    pub user_messages: usize,
+    pub assistant_messages: usize,
+    /// Estimated tokens written by the user (text and images)
+    pub user_tokens: u64,
+    /// Estimated tokens produced by the model, including thinking
+    pub assistant_tokens: u64,
+    /// Estimated input tokens billed: each reply re-reads the conversation so far
+    pub billed_input_tokens: u64,
+    /// Estimated cost in USD; None when a model has no known price
+    pub estimated_cost_usd: Option<f64>,
+    pub models: Vec<String>,
+    /// Tool use by kind (web search, code execution, generated files, ...)
+    pub tools: BTreeMap<String, usize>,
+    pub attachments: AttachmentStats,
+    pub started_at: Option<String>,
+    pub last_message_at: Option<String>,
+    pub duration_secs: Option<i64>,
+}
+
+/// Price for a model: a user override by exact id, else the built-in prefix table
+fn model_price(model: &str, overrides: &BTreeMap<String, ModelPrice>) -> Option<ModelPrice> {
+    overrides.get(model).copied().or_else(|| {
+        BUILTIN_PRICES
+            .iter()
+            .find(|(prefix, _, _)| model.starts_with(prefix))
+            .map(|&(_, input, output)| ModelPrice {
+                input_per_million: input,
+                output_per_million: output,
+            })
+    })
+}
+
+fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+fn count_tool(tools: &mut BTreeMap<String, usize>, name: &str, count: usize) {
+    if count > 0 {
+        *tools.entry(name.to_string()).or_default() += count;
+    }
+}
+
+/// Compute statistics for a session in the frontend's `ChatSession` shape
+fn compute_session_stats(
+    session: &serde_json::Value,
+    overrides: &BTreeMap<String, ModelPrice>,
+) -> SessionStats {
+    let mut stats = SessionStats::default();
+    let session_model = session["settings"]["frontierModel"].as_str();
+    let mut models = BTreeSet::new();
+    let mut context_tokens = 0u64;
+    let mut cost = Some(0.0);
+    let mut timestamps = Vec::new();
+
+    for message in session["messages"].as_array().into_iter().flatten() {
+        stats.message_count += 1;
+        if let Some(timestamp) = message["timestamp"]
+            .as_str()
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        {
+            timestamps.push(timestamp);
+        }
+
+        let mut tokens = estimate_tokens(message["content"].as_str().unwrap_or_default());
+        for attachment in message["attachments"].as_array().into_iter().flatten() {
+            let data_len = attachment["data"]
+                .as_str()
+                .map(|d| d.len() as u64 * 3 / 4)
+                .unwrap_or(0);
+            stats.attachments.total_bytes += data_len;
+            if attachment["type"].as_str() == Some("image") {
+                stats.attachments.images += 1;
+                tokens += IMAGE_TOKENS;
+            } else {
+                stats.attachments.documents += 1;
+            }
+        }
+
+        if message["role"].as_str() == Some("assistant") {
+            stats.assistant_messages += 1;
+            tokens += estimate_tokens(message["thinkingContent"].as_str().unwrap_or_default());
+            stats.assistant_tokens += tokens;
+            stats.billed_input_tokens += context_tokens;
+
+            let model = message["model"].as_str().or(session_model);
+            if let Some(model) = model {
+                models.insert(model.to_string());
+            }
+            cost = match (cost, model.and_then(|m| model_price(m, overrides))) {
+                (Some(total), Some(price)) => Some(
+                    total
+                        + context_tokens as f64 * price.input_per_million / 1_000_000.0
+                        + tokens as f64 * price.output_per_million / 1_000_000.0,
+                ),
+                _ => None,
+            };
+
+            let citations = message["citations"].as_array().map_or(0, |c| c.len())
+                + message["inlineCitations"].as_array().map_or(0, |c| c.len());
+            count_tool(&mut stats.tools, "webSearch", usize::from(citations > 0));
+            count_tool(
+                &mut stats.tools,
+                "codeExecution",
+                usize::from(message["executionCode"].is_string()),
+            );
+            count_tool(
+                &mut stats.tools,
+                "generatedFiles",
+                message["generatedFiles"].as_array().map_or(0, |f| f.len()),
+            );
+        } else {
+            stats.user_messages += 1;
+            stats.user_tokens += tokens;
+            if message["includedDiscovery"].is_object() {
+                count_tool(&mut stats.tools, "discovery", 1);
+            }
+        }
+        context_tokens += tokens;
+    }
+
+    count_tool(
+        &mut stats.tools,
+        "voiceTurns",
+        session["voiceTurns"].as_object().map_or(0, |t| t.len()),
+    );
+
+    stats.models = models.into_iter().collect();
+    stats.estimated_cost_usd = cost.filter(|_| stats.assistant_messages > 0);
+    if let (Some(first), Some(last)) = (timestamps.iter().min(), timestamps.iter().max()) {
+        stats.started_at = Some(first.to_rfc3339());
+        stats.last_message_at = Some(last.to_rfc3339());
+        stats.duration_secs = Some((*last - *first).num_seconds());
+    }
+    stats
+}
+
+#[tauri::command]
+pub fn get_session_stats(
+    app: tauri::AppHandle,
+    session_id: String,
+) -> Result<SessionStats, String> {
+    let session = get_stored_session(&app, &session_id)?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    let overrides = settings::get_setting(&app, MODEL_PRICING_KEY).unwrap_or_default();
+    Ok(compute_session_stats(&session, &overrides))
+}
+
+/// Price overrides by exact model id (merged over the built-in table)
+#[tauri::command]
+pub fn get_model_pricing(app: tauri::AppHandle) -> BTreeMap<String, ModelPrice> {
+    settings::get_setting(&app, MODEL_PRICING_KEY).unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn set_model_pricing(
+    app: tauri::AppHandle,
+    pricing: BTreeMap<String, ModelPrice>,
+) -> Result<(), String> {
+    settings::set_setting(&app, MODEL_PRICING_KEY, &pricing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_messages_tokens_tools_and_cost() {
+        let session = serde_json::json!({
+            "settings": {"frontierModel": "claude-sonnet-4-6"},
+            "messages": [
+                {"role": "user", "content": "a".repeat(400), "timestamp": "2026-03-01T10:00:00Z",
+                 "attachments": [{"type": "image", "data": "AAAA"}]},
+                {"role": "assistant", "content": "b".repeat(800), "timestamp": "2026-03-01T10:01:30Z",
+                 "citations": [{"url": "https://example.com"}], "executionCode": "print(1)"}
+            ]
+        });
+        let stats = compute_session_stats(&session, &BTreeMap::new());
+
+        assert_eq!((stats.user_messages, stats.assistant_messages), (1, 1));
+        assert_eq!(stats.user_tokens, 100 + IMAGE_TOKENS);
+        assert_eq!(stats.assistant_tokens, 200);
+        assert_eq!(stats.billed_input_tokens, 100 + IMAGE_TOKENS);
+        assert_eq!(stats.models, vec!["claude-sonnet-4-6"]);
+        assert_eq!(stats.tools["webSearch"], 1);
+        assert_eq!(stats.tools["codeExecution"], 1);
+        assert_eq!(stats.attachments.images, 1);
+        assert_eq!(stats.duration_secs, Some(90));
+
+        let expected = (1_700.0 * 3.0 + 200.0 * 15.0) / 1_000_000.0;
+        assert!((stats.estimated_cost_usd.unwrap() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn unknown_models_have_no_cost_unless_priced() {
+        let session = serde_json::json!({
+            "settings": {"frontierModel": "local-llama"},
+            "messages": [{"role": "user", "content": "hi"}, {"role": "assistant", "content": "hello"}]
+        });
+        assert_eq!(
+            compute_session_stats(&session, &BTreeMap::new()).estimated_cost_usd,
+            None
+        );
+
+        let overrides = BTreeMap::from([(
+            "local-llama".to_string(),
+            ModelPrice {
+                input_per_million: 0.0,
+                output_per_million: 0.0,
+            },
+        )]);
+        assert_eq!(
+            compute_session_stats(&session, &overrides).estimated_cost_usd,
+            Some(0.0)
+        );
+    }
+}
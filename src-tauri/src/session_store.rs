@@ -0,0 +1,304 @@
+//! SQLite session storage
+//!
+//! Sessions are kept in `app_data_dir/sessions.db`, one row per session with
+//! the session JSON in `data` and `updated_at` indexed for the session list,
+//! so listing a page doesn't load every session's inline attachments. The
+//! schema is versioned with `PRAGMA user_version` and upgraded on open.
+//! Prompt templates (see `prompts`) are kept in the same database.
+//!
+//! Sessions used to live in the `chat-sessions.json` plugin store; they are
+//! imported once when the database is first created. The JSON file is left
+//! in place as a backup.
+
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+const DATABASE_FILE: &str = "sessions.db";
+
+/// The plugin store sessions were kept in before the database
+const LEGACY_STORE_PATH: &str = "chat-sessions.json";
+
+/// `meta` key set once the legacy store has been imported
+const LEGACY_IMPORTED_KEY: &str = "legacy_json_imported";
+
+/// Schema migrations, applied in order; the database's `user_version` is
+/// the number already applied
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE sessions (
+        id TEXT PRIMARY KEY,
+        updated_at TEXT NOT NULL DEFAULT '',
+        data TEXT NOT NULL
+    );
+    CREATE INDEX sessions_updated_at ON sessions (updated_at DESC);
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );",
+    "CREATE TABLE prompt_templates (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        data TEXT NOT NULL
+    );",
+];
+
+/// The open session database (managed state)
+pub struct SessionStore {
+    conn: Mutex<Connection>,
+}
+
+fn db_error(e: rusqlite::Error) -> String {
+    format!("Session database error: {}", e)
+}
+
+/// Bring the schema up to date
+fn migrate(conn: &mut Connection) -> Result<(), String> {
+    let version: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(db_error)?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction().map_err(db_error)?;
+        tx.execute_batch(migration).map_err(db_error)?;
+        tx.pragma_update(None, "user_version", index + 1).map_err(db_error)?;
+        tx.commit().map_err(db_error)?;
+    }
+    Ok(())
+}
+
+fn session_id(session: &Value) -> Result<&str, String> {
+    session
+        .get("id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Session must have an id".to_string())
+}
+
+fn put(conn: &Connection, session: &Value) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO sessions (id, updated_at, data) VALUES (?1, ?2, ?3)
+         ON CONFLICT (id) DO UPDATE SET updated_at = excluded.updated_at, data = excluded.data",
+        params![
+            session_id(session)?,
+            session["updatedAt"].as_str().unwrap_or_default(),
+            session.to_string()
+        ],
+    )
+    .map_err(db_error)?;
+    Ok(())
+}
+
+fn get(conn: &Connection, id: &str) -> Result<Option<Value>, String> {
+    let data: Option<String> = conn
+        .query_row("SELECT data FROM sessions WHERE id = ?1", [id], |row| row.get(0))
+        .optional()
+        .map_err(db_error)?;
+    data.map(|data| serde_json::from_str(&data).map_err(|e| format!("Corrupt session {}: {}", id, e)))
+        .transpose()
+}
+
+/// Sessions, most recently updated first. `limit` None returns all of them.
+fn page(conn: &Connection, offset: u32, limit: Option<u32>) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare("SELECT data FROM sessions ORDER BY updated_at DESC, id LIMIT ?1 OFFSET ?2")
+        .map_err(db_error)?;
+    let limit = limit.map_or(-1, i64::from);
+    let rows = stmt
+        .query_map(params![limit, offset], |row| row.get::<_, String>(0))
+        .map_err(db_error)?;
+    let mut sessions = Vec::new();
+    for data in rows {
+        match serde_json::from_str(&data.map_err(db_error)?) {
+            Ok(session) => sessions.push(session),
+            Err(e) => eprintln!("Skipping corrupt stored session: {}", e),
+        }
+    }
+    Ok(sessions)
+}
+
+/// Copy sessions from the legacy JSON store, once
+fn import_legacy(conn: &mut Connection, sessions: Vec<Value>) -> Result<usize, String> {
+    let tx = conn.transaction().map_err(db_error)?;
+    let imported: Option<String> = tx
+        .query_row("SELECT value FROM meta WHERE key = ?1", [LEGACY_IMPORTED_KEY], |row| row.get(0))
+        .optional()
+        .map_err(db_error)?;
+    if imported.is_some() {
+        return Ok(0);
+    }
+    let mut count = 0;
+    for session in &sessions {
+        // Sessions the database already has are newer than the JSON copy
+        if session_id(session).is_ok_and(|id| get(&tx, id).ok().flatten().is_none()) {
+            put(&tx, session)?;
+            count += 1;
+        }
+    }
+    tx.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2)",
+        params![LEGACY_IMPORTED_KEY, chrono::Utc::now().to_rfc3339()],
+    )
+    .map_err(db_error)?;
+    tx.commit().map_err(db_error)?;
+    Ok(count)
+}
+
+/// Open (creating or upgrading) the session database, import the legacy
+/// JSON store the first time, and register the store as managed state.
+/// Called once at startup.
+pub fn init(app: &tauri::AppHandle) -> Result<(), String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    std::fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    let mut conn = Connection::open(app_data_dir.join(DATABASE_FILE)).map_err(db_error)?;
+    migrate(&mut conn)?;
+
+    let legacy: Vec<Value> = app
+        .store(LEGACY_STORE_PATH)
+        .map(|store| store.entries().into_iter().map(|(_, v)| v).collect())
+        .unwrap_or_default();
+    let imported = import_legacy(&mut conn, legacy)?;
+    if imported > 0 {
+        eprintln!("Imported {} sessions from {} into {}", imported, LEGACY_STORE_PATH, DATABASE_FILE);
+    }
+
+    app.manage(SessionStore {
+        conn: Mutex::new(conn),
+    });
+    Ok(())
+}
+
+fn store(app: &tauri::AppHandle) -> tauri::State<'_, SessionStore> {
+    app.state::<SessionStore>()
+}
+
+// ============================================================================
+// Session access (used via `commands`)
+// ============================================================================
+
+pub fn load(app: &tauri::AppHandle, id: &str) -> Result<Option<Value>, String> {
+    get(&store(app).conn.lock(), id)
+}
+
+pub fn save(app: &tauri::AppHandle, session: &Value) -> Result<(), String> {
+    put(&store(app).conn.lock(), session)
+}
+
+/// Read, edit and write a session back in one transaction. `edit` gets the
+/// stored copy (None if there is none) and returns the session to store.
+pub fn modify<F>(app: &tauri::AppHandle, id: &str, edit: F) -> Result<Value, String>
+where
+    F: FnOnce(Option<Value>) -> Result<Value, String>,
+{
+    let state = store(app);
+    let mut conn = state.conn.lock();
+    let tx = conn.transaction().map_err(db_error)?;
+    let session = edit(get(&tx, id)?)?;
+    put(&tx, &session)?;
+    tx.commit().map_err(db_error)?;
+    Ok(session)
+}
+
+pub fn list(app: &tauri::AppHandle, offset: u32, limit: Option<u32>) -> Result<Vec<Value>, String> {
+    page(&store(app).conn.lock(), offset, limit)
+}
+
+pub fn count(app: &tauri::AppHandle) -> Result<u32, String> {
+    store(app)
+        .conn
+        .lock()
+        .query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))
+        .map_err(db_error)
+}
+
+pub fn delete(app: &tauri::AppHandle, id: &str) -> Result<(), String> {
+    store(app)
+        .conn
+        .lock()
+        .execute("DELETE FROM sessions WHERE id = ?1", [id])
+        .map_err(db_error)?;
+    Ok(())
+}
+
+pub fn clear(app: &tauri::AppHandle) -> Result<(), String> {
+    store(app)
+        .conn
+        .lock()
+        .execute("DELETE FROM sessions", [])
+        .map_err(db_error)?;
+    Ok(())
+}
+
+// ============================================================================
+// Prompt templates (used via `prompts`)
+// ============================================================================
+
+/// All stored prompt templates (as JSON), by name
+pub fn list_prompt_templates(app: &tauri::AppHandle) -> Result<Vec<Value>, String> {
+    let state = store(app);
+    let conn = state.conn.lock();
+    let mut stmt = conn
+        .prepare("SELECT data FROM prompt_templates ORDER BY name COLLATE NOCASE, id")
+        .map_err(db_error)?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0)).map_err(db_error)?;
+    let mut templates = Vec::new();
+    for data in rows {
+        match serde_json::from_str(&data.map_err(db_error)?) {
+            Ok(template) => templates.push(template),
+            Err(e) => eprintln!("Skipping corrupt prompt template: {}", e),
+        }
+    }
+    Ok(templates)
+}
+
+pub fn save_prompt_template(app: &tauri::AppHandle, id: &str, name: &str, template: &Value) -> Result<(), String> {
+    store(app)
+        .conn
+        .lock()
+        .execute(
+            "INSERT INTO prompt_templates (id, name, data) VALUES (?1, ?2, ?3)
+             ON CONFLICT (id) DO UPDATE SET name = excluded.name, data = excluded.data",
+            params![id, name, template.to_string()],
+        )
+        .map_err(db_error)?;
+    Ok(())
+}
+
+pub fn delete_prompt_template(app: &tauri::AppHandle, id: &str) -> Result<(), String> {
+    store(app)
+        .conn
+        .lock()
+        .execute("DELETE FROM prompt_templates WHERE id = ?1", [id])
+        .map_err(db_error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_imports_once_and_pages_by_recency() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        migrate(&mut conn).unwrap();
+
+        put(&conn, &serde_json::json!({"id": "b", "updatedAt": "2026-03-01", "title": "db copy"})).unwrap();
+        let legacy = vec![
+            serde_json::json!({"id": "a", "updatedAt": "2026-01-01"}),
+            serde_json::json!({"id": "b", "updatedAt": "2026-02-01", "title": "json copy"}),
+            serde_json::json!({"id": "c", "updatedAt": "2026-04-01"}),
+            serde_json::json!({"title": "no id"}),
+        ];
+        assert_eq!(import_legacy(&mut conn, legacy.clone()).unwrap(), 2);
+        assert_eq!(import_legacy(&mut conn, legacy).unwrap(), 0);
+        assert_eq!(get(&conn, "b").unwrap().unwrap()["title"], "db copy");
+
+        let ids = |sessions: Vec<Value>| sessions.iter().map(|s| s["id"].to_string()).collect::<Vec<_>>();
+        assert_eq!(ids(page(&conn, 0, None).unwrap()), ["\"c\"", "\"b\"", "\"a\""]);
+        assert_eq!(ids(page(&conn, 1, Some(1)).unwrap()), ["\"b\""]);
+        assert!(get(&conn, "missing").unwrap().is_none());
+    }
+}
@@ -0,0 +1,207 @@
+//! Backend settings persisted with tauri-plugin-store
+//!
+//! Settings that the Rust side needs to read on its own (without the frontend
+//! passing them on every call) live in `settings.json`, one key per setting.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tauri_plugin_store::StoreExt;
+
+const SETTINGS_STORE_PATH: &str = "settings.json";
+
+const TRANSCRIPTION_LANGUAGE_KEY: &str = "transcription_language";
+
+/// Read a setting, returning None if it is unset or can't be deserialized
+pub fn get_setting<T: DeserializeOwned>(app: &tauri::AppHandle, key: &str) -> Option<T> {
+    let store = app.store(SETTINGS_STORE_PATH).ok()?;
+    store
+        .get(key)
+        .and_then(|value| serde_json::from_value(value).ok())
+}
+
+/// Write a setting and flush the store to disk
+pub fn set_setting<T: Serialize>(
+    app: &tauri::AppHandle,
+    key: &str,
+    value: &T,
+) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE_PATH)
+        .map_err(|e| e.to_string())?;
+
+    let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    store.set(key, value);
+    store.save().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Remove a setting so it falls back to its default
+pub fn delete_setting(app: &tauri::AppHandle, key: &str) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE_PATH)
+        .map_err(|e| e.to_string())?;
+
+    let _ = store.delete(key);
+    store.save().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Transcription Language
+// ============================================================================
+
+/// Language hint for voice transcription as an ISO-639-1 code (e.g. "de").
+/// None means the provider should auto-detect the spoken language.
+pub fn transcription_language(app: &tauri::AppHandle) -> Option<String> {
+    get_setting::<String>(app, TRANSCRIPTION_LANGUAGE_KEY).and_then(normalize_language)
+}
+
+/// Normalize a user-supplied language code; "auto" and blank mean auto-detect
+fn normalize_language(language: String) -> Option<String> {
+    let language = language.trim().to_lowercase();
+    if language.is_empty() || language == "auto" {
+        None
+    } else {
+        Some(language)
+    }
+}
+
+#[tauri::command]
+pub fn get_transcription_language(app: tauri::AppHandle) -> Option<String> {
+    transcription_language(&app)
+}
+
+#[tauri::command]
+pub fn set_transcription_language(
+    app: tauri::AppHandle,
+    language: Option<String>,
+) -> Result<(), String> {
+    match language.and_then(normalize_language) {
+        Some(language) => set_setting(&app, TRANSCRIPTION_LANGUAGE_KEY, &language),
+        None => delete_setting(&app, TRANSCRIPTION_LANGUAGE_KEY),
+    }
+}
+
+// ============================================================================
+// Model Defaults
+// ============================================================================
+
+const MODEL_DEFAULTS_KEY: &str = "model_defaults";
+
+/// Built-in transcription models, used when the configured default belongs to
+/// the other transcription provider
+pub const DEFAULT_OPENAI_TRANSCRIPTION_MODEL: &str = "gpt-4o-mini-transcribe";
+pub const DEFAULT_GEMINI_TRANSCRIPTION_MODEL: &str = "gemini-2.0-flash";
+
+/// Models used by each feature when a command isn't given one explicitly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelDefaults {
+    pub chat: String,
+    pub discovery: String,
+    /// An OpenAI transcription model or a Gemini model
+    pub transcription: String,
+    pub title_generation: String,
+    pub summarization: String,
+    /// An OpenAI image model or a Gemini/Imagen model
+    pub image_generation: String,
+}
+
+impl Default for ModelDefaults {
+    fn default() -> Self {
+        Self {
+            chat: "claude-opus-4-8".to_string(),
+            discovery: "claude-haiku-4-5-20251001".to_string(),
+            transcription: DEFAULT_OPENAI_TRANSCRIPTION_MODEL.to_string(),
+            title_generation: "claude-haiku-4-5-20251001".to_string(),
+            summarization: "claude-sonnet-4-6".to_string(),
+            image_generation: "gpt-image-1".to_string(),
+        }
+    }
+}
+
+/// Stored model defaults; unset features fall back to the built-in defaults
+pub fn model_defaults(app: &tauri::AppHandle) -> ModelDefaults {
+    get_setting(app, MODEL_DEFAULTS_KEY).unwrap_or_default()
+}
+
+/// Transcription model for the OpenAI transcription endpoint
+pub fn openai_transcription_model(app: &tauri::AppHandle) -> String {
+    let model = model_defaults(app).transcription;
+    if model.starts_with("gemini") {
+        DEFAULT_OPENAI_TRANSCRIPTION_MODEL.to_string()
+    } else {
+        model
+    }
+}
+
+/// Model for Gemini-based transcription
+pub fn gemini_transcription_model(app: &tauri::AppHandle) -> String {
+    let model = model_defaults(app).transcription;
+    if model.starts_with("gemini") {
+        model
+    } else {
+        DEFAULT_GEMINI_TRANSCRIPTION_MODEL.to_string()
+    }
+}
+
+#[tauri::command]
+pub fn get_model_defaults(app: tauri::AppHandle) -> ModelDefaults {
+    model_defaults(&app)
+}
+
+#[tauri::command]
+pub fn set_model_defaults(app: tauri::AppHandle, defaults: ModelDefaults) -> Result<(), String> {
+    set_setting(&app, MODEL_DEFAULTS_KEY, &defaults)
+}
+
+// ============================================================================
+// Transcript Timestamps
+// ============================================================================
+
+const TRANSCRIPT_TIMESTAMPS_KEY: &str = "transcript_timestamps";
+
+/// Only whisper-1 returns `verbose_json` with timestamps; the gpt-4o
+/// transcription models are limited to plain text and json
+pub const DEFAULT_OPENAI_TIMESTAMP_MODEL: &str = "whisper-1";
+
+/// Timing carried by meeting transcripts from the OpenAI transcription path
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptTimestamps {
+    /// Plain text from the configured transcription model
+    #[default]
+    Off,
+    Segment,
+    Word,
+}
+
+pub fn transcript_timestamps(app: &tauri::AppHandle) -> TranscriptTimestamps {
+    get_setting(app, TRANSCRIPT_TIMESTAMPS_KEY).unwrap_or_default()
+}
+
+/// Transcription model for timestamped transcripts: the configured model if
+/// it supports them, whisper-1 otherwise
+pub fn openai_timestamp_model(app: &tauri::AppHandle) -> String {
+    let model = openai_transcription_model(app);
+    if model.starts_with("whisper") {
+        model
+    } else {
+        DEFAULT_OPENAI_TIMESTAMP_MODEL.to_string()
+    }
+}
+
+#[tauri::command]
+pub fn get_transcript_timestamps(app: tauri::AppHandle) -> TranscriptTimestamps {
+    transcript_timestamps(&app)
+}
+
+#[tauri::command]
+pub fn set_transcript_timestamps(
+    app: tauri::AppHandle,
+    timestamps: TranscriptTimestamps,
+) -> Result<(), String> {
+    set_setting(&app, TRANSCRIPT_TIMESTAMPS_KEY, &timestamps)
+}
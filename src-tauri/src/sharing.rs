@@ -0,0 +1,299 @@
+//! Portable single-file session bundles (`.sidestream`)
+//!
+//! A bundle holds the session JSON (messages carry their attachments inline as
+//! base64) plus any recorded voice-turn audio, archived snapshots of cited
+//! pages and the session's persona, so a colleague can open an exact copy.
+//! Bundles can optionally be encrypted with a passphrase (AES-256-GCM).
+//!
+//! The key is derived with Argon2id; the parameters are stored in the bundle
+//! so they can be raised later without breaking older files.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::commands::{get_stored_session, new_session_id, store_session};
+use crate::personas::{self, Persona};
+use crate::secure_storage;
+use crate::session_import;
+use crate::snapshots::{self, PageSnapshot};
+#[cfg(desktop)]
+use crate::tray;
+use crate::voice_turns;
+
+const BUNDLE_FORMAT: &str = "sidestream-session";
+const BUNDLE_VERSION: u32 = 1;
+
+const SALT_SIZE: usize = 16;
+
+const KDF_ALGORITHM: &str = "argon2id";
+/// Upper bounds on the stored parameters, so a crafted file can't make
+/// import allocate or spin without limit
+const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 64;
+const MAX_KDF_PARALLELISM: u32 = 16;
+
+/// Passphrase key derivation parameters, stored in encrypted bundles
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KdfParams {
+    algorithm: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// OWASP's recommended Argon2id minimum: 19 MiB, 2 passes, 1 lane
+    fn default() -> Self {
+        Self {
+            algorithm: KDF_ALGORITHM.to_string(),
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// Outer file structure; `payload` is either the plain contents or the
+/// base64 ciphertext of the serialized contents
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleFile {
+    format: String,
+    version: u32,
+    exported_at: String,
+    encrypted: bool,
+    /// Base64 salt for passphrase stretching (encrypted bundles only)
+    salt: Option<String>,
+    /// Key derivation parameters (encrypted bundles only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<KdfParams>,
+    payload: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleContents {
+    session: serde_json::Value,
+    /// Voice turn audio by filename, base64 encoded
+    #[serde(default)]
+    voice_audio: BTreeMap<String, String>,
+    /// The session's persona, so the recipient gets the same context
+    #[serde(default)]
+    persona: Option<Persona>,
+    /// Archived text of the pages the session cites
+    #[serde(default)]
+    snapshots: Vec<PageSnapshot>,
+}
+
+fn passphrase_key(passphrase: &str, salt: &[u8], kdf: &KdfParams) -> Result<[u8; 32], String> {
+    if kdf.algorithm != KDF_ALGORITHM
+        || kdf.memory_kib > MAX_KDF_MEMORY_KIB
+        || kdf.iterations > MAX_KDF_ITERATIONS
+        || kdf.parallelism > MAX_KDF_PARALLELISM
+    {
+        return Err("Unsupported passphrase settings in session file".to_string());
+    }
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| format!("Invalid passphrase settings in session file: {}", e))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+    Ok(key)
+}
+
+fn encode_bundle(contents: &BundleContents, passphrase: Option<&str>) -> Result<BundleFile, String> {
+    let exported_at = chrono::Utc::now().to_rfc3339();
+
+    match passphrase.filter(|p| !p.is_empty()) {
+        None => Ok(BundleFile {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            exported_at,
+            encrypted: false,
+            salt: None,
+            kdf: None,
+            payload: serde_json::to_value(contents).map_err(|e| e.to_string())?,
+        }),
+        Some(passphrase) => {
+            let mut salt = [0u8; SALT_SIZE];
+            rand::thread_rng().fill_bytes(&mut salt);
+            let kdf = KdfParams::default();
+            let plaintext = serde_json::to_vec(contents).map_err(|e| e.to_string())?;
+            let ciphertext = secure_storage::encrypt(&passphrase_key(passphrase, &salt, &kdf)?, &plaintext)?;
+            Ok(BundleFile {
+                format: BUNDLE_FORMAT.to_string(),
+                version: BUNDLE_VERSION,
+                exported_at,
+                encrypted: true,
+                salt: Some(BASE64.encode(salt)),
+                kdf: Some(kdf),
+                payload: serde_json::Value::String(BASE64.encode(ciphertext)),
+            })
+        }
+    }
+}
+
+fn decode_bundle(bundle: BundleFile, passphrase: Option<&str>) -> Result<BundleContents, String> {
+    if bundle.format != BUNDLE_FORMAT {
+        return Err("Not a Sidestream session file".to_string());
+    }
+    if bundle.version > BUNDLE_VERSION {
+        return Err("This session file was created by a newer version of Sidestream".to_string());
+    }
+
+    if !bundle.encrypted {
+        return serde_json::from_value(bundle.payload)
+            .map_err(|e| format!("Invalid session file: {}", e));
+    }
+
+    let passphrase = passphrase
+        .filter(|p| !p.is_empty())
+        .ok_or("This session file is encrypted; a passphrase is required")?;
+    let salt = BASE64
+        .decode(bundle.salt.as_deref().unwrap_or(""))
+        .map_err(|e| format!("Invalid session file: {}", e))?;
+    let ciphertext = BASE64
+        .decode(bundle.payload.as_str().unwrap_or(""))
+        .map_err(|e| format!("Invalid session file: {}", e))?;
+
+    let kdf = bundle.kdf.as_ref().ok_or("Invalid session file: missing passphrase settings")?;
+    let key = passphrase_key(passphrase, &salt, kdf)?;
+    let plaintext = secure_storage::decrypt(&key, &ciphertext)
+        .map_err(|_| "Wrong passphrase or corrupted session file".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Invalid session file: {}", e))
+}
+
+/// Write a session (and its voice audio) to a portable `.sidestream` file.
+/// Pass a passphrase to encrypt the bundle. Returns the written path.
+#[tauri::command]
+pub async fn share_session(
+    app: tauri::AppHandle,
+    session_id: String,
+    destination_path: String,
+    passphrase: Option<String>,
+) -> Result<String, String> {
+    let session = get_stored_session(&app, &session_id)?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+
+    let voice_audio = voice_turns::read_session_audio(&app, &session_id)
+        .into_iter()
+        .map(|(name, bytes)| (name, BASE64.encode(bytes)))
+        .collect();
+
+    let persona = personas::session_persona(&app, &session_id);
+    let snapshots = snapshots::read_session_snapshots(&app, &session_id);
+
+    let contents = BundleContents {
+        session,
+        voice_audio,
+        persona,
+        snapshots,
+    };
+    let bundle = encode_bundle(&contents, passphrase.as_deref())?;
+
+    let mut path = std::path::PathBuf::from(&destination_path);
+    if path.extension().is_none() {
+        path.set_extension("sidestream");
+    }
+    let json = serde_json::to_vec_pretty(&bundle).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write session file: {}", e))?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Import a `.sidestream` file as a new session and return it. The session
+/// always gets a fresh id, so importing never overwrites a local
+/// conversation (nor one imported from the same file before).
+#[tauri::command]
+pub async fn import_shared_session(
+    app: tauri::AppHandle,
+    path: String,
+    passphrase: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let raw = fs::read(&path).map_err(|e| format!("Failed to read session file: {}", e))?;
+    let bundle: BundleFile =
+        serde_json::from_slice(&raw).map_err(|_| "Not a Sidestream session file".to_string())?;
+    let contents = decode_bundle(bundle, passphrase.as_deref())?;
+
+    let mut session = contents.session;
+    if !session_import::valid_sidestream_session(&session) {
+        return Err("Invalid session file: the session has no id or unreadable messages".to_string());
+    }
+    let session_id = new_session_id();
+    session["id"] = serde_json::Value::String(session_id.clone());
+    if let Some(items) = session.get_mut("discoveryItems").and_then(|d| d.as_array_mut()) {
+        for item in items.iter_mut().filter(|item| item.is_object()) {
+            item["sessionId"] = serde_json::Value::String(session_id.clone());
+        }
+    }
+
+    let audio: Vec<(String, Vec<u8>)> = contents
+        .voice_audio
+        .into_iter()
+        .filter_map(|(name, b64)| BASE64.decode(b64).ok().map(|bytes| (name, bytes)))
+        .collect();
+    voice_turns::write_session_audio(&app, &session_id, &audio)?;
+    snapshots::write_session_snapshots(&app, &session_id, &contents.snapshots)?;
+    if let Some(persona) = contents.persona {
+        personas::import_persona(&app, persona)?;
+    }
+
+    store_session(&app, session.clone())?;
+    #[cfg(desktop)]
+    tray::refresh_recent_sessions(&app);
+    Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_contents() -> BundleContents {
+        BundleContents {
+            session: serde_json::json!({"id": "abc", "title": "Trip planning", "messages": []}),
+            voice_audio: BTreeMap::from([("t1.wav".to_string(), "UklGRg==".to_string())]),
+            persona: None,
+            snapshots: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn encrypted_bundle_round_trips_with_passphrase() {
+        let bundle = encode_bundle(&sample_contents(), Some("hunter2")).unwrap();
+        assert!(bundle.encrypted);
+        let decoded = decode_bundle(bundle, Some("hunter2")).unwrap();
+        assert_eq!(decoded.session["title"], "Trip planning");
+        assert_eq!(decoded.voice_audio["t1.wav"], "UklGRg==");
+    }
+
+    #[test]
+    fn encrypted_bundle_rejects_wrong_or_missing_passphrase() {
+        let bundle = encode_bundle(&sample_contents(), Some("hunter2")).unwrap();
+        let json = serde_json::to_value(&bundle).unwrap();
+        assert!(decode_bundle(bundle, Some("wrong")).is_err());
+        let bundle: BundleFile = serde_json::from_value(json).unwrap();
+        assert!(decode_bundle(bundle, None).is_err());
+    }
+
+    #[test]
+    fn excessive_kdf_parameters_are_refused() {
+        let kdf = KdfParams {
+            memory_kib: MAX_KDF_MEMORY_KIB * 4,
+            ..KdfParams::default()
+        };
+        assert!(passphrase_key("hunter2", &[0u8; SALT_SIZE], &kdf).is_err());
+    }
+
+    #[test]
+    fn plain_bundle_round_trips() {
+        let bundle = encode_bundle(&sample_contents(), None).unwrap();
+        assert!(!bundle.encrypted);
+        assert_eq!(decode_bundle(bundle, None).unwrap().session["id"], "abc");
+    }
+}
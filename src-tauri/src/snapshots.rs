@@ -0,0 +1,264 @@
+//! Snapshots of cited pages
+//!
+//! Research chats cite pages that later change or disappear. A snapshot stores
+//! the readable text of a cited page (scripts, navigation and boilerplate
+//! stripped) with the session, under `app_data_dir/snapshots/<session_id>`,
+//! so exported sessions remain verifiable.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::attachments::content_key;
+use crate::citations::decode_entities;
+use crate::proxy;
+
+/// Pages larger than this are truncated before extraction
+const MAX_PAGE_BYTES: usize = 4 * 1024 * 1024;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageSnapshot {
+    /// The cited URL
+    pub url: String,
+    /// The page's final URL after redirects
+    pub resolved_url: String,
+    pub title: Option<String>,
+    pub captured_at: String,
+    /// Readable text of the page
+    pub text: String,
+}
+
+fn snapshot_root_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join("snapshots"))
+}
+
+fn session_snapshot_dir(app: &tauri::AppHandle, session_id: &str) -> Result<PathBuf, String> {
+    let valid = !session_id.is_empty()
+        && session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Invalid session id: {}", session_id));
+    }
+    Ok(snapshot_root_dir(app)?.join(session_id))
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+/// Elements that never hold article text, removed with their contents
+const NOISE_TAGS: &[&str] = &[
+    "script", "style", "noscript", "svg", "nav", "header", "footer", "aside", "form", "iframe",
+    "template",
+];
+
+fn noise_regexes() -> &'static Vec<Regex> {
+    static RES: OnceLock<Vec<Regex>> = OnceLock::new();
+    RES.get_or_init(|| {
+        let mut res = vec![Regex::new(r"(?s)<!--.*?-->").unwrap()];
+        res.extend(
+            NOISE_TAGS
+                .iter()
+                .map(|tag| Regex::new(&format!(r"(?is)<{0}\b.*?</{0}\s*>", tag)).unwrap()),
+        );
+        res
+    })
+}
+
+/// Extract the readable text of an HTML page: prefer <article> or <main>,
+/// drop non-content elements, keep block structure as line breaks
+fn extract_readable_text(html: &str) -> (Option<String>, String) {
+    static TITLE: OnceLock<Regex> = OnceLock::new();
+    static ARTICLE: OnceLock<Regex> = OnceLock::new();
+    static MAIN: OnceLock<Regex> = OnceLock::new();
+    static BODY: OnceLock<Regex> = OnceLock::new();
+    static BLOCK: OnceLock<Regex> = OnceLock::new();
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    static BLANK_LINES: OnceLock<Regex> = OnceLock::new();
+
+    let title = regex(&TITLE, r"(?is)<title[^>]*>(.*?)</title>")
+        .captures(html)
+        .map(|c| decode_entities(&c[1]).split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty());
+
+    let mut cleaned = html.to_string();
+    for re in noise_regexes() {
+        cleaned = re.replace_all(&cleaned, " ").into_owned();
+    }
+
+    let content = regex(&ARTICLE, r"(?is)<article\b[^>]*>(.*)</article>")
+        .captures(&cleaned)
+        .or_else(|| regex(&MAIN, r"(?is)<main\b[^>]*>(.*)</main>").captures(&cleaned))
+        .or_else(|| regex(&BODY, r"(?is)<body\b[^>]*>(.*)</body>").captures(&cleaned))
+        .map(|c| c[1].to_string())
+        .unwrap_or_else(|| cleaned.to_string());
+
+    let with_breaks = regex(
+        &BLOCK,
+        r"(?i)</?(p|div|br|li|ul|ol|h[1-6]|tr|table|section|blockquote|pre)\b[^>]*>",
+    )
+    .replace_all(&content, "\n");
+    let text = decode_entities(&regex(&TAG, r"(?s)<[^>]+>").replace_all(&with_breaks, ""));
+
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    let text = regex(&BLANK_LINES, r"\n{3,}")
+        .replace_all(lines.join("\n").trim(), "\n\n")
+        .to_string();
+
+    (title, text)
+}
+
+async fn capture_page(url: &str) -> Result<PageSnapshot, String> {
+    let client = proxy::client_builder()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Sidestream")
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch {}: HTTP {}", url, response.status()));
+    }
+    let resolved_url = response.url().to_string();
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        body.extend_from_slice(&chunk);
+        if body.len() >= MAX_PAGE_BYTES {
+            break;
+        }
+    }
+
+    let (title, text) = extract_readable_text(&String::from_utf8_lossy(&body));
+    Ok(PageSnapshot {
+        url: url.to_string(),
+        resolved_url,
+        title,
+        captured_at: chrono::Utc::now().to_rfc3339(),
+        text,
+    })
+}
+
+/// Read all snapshots stored for a session
+pub fn read_session_snapshots(app: &tauri::AppHandle, session_id: &str) -> Vec<PageSnapshot> {
+    let Ok(dir) = session_snapshot_dir(app, session_id) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut snapshots: Vec<PageSnapshot> = entries
+        .flatten()
+        .filter_map(|entry| fs::read(entry.path()).ok())
+        .filter_map(|bytes| serde_json::from_slice(&bytes).ok())
+        .collect();
+    snapshots.sort_by(|a, b| a.captured_at.cmp(&b.captured_at));
+    snapshots
+}
+
+/// Store snapshots for a session (used when importing a shared session)
+pub fn write_session_snapshots(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    snapshots: &[PageSnapshot],
+) -> Result<(), String> {
+    if snapshots.is_empty() {
+        return Ok(());
+    }
+    let dir = session_snapshot_dir(app, session_id)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create snapshot directory: {}", e))?;
+    for snapshot in snapshots {
+        let json = serde_json::to_vec(snapshot).map_err(|e| e.to_string())?;
+        fs::write(dir.join(format!("{}.json", content_key(snapshot.url.as_bytes()))), json)
+            .map_err(|e| format!("Failed to write snapshot: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Remove a session's snapshots (called when the session is deleted)
+pub fn delete_session_snapshots(app: &tauri::AppHandle, session_id: &str) {
+    if let Ok(dir) = session_snapshot_dir(app, session_id) {
+        if dir.exists() {
+            if let Err(e) = fs::remove_dir_all(&dir) {
+                eprintln!("Failed to delete snapshots for {}: {}", session_id, e);
+            }
+        }
+    }
+}
+
+/// Remove all snapshots (called when the session store is cleared)
+pub fn delete_all_snapshots(app: &tauri::AppHandle) {
+    if let Ok(dir) = snapshot_root_dir(app) {
+        if dir.exists() {
+            if let Err(e) = fs::remove_dir_all(&dir) {
+                eprintln!("Failed to delete snapshots: {}", e);
+            }
+        }
+    }
+}
+
+/// Archive the readable text of cited pages with a session. URLs that already
+/// have a snapshot are skipped; pages that fail to load are reported in the
+/// log and skipped. Returns the snapshots captured by this call.
+#[tauri::command]
+pub async fn snapshot_citations(
+    app: tauri::AppHandle,
+    session_id: String,
+    urls: Vec<String>,
+) -> Result<Vec<PageSnapshot>, String> {
+    let dir = session_snapshot_dir(&app, &session_id)?;
+
+    let mut captured = Vec::new();
+    for url in urls {
+        if dir.join(format!("{}.json", content_key(url.as_bytes()))).exists() {
+            continue;
+        }
+        match capture_page(&url).await {
+            Ok(snapshot) => captured.push(snapshot),
+            Err(e) => eprintln!("Failed to snapshot {}: {}", url, e),
+        }
+    }
+
+    write_session_snapshots(&app, &session_id, &captured)?;
+    Ok(captured)
+}
+
+#[tauri::command]
+pub fn get_citation_snapshots(app: tauri::AppHandle, session_id: String) -> Vec<PageSnapshot> {
+    read_session_snapshots(&app, &session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_article_text_and_drops_boilerplate() {
+        let html = r#"<html><head><title>Moon &amp; Tides</title><script>var x = 1;</script></head>
+            <body><nav><a href="/">Home</a></nav>
+            <article><h1>Tides</h1><p>The moon   pulls the ocean.</p><aside>Ad</aside><p>Twice a day.</p></article>
+            <footer>Copyright</footer></body></html>"#;
+        let (title, text) = extract_readable_text(html);
+        assert_eq!(title.as_deref(), Some("Moon & Tides"));
+        assert_eq!(text, "Tides\n\nThe moon pulls the ocean.\n\nTwice a day.");
+    }
+}
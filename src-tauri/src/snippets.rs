@@ -0,0 +1,202 @@
+//! Prompt snippets
+//!
+//! Snippets are short, reusable prompt fragments ("explain like I'm new to
+//! this", a house style for code reviews, ...) with keywords for searching.
+//! Unlike system prompt layers they are part of what the user writes:
+//! `/name` in a user message is replaced by the snippet's text when a chat
+//! request is built, so stored messages keep the short reference.
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::commands::new_session_id;
+use crate::llm::ChatMessage;
+use crate::settings;
+
+const SNIPPETS_SETTING_KEY: &str = "snippets";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    /// Assigned on first save when empty
+    #[serde(default)]
+    pub id: String,
+    /// Referenced as `/name`; letters, digits, `-` and `_`
+    pub name: String,
+    pub text: String,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+fn load_snippets(app: &tauri::AppHandle) -> Vec<Snippet> {
+    settings::get_setting(app, SNIPPETS_SETTING_KEY).unwrap_or_default()
+}
+
+fn save_snippets(app: &tauri::AppHandle, snippets: &[Snippet]) -> Result<(), String> {
+    settings::set_setting(app, SNIPPETS_SETTING_KEY, &snippets)
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// `/name` at the start of the text or after whitespace. The trailing
+/// slash group lets paths like `/usr/bin` be left alone.
+fn reference_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(^|\s)/([A-Za-z0-9_-]+)(/?)").unwrap())
+}
+
+/// Replace `/name` references to known snippets (names match
+/// case-insensitively); anything else is left as written
+fn expand_text(text: &str, snippets: &[Snippet]) -> String {
+    reference_regex()
+        .replace_all(text, |caps: &Captures| {
+            let snippet = snippets
+                .iter()
+                .find(|s| s.name.eq_ignore_ascii_case(&caps[2]))
+                .filter(|_| caps[3].is_empty());
+            match snippet {
+                Some(snippet) => format!("{}{}", &caps[1], snippet.text),
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// Expand snippet references in user messages (plain text content and text
+/// blocks)
+pub fn expand_messages(app: &tauri::AppHandle, messages: Vec<ChatMessage>) -> Vec<ChatMessage> {
+    let snippets = load_snippets(app);
+    if snippets.is_empty() {
+        return messages;
+    }
+    messages
+        .into_iter()
+        .map(|mut message| {
+            if message.role != "user" {
+                return message;
+            }
+            if let Some(text) = message.content.as_str() {
+                message.content = serde_json::Value::String(expand_text(text, &snippets));
+            } else if let Some(blocks) = message.content.as_array_mut() {
+                for block in blocks.iter_mut().filter(|b| b["type"].as_str() == Some("text")) {
+                    if let Some(text) = block["text"].as_str() {
+                        block["text"] = serde_json::Value::String(expand_text(text, &snippets));
+                    }
+                }
+            }
+            message
+        })
+        .collect()
+}
+
+/// Snippets matching `query`, best first: name matches, then keyword
+/// matches, then matches in the text
+fn search(snippets: Vec<Snippet>, query: &str) -> Vec<Snippet> {
+    let query = query.trim().trim_start_matches('/').to_lowercase();
+    if query.is_empty() {
+        return snippets;
+    }
+    let score = |snippet: &Snippet| {
+        if snippet.name.to_lowercase().starts_with(&query) {
+            3
+        } else if snippet.keywords.iter().any(|k| k.to_lowercase().contains(&query)) {
+            2
+        } else if snippet.text.to_lowercase().contains(&query) {
+            1
+        } else {
+            0
+        }
+    };
+    let mut matches: Vec<(u8, Snippet)> = snippets
+        .into_iter()
+        .map(|snippet| (score(&snippet), snippet))
+        .filter(|(score, _)| *score > 0)
+        .collect();
+    matches.sort_by(|(a, x), (b, y)| b.cmp(a).then_with(|| x.name.cmp(&y.name)));
+    matches.into_iter().map(|(_, snippet)| snippet).collect()
+}
+
+#[tauri::command]
+pub fn list_snippets(app: tauri::AppHandle) -> Vec<Snippet> {
+    load_snippets(&app)
+}
+
+/// Snippets matching a query on name, keywords or text (for `/` completion)
+#[tauri::command]
+pub fn search_snippets(app: tauri::AppHandle, query: String) -> Vec<Snippet> {
+    search(load_snippets(&app), &query)
+}
+
+/// Create or update a snippet (matched by id). Returns the saved snippet.
+#[tauri::command]
+pub fn save_snippet(app: tauri::AppHandle, snippet: Snippet) -> Result<Snippet, String> {
+    let mut snippet = snippet;
+    snippet.name = snippet.name.trim().to_string();
+    if !valid_name(&snippet.name) {
+        return Err("Snippet names may only contain letters, digits, '-' and '_'".to_string());
+    }
+
+    let mut snippets = load_snippets(&app);
+    if snippets
+        .iter()
+        .any(|s| s.id != snippet.id && s.name.eq_ignore_ascii_case(&snippet.name))
+    {
+        return Err(format!("A snippet named /{} already exists", snippet.name));
+    }
+    if snippet.id.is_empty() {
+        snippet.id = new_session_id();
+    }
+
+    match snippets.iter_mut().find(|s| s.id == snippet.id) {
+        Some(existing) => *existing = snippet.clone(),
+        None => snippets.push(snippet.clone()),
+    }
+    save_snippets(&app, &snippets)?;
+
+    Ok(snippet)
+}
+
+#[tauri::command]
+pub fn delete_snippet(app: tauri::AppHandle, snippet_id: String) -> Result<(), String> {
+    let mut snippets = load_snippets(&app);
+    snippets.retain(|s| s.id != snippet_id);
+    save_snippets(&app, &snippets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(name: &str, text: &str, keywords: &[&str]) -> Snippet {
+        Snippet {
+            id: name.to_string(),
+            name: name.to_string(),
+            text: text.to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn expands_known_references_and_ranks_search() {
+        let snippets = vec![
+            snippet("eli5", "Explain it simply.", &[]),
+            snippet("review", "Review this code for bugs.", &["simple"]),
+        ];
+
+        assert_eq!(
+            expand_text("/ELI5 what is DNS? See /usr/bin and /unknown", &snippets),
+            "Explain it simply. what is DNS? See /usr/bin and /unknown"
+        );
+        assert_eq!(expand_text("and/eli5", &snippets), "and/eli5");
+
+        let names = |found: Vec<Snippet>| found.into_iter().map(|s| s.name).collect::<Vec<_>>();
+        assert_eq!(names(search(snippets.clone(), "simpl")), ["review", "eli5"]);
+        assert_eq!(names(search(snippets, "/rev")), ["review"]);
+    }
+}
@@ -0,0 +1,312 @@
+//! Speaking replies on the device
+//!
+//! `speak_text` synthesizes a reply with the effective readback settings and
+//! plays it through the default output device as the audio arrives, so a
+//! voice conversation needs no round trip through the webview's audio
+//! element. Long text is synthesized in sentence-aligned segments. The
+//! command resolves once playback has finished (or was stopped), which lets
+//! the frontend start listening again right away. `stop_speaking` cuts it
+//! short; starting to speak again stops whatever is playing. The read-aloud
+//! queue (`read_aloud`) plays its sentences through the same path.
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::tts::{effective_readback_settings, stream_speech_pcm, ReadbackSettings};
+
+/// Longest text sent in one speech request (OpenAI accepts up to 4096
+/// characters)
+pub(crate) const MAX_SEGMENT_CHARS: usize = 4000;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Time left for the device to play out its own buffer once ours is empty
+const DRAIN_TAIL: Duration = Duration::from_millis(200);
+
+// ============================================================================
+// State Types
+// ============================================================================
+
+/// Audio shared between the synthesis task and the output stream
+#[derive(Default)]
+pub(crate) struct Playback {
+    /// 16-bit mono samples not played yet
+    samples: VecDeque<i16>,
+    source_rate: u32,
+    /// Position between the first two samples, for resampling
+    position: f64,
+    /// No more audio will be added
+    input_finished: bool,
+    stopped: bool,
+    /// Output is silent and the buffer is held until resumed
+    paused: bool,
+    /// The output stream has closed
+    done: bool,
+    error: Option<String>,
+}
+
+impl Playback {
+    fn push(&mut self, sample_rate: u32, samples: &[i16]) {
+        self.source_rate = sample_rate;
+        self.samples.extend(samples);
+    }
+
+    /// The next output sample (-1.0..1.0) at `output_rate`, linearly
+    /// interpolated. None when the buffer has run dry.
+    fn next_sample(&mut self, output_rate: u32) -> Option<f32> {
+        let value = match (self.samples.front(), self.samples.get(1)) {
+            (Some(&a), Some(&b)) => f64::from(a) + (f64::from(b) - f64::from(a)) * self.position,
+            (Some(&a), None) if self.input_finished => {
+                self.samples.pop_front();
+                return Some(f32::from(a) / 32768.0);
+            }
+            _ => return None,
+        };
+        self.position += f64::from(self.source_rate) / f64::from(output_rate);
+        while self.position >= 1.0 && self.samples.len() > 1 {
+            self.samples.pop_front();
+            self.position -= 1.0;
+        }
+        Some((value / 32768.0) as f32)
+    }
+
+    fn finished(&self) -> bool {
+        self.stopped || (self.input_finished && self.samples.is_empty())
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.stopped = true;
+    }
+
+    pub(crate) fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+}
+
+/// Tauri-managed speech state: the playback currently running, if any
+pub struct SpeechState {
+    current: Mutex<Option<Arc<Mutex<Playback>>>>,
+}
+
+impl SpeechState {
+    pub fn new() -> Self {
+        Self {
+            current: Mutex::new(None),
+        }
+    }
+}
+
+impl Default for SpeechState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// Segmenting
+// ============================================================================
+
+/// Split text into pieces of at most `max_chars`, at sentence ends where
+/// possible and at word boundaries otherwise
+fn speech_segments(text: &str, max_chars: usize) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    for sentence in text.split_inclusive(['.', '!', '?', '\n']) {
+        if !current.is_empty() && current.chars().count() + sentence.chars().count() > max_chars {
+            segments.push(current.trim().to_string());
+            current.clear();
+        }
+        current.push_str(if current.is_empty() { sentence.trim_start() } else { sentence });
+        while current.chars().count() > max_chars {
+            let cut: String = current.chars().take(max_chars).collect();
+            let end = cut.rfind(' ').filter(|&i| i > 0).unwrap_or(cut.len());
+            segments.push(cut[..end].trim().to_string());
+            current = current[end..].to_string();
+        }
+    }
+    segments.push(current.trim().to_string());
+    segments.retain(|s| !s.is_empty());
+    segments
+}
+
+// ============================================================================
+// Output Thread
+// ============================================================================
+
+fn build_output_stream<T>(device: &Device, config: &StreamConfig, playback: Arc<Mutex<Playback>>) -> Result<Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = usize::from(config.channels);
+    let output_rate = config.sample_rate.0;
+    let err_fn = |err| eprintln!("Audio output stream error: {}", err);
+
+    device
+        .build_output_stream(
+            config,
+            move |out: &mut [T], _: &cpal::OutputCallbackInfo| {
+                let mut playback = playback.lock();
+                for frame in out.chunks_mut(channels) {
+                    let value = if playback.stopped || playback.paused {
+                        0.0
+                    } else {
+                        // Silence while waiting for more audio
+                        playback.next_sample(output_rate).unwrap_or(0.0)
+                    };
+                    frame.fill(T::from_sample(value));
+                }
+            },
+            err_fn,
+            None,
+        )
+        .map_err(|e| format!("Failed to build output stream: {}", e))
+}
+
+/// Play `playback` on the default output device until it is finished
+fn play_until_finished(playback: &Arc<Mutex<Playback>>) -> Result<(), String> {
+    let host = cpal::default_host();
+    let device = host
+        .default_output_device()
+        .ok_or("No output device available")?;
+    let supported_config = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get output config: {}", e))?;
+    let config: StreamConfig = supported_config.config();
+
+    let stream = match supported_config.sample_format() {
+        SampleFormat::I16 => build_output_stream::<i16>(&device, &config, playback.clone()),
+        SampleFormat::U16 => build_output_stream::<u16>(&device, &config, playback.clone()),
+        SampleFormat::F32 => build_output_stream::<f32>(&device, &config, playback.clone()),
+        format => Err(format!("Unsupported sample format: {:?}", format)),
+    }?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start output stream: {}", e))?;
+
+    while !playback.lock().finished() {
+        thread::sleep(POLL_INTERVAL);
+    }
+    if !playback.lock().stopped {
+        thread::sleep(DRAIN_TAIL);
+    }
+    Ok(())
+}
+
+fn run_playback_thread(playback: Arc<Mutex<Playback>>) {
+    let result = play_until_finished(&playback);
+    let mut guard = playback.lock();
+    if let Err(e) = result {
+        guard.error = Some(e);
+    }
+    guard.done = true;
+}
+
+/// Synthesize `segments` in order into `playback` and play it on the default
+/// output device. Resolves when playback has finished or was stopped.
+pub(crate) async fn play_segments(
+    app: &tauri::AppHandle,
+    segments: &[String],
+    settings: &ReadbackSettings,
+    playback: &Arc<Mutex<Playback>>,
+) -> Result<(), String> {
+    let thread_playback = playback.clone();
+    thread::spawn(move || run_playback_thread(thread_playback));
+
+    let mut result = Ok(());
+    for segment in segments {
+        result = stream_speech_pcm(app, segment, settings, |sample_rate, samples| {
+            let mut guard = playback.lock();
+            guard.push(sample_rate, samples);
+            !guard.stopped && !guard.done
+        })
+        .await;
+        let guard = playback.lock();
+        if result.is_err() || guard.stopped || guard.done {
+            break;
+        }
+    }
+    playback.lock().input_finished = true;
+
+    while !playback.lock().done {
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    result?;
+    let error = playback.lock().error.take();
+    match error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Speak `text` aloud with the session's (or global) readback settings.
+/// Resolves when playback finishes or is stopped.
+#[tauri::command]
+pub async fn speak_text(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, SpeechState>,
+    text: String,
+    session_id: Option<String>,
+) -> Result<(), String> {
+    let segments = speech_segments(&text, MAX_SEGMENT_CHARS);
+    if segments.is_empty() {
+        return Err("Nothing to read aloud".to_string());
+    }
+    let settings = effective_readback_settings(&app, session_id.as_deref());
+
+    let playback = Arc::new(Mutex::new(Playback::default()));
+    if let Some(previous) = state.current.lock().replace(playback.clone()) {
+        previous.lock().stopped = true;
+    }
+
+    let result = play_segments(&app, &segments, &settings, &playback).await;
+
+    {
+        let mut current = state.current.lock();
+        if current.as_ref().is_some_and(|c| Arc::ptr_eq(c, &playback)) {
+            *current = None;
+        }
+    }
+    result
+}
+
+/// Stop speaking (no-op when nothing is playing)
+#[tauri::command]
+pub fn stop_speaking(state: tauri::State<'_, SpeechState>) {
+    if let Some(playback) = state.current.lock().take() {
+        playback.lock().stopped = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resamples_and_segments_speech() {
+        let mut playback = Playback::default();
+        playback.push(24000, &[0, 16384, -16384]);
+        let played: Vec<Option<f32>> = (0..4).map(|_| playback.next_sample(48000)).collect();
+        assert_eq!(played, [Some(0.0), Some(0.25), Some(0.5), Some(0.0)]);
+        assert_eq!(playback.next_sample(48000), None);
+        playback.input_finished = true;
+        assert_eq!(playback.next_sample(48000), Some(-0.5));
+        assert!(playback.finished());
+
+        assert_eq!(
+            speech_segments("One two. Three four five! Six?", 16),
+            ["One two.", "Three four five!", "Six?"]
+        );
+        assert_eq!(speech_segments("alpha beta gamma", 11), ["alpha beta", "gamma"]);
+        assert!(speech_segments("  \n", 10).is_empty());
+    }
+}
@@ -0,0 +1,233 @@
+//! Server-sent event decoding and parse diagnostics
+//!
+//! `SseDecoder` turns the chunks of a provider response body into the data of
+//! each complete event. Only whole lines are decoded, so events and UTF-8
+//! characters split across chunks come through intact, and CRLF line endings,
+//! comments and `data:` without a space are accepted.
+//!
+//! The provider parsers count events they can't make sense of here (invalid
+//! JSON, unhandled event types, missing fields) instead of silently dropping
+//! them; `get_stream_parse_warnings` shows the counts.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    /// Bytes at the start of `buffer` already searched for a line ending
+    scanned: usize,
+    /// Data lines of the event being read
+    data: Vec<String>,
+    line_delimited: bool,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A decoder that ends an event at every `data:` line, for streams that
+    /// don't always separate events with a blank line (Gemini)
+    pub fn line_delimited() -> Self {
+        Self {
+            line_delimited: true,
+            ..Self::default()
+        }
+    }
+
+    /// Add a chunk of the body; returns the data of each event it completed
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        let mut start = 0;
+        let mut search_from = self.scanned;
+        while let Some(offset) = self.buffer[search_from..].iter().position(|&b| b == b'\n') {
+            let end = search_from + offset;
+            let line = self.buffer[start..end].to_vec();
+            self.process_line(&line, &mut events);
+            start = end + 1;
+            search_from = start;
+        }
+        self.buffer.drain(..start);
+        self.scanned = self.buffer.len();
+        events
+    }
+
+    /// End of the body: returns the last event if it wasn't closed by a blank line
+    pub fn finish(&mut self) -> Vec<String> {
+        let mut events = Vec::new();
+        let rest = std::mem::take(&mut self.buffer);
+        self.scanned = 0;
+        if !rest.is_empty() {
+            self.process_line(&rest, &mut events);
+        }
+        self.dispatch(&mut events);
+        events
+    }
+
+    fn process_line(&mut self, line: &[u8], events: &mut Vec<String>) {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            self.dispatch(events);
+            return;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        // Comment lines (keep-alives) start with a colon
+        if line.starts_with(':') {
+            return;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+        // `event:`, `id:` and `retry:` aren't needed: every provider repeats
+        // the event type inside the JSON data
+        if field == "data" {
+            self.data.push(value.to_string());
+            if self.line_delimited {
+                self.dispatch(events);
+            }
+        }
+    }
+
+    fn dispatch(&mut self, events: &mut Vec<String>) {
+        if !self.data.is_empty() {
+            events.push(self.data.join("\n"));
+            self.data.clear();
+        }
+    }
+}
+
+// ============================================================================
+// Parse warnings
+// ============================================================================
+
+/// Counts per (provider, warning)
+static PARSE_WARNINGS: Mutex<BTreeMap<(String, String), u64>> = Mutex::new(BTreeMap::new());
+
+/// Count an event a provider parser couldn't fully understand. The first
+/// occurrence of each warning is also logged.
+pub fn record_parse_warning(provider: &str, warning: impl Into<String>) {
+    let key = (provider.to_string(), warning.into());
+    let mut warnings = PARSE_WARNINGS.lock();
+    let count = warnings.entry(key.clone()).or_insert(0);
+    if *count == 0 {
+        eprintln!("[SSE] {}: {}", key.0, key.1);
+    }
+    *count += 1;
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseWarningCount {
+    pub provider: String,
+    pub warning: String,
+    pub count: u64,
+}
+
+/// Parse warnings since launch (or the last reset), most frequent first
+#[tauri::command]
+pub fn get_stream_parse_warnings() -> Vec<ParseWarningCount> {
+    let mut counts: Vec<ParseWarningCount> = PARSE_WARNINGS
+        .lock()
+        .iter()
+        .map(|((provider, warning), count)| ParseWarningCount {
+            provider: provider.clone(),
+            warning: warning.clone(),
+            count: *count,
+        })
+        .collect();
+    counts.sort_by_key(|c| std::cmp::Reverse(c.count));
+    counts
+}
+
+#[tauri::command]
+pub fn reset_stream_parse_warnings() {
+    PARSE_WARNINGS.lock().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn decode_in_pieces(mut decoder: SseDecoder, body: &[u8], splits: &[usize]) -> Vec<String> {
+        let mut events = Vec::new();
+        let mut start = 0;
+        for &split in splits {
+            events.extend(decoder.push(&body[start..split]));
+            start = split;
+        }
+        events.extend(decoder.push(&body[start..]));
+        events.extend(decoder.finish());
+        events
+    }
+
+    #[test]
+    fn decodes_the_same_events_wherever_the_body_is_split() {
+        let body = ": keep-alive\r\n\r\nevent: delta\r\ndata: {\"text\":\"héllo 🚀\"}\r\n\r\ndata:{\"a\":1}\ndata: {\"b\":2}\n\nid: 7\ndata: [DONE]";
+        let expected = vec![
+            "{\"text\":\"héllo 🚀\"}".to_string(),
+            "{\"a\":1}\n{\"b\":2}".to_string(),
+            "[DONE]".to_string(),
+        ];
+        let bytes = body.as_bytes();
+        assert_eq!(decode_in_pieces(SseDecoder::new(), bytes, &[]), expected);
+        for split in 1..bytes.len() {
+            assert_eq!(decode_in_pieces(SseDecoder::new(), bytes, &[split]), expected, "split at {}", split);
+        }
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..200 {
+            let mut splits: Vec<usize> = (0..rng.gen_range(1..8)).map(|_| rng.gen_range(0..bytes.len())).collect();
+            splits.sort();
+            assert_eq!(decode_in_pieces(SseDecoder::new(), bytes, &splits), expected);
+        }
+    }
+
+    #[test]
+    fn line_delimited_decoder_ends_events_at_each_data_line() {
+        let body = b"data: {\"a\":1}\r\ndata: {\"b\":2}\r\n";
+        assert_eq!(
+            decode_in_pieces(SseDecoder::line_delimited(), body, &[5]),
+            vec!["{\"a\":1}", "{\"b\":2}"]
+        );
+    }
+
+    #[test]
+    fn provider_parsers_survive_mangled_events() {
+        let samples = [
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#,
+            r#"{"type":"message_delta","delta":{"container":{"id":"c1"}},"usage":{"output_tokens":3}}"#,
+            r#"{"type":"response.output_text.done","text":"See sandbox:/mnt/data/a.csv","annotations":[{"type":"url_citation","url":"https://x.y","title":"X"}]}"#,
+            r#"{"type":"response.output_item.done","item":{"type":"code_interpreter_call","id":"ci","output":{"logs":"ok"}}}"#,
+            r#"{"candidates":[{"content":{"parts":[{"text":"Hi"},{"inlineData":{"mimeType":"image/png","data":"AA=="}}]},"finishReason":"STOP","groundingMetadata":{"groundingSupports":[{"segment":{"startIndex":0}}]}}]}"#,
+        ];
+        let mut rng = StdRng::seed_from_u64(42);
+        for sample in samples {
+            for _ in 0..300 {
+                let mut bytes = sample.as_bytes().to_vec();
+                match rng.gen_range(0..3) {
+                    0 => bytes.truncate(rng.gen_range(0..bytes.len())),
+                    1 => {
+                        let i = rng.gen_range(0..bytes.len());
+                        bytes[i] = rng.gen_range(b' '..=b'~');
+                    }
+                    _ => {
+                        let i = rng.gen_range(0..bytes.len());
+                        bytes.remove(i);
+                    }
+                }
+                let data = String::from_utf8_lossy(&bytes);
+                crate::providers::anthropic::parse_sse_event(&data);
+                crate::providers::openai::parse_sse_event(&data);
+                crate::providers::gemini::parse_sse_event(&data);
+            }
+        }
+    }
+}
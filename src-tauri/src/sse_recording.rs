@@ -0,0 +1,259 @@
+//! Recording and replay of provider response streams
+//!
+//! With stream recording turned on, the raw bytes of every chat response
+//! stream are written to `app_data_dir/sse-recordings`, one JSON line per
+//! chunk as it arrived. `replay_stream` feeds a recording back through the
+//! provider's stream handling and emits the same events to the window, so
+//! streaming bugs users report can be reproduced without calling the APIs.
+//! Replay is a development tool and only exists in debug builds.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+#[cfg(debug_assertions)]
+use std::io::{BufRead, BufReader};
+use std::io::Write;
+#[cfg(debug_assertions)]
+use std::path::Path;
+use std::path::PathBuf;
+#[cfg(debug_assertions)]
+use std::time::Duration;
+use std::time::Instant;
+use tauri::Manager;
+#[cfg(debug_assertions)]
+use tokio_util::sync::CancellationToken;
+
+use crate::incognito;
+#[cfg(debug_assertions)]
+use crate::llm_anthropic::stream_anthropic_response;
+#[cfg(debug_assertions)]
+use crate::llm_gemini::stream_gemini_response;
+#[cfg(debug_assertions)]
+use crate::llm_openai::stream_openai_response;
+#[cfg(debug_assertions)]
+use crate::llm_openrouter::stream_openrouter_response;
+use crate::settings;
+
+/// Chunks of a provider response body, as handed to the stream handlers
+pub type SseByteStream = BoxStream<'static, Result<Vec<u8>, String>>;
+
+const RECORD_SSE_STREAMS_KEY: &str = "record_sse_streams";
+
+/// First line of a recording
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordingHeader {
+    provider: String,
+    model: String,
+    recorded_at: String,
+}
+
+/// One chunk of the response body
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordedChunk {
+    /// Milliseconds since the response started
+    elapsed_ms: u64,
+    /// Base64 of the raw bytes, which may split a UTF-8 character
+    data: String,
+}
+
+pub fn recordings_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join("sse-recordings"))
+}
+
+/// Appends chunks to a recording file
+struct Recorder {
+    file: File,
+    started: Instant,
+    failed: bool,
+}
+
+impl Recorder {
+    fn create(app: &tauri::AppHandle, provider: &str, model: &str) -> Result<Self, String> {
+        let dir = recordings_dir(app)?;
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create recordings dir: {}", e))?;
+
+        let now = chrono::Utc::now();
+        let path = dir.join(format!("{}-{}.jsonl", now.format("%Y%m%dT%H%M%S%.3f"), provider));
+        let mut file = File::create(&path).map_err(|e| format!("Failed to create recording: {}", e))?;
+
+        let header = RecordingHeader {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            recorded_at: now.to_rfc3339(),
+        };
+        let line = serde_json::to_string(&header).map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write recording: {}", e))?;
+
+        Ok(Self {
+            file,
+            started: Instant::now(),
+            failed: false,
+        })
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        if self.failed {
+            return;
+        }
+        let chunk = RecordedChunk {
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            data: BASE64.encode(bytes),
+        };
+        let written = serde_json::to_string(&chunk)
+            .map_err(|e| e.to_string())
+            .and_then(|line| writeln!(self.file, "{}", line).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            eprintln!("Failed to record stream chunk, stopping recording: {}", e);
+            self.failed = true;
+        }
+    }
+}
+
+fn recording_enabled(app: &tauri::AppHandle) -> bool {
+    settings::get_setting(app, RECORD_SSE_STREAMS_KEY).unwrap_or(false)
+}
+
+/// The body of a streaming response, recorded to disk when recording is on
+pub fn response_stream(
+    app: &tauri::AppHandle,
+    provider: &str,
+    model: &str,
+    response: reqwest::Response,
+) -> SseByteStream {
+    let stream = response
+        .bytes_stream()
+        .map(|chunk| chunk.map(|bytes| bytes.to_vec()).map_err(|e| e.to_string()));
+
+    if !recording_enabled(app) || incognito::is_active() {
+        return stream.boxed();
+    }
+    match Recorder::create(app, provider, model) {
+        Ok(mut recorder) => stream
+            .inspect(move |chunk| {
+                if let Ok(bytes) = chunk {
+                    recorder.write(bytes);
+                }
+            })
+            .boxed(),
+        Err(e) => {
+            eprintln!("Failed to start stream recording: {}", e);
+            stream.boxed()
+        }
+    }
+}
+
+/// A recorded chunk's arrival time (ms since the response started) and bytes
+#[cfg(debug_assertions)]
+type TimedChunk = (u64, Vec<u8>);
+
+/// Read a recording: its header and chunks
+#[cfg(debug_assertions)]
+fn read_recording(path: &Path) -> Result<(RecordingHeader, Vec<TimedChunk>), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open recording: {}", e))?;
+    let mut lines = BufReader::new(file).lines();
+
+    let header_line = lines
+        .next()
+        .ok_or("Recording is empty")?
+        .map_err(|e| format!("Failed to read recording: {}", e))?;
+    let header: RecordingHeader =
+        serde_json::from_str(&header_line).map_err(|e| format!("Invalid recording header: {}", e))?;
+
+    let mut chunks = Vec::new();
+    for line in lines {
+        let line = line.map_err(|e| format!("Failed to read recording: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let chunk: RecordedChunk =
+            serde_json::from_str(&line).map_err(|e| format!("Invalid recorded chunk: {}", e))?;
+        let bytes = BASE64
+            .decode(&chunk.data)
+            .map_err(|e| format!("Invalid recorded chunk data: {}", e))?;
+        chunks.push((chunk.elapsed_ms, bytes));
+    }
+    Ok((header, chunks))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_stream_recording(app: tauri::AppHandle) -> bool {
+    recording_enabled(&app)
+}
+
+#[tauri::command]
+pub fn set_stream_recording(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set_setting(&app, RECORD_SSE_STREAMS_KEY, &enabled)
+}
+
+/// File names of the recorded streams, newest first
+#[tauri::command]
+pub fn list_stream_recordings(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let dir = recordings_dir(&app)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read recordings dir: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.ends_with(".jsonl"))
+        .collect();
+    names.sort_by(|a, b| b.cmp(a));
+    Ok(names)
+}
+
+/// Feed a recorded stream back through its provider's stream handling,
+/// emitting chat events for `turn_id` as if the response were live. `file` is
+/// a name from `list_stream_recordings` or a path. With `realtime` the chunks
+/// arrive with their recorded timing, otherwise all at once. Debug builds only.
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub async fn replay_stream(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    file: String,
+    turn_id: String,
+    realtime: Option<bool>,
+) -> Result<(), String> {
+    let path = PathBuf::from(&file);
+    let path = if path.is_absolute() {
+        path
+    } else {
+        recordings_dir(&app)?.join(path)
+    };
+    let (header, chunks) = read_recording(&path)?;
+
+    let started = tokio::time::Instant::now();
+    let realtime = realtime.unwrap_or(false);
+    let stream: SseByteStream = futures::stream::iter(chunks)
+        .then(move |(elapsed_ms, bytes)| async move {
+            if realtime {
+                tokio::time::sleep_until(started + Duration::from_millis(elapsed_ms)).await;
+            }
+            Ok(bytes)
+        })
+        .boxed();
+
+    // Without an API key, files generated in the recorded turn can't be
+    // downloaded; the handlers report that and carry on
+    let cancel_token = CancellationToken::new();
+    match header.provider.as_str() {
+        "anthropic" => Ok(stream_anthropic_response(&window, cancel_token, "", turn_id, stream).await?),
+        "openai" => Ok(stream_openai_response(&window, cancel_token, "", turn_id, None, stream).await?),
+        "google" => Ok(stream_gemini_response(&window, cancel_token, turn_id, stream).await?),
+        "openrouter" => Ok(stream_openrouter_response(&window, cancel_token, turn_id, stream).await?),
+        other => Err(format!("Unknown provider in recording: {}", other)),
+    }
+}
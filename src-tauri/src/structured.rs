@@ -0,0 +1,295 @@
+//! Structured (schema-constrained) completions
+//!
+//! `structured_completion` asks a model for JSON matching a JSON Schema using
+//! each provider's own mechanism: a forced tool call on Anthropic, a
+//! `json_schema` text format on OpenAI and `responseJsonSchema` on Gemini. The
+//! reply is validated against the schema (one retry with the validation error
+//! on failure), so callers get data rather than free text to parse.
+
+use serde_json::Value;
+
+use crate::commands::get_api_key_async;
+use crate::content_filters;
+use crate::llm::get_provider_for_model;
+use crate::providers::anthropic::AnthropicClient;
+use crate::providers::gemini::GeminiClient;
+use crate::providers::openai::{extract_output_text, OpenAIClient};
+use crate::settings;
+
+/// Name of the tool / format that carries the structured reply
+const OUTPUT_NAME: &str = "structured_output";
+
+/// Anthropic tool inputs and OpenAI formats must be objects; other schemas
+/// are wrapped in an object under this key
+const WRAPPED_VALUE_KEY: &str = "value";
+
+// ============================================================================
+// Schema Validation
+// ============================================================================
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Check `value` against the commonly used subset of JSON Schema: `type`,
+/// `enum`, `const`, `required`, `properties`, `additionalProperties: false`,
+/// `items`, `minItems`/`maxItems` and `anyOf`. Returns the first violation.
+pub fn validate_against_schema(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let at = if path.is_empty() { "$" } else { path };
+
+    if let Some(options) = schema["anyOf"].as_array() {
+        if !options.iter().any(|option| validate_against_schema(option, value, path).is_ok()) {
+            return Err(format!("{} matches none of the allowed schemas", at));
+        }
+    }
+
+    match &schema["type"] {
+        Value::String(expected) if !type_matches(expected, value) => {
+            return Err(format!("{} should be of type {}", at, expected));
+        }
+        Value::Array(types) if !types.iter().filter_map(|t| t.as_str()).any(|t| type_matches(t, value)) => {
+            return Err(format!("{} has an unexpected type", at));
+        }
+        _ => {}
+    }
+
+    if let Some(allowed) = schema["enum"].as_array() {
+        if !allowed.contains(value) {
+            return Err(format!("{} is not one of the allowed values", at));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            return Err(format!("{} should be {}", at, expected));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for key in schema["required"].as_array().into_iter().flatten().filter_map(|k| k.as_str()) {
+            if !object.contains_key(key) {
+                return Err(format!("{} is missing required property \"{}\"", at, key));
+            }
+        }
+        let properties = schema["properties"].as_object();
+        for (key, property) in object {
+            match properties.and_then(|p| p.get(key)) {
+                Some(property_schema) => {
+                    validate_against_schema(property_schema, property, &format!("{}.{}", at, key))?
+                }
+                None if schema["additionalProperties"] == Value::Bool(false) => {
+                    return Err(format!("{} has unexpected property \"{}\"", at, key));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        if let Some(min) = schema["minItems"].as_u64() {
+            if (items.len() as u64) < min {
+                return Err(format!("{} should have at least {} items", at, min));
+            }
+        }
+        if let Some(max) = schema["maxItems"].as_u64() {
+            if items.len() as u64 > max {
+                return Err(format!("{} should have at most {} items", at, max));
+            }
+        }
+        if schema["items"].is_object() {
+            for (index, item) in items.iter().enumerate() {
+                validate_against_schema(&schema["items"], item, &format!("{}[{}]", at, index))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse JSON from model text, tolerating code fences around it
+fn parse_json_reply(text: &str) -> Result<Value, String> {
+    let trimmed = text.trim();
+    let trimmed = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .map(|rest| rest.trim_end().trim_end_matches("```"))
+        .unwrap_or(trimmed);
+    serde_json::from_str(trimmed.trim()).map_err(|e| format!("Model did not return valid JSON: {}", e))
+}
+
+// ============================================================================
+// Provider Requests
+// ============================================================================
+
+/// Run one schema-constrained request. `schema` must have an object root for
+/// Anthropic and OpenAI (see `structured_completion`).
+async fn request_structured(
+    app: &tauri::AppHandle,
+    model: &str,
+    system_prompt: Option<&str>,
+    prompt: &str,
+    schema: &Value,
+) -> Result<Value, String> {
+    match get_provider_for_model(model) {
+        "openai" => {
+            let client = OpenAIClient::new(get_api_key_async(app, "openai").await?);
+            let mut input = Vec::new();
+            if let Some(system) = system_prompt {
+                input.push(serde_json::json!({"type": "message", "role": "system", "content": system}));
+            }
+            input.push(serde_json::json!({"type": "message", "role": "user", "content": prompt}));
+            let body = serde_json::json!({
+                "model": model,
+                "input": input,
+                "text": {"format": {"type": "json_schema", "name": OUTPUT_NAME, "schema": schema, "strict": false}}
+            });
+            let response = client.send_request(&body).await?;
+            parse_json_reply(&extract_output_text(&response))
+        }
+        "google" => {
+            let client = GeminiClient::new(get_api_key_async(app, "google").await?);
+            let mut body = serde_json::json!({
+                "contents": [{"role": "user", "parts": [{"text": prompt}]}],
+                "generationConfig": {"responseMimeType": "application/json", "responseJsonSchema": schema}
+            });
+            if let Some(system) = system_prompt {
+                body["systemInstruction"] = serde_json::json!({"parts": [{"text": system}]});
+            }
+            parse_json_reply(&client.send_request(model, &body).await?)
+        }
+        "openrouter" => Err("Structured output isn't supported for OpenRouter models".to_string()),
+        _ => {
+            let client = AnthropicClient::new(get_api_key_async(app, "anthropic").await?);
+            let mut body = serde_json::json!({
+                "model": model,
+                "max_tokens": 16000,
+                "messages": [{"role": "user", "content": prompt}],
+                "tools": [{
+                    "name": OUTPUT_NAME,
+                    "description": "Return the requested data.",
+                    "input_schema": schema
+                }],
+                "tool_choice": {"type": "tool", "name": OUTPUT_NAME}
+            });
+            if let Some(system) = system_prompt {
+                body["system"] = serde_json::json!(system);
+            }
+            let response = client.send_request(&body).await?;
+            response["content"]
+                .as_array()
+                .and_then(|blocks| blocks.iter().find(|b| b["type"].as_str() == Some("tool_use")))
+                .map(|block| block["input"].clone())
+                .ok_or_else(|| "Model did not return structured output".to_string())
+        }
+    }
+}
+
+/// Run a prompt and return JSON that validates against `schema`
+pub async fn structured_completion(
+    app: &tauri::AppHandle,
+    model: &str,
+    system_prompt: Option<&str>,
+    prompt: &str,
+    schema: &Value,
+) -> Result<Value, String> {
+    let needs_wrapping =
+        schema["type"].as_str() != Some("object") && get_provider_for_model(model) != "google";
+    let request_schema = if needs_wrapping {
+        serde_json::json!({
+            "type": "object",
+            "properties": {WRAPPED_VALUE_KEY: schema},
+            "required": [WRAPPED_VALUE_KEY]
+        })
+    } else {
+        schema.clone()
+    };
+
+    let mut prompt = content_filters::mask_text(app, prompt);
+    let mut last_error = String::new();
+    for _ in 0..2 {
+        let mut value = request_structured(app, model, system_prompt, &prompt, &request_schema).await?;
+        if needs_wrapping {
+            value = value[WRAPPED_VALUE_KEY].take();
+        }
+        match validate_against_schema(schema, &value, "") {
+            Ok(()) => return Ok(value),
+            Err(e) => {
+                // Retry once, telling the model what was wrong
+                prompt = format!(
+                    "{}\n\nA previous answer did not match the required schema ({}). Follow the schema exactly.",
+                    prompt, e
+                );
+                last_error = e;
+            }
+        }
+    }
+    Err(format!("Structured output did not match the schema: {}", last_error))
+}
+
+/// Run a prompt and return schema-validated JSON. Uses the summarization
+/// model default unless a model is given.
+#[tauri::command]
+pub async fn structured_request(
+    app: tauri::AppHandle,
+    schema: Value,
+    prompt: String,
+    model: Option<String>,
+    system_prompt: Option<String>,
+) -> Result<Value, String> {
+    let model = model.unwrap_or_else(|| settings::model_defaults(&app).summarization);
+    structured_completion(&app, &model, system_prompt.as_deref(), &prompt, &schema).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_nested_objects_arrays_and_enums() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": ["items"],
+            "additionalProperties": false,
+            "properties": {
+                "items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["kind"],
+                        "properties": {"kind": {"enum": ["a", "b"]}, "count": {"type": "integer"}}
+                    }
+                }
+            }
+        });
+
+        let valid = serde_json::json!({"items": [{"kind": "a", "count": 2}]});
+        assert!(validate_against_schema(&schema, &valid, "").is_ok());
+
+        let bad_enum = serde_json::json!({"items": [{"kind": "c"}]});
+        assert_eq!(
+            validate_against_schema(&schema, &bad_enum, ""),
+            Err("$.items[0].kind is not one of the allowed values".to_string())
+        );
+
+        let extra = serde_json::json!({"items": [], "other": 1});
+        assert!(validate_against_schema(&schema, &extra, "").is_err());
+        assert!(validate_against_schema(&schema, &serde_json::json!({}), "").is_err());
+    }
+
+    #[test]
+    fn parses_json_inside_code_fences() {
+        assert_eq!(
+            parse_json_reply("```json\n{\"a\": 1}\n```").unwrap(),
+            serde_json::json!({"a": 1})
+        );
+        assert_eq!(parse_json_reply("[1, 2]").unwrap(), serde_json::json!([1, 2]));
+    }
+}
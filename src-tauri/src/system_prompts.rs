@@ -0,0 +1,315 @@
+//! Layered system prompts
+//!
+//! On top of the app's own system prompt, users can set a global prompt (all
+//! chats), a workspace prompt, a persona and a per-session prompt. Layers are
+//! composed from most to least stable so edits to a session's prompt keep the
+//! cached prefix of the layers above it. The current date, time zone and
+//! locale (optional) come first, shared by every chat; only the day is given,
+//! so they change once a day. A preferred response language, if set globally or per
+//! session, is appended last.
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::commands::{get_stored_session, update_stored_session};
+use crate::personas::{load_personas, PERSONA_ID_FIELD};
+use crate::settings;
+
+const GLOBAL_PROMPT_KEY: &str = "global_system_prompt";
+const WORKSPACE_PROMPTS_KEY: &str = "workspace_system_prompts";
+
+/// Session field holding the session's own prompt layer
+pub const SESSION_PROMPT_FIELD: &str = "sessionSystemPrompt";
+/// Session field naming the workspace the session belongs to
+pub const WORKSPACE_ID_FIELD: &str = "workspaceId";
+
+const RESPONSE_LANGUAGE_KEY: &str = "response_language";
+/// Session field overriding the response language ("auto" turns it off)
+pub const RESPONSE_LANGUAGE_FIELD: &str = "responseLanguage";
+
+/// Override value meaning "answer in whatever language fits"
+const AUTO_LANGUAGE: &str = "auto";
+
+const DATE_CONTEXT_KEY: &str = "date_context";
+
+/// Whether to tell the model the current date, time zone and locale
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DateContextSettings {
+    pub enabled: bool,
+    /// BCP 47 locale such as "en-GB"; detected from the environment when unset
+    pub locale: Option<String>,
+}
+
+/// Join the non-empty layers in order, separated by blank lines
+fn compose_layers(layers: &[Option<&str>]) -> Option<String> {
+    let parts: Vec<&str> = layers
+        .iter()
+        .flatten()
+        .map(|layer| layer.trim())
+        .filter(|layer| !layer.is_empty())
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("\n\n"))
+    }
+}
+
+/// Instruction pinning the reply language; last in the prompt so it wins over
+/// examples or instructions written in other languages above it
+fn language_instruction(language: &str) -> String {
+    format!(
+        "Always respond in {}, regardless of the language of the user's message or any attached material, unless the user explicitly asks for a different language.",
+        language
+    )
+}
+
+/// The response language for a session: its override, else the global setting
+fn response_language(app: &tauri::AppHandle, session: &serde_json::Value) -> Option<String> {
+    let language = match session[RESPONSE_LANGUAGE_FIELD].as_str() {
+        Some(language) => language.to_string(),
+        None => settings::get_setting::<String>(app, RESPONSE_LANGUAGE_KEY)?,
+    };
+    Some(language).filter(|l| !l.trim().is_empty() && !l.eq_ignore_ascii_case(AUTO_LANGUAGE))
+}
+
+/// The system locale from the environment ("de_DE.UTF-8" → "de-DE")
+fn detected_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|value| value.split(['.', '@']).next().unwrap_or_default().replace('_', "-"))
+        .find(|locale| !locale.is_empty() && locale != "C" && locale != "POSIX")
+}
+
+/// The date/time zone/locale layer. Only the day is given, so the layer (and
+/// the cached prompt behind it) stays the same all day.
+fn date_context(now: DateTime<FixedOffset>, timezone: Option<&str>, locale: Option<&str>) -> String {
+    let zone = match timezone {
+        Some(timezone) => format!("{}, UTC{}", timezone, now.format("%:z")),
+        None => format!("UTC{}", now.format("%:z")),
+    };
+    let mut context = format!(
+        "The current date is {} ({}).",
+        now.format("%A, %-d %B %Y"),
+        zone
+    );
+    if let Some(locale) = locale {
+        context.push_str(&format!(" The user's locale is {}.", locale));
+    }
+    context
+}
+
+fn date_context_layer(app: &tauri::AppHandle) -> Option<String> {
+    let settings: DateContextSettings = settings::get_setting(app, DATE_CONTEXT_KEY).unwrap_or_default();
+    if !settings.enabled {
+        return None;
+    }
+    let locale = settings
+        .locale
+        .filter(|l| !l.trim().is_empty())
+        .or_else(detected_locale);
+    Some(date_context(
+        chrono::Local::now().fixed_offset(),
+        iana_time_zone::get_timezone().ok().as_deref(),
+        locale.as_deref(),
+    ))
+}
+
+fn global_prompt(app: &tauri::AppHandle) -> Option<String> {
+    settings::get_setting(app, GLOBAL_PROMPT_KEY)
+}
+
+fn workspace_prompts(app: &tauri::AppHandle) -> BTreeMap<String, String> {
+    settings::get_setting(app, WORKSPACE_PROMPTS_KEY).unwrap_or_default()
+}
+
+/// Compose the full system prompt for a request: the date context, the app's
+/// base prompt, then the global, workspace, persona and session layers and
+/// finally the response language instruction. The workspace, persona and
+/// language override are read from the stored session.
+pub fn layered_system_prompt(
+    app: &tauri::AppHandle,
+    base: Option<&str>,
+    session_id: Option<&str>,
+) -> Option<String> {
+    let session = session_id
+        .and_then(|id| get_stored_session(app, id).ok().flatten())
+        .unwrap_or_default();
+    let workspace_prompt = session[WORKSPACE_ID_FIELD]
+        .as_str()
+        .and_then(|id| workspace_prompts(app).remove(id));
+    let persona_prompt = session[PERSONA_ID_FIELD].as_str().and_then(|id| {
+        load_personas(app)
+            .into_iter()
+            .find(|p| p.id == id)
+            .map(|p| p.system_prompt)
+    });
+
+    let language = response_language(app, &session).map(|l| language_instruction(&l));
+    let date_context = date_context_layer(app);
+
+    compose_layers(&[
+        date_context.as_deref(),
+        base,
+        global_prompt(app).as_deref(),
+        workspace_prompt.as_deref(),
+        persona_prompt.as_deref(),
+        session[SESSION_PROMPT_FIELD].as_str(),
+        language.as_deref(),
+    ])
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_global_system_prompt(app: tauri::AppHandle) -> Option<String> {
+    global_prompt(&app)
+}
+
+/// Set the global prompt layer; None or blank clears it
+#[tauri::command]
+pub fn set_global_system_prompt(app: tauri::AppHandle, prompt: Option<String>) -> Result<(), String> {
+    match prompt.filter(|p| !p.trim().is_empty()) {
+        Some(prompt) => settings::set_setting(&app, GLOBAL_PROMPT_KEY, &prompt),
+        None => settings::delete_setting(&app, GLOBAL_PROMPT_KEY),
+    }
+}
+
+/// All workspace prompts, keyed by workspace id
+#[tauri::command]
+pub fn list_workspace_system_prompts(app: tauri::AppHandle) -> BTreeMap<String, String> {
+    workspace_prompts(&app)
+}
+
+#[tauri::command]
+pub fn get_workspace_system_prompt(app: tauri::AppHandle, workspace_id: String) -> Option<String> {
+    workspace_prompts(&app).remove(&workspace_id)
+}
+
+/// Set a workspace's prompt layer; None or blank clears it
+#[tauri::command]
+pub fn set_workspace_system_prompt(
+    app: tauri::AppHandle,
+    workspace_id: String,
+    prompt: Option<String>,
+) -> Result<(), String> {
+    let mut prompts = workspace_prompts(&app);
+    match prompt.filter(|p| !p.trim().is_empty()) {
+        Some(prompt) => {
+            prompts.insert(workspace_id, prompt);
+        }
+        None => {
+            prompts.remove(&workspace_id);
+        }
+    }
+    settings::set_setting(&app, WORKSPACE_PROMPTS_KEY, &prompts)
+}
+
+#[tauri::command]
+pub fn get_session_system_prompt(app: tauri::AppHandle, session_id: String) -> Result<Option<String>, String> {
+    Ok(get_stored_session(&app, &session_id)?
+        .and_then(|session| session[SESSION_PROMPT_FIELD].as_str().map(|s| s.to_string())))
+}
+
+/// Set a session's prompt layer; None or blank clears it
+#[tauri::command]
+pub fn set_session_system_prompt(
+    app: tauri::AppHandle,
+    session_id: String,
+    prompt: Option<String>,
+) -> Result<(), String> {
+    update_stored_session(&app, &session_id, |fields| {
+        match prompt.filter(|p| !p.trim().is_empty()) {
+            Some(prompt) => {
+                fields.insert(SESSION_PROMPT_FIELD.to_string(), serde_json::Value::String(prompt));
+            }
+            None => {
+                fields.remove(SESSION_PROMPT_FIELD);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_response_language(app: tauri::AppHandle) -> Option<String> {
+    settings::get_setting(&app, RESPONSE_LANGUAGE_KEY)
+}
+
+/// Set the language every reply should be in (e.g. "German"); None or blank clears it
+#[tauri::command]
+pub fn set_response_language(app: tauri::AppHandle, language: Option<String>) -> Result<(), String> {
+    match language.filter(|l| !l.trim().is_empty()) {
+        Some(language) => settings::set_setting(&app, RESPONSE_LANGUAGE_KEY, &language.trim()),
+        None => settings::delete_setting(&app, RESPONSE_LANGUAGE_KEY),
+    }
+}
+
+/// Override the response language for one session. "auto" disables the
+/// global preference for this session; None or blank removes the override.
+#[tauri::command]
+pub fn set_session_response_language(
+    app: tauri::AppHandle,
+    session_id: String,
+    language: Option<String>,
+) -> Result<(), String> {
+    update_stored_session(&app, &session_id, |fields| {
+        match language.filter(|l| !l.trim().is_empty()) {
+            Some(language) => {
+                fields.insert(
+                    RESPONSE_LANGUAGE_FIELD.to_string(),
+                    serde_json::Value::String(language.trim().to_string()),
+                );
+            }
+            None => {
+                fields.remove(RESPONSE_LANGUAGE_FIELD);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_date_context_settings(app: tauri::AppHandle) -> DateContextSettings {
+    settings::get_setting(&app, DATE_CONTEXT_KEY).unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn set_date_context_settings(app: tauri::AppHandle, date_context: DateContextSettings) -> Result<(), String> {
+    settings::set_setting(&app, DATE_CONTEXT_KEY, &date_context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_compose_in_order_skipping_blanks() {
+        assert_eq!(
+            compose_layers(&[Some("base"), None, Some("  "), Some("session\n")]),
+            Some("base\n\nsession".to_string())
+        );
+        assert_eq!(compose_layers(&[None, Some("")]), None);
+    }
+
+    #[test]
+    fn date_context_names_day_zone_and_locale() {
+        let now = DateTime::parse_from_rfc3339("2026-10-16T14:05:09+02:00").unwrap();
+        assert_eq!(
+            date_context(now, Some("Europe/Berlin"), Some("de-DE")),
+            "The current date is Friday, 16 October 2026 (Europe/Berlin, UTC+02:00). \
+             The user's locale is de-DE."
+        );
+        assert_eq!(
+            date_context(now, None, None),
+            "The current date is Friday, 16 October 2026 (UTC+02:00)."
+        );
+    }
+}
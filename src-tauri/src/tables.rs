@@ -0,0 +1,351 @@
+//! Table extraction from responses
+//!
+//! When a turn finishes, its response is scanned for markdown tables and
+//! fenced CSV blocks. The tables found are kept for recent turns and
+//! announced with a `chat-tables` event; `export_table` saves one as CSV or
+//! XLSX through the save dialog. Both formats are written here, the XLSX as a
+//! minimal single-sheet workbook.
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Write};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::commands::stored_sessions;
+use crate::discovery_context::message_text;
+use crate::session_events;
+
+/// Turns whose tables are kept for export without a session lookup
+const MAX_CACHED_TURNS: usize = 50;
+
+/// Tables of recent turns, oldest first
+static TURN_TABLES: RwLock<Vec<(String, Vec<Table>)>> = RwLock::new(Vec::new());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// A table as announced to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TableSummary {
+    index: usize,
+    headers: Vec<String>,
+    row_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TablesEvent {
+    turn_id: String,
+    tables: Vec<TableSummary>,
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Cells of a markdown table row (`| a | b |` or `a | b`); `\|` is a
+/// literal pipe
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    let mut cells = vec![String::new()];
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                chars.next();
+                cells.last_mut().unwrap().push('|');
+            }
+            '|' => cells.push(String::new()),
+            c => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells.into_iter().map(|cell| cell.trim().to_string()).collect()
+}
+
+/// `|---|:--:|` style separator under a table header
+fn is_separator_row(line: &str) -> bool {
+    let cells = table_cells(line);
+    line.contains('-')
+        && cells
+            .iter()
+            .all(|c| !c.is_empty() && c.chars().all(|ch| matches!(ch, '-' | ':' | ' ')))
+}
+
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    records
+}
+
+/// Markdown tables and fenced CSV blocks in a response, in order
+pub fn parse_tables(text: &str) -> Vec<Table> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut tables = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim_start();
+        if let Some(info) = line.strip_prefix("```") {
+            let end = lines[i + 1..]
+                .iter()
+                .position(|l| l.trim_start().starts_with("```"))
+                .map_or(lines.len(), |p| i + 1 + p);
+            if info.trim().eq_ignore_ascii_case("csv") {
+                let mut records = csv_records(&lines[i + 1..end].join("\n"));
+                if !records.is_empty() {
+                    let headers = records.remove(0);
+                    tables.push(Table { headers, rows: records });
+                }
+            }
+            i = end + 1;
+            continue;
+        }
+        if line.contains('|') && lines.get(i + 1).is_some_and(|next| is_separator_row(next)) {
+            let headers = table_cells(line);
+            let mut rows = Vec::new();
+            i += 2;
+            while i < lines.len() && lines[i].contains('|') && !lines[i].trim().is_empty() {
+                let mut row = table_cells(lines[i]);
+                row.resize(headers.len(), String::new());
+                rows.push(row);
+                i += 1;
+            }
+            tables.push(Table { headers, rows });
+            continue;
+        }
+        i += 1;
+    }
+    tables
+}
+
+// ============================================================================
+// Writing
+// ============================================================================
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_csv(table: &Table) -> String {
+    std::iter::once(&table.headers)
+        .chain(&table.rows)
+        .map(|row| row.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","))
+        .map(|line| line + "\r\n")
+        .collect()
+}
+
+/// Spreadsheet column name for a zero-based index (0 → A, 26 → AA)
+fn column_name(index: usize) -> String {
+    let mut name = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        name.push(b'A' + ((n - 1) % 26) as u8);
+        n = (n - 1) / 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn sheet_xml(table: &Table) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+    );
+    for (r, row) in std::iter::once(&table.headers).chain(&table.rows).enumerate() {
+        xml.push_str(&format!(r#"<row r="{}">"#, r + 1));
+        for (c, value) in row.iter().enumerate() {
+            let cell = format!("{}{}", column_name(c), r + 1);
+            // Numbers (not the header row) are stored as numbers so they sum and sort
+            match value.replace(',', "").parse::<f64>() {
+                Ok(number) if r > 0 && number.is_finite() => {
+                    xml.push_str(&format!(r#"<c r="{}"><v>{}</v></c>"#, cell, number))
+                }
+                _ => xml.push_str(&format!(
+                    r#"<c r="{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                    cell,
+                    xml_escape(value)
+                )),
+            }
+        }
+        xml.push_str("</row>");
+    }
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+fn to_xlsx(table: &Table) -> Result<Vec<u8>, String> {
+    let parts = [
+        (
+            "[Content_Types].xml",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#.to_string(),
+        ),
+        (
+            "_rels/.rels",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_string(),
+        ),
+        (
+            "xl/workbook.xml",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Table" sheetId="1" r:id="rId1"/></sheets></workbook>"#.to_string(),
+        ),
+        (
+            "xl/_rels/workbook.xml.rels",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#.to_string(),
+        ),
+        ("xl/worksheets/sheet1.xml", sheet_xml(table)),
+    ];
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for (name, content) in parts {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(content.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let cursor = zip.finish().map_err(|e| e.to_string())?;
+    Ok(cursor.into_inner())
+}
+
+// ============================================================================
+// Turns
+// ============================================================================
+
+/// Extract the tables of a finished turn, keep them for export and tell the
+/// frontend about them
+pub fn record_turn(window: &tauri::Window, turn_id: &str, text: &str) {
+    let tables = parse_tables(text);
+    if tables.is_empty() {
+        return;
+    }
+    let event = TablesEvent {
+        turn_id: turn_id.to_string(),
+        tables: tables
+            .iter()
+            .enumerate()
+            .map(|(index, table)| TableSummary {
+                index,
+                headers: table.headers.clone(),
+                row_count: table.rows.len(),
+            })
+            .collect(),
+    };
+    {
+        let mut cache = TURN_TABLES.write();
+        cache.retain(|(id, _)| id != turn_id);
+        cache.push((turn_id.to_string(), tables));
+        if cache.len() > MAX_CACHED_TURNS {
+            cache.remove(0);
+        }
+    }
+    if let Err(err) = session_events::emit(window, turn_id, "chat-tables", event) {
+        eprintln!("Failed to emit chat-tables event: {}", err);
+    }
+}
+
+/// Tables of a turn: from the cache, or parsed from the stored reply
+fn turn_tables(app: &tauri::AppHandle, turn_id: &str) -> Result<Vec<Table>, String> {
+    if let Some((_, tables)) = TURN_TABLES.read().iter().find(|(id, _)| id == turn_id) {
+        return Ok(tables.clone());
+    }
+    let reply = stored_sessions(app)?.into_iter().find_map(|session| {
+        session["messages"].as_array()?.iter().find_map(|m| {
+            (m["turnId"].as_str() == Some(turn_id) && m["role"].as_str() == Some("assistant"))
+                .then(|| message_text(&m["content"]))
+        })
+    });
+    let reply = reply.ok_or_else(|| format!("Turn not found: {}", turn_id))?;
+    Ok(parse_tables(&reply))
+}
+
+/// Save a table from a turn's response as "csv" or "xlsx" through the save
+/// dialog. Returns the saved path, or None if the dialog was cancelled.
+#[tauri::command]
+pub async fn export_table(
+    app: tauri::AppHandle,
+    turn_id: String,
+    index: usize,
+    format: String,
+) -> Result<Option<String>, String> {
+    let table = turn_tables(&app, &turn_id)?
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| format!("Table {} not found in turn {}", index, turn_id))?;
+    let (bytes, filter) = match format.as_str() {
+        "csv" => (to_csv(&table).into_bytes(), "CSV"),
+        "xlsx" => (to_xlsx(&table)?, "Excel workbook"),
+        _ => return Err(format!("Unsupported table format: {}", format)),
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter(filter, &[format.as_str()])
+        .set_file_name(format!("table-{}.{}", index + 1, format))
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    let Some(path) = rx.await.map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| e.to_string())?;
+    std::fs::write(&path, bytes).map_err(|e| format!("Failed to save table: {}", e))?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_markdown_and_csv_tables() {
+        let text = "Results:\n\n| Name | Score |\n|:-----|------:|\n| Ada | 1,200 |\n| Bo \\| Co | 3 |\n\n\
+                    ```python\nprint('a | b')\n|---|\n```\n\n```csv\nx,y\n\"1,5\",\"say \"\"hi\"\"\"\n```\n";
+        let tables = parse_tables(text);
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].headers, ["Name", "Score"]);
+        assert_eq!(tables[0].rows, [vec!["Ada", "1,200"], vec!["Bo | Co", "3"]]);
+        assert_eq!(tables[1].rows, [vec!["1,5", "say \"hi\""]]);
+
+        assert_eq!(to_csv(&tables[1]), "x,y\r\n\"1,5\",\"say \"\"hi\"\"\"\r\n");
+        assert!(sheet_xml(&tables[0]).contains(r#"<c r="B2"><v>1200</v></c>"#));
+        assert_eq!((column_name(0), column_name(25), column_name(27)), ("A".into(), "Z".into(), "AB".into()));
+    }
+}
@@ -0,0 +1,147 @@
+//! Thinking transcripts
+//!
+//! Thinking and reasoning-summary text streams into the UI while a reply is
+//! generated and is otherwise not kept in full. With `persist_thinking` on,
+//! each turn's complete thinking is stored on the session under
+//! `thinkingTranscripts` (by turn id, apart from the messages) and included
+//! in Markdown and HTML exports, so how an answer was reached can be audited.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::commands::update_stored_session;
+use crate::settings;
+
+const PERSIST_THINKING_KEY: &str = "persist_thinking";
+
+/// Session field holding the transcripts, keyed by turn id
+pub const THINKING_TRANSCRIPTS_FIELD: &str = "thinkingTranscripts";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThinkingTranscript {
+    pub model: String,
+    pub text: String,
+    pub recorded_at: String,
+}
+
+fn persist_enabled(app: &tauri::AppHandle) -> bool {
+    settings::get_setting(app, PERSIST_THINKING_KEY).unwrap_or(false)
+}
+
+/// Store a finished turn's thinking on its session, if persistence is on
+pub fn record(app: &tauri::AppHandle, session_id: &str, turn_id: &str, model: &str, thinking: &str) {
+    if thinking.trim().is_empty() || !persist_enabled(app) {
+        return;
+    }
+    let transcript = ThinkingTranscript {
+        model: model.to_string(),
+        text: thinking.to_string(),
+        recorded_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let stored = update_stored_session(app, session_id, |fields| {
+        let transcripts = fields
+            .entry(THINKING_TRANSCRIPTS_FIELD)
+            .or_insert_with(|| serde_json::json!({}));
+        if !transcripts.is_object() {
+            *transcripts = serde_json::json!({});
+        }
+        transcripts[turn_id] = serde_json::to_value(&transcript).map_err(|e| e.to_string())?;
+        Ok(())
+    });
+    if let Err(e) = stored {
+        eprintln!("Failed to store thinking transcript: {}", e);
+    }
+}
+
+/// A stored session's transcripts in the order of its turns
+fn transcripts_in_turn_order(session: &serde_json::Value) -> Vec<ThinkingTranscript> {
+    let mut transcripts: BTreeMap<String, ThinkingTranscript> =
+        serde_json::from_value(session[THINKING_TRANSCRIPTS_FIELD].clone()).unwrap_or_default();
+    session["messages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|message| message["role"].as_str() == Some("assistant"))
+        .filter_map(|message| transcripts.remove(message["turnId"].as_str()?))
+        .collect()
+}
+
+/// The transcripts as a Markdown appendix; empty when there are none
+pub fn markdown_section(session: &serde_json::Value) -> String {
+    let transcripts = transcripts_in_turn_order(session);
+    if transcripts.is_empty() {
+        return String::new();
+    }
+    let mut section = String::from("\n---\n\n## Thinking transcripts\n");
+    for (index, transcript) in transcripts.iter().enumerate() {
+        section.push_str(&format!(
+            "\n### Reply {} ({})\n\n````text\n{}\n````\n",
+            index + 1,
+            transcript.model,
+            transcript.text.trim_end()
+        ));
+    }
+    section
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The transcripts as an HTML section; empty when there are none
+pub fn html_section(session: &serde_json::Value) -> String {
+    let transcripts = transcripts_in_turn_order(session);
+    if transcripts.is_empty() {
+        return String::new();
+    }
+    let mut section = String::from("<section class=\"thinking-transcripts\"><hr><h2>Thinking transcripts</h2>");
+    for (index, transcript) in transcripts.iter().enumerate() {
+        section.push_str(&format!(
+            "<h3>Reply {} ({})</h3><pre style=\"white-space: pre-wrap\">{}</pre>",
+            index + 1,
+            escape_html(&transcript.model),
+            escape_html(transcript.text.trim_end())
+        ));
+    }
+    section.push_str("</section>");
+    section
+}
+
+#[tauri::command]
+pub fn get_persist_thinking(app: tauri::AppHandle) -> bool {
+    persist_enabled(&app)
+}
+
+#[tauri::command]
+pub fn set_persist_thinking(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set_setting(&app, PERSIST_THINKING_KEY, &enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_follow_turn_order_and_escape_html() {
+        let transcript = |text: &str| serde_json::json!({"model": "m", "text": text, "recordedAt": ""});
+        let session = serde_json::json!({
+            "messages": [
+                {"role": "user", "turnId": "b"},
+                {"role": "assistant", "turnId": "b"},
+                {"role": "assistant", "turnId": "a"},
+                {"role": "assistant", "turnId": "no-thinking"}
+            ],
+            "thinkingTranscripts": {"a": transcript("second <b>"), "b": transcript("first")}
+        });
+
+        let markdown = markdown_section(&session);
+        assert!(markdown.find("first").unwrap() < markdown.find("second").unwrap());
+        assert!(markdown.contains("### Reply 2 (m)"));
+        assert!(html_section(&session).contains("second &lt;b&gt;"));
+        assert_eq!(markdown_section(&serde_json::json!({"messages": []})), "");
+    }
+}
@@ -0,0 +1,165 @@
+//! Token counting
+//!
+//! `count_tokens` tells the UI how many input tokens a conversation will
+//! take before it is sent, for a context-usage meter. OpenAI models are
+//! counted locally with their tokenizer (o200k_base); Claude models are
+//! counted by Anthropic's count_tokens endpoint, falling back to an
+//! estimate without a key or connection; other models are estimated at
+//! about four characters per token. Attachments other than text are
+//! estimated everywhere except Anthropic's count: a flat figure per image
+//! and per PDF page.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tiktoken_rs::o200k_base_singleton;
+
+use crate::commands::get_api_key_async;
+use crate::llm::{get_provider_for_model, ChatMessage};
+use crate::message_format::{anthropic as anthropic_format, AttachmentKind, Message, Part, Source};
+use crate::providers::anthropic::AnthropicClient;
+use crate::request_validation::pdf_page_count;
+
+/// Formatting tokens each message adds around its content
+const MESSAGE_OVERHEAD_TOKENS: u64 = 4;
+
+/// Estimated tokens for an image (roughly a 1000×1000 image)
+const IMAGE_TOKENS: u64 = 1_000;
+
+/// Estimated tokens per PDF page, counting its text and page image
+const PDF_PAGE_TOKENS: u64 = 1_500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CountMethod {
+    /// Counted with the model's tokenizer
+    Tokenizer,
+    /// Counted by the provider
+    Provider,
+    /// Estimated from the text length
+    Estimate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenCount {
+    pub tokens: u64,
+    pub method: CountMethod,
+}
+
+fn estimate_text(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+fn tokenize_text(text: &str) -> u64 {
+    o200k_base_singleton().lock().encode_with_special_tokens(text).len() as u64
+}
+
+/// Tokens for a part; text goes through `count_text`
+fn part_tokens(part: &Part, count_text: &impl Fn(&str) -> u64) -> u64 {
+    match part {
+        Part::Text(text) => count_text(text),
+        Part::Attachment(attachment) => match (attachment.kind, &attachment.source) {
+            (AttachmentKind::Image, _) => IMAGE_TOKENS,
+            (_, Source::Base64 { media_type, data }) if media_type == "application/pdf" => {
+                pdf_page_count(data).unwrap_or(1) as u64 * PDF_PAGE_TOKENS
+            }
+            // Other files go as text, about three bytes per four base64 characters
+            (_, Source::Base64 { data, .. }) => (data.len() as u64 * 3 / 4).div_ceil(4),
+            (_, Source::Url(_) | Source::FileId(_)) => PDF_PAGE_TOKENS,
+        },
+        Part::Other(block) => count_text(&block.to_string()),
+    }
+}
+
+/// Tokens for a conversation, with text counted by `count_text`
+fn conversation_tokens(messages: &[Message], system_prompt: Option<&str>, count_text: impl Fn(&str) -> u64) -> u64 {
+    let system = system_prompt.map_or(0, |prompt| count_text(prompt) + MESSAGE_OVERHEAD_TOKENS);
+    let messages: u64 = messages
+        .iter()
+        .map(|message| {
+            let content: u64 = message.parts.iter().map(|part| part_tokens(part, &count_text)).sum();
+            content + MESSAGE_OVERHEAD_TOKENS
+        })
+        .sum();
+    system + messages
+}
+
+/// Count with Anthropic's count_tokens endpoint
+async fn count_with_anthropic(
+    app: &tauri::AppHandle,
+    model: &str,
+    messages: &[Message],
+    system_prompt: Option<&str>,
+) -> Result<u64, String> {
+    let api_key = get_api_key_async(app, "anthropic").await?;
+    let messages: Vec<Value> = messages
+        .iter()
+        .map(|m| serde_json::json!({"role": m.role.as_str(), "content": anthropic_format::content(&m.parts)}))
+        .collect();
+    let mut body = serde_json::json!({"model": model, "messages": messages});
+    if let Some(system) = system_prompt {
+        body["system"] = Value::String(system.to_string());
+    }
+    Ok(AnthropicClient::new(api_key).count_tokens(&body).await?)
+}
+
+/// Input tokens `messages` (and the system prompt) will take with `model`
+#[tauri::command]
+pub async fn count_tokens(
+    app: tauri::AppHandle,
+    model: String,
+    messages: Vec<ChatMessage>,
+    system_prompt: Option<String>,
+) -> Result<TokenCount, String> {
+    let messages: Vec<Message> = messages
+        .iter()
+        .map(|m| Message::new(&m.role, &m.content))
+        .collect();
+    let system_prompt = system_prompt.as_deref();
+
+    match get_provider_for_model(&model) {
+        "openai" => Ok(TokenCount {
+            tokens: conversation_tokens(&messages, system_prompt, tokenize_text),
+            method: CountMethod::Tokenizer,
+        }),
+        "anthropic" => match count_with_anthropic(&app, &model, &messages, system_prompt).await {
+            Ok(tokens) => Ok(TokenCount {
+                tokens,
+                method: CountMethod::Provider,
+            }),
+            Err(err) => {
+                eprintln!("Failed to count tokens with Anthropic, estimating: {}", err);
+                Ok(TokenCount {
+                    tokens: conversation_tokens(&messages, system_prompt, estimate_text),
+                    method: CountMethod::Estimate,
+                })
+            }
+        },
+        _ => Ok(TokenCount {
+            tokens: conversation_tokens(&messages, system_prompt, estimate_text),
+            method: CountMethod::Estimate,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_text_and_estimates_attachments() {
+        let messages = vec![
+            Message::new("user", &serde_json::json!([
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}},
+                {"type": "text", "text": "What is in this picture?"}
+            ])),
+            Message::new("assistant", &Value::String("A cat.".to_string())),
+        ];
+        let tokenized = conversation_tokens(&messages, Some("Be brief."), tokenize_text);
+        // "Be brief." 3, "What is in this picture?" 6, "A cat." 3
+        assert_eq!(tokenized, 3 + 6 + 3 + IMAGE_TOKENS + 3 * MESSAGE_OVERHEAD_TOKENS);
+
+        let estimated = conversation_tokens(&messages, None, estimate_text);
+        assert_eq!(estimated, 6 + 2 + IMAGE_TOKENS + 2 * MESSAGE_OVERHEAD_TOKENS);
+    }
+}
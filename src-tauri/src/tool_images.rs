@@ -0,0 +1,159 @@
+//! Re-attaching generated images to follow-up questions
+//!
+//! Charts made by code execution live in the provider's container (or only
+//! in our session once a Gemini turn is over), so a follow-up like "make the
+//! x-axis logarithmic" can reach a model that never sees the chart. When
+//! enabled, the images generated in the reply just before the new user
+//! message are attached to that message as image blocks, within size limits.
+//! Stored messages keep their original content.
+
+use serde_json::Value;
+
+use crate::commands::get_stored_session;
+use crate::llm::ChatMessage;
+use crate::settings;
+
+const REATTACH_TOOL_IMAGES_KEY: &str = "reattach_tool_images";
+
+/// Most images attached to one message
+const MAX_IMAGES: usize = 2;
+
+/// Largest image attached, in base64 characters (~3.75 MB decoded, inside
+/// every provider's per-image limit)
+const MAX_IMAGE_DATA_CHARS: usize = 5_000_000;
+
+/// Image types all providers accept
+const IMAGE_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+fn reattach_enabled(app: &tauri::AppHandle) -> bool {
+    settings::get_setting(app, REATTACH_TOOL_IMAGES_KEY).unwrap_or(false)
+}
+
+/// Image blocks for the images a stored assistant message generated. Data
+/// comes from the file's inline data or its downloaded preview.
+fn generated_image_blocks(message: &Value) -> Vec<Value> {
+    message["generatedFiles"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|file| {
+            let preview = file["image_preview"].as_str().and_then(|p| p.strip_prefix("data:"));
+            let mime_type = file["mime_type"]
+                .as_str()
+                .or_else(|| preview.and_then(|p| p.split(';').next()))?;
+            if !IMAGE_MIME_TYPES.contains(&mime_type) {
+                return None;
+            }
+            let data = file["inline_data"]
+                .as_str()
+                .filter(|d| !d.is_empty())
+                .or_else(|| preview.and_then(|p| p.split_once(";base64,")).map(|(_, d)| d))?;
+            (data.len() <= MAX_IMAGE_DATA_CHARS).then(|| {
+                serde_json::json!({
+                    "type": "image",
+                    "source": {"type": "base64", "media_type": mime_type, "data": data},
+                })
+            })
+        })
+        .take(MAX_IMAGES)
+        .collect()
+}
+
+/// Put `images` ahead of a message's content, with a note saying where they
+/// came from
+fn attach_images(message: &mut ChatMessage, images: Vec<Value>) {
+    let note = serde_json::json!({
+        "type": "text",
+        "text": "[Images generated in your previous reply, attached for reference]",
+    });
+    let content = match std::mem::take(&mut message.content) {
+        Value::Array(blocks) => blocks,
+        Value::String(text) => vec![serde_json::json!({"type": "text", "text": text})],
+        Value::Null => Vec::new(),
+        other => vec![other],
+    };
+    message.content = Value::Array(std::iter::once(note).chain(images).chain(content).collect());
+}
+
+/// Attach the images generated in the previous reply to the latest user
+/// message, if re-attaching is enabled
+pub fn reattach_tool_images(
+    app: &tauri::AppHandle,
+    session_id: Option<&str>,
+    mut messages: Vec<ChatMessage>,
+) -> Vec<ChatMessage> {
+    let Some(session_id) = session_id.filter(|_| reattach_enabled(app)) else {
+        return messages;
+    };
+    let [.., previous, latest] = messages.as_slice() else {
+        return messages;
+    };
+    if latest.role != "user" || previous.role != "assistant" {
+        return messages;
+    }
+    let Some(previous_id) = previous.id.clone() else {
+        return messages;
+    };
+    let Ok(Some(session)) = get_stored_session(app, session_id) else {
+        return messages;
+    };
+
+    let images = session["messages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|m| m["id"].as_str() == Some(previous_id.as_str()))
+        .map(generated_image_blocks)
+        .unwrap_or_default();
+    if !images.is_empty() {
+        if let Some(latest) = messages.last_mut() {
+            attach_images(latest, images);
+        }
+    }
+    messages
+}
+
+#[tauri::command]
+pub fn get_reattach_tool_images(app: tauri::AppHandle) -> bool {
+    reattach_enabled(&app)
+}
+
+#[tauri::command]
+pub fn set_reattach_tool_images(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set_setting(&app, REATTACH_TOOL_IMAGES_KEY, &enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attaches_generated_images_within_limits() {
+        let stored = serde_json::json!({
+            "id": "a1",
+            "generatedFiles": [
+                {"file_id": "f1", "filename": "chart.png", "image_preview": "data:image/png;base64,AAAA"},
+                {"file_id": "f2", "filename": "data.csv", "mime_type": "text/csv", "inline_data": "eCx5"},
+                {"file_id": "f3", "filename": "big.png", "mime_type": "image/png", "inline_data": "A".repeat(MAX_IMAGE_DATA_CHARS + 1)},
+                {"file_id": "f4", "filename": "plot.jpg", "mime_type": "image/jpeg", "inline_data": "BBBB"}
+            ]
+        });
+        let images = generated_image_blocks(&stored);
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0]["source"]["media_type"], "image/png");
+        assert_eq!(images[0]["source"]["data"], "AAAA");
+        assert_eq!(images[1]["source"]["data"], "BBBB");
+
+        let mut message = ChatMessage {
+            id: None,
+            role: "user".to_string(),
+            content: serde_json::json!("Make the x-axis logarithmic"),
+            thinking_blocks: None,
+        };
+        attach_images(&mut message, images);
+        let blocks = message.content.as_array().unwrap();
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(blocks[1]["type"], "image");
+        assert_eq!(blocks[3]["text"], "Make the x-axis logarithmic");
+    }
+}
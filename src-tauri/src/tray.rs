@@ -0,0 +1,156 @@
+//! System tray (desktop)
+//!
+//! A tray icon with quick actions: New Chat, Start Voice Capture, Toggle
+//! Window, and the three most recently updated sessions. New Chat, voice
+//! capture and opening a session bring the main window forward and are
+//! routed to the frontend as `tray-new-chat`, `tray-start-voice-capture`
+//! and `tray-open-session` (with the session id); toggling the window is
+//! handled here. The recent sessions are rebuilt whenever a session is
+//! saved or deleted.
+
+use serde_json::Value;
+use tauri::menu::{Menu, MenuBuilder, MenuEvent, MenuItemBuilder};
+use tauri::tray::TrayIconBuilder;
+use tauri::{Emitter, Manager};
+
+use crate::session_store;
+
+const TRAY_ID: &str = "main";
+
+const NEW_CHAT_ID: &str = "tray-new-chat";
+const VOICE_CAPTURE_ID: &str = "tray-start-voice-capture";
+const TOGGLE_WINDOW_ID: &str = "tray-toggle-window";
+/// Recent session items are this prefix followed by the session id
+const SESSION_ID_PREFIX: &str = "tray-session:";
+
+/// Recent sessions listed
+const RECENT_SESSIONS: u32 = 3;
+
+/// Longest session title shown before it is cut short
+const MAX_TITLE_CHARS: usize = 40;
+
+/// Menu label for a stored session
+fn session_label(session: &Value) -> String {
+    let title = session["title"]
+        .as_str()
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .unwrap_or("Untitled chat");
+    if title.chars().count() > MAX_TITLE_CHARS {
+        let short: String = title.chars().take(MAX_TITLE_CHARS - 1).collect();
+        format!("{}…", short.trim_end())
+    } else {
+        title.to_string()
+    }
+}
+
+fn build_menu(app: &tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let mut menu = MenuBuilder::new(app)
+        .item(&MenuItemBuilder::new("New Chat").id(NEW_CHAT_ID).build(app)?)
+        .item(&MenuItemBuilder::new("Start Voice Capture").id(VOICE_CAPTURE_ID).build(app)?)
+        .item(&MenuItemBuilder::new("Toggle Window").id(TOGGLE_WINDOW_ID).build(app)?);
+
+    let recent = session_store::list(app, 0, Some(RECENT_SESSIONS)).unwrap_or_else(|err| {
+        eprintln!("Failed to list recent sessions: {}", err);
+        Vec::new()
+    });
+    let recent: Vec<(&str, String)> = recent
+        .iter()
+        .filter_map(|session| Some((session["id"].as_str()?, session_label(session))))
+        .collect();
+    if !recent.is_empty() {
+        menu = menu.separator();
+    }
+    for (id, label) in recent {
+        let item = MenuItemBuilder::new(label)
+            .id(format!("{}{}", SESSION_ID_PREFIX, id))
+            .build(app)?;
+        menu = menu.item(&item);
+    }
+    menu.build()
+}
+
+/// Show and focus the main window
+pub fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        if let Err(e) = window.show().and_then(|_| window.set_focus()) {
+            eprintln!("Failed to show window: {}", e);
+        }
+    }
+}
+
+/// Hide the main window if it is in front, otherwise bring it forward
+fn toggle_main_window(app: &tauri::AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let in_front = window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false);
+    if in_front {
+        if let Err(e) = window.hide() {
+            eprintln!("Failed to hide window: {}", e);
+        }
+    } else {
+        show_main_window(app);
+    }
+}
+
+/// Bring the window forward and pass an action on to the frontend
+fn route_to_frontend<S: serde::Serialize + Clone>(app: &tauri::AppHandle, event: &str, payload: S) {
+    show_main_window(app);
+    if let Err(err) = app.emit(event, payload) {
+        eprintln!("Failed to emit {} event: {}", event, err);
+    }
+}
+
+fn handle_menu_event(app: &tauri::AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        NEW_CHAT_ID => route_to_frontend(app, "tray-new-chat", ()),
+        VOICE_CAPTURE_ID => route_to_frontend(app, "tray-start-voice-capture", ()),
+        TOGGLE_WINDOW_ID => toggle_main_window(app),
+        id => {
+            if let Some(session_id) = id.strip_prefix(SESSION_ID_PREFIX) {
+                route_to_frontend(app, "tray-open-session", session_id.to_string());
+            }
+        }
+    }
+}
+
+/// Create the tray icon. Called once at startup, after the session store
+/// is open.
+pub fn init(app: &tauri::AppHandle) -> tauri::Result<()> {
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Sidestream")
+        .menu(&build_menu(app)?)
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    Ok(())
+}
+
+/// Rebuild the recent sessions after sessions change
+pub fn refresh_recent_sessions(app: &tauri::AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Err(err) = build_menu(app).and_then(|menu| tray.set_menu(Some(menu))) {
+        eprintln!("Failed to update tray menu: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_labels_are_short_titles() {
+        assert_eq!(session_label(&serde_json::json!({"title": " Trip plans "})), "Trip plans");
+        assert_eq!(session_label(&serde_json::json!({"title": ""})), "Untitled chat");
+        assert_eq!(session_label(&serde_json::json!({})), "Untitled chat");
+
+        let long = session_label(&serde_json::json!({"title": "word ".repeat(20)}));
+        assert_eq!(long.chars().count(), MAX_TITLE_CHARS);
+        assert!(long.ends_with("word…"));
+    }
+}
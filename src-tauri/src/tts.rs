@@ -0,0 +1,293 @@
+//! Text-to-speech for reading assistant replies aloud
+//!
+//! Readback settings (provider, voice, autoplay) have a global default in the
+//! backend settings store and an optional per-session override stored in the
+//! session's `readback` field. `synthesize_speech` resolves the effective
+//! settings and returns WAV audio from OpenAI or Gemini; `stream_speech_pcm`
+//! hands raw samples over as they arrive, for playback on the device (see
+//! `speech_playback`).
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::encode_wav;
+use crate::audit_log;
+use crate::commands::{get_api_key_async, get_stored_session, update_stored_session};
+use crate::providers::gemini::GeminiClient;
+use crate::personas;
+use crate::provider_endpoints;
+use crate::proxy;
+use crate::settings;
+
+const READBACK_SETTINGS_KEY: &str = "readback";
+const READBACK_SESSION_FIELD: &str = "readback";
+
+const OPENAI_TTS_MODEL: &str = "gpt-4o-mini-tts";
+const OPENAI_DEFAULT_VOICE: &str = "alloy";
+/// OpenAI's `pcm` response format: 16-bit mono at 24kHz
+const OPENAI_PCM_SAMPLE_RATE: u32 = 24000;
+
+const GEMINI_TTS_MODEL: &str = "gemini-2.5-flash-preview-tts";
+const GEMINI_DEFAULT_VOICE: &str = "Kore";
+/// Gemini TTS returns 24kHz mono PCM unless the mime type says otherwise
+const GEMINI_DEFAULT_SAMPLE_RATE: u32 = 24000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TtsProvider {
+    #[default]
+    Openai,
+    Gemini,
+}
+
+/// Which voice reads replies aloud and whether it happens automatically
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadbackSettings {
+    pub provider: TtsProvider,
+    /// Provider voice name; None uses the provider's default voice
+    pub voice: Option<String>,
+    /// Play assistant replies automatically when they finish streaming
+    pub autoplay: bool,
+}
+
+impl ReadbackSettings {
+    fn voice_or_default(&self) -> &str {
+        match (&self.voice, self.provider) {
+            (Some(voice), _) if !voice.trim().is_empty() => voice,
+            (_, TtsProvider::Openai) => OPENAI_DEFAULT_VOICE,
+            (_, TtsProvider::Gemini) => GEMINI_DEFAULT_VOICE,
+        }
+    }
+}
+
+/// Synthesized audio returned to the frontend
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SynthesizedSpeech {
+    pub audio_base64: String,
+    pub mime_type: String,
+}
+
+/// Resolve readback settings: session override, then global default
+pub fn effective_readback_settings(
+    app: &tauri::AppHandle,
+    session_id: Option<&str>,
+) -> ReadbackSettings {
+    let session_override = session_id
+        .and_then(|id| get_stored_session(app, id).ok().flatten())
+        .and_then(|session| session.get(READBACK_SESSION_FIELD).cloned())
+        .and_then(|value| serde_json::from_value::<ReadbackSettings>(value).ok());
+
+    if let Some(settings) = session_override {
+        return settings;
+    }
+
+    let mut settings: ReadbackSettings =
+        settings::get_setting(app, READBACK_SETTINGS_KEY).unwrap_or_default();
+    // A session's persona can choose the voice unless the session overrides readback
+    if let Some(voice) = session_id
+        .and_then(|id| personas::session_persona(app, id))
+        .and_then(|persona| persona.voice)
+    {
+        settings.voice = Some(voice);
+    }
+    settings
+}
+
+#[tauri::command]
+pub fn get_readback_settings(
+    app: tauri::AppHandle,
+    session_id: Option<String>,
+) -> ReadbackSettings {
+    effective_readback_settings(&app, session_id.as_deref())
+}
+
+#[tauri::command]
+pub fn set_readback_settings(
+    app: tauri::AppHandle,
+    settings: ReadbackSettings,
+) -> Result<(), String> {
+    crate::settings::set_setting(&app, READBACK_SETTINGS_KEY, &settings)
+}
+
+/// Set or clear (with None) a session's readback override
+#[tauri::command]
+pub fn set_session_readback_settings(
+    app: tauri::AppHandle,
+    session_id: String,
+    settings: Option<ReadbackSettings>,
+) -> Result<(), String> {
+    update_stored_session(&app, &session_id, |fields| {
+        match settings {
+            Some(settings) => {
+                let value = serde_json::to_value(&settings).map_err(|e| e.to_string())?;
+                fields.insert(READBACK_SESSION_FIELD.to_string(), value);
+            }
+            None => {
+                fields.remove(READBACK_SESSION_FIELD);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Synthesize speech for `text` using the given readback settings
+pub async fn synthesize_speech_impl(
+    app: &tauri::AppHandle,
+    text: &str,
+    settings: &ReadbackSettings,
+) -> Result<SynthesizedSpeech, String> {
+    if text.trim().is_empty() {
+        return Err("Nothing to read aloud".to_string());
+    }
+
+    let wav = match settings.provider {
+        TtsProvider::Openai => synthesize_openai(app, text, settings.voice_or_default()).await?,
+        TtsProvider::Gemini => synthesize_gemini(app, text, settings.voice_or_default()).await?,
+    };
+
+    Ok(SynthesizedSpeech {
+        audio_base64: BASE64.encode(&wav),
+        mime_type: "audio/wav".to_string(),
+    })
+}
+
+/// Synthesize speech with the session's (or global) readback settings
+#[tauri::command]
+pub async fn synthesize_speech(
+    app: tauri::AppHandle,
+    text: String,
+    session_id: Option<String>,
+) -> Result<SynthesizedSpeech, String> {
+    let settings = effective_readback_settings(&app, session_id.as_deref());
+    synthesize_speech_impl(&app, &text, &settings).await
+}
+
+async fn synthesize_openai(
+    app: &tauri::AppHandle,
+    text: &str,
+    voice: &str,
+) -> Result<Vec<u8>, String> {
+    let api_key = get_api_key_async(app, "openai").await?;
+
+    let body = serde_json::json!({
+        "model": OPENAI_TTS_MODEL,
+        "voice": voice,
+        "input": text,
+        "response_format": "wav",
+    });
+
+    audit_log::record_json("openai", OPENAI_TTS_MODEL, "audio/speech", &body);
+    let response = provider_endpoints::request(&proxy::client(), reqwest::Method::POST, "openai", "/audio/speech", &api_key)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Speech request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Speech API error: {}", error_text));
+    }
+
+    response
+        .bytes()
+        .await
+        .map(|b| b.to_vec())
+        .map_err(|e| format!("Failed to read speech audio: {}", e))
+}
+
+/// Gemini speech as (sample rate, 16-bit mono samples)
+async fn gemini_pcm(app: &tauri::AppHandle, text: &str, voice: &str) -> Result<(u32, Vec<i16>), String> {
+    let api_key = get_api_key_async(app, "google").await?;
+    let client = GeminiClient::new(api_key);
+
+    let body = client.build_speech_request(text, voice);
+    let (mime_type, data) = client.send_speech_request(GEMINI_TTS_MODEL, &body).await?;
+
+    let pcm = BASE64
+        .decode(data)
+        .map_err(|e| format!("Failed to decode speech audio: {}", e))?;
+    Ok((pcm_sample_rate(&mime_type), pcm_samples(&pcm)))
+}
+
+async fn synthesize_gemini(
+    app: &tauri::AppHandle,
+    text: &str,
+    voice: &str,
+) -> Result<Vec<u8>, String> {
+    let (sample_rate, samples) = gemini_pcm(app, text, voice).await?;
+    encode_wav(&samples, sample_rate, 1)
+}
+
+/// Little-endian 16-bit PCM bytes as samples (a trailing odd byte is ignored)
+fn pcm_samples(pcm: &[u8]) -> Vec<i16> {
+    pcm.chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect()
+}
+
+/// Synthesize `text` and hand the audio to `on_chunk` as (sample rate,
+/// 16-bit mono samples) while it arrives: OpenAI audio is streamed, Gemini's
+/// comes in one piece. Stops early when `on_chunk` returns false.
+pub async fn stream_speech_pcm<F>(
+    app: &tauri::AppHandle,
+    text: &str,
+    settings: &ReadbackSettings,
+    mut on_chunk: F,
+) -> Result<(), String>
+where
+    F: FnMut(u32, &[i16]) -> bool,
+{
+    use futures::StreamExt;
+
+    if settings.provider == TtsProvider::Gemini {
+        let (sample_rate, samples) = gemini_pcm(app, text, settings.voice_or_default()).await?;
+        on_chunk(sample_rate, &samples);
+        return Ok(());
+    }
+
+    let api_key = get_api_key_async(app, "openai").await?;
+    let body = serde_json::json!({
+        "model": OPENAI_TTS_MODEL,
+        "voice": settings.voice_or_default(),
+        "input": text,
+        "response_format": "pcm",
+    });
+
+    audit_log::record_json("openai", OPENAI_TTS_MODEL, "audio/speech", &body);
+    let response = provider_endpoints::request(&proxy::client(), reqwest::Method::POST, "openai", "/audio/speech", &api_key)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Speech request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Speech API error: {}", error_text));
+    }
+
+    // Chunks can split a sample; an odd byte waits for the next chunk
+    let mut pending: Vec<u8> = Vec::new();
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Failed to read speech audio: {}", e))?;
+        pending.extend_from_slice(&chunk);
+        let whole = pending.len() - pending.len() % 2;
+        let samples = pcm_samples(&pending[..whole]);
+        pending.drain(..whole);
+        if !on_chunk(OPENAI_PCM_SAMPLE_RATE, &samples) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Read the sample rate from a PCM mime type like "audio/L16;codec=pcm;rate=24000"
+fn pcm_sample_rate(mime_type: &str) -> u32 {
+    mime_type
+        .split(';')
+        .filter_map(|param| param.trim().strip_prefix("rate="))
+        .find_map(|rate| rate.parse().ok())
+        .unwrap_or(GEMINI_DEFAULT_SAMPLE_RATE)
+}
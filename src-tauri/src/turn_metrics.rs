@@ -0,0 +1,128 @@
+//! Turn latency and throughput
+//!
+//! Each streamed turn is timed from the moment it is registered: time to
+//! first token (the first text or thinking delta), total duration, and
+//! output tokens per second over the generation window (first token to
+//! end). Anthropic, OpenAI and Gemini report their output token counts; for
+//! the other providers the count is estimated from the streamed text. Metrics
+//! go out with the final `chat-usage` event of a turn and to the LLM log,
+//! so providers and models can be compared on real responsiveness.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Rough characters per token, for estimating unreported output counts
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Shortest generation window a rate is computed over; a reply that arrives
+/// in one chunk has no meaningful tokens/sec
+const MIN_GENERATION_WINDOW: Duration = Duration::from_millis(50);
+
+/// Timing of an in-flight turn
+#[derive(Debug, Clone)]
+pub struct TurnTiming {
+    started: Instant,
+    first_token: Option<Instant>,
+    /// Output tokens reported by the provider, summed over the turn's
+    /// responses (tool use continues a turn with another response)
+    output_tokens: Option<u64>,
+}
+
+/// Latency and throughput of a completed turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnMetrics {
+    /// Time to first token; None if nothing was streamed
+    pub ttft_ms: Option<u64>,
+    pub duration_ms: u64,
+    pub output_tokens: u64,
+    /// True when the provider didn't report output tokens
+    pub output_tokens_estimated: bool,
+    /// Output tokens per second from first token to end
+    pub tokens_per_second: Option<f64>,
+}
+
+impl TurnTiming {
+    pub fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            first_token: None,
+            output_tokens: None,
+        }
+    }
+
+    /// Note streamed output; only the first call counts
+    pub fn record_output(&mut self) {
+        self.first_token.get_or_insert_with(Instant::now);
+    }
+
+    pub fn add_output_tokens(&mut self, tokens: u64) {
+        *self.output_tokens.get_or_insert(0) += tokens;
+    }
+
+    /// Metrics as of now; `streamed_chars` estimates the output tokens when
+    /// the provider reported none
+    pub fn finish(&self, streamed_chars: usize) -> TurnMetrics {
+        let reported = self.output_tokens;
+        let output_tokens = reported.unwrap_or((streamed_chars / CHARS_PER_TOKEN) as u64);
+        metrics(
+            self.first_token.map(|first| first - self.started),
+            self.started.elapsed(),
+            output_tokens,
+            reported.is_none(),
+        )
+    }
+}
+
+fn metrics(ttft: Option<Duration>, duration: Duration, output_tokens: u64, estimated: bool) -> TurnMetrics {
+    let tokens_per_second = ttft
+        .map(|ttft| duration.saturating_sub(ttft))
+        .filter(|window| *window >= MIN_GENERATION_WINDOW && output_tokens > 0)
+        .map(|window| (output_tokens as f64 / window.as_secs_f64() * 10.0).round() / 10.0);
+    TurnMetrics {
+        ttft_ms: ttft.map(|ttft| ttft.as_millis() as u64),
+        duration_ms: duration.as_millis() as u64,
+        output_tokens,
+        output_tokens_estimated: estimated,
+        tokens_per_second,
+    }
+}
+
+impl fmt::Display for TurnMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ttft_ms {
+            Some(ttft) => write!(f, "TTFT {} ms, ", ttft)?,
+            None => write!(f, "no output, ")?,
+        }
+        write!(
+            f,
+            "{} ms total, {}{} output tokens",
+            self.duration_ms,
+            if self.output_tokens_estimated { "~" } else { "" },
+            self.output_tokens
+        )?;
+        if let Some(rate) = self.tokens_per_second {
+            write!(f, ", {} tokens/s", rate)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_covers_the_generation_window() {
+        let turn = metrics(Some(Duration::from_millis(800)), Duration::from_millis(2800), 100, false);
+        assert_eq!(turn.ttft_ms, Some(800));
+        assert_eq!(turn.tokens_per_second, Some(50.0));
+        assert_eq!(turn.to_string(), "TTFT 800 ms, 2800 ms total, 100 output tokens, 50 tokens/s");
+
+        // Nothing streamed, or all of it at once: no rate
+        assert_eq!(metrics(None, Duration::from_secs(1), 0, true).tokens_per_second, None);
+        let burst = metrics(Some(Duration::from_millis(990)), Duration::from_millis(1000), 40, true);
+        assert_eq!(burst.tokens_per_second, None);
+        assert_eq!(burst.to_string(), "TTFT 990 ms, 1000 ms total, ~40 output tokens");
+    }
+}
@@ -0,0 +1,135 @@
+//! Spoken command detection for hands-free dictation.
+//!
+//! After a recording is transcribed we check whether the utterance ends with a
+//! command phrase ("send it", "scratch that", "new line", ...). When it does,
+//! the phrase is stripped from the dictated text and a `voice-intent` event is
+//! emitted so the frontend can act on it without the user touching the keyboard.
+
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+/// A command recognised at the end of a dictated utterance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoiceIntent {
+    /// Send the current input as a chat message
+    Send,
+    /// Discard the most recent dictation
+    ScratchThat,
+    /// Insert a line break after the dictated text
+    NewLine,
+    /// Insert a blank line after the dictated text
+    NewParagraph,
+}
+
+/// Event emitted when a transcription ends with a spoken command
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VoiceIntentEvent {
+    pub intent: VoiceIntent,
+    /// The dictated text with the command phrase removed (may be empty)
+    pub text: String,
+}
+
+/// Command phrases, matched case-insensitively at the end of an utterance.
+/// Longer phrases come first so "send the message" wins over "send".
+const INTENT_PHRASES: &[(&str, VoiceIntent)] = &[
+    ("send the message", VoiceIntent::Send),
+    ("send message", VoiceIntent::Send),
+    ("send it", VoiceIntent::Send),
+    ("scratch that", VoiceIntent::ScratchThat),
+    ("delete that", VoiceIntent::ScratchThat),
+    ("new paragraph", VoiceIntent::NewParagraph),
+    ("new line", VoiceIntent::NewLine),
+];
+
+/// Detect a trailing command phrase in a transcription.
+/// Returns the intent and the remaining dictated text, or None if the
+/// utterance is plain dictation.
+pub fn detect_voice_intent(transcription: &str) -> Option<(VoiceIntent, String)> {
+    // Whisper and Gemini both like to add terminal punctuation ("Send it.")
+    let trimmed = transcription
+        .trim_end_matches(|c: char| c.is_whitespace() || matches!(c, '.' | '!' | '?' | ','));
+
+    for (phrase, intent) in INTENT_PHRASES {
+        // Compared in place: lowercasing the whole text can change its length
+        // (e.g. "İ"), which would shift the split point
+        let Some(split) = trimmed.len().checked_sub(phrase.len()) else {
+            continue;
+        };
+        if !trimmed.is_char_boundary(split) || !trimmed[split..].eq_ignore_ascii_case(phrase) {
+            continue;
+        }
+        // Require a word boundary so "resend it" doesn't trigger a send
+        let at_boundary = trimmed[..split]
+            .chars()
+            .last()
+            .map(|c| !c.is_alphanumeric())
+            .unwrap_or(true);
+        if !at_boundary {
+            continue;
+        }
+        let text = trimmed[..split]
+            .trim_end_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':'))
+            .to_string();
+        return Some((*intent, text));
+    }
+
+    None
+}
+
+/// Check a finished transcription for a spoken command.
+/// Emits a `voice-intent` event when one is found and returns the dictated
+/// text with the command removed; otherwise returns the transcription unchanged.
+pub fn process_transcription(app: &tauri::AppHandle, transcription: String) -> String {
+    match detect_voice_intent(&transcription) {
+        Some((intent, text)) => {
+            if let Err(err) = app.emit(
+                "voice-intent",
+                VoiceIntentEvent {
+                    intent,
+                    text: text.clone(),
+                },
+            ) {
+                eprintln!("Failed to emit voice-intent event: {}", err);
+            }
+            text
+        }
+        None => transcription,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_trailing_send_and_strips_phrase() {
+        assert_eq!(
+            detect_voice_intent("What's the weather in Paris? Send it."),
+            Some((VoiceIntent::Send, "What's the weather in Paris?".to_string()))
+        );
+        assert_eq!(
+            detect_voice_intent("Flights to İzmir, SEND IT"),
+            Some((VoiceIntent::Send, "Flights to İzmir".to_string()))
+        );
+    }
+
+    #[test]
+    fn bare_command_yields_empty_text() {
+        assert_eq!(
+            detect_voice_intent("Scratch that."),
+            Some((VoiceIntent::ScratchThat, String::new()))
+        );
+        assert_eq!(
+            detect_voice_intent("new paragraph"),
+            Some((VoiceIntent::NewParagraph, String::new()))
+        );
+    }
+
+    #[test]
+    fn ignores_plain_dictation_and_partial_words() {
+        assert_eq!(detect_voice_intent("Please write me a haiku"), None);
+        assert_eq!(detect_voice_intent("I asked them to resend it"), None);
+        assert_eq!(detect_voice_intent("send it to Bob tomorrow"), None);
+    }
+}
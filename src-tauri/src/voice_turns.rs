@@ -0,0 +1,259 @@
+//! Structured voice turns persisted alongside chat sessions
+//!
+//! A voice turn links the recorded audio, its transcription and the model's
+//! reply so the audio can be replayed or re-transcribed later with a better
+//! model. Audio is written to `app_data_dir/voice/<session_id>/<turn_id>.wav`
+//! and the turn metadata is kept in the session's `voiceTurns` array.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::audio::AudioState;
+use crate::commands::{get_stored_session, transcribe_audio_bytes, update_stored_session};
+use crate::llm_voice::transcribe_audio_gemini_impl;
+
+const VOICE_TURNS_FIELD: &str = "voiceTurns";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceTurn {
+    pub turn_id: String,
+    /// Audio filename relative to the session's voice directory
+    pub audio_file: String,
+    pub transcription: String,
+    /// Which transcription service produced `transcription` ("openai" or "gemini")
+    pub transcription_provider: Option<String>,
+    pub reply: Option<String>,
+    pub created_at: String,
+}
+
+/// Session and turn ids are used as path components, so only allow a safe subset
+fn validate_id(id: &str) -> Result<(), String> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid id: {}", id))
+    }
+}
+
+fn voice_root_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join("voice"))
+}
+
+fn session_audio_dir(app: &tauri::AppHandle, session_id: &str) -> Result<PathBuf, String> {
+    validate_id(session_id)?;
+    Ok(voice_root_dir(app)?.join(session_id))
+}
+
+fn read_voice_turns(session: &serde_json::Map<String, serde_json::Value>) -> Vec<VoiceTurn> {
+    session
+        .get(VOICE_TURNS_FIELD)
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn write_voice_turns(
+    session: &mut serde_json::Map<String, serde_json::Value>,
+    turns: &[VoiceTurn],
+) -> Result<(), String> {
+    let value = serde_json::to_value(turns).map_err(|e| e.to_string())?;
+    session.insert(VOICE_TURNS_FIELD.to_string(), value);
+    Ok(())
+}
+
+/// Look up a voice turn in a stored session
+fn find_voice_turn(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    turn_id: &str,
+) -> Result<VoiceTurn, String> {
+    let session = get_stored_session(app, session_id)?
+        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    let fields = session
+        .as_object()
+        .ok_or("Stored session is not an object")?;
+
+    read_voice_turns(fields)
+        .into_iter()
+        .find(|t| t.turn_id == turn_id)
+        .ok_or_else(|| format!("Voice turn not found: {}", turn_id))
+}
+
+/// Insert or replace a turn (matched by turn id) and persist the session
+fn upsert_voice_turn(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    turn: VoiceTurn,
+) -> Result<(), String> {
+    update_stored_session(app, session_id, |fields| {
+        let mut turns = read_voice_turns(fields);
+        match turns.iter_mut().find(|t| t.turn_id == turn.turn_id) {
+            Some(existing) => *existing = turn,
+            None => turns.push(turn),
+        }
+        write_voice_turns(fields, &turns)
+    })?;
+    Ok(())
+}
+
+/// Read all of a session's recorded audio files as (filename, bytes)
+pub fn read_session_audio(app: &tauri::AppHandle, session_id: &str) -> Vec<(String, Vec<u8>)> {
+    let Ok(dir) = session_audio_dir(app, session_id) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            fs::read(entry.path()).ok().map(|bytes| (name, bytes))
+        })
+        .collect()
+}
+
+/// Write recorded audio files into a session's voice directory
+pub fn write_session_audio(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    files: &[(String, Vec<u8>)],
+) -> Result<(), String> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let dir = session_audio_dir(app, session_id)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create voice directory: {}", e))?;
+    for (name, bytes) in files {
+        // Filenames come from `<turn_id>.wav`; reject anything else
+        let stem = name.strip_suffix(".wav").unwrap_or("");
+        validate_id(stem)?;
+        fs::write(dir.join(name), bytes)
+            .map_err(|e| format!("Failed to write voice audio: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Remove a session's recorded audio (called when the session is deleted)
+pub fn delete_session_audio(app: &tauri::AppHandle, session_id: &str) {
+    if let Ok(dir) = session_audio_dir(app, session_id) {
+        if dir.exists() {
+            if let Err(e) = fs::remove_dir_all(&dir) {
+                eprintln!("Failed to delete voice audio for {}: {}", session_id, e);
+            }
+        }
+    }
+}
+
+/// Remove all recorded audio (called when the session store is cleared)
+pub fn delete_all_audio(app: &tauri::AppHandle) {
+    if let Ok(dir) = voice_root_dir(app) {
+        if dir.exists() {
+            if let Err(e) = fs::remove_dir_all(&dir) {
+                eprintln!("Failed to delete voice audio: {}", e);
+            }
+        }
+    }
+}
+
+/// Save the most recent recording as a voice turn in a session.
+/// Calling again for the same turn updates the transcription and reply (e.g.
+/// once the model's reply has finished streaming) without touching the audio.
+#[tauri::command]
+pub async fn save_voice_turn(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AudioState>,
+    session_id: String,
+    turn_id: String,
+    transcription: String,
+    transcription_provider: Option<String>,
+    reply: Option<String>,
+) -> Result<VoiceTurn, String> {
+    validate_id(&turn_id)?;
+    let dir = session_audio_dir(&app, &session_id)?;
+
+    if let Ok(mut existing) = find_voice_turn(&app, &session_id, &turn_id) {
+        existing.transcription = transcription;
+        if transcription_provider.is_some() {
+            existing.transcription_provider = transcription_provider;
+        }
+        if reply.is_some() {
+            existing.reply = reply;
+        }
+        upsert_voice_turn(&app, &session_id, existing.clone())?;
+        return Ok(existing);
+    }
+
+    let audio = state
+        .data
+        .lock()
+        .last_recording
+        .take()
+        .ok_or("No recording available to save")?;
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create voice directory: {}", e))?;
+    let audio_file = format!("{}.wav", turn_id);
+    fs::write(dir.join(&audio_file), &audio)
+        .map_err(|e| format!("Failed to write voice audio: {}", e))?;
+
+    let turn = VoiceTurn {
+        turn_id,
+        audio_file,
+        transcription,
+        transcription_provider,
+        reply,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    upsert_voice_turn(&app, &session_id, turn.clone())?;
+
+    Ok(turn)
+}
+
+/// Load a voice turn's recorded audio as base64 WAV for replay
+#[tauri::command]
+pub async fn get_voice_turn_audio(
+    app: tauri::AppHandle,
+    session_id: String,
+    turn_id: String,
+) -> Result<String, String> {
+    let turn = find_voice_turn(&app, &session_id, &turn_id)?;
+    let path = session_audio_dir(&app, &session_id)?.join(&turn.audio_file);
+    let audio = fs::read(&path).map_err(|e| format!("Failed to read voice audio: {}", e))?;
+    Ok(BASE64.encode(&audio))
+}
+
+/// Re-run transcription of a saved voice turn with the given provider
+/// ("openai" or "gemini") and store the new transcription
+#[tauri::command]
+pub async fn retranscribe_voice_turn(
+    app: tauri::AppHandle,
+    session_id: String,
+    turn_id: String,
+    provider: String,
+) -> Result<VoiceTurn, String> {
+    let mut turn = find_voice_turn(&app, &session_id, &turn_id)?;
+    let path = session_audio_dir(&app, &session_id)?.join(&turn.audio_file);
+    let audio = fs::read(&path).map_err(|e| format!("Failed to read voice audio: {}", e))?;
+
+    turn.transcription = match provider.as_str() {
+        "openai" => transcribe_audio_bytes(&app, audio, "audio.wav", "audio/wav").await?,
+        "gemini" => transcribe_audio_gemini_impl(&app, BASE64.encode(&audio)).await?,
+        _ => return Err(format!("Unsupported transcription provider: {}", provider)),
+    };
+    turn.transcription_provider = Some(provider);
+
+    upsert_voice_turn(&app, &session_id, turn.clone())?;
+    Ok(turn)
+}
@@ -0,0 +1,320 @@
+//! Watch-folder ingestion
+//!
+//! The user can designate a folder; files dropped into it are picked up by a
+//! background poller and ingested as a user message: audio is transcribed,
+//! office documents and images are converted to text, text files are read as
+//! is and PDFs are attached for the model to read. Each file lands in a new
+//! session or is appended to an "Inbox" session, and a `watch-folder-ingested`
+//! (or `watch-folder-failed`) event lets the frontend notify the user.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::commands::{
+    get_stored_session, has_api_key_async, new_session_id, new_session_json, session_message, store_session,
+    transcribe_audio_bytes, update_stored_session,
+};
+use crate::extraction::{extract_text_from_image, office_file_to_text};
+use crate::llm_voice::transcribe_audio_file_gemini_impl;
+use crate::mime_utils::{audio_mime_type, extension_to_mime};
+use crate::settings;
+
+const WATCH_FOLDER_SETTING_KEY: &str = "watch_folder";
+
+/// How often the watched folder is scanned
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+const INBOX_TITLE: &str = "Inbox";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchDestination {
+    /// Each file starts a new session
+    #[default]
+    NewSession,
+    /// Files are appended to a single "Inbox" session
+    Inbox,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolderSettings {
+    /// Folder to watch; None disables watching
+    pub path: Option<String>,
+    #[serde(default)]
+    pub destination: WatchDestination,
+    /// Model recorded on sessions created from ingested files
+    #[serde(default)]
+    pub model: String,
+    /// Session that collects files in Inbox mode (created on first use)
+    pub inbox_session_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolderIngestedEvent {
+    pub filename: String,
+    pub session_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolderFailedEvent {
+    pub filename: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Audio,
+    Image,
+    Office,
+    Pdf,
+    Text,
+}
+
+fn file_kind(filename: &str) -> Option<FileKind> {
+    let extension = filename.rsplit_once('.')?.1.to_lowercase();
+    match extension.as_str() {
+        "wav" | "mp3" | "m4a" | "mp4" | "mpeg" | "mpga" | "ogg" | "webm" | "flac" => Some(FileKind::Audio),
+        "png" | "jpg" | "jpeg" | "gif" | "webp" => Some(FileKind::Image),
+        "xlsx" | "xlsm" | "xls" | "ods" | "docx" => Some(FileKind::Office),
+        "pdf" => Some(FileKind::Pdf),
+        "txt" | "md" | "csv" | "json" | "xml" | "html" | "htm" | "log" => Some(FileKind::Text),
+        _ => None,
+    }
+}
+
+fn load_settings(app: &tauri::AppHandle) -> WatchFolderSettings {
+    settings::get_setting(app, WATCH_FOLDER_SETTING_KEY).unwrap_or_default()
+}
+
+/// List the regular, non-hidden files directly inside a folder with their sizes
+fn scan_folder(dir: &Path) -> HashMap<PathBuf, u64> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| (entry.path(), metadata.len()))
+        })
+        .collect()
+}
+
+/// Turn a file into the content (and optional attachment) of a user message
+async fn ingest_file(
+    app: &tauri::AppHandle,
+    filename: &str,
+    bytes: Vec<u8>,
+) -> Result<(String, Option<serde_json::Value>), String> {
+    let kind = file_kind(filename).ok_or_else(|| format!("Unsupported file type: {}", filename))?;
+
+    let text = match kind {
+        FileKind::Audio => {
+            let transcription = if has_api_key_async(app, "openai").await {
+                transcribe_audio_bytes(app, bytes, filename, audio_mime_type(filename)).await?
+            } else {
+                transcribe_audio_file_gemini_impl(app, bytes, audio_mime_type(filename)).await?
+            };
+            format!("--- Transcription: {} ---\n{}", filename, transcription.trim())
+        }
+        FileKind::Image => {
+            let mime_type = extension_to_mime(filename).map(|m| m.to_string());
+            let text = extract_text_from_image(app.clone(), BASE64.encode(&bytes), mime_type, None).await?;
+            format!("--- File: {} (text extracted from image) ---\n{}", filename, text)
+        }
+        FileKind::Office => {
+            let text = office_file_to_text(&bytes, filename)
+                .ok_or_else(|| format!("Unsupported file type: {}", filename))??;
+            format!("--- File: {} ---\n{}", filename, text)
+        }
+        FileKind::Text => {
+            format!("--- File: {} ---\n{}", filename, String::from_utf8_lossy(&bytes))
+        }
+        FileKind::Pdf => {
+            let attachment = serde_json::json!({
+                "id": new_session_id(),
+                "type": "document",
+                "name": filename,
+                "mimeType": "application/pdf",
+                "data": BASE64.encode(&bytes),
+            });
+            return Ok((format!("Added {}", filename), Some(attachment)));
+        }
+    };
+
+    Ok((text, None))
+}
+
+/// Store an ingested file as a user message according to the destination
+/// setting. Returns the session id it was written to.
+fn store_ingested_message(
+    app: &tauri::AppHandle,
+    settings: &WatchFolderSettings,
+    filename: &str,
+    message: serde_json::Value,
+) -> Result<String, String> {
+    if settings.destination == WatchDestination::Inbox {
+        if let Some(inbox_id) = &settings.inbox_session_id {
+            if get_stored_session(app, inbox_id)?.is_some() {
+                update_stored_session(app, inbox_id, |fields| {
+                    fields
+                        .entry("messages")
+                        .or_insert_with(|| serde_json::json!([]))
+                        .as_array_mut()
+                        .ok_or("Session messages is not an array")?
+                        .push(message.clone());
+                    fields.insert(
+                        "updatedAt".to_string(),
+                        serde_json::Value::String(chrono::Utc::now().to_rfc3339()),
+                    );
+                    Ok(())
+                })?;
+                return Ok(inbox_id.clone());
+            }
+        }
+    }
+
+    let title = match settings.destination {
+        WatchDestination::Inbox => INBOX_TITLE,
+        WatchDestination::NewSession => filename,
+    };
+    let session = new_session_json(title, &settings.model, false, vec![message]);
+    let session_id = session["id"].as_str().unwrap_or_default().to_string();
+    store_session(app, session)?;
+
+    if settings.destination == WatchDestination::Inbox {
+        let mut updated = settings.clone();
+        updated.inbox_session_id = Some(session_id.clone());
+        settings::set_setting(app, WATCH_FOLDER_SETTING_KEY, &updated)?;
+    }
+
+    Ok(session_id)
+}
+
+async fn process_file(app: &tauri::AppHandle, settings: &WatchFolderSettings, path: &Path) {
+    let filename = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let result = async {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let (content, attachment) = ingest_file(app, &filename, bytes).await?;
+        let mut message = session_message("user", &content, &new_session_id());
+        if let Some(attachment) = attachment {
+            message["attachments"] = serde_json::json!([attachment]);
+        }
+        store_ingested_message(app, settings, &filename, message)
+    }
+    .await;
+
+    match result {
+        Ok(session_id) => {
+            let event = WatchFolderIngestedEvent {
+                filename,
+                session_id,
+            };
+            if let Err(err) = app.emit("watch-folder-ingested", event) {
+                eprintln!("Failed to emit watch-folder-ingested event: {}", err);
+            }
+        }
+        Err(error) => {
+            let event = WatchFolderFailedEvent { filename, error };
+            if let Err(err) = app.emit("watch-folder-failed", event) {
+                eprintln!("Failed to emit watch-folder-failed event: {}", err);
+            }
+        }
+    }
+}
+
+/// Background loop that polls the watched folder. Files already present when
+/// watching starts are left alone; a new file is ingested once its size has
+/// stopped changing between two polls, so partially copied files are skipped.
+/// Spawned once from `setup`.
+pub async fn run_watcher(app: tauri::AppHandle) {
+    let mut watched: Option<PathBuf> = None;
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    let mut pending: HashMap<PathBuf, u64> = HashMap::new();
+
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        let settings = load_settings(&app);
+        let Some(dir) = settings.path.as_ref().map(PathBuf::from) else {
+            watched = None;
+            continue;
+        };
+
+        let files = scan_folder(&dir);
+        if watched.as_ref() != Some(&dir) {
+            // Newly configured folder: baseline the existing files
+            seen = files.into_keys().collect();
+            pending.clear();
+            watched = Some(dir);
+            continue;
+        }
+
+        for (path, size) in files {
+            if seen.contains(&path) {
+                continue;
+            }
+            if pending.get(&path) != Some(&size) {
+                pending.insert(path, size);
+                continue;
+            }
+            pending.remove(&path);
+            seen.insert(path.clone());
+            if file_kind(&path.to_string_lossy()).is_some() {
+                process_file(&app, &settings, &path).await;
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_watch_folder_settings(app: tauri::AppHandle) -> WatchFolderSettings {
+    load_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_watch_folder_settings(
+    app: tauri::AppHandle,
+    settings: WatchFolderSettings,
+) -> Result<(), String> {
+    if let Some(path) = &settings.path {
+        if !Path::new(path).is_dir() {
+            return Err(format!("Not a folder: {}", path));
+        }
+    }
+    // The inbox session is managed by the watcher; keep it across updates
+    let mut settings = settings;
+    if settings.inbox_session_id.is_none() {
+        settings.inbox_session_id = load_settings(&app).inbox_session_id;
+    }
+    settings::set_setting(&app, WATCH_FOLDER_SETTING_KEY, &settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_files_by_extension() {
+        assert_eq!(file_kind("memo.M4A"), Some(FileKind::Audio));
+        assert_eq!(file_kind("scan.jpeg"), Some(FileKind::Image));
+        assert_eq!(file_kind("budget.xlsx"), Some(FileKind::Office));
+        assert_eq!(file_kind("paper.pdf"), Some(FileKind::Pdf));
+        assert_eq!(file_kind("notes.md"), Some(FileKind::Text));
+        assert_eq!(file_kind("archive.zip"), None);
+        assert_eq!(file_kind("README"), None);
+    }
+}
@@ -0,0 +1,178 @@
+//! Anthropic web search result caching
+//!
+//! Every `web_search` Claude runs is billed, and regenerating a turn
+//! usually repeats the same searches. The searches a turn ran (its
+//! `server_tool_use` block and the `web_search_tool_result` that answered
+//! it) are cached for a few minutes, keyed by the conversation they were
+//! run for and the query. When the same conversation is sent again within
+//! that time, the cached searches are replayed as the start of Claude's
+//! reply, so it answers from them instead of searching again, and a
+//! `chat-cached-sources` event lets the UI mark the sources as cached.
+//! Replaying needs an assistant prefill, which thinking doesn't allow, so
+//! turns with thinking on always search afresh.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use crate::session_events;
+
+/// How long a search result is reused
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// (conversation key, normalized query) → search
+static CACHE: Mutex<BTreeMap<(u64, String), CachedSearch>> = Mutex::new(BTreeMap::new());
+
+/// Turn id → searches seen in its stream, cached when the turn succeeds
+static PENDING: Mutex<BTreeMap<String, PendingSearches>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone)]
+struct CachedSearch {
+    query: String,
+    tool_use: Value,
+    result: Value,
+    stored: Instant,
+}
+
+#[derive(Debug, Default)]
+struct PendingSearches {
+    /// Tool use id → (query, server_tool_use block)
+    tool_uses: BTreeMap<String, (String, Value)>,
+    results: Vec<Value>,
+}
+
+/// Event payload when a turn reuses cached searches
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CachedSourcesEvent {
+    pub turn_id: String,
+    pub queries: Vec<String>,
+    /// Search results across the queries
+    pub result_count: usize,
+}
+
+fn normalize_query(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Key for the conversation a request sends
+pub fn conversation_key(messages: &[Value]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(messages).unwrap_or_default().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Note a web_search call from a turn's stream
+pub fn record_query(turn_id: &str, tool_use_id: &str, query: &str) {
+    let tool_use = serde_json::json!({
+        "type": "server_tool_use",
+        "id": tool_use_id,
+        "name": "web_search",
+        "input": {"query": query},
+    });
+    PENDING
+        .lock()
+        .entry(turn_id.to_string())
+        .or_default()
+        .tool_uses
+        .insert(tool_use_id.to_string(), (query.to_string(), tool_use));
+}
+
+/// Note a web_search_tool_result block from a turn's stream
+pub fn record_result(turn_id: &str, block: &Value) {
+    // Errors come as an object rather than a list of results
+    if block["content"].is_array() {
+        PENDING.lock().entry(turn_id.to_string()).or_default().results.push(block.clone());
+    }
+}
+
+/// Cache the searches a turn ran for `conversation`, or drop them if the
+/// turn failed (`conversation` None)
+pub fn finish_turn(turn_id: &str, conversation: Option<u64>) {
+    let Some(pending) = PENDING.lock().remove(turn_id) else {
+        return;
+    };
+    let Some(conversation) = conversation else {
+        return;
+    };
+    let now = Instant::now();
+    let mut cache = CACHE.lock();
+    cache.retain(|_, search| now.duration_since(search.stored) < CACHE_TTL);
+    for result in pending.results {
+        let Some((query, tool_use)) = result["tool_use_id"].as_str().and_then(|id| pending.tool_uses.get(id)) else {
+            continue;
+        };
+        let search = CachedSearch {
+            query: query.clone(),
+            tool_use: tool_use.clone(),
+            result,
+            stored: now,
+        };
+        cache.insert((conversation, normalize_query(query)), search);
+    }
+}
+
+/// The cached searches for `conversation`, still within their TTL
+fn cached_searches(conversation: u64) -> Vec<CachedSearch> {
+    let now = Instant::now();
+    CACHE
+        .lock()
+        .range((conversation, String::new())..)
+        .take_while(|((key, _), _)| *key == conversation)
+        .filter(|(_, search)| now.duration_since(search.stored) < CACHE_TTL)
+        .map(|(_, search)| search.clone())
+        .collect()
+}
+
+/// Reply prefill replaying the cached searches for `conversation`, if any,
+/// and tell the frontend the turn's sources are cached
+pub fn cached_prefill(window: &tauri::Window, turn_id: &str, conversation: u64) -> Option<Value> {
+    let searches = cached_searches(conversation);
+    if searches.is_empty() {
+        return None;
+    }
+    let event = CachedSourcesEvent {
+        turn_id: turn_id.to_string(),
+        queries: searches.iter().map(|s| s.query.clone()).collect(),
+        result_count: searches.iter().map(|s| s.result["content"].as_array().map_or(0, Vec::len)).sum(),
+    };
+    if let Err(err) = session_events::emit(window, turn_id, "chat-cached-sources", event) {
+        eprintln!("Failed to emit chat-cached-sources event: {}", err);
+    }
+    let content: Vec<Value> = searches.into_iter().flat_map(|s| [s.tool_use, s.result]).collect();
+    Some(serde_json::json!({"role": "assistant", "content": content}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn successful_searches_are_cached_per_conversation() {
+        let conversation = conversation_key(&[serde_json::json!({"role": "user", "content": "Tide times?"})]);
+        record_query("turn-a", "srvtoolu_1", "Tide  times Brighton");
+        record_query("turn-a", "srvtoolu_2", "tide tables");
+        record_result(
+            "turn-a",
+            &serde_json::json!({"type": "web_search_tool_result", "tool_use_id": "srvtoolu_1", "content": [{"url": "https://example.com"}]}),
+        );
+        record_result(
+            "turn-a",
+            &serde_json::json!({"type": "web_search_tool_result", "tool_use_id": "srvtoolu_2", "content": {"type": "web_search_tool_result_error"}}),
+        );
+        finish_turn("turn-a", Some(conversation));
+
+        let cached = cached_searches(conversation);
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].query, "Tide  times Brighton");
+        assert_eq!(cached[0].tool_use["input"]["query"], "Tide  times Brighton");
+        assert!(cached_searches(conversation + 1).is_empty());
+
+        record_query("turn-b", "srvtoolu_3", "tide tables");
+        finish_turn("turn-b", None);
+        assert!(PENDING.lock().get("turn-b").is_none());
+    }
+}
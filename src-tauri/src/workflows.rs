@@ -0,0 +1,229 @@
+//! Saved workflows of chained prompts
+//!
+//! A workflow is an ordered list of prompt steps (e.g. summarize → critique →
+//! rewrite). Running one feeds each step's output into the next, stores every
+//! step as a user/assistant turn in a new session and emits `workflow-step`
+//! events as steps start and finish.
+
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::commands::{new_session_id, new_session_json, session_message, store_session, update_stored_session};
+use crate::llm::complete_prompt;
+use crate::settings;
+
+const WORKFLOWS_SETTING_KEY: &str = "workflows";
+
+/// Placeholder for the text the workflow was started with
+const INPUT_PLACEHOLDER: &str = "{{input}}";
+/// Placeholder for the previous step's output
+const PREVIOUS_PLACEHOLDER: &str = "{{previous}}";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowStep {
+    pub name: String,
+    /// Prompt template; may reference {{input}} and {{previous}}
+    pub prompt: String,
+    /// Overrides the workflow's model for this step
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workflow {
+    /// Assigned on first save when empty
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub model: String,
+    pub system_prompt: Option<String>,
+    #[serde(default)]
+    pub web_search_enabled: bool,
+    pub steps: Vec<WorkflowStep>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowStepStatus {
+    Started,
+    Completed,
+    Failed { error: String },
+}
+
+/// Event payload for workflow progress
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowStepEvent {
+    pub workflow_id: String,
+    pub session_id: String,
+    pub step_index: usize,
+    pub step_count: usize,
+    pub step_name: String,
+    pub status: WorkflowStepStatus,
+    /// The step's output (on completion)
+    pub output: Option<String>,
+}
+
+fn load_workflows(app: &tauri::AppHandle) -> Vec<Workflow> {
+    settings::get_setting(app, WORKFLOWS_SETTING_KEY).unwrap_or_default()
+}
+
+fn save_workflows(app: &tauri::AppHandle, workflows: &[Workflow]) -> Result<(), String> {
+    settings::set_setting(app, WORKFLOWS_SETTING_KEY, &workflows)
+}
+
+/// Fill a step's prompt template. A later step that doesn't reference
+/// {{previous}} gets the previous output appended, so simple instructions
+/// like "Critique this" chain without any template syntax.
+fn render_step_prompt(template: &str, input: &str, previous: Option<&str>) -> String {
+    let mut prompt = template.replace(INPUT_PLACEHOLDER, input);
+    match previous {
+        Some(previous) if template.contains(PREVIOUS_PLACEHOLDER) => {
+            prompt = prompt.replace(PREVIOUS_PLACEHOLDER, previous);
+        }
+        Some(previous) => {
+            prompt = format!("{}\n\n{}", prompt.trim_end(), previous);
+        }
+        None if !template.contains(INPUT_PLACEHOLDER) && !input.is_empty() => {
+            prompt = format!("{}\n\n{}", prompt.trim_end(), input);
+        }
+        None => {}
+    }
+    prompt.replace(PREVIOUS_PLACEHOLDER, "")
+}
+
+fn emit_step(window: &tauri::Window, event: WorkflowStepEvent) {
+    if let Err(err) = window.emit("workflow-step", event) {
+        eprintln!("Failed to emit workflow-step event: {}", err);
+    }
+}
+
+#[tauri::command]
+pub fn list_workflows(app: tauri::AppHandle) -> Vec<Workflow> {
+    load_workflows(&app)
+}
+
+/// Create or update a workflow (matched by id). Returns the saved workflow.
+#[tauri::command]
+pub fn save_workflow(app: tauri::AppHandle, workflow: Workflow) -> Result<Workflow, String> {
+    if workflow.steps.is_empty() {
+        return Err("A workflow needs at least one step".to_string());
+    }
+
+    let mut workflow = workflow;
+    if workflow.id.is_empty() {
+        workflow.id = new_session_id();
+    }
+
+    let mut workflows = load_workflows(&app);
+    match workflows.iter_mut().find(|w| w.id == workflow.id) {
+        Some(existing) => *existing = workflow.clone(),
+        None => workflows.push(workflow.clone()),
+    }
+    save_workflows(&app, &workflows)?;
+
+    Ok(workflow)
+}
+
+#[tauri::command]
+pub fn delete_workflow(app: tauri::AppHandle, workflow_id: String) -> Result<(), String> {
+    let mut workflows = load_workflows(&app);
+    workflows.retain(|w| w.id != workflow_id);
+    save_workflows(&app, &workflows)
+}
+
+/// Run a workflow on some input text. Creates a new session up front (so the
+/// frontend can open it while steps run) and appends each step's turn as it
+/// completes. Returns the session id.
+#[tauri::command]
+pub async fn run_workflow(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    workflow_id: String,
+    input: String,
+) -> Result<String, String> {
+    let workflow = load_workflows(&app)
+        .into_iter()
+        .find(|w| w.id == workflow_id)
+        .ok_or_else(|| format!("Workflow not found: {}", workflow_id))?;
+
+    let session = new_session_json(&workflow.name, &workflow.model, workflow.web_search_enabled, Vec::new());
+    let session_id = session["id"].as_str().unwrap_or_default().to_string();
+    store_session(&app, session)?;
+
+    let step_count = workflow.steps.len();
+    let mut previous: Option<String> = None;
+
+    for (step_index, step) in workflow.steps.iter().enumerate() {
+        let event = |status, output| WorkflowStepEvent {
+            workflow_id: workflow.id.clone(),
+            session_id: session_id.clone(),
+            step_index,
+            step_count,
+            step_name: step.name.clone(),
+            status,
+            output,
+        };
+        emit_step(&window, event(WorkflowStepStatus::Started, None));
+
+        let prompt = render_step_prompt(&step.prompt, &input, previous.as_deref());
+        let model = step.model.as_deref().unwrap_or(&workflow.model);
+        let output = match complete_prompt(
+            &app,
+            model,
+            workflow.system_prompt.as_deref(),
+            &prompt,
+            workflow.web_search_enabled,
+        )
+        .await
+        {
+            Ok(output) => output,
+            Err(error) => {
+                emit_step(&window, event(WorkflowStepStatus::Failed { error: error.to_string() }, None));
+                return Err(format!("Step \"{}\" failed: {}", step.name, error));
+            }
+        };
+
+        let turn_id = new_session_id();
+        update_stored_session(&app, &session_id, |fields| {
+            let messages = fields
+                .entry("messages")
+                .or_insert_with(|| serde_json::json!([]))
+                .as_array_mut()
+                .ok_or("Session messages is not an array")?;
+            messages.push(session_message("user", &prompt, &turn_id));
+            messages.push(session_message("assistant", &output, &turn_id));
+            fields.insert(
+                "updatedAt".to_string(),
+                serde_json::Value::String(chrono::Utc::now().to_rfc3339()),
+            );
+            Ok(())
+        })?;
+
+        emit_step(&window, event(WorkflowStepStatus::Completed, Some(output.clone())));
+        previous = Some(output);
+    }
+
+    Ok(session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_step_fills_or_appends_input() {
+        assert_eq!(render_step_prompt("Summarize: {{input}}", "text", None), "Summarize: text");
+        assert_eq!(render_step_prompt("Summarize this.", "text", None), "Summarize this.\n\ntext");
+    }
+
+    #[test]
+    fn later_steps_chain_previous_output() {
+        assert_eq!(
+            render_step_prompt("Critique {{previous}} given {{input}}", "src", Some("sum")),
+            "Critique sum given src"
+        );
+        assert_eq!(render_step_prompt("Rewrite it.", "src", Some("draft")), "Rewrite it.\n\ndraft");
+    }
+}
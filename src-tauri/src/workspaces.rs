@@ -0,0 +1,343 @@
+//! Workspaces
+//!
+//! A workspace groups sessions, the document folders used with them and the
+//! defaults for new sessions (persona, model), so separate client contexts
+//! stay apart. Sessions belong to a workspace through their `workspaceId`
+//! field, which also selects the workspace's system prompt layer (see
+//! `system_prompts`). Sessions created while a workspace is current join it
+//! and start with its persona and model.
+//!
+//! The files in a workspace's document folders can be listed and read as text
+//! (`list_workspace_documents`, `read_workspace_document`) to attach them to
+//! a conversation.
+//!
+//! A workspace can name an API key profile: while it is current, provider
+//! keys are read from that profile instead of the default keys, so each
+//! client's usage is billed to the right account.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::commands::{new_session_id, stored_sessions, update_stored_session};
+use crate::extraction::office_file_to_text;
+use crate::personas::{load_personas, PERSONA_ID_FIELD};
+use crate::settings;
+use crate::system_prompts::{self, WORKSPACE_ID_FIELD};
+
+const WORKSPACES_SETTING_KEY: &str = "workspaces";
+const CURRENT_WORKSPACE_SETTING_KEY: &str = "current_workspace";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    /// Assigned on first save when empty
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// Folders holding the workspace's reference documents
+    #[serde(default)]
+    pub document_folders: Vec<String>,
+    /// Persona applied to new sessions in the workspace
+    pub default_persona_id: Option<String>,
+    /// Model for new sessions in the workspace
+    pub default_model: Option<String>,
+    /// API key profile used while the workspace is current; None uses the
+    /// default keys
+    pub api_key_profile: Option<String>,
+}
+
+fn load_workspaces(app: &tauri::AppHandle) -> Vec<Workspace> {
+    settings::get_setting(app, WORKSPACES_SETTING_KEY).unwrap_or_default()
+}
+
+fn save_workspaces(app: &tauri::AppHandle, workspaces: &[Workspace]) -> Result<(), String> {
+    settings::set_setting(app, WORKSPACES_SETTING_KEY, &workspaces)
+}
+
+fn current_workspace(app: &tauri::AppHandle) -> Option<Workspace> {
+    let id: String = settings::get_setting(app, CURRENT_WORKSPACE_SETTING_KEY)?;
+    load_workspaces(app).into_iter().find(|w| w.id == id)
+}
+
+/// A file in one of a workspace's document folders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceDocument {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+}
+
+/// Whether a document folder file can be read as text
+fn readable_document(filename: &str) -> bool {
+    let Some((_, extension)) = filename.rsplit_once('.') else {
+        return false;
+    };
+    matches!(
+        extension.to_lowercase().as_str(),
+        "txt" | "md" | "csv" | "json" | "xml" | "html" | "htm" | "log" | "xlsx" | "xlsm" | "xls" | "ods" | "docx"
+    )
+}
+
+/// The model new chats use when none is given: the current workspace's
+/// default model, else the app's default chat model
+pub fn default_chat_model(app: &tauri::AppHandle) -> String {
+    current_workspace(app)
+        .and_then(|w| w.default_model)
+        .unwrap_or_else(|| settings::model_defaults(app).chat)
+}
+
+/// Put a newly created session in the current workspace and give it the
+/// workspace's default persona, unless it already names its own
+pub fn join_current_workspace(app: &tauri::AppHandle, session: &mut serde_json::Value) {
+    let Some(workspace) = current_workspace(app) else {
+        return;
+    };
+    let Some(fields) = session.as_object_mut() else {
+        return;
+    };
+    fields
+        .entry(WORKSPACE_ID_FIELD)
+        .or_insert_with(|| serde_json::Value::String(workspace.id.clone()));
+    if let Some(persona_id) = workspace.default_persona_id {
+        fields
+            .entry(PERSONA_ID_FIELD)
+            .or_insert(serde_json::Value::String(persona_id));
+    }
+}
+
+fn valid_profile_name(profile: &str) -> bool {
+    !profile.is_empty()
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Name under which a provider's key is stored for a key profile
+/// (`openai@acme`); the default keys use the plain provider name
+pub fn profile_key_name(provider: &str, profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("{}@{}", provider, profile),
+        None => provider.to_string(),
+    }
+}
+
+/// The API key profile of the current workspace, if any
+pub fn current_key_profile(app: &tauri::AppHandle) -> Option<String> {
+    current_workspace(app).and_then(|w| w.api_key_profile)
+}
+
+/// Check a key profile name given to an API key command
+pub fn validate_key_profile(profile: Option<&str>) -> Result<(), String> {
+    match profile {
+        Some(profile) if !valid_profile_name(profile) => Err(
+            "API key profile names may only contain letters, digits, '-' and '_'".to_string(),
+        ),
+        _ => Ok(()),
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn list_workspaces(app: tauri::AppHandle) -> Vec<Workspace> {
+    load_workspaces(&app)
+}
+
+/// Create or update a workspace (matched by id). Returns the saved workspace.
+#[tauri::command]
+pub fn save_workspace(app: tauri::AppHandle, workspace: Workspace) -> Result<Workspace, String> {
+    let mut workspace = workspace;
+    workspace.name = workspace.name.trim().to_string();
+    if workspace.name.is_empty() {
+        return Err("Workspace name cannot be empty".to_string());
+    }
+    validate_key_profile(workspace.api_key_profile.as_deref())?;
+    if let Some(persona_id) = &workspace.default_persona_id {
+        if !load_personas(&app).iter().any(|p| &p.id == persona_id) {
+            return Err(format!("Persona not found: {}", persona_id));
+        }
+    }
+    if workspace.id.is_empty() {
+        workspace.id = new_session_id();
+    }
+
+    let mut workspaces = load_workspaces(&app);
+    match workspaces.iter_mut().find(|w| w.id == workspace.id) {
+        Some(existing) => *existing = workspace.clone(),
+        None => workspaces.push(workspace.clone()),
+    }
+    save_workspaces(&app, &workspaces)?;
+
+    Ok(workspace)
+}
+
+/// Delete a workspace and its prompt layer. Its sessions are kept and no
+/// longer belong to any workspace.
+#[tauri::command]
+pub fn delete_workspace(app: tauri::AppHandle, workspace_id: String) -> Result<(), String> {
+    let mut workspaces = load_workspaces(&app);
+    workspaces.retain(|w| w.id != workspace_id);
+    save_workspaces(&app, &workspaces)?;
+    system_prompts::set_workspace_system_prompt(app.clone(), workspace_id.clone(), None)?;
+
+    if settings::get_setting::<String>(&app, CURRENT_WORKSPACE_SETTING_KEY).as_deref() == Some(workspace_id.as_str()) {
+        settings::set_setting(&app, CURRENT_WORKSPACE_SETTING_KEY, &None::<String>)?;
+    }
+    for session in stored_sessions(&app)? {
+        if session[WORKSPACE_ID_FIELD].as_str() != Some(workspace_id.as_str()) {
+            continue;
+        }
+        if let Some(id) = session["id"].as_str() {
+            update_stored_session(&app, id, |fields| {
+                fields.remove(WORKSPACE_ID_FIELD);
+                Ok(())
+            })?;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_current_workspace(app: tauri::AppHandle) -> Option<Workspace> {
+    current_workspace(&app)
+}
+
+/// Switch to a workspace (None leaves all workspaces). Returns it so the
+/// frontend can apply its defaults.
+#[tauri::command]
+pub fn set_current_workspace(app: tauri::AppHandle, workspace_id: Option<String>) -> Result<Option<Workspace>, String> {
+    let workspace = match &workspace_id {
+        Some(id) => Some(
+            load_workspaces(&app)
+                .into_iter()
+                .find(|w| &w.id == id)
+                .ok_or_else(|| format!("Workspace not found: {}", id))?,
+        ),
+        None => None,
+    };
+    settings::set_setting(&app, CURRENT_WORKSPACE_SETTING_KEY, &workspace_id)?;
+    Ok(workspace)
+}
+
+/// Move a session into a workspace (None removes it from its workspace)
+#[tauri::command]
+pub fn set_session_workspace(
+    app: tauri::AppHandle,
+    session_id: String,
+    workspace_id: Option<String>,
+) -> Result<(), String> {
+    if let Some(id) = &workspace_id {
+        if !load_workspaces(&app).iter().any(|w| &w.id == id) {
+            return Err(format!("Workspace not found: {}", id));
+        }
+    }
+    update_stored_session(&app, &session_id, |fields| {
+        match &workspace_id {
+            Some(id) => {
+                fields.insert(WORKSPACE_ID_FIELD.to_string(), serde_json::Value::String(id.clone()));
+            }
+            None => {
+                fields.remove(WORKSPACE_ID_FIELD);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Ids of the sessions in a workspace
+#[tauri::command]
+pub fn list_workspace_sessions(app: tauri::AppHandle, workspace_id: String) -> Result<Vec<String>, String> {
+    Ok(stored_sessions(&app)?
+        .iter()
+        .filter(|s| s[WORKSPACE_ID_FIELD].as_str() == Some(workspace_id.as_str()))
+        .filter_map(|s| s["id"].as_str().map(str::to_string))
+        .collect())
+}
+
+/// Readable files directly inside a workspace's document folders
+#[tauri::command]
+pub fn list_workspace_documents(app: tauri::AppHandle, workspace_id: String) -> Result<Vec<WorkspaceDocument>, String> {
+    let workspace = load_workspaces(&app)
+        .into_iter()
+        .find(|w| w.id == workspace_id)
+        .ok_or_else(|| format!("Workspace not found: {}", workspace_id))?;
+
+    let mut documents = Vec::new();
+    for folder in &workspace.document_folders {
+        let Ok(entries) = fs::read_dir(folder) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') || !readable_document(&name) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_file() {
+                documents.push(WorkspaceDocument {
+                    name,
+                    path: entry.path().to_string_lossy().to_string(),
+                    size: metadata.len(),
+                });
+            }
+        }
+    }
+    documents.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(documents)
+}
+
+/// Text of a file in one of the current workspace's document folders.
+/// Office files are converted to text.
+#[tauri::command]
+pub fn read_workspace_document(app: tauri::AppHandle, path: String) -> Result<String, String> {
+    let path = fs::canonicalize(&path).map_err(|e| format!("Failed to open document: {}", e))?;
+    let folders: Vec<PathBuf> = current_workspace(&app)
+        .map(|w| w.document_folders)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|folder| fs::canonicalize(folder).ok())
+        .collect();
+    if !folders.iter().any(|folder| path.parent() == Some(folder.as_path())) {
+        return Err("Document is not in a folder of the current workspace".to_string());
+    }
+
+    let filename = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if !readable_document(&filename) {
+        return Err(format!("Unsupported file type: {}", filename));
+    }
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read document: {}", e))?;
+    match office_file_to_text(&bytes, &filename) {
+        Some(text) => text,
+        None => Ok(String::from_utf8_lossy(&bytes).to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_profiles_get_their_own_key_names() {
+        assert_eq!(profile_key_name("openai", None), "openai");
+        assert_eq!(profile_key_name("openai", Some("acme")), "openai@acme");
+        assert!(validate_key_profile(Some("client-a_2")).is_ok());
+        assert!(validate_key_profile(Some("acme corp")).is_err());
+        assert!(validate_key_profile(Some("")).is_err());
+        assert!(validate_key_profile(None).is_ok());
+    }
+
+    #[test]
+    fn only_text_and_office_documents_are_readable() {
+        assert!(readable_document("notes.md"));
+        assert!(readable_document("Budget.XLSX"));
+        assert!(!readable_document("scan.pdf"));
+        assert!(!readable_document("README"));
+    }
+}
@@ -1,14 +1,18 @@
 import { useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { AppLayout } from './components/layout/AppLayout';
 import { useSessionStore } from './stores/sessionStore';
 import { useDiscoveryStore } from './stores/discoveryStore';
 import { useSettingsStore } from './stores/settingsStore';
 import { useChatStore } from './stores/chatStore';
 import { checkForUpdate } from './lib/updateChecker';
+import { logError } from './lib/logger';
+import type { TranscriptionSettings } from './lib/types';
 import './index.css';
 
 function App() {
   const { loadSessionList, setActiveSessionId } = useSessionStore();
+  const activeSessionId = useSessionStore((state) => state.activeSessionId);
   const { fontScale, increaseFontScale, decreaseFontScale, resetFontScale, theme, setUpdateInfo } = useSettingsStore();
 
   // Check for updates on app launch
@@ -20,6 +24,13 @@ function App() {
     });
   }, [setUpdateInfo]);
 
+  // Load the transcription backend so voice input uses local whisper.cpp when chosen
+  useEffect(() => {
+    invoke<TranscriptionSettings>('get_transcription_settings')
+      .then((settings) => useSettingsStore.getState().setTranscriptionBackend(settings.backend))
+      .catch((error) => logError('App.loadTranscriptionSettings', error));
+  }, []);
+
   useEffect(() => {
     // Load session list on app start
     loadSessionList().then(() => {
@@ -33,6 +44,19 @@ function App() {
     });
   }, [loadSessionList, setActiveSessionId]);
 
+  // Route the active session's turn events to this window (see session_events.rs)
+  useEffect(() => {
+    if (!activeSessionId) return;
+    invoke('subscribe_session_events', { sessionId: activeSessionId }).catch((error) =>
+      logError('App.subscribeSessionEvents', error)
+    );
+    return () => {
+      invoke('unsubscribe_session_events', { sessionId: activeSessionId }).catch((error) =>
+        logError('App.unsubscribeSessionEvents', error)
+      );
+    };
+  }, [activeSessionId]);
+
   // Apply font scale to document root
   useEffect(() => {
     document.documentElement.style.setProperty('--font-scale', fontScale.toString());
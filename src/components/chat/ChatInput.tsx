@@ -2,6 +2,7 @@ import { useRef, useEffect, useState, useCallback, useMemo, memo } from 'react';
 import { useChatStore } from '../../stores/chatStore';
 import { useSettingsStore } from '../../stores/settingsStore';
 import { useShallow } from 'zustand/react/shallow';
+import { invoke } from '@tauri-apps/api/core';
 import { useChat, useTextInputContextMenu } from '../../hooks';
 import { AttachmentButton } from './AttachmentButton';
 import { AttachmentPreview } from './AttachmentPreview';
@@ -20,6 +21,8 @@ import {
   getValidAnthropicThinkingLevel,
   getAnthropicThinkingLetter,
 } from '../../lib/thinkingOptions';
+import { logError } from '../../lib/logger';
+import type { PastedTextResult, VoiceIntent } from '../../lib/types';
 export const ChatInput = memo(function ChatInput() {
   // Use local state for instant typing responsiveness
   // Only sync to Zustand when needed (submit, blur, external changes)
@@ -28,6 +31,10 @@ export const ChatInput = memo(function ChatInput() {
   const isStreaming = useChatStore((state) => state.isStreaming);
   const attachments = useChatStore((state) => state.attachments);
   const registerChatInputFocus = useChatStore((state) => state.registerChatInputFocus);
+  const incognito = useChatStore((state) => state.incognito);
+  const setIncognito = useChatStore((state) => state.setIncognito);
+  const addAttachment = useChatStore((state) => state.addAttachment);
+  const appendToInput = useChatStore((state) => state.appendToInput);
 
   // Local state for textarea - bypasses React/Zustand on each keystroke
   const [localValue, setLocalValue] = useState(storeInputValue);
@@ -57,6 +64,38 @@ export const ChatInput = memo(function ChatInput() {
     setLocalValue
   );
 
+  // Pastes estimated above this many tokens are attached instead of inlined
+  const [pasteThreshold, setPasteThreshold] = useState<number | null>(null);
+  useEffect(() => {
+    invoke<number>('get_paste_threshold')
+      .then(setPasteThreshold)
+      .catch((err) => logError('ChatInput.loadPasteThreshold', err));
+  }, []);
+
+  const handlePaste = useCallback(
+    (e: React.ClipboardEvent<HTMLTextAreaElement>) => {
+      const text = e.clipboardData.getData('text/plain');
+      // Same ~4 characters per token estimate the backend uses
+      if (pasteThreshold === null || Math.ceil(text.length / 4) <= pasteThreshold) return;
+      e.preventDefault();
+      invoke<PastedTextResult>('prepare_pasted_text', { text })
+        .then(({ attachment }) => {
+          if (attachment) {
+            addAttachment({
+              type: attachment.type,
+              name: attachment.name,
+              mimeType: attachment.mimeType,
+              data: attachment.data,
+            });
+          } else {
+            appendToInput(text);
+          }
+        })
+        .catch((err) => logError('ChatInput.preparePastedText', err));
+    },
+    [pasteThreshold, addAttachment, appendToInput]
+  );
+
   // Register focus function for global keyboard handling
   useEffect(() => {
     registerChatInputFocus(() => {
@@ -157,19 +196,34 @@ export const ChatInput = memo(function ChatInput() {
     });
   }, [setFrontierLLM, frontierLLM.webSearchEnabled]);
 
+  const toggleIncognito = useCallback(() => {
+    setIncognito(!incognito);
+  }, [setIncognito, incognito]);
+
   const handleModelChange = useCallback((model: string) => {
     setFrontierLLM({ model });
   }, [setFrontierLLM]);
 
-  const handleTranscription = useCallback((text: string) => {
+  const handleTranscription = useCallback((text: string, intent: VoiceIntent | null) => {
+    // "Scratch that" drops what was just dictated
+    if (intent === 'scratch_that') return;
+
     if (voiceMode === 'textbox') {
-      const newValue = localValue ? `${localValue} ${text}` : text;
+      const separator = localValue && text && !localValue.endsWith('\n') ? ' ' : '';
+      const lineBreak = intent === 'new_line' ? '\n' : intent === 'new_paragraph' ? '\n\n' : '';
+      const newValue = `${localValue}${separator}${text}${lineBreak}`;
+      // "Send it" sends the whole input; while a reply streams the text stays in the box
+      if (intent === 'send' && newValue.trim() && !isStreaming) {
+        setLocalValue('');
+        sendMessage(newValue.trim());
+        return;
+      }
       setLocalValue(newValue);
       setStoreInput(newValue);
-    } else if (voiceMode === 'chat_request') {
+    } else if (voiceMode === 'chat_request' && text) {
       sendTranscribedMessage(text);
     }
-  }, [voiceMode, localValue, setStoreInput, sendTranscribedMessage]);
+  }, [voiceMode, localValue, isStreaming, setStoreInput, sendMessage, sendTranscribedMessage]);
 
   // Auto-resize textarea
   useEffect(() => {
@@ -414,6 +468,42 @@ export const ChatInput = memo(function ChatInput() {
           </button>
         </Tooltip>
 
+        {/* Incognito Toggle */}
+        <Tooltip
+          content={
+            incognito
+              ? 'Incognito: new messages are not saved'
+              : 'Send incognito (nothing is saved)'
+          }
+        >
+          <button
+            onClick={toggleIncognito}
+            className={`
+              p-2 rounded transition-colors
+              ${
+                incognito
+                  ? 'text-purple-600 bg-purple-50 hover:bg-purple-100 dark:text-purple-400 dark:bg-purple-900/50 dark:hover:bg-purple-900/70'
+                  : 'text-stone-500 hover:text-purple-600 hover:bg-purple-50 dark:text-gray-400 dark:hover:text-purple-400 dark:hover:bg-purple-900/30'
+              }
+            `}
+            aria-label="Toggle incognito"
+          >
+            <svg
+              className="w-5 h-5"
+              fill="none"
+              stroke="currentColor"
+              viewBox="0 0 24 24"
+            >
+              <path
+                strokeLinecap="round"
+                strokeLinejoin="round"
+                strokeWidth={2}
+                d="M13.875 18.825A10.05 10.05 0 0112 19c-4.478 0-8.268-2.943-9.543-7a9.97 9.97 0 011.563-3.029m5.858.908a3 3 0 114.243 4.243M9.878 9.878l4.242 4.242M9.88 9.88l-3.29-3.29m7.532 7.532l3.29 3.29M3 3l3.59 3.59m0 0A9.953 9.953 0 0112 5c4.478 0 8.268 2.943 9.543 7a10.025 10.025 0 01-4.132 5.411m0 0L21 21"
+              />
+            </svg>
+          </button>
+        </Tooltip>
+
         <textarea
           ref={textareaRef}
           value={localValue}
@@ -423,6 +513,7 @@ export const ChatInput = memo(function ChatInput() {
             setStoreInput(e.target.value);
           }}
           onKeyDown={handleKeyDown}
+          onPaste={handlePaste}
           onContextMenu={handleContextMenu}
           placeholder={
             isStreaming ? 'Waiting for response...' : 'Type your message...'
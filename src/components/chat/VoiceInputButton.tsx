@@ -1,9 +1,10 @@
 import { useVoiceInput } from '../../hooks/useVoiceInput';
+import type { VoiceIntent } from '../../lib/types';
 import { useSettingsStore } from '../../stores/settingsStore';
 import { Tooltip } from '../shared/Tooltip';
 
 interface VoiceInputButtonProps {
-  onTranscription: (text: string) => void;
+  onTranscription: (text: string, intent: VoiceIntent | null) => void;
 }
 
 export function VoiceInputButton({ onTranscription }: VoiceInputButtonProps) {
@@ -22,8 +23,8 @@ export function VoiceInputButton({ onTranscription }: VoiceInputButtonProps) {
     if (isRecording) {
       // Stop recording and transcribe using the appropriate model
       const transcript = await stopRecording(voiceModel);
-      if (transcript) {
-        onTranscription(transcript);
+      if (transcript && (transcript.text || transcript.intent)) {
+        onTranscription(transcript.text, transcript.intent);
       }
     } else if (!isTranscribing) {
       startRecording();
@@ -1,17 +1,16 @@
 import { useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { save, open } from '@tauri-apps/plugin-dialog';
-import { writeTextFile, readTextFile } from '@tauri-apps/plugin-fs';
+import { writeTextFile } from '@tauri-apps/plugin-fs';
 import { ConfirmDialog } from '../shared/ConfirmDialog';
 import { useSessionStore } from '../../stores/sessionStore';
 import { useChatStore } from '../../stores/chatStore';
 import { useDiscoveryStore } from '../../stores/discoveryStore';
 import { logError } from '../../lib/logger';
-import { migrateChatSessionSettings } from '../../lib/sessionMigration';
-import type { ChatSession, ChatExportData, DiscoveryItem, Message } from '../../lib/types';
+import type { ChatSession, ChatExportData, ImportSummary } from '../../lib/types';
 
 export function SavedChatsSection() {
-  const { sessionMetas, loadSessionList } = useSessionStore();
+  const { totalSessions, loadSessionList } = useSessionStore();
   const [showDeleteConfirm, setShowDeleteConfirm] = useState(false);
   const [isDeleting, setIsDeleting] = useState(false);
   const [isExporting, setIsExporting] = useState(false);
@@ -36,6 +35,7 @@ export function SavedChatsSection() {
         activeSessionId: newId,
         sessionMetas: [],
         sessionCache: new Map(),
+        totalSessions: 0,
         isDirty: false,
       });
 
@@ -88,9 +88,9 @@ export function SavedChatsSection() {
     setImportSuccess(null);
 
     try {
-      // Open file dialog
+      // Open file dialog; ChatGPT and Claude.ai exports are zip files
       const filePath = await open({
-        filters: [{ name: 'JSON', extensions: ['json'] }],
+        filters: [{ name: 'Chat exports', extensions: ['json', 'zip'] }],
         multiple: false,
       });
 
@@ -99,89 +99,21 @@ export function SavedChatsSection() {
         return;
       }
 
-      // Read file content
-      const content = await readTextFile(filePath as string);
-      const importData = JSON.parse(content) as ChatExportData;
-
-      // Validate structure
-      if (!importData.version || !Array.isArray(importData.sessions)) {
-        throw new Error('Invalid export file format');
-      }
-
-      // Get existing session IDs
-      const existingSessions = await invoke<ChatSession[]>('list_chat_sessions');
-      const existingIds = new Set(existingSessions.map((s) => s.id));
-
-      let importedCount = 0;
-
-      for (const session of importData.sessions) {
-        // Check for ID conflict
-        let sessionToSave = session;
-
-        if (existingIds.has(session.id)) {
-          // Generate new IDs for the session and all related items
-          const newSessionId = crypto.randomUUID();
-          const turnIdMap = new Map<string, string>();
-
-          // Collect all unique turnIds and create mappings
-          for (const msg of session.messages) {
-            if (msg.turnId && !turnIdMap.has(msg.turnId)) {
-              turnIdMap.set(msg.turnId, crypto.randomUUID());
-            }
-          }
-          for (const item of session.discoveryItems) {
-            if (item.turnId && !turnIdMap.has(item.turnId)) {
-              turnIdMap.set(item.turnId, crypto.randomUUID());
-            }
-          }
-
-          // Transform messages with new IDs
-          const newMessages: Message[] = session.messages.map((msg) => ({
-            ...msg,
-            id: crypto.randomUUID(),
-            turnId: msg.turnId ? turnIdMap.get(msg.turnId) : undefined,
-          }));
-
-          // Transform discovery items with new IDs
-          const newDiscoveryItems: DiscoveryItem[] = session.discoveryItems.map((item) => ({
-            ...item,
-            id: crypto.randomUUID(),
-            turnId: turnIdMap.get(item.turnId) || item.turnId,
-            sessionId: newSessionId,
-          }));
-
-          sessionToSave = {
-            ...session,
-            id: newSessionId,
-            messages: newMessages,
-            discoveryItems: newDiscoveryItems,
-          };
-        } else {
-          // Update sessionId in discovery items to match (in case of old export format)
-          sessionToSave = {
-            ...session,
-            discoveryItems: session.discoveryItems.map((item) => ({
-              ...item,
-              sessionId: session.id,
-            })),
-          };
-        }
-
-        // Migrate legacy model IDs (Opus 4.5 → Opus 4.6) before saving
-        sessionToSave = {
-          ...sessionToSave,
-          settings: migrateChatSessionSettings(sessionToSave.settings),
-        };
-
-        // Save the session
-        await invoke('save_chat_session', { session: sessionToSave });
-        importedCount++;
-      }
+      // The backend validates each session and stores it under a fresh ID
+      const summary = await invoke<ImportSummary>('import_chat_sessions', { path: filePath as string });
 
       // Reload session list
       await loadSessionList();
 
-      setImportSuccess(`Imported ${importedCount} chat${importedCount !== 1 ? 's' : ''} successfully`);
+      const importedCount = summary.sessionIds.length;
+      const notes = [
+        summary.skipped > 0 ? `${summary.skipped} already imported` : null,
+        summary.invalid > 0 ? `${summary.invalid} unreadable` : null,
+      ].filter(Boolean);
+      setImportSuccess(
+        `Imported ${importedCount} chat${importedCount !== 1 ? 's' : ''}` +
+          (notes.length > 0 ? ` (skipped ${notes.join(', ')})` : ' successfully')
+      );
     } catch (error) {
       logError('SavedChatsSection.handleImportChats', error);
       setImportError('Failed to import chats: ' + String(error));
@@ -193,13 +125,13 @@ export function SavedChatsSection() {
   return (
     <section className="space-y-4 pt-4 flex flex-col items-center">
       <p className="text-sm text-gray-500 dark:text-gray-400">
-        You have {sessionMetas.length} saved chat{sessionMetas.length !== 1 ? 's' : ''}.
+        You have {totalSessions} saved chat{totalSessions !== 1 ? 's' : ''}.
       </p>
 
       <div className="flex flex-col gap-2 items-center">
         <button
           onClick={handleExport}
-          disabled={isExporting || sessionMetas.length === 0}
+          disabled={isExporting || totalSessions === 0}
           className="px-3 py-1.5 text-sm font-medium text-gray-700 bg-gray-100 hover:bg-gray-200 dark:text-gray-200 dark:bg-gray-700 dark:hover:bg-gray-600 rounded-md border border-gray-300 dark:border-gray-600 transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
         >
           {isExporting ? 'Exporting...' : 'Export Chats'}
@@ -215,7 +147,7 @@ export function SavedChatsSection() {
 
         <button
           onClick={() => setShowDeleteConfirm(true)}
-          disabled={sessionMetas.length === 0}
+          disabled={totalSessions === 0}
           className="px-3 py-1.5 text-sm font-medium text-red-700 bg-red-50 hover:bg-red-100 dark:text-red-400 dark:bg-red-900/30 dark:hover:bg-red-900/50 rounded-md border border-red-300 dark:border-red-700 transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
         >
           Delete All Chats
@@ -237,7 +169,7 @@ export function SavedChatsSection() {
         title="Delete All Saved Chats"
         message={
           <div className="space-y-2">
-            <p>Are you sure you want to delete all {sessionMetas.length} saved chat{sessionMetas.length !== 1 ? 's' : ''}?</p>
+            <p>Are you sure you want to delete all {totalSessions} saved chat{totalSessions !== 1 ? 's' : ''}?</p>
             <p className="font-medium">This action cannot be undone.</p>
           </div>
         }
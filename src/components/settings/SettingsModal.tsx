@@ -3,6 +3,7 @@ import { Modal } from '../shared/Modal';
 import { AlertModal } from '../shared/AlertModal';
 import { ApiKeyForm } from './ApiKeyForm';
 import { SavedChatsSection } from './SavedChatsSection';
+import { TranscriptionSection } from './TranscriptionSection';
 import { useSettingsStore, type SettingsTab } from '../../stores/settingsStore';
 import { APP_VERSION, checkForUpdate } from '../../lib/updateChecker';
 import type { ThemeMode, VoiceMode } from '../../lib/types';
@@ -115,6 +116,8 @@ export function SettingsModal({ onClose }: SettingsModalProps) {
                 </div>
               </section>
 
+              <TranscriptionSection />
+
               <hr className="border-stone-200 dark:border-gray-700" />
 
               {/* Auto Model Selection */}
@@ -0,0 +1,158 @@
+import { useState, useEffect, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { useSettingsStore } from '../../stores/settingsStore';
+import { errorMessage, logError } from '../../lib/logger';
+import type {
+  TranscriptionBackend,
+  TranscriptionSettings,
+  WhisperModelInfo,
+  WhisperModelDownloadProgressEvent,
+} from '../../lib/types';
+
+export function TranscriptionSection() {
+  const setTranscriptionBackend = useSettingsStore((state) => state.setTranscriptionBackend);
+  const [available, setAvailable] = useState(false);
+  const [settings, setSettings] = useState<TranscriptionSettings | null>(null);
+  const [models, setModels] = useState<WhisperModelInfo[]>([]);
+  const [downloading, setDownloading] = useState<WhisperModelDownloadProgressEvent | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const loadModels = useCallback(async () => {
+    try {
+      setModels(await invoke<WhisperModelInfo[]>('list_whisper_models'));
+    } catch (err) {
+      logError('TranscriptionSection.loadModels', err);
+    }
+  }, []);
+
+  useEffect(() => {
+    invoke<boolean>('local_transcription_available')
+      .then(setAvailable)
+      .catch((err) => logError('TranscriptionSection.loadAvailability', err));
+    invoke<TranscriptionSettings>('get_transcription_settings')
+      .then(setSettings)
+      .catch((err) => logError('TranscriptionSection.loadSettings', err));
+    loadModels();
+  }, [loadModels]);
+
+  useEffect(() => {
+    const unlisten = listen<WhisperModelDownloadProgressEvent>('whisper-model-download-progress', (event) => {
+      setDownloading(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const saveSettings = async (updated: TranscriptionSettings) => {
+    setError(null);
+    try {
+      await invoke('set_transcription_settings', { settings: updated });
+      setSettings(updated);
+      setTranscriptionBackend(updated.backend);
+    } catch (err) {
+      logError('TranscriptionSection.saveSettings', err);
+      setError(errorMessage(err));
+    }
+  };
+
+  const handleDownload = async (name: string) => {
+    setError(null);
+    setDownloading({ name, downloadedBytes: 0, totalBytes: null });
+    try {
+      await invoke('download_whisper_model', { name });
+    } catch (err) {
+      logError('TranscriptionSection.downloadModel', err);
+      setError(errorMessage(err));
+    } finally {
+      setDownloading(null);
+      loadModels();
+    }
+  };
+
+  const handleDelete = async (name: string) => {
+    setError(null);
+    try {
+      await invoke('delete_whisper_model', { name });
+    } catch (err) {
+      logError('TranscriptionSection.deleteModel', err);
+      setError(errorMessage(err));
+    }
+    loadModels();
+  };
+
+  if (!settings) return null;
+
+  const selectedModel = models.find((m) => m.name === settings.model);
+
+  return (
+    <section className="space-y-3">
+      <div className="flex items-center gap-3">
+        <label htmlFor="transcriptionBackendSelect" className="text-sm font-medium text-gray-700 dark:text-gray-300">
+          Transcription
+        </label>
+        <select
+          id="transcriptionBackendSelect"
+          value={settings.backend}
+          onChange={(e) => saveSettings({ ...settings, backend: e.target.value as TranscriptionBackend })}
+          className="px-3 py-1.5 bg-stone-100 dark:bg-gray-700 rounded-lg border border-stone-300 dark:border-gray-600 text-sm text-gray-700 dark:text-gray-200 focus:border-stone-400 dark:focus:border-gray-500 focus:outline-none cursor-pointer"
+        >
+          <option value="openai" className="bg-white dark:bg-gray-700 dark:text-gray-200">Cloud (OpenAI or Gemini key)</option>
+          <option value="local" disabled={!available} className="bg-white dark:bg-gray-700 dark:text-gray-200">
+            On this device (Whisper){available ? '' : ' - not included in this build'}
+          </option>
+        </select>
+      </div>
+
+      {settings.backend === 'local' && (
+        <div className="space-y-2">
+          {selectedModel && !selectedModel.downloaded && (
+            <p className="text-sm text-amber-600 dark:text-amber-400">
+              Download the {selectedModel.name} model to use voice input.
+            </p>
+          )}
+          {models.map((model) => {
+            const isDownloading = downloading?.name === model.name;
+            const progress = isDownloading && downloading.totalBytes
+              ? Math.round((downloading.downloadedBytes / downloading.totalBytes) * 100)
+              : null;
+            return (
+              <div key={model.name} className="flex items-center gap-3 text-sm text-gray-700 dark:text-gray-300">
+                <input
+                  type="radio"
+                  id={`whisperModel-${model.name}`}
+                  name="whisperModel"
+                  checked={settings.model === model.name}
+                  onChange={() => saveSettings({ ...settings, model: model.name })}
+                  className="h-4 w-4 cursor-pointer"
+                />
+                <label htmlFor={`whisperModel-${model.name}`} className="flex-1 cursor-pointer">
+                  {model.name} <span className="text-gray-500 dark:text-gray-400">({model.sizeMb} MB)</span>
+                </label>
+                {model.downloaded ? (
+                  <button
+                    onClick={() => handleDelete(model.name)}
+                    className="px-2 py-1 text-xs font-medium text-red-700 bg-red-50 hover:bg-red-100 dark:text-red-400 dark:bg-red-900/30 dark:hover:bg-red-900/50 rounded-md border border-red-300 dark:border-red-700 transition-colors"
+                  >
+                    Delete
+                  </button>
+                ) : (
+                  <button
+                    onClick={() => handleDownload(model.name)}
+                    disabled={downloading !== null}
+                    className="px-2 py-1 text-xs font-medium text-gray-700 bg-gray-100 hover:bg-gray-200 dark:text-gray-200 dark:bg-gray-700 dark:hover:bg-gray-600 rounded-md border border-gray-300 dark:border-gray-600 transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+                  >
+                    {isDownloading ? (progress !== null ? `${progress}%` : 'Downloading...') : 'Download'}
+                  </button>
+                )}
+              </div>
+            );
+          })}
+        </div>
+      )}
+
+      {error && <p className="text-sm text-red-600 dark:text-red-400">{error}</p>}
+    </section>
+  );
+}
@@ -18,6 +18,9 @@ export function ChatSidebar({ width }: ChatSidebarProps) {
     searchQuery,
     setSearchQuery,
     getFilteredMetas,
+    hasMoreSessions,
+    loadMoreSessions,
+    isLoadingMoreSessions,
     switchToSession,
     deleteSession,
     renameSession,
@@ -173,6 +176,16 @@ export function ChatSidebar({ width }: ChatSidebarProps) {
               />
             ))
           )}
+          {/* Older sessions load a page at a time; search covers loaded ones */}
+          {!isLoadingSessions && hasMoreSessions() && (
+            <button
+              onClick={() => loadMoreSessions()}
+              disabled={isLoadingMoreSessions}
+              className="w-full px-3 py-2 text-sm text-stone-500 hover:text-stone-700 hover:bg-stone-100 dark:text-gray-400 dark:hover:text-gray-200 dark:hover:bg-gray-800 transition-colors disabled:opacity-50"
+            >
+              {isLoadingMoreSessions ? 'Loading...' : 'Load older chats'}
+            </button>
+          )}
         </div>
       </div>
 
@@ -8,6 +8,7 @@ const DEFAULT_CONFIG: ApiKeysConfig = {
   anthropic: false,
   openai: false,
   google: false,
+  openrouter: false,
 };
 
 export function useApiKeys() {
@@ -1,6 +1,6 @@
 import { useEffect, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { listen } from '@tauri-apps/api/event';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 import { useChatStore } from '../stores/chatStore';
 import { useSettingsStore } from '../stores/settingsStore';
 import { useSessionStore } from '../stores/sessionStore';
@@ -14,7 +14,7 @@ import {
   clearStreamingBuffer,
   flushStreamingBuffer,
 } from '../lib/streamingBuffer';
-import type { Message, ContentBlock, StreamDelta, StreamEvent, ContainerIdEvent, ExecutionDelta, Citation, InlineCitation, GeneratedFile } from '../lib/types';
+import type { Message, Attachment, ContentBlock, StreamDelta, StreamEvent, StreamDoneEvent, ContainerIdEvent, ThinkingBlocksEvent, ExecutionDelta, Citation, InlineCitation, GeneratedFile } from '../lib/types';
 
 /**
  * Process execution delta and update UI state.
@@ -167,10 +167,12 @@ export function useChat() {
   }, [updateStreamingContent]);
 
   // Set up streaming listeners
-  // Events now include turn_id in payload, so we use that for routing instead of a shared ref
+  // Events now include turn_id in payload, so we use that for routing instead of a shared ref.
+  // Listeners are window-scoped so the backend can route a session's events to this window only.
   useEffect(() => {
     const setupListeners = async () => {
-      const unlistenDelta = await listen<StreamDelta>('chat-stream-delta', (event) => {
+      const appWindow = getCurrentWebviewWindow();
+      const unlistenDelta = await appWindow.listen<StreamDelta>('chat-stream-delta', (event) => {
         const delta = event.payload;
         const turnId = delta.turn_id;
         const backgroundStore = useBackgroundStreamStore.getState();
@@ -245,7 +247,7 @@ export function useChat() {
         }
       });
 
-      const unlistenDone = await listen<StreamEvent>('chat-stream-done', (event) => {
+      const unlistenDone = await appWindow.listen<StreamDoneEvent>('chat-stream-done', (event) => {
         const turnId = event.payload.turn_id;
         const backgroundStore = useBackgroundStreamStore.getState();
         const chatStore = useChatStore.getState();
@@ -291,7 +293,7 @@ export function useChat() {
         }
       });
 
-      const unlistenCancelled = await listen<StreamEvent>('chat-stream-cancelled', (event) => {
+      const unlistenCancelled = await appWindow.listen<StreamEvent>('chat-stream-cancelled', (event) => {
         const turnId = event.payload.turn_id;
         const backgroundStore = useBackgroundStreamStore.getState();
         const chatStore = useChatStore.getState();
@@ -323,7 +325,7 @@ export function useChat() {
       });
 
       // Listen for container ID updates (Claude code execution / OpenAI code interpreter)
-      const unlistenContainerId = await listen<ContainerIdEvent>('chat-container-id', (event) => {
+      const unlistenContainerId = await appWindow.listen<ContainerIdEvent>('chat-container-id', (event) => {
         const { container_id } = event.payload;
         // Route to correct provider based on current model
         const currentModel = useSettingsStore.getState().frontierLLM.model;
@@ -335,11 +337,19 @@ export function useChat() {
         }
       });
 
+      // Listen for the signed thinking blocks of an Anthropic response (sent just before chat-stream-done),
+      // kept on the message so they can be replayed
+      const unlistenThinkingBlocks = await appWindow.listen<ThinkingBlocksEvent>('chat-thinking-blocks', (event) => {
+        const { turn_id, blocks } = event.payload;
+        useBackgroundStreamStore.getState().setChatThinkingBlocks(turn_id, blocks);
+      });
+
       return () => {
         unlistenDelta();
         unlistenDone();
         unlistenCancelled();
         unlistenContainerId();
+        unlistenThinkingBlocks();
       };
     };
 
@@ -361,9 +371,12 @@ export function useChat() {
         return;
       }
 
+      // Incognito is captured per turn so toggling it mid-stream doesn't change what gets saved
+      const incognito = useChatStore.getState().incognito;
+
       // Register with background stream store BEFORE anything else
       // Pass the model now so it's captured for this stream (prevents cross-session model contamination)
-      useBackgroundStreamStore.getState().startChatStream(sessionId, turnId, frontierLLM.model);
+      useBackgroundStreamStore.getState().startChatStream(sessionId, turnId, frontierLLM.model, incognito);
 
       // Store turnId for the assistant message (used by UI to show streaming state)
       setPendingTurnId(turnId);
@@ -373,6 +386,8 @@ export function useChat() {
       const supportedAttachments = attachments.filter((attachment) => {
         if (attachment.type === 'image') return true;
         if (attachment.mimeType === 'application/pdf') return true;
+        // Office documents are converted to text by the backend
+        if (isOfficeDocument(attachment)) return true;
         // Try to decode as text
         try {
           const textContent = atob(attachment.data);
@@ -398,6 +413,7 @@ export function useChat() {
         turnId,
         // Store container hint with the message for cache stability on future turns
         containerHint: containerContext || undefined,
+        incognito: incognito || undefined,
       };
 
       addMessage(userMessage);
@@ -406,8 +422,11 @@ export function useChat() {
       setStreaming(true);
 
       // Save session immediately so it appears in sidebar (important for first message)
-      // This ensures user can switch back to this session while streaming
-      useSessionStore.getState().saveCurrentSession();
+      // This ensures user can switch back to this session while streaming.
+      // Incognito turns are never saved.
+      if (!incognito) {
+        useSessionStore.getState().saveCurrentSession();
+      }
 
       // If there were unsupported files, show error and don't call API
       if (unsupportedFiles.length > 0) {
@@ -433,7 +452,7 @@ export function useChat() {
         const isGemini = frontierLLM.model.startsWith('gemini');
 
         const apiMessages = allMessages.map((m, index) => {
-          const formattedContent = formatMessageContent(m).content;
+          const { content: formattedContent, thinkingBlocks } = formatMessageContent(m);
           const isLastMessage = index === allMessages.length - 1;
 
           // Determine which container hint to use:
@@ -481,7 +500,7 @@ export function useChat() {
             }
           }
 
-          return { role: m.role, content: formattedContent };
+          return { role: m.role, content: formattedContent, thinking_blocks: thinkingBlocks };
         });
 
         // Build system prompt: shared Part A, then user custom instructions
@@ -495,7 +514,8 @@ export function useChat() {
 
         await invoke('send_chat_message', {
           model: frontierLLM.model,
-          messages: apiMessages,
+          // Message ids let the backend keep pinned messages when trimming context
+          messages: apiMessages.map((m, index) => ({ ...m, id: allMessages[index].id || undefined })),
           systemPrompt,
           webSearchEnabled: frontierLLM.webSearchEnabled,
           codeExecutionEnabled: true, // Enable code execution for file generation
@@ -503,6 +523,7 @@ export function useChat() {
           turnId, // Pass turnId to backend so events can be routed correctly
           anthropicContainerId: useChatStore.getState().anthropicContainerId, // Persist container across turns (Claude)
           openaiContainerId: useChatStore.getState().openaiContainerId, // Persist container across turns (OpenAI)
+          incognito, // Backend writes nothing for this turn (logs, usage, session metadata)
           ...buildProviderThinkingParams(frontierLLM),
         });
       } catch (error) {
@@ -534,7 +555,8 @@ export function useChat() {
 
   const cancelStream = useCallback(async () => {
     try {
-      await invoke('cancel_chat_stream');
+      // Only stop this session's turn; other streams keep going
+      await invoke('cancel_chat_stream', { turnId: useChatStore.getState().pendingTurnId ?? undefined });
     } catch (error) {
       logError('useChat.cancelStream', error);
     }
@@ -563,11 +585,30 @@ export function useChat() {
 interface FormatResult {
   content: string | ContentBlock[];
   unsupportedFiles: string[]; // Names of files that couldn't be processed
+  thinkingBlocks?: unknown[]; // Replayed ahead of an assistant message's content (Anthropic)
+}
+
+const OFFICE_MIME_TYPES = new Set([
+  'application/vnd.openxmlformats-officedocument.spreadsheetml.sheet',
+  'application/vnd.ms-excel',
+  'application/vnd.ms-excel.sheet.macroEnabled.12',
+  'application/vnd.oasis.opendocument.spreadsheet',
+  'application/vnd.openxmlformats-officedocument.wordprocessingml.document',
+]);
+const OFFICE_EXTENSIONS = new Set(['xlsx', 'xlsm', 'xls', 'ods', 'docx']);
+
+// Spreadsheets and Word documents (matches the backend's office_format,
+// including the extension fallback for pickers that report octet-stream)
+function isOfficeDocument(attachment: Attachment): boolean {
+  if (OFFICE_MIME_TYPES.has(attachment.mimeType)) return true;
+  const dot = attachment.name.lastIndexOf('.');
+  return dot >= 0 && OFFICE_EXTENSIONS.has(attachment.name.slice(dot + 1).toLowerCase());
 }
 
 function formatMessageContent(message: Message): FormatResult {
+  const thinkingBlocks = message.role === 'assistant' ? message.thinkingBlocks : undefined;
   if (!message.attachments?.length) {
-    return { content: message.content, unsupportedFiles: [] };
+    return { content: message.content, unsupportedFiles: [], thinkingBlocks };
   }
 
   // Multipart message with attachments
@@ -580,6 +621,8 @@ function formatMessageContent(message: Message): FormatResult {
       // Images: send as base64 image blocks
       parts.push({
         type: 'image',
+        filename: attachment.name, // Names the placeholder when elided
+        attachmentId: attachment.id,
         source: {
           type: 'base64',
           media_type: attachment.mimeType,
@@ -591,12 +634,38 @@ function formatMessageContent(message: Message): FormatResult {
       parts.push({
         type: 'document',
         filename: attachment.name, // OpenAI requires filename for PDFs
+        attachmentId: attachment.id,
         source: {
           type: 'base64',
           media_type: 'application/pdf',
           data: attachment.data,
         },
       });
+    } else if (isOfficeDocument(attachment)) {
+      // Office documents: send as document blocks, converted to text by the backend
+      parts.push({
+        type: 'document',
+        filename: attachment.name,
+        attachmentId: attachment.id,
+        source: {
+          type: 'base64',
+          media_type: attachment.mimeType,
+          data: attachment.data,
+        },
+      });
+    } else if (attachment.mimeType === 'text/plain') {
+      // Plain text (long pastes, .txt files): send as document blocks, which
+      // the backend adapts per provider
+      parts.push({
+        type: 'document',
+        filename: attachment.name,
+        attachmentId: attachment.id,
+        source: {
+          type: 'base64',
+          media_type: 'text/plain',
+          data: attachment.data,
+        },
+      });
     } else {
       // All other files: try to decode as text
       try {
@@ -624,5 +693,5 @@ function formatMessageContent(message: Message): FormatResult {
     text: message.content,
   });
 
-  return { content: parts, unsupportedFiles };
+  return { content: parts, unsupportedFiles, thinkingBlocks };
 }
@@ -1,6 +1,7 @@
 import { useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { UnlistenFn } from '@tauri-apps/api/event';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 import { useChatStore } from '../stores/chatStore';
 import { useDiscoveryStore } from '../stores/discoveryStore';
 import { useSettingsStore } from '../stores/settingsStore';
@@ -95,6 +96,7 @@ export function useDiscovery() {
 
     try {
       // Format conversation for analysis with clear message numbering
+      // (the fallback when the backend can't build context from the messages)
       const conversationText = messages
         .map((m, i) => `MESSAGE #${i + 1} (${m.role.toUpperCase()}):\n${m.content}`)
         .join('\n\n');
@@ -103,8 +105,9 @@ export function useDiscovery() {
       const discoveryMode = useSettingsStore.getState().discoveryMode;
       const modeConfig = getDiscoveryMode(discoveryMode);
 
-      // Set up event listeners BEFORE invoking
-      const unlistenItem = await listen<DiscoveryItemEvent>(
+      // Set up event listeners BEFORE invoking (window-scoped, so routed events reach only this window)
+      const appWindow = getCurrentWebviewWindow();
+      const unlistenItem = await appWindow.listen<DiscoveryItemEvent>(
         'discovery-item',
         (event) => {
           if (event.payload.turnId !== turnId) return;
@@ -134,7 +137,7 @@ export function useDiscovery() {
         }
       );
 
-      const unlistenDone = await listen<DiscoveryDoneEvent>(
+      const unlistenDone = await appWindow.listen<DiscoveryDoneEvent>(
         'discovery-done',
         (event) => {
           if (event.payload.turnId === turnId) {
@@ -161,7 +164,7 @@ export function useDiscovery() {
         }
       );
 
-      const unlistenError = await listen<DiscoveryErrorEvent>(
+      const unlistenError = await appWindow.listen<DiscoveryErrorEvent>(
         'discovery-error',
         (event) => {
           if (event.payload.turnId === turnId) {
@@ -193,7 +196,12 @@ export function useDiscovery() {
       // Call the discovery endpoint - returns when streaming starts, not when done
       await invoke('discover_resources', {
         turnId,
+        // Routes the run's events to the windows showing this session
+        sessionId,
         model: evaluatorLLM.model,
+        // The backend builds the conversation context from these messages,
+        // which include the just-finalized reply even before it is saved
+        messages,
         conversation: conversationText,
         systemPrompt: modeConfig.systemPrompt,
         maxResults: MAX_DISCOVERIES_PER_SEARCH,
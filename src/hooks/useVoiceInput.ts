@@ -1,21 +1,39 @@
-import { useState, useCallback } from 'react';
+import { useState, useCallback, useEffect, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { logError } from '../lib/logger';
-import type { VoiceModel } from '../lib/types';
+import { listen } from '@tauri-apps/api/event';
+import { errorMessage, logError } from '../lib/logger';
+import type { VoiceIntent, VoiceIntentEvent, VoiceModel } from '../lib/types';
 
 export type VoiceInputState = 'idle' | 'recording' | 'transcribing' | 'error';
 
+export interface VoiceTranscript {
+  text: string;
+  // Command spoken at the end of the dictation, already stripped from `text`
+  intent: VoiceIntent | null;
+}
+
 interface UseVoiceInputReturn {
   state: VoiceInputState;
   error: string | null;
   startRecording: () => Promise<void>;
-  stopRecording: (voiceModel: VoiceModel) => Promise<string | null>;
+  stopRecording: (voiceModel: VoiceModel) => Promise<VoiceTranscript | null>;
   cancelRecording: () => Promise<void>;
 }
 
 export function useVoiceInput(): UseVoiceInputReturn {
   const [state, setState] = useState<VoiceInputState>('idle');
   const [error, setError] = useState<string | null>(null);
+  // Last voice-intent event; matched to the transcript the command returns
+  const pendingIntent = useRef<VoiceIntentEvent | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<VoiceIntentEvent>('voice-intent', (event) => {
+      pendingIntent.current = event.payload;
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
 
   const startRecording = useCallback(async () => {
     try {
@@ -31,14 +49,16 @@ export function useVoiceInput(): UseVoiceInputReturn {
     }
   }, []);
 
-  const stopRecording = useCallback(async (voiceModel: VoiceModel): Promise<string | null> => {
+  const stopRecording = useCallback(async (voiceModel: VoiceModel): Promise<VoiceTranscript | null> => {
     try {
       setState('transcribing');
+      pendingIntent.current = null;
 
       let transcript: string;
 
-      if (voiceModel === 'openai') {
-        // OpenAI Whisper: stop recording and transcribe in one call
+      if (voiceModel === 'openai' || voiceModel === 'local') {
+        // OpenAI Whisper or local whisper.cpp (per the transcription settings):
+        // stop recording and transcribe in one call
         transcript = await invoke<string>('stop_audio_recording');
       } else if (voiceModel === 'gemini') {
         // Gemini: get raw audio, then transcribe separately
@@ -48,11 +68,13 @@ export function useVoiceInput(): UseVoiceInputReturn {
         throw new Error('No voice model available');
       }
 
+      const intent = pendingIntent.current?.text === transcript ? pendingIntent.current.intent : null;
+      pendingIntent.current = null;
       setState('idle');
-      return transcript;
+      return { text: transcript, intent };
     } catch (err) {
       logError('useVoiceInput.stopRecording', err);
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
       setState('error');
       return null;
     }
@@ -107,6 +107,7 @@ export async function exportToJson({
     messages: messages.map(serializeMessage),
     discoveryItems: discoveryItems.map(serializeDiscoveryItem),
     settings: buildSessionSettings(settingsStore),
+    appearance: sessionMeta?.appearance,
   };
 
   const exportData: ChatExportData = {
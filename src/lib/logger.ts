@@ -1,14 +1,29 @@
 import { invoke } from '@tauri-apps/api/core';
+import type { SidestreamError } from './types';
+
+function isSidestreamError(error: unknown): error is SidestreamError {
+  return typeof error === 'object' && error !== null && 'kind' in error && 'message' in error;
+}
+
+/**
+ * The message of a thrown Error, a structured backend error or a plain string.
+ */
+export function errorMessage(error: unknown): string {
+  if (error instanceof Error) {
+    return error.message;
+  }
+  return isSidestreamError(error) ? error.message : String(error);
+}
 
 /**
  * Log errors to the Rust backend (visible in terminal where app runs).
  * In Tauri, browser console is not accessible, so this routes errors to stderr.
  */
 export function logError(context: string, error: unknown): void {
-  const errorMessage = error instanceof Error ? error.message : String(error);
+  const message = isSidestreamError(error) ? `${error.kind}: ${error.message}` : errorMessage(error);
 
   // Fire and forget - don't await or handle errors from logging itself
-  invoke('log_frontend_error', { context, error: errorMessage }).catch(() => {
+  invoke('log_frontend_error', { context, error: message }).catch(() => {
     // Silently fail if logging fails - nothing we can do
   });
 }
@@ -28,8 +43,40 @@ export function logDebug(context: string, message: string): void {
  * Analyzes the error string to provide specific guidance.
  */
 export function getUserFriendlyErrorMessage(error: unknown): string {
+  // Structured provider errors say what went wrong; other kinds fall through
+  // to the string checks below
+  if (isSidestreamError(error)) {
+    switch (error.kind) {
+      case 'auth_error':
+        return 'API key error. Please check your API key in Settings.';
+      case 'rate_limited':
+        return error.retryAfter
+          ? `Rate limit reached. Please wait ${error.retryAfter} seconds and try again.`
+          : 'Rate limit reached. Please wait a moment and try again.';
+      case 'context_too_long':
+        return 'The conversation is too long. Please start a new chat or remove some messages.';
+      case 'network_error':
+        return 'Network error. Please check your internet connection and try again.';
+      case 'provider_error':
+        return getUserFriendlyErrorMessage(
+          error.code ? `API error (${error.code}): ${error.message}` : error.message
+        );
+      case 'session_busy':
+        return 'A response is still streaming in this chat. Wait for it to finish or stop it first.';
+      case 'session_locked':
+        return 'This chat is locked (read-only). Unlock it to continue the conversation.';
+      case 'invalid_request':
+        return error.message;
+    }
+  }
+
   const errorStr = String(error).toLowerCase();
 
+  // Stopped by a warning content filter (see content_filters)
+  if (errorStr.startsWith('content_filter_warning:')) {
+    return String(error).slice('content_filter_warning:'.length).trim();
+  }
+
   // API key issues
   if (errorStr.includes('api key') || errorStr.includes('authentication') || errorStr.includes('unauthorized') || errorStr.includes('401')) {
     return 'API key error. Please check your API key in Settings.';
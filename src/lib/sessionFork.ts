@@ -1,6 +1,6 @@
 import { invoke } from '@tauri-apps/api/core';
 import type { ChatSession, ChatSessionMeta, Message, DiscoveryItem, Attachment, DiscoveryModeId, LLMConfig } from './types';
-import { buildSessionSettings, serializeMessage, serializeDiscoveryItem } from './sessionHelpers';
+import { buildSessionSettings, persistableMessages, serializeMessage, serializeDiscoveryItem } from './sessionHelpers';
 import { remapMessageIds, remapDiscoveryItems } from './messageHelpers';
 import { logError } from './logger';
 
@@ -122,7 +122,7 @@ export async function forkFromMessage(
     title: forkTitle,
     createdAt: now,
     updatedAt: now,
-    messages: newMessages.map(serializeMessage),
+    messages: persistableMessages(newMessages).map(serializeMessage),
     discoveryItems: newDiscoveryItems.map(serializeDiscoveryItem),
     settings: buildSessionSettings(settingsStore),
   };
@@ -196,7 +196,7 @@ export async function forkCurrentSession(
     title: forkTitle,
     createdAt: now,
     updatedAt: now,
-    messages: newMessages.map(serializeMessage),
+    messages: persistableMessages(newMessages).map(serializeMessage),
     discoveryItems: newDiscoveryItems.map(serializeDiscoveryItem),
     settings: buildSessionSettings(settingsStore),
   };
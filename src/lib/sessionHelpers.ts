@@ -34,6 +34,17 @@ export function buildSessionSettings(settingsStore: SettingsStoreState, chatStor
   };
 }
 
+/**
+ * Messages that may be written to disk: drops incognito turns (the flagged
+ * user message and every message sharing its turnId).
+ */
+export function persistableMessages(messages: Message[]): Message[] {
+  const incognitoTurns = new Set(
+    messages.filter((m) => m.incognito && m.turnId).map((m) => m.turnId)
+  );
+  return messages.filter((m) => !m.incognito && !(m.turnId && incognitoTurns.has(m.turnId)));
+}
+
 /**
  * Generate a chat title from the first message content.
  */
@@ -11,6 +11,7 @@ export interface ApiKeysConfig {
   anthropic: boolean;
   openai: boolean;
   google: boolean;
+  openrouter: boolean;
 }
 
 // Attachment types for files/images
@@ -23,6 +24,13 @@ export interface Attachment {
   preview?: string; // For images
 }
 
+// A long paste turned into an attachment (prepare_pasted_text); `attachment` is absent when it stays inline
+export interface PastedTextResult {
+  tokenCount: number;
+  thresholdTokens: number;
+  attachment: (Omit<Attachment, 'id' | 'preview'> & { tokenCount: number }) | null;
+}
+
 // Chat message types
 export interface Message {
   id: string;
@@ -40,6 +48,7 @@ export interface Message {
   turnId?: string; // Associates user+assistant messages within a turn
   thinkingContent?: string; // Persisted thinking/reasoning content for collapsed display
   thinkingDurationMs?: number; // How long the model spent thinking
+  thinkingBlocks?: unknown[]; // Signed Anthropic thinking blocks, replayed ahead of the content (chat-thinking-blocks)
   // Code execution fields (for Claude code_execution, OpenAI code_interpreter)
   executionCode?: string; // The code that was executed
   executionOutput?: string; // Combined stdout/stderr from execution
@@ -49,6 +58,7 @@ export interface Message {
   executionTextPosition?: number; // Character position in content where execution occurred
   generatedFiles?: GeneratedFile[]; // Files created by code execution
   containerHint?: string; // Container context hint that was appended when this message was sent (for cache stability)
+  incognito?: boolean; // Turn was sent incognito: shown in the chat but never saved with the session
 }
 
 // Discovery item types
@@ -69,6 +79,13 @@ export interface DiscoveryItem {
   modeId?: import('./discoveryModes').DiscoveryModeId; // Which mode generated this chip (optional for backward compat)
 }
 
+// Anthropic discovery sent as a Message Batch (get_discovery_batch_mode / set_discovery_batch_mode).
+// discover_resources returns once submitted; discovery-item / discovery-done follow when the batch ends.
+export interface DiscoveryBatchSubmittedEvent {
+  turnId: string;
+  batchId: string;
+}
+
 // Adaptive thinking levels for Anthropic models that support effort
 // (Opus 4.8, Opus 4.6, Sonnet 4.6).
 // - off: no thinking
@@ -98,12 +115,45 @@ export type GeminiThinkingLevel = 'minimal' | 'low' | 'medium' | 'high';
 // Theme mode for dark/light mode support
 export type ThemeMode = 'light' | 'dark' | 'system';
 
-// Voice input model - which transcription service to use (auto-determined from API keys)
-export type VoiceModel = 'none' | 'openai' | 'gemini';
+// Voice input model - which transcription service to use (auto-determined from API keys
+// and the transcription backend; 'local' is whisper.cpp on the device)
+export type VoiceModel = 'none' | 'openai' | 'gemini' | 'local';
+
+// Which engine transcribes native recordings (get_transcription_settings / set_transcription_settings).
+// 'local' runs whisper.cpp on the device and needs the chosen model downloaded.
+export type TranscriptionBackend = 'openai' | 'local';
+
+export interface TranscriptionSettings {
+  backend: TranscriptionBackend;
+  model: string; // Whisper model name, e.g. 'base.en'
+}
+
+// Whisper models for local transcription (list_whisper_models / download_whisper_model / delete_whisper_model)
+export interface WhisperModelInfo {
+  name: string;
+  sizeMb: number;
+  downloaded: boolean;
+}
+
+// Event payload while a Whisper model downloads (whisper-model-download-progress)
+export interface WhisperModelDownloadProgressEvent {
+  name: string;
+  downloadedBytes: number;
+  totalBytes: number | null;
+}
 
 // Voice input mode - how voice input behaves (user-configurable)
 export type VoiceMode = 'none' | 'textbox' | 'chat_request';
 
+// Command spoken at the end of a dictation ("send it", "scratch that", "new line", "new paragraph")
+export type VoiceIntent = 'send' | 'scratch_that' | 'new_line' | 'new_paragraph';
+
+// voice-intent event: emitted before the transcription command returns `text` (the dictation without the command)
+export interface VoiceIntentEvent {
+  intent: VoiceIntent;
+  text: string;
+}
+
 // LLM configuration
 export interface LLMConfig {
   model: string;
@@ -118,7 +168,8 @@ export interface ContentBlock {
   type: 'text' | 'image' | 'document' | 'file';
   text?: string;
   source?: ImageSource | DocumentSource | FileSource;
-  filename?: string; // For document/file blocks
+  filename?: string; // Attachment name; providers that don't take one for images never see it
+  attachmentId?: string; // Attachment.id, for keeping attachments live (set_attachment_live); not sent to providers
 }
 
 export interface ImageSource {
@@ -166,7 +217,7 @@ export interface StreamDelta {
 
 // Delta for code execution events (Claude code_execution, OpenAI code_interpreter)
 export interface ExecutionDelta {
-  tool_name: string; // "bash_code_execution", "text_editor_code_execution", "code_interpreter"
+  tool_name: string; // "bash_code_execution", "text_editor_code_execution", "code_interpreter", "image_generation"
   stdout?: string;
   stderr?: string;
   status: ExecutionStatus;
@@ -226,12 +277,220 @@ export interface StreamEvent {
   turn_id: string;
 }
 
+// Identifiers a provider returned for a response (model snapshot, request id)
+export interface ResponseMetadata {
+  provider: string;
+  model?: string;
+  response_id?: string;
+  request_id?: string;
+}
+
+// Event payload for stream completion
+export interface StreamDoneEvent extends StreamEvent {
+  metadata?: ResponseMetadata;
+}
+
+// A frontend-implemented tool offered to the model (register_user_tools)
+export interface UserTool {
+  name: string;
+  description: string;
+  input_schema: Record<string, unknown>;
+}
+
+// Event payload asking the frontend to run a user tool; answer with submit_tool_result
+export interface ToolCallRequestEvent {
+  turn_id: string;
+  tool_use_id: string;
+  tool_name: string;
+  input: Record<string, unknown>;
+}
+
 // Event payload for container ID updates (Claude code execution)
 export interface ContainerIdEvent {
   turn_id: string;
   container_id: string;
 }
 
+// Complete thinking blocks of an Anthropic response, sent back as ChatMessage thinking_blocks
+export interface ThinkingBlocksEvent {
+  turn_id: string;
+  blocks: unknown[];
+}
+
+// Event payload while a stream is quiet during searches, code execution or thinking (chat-stream-heartbeat)
+export interface StreamHeartbeatEvent {
+  turn_id: string;
+  phase: 'waiting' | 'thinking' | 'searching' | 'executing' | 'writing';
+  elapsed_ms: number; // Since the turn started
+}
+
+// Event payload when a regenerated turn reuses recent web searches instead of searching again (chat-cached-sources)
+export interface CachedSourcesEvent {
+  turn_id: string;
+  queries: string[];
+  result_count: number; // Search results across the queries
+}
+
+// Event payload listing how a turn's request was changed so the provider accepts it (request-adjusted)
+export interface RequestAdjustedEvent {
+  turn_id: string;
+  adjustments: {
+    kind: 'effort_changed' | 'context_trimmed' | 'feature_changed' | 'attachments_elided';
+    description: string; // What changed, for the user
+  }[];
+}
+
+// Event payload with a request's input token usage, including prompt cache writes/reads (chat-usage); repeated with metrics when the turn ends
+export interface ChatUsageEvent {
+  turn_id: string;
+  provider: string;
+  input_tokens: number;
+  cache_creation_input_tokens: number;
+  cache_read_input_tokens: number;
+  metrics?: TurnMetrics; // On the event repeated when the turn ends
+}
+
+// Latency and throughput of a completed turn (ChatUsageEvent.metrics)
+export interface TurnMetrics {
+  ttft_ms: number | null; // Time to first token
+  duration_ms: number;
+  output_tokens: number;
+  output_tokens_estimated: boolean; // The provider didn't report a count
+  tokens_per_second: number | null;
+}
+
+// Event payload once a session's prompt cache has been warmed (prompt-cache-warmed)
+export interface PromptCacheWarmedEvent {
+  session_id: string;
+  provider: string;
+  model: string;
+  input_tokens: number;
+  cache_write_tokens: number; // Anthropic only
+  cache_read_tokens: number;
+}
+
+// Event payload confirming an incognito turn wrote nothing (chat-incognito-complete)
+export interface IncognitoTurnEvent {
+  turn_id: string;
+  audit_logged: boolean; // The enabled audit log still recorded the requests
+}
+
+// Read-aloud queue events (read_aloud_push / read_aloud_finish, paused and skipped with
+// pause_read_aloud / resume_read_aloud / skip_read_aloud_sentence / stop_read_aloud)
+export interface ReadAloudSentenceEvent {
+  turnId: string;
+  index: number; // Position of the sentence in the turn
+  text: string;
+}
+
+export interface ReadAloudDoneEvent {
+  turnId: string;
+}
+
+export interface ReadAloudErrorEvent {
+  turnId: string;
+  error: string;
+}
+
+// Result of import_chat_sessions (Sidestream, ChatGPT or Claude.ai exports)
+export interface ImportSummary {
+  sessionIds: string[];
+  skipped: number; // Conversations that were already imported
+  invalid: number; // Conversations that couldn't be read
+}
+
+// Reusable system prompt with {{variable}} placeholders (list_prompt_templates / save_prompt_template)
+export interface PromptTemplate {
+  id: string; // Empty when creating
+  name: string;
+  content: string;
+  defaults: Record<string, string>; // Used when neither the call nor the session sets a value
+  variables: string[]; // Filled in by the backend
+}
+
+// Outbound proxy (get_proxy_settings / set_proxy_settings / test_proxy)
+export interface ProxySettings {
+  enabled: boolean;
+  url: string; // http://, https://, socks5:// or socks5h:// with host and port
+  username: string | null;
+  password?: string | null; // Never returned; when saving, a new password ('' clears it, null keeps the stored one)
+  hasPassword: boolean; // A password is in the secure store
+  bypass: string[]; // Hosts, .domain suffixes, IPs or CIDR ranges that connect directly
+}
+
+// Anthropic beta feature and its toggle (get_anthropic_betas / set_anthropic_beta_enabled)
+export interface AnthropicBeta {
+  id: string; // e.g. 'code_execution', 'files_api'
+  headerValue: string; // anthropic-beta header value, e.g. 'files-api-2025-04-14'
+  enabled: boolean;
+}
+
+// Replacing old attachments with placeholders in requests (get_attachment_elision_settings / set_attachment_elision_settings).
+// set_attachment_live keeps one attachment (by Attachment.id) sent in full.
+export interface AttachmentElisionSettings {
+  enabled: boolean;
+  afterTurns: number; // Attachments from more turns back than this are replaced
+}
+
+// System-wide push-to-talk hotkey (get_push_to_talk_settings / set_push_to_talk_settings; desktop only).
+// Emits push-to-talk-pressed once recording starts, push-to-talk-released to stop and send, push-to-talk-failed with the reason.
+export interface PushToTalkSettings {
+  enabled: boolean;
+  shortcut: string; // Accelerator, e.g. 'CommandOrControl+Shift+Space'
+}
+
+// Custom provider endpoint (get_provider_endpoints / set_provider_endpoint)
+export interface ProviderEndpoint {
+  baseUrl: string | null; // API root, e.g. 'https://acme.openai.azure.com/openai/v1'
+  authHeader: string | null; // Header carrying the key as-is, e.g. 'api-key' for Azure
+  headers: Record<string, string>;
+  query: Record<string, string>; // e.g. { 'api-version': 'preview' }
+}
+
+// Workspace (list_workspaces / save_workspace / get_current_workspace / set_current_workspace)
+export interface Workspace {
+  id: string;
+  name: string;
+  documentFolders: string[];
+  defaultPersonaId: string | null;
+  defaultModel: string | null; // Model for new chats while the workspace is current
+  apiKeyProfile: string | null;
+}
+
+// File in a workspace document folder (list_workspace_documents; read_workspace_document returns its text)
+export interface WorkspaceDocument {
+  name: string;
+  path: string;
+  size: number;
+}
+
+// Pre-send content filter (get_content_filters / save_content_filters)
+export interface ContentFilter {
+  id: string; // 'api_keys' and 'credit_cards' are built in
+  name: string;
+  pattern: string | null; // Regex of a custom filter
+  enabled: boolean;
+  action: 'warn' | 'mask';
+}
+
+// Event payload listing what the content filters found (content-filter-findings)
+export interface ContentFilterEvent {
+  turn_id: string;
+  findings: { filterId: string; name: string; action: 'warn' | 'mask'; count: number }[];
+  blocked: boolean;
+}
+
+// Structured error from provider commands (e.g. send_chat_message), tagged by kind
+export type SidestreamError =
+  | { kind: 'auth_error'; message: string }
+  | { kind: 'rate_limited'; retryAfter: number | null; message: string }
+  | { kind: 'context_too_long'; message: string }
+  | { kind: 'network_error'; message: string }
+  | { kind: 'provider_error'; code: number | null; message: string }
+  | { kind: 'session_busy'; turnId: string; message: string } // Another turn is streaming in the session
+  | { kind: 'session_locked'; sessionId: string; message: string } // The session is read-only (see lock_session)
+  | { kind: 'invalid_request'; message: string }; // Broke a provider limit or rule; not sent. The message says what to change
+
 // Discovery mode type - re-exported from discoveryModes for convenience
 export type { DiscoveryModeId } from './discoveryModes';
 
@@ -257,6 +516,13 @@ export interface ChatSessionSettings {
   openaiContainerId?: string;
 }
 
+// Visual markers for the session list (set via set_session_appearance)
+export interface SessionAppearance {
+  color?: string; // Named color or "#rrggbb"
+  emoji?: string;
+  icon?: string;
+}
+
 export interface ChatSession {
   id: string;
   title: string;
@@ -265,6 +531,7 @@ export interface ChatSession {
   messages: Message[];
   discoveryItems: DiscoveryItem[];
   settings: ChatSessionSettings;
+  appearance?: SessionAppearance;
 }
 
 export interface ChatSessionMeta {
@@ -273,6 +540,27 @@ export interface ChatSessionMeta {
   updatedAt: string;
   messageCount: number;
   discoveryMode?: import('./discoveryModes').DiscoveryModeId;
+  appearance?: SessionAppearance;
+}
+
+// Linked sessions from get_session_branches. fork_chat_session branches after a turn;
+// regenerate_chat_turn branches just before a turn with another model; its user message is then sent there.
+export interface BranchSummary {
+  id: string;
+  title: string;
+  turnIndex: number | null; // 0-based turn the branch was taken at
+  model: string | null; // Set for regenerated branches
+}
+
+export interface SessionBranches {
+  parent: BranchSummary | null; // Session this one was branched from, if it still exists
+  branches: BranchSummary[];
+}
+
+// Input tokens a conversation will take (count_tokens), for a context-usage meter
+export interface TokenCount {
+  tokens: number;
+  method: 'tokenizer' | 'provider' | 'estimate'; // OpenAI tokenizer, Anthropic count_tokens, or ~4 chars per token
 }
 
 // Export format for saved chats
@@ -13,11 +13,13 @@ interface BackgroundChatStream {
   sessionId: string;
   turnId: string;
   model: string; // Model captured at stream start to avoid cross-session contamination
+  incognito: boolean; // Turn was sent incognito: its reply is never saved
   streamingContent: string;
   streamingCitations: Citation[];
   streamingInlineCitations: InlineCitation[];
   streamingThinking: string;
   thinkingStartTime: number | null;
+  thinkingBlocks: unknown[] | null; // Signed thinking blocks, sent once the response ends
   startedAt: Date;
   // Execution tracking
   streamingExecutionCode: string;
@@ -44,11 +46,12 @@ interface BackgroundStreamState {
   discoveryStreams: Map<string, BackgroundDiscoveryStream>;
 
   // Actions for chat streams
-  startChatStream: (sessionId: string, turnId: string, model: string) => void;
+  startChatStream: (sessionId: string, turnId: string, model: string, incognito?: boolean) => void;
   appendChatDelta: (turnId: string, text: string) => void;
   addChatCitations: (turnId: string, citations: Citation[]) => void;
   addChatInlineCitations: (turnId: string, citations: InlineCitation[]) => void;
   appendChatThinking: (turnId: string, text: string) => void;
+  setChatThinkingBlocks: (turnId: string, blocks: unknown[]) => void;
   setExecutionStarted: (turnId: string, code: string) => void;
   appendExecutionOutput: (turnId: string, output: string) => void;
   setExecutionCompleted: (turnId: string, files?: GeneratedFile[]) => void;
@@ -73,18 +76,20 @@ export const useBackgroundStreamStore = create<BackgroundStreamState>((set, get)
   chatStreams: new Map(),
   discoveryStreams: new Map(),
 
-  startChatStream: (sessionId, turnId, model) => {
+  startChatStream: (sessionId, turnId, model, incognito = false) => {
     set((state) => {
       const newStreams = new Map(state.chatStreams);
       newStreams.set(turnId, {
         sessionId,
         turnId,
         model,
+        incognito,
         streamingContent: '',
         streamingCitations: [],
         streamingInlineCitations: [],
         streamingThinking: '',
         thinkingStartTime: null,
+        thinkingBlocks: null,
         startedAt: new Date(),
         // Execution fields
         streamingExecutionCode: '',
@@ -157,6 +162,17 @@ export const useBackgroundStreamStore = create<BackgroundStreamState>((set, get)
     });
   },
 
+  setChatThinkingBlocks: (turnId, blocks) => {
+    set((state) => {
+      const stream = state.chatStreams.get(turnId);
+      if (!stream) return state;
+
+      const newStreams = new Map(state.chatStreams);
+      newStreams.set(turnId, { ...stream, thinkingBlocks: blocks });
+      return { chatStreams: newStreams };
+    });
+  },
+
   setExecutionStarted: (turnId, code) => {
     set((state) => {
       const stream = state.chatStreams.get(turnId);
@@ -267,6 +283,7 @@ export const useBackgroundStreamStore = create<BackgroundStreamState>((set, get)
       turnId: stream.turnId,
       thinkingContent: stream.streamingThinking || undefined,
       thinkingDurationMs,
+      thinkingBlocks: stream.thinkingBlocks ?? undefined,
       // Execution fields
       executionCode: stream.streamingExecutionCode || undefined,
       executionOutput: stream.streamingExecutionOutput || undefined,
@@ -295,11 +312,20 @@ export const useBackgroundStreamStore = create<BackgroundStreamState>((set, get)
       });
 
       // Use finalizeStreaming which handles the UI transition
-      chatStore.finalizeStreaming();
+      chatStore.finalizeStreaming(stream.thinkingBlocks ?? undefined);
 
       // Save session immediately so it's persisted before discovery completes
       // This ensures if user switches away and back during discovery, they see the response
-      useSessionStore.getState().saveCurrentSession();
+      if (!stream.incognito) {
+        useSessionStore.getState().saveCurrentSession();
+      }
+    } else if (stream.incognito) {
+      // User switched away from an incognito turn - its reply is dropped, never saved
+      set((state) => {
+        const newStreams = new Map(state.chatStreams);
+        newStreams.delete(turnId);
+        return { chatStreams: newStreams };
+      });
     } else {
       // User switched away - save directly to the session
       try {
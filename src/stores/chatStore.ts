@@ -27,6 +27,8 @@ interface ChatState {
   anthropicContainerId: string | null;
   // OpenAI code interpreter container ID (persists file access across requests)
   openaiContainerId: string | null;
+  // Send new turns incognito (nothing about them is written to disk)
+  incognito: boolean;
 
   // Focus management
   _focusChatInput: (() => void) | null;
@@ -42,7 +44,7 @@ interface ChatState {
   appendExecutionOutput: (output: string) => void;
   setExecutionCompleted: (files?: GeneratedFile[]) => void;
   setExecutionFailed: (error: string) => void;
-  finalizeStreaming: () => void;
+  finalizeStreaming: (thinkingBlocks?: unknown[]) => void;
   setInput: (value: string) => void;
   appendToInput: (text: string) => void;
   clearInput: () => void;
@@ -71,6 +73,7 @@ interface ChatState {
   setPendingTurnId: (turnId: string | null) => void;
   setAnthropicContainerId: (containerId: string) => void;
   setOpenaiContainerId: (containerId: string) => void;
+  setIncognito: (incognito: boolean) => void;
   clearStreamingContent: () => void;
   registerChatInputFocus: (focusFn: () => void) => void;
   focusChatInput: () => void;
@@ -99,6 +102,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
   pendingTurnId: null,
   anthropicContainerId: null,
   openaiContainerId: null,
+  incognito: false,
   _focusChatInput: null,
 
   addMessage: (message) => {
@@ -170,7 +174,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
       executionError: error,
     }),
 
-  finalizeStreaming: () => {
+  finalizeStreaming: (thinkingBlocks) => {
     const state = useChatStore.getState();
     if (!state.streamingContent) return;
 
@@ -203,6 +207,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
           turnId: state.pendingTurnId ?? undefined,
           thinkingContent: state.streamingThinking || undefined,
           thinkingDurationMs,
+          thinkingBlocks,
           // Code execution fields
           executionCode: state.streamingExecutionCode || undefined,
           executionOutput: state.streamingExecutionOutput || undefined,
@@ -348,6 +353,8 @@ export const useChatStore = create<ChatState>((set, get) => ({
 
   setPendingTurnId: (turnId) => set({ pendingTurnId: turnId }),
 
+  setIncognito: (incognito) => set({ incognito }),
+
   setAnthropicContainerId: (containerId) => {
     set({ anthropicContainerId: containerId });
     // Mark session dirty so the container ID gets persisted
@@ -3,8 +3,10 @@ import { invoke } from '@tauri-apps/api/core';
 import type {
   ChatSession,
   ChatSessionMeta,
+  SessionAppearance,
+  Workspace,
 } from '../lib/types';
-import { buildSessionSettings, generateChatTitle, serializeMessage, serializeDiscoveryItem } from '../lib/sessionHelpers';
+import { buildSessionSettings, generateChatTitle, persistableMessages, serializeMessage, serializeDiscoveryItem } from '../lib/sessionHelpers';
 import { migrateChatSessionSettings } from '../lib/sessionMigration';
 import { filterSessionMetas } from '../lib/sessionSearch';
 import { forkFromMessage as forkFromMessageImpl, forkCurrentSession as forkCurrentSessionImpl, type ForkStores } from '../lib/sessionFork';
@@ -14,10 +16,26 @@ import { useDiscoveryStore } from './discoveryStore';
 import { useSettingsStore } from './settingsStore';
 import { useBackgroundStreamStore } from './backgroundStreamStore';
 
+// Sessions loaded per page of list_chat_sessions
+const SESSION_PAGE_SIZE = 50;
+
+function sessionMeta(session: ChatSession): ChatSessionMeta {
+  return {
+    id: session.id,
+    title: session.title,
+    updatedAt: session.updatedAt,
+    messageCount: session.messages.length,
+    discoveryMode: session.settings?.discoveryMode,
+    appearance: session.appearance,
+  };
+}
+
 interface SessionState {
   activeSessionId: string | null;
-  sessionMetas: ChatSessionMeta[];
+  sessionMetas: ChatSessionMeta[]; // Loaded pages only; see totalSessions
   sessionCache: Map<string, ChatSession>; // Cache full sessions for search
+  totalSessions: number; // Stored sessions, loaded or not
+  isLoadingMoreSessions: boolean;
   draftInputs: Map<string, string>; // Store draft input text per session
   searchQuery: string;
   isSidebarOpen: boolean;
@@ -27,11 +45,14 @@ interface SessionState {
 
   // Actions
   loadSessionList: () => Promise<void>;
+  loadMoreSessions: () => Promise<void>;
+  hasMoreSessions: () => boolean;
   createNewSession: () => string;
   switchToSession: (sessionId: string) => Promise<void>;
   saveCurrentSession: () => Promise<void>;
   deleteSession: (sessionId: string) => Promise<void>;
   renameSession: (sessionId: string, newTitle: string) => Promise<void>;
+  setSessionAppearance: (sessionId: string, appearance: SessionAppearance) => Promise<void>;
   forkFromMessage: (messageId: string) => Promise<void>;
   forkCurrentSession: () => Promise<void>;
   toggleSidebar: () => void;
@@ -46,6 +67,8 @@ export const useSessionStore = create<SessionState>((set, get) => ({
   activeSessionId: null,
   sessionMetas: [],
   sessionCache: new Map<string, ChatSession>(),
+  totalSessions: 0,
+  isLoadingMoreSessions: false,
   draftInputs: new Map<string, string>(),
   searchQuery: '',
   isSidebarOpen: (() => {
@@ -59,18 +82,15 @@ export const useSessionStore = create<SessionState>((set, get) => ({
   loadSessionList: async () => {
     set({ isLoadingSessions: true });
     try {
-      const sessions = await invoke<ChatSession[]>('list_chat_sessions');
-
-      const metas: ChatSessionMeta[] = sessions.map((session) => ({
-        id: session.id,
-        title: session.title,
-        updatedAt: session.updatedAt,
-        messageCount: session.messages.length,
-        discoveryMode: session.settings?.discoveryMode,
-      }));
+      // Reload as many sessions as are already shown, so a refresh doesn't shrink the list
+      const limit = Math.max(SESSION_PAGE_SIZE, get().sessionMetas.length);
+      const [sessions, total] = await Promise.all([
+        invoke<ChatSession[]>('list_chat_sessions', { offset: 0, limit }),
+        invoke<number>('count_chat_sessions'),
+      ]);
 
-      // Sort by updatedAt descending (most recent first)
-      metas.sort((a, b) => new Date(b.updatedAt).getTime() - new Date(a.updatedAt).getTime());
+      // Sessions come most recently updated first
+      const metas = sessions.map(sessionMeta);
 
       // Populate session cache for search
       const cache = new Map<string, ChatSession>();
@@ -78,13 +98,45 @@ export const useSessionStore = create<SessionState>((set, get) => ({
         cache.set(session.id, session);
       }
 
-      set({ sessionMetas: metas, sessionCache: cache, isLoadingSessions: false });
+      set({ sessionMetas: metas, sessionCache: cache, totalSessions: total, isLoadingSessions: false });
     } catch (error) {
       logError('sessionStore.loadSessionList', error);
       set({ isLoadingSessions: false });
     }
   },
 
+  loadMoreSessions: async () => {
+    if (get().isLoadingMoreSessions || !get().hasMoreSessions()) return;
+    set({ isLoadingMoreSessions: true });
+    try {
+      const [sessions, total] = await Promise.all([
+        invoke<ChatSession[]>('list_chat_sessions', { offset: get().sessionMetas.length, limit: SESSION_PAGE_SIZE }),
+        invoke<number>('count_chat_sessions'),
+      ]);
+
+      set((state) => {
+        // Sessions saved since the last page shift the offsets; skip ones already shown
+        const known = new Set(state.sessionMetas.map((m) => m.id));
+        const added = sessions.filter((session) => !known.has(session.id));
+        const newCache = new Map(state.sessionCache);
+        for (const session of added) {
+          newCache.set(session.id, session);
+        }
+        return {
+          sessionMetas: [...state.sessionMetas, ...added.map(sessionMeta)],
+          sessionCache: newCache,
+          totalSessions: total,
+          isLoadingMoreSessions: false,
+        };
+      });
+    } catch (error) {
+      logError('sessionStore.loadMoreSessions', error);
+      set({ isLoadingMoreSessions: false });
+    }
+  },
+
+  hasMoreSessions: () => get().sessionMetas.length < get().totalSessions,
+
   createNewSession: () => {
     const currentState = get();
     const chatStore = useChatStore.getState();
@@ -107,6 +159,15 @@ export const useSessionStore = create<SessionState>((set, get) => ({
 
     set({ activeSessionId: newId, isDirty: false });
 
+    // New chats start with the current workspace's default model
+    invoke<Workspace | null>('get_current_workspace')
+      .then((workspace) => {
+        if (workspace?.defaultModel && get().activeSessionId === newId) {
+          useSettingsStore.getState().setFrontierLLM({ model: workspace.defaultModel });
+        }
+      })
+      .catch((err) => logError('sessionStore.createNewSession', err));
+
     return newId;
   },
 
@@ -240,8 +301,11 @@ export const useSessionStore = create<SessionState>((set, get) => ({
     const discoveryStore = useDiscoveryStore.getState();
     const settingsStore = useSettingsStore.getState();
 
+    // Incognito turns are never saved
+    const messages = persistableMessages(chatStore.messages);
+
     // Don't save empty sessions
-    if (chatStore.messages.length === 0) return;
+    if (messages.length === 0) return;
 
     set({ isSaving: true });
 
@@ -257,7 +321,7 @@ export const useSessionStore = create<SessionState>((set, get) => ({
       if (existingMeta) {
         title = existingMeta.title;
       } else {
-        const firstUserMessage = chatStore.messages.find((m) => m.role === 'user');
+        const firstUserMessage = messages.find((m) => m.role === 'user');
         title = firstUserMessage
           ? generateChatTitle(firstUserMessage.content)
           : 'New Chat';
@@ -270,7 +334,7 @@ export const useSessionStore = create<SessionState>((set, get) => ({
         title,
         createdAt: existingMeta ? existingMeta.updatedAt : now, // Keep original creation time
         updatedAt: now,
-        messages: chatStore.messages.map((msg) => ({
+        messages: messages.map((msg) => ({
           ...msg,
           timestamp: msg.timestamp instanceof Date ? msg.timestamp : new Date(msg.timestamp),
         })),
@@ -299,6 +363,7 @@ export const useSessionStore = create<SessionState>((set, get) => ({
         updatedAt: session.updatedAt,
         messageCount: session.messages.length,
         discoveryMode: settingsStore.discoveryMode,
+        appearance: get().sessionMetas.find((m) => m.id === session.id)?.appearance,
       };
 
       set((state) => {
@@ -311,7 +376,11 @@ export const useSessionStore = create<SessionState>((set, get) => ({
         const newCache = new Map(state.sessionCache);
         newCache.set(session.id, session);
 
-        return { sessionMetas: newMetas, sessionCache: newCache, isSaving: false, isDirty: false };
+        // A session not in the loaded pages is new (or sat on a page not loaded yet)
+        const isNew = filteredMetas.length === state.sessionMetas.length;
+        const totalSessions = isNew ? state.totalSessions + 1 : state.totalSessions;
+
+        return { sessionMetas: newMetas, sessionCache: newCache, totalSessions, isSaving: false, isDirty: false };
       });
     } catch (error) {
       logError('sessionStore.saveCurrentSession', error);
@@ -337,6 +406,7 @@ export const useSessionStore = create<SessionState>((set, get) => ({
         return {
           sessionMetas: state.sessionMetas.filter((m) => m.id !== sessionId),
           sessionCache: newCache,
+          totalSessions: Math.max(0, state.totalSessions - 1),
         };
       });
 
@@ -391,6 +461,29 @@ export const useSessionStore = create<SessionState>((set, get) => ({
     }
   },
 
+  setSessionAppearance: async (sessionId: string, appearance: SessionAppearance) => {
+    try {
+      // The backend validates and normalizes the fields (empty ones are cleared)
+      const saved = await invoke<SessionAppearance>('set_session_appearance', { sessionId, appearance });
+
+      set((state) => {
+        const newMetas = state.sessionMetas.map((m) =>
+          m.id === sessionId ? { ...m, appearance: saved } : m
+        );
+
+        const newCache = new Map(state.sessionCache);
+        const cached = newCache.get(sessionId);
+        if (cached) {
+          newCache.set(sessionId, { ...cached, appearance: saved });
+        }
+
+        return { sessionMetas: newMetas, sessionCache: newCache };
+      });
+    } catch (error) {
+      logError('sessionStore.setSessionAppearance', error);
+    }
+  },
+
   forkFromMessage: async (messageId: string) => {
     const currentState = get();
     const stores: ForkStores = {
@@ -423,7 +516,7 @@ export const useSessionStore = create<SessionState>((set, get) => ({
       const newCache = new Map(state.sessionCache);
       newCache.set(session.id, session);
 
-      return { sessionMetas: newMetas, sessionCache: newCache, isDirty: false };
+      return { sessionMetas: newMetas, sessionCache: newCache, totalSessions: state.totalSessions + 1, isDirty: false };
     });
   },
 
@@ -459,7 +552,7 @@ export const useSessionStore = create<SessionState>((set, get) => ({
       const newCache = new Map(state.sessionCache);
       newCache.set(session.id, session);
 
-      return { sessionMetas: newMetas, sessionCache: newCache, isDirty: false };
+      return { sessionMetas: newMetas, sessionCache: newCache, totalSessions: state.totalSessions + 1, isDirty: false };
     });
   },
 
@@ -8,6 +8,7 @@ import type {
   GeminiThinkingLevel,
   Opus46ThinkingLevel,
   ThemeMode,
+  TranscriptionBackend,
   VoiceModel,
   VoiceMode,
 } from '../lib/types';
@@ -222,7 +223,7 @@ function getSavedVoiceMode(): VoiceMode {
 // Load saved voice model from localStorage (will be recomputed when providers are set)
 function getSavedVoiceModel(): VoiceModel {
   const saved = localStorage.getItem('voiceModel');
-  if (saved && ['none', 'openai', 'gemini'].includes(saved)) {
+  if (saved && ['none', 'openai', 'gemini', 'local'].includes(saved)) {
     return saved as VoiceModel;
   }
   return 'none';
@@ -251,9 +252,10 @@ function getSavedAllowChatGPT5Pro(): boolean {
   return false;
 }
 
-// Compute voice model from configured providers
-// Priority: openai (Whisper) > gemini > none
-function computeVoiceModel(providers: ApiKeysConfig): VoiceModel {
+// Compute voice model from the transcription backend and configured providers
+// Priority: local (whisper.cpp, needs no key) > openai (Whisper) > gemini > none
+function computeVoiceModel(providers: ApiKeysConfig, transcriptionBackend: TranscriptionBackend): VoiceModel {
+  if (transcriptionBackend === 'local') return 'local';
   if (providers.openai) return 'openai';
   if (providers.google) return 'gemini';
   return 'none';
@@ -289,7 +291,8 @@ interface SettingsState {
   autoSelectDiscoveryModel: boolean;
   showCitations: boolean;
   theme: ThemeMode;
-  voiceModel: VoiceModel; // Auto-determined from API keys
+  voiceModel: VoiceModel; // Auto-determined from API keys and the transcription backend
+  transcriptionBackend: TranscriptionBackend; // Mirrors the backend's transcription settings
   voiceMode: VoiceMode; // User-configurable
   customSystemPrompt: string; // User's personalized system prompt
   allowChatGPTExtraHighThinking: boolean; // Allow extra-high thinking for OpenAI models
@@ -305,6 +308,7 @@ interface SettingsState {
   setEvaluatorLLM: (config: Partial<LLMConfig>) => void;
   setApiKeyConfigured: (configured: boolean) => void;
   setConfiguredProviders: (providers: ApiKeysConfig) => void;
+  setTranscriptionBackend: (backend: TranscriptionBackend) => void;
   setDiscoveryMode: (mode: DiscoveryModeId) => void;
   loadSettings: (settings: ChatSessionSettings) => void;
   increaseFontScale: () => void;
@@ -352,12 +356,14 @@ export const useSettingsStore = create<SettingsState>((set) => ({
     anthropic: false,
     openai: false,
     google: false,
+    openrouter: false,
   },
   fontScale: getSavedFontScale(),
   autoSelectDiscoveryModel: getSavedAutoSelectDiscoveryModel(),
   showCitations: getSavedShowCitations(),
   theme: getSavedTheme(),
   voiceModel: getSavedVoiceModel(),
+  transcriptionBackend: 'openai',
   voiceMode: getSavedVoiceMode(),
   customSystemPrompt: getSavedCustomSystemPrompt(),
   allowChatGPTExtraHighThinking: getSavedAllowChatGPTExtraHighThinking(),
@@ -485,7 +491,7 @@ export const useSettingsStore = create<SettingsState>((set) => ({
       }
 
       // Compute voice model from available providers
-      const newVoiceModel = computeVoiceModel(providers);
+      const newVoiceModel = computeVoiceModel(providers, state.transcriptionBackend);
       updates.voiceModel = newVoiceModel;
       localStorage.setItem('voiceModel', newVoiceModel);
 
@@ -493,6 +499,14 @@ export const useSettingsStore = create<SettingsState>((set) => ({
     });
   },
 
+  setTranscriptionBackend: (backend) => {
+    set((state) => {
+      const voiceModel = computeVoiceModel(state.configuredProviders, backend);
+      localStorage.setItem('voiceModel', voiceModel);
+      return { transcriptionBackend: backend, voiceModel };
+    });
+  },
+
   setDiscoveryMode: (mode) => {
     set({ discoveryMode: mode });
     // Persist to localStorage